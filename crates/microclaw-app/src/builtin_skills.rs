@@ -4,12 +4,18 @@ use std::path::Path;
 
 static BUILTIN_SKILLS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../../skills/built-in");
 
-pub fn ensure_builtin_skills(skills_root: &Path) -> std::io::Result<()> {
+/// Install the embedded skills that suit this host and `microclaw_version`,
+/// without overwriting files that already exist.
+pub fn ensure_builtin_skills(skills_root: &Path, microclaw_version: &str) -> std::io::Result<()> {
     std::fs::create_dir_all(skills_root)?;
-    copy_compatible_skills(&BUILTIN_SKILLS_DIR, skills_root)
+    copy_compatible_skills(&BUILTIN_SKILLS_DIR, skills_root, microclaw_version)
 }
 
-fn copy_compatible_skills(embedded: &Dir<'_>, destination: &Path) -> std::io::Result<()> {
+fn copy_compatible_skills(
+    embedded: &Dir<'_>,
+    destination: &Path,
+    microclaw_version: &str,
+) -> std::io::Result<()> {
     for entry in embedded.entries() {
         let DirEntry::Dir(skill_dir) = entry else {
             continue;
@@ -17,11 +23,11 @@ fn copy_compatible_skills(embedded: &Dir<'_>, destination: &Path) -> std::io::Re
         let Some(skill_name) = skill_dir.path().file_name() else {
             continue;
         };
-        let Some(skill_md) = skill_dir.get_file("SKILL.md") else {
+        let Some(skill_md) = skill_dir.get_file(skill_dir.path().join("SKILL.md")) else {
             continue;
         };
         let content = String::from_utf8_lossy(skill_md.contents());
-        if let Some(reason) = skill_skip_reason(&content) {
+        if let Some(reason) = skill_skip_reason(&content, microclaw_version) {
             tracing::debug!(
                 "Skipping built-in skill '{}' on this host: {}",
                 skill_name.to_string_lossy(),
@@ -68,6 +74,10 @@ struct SkillFrontmatter {
     #[serde(default)]
     deps: Vec<String>,
     #[serde(default)]
+    min_microclaw_version: Option<String>,
+    #[serde(default)]
+    max_microclaw_version: Option<String>,
+    #[serde(default)]
     compatibility: SkillCompatibility,
 }
 
@@ -77,6 +87,10 @@ struct SkillCompatibility {
     os: Vec<String>,
     #[serde(default)]
     deps: Vec<String>,
+    #[serde(default)]
+    min_microclaw_version: Option<String>,
+    #[serde(default)]
+    max_microclaw_version: Option<String>,
 }
 
 fn parse_frontmatter(content: &str) -> Option<SkillFrontmatter> {
@@ -87,8 +101,20 @@ fn parse_frontmatter(content: &str) -> Option<SkillFrontmatter> {
     serde_yaml::from_str(yaml).ok()
 }
 
-fn skill_skip_reason(content: &str) -> Option<String> {
+fn skill_skip_reason(content: &str, microclaw_version: &str) -> Option<String> {
     let fm = parse_frontmatter(content)?;
+    let min = fm
+        .min_microclaw_version
+        .as_deref()
+        .or(fm.compatibility.min_microclaw_version.as_deref());
+    let max = fm
+        .max_microclaw_version
+        .as_deref()
+        .or(fm.compatibility.max_microclaw_version.as_deref());
+    if let Some(reason) = microclaw_version_mismatch(min, max, microclaw_version) {
+        return Some(reason);
+    }
+
     let mut supported = fm.platforms;
     supported.extend(fm.compatibility.os);
     supported.sort();
@@ -112,6 +138,66 @@ fn skill_skip_reason(content: &str) -> Option<String> {
     None
 }
 
+/// Parse `major[.minor[.patch]]`, ignoring a leading `v` and any
+/// pre-release/build suffix. Returns None for anything else.
+fn parse_version(value: &str) -> Option<(u64, u64, u64)> {
+    let v = value.trim().trim_start_matches(['v', 'V']);
+    let core = v.split(['-', '+']).next()?;
+    let mut parts = core.split('.');
+    let major = parts.next()?.trim().parse().ok()?;
+    let minor = match parts.next() {
+        Some(p) => p.trim().parse().ok()?,
+        None => 0,
+    };
+    let patch = match parts.next() {
+        Some(p) => p.trim().parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+/// Why a skill declaring `min`/`max` MicroClaw versions (both inclusive) cannot
+/// run on `current`, or None when it can. Unparseable bounds are reported too,
+/// since a typo would otherwise silently disable the check.
+pub fn microclaw_version_mismatch(
+    min: Option<&str>,
+    max: Option<&str>,
+    current: &str,
+) -> Option<String> {
+    let min = min.map(str::trim).filter(|v| !v.is_empty());
+    let max = max.map(str::trim).filter(|v| !v.is_empty());
+    if min.is_none() && max.is_none() {
+        return None;
+    }
+    let Some(cur) = parse_version(current) else {
+        return Some(format!(
+            "cannot compare against MicroClaw version '{current}'"
+        ));
+    };
+    if let Some(min) = min {
+        match parse_version(min) {
+            None => return Some(format!("invalid min_microclaw_version '{min}'")),
+            Some(v) if cur < v => {
+                return Some(format!("requires MicroClaw >= {min} (current: {current})"))
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(max) = max {
+        match parse_version(max) {
+            None => return Some(format!("invalid max_microclaw_version '{max}'")),
+            Some(v) if cur > v => {
+                return Some(format!("requires MicroClaw <= {max} (current: {current})"))
+            }
+            Some(_) => {}
+        }
+    }
+    None
+}
+
 fn current_platform() -> &'static str {
     if cfg!(target_os = "macos") {
         "darwin"
@@ -203,7 +289,7 @@ mod tests {
     fn test_ensure_builtin_skills_writes_missing_files() {
         let root = temp_root();
         let skills_root = root.join("skills");
-        ensure_builtin_skills(&skills_root).unwrap();
+        ensure_builtin_skills(&skills_root, "0.0.1").unwrap();
         let sample = skills_root.join("pdf").join("SKILL.md");
         assert!(sample.exists());
         let content = std::fs::read_to_string(sample).unwrap();
@@ -220,7 +306,7 @@ mod tests {
         let custom_file = custom_pdf.join("SKILL.md");
         std::fs::write(&custom_file, "custom-content").unwrap();
 
-        ensure_builtin_skills(&skills_root).unwrap();
+        ensure_builtin_skills(&skills_root, "0.0.1").unwrap();
         let content = std::fs::read_to_string(custom_file).unwrap();
         assert_eq!(content, "custom-content");
         cleanup(&root);
@@ -230,7 +316,7 @@ mod tests {
     fn test_ensure_builtin_skills_includes_new_macos_and_weather_skills() {
         let root = temp_root();
         let skills_root = root.join("skills");
-        ensure_builtin_skills(&skills_root).unwrap();
+        ensure_builtin_skills(&skills_root, "0.0.1").unwrap();
 
         for skill in ["pdf", "docx", "xlsx", "pptx", "skill-creator"] {
             let skill_file = skills_root.join(skill).join("SKILL.md");
@@ -269,11 +355,53 @@ compatibility:
 ---
 body
 "#;
-        let reason = skill_skip_reason(content);
+        let reason = skill_skip_reason(content, "0.0.1");
         if cfg!(target_os = "macos") && command_exists("curl") {
             assert!(reason.is_none());
         } else {
             assert!(reason.is_some());
         }
     }
+
+    #[test]
+    fn test_microclaw_version_mismatch() {
+        assert_eq!(microclaw_version_mismatch(None, None, "0.0.127"), None);
+        assert_eq!(
+            microclaw_version_mismatch(Some("0.0.100"), Some("0.1"), "0.0.127"),
+            None
+        );
+        assert_eq!(
+            microclaw_version_mismatch(Some("v0.0.127"), Some("0.0.127"), "0.0.127-dev"),
+            None
+        );
+        assert_eq!(
+            microclaw_version_mismatch(Some("0.1.0"), None, "0.0.127").as_deref(),
+            Some("requires MicroClaw >= 0.1.0 (current: 0.0.127)")
+        );
+        assert_eq!(
+            microclaw_version_mismatch(None, Some("0.0.99"), "0.0.127").as_deref(),
+            Some("requires MicroClaw <= 0.0.99 (current: 0.0.127)")
+        );
+        assert_eq!(
+            microclaw_version_mismatch(Some("latest"), None, "0.0.127").as_deref(),
+            Some("invalid min_microclaw_version 'latest'")
+        );
+    }
+
+    #[test]
+    fn test_skill_skip_reason_checks_microclaw_version() {
+        let content = r#"---
+name: x
+description: x
+compatibility:
+  min_microclaw_version: "0.2.0"
+---
+body
+"#;
+        assert_eq!(
+            skill_skip_reason(content, "0.1.5").as_deref(),
+            Some("requires MicroClaw >= 0.2.0 (current: 0.1.5)")
+        );
+        assert!(skill_skip_reason(content, "0.2.0").is_none());
+    }
 }
@@ -0,0 +1,141 @@
+//! Channel-neutral description of files users send to the bot.
+//!
+//! Each channel downloads what the platform gives it (image keys, URLs,
+//! base64 payloads) into an [`InboundAttachment`]; the runtime stores it in the
+//! chat's working dir and tells the agent where it is.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentKind {
+    Image,
+    Audio,
+    Video,
+    File,
+}
+
+impl AttachmentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttachmentKind::Image => "image",
+            AttachmentKind::Audio => "audio",
+            AttachmentKind::Video => "video",
+            AttachmentKind::File => "file",
+        }
+    }
+}
+
+/// A file received from a user, already downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundAttachment {
+    /// Name as given by the platform or sender; may be empty.
+    pub file_name: String,
+    /// MIME type when the platform reports one.
+    pub media_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl InboundAttachment {
+    pub fn new(file_name: impl Into<String>, media_type: Option<String>, data: Vec<u8>) -> Self {
+        Self {
+            file_name: file_name.into(),
+            media_type: media_type
+                .map(|m| m.trim().to_ascii_lowercase())
+                .filter(|m| !m.is_empty()),
+            data,
+        }
+    }
+
+    /// The reported MIME type, or one sniffed from the bytes for common images.
+    pub fn effective_media_type(&self) -> Option<String> {
+        self.media_type
+            .clone()
+            .or_else(|| sniff_image_media_type(&self.data).map(str::to_string))
+    }
+
+    pub fn kind(&self) -> AttachmentKind {
+        match self.effective_media_type() {
+            Some(m) if m.starts_with("image/") => AttachmentKind::Image,
+            Some(m) if m.starts_with("audio/") => AttachmentKind::Audio,
+            Some(m) if m.starts_with("video/") => AttachmentKind::Video,
+            _ => AttachmentKind::File,
+        }
+    }
+
+    /// File name reduced to `[A-Za-z0-9._-]`, never empty and never a dot-only name.
+    pub fn safe_file_name(&self) -> String {
+        let name = self
+            .file_name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default();
+        let safe: String = name
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+                _ => '_',
+            })
+            .collect();
+        if safe.trim_matches('.').is_empty() {
+            format!("{}.{}", self.kind().as_str(), self.default_extension())
+        } else {
+            safe
+        }
+    }
+
+    fn default_extension(&self) -> &'static str {
+        match self.effective_media_type().as_deref() {
+            Some("image/png") => "png",
+            Some("image/jpeg") => "jpg",
+            Some("image/gif") => "gif",
+            Some("image/webp") => "webp",
+            Some("application/pdf") => "pdf",
+            Some("text/plain") => "txt",
+            _ => "bin",
+        }
+    }
+}
+
+/// MIME type of PNG, JPEG, GIF and WebP data, by magic bytes.
+pub fn sniff_image_media_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+        Some("image/png")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_uses_reported_or_sniffed_media_type() {
+        let png = InboundAttachment::new("", None, vec![0x89, b'P', b'N', b'G', 0]);
+        assert_eq!(png.kind(), AttachmentKind::Image);
+        assert_eq!(png.effective_media_type().as_deref(), Some("image/png"));
+
+        let pdf = InboundAttachment::new("a.pdf", Some(" Application/PDF ".into()), vec![1]);
+        assert_eq!(pdf.media_type.as_deref(), Some("application/pdf"));
+        assert_eq!(pdf.kind(), AttachmentKind::File);
+
+        let voice = InboundAttachment::new("v.ogg", Some("audio/ogg".into()), vec![]);
+        assert_eq!(voice.kind(), AttachmentKind::Audio);
+    }
+
+    #[test]
+    fn safe_file_name_strips_directories_and_odd_characters() {
+        let name = |n: &str| InboundAttachment::new(n, None, vec![]).safe_file_name();
+        assert_eq!(name("../../etc/passwd"), "passwd");
+        assert_eq!(name("C:\\Users\\me\\report v2.pdf"), "report_v2.pdf");
+        assert_eq!(name("报告.txt"), "__.txt");
+        assert_eq!(name(".."), "file.bin");
+        assert_eq!(name(""), "file.bin");
+        let jpeg = InboundAttachment::new("", None, vec![0xFF, 0xD8, 0xFF]);
+        assert_eq!(jpeg.safe_file_name(), "image.jpg");
+    }
+}
@@ -1,7 +1,12 @@
 use std::sync::Arc;
 
-use crate::channel_adapter::ChannelRegistry;
+use crate::channel_adapter::{apply_mentions, ChannelRegistry, ResolvedMention};
+use crate::health::SendHealthTransition;
 use microclaw_storage::db::{call_blocking, Database, StoredMessage};
+use tracing::{info, warn};
+
+/// `audit_logs.kind` used for channel delivery health transitions.
+pub const CHANNEL_DELIVERY_AUDIT_KIND: &str = "channel_delivery";
 
 #[derive(Clone, Debug)]
 struct ToolAuthContext {
@@ -159,6 +164,21 @@ pub async fn deliver_and_store_bot_message(
     bot_username: &str,
     chat_id: i64,
     text: &str,
+) -> Result<(), String> {
+    deliver_and_store_bot_message_with_mentions(registry, db, bot_username, chat_id, text, &[])
+        .await
+}
+
+/// Like [`deliver_and_store_bot_message`], but pings `mentions` with the channel's
+/// mention syntax. The stored copy uses plain `@display` text and omits the
+/// channel's reply prefix/suffix.
+pub async fn deliver_and_store_bot_message_with_mentions(
+    registry: &ChannelRegistry,
+    db: Arc<Database>,
+    bot_username: &str,
+    chat_id: i64,
+    text: &str,
+    mentions: &[ResolvedMention],
 ) -> Result<(), String> {
     let routing = get_required_chat_routing(registry, db.clone(), chat_id).await?;
     let external_chat_id = call_blocking(db.clone(), move |d| d.get_chat_external_id(chat_id))
@@ -168,7 +188,16 @@ pub async fn deliver_and_store_bot_message(
 
     if let Some(adapter) = registry.get(&routing.channel_name) {
         if !adapter.is_local_only() {
-            adapter.send_text(&external_chat_id, text).await?;
+            let outgoing = registry.decorate_reply(&routing.channel_name, text);
+            let result = if mentions.is_empty() {
+                adapter.send_text(&external_chat_id, &outgoing).await
+            } else {
+                adapter
+                    .send_text_with_mentions(&external_chat_id, &outgoing, mentions)
+                    .await
+            };
+            record_send_result(registry, db.clone(), &routing.channel_name, &result).await;
+            result?;
         }
     } else {
         return Err(format!(
@@ -177,11 +206,16 @@ pub async fn deliver_and_store_bot_message(
         ));
     }
 
+    let content = if mentions.is_empty() {
+        text.to_string()
+    } else {
+        apply_mentions(text, mentions, |m| format!("@{}", m.display))
+    };
     let msg = StoredMessage {
         id: uuid::Uuid::new_v4().to_string(),
         chat_id,
         sender_name: bot_username.to_string(),
-        content: text.to_string(),
+        content,
         is_from_bot: true,
         timestamp: chrono::Utc::now().to_rfc3339(),
     };
@@ -189,3 +223,254 @@ pub async fn deliver_and_store_bot_message(
         .await
         .map_err(|e| format!("Failed to store sent message: {e}"))
 }
+
+/// What [`deliver_or_defer_bot_message`] did with a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboundDelivery {
+    Sent,
+    /// Queued until the channel's quiet hours end at this time.
+    Deferred(chrono::DateTime<chrono::Utc>),
+}
+
+/// Deliver a message no user asked for, or queue it while the chat's channel
+/// is in quiet hours. [`flush_deferred_messages`] sends the queue later.
+pub async fn deliver_or_defer_bot_message(
+    registry: &ChannelRegistry,
+    db: Arc<Database>,
+    bot_username: &str,
+    chat_id: i64,
+    text: &str,
+) -> Result<OutboundDelivery, String> {
+    let routing = get_required_chat_routing(registry, db.clone(), chat_id).await?;
+    let now = chrono::Utc::now();
+    if let Some(quiet_hours) = registry
+        .quiet_hours(&routing.channel_name)
+        .filter(|q| q.is_quiet_at(now))
+    {
+        let until = quiet_hours.ends_after(now);
+        let bot = bot_username.to_string();
+        let content = text.to_string();
+        call_blocking(db, move |d| {
+            d.insert_deferred_message(chat_id, &bot, &content)
+        })
+        .await
+        .map_err(|e| format!("Failed to queue message for chat {chat_id}: {e}"))?;
+        info!(
+            "Deferred message for chat {chat_id}: {} quiet hours ({}) end at {until}",
+            routing.channel_name,
+            quiet_hours.describe()
+        );
+        return Ok(OutboundDelivery::Deferred(until));
+    }
+    deliver_and_store_bot_message(registry, db, bot_username, chat_id, text).await?;
+    Ok(OutboundDelivery::Sent)
+}
+
+/// Failed sends after which a deferred message is dropped.
+pub const DEFERRED_MESSAGE_MAX_ATTEMPTS: i64 = 5;
+
+/// Send queued messages, oldest first, for chats whose channel is out of quiet
+/// hours. A failed send stays queued for the next flush until it has failed
+/// [`DEFERRED_MESSAGE_MAX_ATTEMPTS`] times. Returns the number delivered.
+pub async fn flush_deferred_messages(registry: &ChannelRegistry, db: Arc<Database>) -> usize {
+    let queued = match call_blocking(db.clone(), |d| d.list_deferred_messages()).await {
+        Ok(queued) => queued,
+        Err(e) => {
+            warn!("Failed to read deferred messages: {e}");
+            return 0;
+        }
+    };
+    let now = chrono::Utc::now();
+    let mut delivered = 0;
+    for msg in queued {
+        let routing = get_chat_routing(registry, db.clone(), msg.chat_id)
+            .await
+            .ok()
+            .flatten();
+        if routing.as_ref().is_some_and(|r| {
+            registry
+                .quiet_hours(&r.channel_name)
+                .is_some_and(|q| q.is_quiet_at(now))
+        }) {
+            continue;
+        }
+        if routing.is_none() {
+            warn!(
+                "Dropping deferred message #{}: chat {} no longer routes to a channel",
+                msg.id, msg.chat_id
+            );
+        } else {
+            match deliver_and_store_bot_message(
+                registry,
+                db.clone(),
+                &msg.bot_username,
+                msg.chat_id,
+                &msg.content,
+            )
+            .await
+            {
+                Ok(()) => delivered += 1,
+                Err(e) => {
+                    let id = msg.id;
+                    let attempts =
+                        call_blocking(db.clone(), move |d| d.record_deferred_message_failure(id))
+                            .await
+                            .unwrap_or(msg.attempts + 1);
+                    if attempts < DEFERRED_MESSAGE_MAX_ATTEMPTS {
+                        warn!(
+                            "Failed to deliver deferred message #{} to chat {} (attempt {attempts} of {DEFERRED_MESSAGE_MAX_ATTEMPTS}); will retry: {e}",
+                            msg.id, msg.chat_id
+                        );
+                        continue;
+                    }
+                    warn!(
+                        "Dropping deferred message #{} to chat {} after {attempts} failed attempts: {e}",
+                        msg.id, msg.chat_id
+                    );
+                }
+            }
+        }
+        let id = msg.id;
+        if let Err(e) = call_blocking(db.clone(), move |d| d.delete_deferred_message(id)).await {
+            warn!("Failed to remove deferred message #{id}: {e}");
+        }
+    }
+    delivered
+}
+
+/// Record the outcome of an outbound send for `channel_name`.
+///
+/// When the channel crosses the configured failure threshold this logs a warning,
+/// writes a `channel_delivery` audit row and alerts the policy's alert chats that
+/// route through a different channel. A later success records the recovery.
+pub async fn record_send_result(
+    registry: &ChannelRegistry,
+    db: Arc<Database>,
+    channel_name: &str,
+    result: &Result<(), String>,
+) {
+    let health = registry.health();
+    let transition = match result {
+        Ok(()) => health.record_success(channel_name),
+        Err(e) => health.record_failure(channel_name, e),
+    };
+    let first_success = result.is_ok() && health.note_first_success(channel_name);
+    match transition {
+        SendHealthTransition::Unchanged if first_success => {
+            reconcile_persisted_failure(db, channel_name).await;
+        }
+        SendHealthTransition::Unchanged => {}
+        SendHealthTransition::Failing {
+            consecutive_failures,
+        } => {
+            let last_error = result.as_ref().err().cloned().unwrap_or_default();
+            warn!(
+                "Channel '{}' is failing: {} consecutive send failures (last error: {})",
+                channel_name, consecutive_failures, last_error
+            );
+            log_delivery_audit(
+                db.clone(),
+                channel_name,
+                "failing",
+                Some(last_error.clone()),
+            )
+            .await;
+            let alert = format!(
+                "[delivery alert] Channel '{channel_name}' failed to send {consecutive_failures} messages in a row. Last error: {last_error}"
+            );
+            send_delivery_alert(registry, db, channel_name, &alert).await;
+        }
+        SendHealthTransition::Recovered { previous_failures } => {
+            info!(
+                "Channel '{}' recovered after {} consecutive send failures",
+                channel_name, previous_failures
+            );
+            log_delivery_audit(
+                db,
+                channel_name,
+                "recovered",
+                Some(format!("previous_failures={previous_failures}")),
+            )
+            .await;
+        }
+    }
+}
+
+/// Record a recovery for a channel whose last audit row, written before a
+/// restart, still says `failing`; otherwise it would stay failing in `doctor`.
+async fn reconcile_persisted_failure(db: Arc<Database>, channel_name: &str) {
+    let actor = channel_name.to_string();
+    let latest = call_blocking(db.clone(), move |d| {
+        d.latest_audit_status(CHANNEL_DELIVERY_AUDIT_KIND, &actor)
+    })
+    .await;
+    match latest {
+        Ok(Some(status)) if status == "failing" => {
+            info!("Channel '{channel_name}' recovered (it was failing before the last restart)");
+            log_delivery_audit(
+                db,
+                channel_name,
+                "recovered",
+                Some("after restart".to_string()),
+            )
+            .await;
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to read channel delivery audit log: {e}"),
+    }
+}
+
+async fn log_delivery_audit(
+    db: Arc<Database>,
+    channel_name: &str,
+    status: &'static str,
+    detail: Option<String>,
+) {
+    let actor = channel_name.to_string();
+    if let Err(e) = call_blocking(db, move |d| {
+        d.log_audit_event(
+            CHANNEL_DELIVERY_AUDIT_KIND,
+            &actor,
+            "send",
+            None,
+            status,
+            detail.as_deref(),
+        )
+    })
+    .await
+    {
+        warn!("Failed to write channel delivery audit log: {e}");
+    }
+}
+
+async fn send_delivery_alert(
+    registry: &ChannelRegistry,
+    db: Arc<Database>,
+    failing_channel: &str,
+    alert: &str,
+) {
+    for &alert_chat_id in &registry.health().policy().alert_chat_ids {
+        let Ok(Some(routing)) = get_chat_routing(registry, db.clone(), alert_chat_id).await else {
+            continue;
+        };
+        // Alerting through the failing channel would only fail again.
+        if routing.channel_name == failing_channel {
+            continue;
+        }
+        let Some(adapter) = registry.get(&routing.channel_name) else {
+            continue;
+        };
+        if adapter.is_local_only() {
+            continue;
+        }
+        let external_chat_id =
+            call_blocking(db.clone(), move |d| d.get_chat_external_id(alert_chat_id))
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| alert_chat_id.to_string());
+        if let Err(e) = adapter.send_text(&external_chat_id, alert).await {
+            warn!("Failed to deliver channel alert to chat {alert_chat_id}: {e}");
+        }
+    }
+}
@@ -1,10 +1,153 @@
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
 use crate::channel::ConversationKind;
+use crate::health::{ChannelHealth, SendFailurePolicy};
+use crate::inbound_queue::{InboundQueuePolicy, InboundQueues};
+use crate::quiet_hours::QuietHours;
+use crate::typing::TypingIndicator;
+
+/// A `send_message` mention resolved to a platform user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedMention {
+    /// Name as requested, without the leading `@`.
+    pub name: String,
+    /// Platform user id to ping.
+    pub user_id: String,
+    /// Label shown in the message.
+    pub display: String,
+}
+
+/// Rewrite every `@name` in `text` with `render(mention)`; mentions the text
+/// does not reference are prepended.
+pub fn apply_mentions(
+    text: &str,
+    mentions: &[ResolvedMention],
+    render: impl Fn(&ResolvedMention) -> String,
+) -> String {
+    let mut by_length: Vec<&ResolvedMention> = mentions.iter().collect();
+    by_length.sort_by_key(|m| std::cmp::Reverse(m.name.len()));
+    let mut used = vec![false; mentions.len()];
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        let hit = by_length.iter().find(|m| {
+            !m.name.is_empty()
+                && after.starts_with(m.name.as_str())
+                && !after[m.name.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        });
+        match hit {
+            Some(m) => {
+                out.push_str(&render(m));
+                if let Some(idx) = mentions.iter().position(|x| std::ptr::eq(x, *m)) {
+                    used[idx] = true;
+                }
+                rest = &after[m.name.len()..];
+            }
+            None => {
+                out.push('@');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+
+    let missing: Vec<String> = mentions
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|(m, _)| render(m))
+        .collect();
+    if missing.is_empty() {
+        out
+    } else {
+        format!("{} {out}", missing.join(" "))
+    }
+}
+
+/// A user's emoji reaction on one of the bot's messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundReaction {
+    pub external_chat_id: String,
+    /// Platform id of the message that was reacted to.
+    pub message_id: String,
+    /// The emoji as the platform reports it (a Unicode emoji, or a name such
+    /// as Feishu's `THUMBSUP`).
+    pub emoji: String,
+    pub sender_id: String,
+    /// Text of the reacted message, when the channel could read it.
+    pub message_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CardButtonStyle {
+    #[default]
+    Default,
+    Primary,
+    Danger,
+}
+
+/// A card button. Pressing it is handled as if the user had sent `value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardButton {
+    pub label: String,
+    pub value: String,
+    pub style: CardButtonStyle,
+}
+
+impl CardButton {
+    pub fn new(label: impl Into<String>, value: impl Into<String>, style: CardButtonStyle) -> Self {
+        Self {
+            label: label.into(),
+            value: value.into(),
+            style,
+        }
+    }
+}
+
+/// Channel-neutral interactive message: a title, a markdown body and reply
+/// buttons.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageCard {
+    pub title: Option<String>,
+    pub body: String,
+    pub buttons: Vec<CardButton>,
+}
+
+/// Text wrapped around every bot reply on one channel account, e.g. a bot
+/// name tag or a disclaimer. Empty parts are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplyDecoration {
+    pub prefix: String,
+    pub suffix: String,
+}
+
+impl ReplyDecoration {
+    pub fn is_empty(&self) -> bool {
+        self.prefix.is_empty() && self.suffix.is_empty()
+    }
+
+    /// `"{prefix} {text}\n\n{suffix}"`, leaving out whichever parts are empty.
+    pub fn apply(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        if !self.prefix.is_empty() {
+            out = format!("{} {out}", self.prefix);
+        }
+        if !self.suffix.is_empty() {
+            out = format!("{out}\n\n{}", self.suffix);
+        }
+        out
+    }
+}
 
 #[async_trait]
 pub trait ChannelAdapter: Send + Sync {
@@ -36,6 +179,64 @@ pub trait ChannelAdapter: Send + Sync {
     ) -> Result<String, String> {
         Err(format!("attachments not supported for {}", self.name()))
     }
+
+    /// Resolve a username or platform user id to a user this channel can ping.
+    /// `known_ids` are the sender ids already seen in the chat. `None` means the
+    /// mention cannot be resolved and is sent as plain text. Default: unsupported.
+    async fn resolve_mention(
+        &self,
+        _external_chat_id: &str,
+        _name: &str,
+        _known_ids: &[String],
+    ) -> Option<ResolvedMention> {
+        None
+    }
+
+    /// Send text that pings `mentions` using the platform's mention syntax.
+    /// Default: plain `@display` text.
+    async fn send_text_with_mentions(
+        &self,
+        external_chat_id: &str,
+        text: &str,
+        mentions: &[ResolvedMention],
+    ) -> Result<(), String> {
+        let text = apply_mentions(text, mentions, |m| format!("@{}", m.display));
+        self.send_text(external_chat_id, &text).await
+    }
+
+    /// How often an active typing indicator must be re-sent before the platform
+    /// expires it. `None` means the channel has no typing indicator.
+    fn typing_refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Show (`on = true`) or clear the typing indicator. Default: no-op.
+    async fn set_typing(&self, _external_chat_id: &str, _on: bool) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Delete (recall) one of the bot's own messages. Default: not supported.
+    async fn delete_message(
+        &self,
+        _external_chat_id: &str,
+        _message_id: &str,
+    ) -> Result<(), String> {
+        Err(format!(
+            "deleting messages is not supported for {}",
+            self.name()
+        ))
+    }
+
+    /// React to a message with `emoji`, in the platform's own emoji naming.
+    /// Default: not supported.
+    async fn add_reaction(
+        &self,
+        _external_chat_id: &str,
+        _message_id: &str,
+        _emoji: &str,
+    ) -> Result<(), String> {
+        Err(format!("reactions are not supported for {}", self.name()))
+    }
 }
 
 #[derive(Default)]
@@ -45,6 +246,14 @@ pub struct ChannelRegistry {
     type_to_channel: HashMap<String, String>,
     /// "slack_dm" -> Private, "group" -> Group, etc.
     type_to_conversation: HashMap<String, ConversationKind>,
+    /// Consecutive send-failure tracking per channel account.
+    health: ChannelHealth,
+    /// Bounded per-channel queues for inbound message processing.
+    inbound_queues: Arc<InboundQueues>,
+    /// Prefix/suffix added to outgoing bot replies, per channel account.
+    reply_decorations: HashMap<String, ReplyDecoration>,
+    /// Windows in which unprompted messages are queued, per channel account.
+    quiet_hours: HashMap<String, QuietHours>,
 }
 
 impl ChannelRegistry {
@@ -52,6 +261,62 @@ impl ChannelRegistry {
         Self::default()
     }
 
+    pub fn set_send_failure_policy(&mut self, policy: SendFailurePolicy) {
+        self.health = ChannelHealth::new(policy);
+    }
+
+    pub fn health(&self) -> &ChannelHealth {
+        &self.health
+    }
+
+    pub fn set_inbound_queue_policy(&mut self, policy: InboundQueuePolicy) {
+        self.inbound_queues = Arc::new(InboundQueues::new(policy));
+    }
+
+    pub fn inbound_queues(&self) -> &Arc<InboundQueues> {
+        &self.inbound_queues
+    }
+
+    pub fn set_reply_decoration(&mut self, channel: &str, decoration: ReplyDecoration) {
+        if decoration.is_empty() {
+            self.reply_decorations.remove(channel);
+        } else {
+            self.reply_decorations
+                .insert(channel.to_string(), decoration);
+        }
+    }
+
+    pub fn set_quiet_hours(&mut self, channel: &str, quiet_hours: Option<QuietHours>) {
+        match quiet_hours {
+            Some(quiet_hours) => {
+                self.quiet_hours.insert(channel.to_string(), quiet_hours);
+            }
+            None => {
+                self.quiet_hours.remove(channel);
+            }
+        }
+    }
+
+    pub fn quiet_hours(&self, channel: &str) -> Option<&QuietHours> {
+        self.quiet_hours.get(channel)
+    }
+
+    /// `text` as it should be sent on `channel`, with that channel's reply
+    /// prefix and suffix applied.
+    pub fn decorate_reply(&self, channel: &str, text: &str) -> String {
+        match self.reply_decorations.get(channel) {
+            Some(decoration) => decoration.apply(text),
+            None => text.to_string(),
+        }
+    }
+
+    /// Names of all registered channel accounts (e.g. `feishu`, `feishu.ops`).
+    pub fn channel_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.adapters.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     pub fn register(&mut self, adapter: Arc<dyn ChannelAdapter>) {
         let name = adapter.name().to_string();
         for (chat_type, kind) in adapter.chat_type_routes() {
@@ -67,6 +332,12 @@ impl ChannelRegistry {
         self.adapters.get(name)
     }
 
+    /// Start a typing indicator for one agent turn on `channel`; stop it once the turn ends.
+    pub fn start_typing(&self, channel: &str, external_chat_id: &str) -> Option<TypingIndicator> {
+        let adapter = self.adapters.get(channel)?;
+        Some(TypingIndicator::start(adapter.clone(), external_chat_id))
+    }
+
     /// Resolve a DB chat_type string to the adapter and conversation kind.
     pub fn resolve(
         &self,
@@ -89,3 +360,65 @@ impl ChannelRegistry {
         !self.adapters.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mention(name: &str, user_id: &str) -> ResolvedMention {
+        ResolvedMention {
+            name: name.to_string(),
+            user_id: user_id.to_string(),
+            display: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_mentions_rewrites_in_place_and_prepends_missing() {
+        let mentions = vec![
+            mention("al", "u1"),
+            mention("alice", "u2"),
+            mention("bob", "u3"),
+        ];
+        let out = apply_mentions("hi @alice and @al, mail a@alx", &mentions, |m| {
+            format!("<{}>", m.user_id)
+        });
+        assert_eq!(out, "<u3> hi <u2> and <u1>, mail a@alx");
+    }
+
+    #[test]
+    fn test_reply_decoration_wraps_only_configured_channels() {
+        let mut registry = ChannelRegistry::new();
+        registry.set_reply_decoration(
+            "feishu",
+            ReplyDecoration {
+                prefix: "[Ops Bot]".into(),
+                suffix: "AI-generated; verify before acting.".into(),
+            },
+        );
+        registry.set_reply_decoration(
+            "dingtalk",
+            ReplyDecoration {
+                prefix: String::new(),
+                suffix: "-- sig".into(),
+            },
+        );
+        assert_eq!(
+            registry.decorate_reply("feishu", "done"),
+            "[Ops Bot] done\n\nAI-generated; verify before acting."
+        );
+        assert_eq!(registry.decorate_reply("dingtalk", "hi"), "hi\n\n-- sig");
+        assert_eq!(registry.decorate_reply("email", "plain"), "plain");
+
+        registry.set_reply_decoration("feishu", ReplyDecoration::default());
+        assert_eq!(registry.decorate_reply("feishu", "done"), "done");
+    }
+
+    #[test]
+    fn test_apply_mentions_without_mentions_is_identity() {
+        assert_eq!(
+            apply_mentions("ping @x", &[], |m| m.name.clone()),
+            "ping @x"
+        );
+    }
+}
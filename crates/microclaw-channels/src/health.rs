@@ -0,0 +1,263 @@
+//! Per-channel delivery health tracking.
+//!
+//! Counts consecutive outbound send failures per channel account so that a
+//! revoked token or a rate-limited API turns into an operator-visible state
+//! instead of silently dropped replies.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendFailurePolicy {
+    /// Consecutive failures before a channel is considered failing. 0 disables tracking alerts.
+    pub alert_threshold: u32,
+    /// Seconds to pause agent processing for a failing channel after its last failure. 0 disables.
+    pub backoff_secs: u64,
+    /// Chats notified when a channel enters the failing state.
+    pub alert_chat_ids: Vec<i64>,
+}
+
+impl Default for SendFailurePolicy {
+    fn default() -> Self {
+        Self {
+            alert_threshold: 3,
+            backoff_secs: 0,
+            alert_chat_ids: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendHealthTransition {
+    Unchanged,
+    /// The channel just crossed the alert threshold.
+    Failing {
+        consecutive_failures: u32,
+    },
+    /// A send succeeded after the channel had been failing.
+    Recovered {
+        previous_failures: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSendState {
+    pub channel: String,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub last_failure_at: Option<String>,
+    pub failing_since: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    last_failure_at: Option<String>,
+    last_failure_instant: Option<Instant>,
+    failing_since: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ChannelHealth {
+    policy: SendFailurePolicy,
+    entries: Mutex<HashMap<String, Entry>>,
+    /// Channels that have sent successfully since this process started.
+    succeeded: Mutex<HashSet<String>>,
+}
+
+impl ChannelHealth {
+    pub fn new(policy: SendFailurePolicy) -> Self {
+        Self {
+            policy,
+            entries: Mutex::new(HashMap::new()),
+            succeeded: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn policy(&self) -> &SendFailurePolicy {
+        &self.policy
+    }
+
+    pub fn record_success(&self, channel: &str) -> SendHealthTransition {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = entries.remove(channel) else {
+            return SendHealthTransition::Unchanged;
+        };
+        if entry.failing_since.is_some() {
+            SendHealthTransition::Recovered {
+                previous_failures: entry.consecutive_failures,
+            }
+        } else {
+            SendHealthTransition::Unchanged
+        }
+    }
+
+    /// Note a successful send; true only for the channel's first since startup.
+    /// Health is not persisted, so the caller uses this to reconcile a failing
+    /// state recorded by a previous process.
+    pub fn note_first_success(&self, channel: &str) -> bool {
+        self.succeeded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(channel.to_string())
+    }
+
+    pub fn record_failure(&self, channel: &str, error: &str) -> SendHealthTransition {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.entry(channel.to_string()).or_default();
+        let now = chrono::Utc::now().to_rfc3339();
+        entry.consecutive_failures = entry.consecutive_failures.saturating_add(1);
+        entry.last_error = Some(error.to_string());
+        entry.last_failure_at = Some(now.clone());
+        entry.last_failure_instant = Some(Instant::now());
+
+        let threshold = self.policy.alert_threshold;
+        if threshold > 0 && entry.failing_since.is_none() && entry.consecutive_failures >= threshold
+        {
+            entry.failing_since = Some(now);
+            return SendHealthTransition::Failing {
+                consecutive_failures: entry.consecutive_failures,
+            };
+        }
+        SendHealthTransition::Unchanged
+    }
+
+    pub fn is_failing(&self, channel: &str) -> bool {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(channel)
+            .map(|e| e.failing_since.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Remaining backoff for a failing channel, if backoff is enabled and still active.
+    pub fn backoff_remaining(&self, channel: &str) -> Option<Duration> {
+        if self.policy.backoff_secs == 0 {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.get(channel)?;
+        entry.failing_since.as_ref()?;
+        let elapsed = entry.last_failure_instant?.elapsed();
+        Duration::from_secs(self.policy.backoff_secs)
+            .checked_sub(elapsed)
+            .filter(|d| !d.is_zero())
+    }
+
+    pub fn state(&self, channel: &str) -> Option<ChannelSendState> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(channel).map(|e| snapshot(channel, e))
+    }
+
+    /// Channels currently in the failing state, sorted by name.
+    pub fn failing_channels(&self) -> Vec<ChannelSendState> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<ChannelSendState> = entries
+            .iter()
+            .filter(|(_, e)| e.failing_since.is_some())
+            .map(|(name, e)| snapshot(name, e))
+            .collect();
+        out.sort_by(|a, b| a.channel.cmp(&b.channel));
+        out
+    }
+}
+
+fn snapshot(channel: &str, entry: &Entry) -> ChannelSendState {
+    ChannelSendState {
+        channel: channel.to_string(),
+        consecutive_failures: entry.consecutive_failures,
+        last_error: entry.last_error.clone(),
+        last_failure_at: entry.last_failure_at.clone(),
+        failing_since: entry.failing_since.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(threshold: u32, backoff_secs: u64) -> SendFailurePolicy {
+        SendFailurePolicy {
+            alert_threshold: threshold,
+            backoff_secs,
+            alert_chat_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_failing_after_threshold_and_recovers() {
+        let health = ChannelHealth::new(policy(2, 0));
+        assert_eq!(
+            health.record_failure("feishu", "401"),
+            SendHealthTransition::Unchanged
+        );
+        assert!(!health.is_failing("feishu"));
+        assert_eq!(
+            health.record_failure("feishu", "401"),
+            SendHealthTransition::Failing {
+                consecutive_failures: 2
+            }
+        );
+        // Further failures do not re-trigger the alert.
+        assert_eq!(
+            health.record_failure("feishu", "401"),
+            SendHealthTransition::Unchanged
+        );
+        assert_eq!(health.failing_channels().len(), 1);
+        assert_eq!(
+            health.record_success("feishu"),
+            SendHealthTransition::Recovered {
+                previous_failures: 3
+            }
+        );
+        assert!(health.failing_channels().is_empty());
+    }
+
+    #[test]
+    fn test_success_resets_counter_before_threshold() {
+        let health = ChannelHealth::new(policy(2, 0));
+        health.record_failure("email", "timeout");
+        assert_eq!(
+            health.record_success("email"),
+            SendHealthTransition::Unchanged
+        );
+        assert_eq!(
+            health.record_failure("email", "timeout"),
+            SendHealthTransition::Unchanged
+        );
+    }
+
+    #[test]
+    fn test_note_first_success_only_once_per_channel() {
+        let health = ChannelHealth::new(policy(3, 0));
+        assert!(health.note_first_success("feishu"));
+        assert!(!health.note_first_success("feishu"));
+        assert!(health.note_first_success("email"));
+    }
+
+    #[test]
+    fn test_zero_threshold_never_fails() {
+        let health = ChannelHealth::new(policy(0, 60));
+        for _ in 0..10 {
+            assert_eq!(
+                health.record_failure("dingtalk", "x"),
+                SendHealthTransition::Unchanged
+            );
+        }
+        assert!(health.backoff_remaining("dingtalk").is_none());
+    }
+
+    #[test]
+    fn test_backoff_only_when_failing_and_enabled() {
+        let health = ChannelHealth::new(policy(1, 60));
+        assert!(health.backoff_remaining("feishu").is_none());
+        health.record_failure("feishu", "429");
+        assert!(health.backoff_remaining("feishu").is_some());
+
+        let no_backoff = ChannelHealth::new(policy(1, 0));
+        no_backoff.record_failure("feishu", "429");
+        assert!(no_backoff.backoff_remaining("feishu").is_none());
+    }
+}
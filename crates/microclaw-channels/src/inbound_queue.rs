@@ -0,0 +1,468 @@
+//! Bounded per-channel inbound work queues.
+//!
+//! Each channel account gets its own queue of pending messages. A shared pool of
+//! worker slots is handed out round-robin across non-empty queues, so a flood on
+//! one channel cannot starve replies on the others. A job that has to wait on
+//! something else, such as its chat's running turn, can hand its slot back for
+//! the wait with [`without_worker_slot`].
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+tokio::task_local! {
+    /// The worker slot of the queued job running on this task.
+    static CURRENT_SLOT: Mutex<Option<WorkerSlot>>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueDropPolicy {
+    /// Discard the longest-waiting message to make room for the new one.
+    #[default]
+    DropOldest,
+    /// Reject the incoming message and keep what is already queued.
+    DropNewest,
+}
+
+impl QueueDropPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "drop_oldest" => Some(Self::DropOldest),
+            "drop_newest" => Some(Self::DropNewest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DropOldest => "drop_oldest",
+            Self::DropNewest => "drop_newest",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundQueuePolicy {
+    /// Pending messages kept per channel account. 0 means unbounded.
+    pub capacity: usize,
+    /// Messages processed concurrently across all channels.
+    pub max_concurrency: usize,
+    pub drop_policy: QueueDropPolicy,
+}
+
+impl Default for InboundQueuePolicy {
+    fn default() -> Self {
+        Self {
+            capacity: 100,
+            max_concurrency: 8,
+            drop_policy: QueueDropPolicy::DropOldest,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    Queued,
+    /// The queue was full and its oldest pending message was discarded.
+    DroppedOldest,
+    /// The queue was full and the submitted message was discarded.
+    DroppedNewest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelQueueStats {
+    pub channel: String,
+    /// Messages waiting for a worker slot.
+    pub depth: usize,
+    pub in_flight: usize,
+    pub processed: u64,
+    pub dropped: u64,
+}
+
+struct Lane {
+    channel: String,
+    jobs: VecDeque<Job>,
+    in_flight: usize,
+    processed: u64,
+    dropped: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    lanes: Vec<Lane>,
+    /// Lane to consider first on the next dispatch.
+    next: usize,
+    active: usize,
+    /// Jobs that gave their slot back and wait for one to continue. They are
+    /// served before new jobs.
+    resuming: VecDeque<(usize, oneshot::Sender<WorkerSlot>)>,
+}
+
+impl Inner {
+    fn lane_index(&mut self, channel: &str) -> usize {
+        if let Some(idx) = self.lanes.iter().position(|l| l.channel == channel) {
+            return idx;
+        }
+        self.lanes.push(Lane {
+            channel: channel.to_string(),
+            jobs: VecDeque::new(),
+            in_flight: 0,
+            processed: 0,
+            dropped: 0,
+        });
+        self.lanes.len() - 1
+    }
+
+    fn pop_next(&mut self) -> Option<(usize, Job)> {
+        let n = self.lanes.len();
+        for offset in 0..n {
+            let idx = (self.next + offset) % n;
+            if let Some(job) = self.lanes[idx].jobs.pop_front() {
+                self.next = (idx + 1) % n;
+                return Some((idx, job));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct InboundQueues {
+    policy: InboundQueuePolicy,
+    inner: Mutex<Inner>,
+}
+
+impl InboundQueues {
+    pub fn new(policy: InboundQueuePolicy) -> Self {
+        Self {
+            policy,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn policy(&self) -> &InboundQueuePolicy {
+        &self.policy
+    }
+
+    /// Queue `job` on `channel` and start it as soon as a worker slot is free.
+    /// Must be called from within a Tokio runtime.
+    pub fn submit<F>(self: &Arc<Self>, channel: &str, job: F) -> SubmitOutcome
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (outcome, depth) = {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            let idx = inner.lane_index(channel);
+            let lane = &mut inner.lanes[idx];
+            let full = self.policy.capacity > 0 && lane.jobs.len() >= self.policy.capacity;
+            let outcome = if !full {
+                lane.jobs.push_back(Box::pin(job));
+                SubmitOutcome::Queued
+            } else {
+                lane.dropped = lane.dropped.saturating_add(1);
+                match self.policy.drop_policy {
+                    QueueDropPolicy::DropOldest => {
+                        lane.jobs.pop_front();
+                        lane.jobs.push_back(Box::pin(job));
+                        SubmitOutcome::DroppedOldest
+                    }
+                    QueueDropPolicy::DropNewest => SubmitOutcome::DroppedNewest,
+                }
+            };
+            (outcome, lane.jobs.len())
+        };
+        if outcome != SubmitOutcome::Queued {
+            warn!(
+                "Inbound queue for channel '{}' is full ({} pending); {} message",
+                channel,
+                depth,
+                match outcome {
+                    SubmitOutcome::DroppedOldest => "dropped oldest",
+                    _ => "dropped newest",
+                }
+            );
+        }
+        self.dispatch(&Handle::current());
+        outcome
+    }
+
+    fn dispatch(self: &Arc<Self>, handle: &Handle) {
+        let max = self.policy.max_concurrency.max(1);
+        let mut resumed = Vec::new();
+        {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            while inner.active < max {
+                if let Some((lane, tx)) = inner.resuming.pop_front() {
+                    inner.active += 1;
+                    inner.lanes[lane].in_flight += 1;
+                    resumed.push((tx, self.slot(handle, lane)));
+                    continue;
+                }
+                let Some((lane, job)) = inner.pop_next() else {
+                    break;
+                };
+                inner.active += 1;
+                inner.lanes[lane].in_flight += 1;
+                let slot = self.slot(handle, lane);
+                handle.spawn(CURRENT_SLOT.scope(Mutex::new(Some(slot)), job));
+            }
+        }
+        // Outside the lock: a slot whose waiter has gone away is released on drop.
+        for (tx, slot) in resumed {
+            let _ = tx.send(slot);
+        }
+    }
+
+    fn slot(self: &Arc<Self>, handle: &Handle, lane: usize) -> WorkerSlot {
+        WorkerSlot {
+            queues: Arc::clone(self),
+            handle: handle.clone(),
+            lane,
+            finished: true,
+        }
+    }
+
+    fn release(self: &Arc<Self>, lane: usize, finished: bool, handle: &Handle) {
+        {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            inner.active = inner.active.saturating_sub(1);
+            let lane = &mut inner.lanes[lane];
+            lane.in_flight = lane.in_flight.saturating_sub(1);
+            if finished {
+                lane.processed = lane.processed.saturating_add(1);
+            }
+        }
+        self.dispatch(handle);
+    }
+
+    fn resume(self: &Arc<Self>, lane: usize, handle: &Handle) -> oneshot::Receiver<WorkerSlot> {
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            inner.resuming.push_back((lane, tx));
+        }
+        self.dispatch(handle);
+        rx
+    }
+
+    /// Per-channel queue counters, sorted by channel name.
+    pub fn snapshot(&self) -> Vec<ChannelQueueStats> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out: Vec<ChannelQueueStats> = inner
+            .lanes
+            .iter()
+            .map(|l| ChannelQueueStats {
+                channel: l.channel.clone(),
+                depth: l.jobs.len(),
+                in_flight: l.in_flight,
+                processed: l.processed,
+                dropped: l.dropped,
+            })
+            .collect();
+        out.sort_by(|a, b| a.channel.cmp(&b.channel));
+        out
+    }
+}
+
+/// Await `fut` without holding the worker slot of the queued job running on
+/// this task, then wait for a slot again. A job blocked behind its chat's
+/// running turn thus leaves the pool to other chats. Outside a queued job this
+/// just awaits `fut`.
+pub async fn without_worker_slot<F: Future>(fut: F) -> F::Output {
+    let slot = CURRENT_SLOT
+        .try_with(|current| current.lock().unwrap_or_else(|e| e.into_inner()).take())
+        .ok()
+        .flatten();
+    let Some(mut slot) = slot else {
+        return fut.await;
+    };
+    let (queues, handle, lane) = (Arc::clone(&slot.queues), slot.handle.clone(), slot.lane);
+    slot.finished = false;
+    drop(slot);
+    let out = fut.await;
+    if let Ok(slot) = queues.resume(lane, &handle).await {
+        let _ = CURRENT_SLOT.try_with(|current| {
+            *current.lock().unwrap_or_else(|e| e.into_inner()) = Some(slot);
+        });
+    }
+    out
+}
+
+/// Returns the worker slot when a job finishes (or panics) and starts the next one.
+struct WorkerSlot {
+    queues: Arc<InboundQueues>,
+    handle: Handle,
+    lane: usize,
+    /// False when the job hands the slot back mid-way and is not done yet.
+    finished: bool,
+}
+
+impl Drop for WorkerSlot {
+    fn drop(&mut self) {
+        self.queues.release(self.lane, self.finished, &self.handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::{mpsc, oneshot};
+
+    fn queues(capacity: usize, drop_policy: QueueDropPolicy) -> Arc<InboundQueues> {
+        Arc::new(InboundQueues::new(InboundQueuePolicy {
+            capacity,
+            max_concurrency: 1,
+            drop_policy,
+        }))
+    }
+
+    fn record(
+        tx: &mpsc::UnboundedSender<&'static str>,
+        label: &'static str,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let tx = tx.clone();
+        async move {
+            let _ = tx.send(label);
+        }
+    }
+
+    /// Occupies the single worker slot until the returned sender fires.
+    fn block(
+        q: &Arc<InboundQueues>,
+        channel: &str,
+        tx: &mpsc::UnboundedSender<&'static str>,
+    ) -> oneshot::Sender<()> {
+        let (release, wait) = oneshot::channel::<()>();
+        let tx = tx.clone();
+        q.submit(channel, async move {
+            let _ = wait.await;
+            let _ = tx.send("block");
+        });
+        release
+    }
+
+    async fn collect(
+        rx: &mut mpsc::UnboundedReceiver<&'static str>,
+        n: usize,
+    ) -> Vec<&'static str> {
+        let mut out = Vec::new();
+        for _ in 0..n {
+            let item = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("job did not run")
+                .expect("channel closed");
+            out.push(item);
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_when_full() {
+        let q = queues(2, QueueDropPolicy::DropOldest);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let release = block(&q, "feishu", &tx);
+        assert_eq!(q.submit("feishu", record(&tx, "a")), SubmitOutcome::Queued);
+        assert_eq!(q.submit("feishu", record(&tx, "b")), SubmitOutcome::Queued);
+        assert_eq!(
+            q.submit("feishu", record(&tx, "c")),
+            SubmitOutcome::DroppedOldest
+        );
+        let stats = q.snapshot();
+        assert_eq!(stats[0].depth, 2);
+        assert_eq!(stats[0].in_flight, 1);
+        assert_eq!(stats[0].dropped, 1);
+
+        release.send(()).unwrap();
+        assert_eq!(collect(&mut rx, 3).await, vec!["block", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_newest_when_full() {
+        let q = queues(2, QueueDropPolicy::DropNewest);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let release = block(&q, "email", &tx);
+        q.submit("email", record(&tx, "a"));
+        q.submit("email", record(&tx, "b"));
+        assert_eq!(
+            q.submit("email", record(&tx, "c")),
+            SubmitOutcome::DroppedNewest
+        );
+
+        release.send(()).unwrap();
+        assert_eq!(collect(&mut rx, 3).await, vec!["block", "a", "b"]);
+        assert_eq!(q.snapshot()[0].dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_across_channels() {
+        let q = queues(0, QueueDropPolicy::DropOldest);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let release = block(&q, "dingtalk", &tx);
+        q.submit("feishu", record(&tx, "flood-1"));
+        q.submit("feishu", record(&tx, "flood-2"));
+        q.submit("feishu", record(&tx, "flood-3"));
+        q.submit("email", record(&tx, "quiet"));
+
+        release.send(()).unwrap();
+        assert_eq!(
+            collect(&mut rx, 5).await,
+            vec!["block", "flood-1", "quiet", "flood-2", "flood-3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_job_waiting_outside_its_slot_does_not_block_other_chats() {
+        let q = queues(0, QueueDropPolicy::DropOldest);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let chat_lock = Arc::new(tokio::sync::Mutex::new(()));
+        let held = chat_lock.clone().lock_owned().await;
+        for label in ["chat-1 first", "chat-1 second"] {
+            let chat_lock = chat_lock.clone();
+            let tx = tx.clone();
+            q.submit("feishu", async move {
+                let _turn = without_worker_slot(chat_lock.lock_owned()).await;
+                let _ = tx.send(label);
+            });
+        }
+        q.submit("feishu", record(&tx, "chat-2"));
+        assert_eq!(collect(&mut rx, 1).await, vec!["chat-2"]);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(q.snapshot()[0].in_flight, 0);
+
+        drop(held);
+        assert_eq!(
+            collect(&mut rx, 2).await,
+            vec!["chat-1 first", "chat-1 second"]
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let stats = q.snapshot();
+        assert_eq!(stats[0].processed, 3);
+        assert_eq!(stats[0].in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_without_worker_slot_outside_a_queued_job() {
+        assert_eq!(without_worker_slot(async { 7 }).await, 7);
+    }
+
+    #[test]
+    fn test_parse_drop_policy() {
+        assert_eq!(
+            QueueDropPolicy::parse(" Drop_Newest "),
+            Some(QueueDropPolicy::DropNewest)
+        );
+        assert_eq!(
+            QueueDropPolicy::parse("drop_oldest"),
+            Some(QueueDropPolicy::DropOldest)
+        );
+        assert_eq!(QueueDropPolicy::parse("fifo"), None);
+    }
+}
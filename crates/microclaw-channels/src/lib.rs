@@ -1,5 +1,10 @@
 //! Channel abstractions and adapters for MicroClaw.
 
+pub mod attachment;
 pub mod channel;
 pub mod channel_adapter;
 pub mod delivery;
+pub mod health;
+pub mod inbound_queue;
+pub mod quiet_hours;
+pub mod typing;
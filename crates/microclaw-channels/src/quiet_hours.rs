@@ -0,0 +1,130 @@
+//! Per-channel quiet hours.
+//!
+//! During the daily window, messages nobody asked for (scheduled task output,
+//! sends from scheduled runs) are queued instead of delivered and flushed once
+//! the window ends. Replies to a user's own message are never held back.
+
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    tz: Tz,
+}
+
+impl QuietHours {
+    /// `start` and `end` are `HH:MM` in `timezone` (an IANA name). An end
+    /// before the start wraps past midnight, e.g. `22:00` to `07:00`.
+    pub fn parse(start: &str, end: &str, timezone: &str) -> Result<Self, String> {
+        let time = |key: &str, value: &str| {
+            NaiveTime::parse_from_str(value.trim(), "%H:%M")
+                .map_err(|_| format!("quiet_hours.{key} must be HH:MM, got '{value}'"))
+        };
+        let start = time("start", start)?;
+        let end = time("end", end)?;
+        if start == end {
+            return Err("quiet_hours.start and quiet_hours.end must differ".into());
+        }
+        let tz = timezone
+            .trim()
+            .parse::<Tz>()
+            .map_err(|_| format!("quiet_hours timezone '{timezone}' is not a valid IANA zone"))?;
+        Ok(Self { start, end, tz })
+    }
+
+    pub fn is_quiet_at(&self, now: DateTime<Utc>) -> bool {
+        let local = now.with_timezone(&self.tz).time();
+        if self.start < self.end {
+            local >= self.start && local < self.end
+        } else {
+            local >= self.start || local < self.end
+        }
+    }
+
+    /// End of the window containing `now`, or `now` itself outside quiet hours.
+    pub fn ends_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.is_quiet_at(now) {
+            return now;
+        }
+        let local = now.with_timezone(&self.tz);
+        let mut date = local.date_naive();
+        if local.time() >= self.end {
+            date = date.succ_opt().unwrap_or(date);
+        }
+        // A DST gap at the end time pushes the end to the next valid hour.
+        self.tz
+            .from_local_datetime(&date.and_time(self.end))
+            .earliest()
+            .or_else(|| {
+                self.tz
+                    .from_local_datetime(&(date.and_time(self.end) + Duration::hours(1)))
+                    .earliest()
+            })
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or(now)
+    }
+
+    /// The window as shown to users, e.g. `22:00-07:00 Europe/Berlin`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{}-{} {}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M"),
+            self.tz
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_overnight_window_wraps_midnight() {
+        let quiet = QuietHours::parse("22:00", "07:00", "Europe/Berlin").unwrap();
+        // 23:30 and 05:00 Berlin (UTC+2 in summer) are quiet; 12:00 is not.
+        assert!(quiet.is_quiet_at(utc("2026-07-01T21:30:00Z")));
+        assert!(quiet.is_quiet_at(utc("2026-07-02T03:00:00Z")));
+        assert!(!quiet.is_quiet_at(utc("2026-07-02T10:00:00Z")));
+        assert!(!quiet.is_quiet_at(utc("2026-07-02T05:00:00Z")));
+
+        assert_eq!(
+            quiet.ends_after(utc("2026-07-01T21:30:00Z")),
+            utc("2026-07-02T05:00:00Z")
+        );
+        assert_eq!(
+            quiet.ends_after(utc("2026-07-02T03:00:00Z")),
+            utc("2026-07-02T05:00:00Z")
+        );
+        let noon = utc("2026-07-02T10:00:00Z");
+        assert_eq!(quiet.ends_after(noon), noon);
+        assert_eq!(quiet.describe(), "22:00-07:00 Europe/Berlin");
+    }
+
+    #[test]
+    fn test_same_day_window_and_parse_errors() {
+        let quiet = QuietHours::parse("12:00", "13:30", "UTC").unwrap();
+        assert!(quiet.is_quiet_at(utc("2026-07-01T12:45:00Z")));
+        assert!(!quiet.is_quiet_at(utc("2026-07-01T13:30:00Z")));
+        assert_eq!(
+            quiet.ends_after(utc("2026-07-01T12:45:00Z")),
+            utc("2026-07-01T13:30:00Z")
+        );
+
+        assert!(QuietHours::parse("25:00", "07:00", "UTC")
+            .unwrap_err()
+            .contains("quiet_hours.start"));
+        assert!(QuietHours::parse("22:00", "22:00", "UTC")
+            .unwrap_err()
+            .contains("must differ"));
+        assert!(QuietHours::parse("22:00", "07:00", "Mars/Base")
+            .unwrap_err()
+            .contains("not a valid IANA zone"));
+    }
+}
@@ -0,0 +1,133 @@
+//! Typing indicator lifecycle for one agent turn.
+//!
+//! The indicator is re-sent on the adapter's refresh cadence until the turn ends,
+//! so long-running work keeps showing as in progress instead of expiring.
+
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::channel_adapter::ChannelAdapter;
+
+pub struct TypingIndicator {
+    adapter: Arc<dyn ChannelAdapter>,
+    external_chat_id: String,
+    refresher: Option<JoinHandle<()>>,
+}
+
+impl TypingIndicator {
+    /// Start showing typing in `external_chat_id`. A no-op for adapters without typing.
+    pub fn start(adapter: Arc<dyn ChannelAdapter>, external_chat_id: &str) -> Self {
+        let refresher = adapter.typing_refresh_interval().map(|interval| {
+            let adapter = adapter.clone();
+            let chat = external_chat_id.to_string();
+            tokio::spawn(async move {
+                loop {
+                    if let Err(e) = adapter.set_typing(&chat, true).await {
+                        debug!("{}: typing indicator failed: {e}", adapter.name());
+                    }
+                    tokio::time::sleep(interval).await;
+                }
+            })
+        });
+        Self {
+            adapter,
+            external_chat_id: external_chat_id.to_string(),
+            refresher,
+        }
+    }
+
+    /// Stop refreshing and clear the indicator.
+    pub async fn stop(mut self) {
+        let Some(refresher) = self.refresher.take() else {
+            return;
+        };
+        refresher.abort();
+        let _ = refresher.await;
+        if let Err(e) = self.adapter.set_typing(&self.external_chat_id, false).await {
+            debug!(
+                "{}: clearing typing indicator failed: {e}",
+                self.adapter.name()
+            );
+        }
+    }
+}
+
+impl Drop for TypingIndicator {
+    fn drop(&mut self) {
+        if let Some(refresher) = self.refresher.take() {
+            refresher.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::ConversationKind;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct RecordingAdapter {
+        interval: Option<Duration>,
+        calls: Mutex<Vec<bool>>,
+    }
+
+    #[async_trait]
+    impl ChannelAdapter for RecordingAdapter {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
+            vec![("recording", ConversationKind::Private)]
+        }
+
+        async fn send_text(&self, _external_chat_id: &str, _text: &str) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn typing_refresh_interval(&self) -> Option<Duration> {
+            self.interval
+        }
+
+        async fn set_typing(&self, _external_chat_id: &str, on: bool) -> Result<(), String> {
+            self.calls.lock().unwrap().push(on);
+            Ok(())
+        }
+    }
+
+    fn adapter(interval: Option<Duration>) -> Arc<RecordingAdapter> {
+        Arc::new(RecordingAdapter {
+            interval,
+            calls: Mutex::new(Vec::new()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_until_stopped_then_clears() {
+        let recording = adapter(Some(Duration::from_millis(20)));
+        let typing = TypingIndicator::start(recording.clone(), "chat-1");
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        typing.stop().await;
+
+        let calls = recording.calls.lock().unwrap().clone();
+        assert!(calls.len() >= 3, "expected refreshes, got {calls:?}");
+        assert_eq!(calls.last(), Some(&false));
+        assert!(calls[..calls.len() - 1].iter().all(|on| *on));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(recording.calls.lock().unwrap().len(), calls.len());
+    }
+
+    #[tokio::test]
+    async fn test_no_typing_support_is_noop() {
+        let recording = adapter(None);
+        let typing = TypingIndicator::start(recording.clone(), "chat-1");
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        typing.stop().await;
+        assert!(recording.calls.lock().unwrap().is_empty());
+    }
+}
@@ -15,6 +15,22 @@ pub struct ImageSource {
     pub data: String,
 }
 
+impl ImageSource {
+    /// Inline image: base64-encoded `data` of type `media_type`.
+    pub fn base64(media_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self {
+            source_type: "base64".into(),
+            media_type: media_type.into(),
+            data: data.into(),
+        }
+    }
+
+    /// `data:` URL form, as OpenAI-compatible APIs expect inline images.
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.data)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ContentBlock {
@@ -35,6 +51,16 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    /// Signed Anthropic reasoning, replayed verbatim during a tool-use loop as
+    /// extended thinking requires. Other providers skip it.
+    #[serde(rename = "thinking")]
+    Thinking {
+        thinking: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +86,68 @@ pub struct MessagesRequest {
     pub tools: Option<Vec<ToolDefinition>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<RequestMetadata>,
+    /// Anthropic `tool_choice` object; omitted means `auto`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<serde_json::Value>,
+    /// Anthropic extended thinking, e.g. `{"type": "enabled", "budget_tokens": 4096}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<serde_json::Value>,
+}
+
+/// Anthropic request `metadata`; `user_id` carries the turn's correlation id.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestMetadata {
+    pub user_id: String,
+}
+
+/// Whether, and which, tool the model must call on a request.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ToolChoice {
+    #[default]
+    Auto,
+    /// Answer without calling tools.
+    None,
+    /// Call at least one tool.
+    Required,
+    /// Call this specific tool.
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// Parse `auto`, `none`, `required` (or `any`), or a tool name, optionally
+    /// written as `tool:<name>`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        match raw.to_ascii_lowercase().as_str() {
+            "" => None,
+            "auto" => Some(ToolChoice::Auto),
+            "none" => Some(ToolChoice::None),
+            "required" | "any" => Some(ToolChoice::Required),
+            _ => {
+                let name = raw.strip_prefix("tool:").unwrap_or(raw).trim();
+                (!name.is_empty()).then(|| ToolChoice::Tool(name.to_string()))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ToolChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolChoice::Auto => write!(f, "auto"),
+            ToolChoice::None => write!(f, "none"),
+            ToolChoice::Required => write!(f, "required"),
+            ToolChoice::Tool(name) => write!(f, "tool:{name}"),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -70,6 +158,66 @@ pub struct MessagesResponse {
     pub usage: Option<Usage>,
 }
 
+impl MessagesResponse {
+    /// Why generation stopped, in provider-independent terms.
+    pub fn finish_reason(&self) -> FinishReason {
+        FinishReason::parse(self.stop_reason.as_deref())
+    }
+}
+
+/// Why a model stopped generating, normalized across providers' stop and
+/// finish reasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinishReason {
+    /// The model finished its answer (Anthropic `end_turn`, OpenAI `stop`).
+    EndTurn,
+    /// The model is waiting on tool results.
+    ToolUse,
+    /// Output hit the token limit; the reply is truncated.
+    MaxTokens,
+    /// A configured stop sequence ended the output.
+    StopSequence,
+    /// The provider withheld or cut the output (OpenAI `content_filter`,
+    /// Anthropic `refusal`).
+    ContentFilter,
+    /// Anything else, e.g. Anthropic `pause_turn`.
+    Other,
+}
+
+impl FinishReason {
+    /// Map a provider's raw reason. A missing reason counts as a normal end.
+    pub fn parse(raw: Option<&str>) -> Self {
+        match raw.map(str::trim) {
+            None | Some("") | Some("end_turn") | Some("stop") => FinishReason::EndTurn,
+            Some("tool_use") | Some("tool_calls") | Some("function_call") => FinishReason::ToolUse,
+            Some("max_tokens") | Some("length") | Some("max_output_tokens") => {
+                FinishReason::MaxTokens
+            }
+            Some("stop_sequence") => FinishReason::StopSequence,
+            Some("content_filter") | Some("refusal") => FinishReason::ContentFilter,
+            Some(_) => FinishReason::Other,
+        }
+    }
+
+    /// The canonical name stored in `MessagesResponse::stop_reason`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FinishReason::EndTurn => "end_turn",
+            FinishReason::ToolUse => "tool_use",
+            FinishReason::MaxTokens => "max_tokens",
+            FinishReason::StopSequence => "stop_sequence",
+            FinishReason::ContentFilter => "content_filter",
+            FinishReason::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for FinishReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type")]
 pub enum ResponseContentBlock {
@@ -81,7 +229,20 @@ pub enum ResponseContentBlock {
         name: String,
         input: serde_json::Value,
     },
-    /// Catch-all for unknown block types (e.g. "thinking" from MiniMax M2.5)
+    /// Model reasoning (Anthropic `thinking` blocks, OpenAI-compatible
+    /// `reasoning_content`). Never part of the user-visible reply.
+    #[serde(rename = "thinking")]
+    Thinking {
+        #[serde(default)]
+        thinking: String,
+        /// Anthropic signature; required to send the block back.
+        #[serde(default)]
+        signature: Option<String>,
+    },
+    /// Anthropic reasoning encrypted by safety systems; only replayable as-is.
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+    /// Catch-all for unknown block types (e.g. "redacted_thinking")
     #[serde(other)]
     Other,
 }
@@ -194,6 +355,38 @@ mod tests {
         assert_eq!(resp.usage.as_ref().unwrap().output_tokens, 5);
     }
 
+    #[test]
+    fn test_finish_reason_normalizes_provider_reasons() {
+        assert_eq!(FinishReason::parse(None), FinishReason::EndTurn);
+        assert_eq!(FinishReason::parse(Some("stop")), FinishReason::EndTurn);
+        assert_eq!(
+            FinishReason::parse(Some("tool_calls")),
+            FinishReason::ToolUse
+        );
+        assert_eq!(FinishReason::parse(Some("length")), FinishReason::MaxTokens);
+        assert_eq!(
+            FinishReason::parse(Some("max_output_tokens")),
+            FinishReason::MaxTokens
+        );
+        assert_eq!(
+            FinishReason::parse(Some("stop_sequence")),
+            FinishReason::StopSequence
+        );
+        assert_eq!(
+            FinishReason::parse(Some("refusal")),
+            FinishReason::ContentFilter
+        );
+        assert_eq!(FinishReason::parse(Some("pause_turn")), FinishReason::Other);
+
+        let resp: MessagesResponse = serde_json::from_value(json!({
+            "content": [{"type": "text", "text": "cut"}],
+            "stop_reason": "max_tokens"
+        }))
+        .unwrap();
+        assert_eq!(resp.finish_reason(), FinishReason::MaxTokens);
+        assert_eq!(resp.finish_reason().to_string(), "max_tokens");
+    }
+
     #[test]
     fn test_response_content_block_tool_use_deserialization() {
         let json = json!({
@@ -225,11 +418,39 @@ mod tests {
             }],
             tools: None,
             stream: None,
+            temperature: None,
+            top_p: None,
+            stop_sequences: None,
+            metadata: None,
+            tool_choice: None,
+            thinking: None,
         };
         let json = serde_json::to_value(&req).unwrap();
         assert_eq!(json["model"], "claude-sonnet-4-5-20250929");
         assert_eq!(json["max_tokens"], 4096);
         assert!(json.get("tools").is_none()); // skip_serializing_if None
+        assert!(json.get("temperature").is_none());
+        assert!(json.get("stop_sequences").is_none());
+        assert!(json.get("metadata").is_none());
+        assert!(json.get("tool_choice").is_none());
+    }
+
+    #[test]
+    fn test_tool_choice_parse_and_display() {
+        assert_eq!(ToolChoice::parse("AUTO"), Some(ToolChoice::Auto));
+        assert_eq!(ToolChoice::parse("none"), Some(ToolChoice::None));
+        assert_eq!(ToolChoice::parse("any"), Some(ToolChoice::Required));
+        assert_eq!(
+            ToolChoice::parse("tool:web_search"),
+            Some(ToolChoice::Tool("web_search".into()))
+        );
+        assert_eq!(
+            ToolChoice::parse("web_search"),
+            Some(ToolChoice::Tool("web_search".into()))
+        );
+        assert_eq!(ToolChoice::parse("  "), None);
+        assert_eq!(ToolChoice::parse("tool:"), None);
+        assert_eq!(ToolChoice::Tool("bash".into()).to_string(), "tool:bash");
     }
 
     #[test]
@@ -245,6 +466,12 @@ mod tests {
                 input_schema: json!({"type": "object"}),
             }]),
             stream: None,
+            temperature: None,
+            top_p: None,
+            stop_sequences: None,
+            metadata: None,
+            tool_choice: None,
+            thinking: None,
         };
         let json = serde_json::to_value(&req).unwrap();
         assert!(json["tools"].is_array());
@@ -278,4 +505,39 @@ mod tests {
         assert_eq!(json["type"], "base64");
         assert_eq!(json["media_type"], "image/png");
     }
+
+    #[test]
+    fn test_image_source_base64_and_data_url() {
+        let source = ImageSource::base64("image/png", "ABCDEF");
+        assert_eq!(source.source_type, "base64");
+        assert_eq!(source.data_url(), "data:image/png;base64,ABCDEF");
+    }
+
+    #[test]
+    fn test_response_thinking_block_deserializes() {
+        let resp: MessagesResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "thinking", "thinking": "step 1", "signature": "sig"},
+                {"type": "thinking"},
+                {"type": "redacted_thinking", "data": "x"},
+                {"type": "text", "text": "answer"}
+            ],
+            "stop_reason": "end_turn"
+        }))
+        .unwrap();
+        assert!(matches!(
+            &resp.content[0],
+            ResponseContentBlock::Thinking { thinking, signature }
+                if thinking == "step 1" && signature.as_deref() == Some("sig")
+        ));
+        assert!(matches!(
+            &resp.content[1],
+            ResponseContentBlock::Thinking { thinking, signature: None } if thinking.is_empty()
+        ));
+        assert!(matches!(
+            &resp.content[2],
+            ResponseContentBlock::RedactedThinking { data } if data == "x"
+        ));
+        assert!(matches!(resp.content[3], ResponseContentBlock::Text { .. }));
+    }
 }
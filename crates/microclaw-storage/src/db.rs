@@ -9,6 +9,98 @@ use microclaw_core::error::MicroClawError;
 
 pub struct Database {
     conn: Mutex<Connection>,
+    /// SQLCipher key the database was opened with; backups reuse it.
+    key: Option<String>,
+}
+
+const DB_FILE_NAME: &str = "microclaw.db";
+
+/// Whether this build links SQLCipher (the `sqlcipher` feature).
+fn sqlcipher_available(conn: &Connection) -> bool {
+    conn.query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0))
+        .is_ok()
+}
+
+/// Apply `key` to a freshly opened connection and check that the file can be
+/// read, so a wrong or missing key fails here rather than on first query.
+fn unlock_connection(
+    conn: &Connection,
+    key: Option<&str>,
+    db_path: &Path,
+) -> Result<(), MicroClawError> {
+    if let Some(key) = key {
+        if !sqlcipher_available(conn) {
+            return Err(MicroClawError::Config(
+                "db_encryption is enabled but this build has no SQLCipher support; rebuild with --features sqlcipher".into(),
+            ));
+        }
+        conn.pragma_update(None, "key", key)?;
+    }
+    match conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(())) {
+        Ok(()) => Ok(()),
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::NotADatabase =>
+        {
+            Err(MicroClawError::Config(if key.is_some() {
+                format!(
+                    "cannot open {}: wrong database key, or the file is not encrypted (encrypt it with `microclaw db encrypt`)",
+                    db_path.display()
+                )
+            } else {
+                format!(
+                    "cannot open {}: the file is encrypted or not a SQLite database; configure db_encryption with its key",
+                    db_path.display()
+                )
+            }))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Encrypt the plaintext database in `data_dir` in place with SQLCipher. The
+/// plaintext file is replaced only once the encrypted copy opens with `key`.
+pub fn encrypt_database(data_dir: &str, key: &str) -> Result<(), MicroClawError> {
+    if key.is_empty() {
+        return Err(MicroClawError::Config(
+            "database key must not be empty".into(),
+        ));
+    }
+    let db_path = Path::new(data_dir).join(DB_FILE_NAME);
+    if !db_path.exists() {
+        return Err(MicroClawError::Config(format!(
+            "no database at {}",
+            db_path.display()
+        )));
+    }
+    let tmp_path = db_path.with_extension("db.encrypting");
+    let _ = std::fs::remove_file(&tmp_path);
+    {
+        let conn = Connection::open(&db_path)?;
+        if !sqlcipher_available(&conn) {
+            return Err(MicroClawError::Config(
+                "this build has no SQLCipher support; rebuild with --features sqlcipher".into(),
+            ));
+        }
+        unlock_connection(&conn, None, &db_path).map_err(|_| {
+            MicroClawError::Config(format!(
+                "{} is already encrypted or not a SQLite database",
+                db_path.display()
+            ))
+        })?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            params![tmp_path.to_string_lossy(), key],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.execute("DETACH DATABASE encrypted", [])?;
+    }
+    unlock_connection(&Connection::open(&tmp_path)?, Some(key), &tmp_path)?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", db_path.display()));
+    }
+    std::fs::rename(&tmp_path, &db_path)?;
+    Ok(())
 }
 
 #[cfg(feature = "sqlite-vec")]
@@ -61,6 +153,8 @@ pub struct TaskRunLog {
     pub duration_ms: i64,
     pub success: bool,
     pub result_summary: Option<String>,
+    /// Run made on startup for a fire time missed while the bot was down.
+    pub catch_up: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +189,139 @@ pub struct Memory {
     pub last_seen_at: String,
     pub is_archived: bool,
     pub archived_at: Option<String>,
+    /// Group member the memory belongs to; `None` is shared by the whole chat.
+    pub sender_id: Option<String>,
+    /// Always injected into context, ahead of ranked memories, and never
+    /// archived as stale.
+    pub pinned: bool,
+}
+
+/// A memory's vector and the model that produced it. Stored only when the
+/// `sqlite-vec` feature is enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryEmbedding {
+    pub vector: Vec<f32>,
+    pub model: String,
+}
+
+/// One write in [`Database::write_memory_batch`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryBatchOp {
+    Insert {
+        chat_id: Option<i64>,
+        content: String,
+        category: String,
+        source: String,
+        confidence: f64,
+        sender_id: Option<String>,
+        embedding: Option<MemoryEmbedding>,
+    },
+    Update {
+        id: i64,
+        content: String,
+        category: String,
+        confidence: f64,
+        source: String,
+        embedding: Option<MemoryEmbedding>,
+    },
+    /// Archive `from_memory_id` and insert its replacement, linked by a supersede edge.
+    Supersede {
+        from_memory_id: i64,
+        content: String,
+        category: String,
+        source: String,
+        confidence: f64,
+        reason: Option<String>,
+        embedding: Option<MemoryEmbedding>,
+    },
+    Touch {
+        id: i64,
+        confidence_floor: Option<f64>,
+    },
+    /// Store a vector for an existing memory without touching its content.
+    Embed { id: i64, embedding: MemoryEmbedding },
+}
+
+impl MemoryBatchOp {
+    /// Text a fresh embedding should be computed from, for ops that write content.
+    pub fn content(&self) -> Option<&str> {
+        match self {
+            Self::Insert { content, .. }
+            | Self::Update { content, .. }
+            | Self::Supersede { content, .. } => Some(content),
+            Self::Touch { .. } | Self::Embed { .. } => None,
+        }
+    }
+
+    /// The embedding slot of a content-writing op.
+    pub fn embedding_mut(&mut self) -> Option<&mut Option<MemoryEmbedding>> {
+        match self {
+            Self::Insert { embedding, .. }
+            | Self::Update { embedding, .. }
+            | Self::Supersede { embedding, .. } => Some(embedding),
+            Self::Touch { .. } | Self::Embed { .. } => None,
+        }
+    }
+}
+
+/// Visibility namespace of a structured memory, written as `global`,
+/// `chat:<id>` or `user:<id>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryScope {
+    Global,
+    Chat(i64),
+    User(String),
+}
+
+impl MemoryScope {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if raw.eq_ignore_ascii_case("global") {
+            return Some(MemoryScope::Global);
+        }
+        if let Some(id) = raw.strip_prefix("chat:") {
+            return id.trim().parse().ok().map(MemoryScope::Chat);
+        }
+        let id = raw.strip_prefix("user:")?.trim();
+        (!id.is_empty()).then(|| MemoryScope::User(id.to_string()))
+    }
+}
+
+impl std::fmt::Display for MemoryScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryScope::Global => write!(f, "global"),
+            MemoryScope::Chat(id) => write!(f, "chat:{id}"),
+            MemoryScope::User(id) => write!(f, "user:{id}"),
+        }
+    }
+}
+
+fn missing_batch_memory(id: i64) -> MicroClawError {
+    MicroClawError::ToolExecution(format!("memory batch aborted: memory #{id} not found"))
+}
+
+impl Memory {
+    /// A sender owner wins over the chat: a user memory stays private to that
+    /// member even when it was recorded in a shared chat.
+    pub fn scope(&self) -> MemoryScope {
+        match (&self.sender_id, self.chat_id) {
+            (Some(sender), _) => MemoryScope::User(sender.clone()),
+            (None, Some(chat_id)) => MemoryScope::Chat(chat_id),
+            (None, None) => MemoryScope::Global,
+        }
+    }
+
+    /// Whether a turn in `chat_id` triggered by `sender_id` may read this memory.
+    pub fn visible_to(&self, chat_id: i64, sender_id: Option<&str>) -> bool {
+        if self.chat_id.is_some_and(|id| id != chat_id) {
+            return false;
+        }
+        match self.sender_id.as_deref() {
+            Some(owner) => sender_id == Some(owner),
+            None => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -180,10 +407,41 @@ pub struct AuditLogRecord {
     pub created_at: String,
 }
 
+/// One tool execution as recorded in the append-only `tool_audit_logs` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolAuditRecord {
+    pub id: i64,
+    pub created_at: String,
+    pub channel: String,
+    pub chat_id: i64,
+    pub tool_name: String,
+    /// Tool input with secrets redacted and internal keys removed.
+    pub input: String,
+    pub risk: String,
+    /// `not_required`, `approved` or `denied`.
+    pub approval: String,
+    /// `ok` or `error`.
+    pub status: String,
+    pub error_type: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// Filters for [`Database::list_tool_audit_logs`]; `None` matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct ToolAuditFilter {
+    pub channel: Option<String>,
+    pub chat_id: Option<i64>,
+    pub tool_name: Option<String>,
+    pub status: Option<String>,
+    /// RFC 3339 lower bound on `created_at`, inclusive.
+    pub since: Option<String>,
+    pub limit: usize,
+}
+
 pub type SessionMetaRow = (String, String, Option<String>, Option<i64>);
 pub type SessionTreeRow = (i64, Option<String>, Option<i64>, String);
 
-const SCHEMA_VERSION_CURRENT: i64 = 11;
+const SCHEMA_VERSION_CURRENT: i64 = 24;
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -197,6 +455,35 @@ pub struct ScheduledTask {
     pub last_run: Option<String>,
     pub status: String, // "active", "paused", "completed", "cancelled"
     pub created_at: String,
+    pub timezone: Option<String>, // IANA name; None uses the configured default
+    pub deliver: String,          // "channel", "dm", "on_error_only" or "silent"
+    pub deliver_chat_id: Option<i64>, // DM target when deliver = "dm"
+    pub template: Option<String>, // task template the task was created from
+    pub missed_run_policy: String, // "skip", "run_once_on_startup" or "run_all"
+}
+
+/// A saved task a chat can schedule again with different parameters. `prompt`
+/// and `schedule_value` may contain `{{param}}` placeholders.
+#[derive(Debug, Clone)]
+pub struct TaskTemplate {
+    pub chat_id: i64,
+    pub name: String,
+    pub prompt: String,
+    pub schedule_type: String,
+    pub schedule_value: String,
+    pub updated_at: String,
+}
+
+/// A bot message held back by the chat's channel quiet hours, sent once they end.
+#[derive(Debug, Clone)]
+pub struct DeferredMessage {
+    pub id: i64,
+    pub chat_id: i64,
+    pub bot_username: String,
+    pub content: String,
+    pub created_at: String,
+    /// Failed delivery attempts so far.
+    pub attempts: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -615,12 +902,210 @@ fn apply_schema_migrations(conn: &Connection) -> Result<(), MicroClawError> {
         set_schema_version(conn, 11)?;
         version = 11;
     }
+    if version < 12 {
+        if !table_has_column(conn, "scheduled_tasks", "timezone")? {
+            conn.execute("ALTER TABLE scheduled_tasks ADD COLUMN timezone TEXT", [])?;
+        }
+        set_schema_version(conn, 12)?;
+        version = 12;
+    }
+    if version < 13 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tool_audit_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                chat_id INTEGER NOT NULL,
+                tool_name TEXT NOT NULL,
+                input TEXT NOT NULL,
+                risk TEXT NOT NULL,
+                approval TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error_type TEXT,
+                duration_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_tool_audit_logs_created
+                ON tool_audit_logs(created_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_tool_audit_logs_chat_created
+                ON tool_audit_logs(channel, chat_id, created_at DESC);
+            CREATE TRIGGER IF NOT EXISTS tool_audit_logs_no_update
+                BEFORE UPDATE ON tool_audit_logs
+                BEGIN SELECT RAISE(ABORT, 'tool_audit_logs is append-only'); END;
+            CREATE TRIGGER IF NOT EXISTS tool_audit_logs_no_delete
+                BEFORE DELETE ON tool_audit_logs
+                BEGIN SELECT RAISE(ABORT, 'tool_audit_logs is append-only'); END;",
+        )?;
+        set_schema_version(conn, 13)?;
+        version = 13;
+    }
+    if version < 14 {
+        if !table_has_column(conn, "scheduled_tasks", "deliver")? {
+            conn.execute(
+                "ALTER TABLE scheduled_tasks ADD COLUMN deliver TEXT NOT NULL DEFAULT 'channel'",
+                [],
+            )?;
+        }
+        if !table_has_column(conn, "scheduled_tasks", "deliver_chat_id")? {
+            conn.execute(
+                "ALTER TABLE scheduled_tasks ADD COLUMN deliver_chat_id INTEGER",
+                [],
+            )?;
+        }
+        set_schema_version(conn, 14)?;
+        version = 14;
+    }
+    if version < 15 {
+        if !table_has_column(conn, "memories", "sender_id")? {
+            conn.execute("ALTER TABLE memories ADD COLUMN sender_id TEXT", [])?;
+        }
+        set_schema_version(conn, 15)?;
+        version = 15;
+    }
+    if version < 16 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS inbound_message_dedup (
+                channel TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                seen_at_ms INTEGER NOT NULL,
+                PRIMARY KEY (channel, message_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_inbound_message_dedup_seen
+                ON inbound_message_dedup(seen_at_ms);",
+        )?;
+        set_schema_version(conn, 16)?;
+        version = 16;
+    }
+    if version < 17 {
+        // Trigram tokens give case-insensitive substring matching that also
+        // works for CJK text.
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts
+                USING fts5(content, tokenize = 'trigram case_sensitive 0');
+            CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages
+                BEGIN
+                    INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+                END;
+            CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages
+                BEGIN
+                    DELETE FROM messages_fts WHERE rowid = old.rowid;
+                    INSERT INTO messages_fts(rowid, content) VALUES (new.rowid, new.content);
+                END;
+            CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages
+                BEGIN
+                    DELETE FROM messages_fts WHERE rowid = old.rowid;
+                END;
+            DELETE FROM messages_fts;
+            INSERT INTO messages_fts(rowid, content) SELECT rowid, content FROM messages;",
+        )?;
+        set_schema_version(conn, 17)?;
+        version = 17;
+    }
+    if version < 18 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_templates (
+                chat_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                schedule_type TEXT NOT NULL,
+                schedule_value TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (chat_id, name)
+            );",
+        )?;
+        if !table_has_column(conn, "scheduled_tasks", "template")? {
+            conn.execute("ALTER TABLE scheduled_tasks ADD COLUMN template TEXT", [])?;
+        }
+        set_schema_version(conn, 18)?;
+        version = 18;
+    }
+    if version < 19 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS deferred_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                bot_username TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_deferred_messages_chat ON deferred_messages(chat_id);",
+        )?;
+        set_schema_version(conn, 19)?;
+        version = 19;
+    }
+    if version < 20 {
+        if !table_has_column(conn, "chats", "max_response_tokens")? {
+            conn.execute(
+                "ALTER TABLE chats ADD COLUMN max_response_tokens INTEGER",
+                [],
+            )?;
+        }
+        set_schema_version(conn, 20)?;
+        version = 20;
+    }
+    if version < 21 {
+        if !table_has_column(conn, "chats", "debug_footer")? {
+            conn.execute(
+                "ALTER TABLE chats ADD COLUMN debug_footer INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        set_schema_version(conn, 21)?;
+        version = 21;
+    }
+    if version < 22 {
+        if !table_has_column(conn, "scheduled_tasks", "missed_run_policy")? {
+            conn.execute(
+                "ALTER TABLE scheduled_tasks ADD COLUMN missed_run_policy TEXT NOT NULL DEFAULT 'run_once_on_startup'",
+                [],
+            )?;
+        }
+        if !table_has_column(conn, "task_run_logs", "catch_up")? {
+            conn.execute(
+                "ALTER TABLE task_run_logs ADD COLUMN catch_up INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        set_schema_version(conn, 22)?;
+        version = 22;
+    }
+    if version < 23 {
+        if !table_has_column(conn, "memories", "pinned")? {
+            conn.execute(
+                "ALTER TABLE memories ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        set_schema_version(conn, 23)?;
+        version = 23;
+    }
+    if version < 24 {
+        if !table_has_column(conn, "deferred_messages", "attempts")? {
+            conn.execute(
+                "ALTER TABLE deferred_messages ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+        set_schema_version(conn, 24)?;
+        version = 24;
+    }
     if version != SCHEMA_VERSION_CURRENT {
         set_schema_version(conn, SCHEMA_VERSION_CURRENT)?;
     }
     Ok(())
 }
 
+fn task_template_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TaskTemplate> {
+    Ok(TaskTemplate {
+        chat_id: row.get(0)?,
+        name: row.get(1)?,
+        prompt: row.get(2)?,
+        schedule_type: row.get(3)?,
+        schedule_value: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
 impl Database {
     fn lock_conn(&self) -> MutexGuard<'_, Connection> {
         match self.conn.lock() {
@@ -629,8 +1114,42 @@ impl Database {
         }
     }
 
+    /// Copy the live database to `dest` with SQLite's online backup API, so the
+    /// snapshot is consistent even while WAL frames are pending.
+    /// An encrypted database is copied encrypted with the same key.
+    pub fn backup_to(&self, dest: &Path) -> Result<(), MicroClawError> {
+        let conn = self.lock_conn();
+        match &self.key {
+            None => conn.backup(rusqlite::MAIN_DB, dest, None)?,
+            Some(key) => {
+                let mut dest_conn = Connection::open(dest)?;
+                dest_conn.pragma_update(None, "key", key)?;
+                rusqlite::backup::Backup::new(&conn, &mut dest_conn)?.run_to_completion(
+                    100,
+                    std::time::Duration::ZERO,
+                    None,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Back up the database into `data_dir` and open the copy, e.g. as a
+    /// scratch database that can be modified without touching the original.
+    pub fn copy_to_dir(&self, data_dir: &str) -> Result<Database, MicroClawError> {
+        std::fs::create_dir_all(data_dir)?;
+        self.backup_to(&Path::new(data_dir).join(DB_FILE_NAME))?;
+        Database::open(data_dir, self.key.as_deref())
+    }
+
     pub fn new(data_dir: &str) -> Result<Self, MicroClawError> {
-        let db_path = Path::new(data_dir).join("microclaw.db");
+        Self::open(data_dir, None)
+    }
+
+    /// Open (or create) the database in `data_dir`; with `key` it is a
+    /// SQLCipher database encrypted with that key.
+    pub fn open(data_dir: &str, key: Option<&str>) -> Result<Self, MicroClawError> {
+        let db_path = Path::new(data_dir).join(DB_FILE_NAME);
         std::fs::create_dir_all(data_dir)?;
 
         #[cfg(feature = "sqlite-vec")]
@@ -640,7 +1159,8 @@ impl Database {
             rusqlite::ffi::sqlite3_auto_extension(Some(init_fn));
         });
 
-        let conn = Connection::open(db_path)?;
+        let conn = Connection::open(&db_path)?;
+        unlock_connection(&conn, key, &db_path)?;
         conn.execute_batch("PRAGMA journal_mode=WAL;")?;
 
         conn.execute_batch(
@@ -876,6 +1396,7 @@ impl Database {
 
         Ok(Database {
             conn: Mutex::new(conn),
+            key: key.map(str::to_string),
         })
     }
 
@@ -968,8 +1489,15 @@ impl Database {
     pub fn store_message(&self, msg: &StoredMessage) -> Result<(), MicroClawError> {
         let conn = self.lock_conn();
         conn.execute(
-            "INSERT OR REPLACE INTO messages (id, chat_id, sender_name, content, is_from_bot, timestamp)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            // An upsert rather than INSERT OR REPLACE: REPLACE deletes without
+            // firing the delete trigger that keeps `messages_fts` in sync.
+            "INSERT INTO messages (id, chat_id, sender_name, content, is_from_bot, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id, chat_id) DO UPDATE SET
+                sender_name = excluded.sender_name,
+                content = excluded.content,
+                is_from_bot = excluded.is_from_bot,
+                timestamp = excluded.timestamp",
             params![
                 msg.id,
                 msg.chat_id,
@@ -999,6 +1527,29 @@ impl Database {
         Ok(affected > 0)
     }
 
+    /// Record an inbound delivery of `message_id` on `channel`. Returns false
+    /// when the same key was already recorded within the last `window_ms`.
+    /// Entries older than the window are pruned.
+    pub fn claim_inbound_message(
+        &self,
+        channel: &str,
+        message_id: &str,
+        now_ms: i64,
+        window_ms: i64,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        conn.execute(
+            "DELETE FROM inbound_message_dedup WHERE seen_at_ms < ?1",
+            params![now_ms.saturating_sub(window_ms)],
+        )?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO inbound_message_dedup (channel, message_id, seen_at_ms)
+             VALUES (?1, ?2, ?3)",
+            params![channel, message_id, now_ms],
+        )?;
+        Ok(inserted > 0)
+    }
+
     pub fn message_exists(&self, chat_id: i64, message_id: &str) -> Result<bool, MicroClawError> {
         let conn = self.lock_conn();
         let exists = conn
@@ -1068,6 +1619,99 @@ impl Database {
         Ok(messages)
     }
 
+    /// Full-text search over stored messages, optionally limited to one chat.
+    ///
+    /// Every whitespace-separated term must occur (case-insensitive substring).
+    /// Terms of three or more characters use the `messages_fts` index; shorter
+    /// ones fall back to a `LIKE` scan. Best matches come first, newest first
+    /// among equals.
+    pub fn search_messages(
+        &self,
+        query: &str,
+        chat_id: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<StoredMessage>, MicroClawError> {
+        let terms: Vec<&str> = query
+            .split_whitespace()
+            .map(|t| t.trim_matches('"'))
+            .filter(|t| !t.is_empty())
+            .collect();
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let (indexed, short): (Vec<&str>, Vec<&str>) =
+            terms.into_iter().partition(|t| t.chars().count() >= 3);
+
+        let mut sql = String::from(
+            "SELECT m.id, m.chat_id, m.sender_name, m.content, m.is_from_bot, m.timestamp
+             FROM messages m",
+        );
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut args: Vec<rusqlite::types::Value> = Vec::new();
+        if !indexed.is_empty() {
+            sql.push_str(" JOIN messages_fts ON messages_fts.rowid = m.rowid");
+            conditions.push("messages_fts MATCH ?");
+            let expr = indexed
+                .iter()
+                .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            args.push(expr.into());
+        }
+        for term in short {
+            conditions.push("m.content LIKE ? ESCAPE '\\'");
+            let escaped = term
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            args.push(format!("%{escaped}%").into());
+        }
+        if let Some(chat_id) = chat_id {
+            conditions.push("m.chat_id = ?");
+            args.push(chat_id.into());
+        }
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+        if indexed.is_empty() {
+            sql.push_str(" ORDER BY m.timestamp DESC");
+        } else {
+            sql.push_str(" ORDER BY bm25(messages_fts), m.timestamp DESC");
+        }
+        sql.push_str(" LIMIT ?");
+        args.push((limit as i64).into());
+
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(&sql)?;
+        let messages = stmt
+            .query_map(rusqlite::params_from_iter(args), |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    sender_name: row.get(2)?,
+                    content: row.get(3)?,
+                    is_from_bot: row.get::<_, i32>(4)? != 0,
+                    timestamp: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    /// Distinct non-bot senders seen in a chat, as stored in `sender_name`.
+    pub fn get_chat_sender_names(&self, chat_id: i64) -> Result<Vec<String>, MicroClawError> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT sender_name
+             FROM messages
+             WHERE chat_id = ?1 AND is_from_bot = 0
+             ORDER BY sender_name",
+        )?;
+        let names = stmt
+            .query_map(params![chat_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(names)
+    }
+
     pub fn get_chats_by_type(
         &self,
         chat_type: &str,
@@ -1175,6 +1819,68 @@ impl Database {
         }
     }
 
+    /// The chat's `/verbosity` reply length limit, if one is set.
+    pub fn get_chat_max_response_tokens(
+        &self,
+        chat_id: i64,
+    ) -> Result<Option<u32>, MicroClawError> {
+        let conn = self.lock_conn();
+        let result = conn.query_row(
+            "SELECT max_response_tokens FROM chats WHERE chat_id = ?1",
+            params![chat_id],
+            |row| row.get::<_, Option<u32>>(0),
+        );
+        match result {
+            Ok(v) => Ok(v),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set (`Some`) or clear (`None`) the chat's reply length limit. Returns
+    /// false when the chat is unknown.
+    pub fn set_chat_max_response_tokens(
+        &self,
+        chat_id: i64,
+        max_response_tokens: Option<u32>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "UPDATE chats SET max_response_tokens = ?2 WHERE chat_id = ?1",
+            params![chat_id, max_response_tokens],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Whether `/debug` turned on the per-turn cost and latency footer.
+    pub fn get_chat_debug_footer(&self, chat_id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let result = conn.query_row(
+            "SELECT debug_footer FROM chats WHERE chat_id = ?1",
+            params![chat_id],
+            |row| row.get::<_, bool>(0),
+        );
+        match result {
+            Ok(v) => Ok(v),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns false when the chat is unknown.
+    pub fn set_chat_debug_footer(
+        &self,
+        chat_id: i64,
+        enabled: bool,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "UPDATE chats SET debug_footer = ?2 WHERE chat_id = ?1",
+            params![chat_id, enabled],
+        )?;
+        Ok(rows > 0)
+    }
+
     pub fn get_chat_channel(&self, chat_id: i64) -> Result<Option<String>, MicroClawError> {
         let conn = self.lock_conn();
         let result = conn.query_row(
@@ -1277,25 +1983,226 @@ impl Database {
     pub fn create_scheduled_task(
         &self,
         chat_id: i64,
-        prompt: &str,
-        schedule_type: &str,
-        schedule_value: &str,
-        next_run: &str,
+        prompt: &str,
+        schedule_type: &str,
+        schedule_value: &str,
+        next_run: &str,
+    ) -> Result<i64, MicroClawError> {
+        self.create_scheduled_task_with_timezone(
+            chat_id,
+            prompt,
+            schedule_type,
+            schedule_value,
+            next_run,
+            None,
+        )
+    }
+
+    pub fn create_scheduled_task_with_timezone(
+        &self,
+        chat_id: i64,
+        prompt: &str,
+        schedule_type: &str,
+        schedule_value: &str,
+        next_run: &str,
+        timezone: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
+        self.create_scheduled_task_with_delivery(
+            chat_id,
+            prompt,
+            schedule_type,
+            schedule_value,
+            next_run,
+            timezone,
+            "channel",
+            None,
+        )
+    }
+
+    /// `deliver_chat_id` is the private chat that receives output when `deliver` is `dm`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_scheduled_task_with_delivery(
+        &self,
+        chat_id: i64,
+        prompt: &str,
+        schedule_type: &str,
+        schedule_value: &str,
+        next_run: &str,
+        timezone: Option<&str>,
+        deliver: &str,
+        deliver_chat_id: Option<i64>,
+    ) -> Result<i64, MicroClawError> {
+        let conn = self.lock_conn();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO scheduled_tasks (chat_id, prompt, schedule_type, schedule_value, next_run, status, created_at, timezone, deliver, deliver_chat_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6, ?7, ?8, ?9)",
+            params![
+                chat_id,
+                prompt,
+                schedule_type,
+                schedule_value,
+                next_run,
+                now,
+                timezone,
+                deliver,
+                deliver_chat_id
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Record the template a task was instantiated from.
+    pub fn set_task_template(&self, task_id: i64, template: &str) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET template = ?1 WHERE id = ?2",
+            params![template, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Set what happens to runs the task misses while the bot is down.
+    pub fn set_task_missed_run_policy(
+        &self,
+        task_id: i64,
+        policy: &str,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "UPDATE scheduled_tasks SET missed_run_policy = ?1 WHERE id = ?2",
+            params![policy, task_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Create or replace the chat's template called `name`.
+    pub fn save_task_template(
+        &self,
+        chat_id: i64,
+        name: &str,
+        prompt: &str,
+        schedule_type: &str,
+        schedule_value: &str,
+    ) -> Result<(), MicroClawError> {
+        let conn = self.lock_conn();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO task_templates (chat_id, name, prompt, schedule_type, schedule_value, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+             ON CONFLICT(chat_id, name) DO UPDATE SET
+                prompt = ?3,
+                schedule_type = ?4,
+                schedule_value = ?5,
+                updated_at = ?6",
+            params![chat_id, name, prompt, schedule_type, schedule_value, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_task_template(
+        &self,
+        chat_id: i64,
+        name: &str,
+    ) -> Result<Option<TaskTemplate>, MicroClawError> {
+        let conn = self.lock_conn();
+        let result = conn.query_row(
+            "SELECT chat_id, name, prompt, schedule_type, schedule_value, updated_at
+             FROM task_templates
+             WHERE chat_id = ?1 AND name = ?2",
+            params![chat_id, name],
+            task_template_from_row,
+        );
+        match result {
+            Ok(template) => Ok(Some(template)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn list_task_templates(&self, chat_id: i64) -> Result<Vec<TaskTemplate>, MicroClawError> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT chat_id, name, prompt, schedule_type, schedule_value, updated_at
+             FROM task_templates
+             WHERE chat_id = ?1
+             ORDER BY name",
+        )?;
+        let templates = stmt
+            .query_map(params![chat_id], task_template_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(templates)
+    }
+
+    pub fn delete_task_template(&self, chat_id: i64, name: &str) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "DELETE FROM task_templates WHERE chat_id = ?1 AND name = ?2",
+            params![chat_id, name],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn insert_deferred_message(
+        &self,
+        chat_id: i64,
+        bot_username: &str,
+        content: &str,
     ) -> Result<i64, MicroClawError> {
         let conn = self.lock_conn();
         let now = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "INSERT INTO scheduled_tasks (chat_id, prompt, schedule_type, schedule_value, next_run, status, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, 'active', ?6)",
-            params![chat_id, prompt, schedule_type, schedule_value, next_run, now],
+            "INSERT INTO deferred_messages (chat_id, bot_username, content, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![chat_id, bot_username, content, now],
         )?;
         Ok(conn.last_insert_rowid())
     }
 
+    /// Every queued message, oldest first.
+    pub fn list_deferred_messages(&self) -> Result<Vec<DeferredMessage>, MicroClawError> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, bot_username, content, created_at, attempts
+             FROM deferred_messages
+             ORDER BY id",
+        )?;
+        let messages = stmt
+            .query_map([], |row| {
+                Ok(DeferredMessage {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    bot_username: row.get(2)?,
+                    content: row.get(3)?,
+                    created_at: row.get(4)?,
+                    attempts: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    pub fn delete_deferred_message(&self, id: i64) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute("DELETE FROM deferred_messages WHERE id = ?1", params![id])?;
+        Ok(rows > 0)
+    }
+
+    /// Count a failed delivery of a queued message; returns the new total.
+    pub fn record_deferred_message_failure(&self, id: i64) -> Result<i64, MicroClawError> {
+        let conn = self.lock_conn();
+        let attempts = conn.query_row(
+            "UPDATE deferred_messages SET attempts = attempts + 1 WHERE id = ?1 RETURNING attempts",
+            params![id],
+            |row| row.get(0),
+        )?;
+        Ok(attempts)
+    }
+
     pub fn get_due_tasks(&self, now: &str) -> Result<Vec<ScheduledTask>, MicroClawError> {
         let conn = self.lock_conn();
         let mut stmt = conn.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
+            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at, timezone, deliver, deliver_chat_id, template, missed_run_policy
              FROM scheduled_tasks
              WHERE status = 'active' AND next_run <= ?1",
         )?;
@@ -1311,6 +2218,11 @@ impl Database {
                     last_run: row.get(6)?,
                     status: row.get(7)?,
                     created_at: row.get(8)?,
+                    timezone: row.get(9)?,
+                    deliver: row.get(10)?,
+                    deliver_chat_id: row.get(11)?,
+                    template: row.get(12)?,
+                    missed_run_policy: row.get(13)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -1326,7 +2238,7 @@ impl Database {
         let tx = conn.unchecked_transaction()?;
 
         let mut stmt = tx.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
+            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at, timezone, deliver, deliver_chat_id, template, missed_run_policy
              FROM scheduled_tasks
              WHERE status = 'active' AND next_run <= ?1
              ORDER BY next_run ASC, id ASC
@@ -1344,6 +2256,11 @@ impl Database {
                     last_run: row.get(6)?,
                     status: row.get(7)?,
                     created_at: row.get(8)?,
+                    timezone: row.get(9)?,
+                    deliver: row.get(10)?,
+                    deliver_chat_id: row.get(11)?,
+                    template: row.get(12)?,
+                    missed_run_policy: row.get(13)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -1371,7 +2288,7 @@ impl Database {
     pub fn get_tasks_for_chat(&self, chat_id: i64) -> Result<Vec<ScheduledTask>, MicroClawError> {
         let conn = self.lock_conn();
         let mut stmt = conn.prepare(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
+            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at, timezone, deliver, deliver_chat_id, template, missed_run_policy
              FROM scheduled_tasks
              WHERE chat_id = ?1 AND status IN ('active', 'paused')
              ORDER BY id",
@@ -1388,6 +2305,11 @@ impl Database {
                     last_run: row.get(6)?,
                     status: row.get(7)?,
                     created_at: row.get(8)?,
+                    timezone: row.get(9)?,
+                    deliver: row.get(10)?,
+                    deliver_chat_id: row.get(11)?,
+                    template: row.get(12)?,
+                    missed_run_policy: row.get(13)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -1397,7 +2319,7 @@ impl Database {
     pub fn get_task_by_id(&self, task_id: i64) -> Result<Option<ScheduledTask>, MicroClawError> {
         let conn = self.lock_conn();
         let result = conn.query_row(
-            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at
+            "SELECT id, chat_id, prompt, schedule_type, schedule_value, next_run, last_run, status, created_at, timezone, deliver, deliver_chat_id, template, missed_run_policy
              FROM scheduled_tasks
              WHERE id = ?1",
             params![task_id],
@@ -1412,6 +2334,11 @@ impl Database {
                     last_run: row.get(6)?,
                     status: row.get(7)?,
                     created_at: row.get(8)?,
+                    timezone: row.get(9)?,
+                    deliver: row.get(10)?,
+                    deliver_chat_id: row.get(11)?,
+                    template: row.get(12)?,
+                    missed_run_policy: row.get(13)?,
                 })
             },
         );
@@ -1473,6 +2400,32 @@ impl Database {
         Ok(())
     }
 
+    /// When every scheduled task was paused at once, if the global pause is set.
+    pub fn scheduler_paused_at(&self) -> Result<Option<String>, MicroClawError> {
+        let conn = self.lock_conn();
+        Ok(conn
+            .query_row(
+                "SELECT value FROM db_meta WHERE key = 'scheduler_paused_at'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Set (`Some(timestamp)`) or clear (`None`) the global scheduler pause.
+    /// Returns whether the state changed; pausing twice keeps the first time.
+    pub fn set_scheduler_paused_at(&self, paused_at: Option<&str>) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = match paused_at {
+            Some(ts) => conn.execute(
+                "INSERT OR IGNORE INTO db_meta(key, value) VALUES('scheduler_paused_at', ?1)",
+                params![ts],
+            )?,
+            None => conn.execute("DELETE FROM db_meta WHERE key = 'scheduler_paused_at'", [])?,
+        };
+        Ok(rows > 0)
+    }
+
     pub fn recover_running_tasks(&self) -> Result<usize, MicroClawError> {
         let conn = self.lock_conn();
         let rows = conn.execute(
@@ -1496,11 +2449,36 @@ impl Database {
         duration_ms: i64,
         success: bool,
         result_summary: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
+        self.log_task_run_with_catch_up(
+            task_id,
+            chat_id,
+            started_at,
+            finished_at,
+            duration_ms,
+            success,
+            result_summary,
+            false,
+        )
+    }
+
+    /// `catch_up` marks a run made on startup for a missed fire time.
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_task_run_with_catch_up(
+        &self,
+        task_id: i64,
+        chat_id: i64,
+        started_at: &str,
+        finished_at: &str,
+        duration_ms: i64,
+        success: bool,
+        result_summary: Option<&str>,
+        catch_up: bool,
     ) -> Result<i64, MicroClawError> {
         let conn = self.lock_conn();
         conn.execute(
-            "INSERT INTO task_run_logs (task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO task_run_logs (task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary, catch_up)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 task_id,
                 chat_id,
@@ -1509,6 +2487,7 @@ impl Database {
                 duration_ms,
                 success as i32,
                 result_summary,
+                catch_up as i32,
             ],
         )?;
         Ok(conn.last_insert_rowid())
@@ -1521,7 +2500,7 @@ impl Database {
     ) -> Result<Vec<TaskRunLog>, MicroClawError> {
         let conn = self.lock_conn();
         let mut stmt = conn.prepare(
-            "SELECT id, task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary
+            "SELECT id, task_id, chat_id, started_at, finished_at, duration_ms, success, result_summary, catch_up
              FROM task_run_logs
              WHERE task_id = ?1
              ORDER BY id DESC
@@ -1538,6 +2517,7 @@ impl Database {
                     duration_ms: row.get(5)?,
                     success: row.get::<_, i32>(6)? != 0,
                     result_summary: row.get(7)?,
+                    catch_up: row.get::<_, i32>(8)? != 0,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -1826,6 +2806,29 @@ impl Database {
         Ok(rows > 0)
     }
 
+    /// Delete the messages stored after `message_id` in a chat, leaving the
+    /// chat history as it was when that message arrived.
+    pub fn delete_messages_after(
+        &self,
+        chat_id: i64,
+        message_id: &str,
+    ) -> Result<usize, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "DELETE FROM messages
+             WHERE chat_id = ?1
+               AND EXISTS (
+                 SELECT 1 FROM messages target
+                 WHERE target.chat_id = ?1 AND target.id = ?2
+                   AND (messages.timestamp > target.timestamp
+                        OR (messages.timestamp = target.timestamp
+                            AND messages.rowid > target.rowid))
+               )",
+            params![chat_id, message_id],
+        )?;
+        Ok(rows)
+    }
+
     /// Clear conversational context for a chat without deleting chat metadata or memories.
     /// This removes resumable session state, historical messages, and scheduled task state
     /// that can otherwise continue producing messages after a reset.
@@ -1866,6 +2869,14 @@ impl Database {
             "DELETE FROM scheduled_tasks WHERE chat_id = ?1",
             params![chat_id],
         )?;
+        affected += tx.execute(
+            "DELETE FROM task_templates WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
+        affected += tx.execute(
+            "DELETE FROM deferred_messages WHERE chat_id = ?1",
+            params![chat_id],
+        )?;
         affected += tx.execute(
             "DELETE FROM memory_reflector_state WHERE chat_id = ?1",
             params![chat_id],
@@ -2173,6 +3184,101 @@ impl Database {
         Ok(rows)
     }
 
+    /// Status of the newest audit row of `kind` for `actor`.
+    pub fn latest_audit_status(
+        &self,
+        kind: &str,
+        actor: &str,
+    ) -> Result<Option<String>, MicroClawError> {
+        let conn = self.lock_conn();
+        let status = conn
+            .query_row(
+                "SELECT status FROM audit_logs
+                 WHERE kind = ?1 AND actor = ?2
+                 ORDER BY id DESC
+                 LIMIT 1",
+                params![kind, actor],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(status)
+    }
+
+    // --- Tool audit log ---
+
+    /// Append a tool execution record; `id` and `created_at` of `record` are ignored.
+    pub fn log_tool_audit(&self, record: &ToolAuditRecord) -> Result<i64, MicroClawError> {
+        let conn = self.lock_conn();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO tool_audit_logs(
+                created_at, channel, chat_id, tool_name, input, risk, approval, status,
+                error_type, duration_ms
+             ) VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                now,
+                record.channel,
+                record.chat_id,
+                record.tool_name,
+                record.input,
+                record.risk,
+                record.approval,
+                record.status,
+                record.error_type,
+                record.duration_ms,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Newest-first tool audit records matching `filter`.
+    pub fn list_tool_audit_logs(
+        &self,
+        filter: &ToolAuditFilter,
+    ) -> Result<Vec<ToolAuditRecord>, MicroClawError> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, created_at, channel, chat_id, tool_name, input, risk, approval, status,
+                    error_type, duration_ms
+             FROM tool_audit_logs
+             WHERE (?1 IS NULL OR channel = ?1)
+               AND (?2 IS NULL OR chat_id = ?2)
+               AND (?3 IS NULL OR tool_name = ?3)
+               AND (?4 IS NULL OR status = ?4)
+               AND (?5 IS NULL OR created_at >= ?5)
+             ORDER BY id DESC
+             LIMIT ?6",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    filter.channel,
+                    filter.chat_id,
+                    filter.tool_name,
+                    filter.status,
+                    filter.since,
+                    filter.limit as i64,
+                ],
+                |row| {
+                    Ok(ToolAuditRecord {
+                        id: row.get(0)?,
+                        created_at: row.get(1)?,
+                        channel: row.get(2)?,
+                        chat_id: row.get(3)?,
+                        tool_name: row.get(4)?,
+                        input: row.get(5)?,
+                        risk: row.get(6)?,
+                        approval: row.get(7)?,
+                        status: row.get(8)?,
+                        error_type: row.get(9)?,
+                        duration_ms: row.get(10)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
     // --- Metrics history ---
 
     pub fn upsert_metrics_history(
@@ -2582,6 +3688,18 @@ impl Database {
         confidence: f64,
     ) -> Result<i64, MicroClawError> {
         let conn = self.lock_conn();
+        Self::insert_memory_row(&conn, chat_id, content, category, source, confidence, None)
+    }
+
+    fn insert_memory_row(
+        conn: &Connection,
+        chat_id: Option<i64>,
+        content: &str,
+        category: &str,
+        source: &str,
+        confidence: f64,
+        sender_id: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
         let now = chrono::Utc::now().to_rfc3339();
         let (chat_channel, external_chat_id) = if let Some(cid) = chat_id {
             conn.query_row(
@@ -2603,8 +3721,8 @@ impl Database {
             "INSERT INTO memories (
                 chat_id, content, category, created_at, updated_at, embedding_model,
                 confidence, source, last_seen_at, is_archived, archived_at,
-                chat_channel, external_chat_id
-            ) VALUES (?1, ?2, ?3, ?4, ?4, NULL, ?5, ?6, ?4, 0, NULL, ?7, ?8)",
+                chat_channel, external_chat_id, sender_id
+            ) VALUES (?1, ?2, ?3, ?4, ?4, NULL, ?5, ?6, ?4, 0, NULL, ?7, ?8, ?9)",
             params![
                 chat_id,
                 content,
@@ -2613,7 +3731,8 @@ impl Database {
                 confidence.clamp(0.0, 1.0),
                 source,
                 chat_channel,
-                external_chat_id
+                external_chat_id,
+                sender_id
             ],
         )?;
         Ok(conn.last_insert_rowid())
@@ -2627,7 +3746,8 @@ impl Database {
         let conn = self.lock_conn();
         let mut stmt = conn.prepare(
             "SELECT id, chat_id, content, category, created_at, updated_at, embedding_model,
-                    confidence, source, last_seen_at, is_archived, archived_at
+                    confidence, source, last_seen_at, is_archived, archived_at, sender_id,
+                    pinned
              FROM memories
              WHERE (chat_id = ?1 OR chat_id IS NULL)
                AND is_archived = 0
@@ -2650,6 +3770,8 @@ impl Database {
                     last_seen_at: row.get(9)?,
                     is_archived: row.get::<_, i64>(10)? != 0,
                     archived_at: row.get(11)?,
+                    sender_id: row.get(12)?,
+                    pinned: row.get::<_, i64>(13)? != 0,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -2663,7 +3785,8 @@ impl Database {
         let conn = self.lock_conn();
         let mut stmt = conn.prepare(
             "SELECT id, chat_id, content, category, created_at, updated_at, embedding_model,
-                    confidence, source, last_seen_at, is_archived, archived_at
+                    confidence, source, last_seen_at, is_archived, archived_at, sender_id,
+                    pinned
              FROM memories
              WHERE (chat_id = ?1 OR (?1 IS NULL AND chat_id IS NULL))",
         )?;
@@ -2682,6 +3805,8 @@ impl Database {
                     last_seen_at: row.get(9)?,
                     is_archived: row.get::<_, i64>(10)? != 0,
                     archived_at: row.get(11)?,
+                    sender_id: row.get(12)?,
+                    pinned: row.get::<_, i64>(13)? != 0,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -2721,7 +3846,8 @@ impl Database {
         let pattern = format!("%{}%", query.to_lowercase());
         let mut sql = String::from(
             "SELECT id, chat_id, content, category, created_at, updated_at, embedding_model,
-                    confidence, source, last_seen_at, is_archived, archived_at
+                    confidence, source, last_seen_at, is_archived, archived_at, sender_id,
+                    pinned
              FROM memories
              WHERE (chat_id = ?1 OR chat_id IS NULL)
                AND LOWER(content) LIKE ?2",
@@ -2749,6 +3875,8 @@ impl Database {
                     last_seen_at: row.get(9)?,
                     is_archived: row.get::<_, i64>(10)? != 0,
                     archived_at: row.get(11)?,
+                    sender_id: row.get(12)?,
+                    pinned: row.get::<_, i64>(13)? != 0,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -2796,6 +3924,17 @@ impl Database {
         source: &str,
     ) -> Result<bool, MicroClawError> {
         let conn = self.lock_conn();
+        Self::update_memory_row(&conn, id, content, category, confidence, source)
+    }
+
+    fn update_memory_row(
+        conn: &Connection,
+        id: i64,
+        content: &str,
+        category: &str,
+        confidence: f64,
+        source: &str,
+    ) -> Result<bool, MicroClawError> {
         let now = chrono::Utc::now().to_rfc3339();
         let rows = conn.execute(
             "UPDATE memories
@@ -2842,7 +3981,7 @@ impl Database {
         let conn = self.lock_conn();
         let mut query = String::from(
             "SELECT id, chat_id, content, category, created_at, updated_at, embedding_model
-             , confidence, source, last_seen_at, is_archived, archived_at
+             , confidence, source, last_seen_at, is_archived, archived_at, sender_id, pinned
              FROM memories
              WHERE embedding_model IS NULL
                AND is_archived = 0",
@@ -2868,6 +4007,8 @@ impl Database {
                 last_seen_at: row.get(9)?,
                 is_archived: row.get::<_, i64>(10)? != 0,
                 archived_at: row.get(11)?,
+                sender_id: row.get(12)?,
+                pinned: row.get::<_, i64>(13)? != 0,
             })
         };
 
@@ -2879,6 +4020,19 @@ impl Database {
         rows.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
+    /// Dimension the vector index was last built with by `prepare_vector_index`.
+    pub fn vector_index_dimension(&self) -> Result<Option<usize>, MicroClawError> {
+        let conn = self.lock_conn();
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM db_meta WHERE key = 'embedding_dim'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.and_then(|v| v.parse().ok()))
+    }
+
     #[cfg(feature = "sqlite-vec")]
     pub fn prepare_vector_index(&self, dimension: usize) -> Result<(), MicroClawError> {
         let conn = self.lock_conn();
@@ -2974,7 +4128,8 @@ impl Database {
         let conn = self.lock_conn();
         let result = conn.query_row(
             "SELECT id, chat_id, content, category, created_at, updated_at, embedding_model,
-                    confidence, source, last_seen_at, is_archived, archived_at
+                    confidence, source, last_seen_at, is_archived, archived_at, sender_id,
+                    pinned
              FROM memories WHERE id = ?1",
             params![id],
             |row| {
@@ -2991,6 +4146,8 @@ impl Database {
                     last_seen_at: row.get(9)?,
                     is_archived: row.get::<_, i64>(10)? != 0,
                     archived_at: row.get(11)?,
+                    sender_id: row.get(12)?,
+                    pinned: row.get::<_, i64>(13)? != 0,
                 })
             },
         );
@@ -3007,6 +4164,14 @@ impl Database {
         confidence_floor: Option<f64>,
     ) -> Result<bool, MicroClawError> {
         let conn = self.lock_conn();
+        Self::touch_memory_row(&conn, id, confidence_floor)
+    }
+
+    fn touch_memory_row(
+        conn: &Connection,
+        id: i64,
+        confidence_floor: Option<f64>,
+    ) -> Result<bool, MicroClawError> {
         let now = chrono::Utc::now().to_rfc3339();
         let rows = if let Some(floor) = confidence_floor {
             conn.execute(
@@ -3037,6 +4202,65 @@ impl Database {
         Ok(rows > 0)
     }
 
+    pub fn set_memory_sender_id(
+        &self,
+        id: i64,
+        sender_id: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "UPDATE memories SET sender_id = ?1 WHERE id = ?2",
+            params![sender_id, id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn set_memory_pinned(&self, id: i64, pinned: bool) -> Result<bool, MicroClawError> {
+        let conn = self.lock_conn();
+        let rows = conn.execute(
+            "UPDATE memories SET pinned = ?1 WHERE id = ?2",
+            params![pinned as i64, id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Active pinned memories visible in `chat_id` (its own and global ones),
+    /// oldest first so their order in context stays stable.
+    pub fn get_pinned_memories(&self, chat_id: i64) -> Result<Vec<Memory>, MicroClawError> {
+        let conn = self.lock_conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, chat_id, content, category, created_at, updated_at, embedding_model,
+                    confidence, source, last_seen_at, is_archived, archived_at, sender_id,
+                    pinned
+             FROM memories
+             WHERE (chat_id = ?1 OR chat_id IS NULL)
+               AND is_archived = 0
+               AND pinned = 1
+             ORDER BY id ASC",
+        )?;
+        let memories = stmt
+            .query_map(params![chat_id], |row| {
+                Ok(Memory {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    content: row.get(2)?,
+                    category: row.get(3)?,
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                    embedding_model: row.get(6)?,
+                    confidence: row.get(7)?,
+                    source: row.get(8)?,
+                    last_seen_at: row.get(9)?,
+                    is_archived: row.get::<_, i64>(10)? != 0,
+                    archived_at: row.get(11)?,
+                    sender_id: row.get(12)?,
+                    pinned: row.get::<_, i64>(13)? != 0,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(memories)
+    }
+
     pub fn archive_stale_memories(&self, stale_days: i64) -> Result<usize, MicroClawError> {
         let conn = self.lock_conn();
         let cutoff = (chrono::Utc::now() - chrono::Duration::days(stale_days.max(1))).to_rfc3339();
@@ -3045,6 +4269,7 @@ impl Database {
             "UPDATE memories
              SET is_archived = 1, archived_at = ?1, updated_at = ?1
              WHERE is_archived = 0
+               AND pinned = 0
                AND confidence < 0.35
                AND COALESCE(last_seen_at, updated_at, created_at) < ?2",
             params![now, cutoff],
@@ -3063,22 +4288,56 @@ impl Database {
     ) -> Result<i64, MicroClawError> {
         let conn = self.lock_conn();
         let tx = conn.unchecked_transaction()?;
-        let (chat_id, chat_channel, external_chat_id): (
+        let to_memory_id = Self::supersede_memory_row(
+            &tx,
+            from_memory_id,
+            new_content,
+            category,
+            source,
+            confidence,
+            reason,
+        )?;
+        tx.commit()?;
+        Ok(to_memory_id)
+    }
+
+    fn supersede_memory_row(
+        conn: &Connection,
+        from_memory_id: i64,
+        new_content: &str,
+        category: &str,
+        source: &str,
+        confidence: f64,
+        reason: Option<&str>,
+    ) -> Result<i64, MicroClawError> {
+        let (chat_id, chat_channel, external_chat_id, sender_id, pinned): (
             Option<i64>,
             Option<String>,
             Option<String>,
-        ) = tx.query_row(
-            "SELECT chat_id, chat_channel, external_chat_id FROM memories WHERE id = ?1",
+            Option<String>,
+            i64,
+        ) = conn.query_row(
+            "SELECT chat_id, chat_channel, external_chat_id, sender_id, pinned
+             FROM memories WHERE id = ?1",
             params![from_memory_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            },
         )?;
 
         let now = chrono::Utc::now().to_rfc3339();
-        tx.execute(
+        conn.execute(
             "INSERT INTO memories (
                 chat_id, content, category, created_at, updated_at, embedding_model,
-                confidence, source, last_seen_at, is_archived, archived_at, chat_channel, external_chat_id
-            ) VALUES (?1, ?2, ?3, ?4, ?4, NULL, ?5, ?6, ?4, 0, NULL, ?7, ?8)",
+                confidence, source, last_seen_at, is_archived, archived_at, chat_channel, external_chat_id,
+                sender_id, pinned
+            ) VALUES (?1, ?2, ?3, ?4, ?4, NULL, ?5, ?6, ?4, 0, NULL, ?7, ?8, ?9, ?10)",
             params![
                 chat_id,
                 new_content,
@@ -3087,26 +4346,154 @@ impl Database {
                 confidence.clamp(0.0, 1.0),
                 source,
                 chat_channel,
-                external_chat_id
+                external_chat_id,
+                sender_id,
+                pinned
             ],
         )?;
-        let to_memory_id = tx.last_insert_rowid();
+        let to_memory_id = conn.last_insert_rowid();
 
-        tx.execute(
+        conn.execute(
             "UPDATE memories
              SET is_archived = 1, archived_at = ?1, updated_at = ?1
              WHERE id = ?2",
             params![now, from_memory_id],
         )?;
-        tx.execute(
+        conn.execute(
             "INSERT INTO memory_supersede_edges(from_memory_id, to_memory_id, reason, created_at)
              VALUES(?1, ?2, ?3, ?4)",
             params![from_memory_id, to_memory_id, reason, now],
         )?;
-        tx.commit()?;
         Ok(to_memory_id)
     }
 
+    /// Apply `ops` in one transaction: either every write lands or none does.
+    /// Returns the affected memory id per op (the new id for inserts and
+    /// supersedes). An op targeting a missing memory fails the whole batch.
+    pub fn write_memory_batch(&self, ops: &[MemoryBatchOp]) -> Result<Vec<i64>, MicroClawError> {
+        let conn = self.lock_conn();
+        let tx = conn.unchecked_transaction()?;
+        let mut ids = Vec::with_capacity(ops.len());
+        for op in ops {
+            let (id, embedding) = match op {
+                MemoryBatchOp::Insert {
+                    chat_id,
+                    content,
+                    category,
+                    source,
+                    confidence,
+                    sender_id,
+                    embedding,
+                } => {
+                    let id = Self::insert_memory_row(
+                        &tx,
+                        *chat_id,
+                        content,
+                        category,
+                        source,
+                        *confidence,
+                        sender_id.as_deref(),
+                    )?;
+                    (id, embedding.as_ref())
+                }
+                MemoryBatchOp::Update {
+                    id,
+                    content,
+                    category,
+                    confidence,
+                    source,
+                    embedding,
+                } => {
+                    if !Self::update_memory_row(&tx, *id, content, category, *confidence, source)? {
+                        return Err(missing_batch_memory(*id));
+                    }
+                    (*id, embedding.as_ref())
+                }
+                MemoryBatchOp::Supersede {
+                    from_memory_id,
+                    content,
+                    category,
+                    source,
+                    confidence,
+                    reason,
+                    embedding,
+                } => {
+                    let id = Self::supersede_memory_row(
+                        &tx,
+                        *from_memory_id,
+                        content,
+                        category,
+                        source,
+                        *confidence,
+                        reason.as_deref(),
+                    )
+                    .map_err(|e| match e {
+                        MicroClawError::Database(rusqlite::Error::QueryReturnedNoRows) => {
+                            missing_batch_memory(*from_memory_id)
+                        }
+                        other => other,
+                    })?;
+                    (id, embedding.as_ref())
+                }
+                MemoryBatchOp::Touch {
+                    id,
+                    confidence_floor,
+                } => {
+                    if !Self::touch_memory_row(&tx, *id, *confidence_floor)? {
+                        return Err(missing_batch_memory(*id));
+                    }
+                    (*id, None)
+                }
+                MemoryBatchOp::Embed { id, embedding } => {
+                    let exists = tx
+                        .query_row("SELECT 1 FROM memories WHERE id = ?1", params![id], |_| {
+                            Ok(())
+                        })
+                        .optional()?
+                        .is_some();
+                    if !exists {
+                        return Err(missing_batch_memory(*id));
+                    }
+                    (*id, Some(embedding))
+                }
+            };
+            if let Some(embedding) = embedding {
+                Self::store_memory_embedding(&tx, id, embedding)?;
+            }
+            ids.push(id);
+        }
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    #[cfg(feature = "sqlite-vec")]
+    fn store_memory_embedding(
+        conn: &Connection,
+        id: i64,
+        embedding: &MemoryEmbedding,
+    ) -> Result<(), MicroClawError> {
+        let vector_json = serde_json::to_string(&embedding.vector)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO memories_vec(rowid, embedding) VALUES(?1, vec_f32(?2))",
+            params![id, vector_json],
+        )?;
+        conn.execute(
+            "UPDATE memories SET embedding_model = ?1 WHERE id = ?2",
+            params![embedding.model, id],
+        )?;
+        Ok(())
+    }
+
+    /// Without sqlite-vec there is no vector table; embeddings are dropped.
+    #[cfg(not(feature = "sqlite-vec"))]
+    fn store_memory_embedding(
+        _conn: &Connection,
+        _id: i64,
+        _embedding: &MemoryEmbedding,
+    ) -> Result<(), MicroClawError> {
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn log_reflector_run(
         &self,
@@ -3444,6 +4831,55 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[cfg(not(feature = "sqlcipher"))]
+    #[test]
+    fn test_open_with_key_requires_sqlcipher_build() {
+        let dir = std::env::temp_dir().join(format!("microclaw_test_{}", uuid::Uuid::new_v4()));
+        let err = Database::open(dir.to_str().unwrap(), Some("k"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("--features sqlcipher"), "{err}");
+        let err = encrypt_database(dir.to_str().unwrap(), "k")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--features sqlcipher"), "{err}");
+        cleanup(&dir);
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn test_encrypt_database_migrates_plaintext_and_rejects_wrong_key() {
+        let (db, dir) = test_db();
+        let data_dir = dir.to_str().unwrap();
+        let chat_id = db
+            .resolve_or_create_chat_id("web", "c1", Some("t"), "web")
+            .unwrap();
+        drop(db);
+
+        encrypt_database(data_dir, "correct horse").unwrap();
+        let err = Database::new(data_dir).err().unwrap().to_string();
+        assert!(err.contains("file is encrypted"), "{err}");
+        let err = Database::open(data_dir, Some("wrong"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("wrong database key"), "{err}");
+
+        let db = Database::open(data_dir, Some("correct horse")).unwrap();
+        assert_eq!(
+            db.resolve_or_create_chat_id("web", "c1", Some("t"), "web")
+                .unwrap(),
+            chat_id
+        );
+        let backup = dir.join("backup.db");
+        db.backup_to(&backup).unwrap();
+        let conn = Connection::open(&backup).unwrap();
+        assert!(unlock_connection(&conn, None, &backup).is_err());
+        assert!(encrypt_database(data_dir, "again").is_err());
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_new_database_creates_tables() {
         let (db, dir) = test_db();
@@ -3640,6 +5076,13 @@ mod tests {
                 )
                 .unwrap();
             }
+            if version >= 8 {
+                conn.execute_batch(
+                    "ALTER TABLE api_keys ADD COLUMN expires_at TEXT;
+                     ALTER TABLE api_keys ADD COLUMN rotated_from_key_id INTEGER;",
+                )
+                .unwrap();
+            }
             if version >= 7 {
                 conn.execute_batch(
                     "CREATE TABLE IF NOT EXISTS metrics_history (
@@ -3684,6 +5127,12 @@ mod tests {
             );
             assert!(table_has_column(&conn, "sessions", "parent_session_key").unwrap());
             assert!(table_has_column(&conn, "sessions", "fork_point").unwrap());
+            assert!(table_has_column(&conn, "scheduled_tasks", "timezone").unwrap());
+            assert!(table_has_column(&conn, "scheduled_tasks", "deliver").unwrap());
+            assert!(table_has_column(&conn, "scheduled_tasks", "deliver_chat_id").unwrap());
+            assert!(table_has_column(&conn, "scheduled_tasks", "missed_run_policy").unwrap());
+            assert!(table_has_column(&conn, "task_run_logs", "catch_up").unwrap());
+            assert!(table_has_column(&conn, "memories", "sender_id").unwrap());
             assert!(table_has_column(&conn, "api_keys", "expires_at").unwrap());
             assert!(table_has_column(&conn, "api_keys", "rotated_from_key_id").unwrap());
             assert!(
@@ -3955,6 +5404,121 @@ mod tests {
         assert_eq!(tasks[0].prompt, "say hello");
         assert_eq!(tasks[0].schedule_type, "cron");
         assert_eq!(tasks[0].status, "active");
+        assert_eq!(tasks[0].deliver, "channel");
+        assert_eq!(tasks[0].deliver_chat_id, None);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_create_scheduled_task_with_delivery() {
+        let (db, dir) = test_db();
+        let id = db
+            .create_scheduled_task_with_delivery(
+                100,
+                "daily digest",
+                "cron",
+                "0 0 9 * * *",
+                "2024-01-01T09:00:00Z",
+                Some("UTC"),
+                "dm",
+                Some(200),
+            )
+            .unwrap();
+        let task = db.get_task_by_id(id).unwrap().unwrap();
+        assert_eq!(task.deliver, "dm");
+        assert_eq!(task.deliver_chat_id, Some(200));
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_task_templates_round_trip() {
+        let (db, dir) = test_db();
+        db.save_task_template(100, "standup", "Remind {{team}}", "cron", "0 0 9 * * 1-5")
+            .unwrap();
+        db.save_task_template(100, "standup", "Ping {{team}}", "cron", "0 30 9 * * 1-5")
+            .unwrap();
+        db.save_task_template(200, "other", "x", "once", "2099-01-01T00:00:00Z")
+            .unwrap();
+
+        let templates = db.list_task_templates(100).unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].prompt, "Ping {{team}}");
+        assert_eq!(templates[0].schedule_value, "0 30 9 * * 1-5");
+        assert!(db.get_task_template(200, "standup").unwrap().is_none());
+
+        let id = db
+            .create_scheduled_task(
+                100,
+                "Ping infra",
+                "cron",
+                "0 30 9 * * 1-5",
+                "2099-01-01T09:30:00Z",
+            )
+            .unwrap();
+        assert!(db.set_task_template(id, "standup").unwrap());
+        assert_eq!(
+            db.get_task_by_id(id).unwrap().unwrap().template.as_deref(),
+            Some("standup")
+        );
+
+        assert!(db.delete_task_template(100, "standup").unwrap());
+        assert!(!db.delete_task_template(100, "standup").unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_chat_max_response_tokens_override() {
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("telegram", "ext-1", Some("team"), "group")
+            .unwrap();
+        assert_eq!(db.get_chat_max_response_tokens(chat_id).unwrap(), None);
+        assert!(db.set_chat_max_response_tokens(chat_id, Some(200)).unwrap());
+        assert_eq!(db.get_chat_max_response_tokens(chat_id).unwrap(), Some(200));
+        assert!(db.set_chat_max_response_tokens(chat_id, None).unwrap());
+        assert_eq!(db.get_chat_max_response_tokens(chat_id).unwrap(), None);
+        assert!(!db.set_chat_max_response_tokens(999_999, Some(50)).unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_chat_debug_footer_flag() {
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("telegram", "ext-1", Some("ops"), "private")
+            .unwrap();
+        assert!(!db.get_chat_debug_footer(chat_id).unwrap());
+        assert!(db.set_chat_debug_footer(chat_id, true).unwrap());
+        assert!(db.get_chat_debug_footer(chat_id).unwrap());
+        assert!(db.set_chat_debug_footer(chat_id, false).unwrap());
+        assert!(!db.get_chat_debug_footer(chat_id).unwrap());
+        assert!(!db.set_chat_debug_footer(999_999, true).unwrap());
+        assert!(!db.get_chat_debug_footer(999_999).unwrap());
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_deferred_messages_queue() {
+        let (db, dir) = test_db();
+        let first = db
+            .insert_deferred_message(100, "bot", "morning digest")
+            .unwrap();
+        db.insert_deferred_message(200, "bot", "other chat")
+            .unwrap();
+
+        let queued = db.list_deferred_messages().unwrap();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].id, first);
+        assert_eq!(queued[0].content, "morning digest");
+        assert_eq!(queued[0].attempts, 0);
+        assert_eq!(db.record_deferred_message_failure(first).unwrap(), 1);
+        assert_eq!(db.record_deferred_message_failure(first).unwrap(), 2);
+        assert_eq!(db.list_deferred_messages().unwrap()[0].attempts, 2);
+
+        assert!(db.delete_deferred_message(first).unwrap());
+        assert!(!db.delete_deferred_message(first).unwrap());
+        db.delete_chat_data(200).unwrap();
+        assert!(db.list_deferred_messages().unwrap().is_empty());
         cleanup(&dir);
     }
 
@@ -4109,6 +5673,46 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_scheduler_pause_flag_persists() {
+        let (db, dir) = test_db();
+        assert_eq!(db.scheduler_paused_at().unwrap(), None);
+        assert!(db
+            .set_scheduler_paused_at(Some("2024-01-01T00:00:00Z"))
+            .unwrap());
+        assert!(!db
+            .set_scheduler_paused_at(Some("2024-02-01T00:00:00Z"))
+            .unwrap());
+        drop(db);
+
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+        assert_eq!(
+            db.scheduler_paused_at().unwrap().as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert!(db.set_scheduler_paused_at(None).unwrap());
+        assert!(!db.set_scheduler_paused_at(None).unwrap());
+        assert_eq!(db.scheduler_paused_at().unwrap(), None);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_claim_inbound_message_within_window() {
+        let (db, dir) = test_db();
+        assert!(db
+            .claim_inbound_message("feishu", "m1", 1_000, 500)
+            .unwrap());
+        assert!(!db
+            .claim_inbound_message("feishu", "m1", 1_400, 500)
+            .unwrap());
+        assert!(db.claim_inbound_message("slack", "m1", 1_400, 500).unwrap());
+        // Outside the window the old entry is pruned and the key is new again.
+        assert!(db
+            .claim_inbound_message("feishu", "m1", 1_600, 500)
+            .unwrap());
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_update_task_after_run_one_shot() {
         let (db, dir) = test_db();
@@ -4172,6 +5776,143 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_copy_to_dir_and_delete_messages_after() {
+        let (db, dir) = test_db();
+        for i in 0..5 {
+            db.store_message(&StoredMessage {
+                id: format!("msg{i}"),
+                chat_id: 100,
+                sender_name: "alice".into(),
+                content: format!("message {i}"),
+                is_from_bot: false,
+                timestamp: format!("2024-01-01T00:00:0{i}Z"),
+            })
+            .unwrap();
+        }
+
+        let scratch = dir.join("scratch");
+        let copy = db.copy_to_dir(scratch.to_str().unwrap()).unwrap();
+        assert_eq!(copy.delete_messages_after(100, "msg2").unwrap(), 2);
+        let remaining = copy.get_all_messages(100).unwrap();
+        assert_eq!(remaining.last().unwrap().id, "msg2");
+        assert_eq!(copy.delete_messages_after(100, "missing").unwrap(), 0);
+
+        // The original database is untouched.
+        assert_eq!(db.get_all_messages(100).unwrap().len(), 5);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_search_messages_full_text() {
+        let (db, dir) = test_db();
+        let store = |id: &str, chat_id: i64, content: &str, ts: &str| {
+            db.store_message(&StoredMessage {
+                id: id.into(),
+                chat_id,
+                sender_name: "alice".into(),
+                content: content.into(),
+                is_from_bot: false,
+                timestamp: ts.into(),
+            })
+            .unwrap();
+        };
+        store(
+            "m1",
+            100,
+            "We agreed to move the Postgres migration to Friday",
+            "2024-01-01T00:00:01Z",
+        );
+        store(
+            "m2",
+            100,
+            "postgres backups run nightly at 2am",
+            "2024-01-01T00:00:02Z",
+        );
+        store(
+            "m3",
+            200,
+            "Postgres upgrade for the other team",
+            "2024-01-01T00:00:03Z",
+        );
+        store("m4", 100, "部署计划改到下周", "2024-01-01T00:00:04Z");
+        store("m5", 100, "100% done", "2024-01-01T00:00:05Z");
+
+        let ids = |hits: Vec<StoredMessage>| hits.into_iter().map(|m| m.id).collect::<Vec<_>>();
+        let mut all = ids(db.search_messages("POSTGRES", None, 10).unwrap());
+        all.sort();
+        assert_eq!(all, ["m1", "m2", "m3"]);
+        assert_eq!(
+            ids(db
+                .search_messages("postgres friday", Some(100), 10)
+                .unwrap()),
+            ["m1"]
+        );
+        // Short terms (including two-character CJK words) fall back to LIKE.
+        assert_eq!(
+            ids(db.search_messages("部署", Some(100), 10).unwrap()),
+            ["m4"]
+        );
+        assert_eq!(
+            ids(db.search_messages("0%", Some(100), 10).unwrap()),
+            ["m5"]
+        );
+        assert!(db.search_messages("   ", None, 10).unwrap().is_empty());
+
+        // Upserts and deletions keep the index in sync.
+        store("m2", 100, "backups moved to 3am", "2024-01-01T00:00:02Z");
+        let indexed: i64 = db
+            .lock_conn()
+            .query_row("SELECT COUNT(*) FROM messages_fts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(indexed, 5);
+        assert_eq!(
+            ids(db.search_messages("postgres", Some(100), 10).unwrap()),
+            ["m1"]
+        );
+        assert_eq!(
+            ids(db.search_messages("3am", Some(100), 10).unwrap()),
+            ["m2"]
+        );
+        db.delete_chat_data(100).unwrap();
+        assert!(db.search_messages("backups", None, 10).unwrap().is_empty());
+        assert_eq!(
+            ids(db.search_messages("postgres", None, 10).unwrap()),
+            ["m3"]
+        );
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_message_fts_migration_backfills_existing_messages() {
+        let (db, dir) = test_db();
+        db.store_message(&StoredMessage {
+            id: "old".into(),
+            chat_id: 100,
+            sender_name: "alice".into(),
+            content: "message from before the index existed".into(),
+            is_from_bot: false,
+            timestamp: "2024-01-01T00:00:00Z".into(),
+        })
+        .unwrap();
+        db.lock_conn()
+            .execute_batch(
+                "DROP TRIGGER messages_fts_insert;
+                 DROP TRIGGER messages_fts_update;
+                 DROP TRIGGER messages_fts_delete;
+                 DROP TABLE messages_fts;
+                 UPDATE db_meta SET value = '16' WHERE key = 'schema_version';",
+            )
+            .unwrap();
+        drop(db);
+
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+        let hits = db.search_messages("before the index", None, 5).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "old");
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_log_task_run() {
         let (db, dir) = test_db();
@@ -4198,6 +5939,25 @@ mod tests {
         assert_eq!(logs[0].duration_ms, 5000);
         assert!(logs[0].success);
         assert_eq!(logs[0].result_summary.as_deref(), Some("Success"));
+        assert!(!logs[0].catch_up);
+
+        db.log_task_run_with_catch_up(
+            task_id,
+            100,
+            "2024-01-02T00:00:00Z",
+            "2024-01-02T00:00:05Z",
+            5000,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+        assert!(db.get_task_run_logs(task_id, 1).unwrap()[0].catch_up);
+        let task = db.get_task_by_id(task_id).unwrap().unwrap();
+        assert_eq!(task.missed_run_policy, "run_once_on_startup");
+        assert!(db.set_task_missed_run_policy(task_id, "skip").unwrap());
+        let task = db.get_task_by_id(task_id).unwrap().unwrap();
+        assert_eq!(task.missed_run_policy, "skip");
         cleanup(&dir);
     }
 
@@ -5014,6 +6774,46 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_pinned_memories_skip_stale_archive_and_survive_supersede() {
+        let (db, dir) = test_db();
+        let pinned = db
+            .insert_memory_with_metadata(Some(100), "user is Dana", "PROFILE", "test", 0.2)
+            .unwrap();
+        let stale = db
+            .insert_memory_with_metadata(Some(100), "likes tea", "PROFILE", "test", 0.2)
+            .unwrap();
+        db.insert_memory_with_metadata(Some(200), "other chat", "PROFILE", "test", 0.9)
+            .unwrap();
+        assert!(db.set_memory_pinned(pinned, true).unwrap());
+        {
+            let conn = db.lock_conn();
+            conn.execute(
+                "UPDATE memories SET last_seen_at = '2020-01-01T00:00:00Z'",
+                [],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(db.archive_stale_memories(30).unwrap(), 1);
+        assert!(db.get_memory_by_id(stale).unwrap().unwrap().is_archived);
+        let kept: Vec<i64> = db
+            .get_pinned_memories(100)
+            .unwrap()
+            .iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(kept, [pinned]);
+        assert!(db.get_pinned_memories(200).unwrap().is_empty());
+
+        let corrected = db
+            .supersede_memory(pinned, "user is Dana Li", "PROFILE", "test", 0.9, None)
+            .unwrap();
+        assert!(db.get_memory_by_id(corrected).unwrap().unwrap().pinned);
+        assert_eq!(db.get_pinned_memories(100).unwrap()[0].id, corrected);
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_supersede_memory_creates_edge_and_archives_old() {
         let (db, dir) = test_db();
@@ -5055,6 +6855,177 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_memory_sender_id_set_and_carried_by_supersede() {
+        let (db, dir) = test_db();
+        let old_id = db
+            .insert_memory(Some(100), "alice prefers tea", "PROFILE")
+            .unwrap();
+        assert!(db
+            .get_memory_by_id(old_id)
+            .unwrap()
+            .unwrap()
+            .sender_id
+            .is_none());
+        assert!(db.set_memory_sender_id(old_id, Some("ou_alice")).unwrap());
+
+        let new_id = db
+            .supersede_memory(
+                old_id,
+                "alice prefers green tea",
+                "PROFILE",
+                "reflector_conflict",
+                0.74,
+                None,
+            )
+            .unwrap();
+        let newm = db.get_memory_by_id(new_id).unwrap().unwrap();
+        assert_eq!(newm.sender_id.as_deref(), Some("ou_alice"));
+        assert!(!db.set_memory_sender_id(9999, Some("ou_alice")).unwrap());
+        cleanup(&dir);
+    }
+
+    fn batch_insert(content: &str, sender_id: Option<&str>) -> MemoryBatchOp {
+        MemoryBatchOp::Insert {
+            chat_id: Some(100),
+            content: content.into(),
+            category: "KNOWLEDGE".into(),
+            source: "reflector".into(),
+            confidence: 0.68,
+            sender_id: sender_id.map(str::to_string),
+            embedding: None,
+        }
+    }
+
+    #[test]
+    fn test_write_memory_batch_applies_all_ops() {
+        let (db, dir) = test_db();
+        let old_id = db
+            .insert_memory(Some(100), "deploys happen on monday", "KNOWLEDGE")
+            .unwrap();
+        let ids = db
+            .write_memory_batch(&[
+                batch_insert("staging lives in eu-west", Some("ou_alice")),
+                MemoryBatchOp::Supersede {
+                    from_memory_id: old_id,
+                    content: "deploys happen on friday".into(),
+                    category: "KNOWLEDGE".into(),
+                    source: "reflector_conflict".into(),
+                    confidence: 0.74,
+                    reason: Some("topic_conflict".into()),
+                    embedding: None,
+                },
+                MemoryBatchOp::Touch {
+                    id: old_id,
+                    confidence_floor: Some(0.9),
+                },
+            ])
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[2], old_id);
+
+        let inserted = db.get_memory_by_id(ids[0]).unwrap().unwrap();
+        assert_eq!(inserted.sender_id.as_deref(), Some("ou_alice"));
+        assert_eq!(inserted.source, "reflector");
+        let replacement = db.get_memory_by_id(ids[1]).unwrap().unwrap();
+        assert_eq!(replacement.content, "deploys happen on friday");
+        let old = db.get_memory_by_id(old_id).unwrap().unwrap();
+        assert!(old.is_archived);
+        assert!((old.confidence - 0.9).abs() < 1e-9);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_write_memory_batch_rolls_back_on_mid_batch_failure() {
+        let (db, dir) = test_db();
+        let kept = db
+            .insert_memory(Some(100), "the office is in Lisbon", "KNOWLEDGE")
+            .unwrap();
+        let err = db
+            .write_memory_batch(&[
+                batch_insert("first new fact", None),
+                MemoryBatchOp::Update {
+                    id: kept,
+                    content: "the office moved to Porto".into(),
+                    category: "KNOWLEDGE".into(),
+                    confidence: 0.7,
+                    source: "reflector".into(),
+                    embedding: None,
+                },
+                MemoryBatchOp::Update {
+                    id: 9999,
+                    content: "no such memory".into(),
+                    category: "KNOWLEDGE".into(),
+                    confidence: 0.7,
+                    source: "reflector".into(),
+                    embedding: None,
+                },
+                batch_insert("never written", None),
+            ])
+            .unwrap_err();
+        assert!(err.to_string().contains("memory #9999 not found"));
+
+        let memories = db.get_all_memories_for_chat(Some(100)).unwrap();
+        assert_eq!(memories.len(), 1, "no partial writes may survive");
+        assert_eq!(memories[0].id, kept);
+        assert_eq!(memories[0].content, "the office is in Lisbon");
+
+        let err = db
+            .write_memory_batch(&[
+                batch_insert("second new fact", None),
+                MemoryBatchOp::Supersede {
+                    from_memory_id: 4242,
+                    content: "x".into(),
+                    category: "KNOWLEDGE".into(),
+                    source: "reflector".into(),
+                    confidence: 0.7,
+                    reason: None,
+                    embedding: None,
+                },
+            ])
+            .unwrap_err();
+        assert!(err.to_string().contains("memory #4242 not found"));
+        assert_eq!(db.get_all_memories_for_chat(Some(100)).unwrap().len(), 1);
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_memory_scope_parse_and_visibility() {
+        assert_eq!(MemoryScope::parse("global"), Some(MemoryScope::Global));
+        assert_eq!(MemoryScope::parse("chat:-42"), Some(MemoryScope::Chat(-42)));
+        assert_eq!(
+            MemoryScope::parse("user:ou_alice"),
+            Some(MemoryScope::User("ou_alice".into()))
+        );
+        assert_eq!(MemoryScope::parse("chat:abc"), None);
+        assert_eq!(MemoryScope::parse("user:"), None);
+        assert_eq!(MemoryScope::User("ou_a".into()).to_string(), "user:ou_a");
+
+        let (db, dir) = test_db();
+        let global = db
+            .insert_memory(None, "server is in fra1", "KNOWLEDGE")
+            .unwrap();
+        let chat = db
+            .insert_memory(Some(100), "standup at 10", "EVENT")
+            .unwrap();
+        let user = db
+            .insert_memory(None, "alice prefers tea", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(user, Some("ou_alice")).unwrap();
+        let load = |id| db.get_memory_by_id(id).unwrap().unwrap();
+
+        assert_eq!(load(global).scope(), MemoryScope::Global);
+        assert_eq!(load(chat).scope(), MemoryScope::Chat(100));
+        assert_eq!(load(user).scope(), MemoryScope::User("ou_alice".into()));
+        assert!(load(global).visible_to(200, None));
+        assert!(load(chat).visible_to(100, Some("ou_bob")));
+        assert!(!load(chat).visible_to(200, Some("ou_bob")));
+        assert!(load(user).visible_to(200, Some("ou_alice")));
+        assert!(!load(user).visible_to(100, Some("ou_bob")));
+        assert!(!load(user).visible_to(100, None));
+        cleanup(&dir);
+    }
+
     #[test]
     fn test_delete_memory() {
         let (db, dir) = test_db();
@@ -5192,11 +7163,92 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_tool_audit_logs_filter_and_are_append_only() {
+        let (db, dir) = test_db();
+        let record = |channel: &str, chat_id: i64, tool: &str, status: &str| ToolAuditRecord {
+            id: 0,
+            created_at: String::new(),
+            channel: channel.to_string(),
+            chat_id,
+            tool_name: tool.to_string(),
+            input: r#"{"command":"ls"}"#.to_string(),
+            risk: "high".to_string(),
+            approval: "not_required".to_string(),
+            status: status.to_string(),
+            error_type: (status == "error").then(|| "process_exit".to_string()),
+            duration_ms: 12,
+        };
+        db.log_tool_audit(&record("feishu", 1, "bash", "ok"))
+            .unwrap();
+        db.log_tool_audit(&record("feishu", 1, "read_file", "ok"))
+            .unwrap();
+        let failed = db
+            .log_tool_audit(&record("email", 2, "bash", "error"))
+            .unwrap();
+
+        let all = db
+            .list_tool_audit_logs(&ToolAuditFilter {
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].id, failed, "newest first");
+        assert_eq!(all[0].error_type.as_deref(), Some("process_exit"));
+
+        let bash_feishu = db
+            .list_tool_audit_logs(&ToolAuditFilter {
+                channel: Some("feishu".into()),
+                tool_name: Some("bash".into()),
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(bash_feishu.len(), 1);
+        assert_eq!(bash_feishu[0].chat_id, 1);
+
+        let errors = db
+            .list_tool_audit_logs(&ToolAuditFilter {
+                status: Some("error".into()),
+                since: Some((chrono::Utc::now() - chrono::Duration::minutes(5)).to_rfc3339()),
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(errors.len(), 1);
+
+        let conn = db.lock_conn();
+        assert!(conn
+            .execute("UPDATE tool_audit_logs SET status = 'ok'", [])
+            .is_err());
+        assert!(conn.execute("DELETE FROM tool_audit_logs", []).is_err());
+        drop(conn);
+
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_vector_index_dimension_reads_db_meta() {
+        let (db, dir) = test_db();
+        assert_eq!(db.vector_index_dimension().unwrap(), None);
+        db.lock_conn()
+            .execute(
+                "INSERT INTO db_meta(key, value) VALUES('embedding_dim', '768')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(db.vector_index_dimension().unwrap(), Some(768));
+        cleanup(&dir);
+    }
+
     #[cfg(feature = "sqlite-vec")]
     #[test]
     fn test_sqlite_vec_prepare_and_knn() {
         let (db, dir) = test_db();
+        assert_eq!(db.vector_index_dimension().unwrap(), None);
         db.prepare_vector_index(3).unwrap();
+        assert_eq!(db.vector_index_dimension().unwrap(), Some(3));
         let id1 = db
             .insert_memory(Some(100), "vector one", "KNOWLEDGE")
             .unwrap();
@@ -114,6 +114,42 @@ pub fn memory_topic_key(content: &str) -> String {
         .join("_")
 }
 
+/// Days after which an unconfirmed memory's confidence counts for half.
+pub const MEMORY_CONFIDENCE_HALF_LIFE_DAYS: f64 = 90.0;
+
+/// Share of the remaining headroom (to 1.0) gained each time a memory is reinforced.
+const MEMORY_REINFORCE_RATE: f64 = 0.25;
+
+/// Stored confidence decayed by the time since the memory was last seen or
+/// confirmed. Unparseable timestamps are treated as fresh.
+pub fn effective_confidence(
+    confidence: f64,
+    last_seen_at: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> f64 {
+    let confidence = confidence.clamp(0.0, 1.0);
+    let Ok(last_seen) = chrono::DateTime::parse_from_rfc3339(last_seen_at) else {
+        return confidence;
+    };
+    let age_days = (now - last_seen.with_timezone(&chrono::Utc)).num_seconds() as f64 / 86_400.0;
+    if age_days <= 0.0 {
+        return confidence;
+    }
+    confidence * 0.5f64.powf(age_days / MEMORY_CONFIDENCE_HALF_LIFE_DAYS)
+}
+
+/// Confidence after the memory is written again or otherwise confirmed.
+pub fn reinforced_confidence(confidence: f64) -> f64 {
+    let confidence = confidence.clamp(0.0, 1.0);
+    confidence + (1.0 - confidence) * MEMORY_REINFORCE_RATE
+}
+
+/// Ranking score for a retrieved memory: `relevance` (0..=1) weighted by its
+/// effective confidence, which can at most halve it.
+pub fn memory_rank_score(relevance: f64, effective_confidence: f64) -> f64 {
+    relevance.clamp(0.0, 1.0) * (0.5 + 0.5 * effective_confidence.clamp(0.0, 1.0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,6 +181,23 @@ mod tests {
         assert!(extract_explicit_memory_command("hello there").is_none());
     }
 
+    #[test]
+    fn test_confidence_decay_and_reinforcement() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-04-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(effective_confidence(0.8, "2026-04-01T00:00:00Z", now), 0.8);
+        let decayed = effective_confidence(0.8, "2026-01-01T00:00:00Z", now);
+        assert!((decayed - 0.4).abs() < 0.01, "{decayed}");
+        assert_eq!(effective_confidence(0.8, "not a date", now), 0.8);
+
+        assert!((reinforced_confidence(0.6) - 0.7).abs() < 1e-9);
+        assert!(reinforced_confidence(1.0) <= 1.0);
+
+        assert!(memory_rank_score(1.0, 0.9) > memory_rank_score(1.0, 0.3));
+        assert!(memory_rank_score(0.2, 1.0) < memory_rank_score(0.8, 0.0));
+    }
+
     #[test]
     fn test_memory_quality_reason() {
         assert!(memory_quality_ok("User prefers Rust and PostgreSQL."));
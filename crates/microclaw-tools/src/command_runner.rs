@@ -6,6 +6,12 @@ pub struct CommandSpec {
 }
 
 pub fn shell_command(command: &str) -> CommandSpec {
+    shell_command_with(None, command)
+}
+
+/// Like [`shell_command`], running `command` through `shell -c` when `shell` is
+/// set. Windows always uses PowerShell.
+pub fn shell_command_with(shell: Option<&str>, command: &str) -> CommandSpec {
     if cfg!(target_os = "windows") {
         CommandSpec {
             program: "powershell".to_string(),
@@ -17,8 +23,11 @@ pub fn shell_command(command: &str) -> CommandSpec {
             ],
         }
     } else {
-        let shell = std::env::var("SHELL")
-            .ok()
+        let shell = shell
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToOwned::to_owned)
+            .or_else(|| std::env::var("SHELL").ok())
             .filter(|s| !s.trim().is_empty())
             .unwrap_or_else(|| "/bin/sh".to_string());
         CommandSpec {
@@ -28,6 +37,33 @@ pub fn shell_command(command: &str) -> CommandSpec {
     }
 }
 
+/// Run `argv[0]` with the remaining arguments as-is, without a shell.
+pub fn argv_command(argv: &[String]) -> Option<CommandSpec> {
+    let (program, args) = argv.split_first()?;
+    Some(CommandSpec {
+        program: program.clone(),
+        args: args.to_vec(),
+    })
+}
+
+/// Quote `argv` into one POSIX shell command line that reproduces it exactly.
+pub fn shell_join(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let safe = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+            if safe {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 pub fn agent_browser_program() -> String {
     if cfg!(target_os = "windows") {
         "agent-browser.cmd".to_string()
@@ -45,6 +81,236 @@ pub fn build_command(spec: &CommandSpec, working_dir: Option<&Path>) -> tokio::p
     cmd
 }
 
+/// Decode captured process output. Invalid UTF-8 is replaced with U+FFFD
+/// rather than failing the whole command.
+pub fn decode_output(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Remove ANSI escape sequences (colors, cursor movement, OSC titles and
+/// hyperlinks) so command output reads cleanly as plain text.
+pub fn strip_ansi(text: &str) -> String {
+    if !text.contains('\u{1b}') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters and intermediates up to a final byte in @..~.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC/DCS/SOS/PM/APC strings end with BEL or ESC \.
+            Some(']' | 'P' | 'X' | '^' | '_') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // nF escapes such as ESC ( B: intermediates, then one final byte.
+            Some(c) if (' '..='/').contains(&c) => {
+                while chars.next_if(|c| (' '..='/').contains(c)).is_some() {}
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Environment that steers common tools away from prompts and pagers when
+/// commands run without a terminal.
+pub const NON_INTERACTIVE_ENV: &[(&str, &str)] = &[
+    ("DEBIAN_FRONTEND", "noninteractive"),
+    ("GIT_TERMINAL_PROMPT", "0"),
+    ("GIT_PAGER", "cat"),
+    ("PAGER", "cat"),
+];
+
+/// Programs that need a terminal regardless of arguments.
+const TERMINAL_PROGRAMS: &[&str] = &[
+    "vi", "vim", "nvim", "nano", "emacs", "less", "more", "most", "man", "top", "htop", "watch",
+    "ssh", "telnet", "ftp", "sftp", "passwd", "su", "visudo",
+];
+
+/// Programs that start an interactive session when given no arguments.
+const REPL_PROGRAMS: &[&str] = &[
+    "python",
+    "python3",
+    "node",
+    "irb",
+    "ghci",
+    "mysql",
+    "psql",
+    "sqlite3",
+    "redis-cli",
+    "bash",
+    "sh",
+    "zsh",
+];
+
+const PACKAGE_MANAGERS: &[&str] = &["apt", "apt-get", "yum", "dnf"];
+
+/// Best-effort guess at why `command` might block waiting for input, e.g.
+/// "`apt-get install` without -y". Returns `None` when nothing looks interactive.
+pub fn likely_interactive(command: &str) -> Option<String> {
+    command
+        .split(['\n', ';', '|', '&'])
+        .find_map(|segment| segment_interactive(segment.trim()))
+}
+
+fn segment_interactive(segment: &str) -> Option<String> {
+    let mut words = segment.split_whitespace().skip_while(|w| {
+        matches!(*w, "sudo" | "env" | "time" | "nohup" | "exec") || is_env_assignment(w)
+    });
+    let program = words.next()?;
+    let program = program.rsplit('/').next().unwrap_or(program);
+    let args: Vec<&str> = words.collect();
+    let has = |flags: &[&str]| args.iter().any(|a| flags.contains(a));
+
+    if TERMINAL_PROGRAMS.contains(&program) {
+        return Some(format!("`{program}` needs an interactive terminal"));
+    }
+    if REPL_PROGRAMS.contains(&program) && args.is_empty() {
+        return Some(format!(
+            "`{program}` without arguments starts an interactive session"
+        ));
+    }
+    if PACKAGE_MANAGERS.contains(&program)
+        && has(&["install", "remove", "purge", "upgrade", "dist-upgrade"])
+        && !has(&["-y", "--yes", "--assume-yes", "-qq"])
+    {
+        return Some(format!("`{program}` asks for confirmation without -y"));
+    }
+    if program == "git" {
+        if has(&["commit"]) && !has(&["-m", "-F", "--message", "--no-edit", "-C"]) {
+            return Some("`git commit` without -m opens an editor".to_string());
+        }
+        if has(&["-i", "--interactive", "-p", "--patch"]) {
+            return Some("interactive `git` mode needs a terminal".to_string());
+        }
+    }
+    if program == "read" {
+        return Some("`read` waits for input".to_string());
+    }
+    None
+}
+
+fn is_env_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Shell syntax that can start a second program, expand a variable or
+/// substitution (`$`, backticks) or redirect to a file (`>`, `>>`, `<` and
+/// process substitution). Command strings containing any of these are refused
+/// while an allowlist is active.
+const ALLOWLIST_FORBIDDEN_SYNTAX: &[&str] = &[";", "|", "&", "`", "$", "<", ">", "\n", "\r"];
+
+/// The program a shell command line starts: the first word after leading
+/// `NAME=value` assignments, with quotes and backslash escapes removed.
+pub fn command_program(command: &str) -> Option<String> {
+    split_command_program(command).map(|(program, _)| program)
+}
+
+/// [`command_program`], plus whether `NAME=value` assignments preceded it.
+fn split_command_program(command: &str) -> Option<(String, bool)> {
+    let mut assignments = false;
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars();
+    let mut quote: Option<char> = None;
+    loop {
+        let c = chars.next();
+        match (quote, c) {
+            (Some('\''), Some('\'')) | (Some('"'), Some('"')) => quote = None,
+            (Some('"'), Some('\\')) => {
+                if let Some(next) = chars.next() {
+                    if !matches!(next, '"' | '\\' | '$' | '`') {
+                        word.push('\\');
+                    }
+                    word.push(next);
+                }
+            }
+            (Some(_), Some(c)) => word.push(c),
+            (None, Some(q @ ('\'' | '"'))) => {
+                quote = Some(q);
+                in_word = true;
+            }
+            (None, Some('\\')) => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (None, Some(c)) if !c.is_whitespace() => {
+                word.push(c);
+                in_word = true;
+            }
+            (_, c) => {
+                if in_word {
+                    if !is_env_assignment(&word) {
+                        return Some((word, assignments));
+                    }
+                    assignments = true;
+                }
+                c?;
+                word.clear();
+                in_word = false;
+            }
+        }
+    }
+}
+
+/// Check `program` against `allowlist`. Bare names match bare entries (looked
+/// up on `PATH`); a program given with a path must be listed with that path.
+pub fn check_program_allowed(allowlist: &[String], program: &str) -> Result<(), String> {
+    if allowlist.iter().any(|allowed| allowed == program) {
+        return Ok(());
+    }
+    Err(format!(
+        "`{program}` is not in bash_command_allowlist (allowed: {})",
+        allowlist.join(", ")
+    ))
+}
+
+/// Allowlist check for a shell command string: refuses chaining, pipes,
+/// expansions, redirections and leading `NAME=value` assignments (`PATH` or
+/// `LD_PRELOAD` would let an allowed program run other code), then checks the
+/// program it starts.
+pub fn check_command_allowed(allowlist: &[String], command: &str) -> Result<(), String> {
+    if let Some(syntax) = ALLOWLIST_FORBIDDEN_SYNTAX
+        .iter()
+        .find(|syntax| command.contains(**syntax))
+    {
+        return Err(format!(
+            "`{}` is not permitted while bash_command_allowlist is set; run one allowed program per call",
+            syntax.escape_debug()
+        ));
+    }
+    let (program, assignments) = split_command_program(command).ok_or("Empty command")?;
+    if assignments {
+        return Err(
+            "leading NAME=value environment assignments are not permitted while bash_command_allowlist is set"
+                .to_string(),
+        );
+    }
+    check_program_allowed(allowlist, &program)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,9 +322,136 @@ mod tests {
         assert!(!spec.args.is_empty());
     }
 
+    #[test]
+    fn test_argv_command_and_shell_join() {
+        assert!(argv_command(&[]).is_none());
+        let argv = vec!["grep".to_string(), "a b".to_string(), "*.rs".to_string()];
+        let spec = argv_command(&argv).unwrap();
+        assert_eq!(spec.program, "grep");
+        assert_eq!(spec.args, ["a b", "*.rs"]);
+        assert_eq!(shell_join(&argv), "grep 'a b' '*.rs'");
+        assert_eq!(shell_join(&["it's".to_string()]), "'it'\\''s'");
+        assert_eq!(shell_join(&[String::new()]), "''");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_command_with_explicit_shell() {
+        let spec = shell_command_with(Some("bash"), "echo hi");
+        assert_eq!(spec.program, "bash");
+        assert_eq!(spec.args, ["-c", "echo hi"]);
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("plain text"), "plain text");
+        assert_eq!(
+            strip_ansi("\u{1b}[1;31merror\u{1b}[0m: \u{1b}[32mok\u{1b}[m"),
+            "error: ok"
+        );
+        assert_eq!(strip_ansi("a\u{1b}[2K\u{1b}[1Gb"), "ab");
+        assert_eq!(
+            strip_ansi(
+                "\u{1b}]8;;https://x.dev\u{1b}\\link\u{1b}]8;;\u{1b}\\ \u{1b}]0;title\u{7}done"
+            ),
+            "link done"
+        );
+        assert_eq!(strip_ansi("\u{1b}(Bx\u{1b}=y\u{1b}"), "xy");
+    }
+
+    #[test]
+    fn test_decode_output_replaces_invalid_utf8() {
+        assert_eq!(decode_output(b"ok \xff\xfe!"), "ok \u{fffd}\u{fffd}!");
+    }
+
     #[test]
     fn test_agent_browser_program_not_empty() {
         let p = agent_browser_program();
         assert!(!p.trim().is_empty());
     }
+
+    #[test]
+    fn test_likely_interactive_detection() {
+        assert!(likely_interactive("sudo apt-get install curl")
+            .unwrap()
+            .contains("-y"));
+        assert!(likely_interactive("cd repo && git commit").is_some());
+        assert!(likely_interactive("DEBUG=1 /usr/bin/vim notes.txt").is_some());
+        assert!(likely_interactive("python3").is_some());
+
+        assert_eq!(likely_interactive("apt-get install -y curl"), None);
+        assert_eq!(likely_interactive("git commit -m 'msg'"), None);
+        assert_eq!(likely_interactive("python3 script.py | grep ok"), None);
+        assert_eq!(likely_interactive("ls -la && cat file"), None);
+    }
+
+    #[test]
+    fn test_command_program_resolution() {
+        assert_eq!(command_program("git status").as_deref(), Some("git"));
+        assert_eq!(
+            command_program("  FOO=1 BAR='a b' ls -la").as_deref(),
+            Some("ls")
+        );
+        assert_eq!(command_program("'r'm -rf x").as_deref(), Some("rm"));
+        assert_eq!(command_program("g\\it log").as_deref(), Some("git"));
+        assert_eq!(
+            command_program("\"/usr/bin/git\"").as_deref(),
+            Some("/usr/bin/git")
+        );
+        assert_eq!(command_program("FOO=1"), None);
+        assert_eq!(command_program("   "), None);
+    }
+
+    #[test]
+    fn test_command_allowlist() {
+        let allow = vec![
+            "git".to_string(),
+            "ls".to_string(),
+            "/opt/bin/tool".to_string(),
+        ];
+        assert!(check_command_allowed(&allow, "git status").is_ok());
+        assert!(check_command_allowed(&allow, "/opt/bin/tool --x").is_ok());
+
+        let err = check_command_allowed(&allow, "rm -rf src").unwrap_err();
+        assert!(err.contains("`rm` is not in bash_command_allowlist"));
+        assert!(check_command_allowed(&allow, "./git status").is_err());
+        assert!(check_command_allowed(&allow, "/usr/bin/git status").is_err());
+        for bypass in [
+            "git status; rm -rf x",
+            "ls | sh",
+            "ls && rm x",
+            "ls `rm x`",
+            "ls $(rm x)",
+            "git log\nrm x",
+            "git log > .git/hooks/pre-commit",
+            "ls >> ~/.bashrc",
+            "git apply < patch.diff",
+            "ls <(rm x)",
+            "git log $HOME",
+            "ls ${IFS}x",
+        ] {
+            assert!(
+                check_command_allowed(&allow, bypass)
+                    .unwrap_err()
+                    .contains("not permitted"),
+                "{bypass}"
+            );
+        }
+        for bypass in [
+            "LANG=C ls -la",
+            "PATH=.:/usr/bin git status",
+            "LD_PRELOAD=./x.so git status",
+            "GIT_SSH_COMMAND='sh -c x' git fetch",
+        ] {
+            assert!(
+                check_command_allowed(&allow, bypass)
+                    .unwrap_err()
+                    .contains("environment assignments are not permitted"),
+                "{bypass}"
+            );
+        }
+        assert!(check_command_allowed(&allow, "PATH=.:$PATH git status")
+            .unwrap_err()
+            .contains("`$` is not permitted"));
+    }
 }
@@ -0,0 +1,181 @@
+//! Model-facing guidance for failed tool calls, keyed by `ToolResult::error_type`.
+//!
+//! The raw error text is kept as-is; a short hint is appended so the model gets
+//! an actionable next step instead of repeating the same call.
+
+use std::collections::HashMap;
+
+const BUILTIN_HINTS: &[(&str, &str)] = &[
+    (
+        "path_policy_blocked",
+        "The path is outside the allowed area. Use a path relative to the chat working directory and avoid absolute /tmp or sensitive locations.",
+    ),
+    (
+        "unknown_tool",
+        "This tool does not exist. Call only tools listed in your tool definitions.",
+    ),
+    (
+        "timeout",
+        "The call timed out. Narrow its scope or split the work before retrying; rerunning it unchanged will likely time out again.",
+    ),
+    (
+        "interactive_input",
+        "The command waited for input that never came. Use non-interactive flags (-y, --batch, -m) or pipe the input instead.",
+    ),
+    (
+        "process_exit",
+        "The command ran but exited non-zero. Read stderr above and fix the cause before retrying; do not rerun it unchanged.",
+    ),
+    (
+        "spawn_error",
+        "The process could not be started. Check the command name and that the program is installed.",
+    ),
+    (
+        "execution_policy_blocked",
+        "This tool is blocked by the execution policy in this environment. Do not retry it; use another approach or tell the user.",
+    ),
+    (
+        "hook_blocked",
+        "A policy hook blocked this call. Do not retry the same call.",
+    ),
+    (
+        "plugin_permission_denied",
+        "This plugin tool is not permitted in this chat. Do not retry it.",
+    ),
+    (
+        "plugin_template_error",
+        "A required input is missing. Supply every parameter the tool's schema requires.",
+    ),
+    (
+        "plugin_env_error",
+        "The plugin is misconfigured. Do not retry; tell the user which plugin failed.",
+    ),
+    (
+        "mcp_rate_limited",
+        "The MCP server is rate-limiting requests. Wait before calling it again or continue without it.",
+    ),
+    (
+        "mcp_circuit_open",
+        "The MCP server is temporarily unavailable after repeated failures. Continue without it for now.",
+    ),
+    (
+        "budget_exceeded",
+        "The sub-agent ran out of its time or token budget. Use the partial result, or delegate a smaller task.",
+    ),
+    (
+        "mcp_bulkhead_rejected",
+        "The MCP server is busy. Avoid parallel calls to it and retry once.",
+    ),
+    (
+        "domain_blocked",
+        "This domain is not allowed by the web domain policy. Do not retry it; use a source on an allowed domain.",
+    ),
+    (
+        "scratch_ref_unknown",
+        "A {{scratch:key}} reference names a key that is not set in this turn. Call scratch_set first, or pass the value directly.",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ToolErrorHintsConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Extra or replacement hints by error type; an empty value disables a built-in hint.
+    #[serde(default)]
+    pub hints: HashMap<String, String>,
+}
+
+const fn default_enabled() -> bool {
+    true
+}
+
+impl Default for ToolErrorHintsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            hints: HashMap::new(),
+        }
+    }
+}
+
+impl ToolErrorHintsConfig {
+    pub fn normalize(&mut self) {
+        self.hints = self
+            .hints
+            .drain()
+            .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+    }
+
+    /// Guidance for `error_type`, with config overrides taking precedence over built-ins.
+    pub fn hint_for(&self, error_type: &str) -> Option<&str> {
+        if !self.enabled {
+            return None;
+        }
+        let key = error_type.trim().to_ascii_lowercase();
+        let hint = match self.hints.get(&key) {
+            Some(custom) => custom.as_str(),
+            None => BUILTIN_HINTS
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| *v)?,
+        };
+        (!hint.is_empty()).then_some(hint)
+    }
+
+    /// Append the hint for `error_type` to a tool error shown to the model.
+    pub fn annotate(&self, content: &str, error_type: Option<&str>) -> String {
+        match error_type.and_then(|t| self.hint_for(t)) {
+            Some(hint) => format!("{content}\n\n[hint] {hint}"),
+            None => content.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_hint_is_appended() {
+        let config = ToolErrorHintsConfig::default();
+        let out = config.annotate("Path is blocked", Some("path_policy_blocked"));
+        assert!(out.starts_with("Path is blocked\n\n[hint] "));
+        assert!(out.contains("relative to the chat working directory"));
+        assert_eq!(
+            config.annotate("boom", Some("tool_error")),
+            "boom",
+            "generic errors carry no hint"
+        );
+        assert_eq!(config.annotate("boom", None), "boom");
+    }
+
+    #[test]
+    fn test_overrides_and_disable() {
+        let mut config = ToolErrorHintsConfig {
+            enabled: true,
+            hints: HashMap::from([
+                (
+                    " Timeout ".to_string(),
+                    "  Use a smaller query. ".to_string(),
+                ),
+                ("process_exit".to_string(), String::new()),
+                (
+                    "my_plugin_error".to_string(),
+                    "Check the plugin docs.".to_string(),
+                ),
+            ]),
+        };
+        config.normalize();
+        assert_eq!(config.hint_for("timeout"), Some("Use a smaller query."));
+        assert_eq!(config.hint_for("process_exit"), None);
+        assert_eq!(
+            config.hint_for("my_plugin_error"),
+            Some("Check the plugin docs.")
+        );
+
+        config.enabled = false;
+        assert_eq!(config.annotate("x", Some("unknown_tool")), "x");
+    }
+}
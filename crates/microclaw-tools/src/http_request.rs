@@ -0,0 +1,464 @@
+//! Generic HTTP requests for the `http_request` tool. URL policy, proxy,
+//! per-host pacing and content validation are shared with `web_fetch`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use microclaw_core::text::floor_char_boundary;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Method, StatusCode, Url};
+use tracing::warn;
+
+use crate::web_content_validation::{validate_web_content_with_config, WebContentValidationConfig};
+use crate::web_fetch::{
+    host_matches_rule, http_client_no_redirect, normalize_host_candidate,
+    resolve_and_validate_redirect_target, resolve_url_validation_config, validate_web_fetch_url,
+    wait_for_host_slot, WebFetchHostRateConfig, WebFetchProxyConfig, WebFetchUrlValidationConfig,
+};
+
+pub const HTTP_REQUEST_METHODS: &[&str] =
+    &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+const MAX_REDIRECTS: usize = 5;
+const MAX_BODY_BYTES: usize = 20_000;
+
+/// Caller-supplied headers that are not forwarded when a redirect leaves the
+/// original host.
+const CROSS_HOST_STRIPPED_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Headers added to requests per host, e.g. `api.github.com: {authorization: "Bearer ..."}`.
+/// A rule also covers subdomains; the most specific matching rule wins.
+/// Configured headers replace caller-supplied headers of the same name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct HttpRequestAuthConfig {
+    pub hosts: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl HttpRequestAuthConfig {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        let mut hosts = BTreeMap::new();
+        for (host, headers) in std::mem::take(&mut self.hosts) {
+            let Some(normalized) = normalize_host_candidate(&host) else {
+                return Err(format!("http_request_auth: invalid host '{host}'"));
+            };
+            let mut checked = BTreeMap::new();
+            for (name, value) in headers {
+                let name = name.trim().to_ascii_lowercase();
+                HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                    format!("http_request_auth.{normalized}: invalid header name '{name}'")
+                })?;
+                HeaderValue::from_str(&value).map_err(|_| {
+                    format!("http_request_auth.{normalized}: invalid value for header '{name}'")
+                })?;
+                checked.insert(name, value);
+            }
+            hosts.insert(normalized, checked);
+        }
+        self.hosts = hosts;
+        Ok(())
+    }
+
+    /// Headers configured for `host` (lowercase), from the longest matching rule.
+    pub fn headers_for(&self, host: &str) -> Option<&BTreeMap<String, String>> {
+        self.hosts
+            .iter()
+            .filter(|(rule, _)| host_matches_rule(host, rule))
+            .max_by_key(|(rule, _)| rule.len())
+            .map(|(_, headers)| headers)
+    }
+
+    pub fn secret_values(&self) -> impl Iterator<Item = &String> {
+        self.hosts
+            .values()
+            .flat_map(|headers| headers.values())
+            .filter(|v| !v.trim().is_empty())
+    }
+}
+
+/// Everything about an `http_request` call that comes from config rather
+/// than from the model.
+#[derive(Debug, Clone, Default)]
+pub struct HttpRequestPolicy {
+    pub timeout_secs: u64,
+    pub validation: WebContentValidationConfig,
+    pub url_validation: WebFetchUrlValidationConfig,
+    pub proxy: WebFetchProxyConfig,
+    pub host_rate: WebFetchHostRateConfig,
+    pub auth: HttpRequestAuthConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequestSpec {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponseOutcome {
+    pub status: StatusCode,
+    pub final_url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub truncated: bool,
+    pub rate_limit_wait: Duration,
+}
+
+impl HttpResponseOutcome {
+    /// Status line, response headers, a blank line, then the body.
+    pub fn format(&self) -> String {
+        let mut out = format!("HTTP {}", self.status);
+        for (name, value) in &self.headers {
+            out.push_str(&format!("\n{name}: {value}"));
+        }
+        if !self.body.is_empty() {
+            out.push_str("\n\n");
+            out.push_str(&self.body);
+        }
+        if self.truncated {
+            out.push_str("\n\n[Truncated at 20KB]");
+        }
+        out
+    }
+}
+
+/// Parse a method name; only the methods in [`HTTP_REQUEST_METHODS`] are accepted.
+pub fn parse_method(raw: &str) -> Result<Method, String> {
+    let upper = raw.trim().to_ascii_uppercase();
+    if !HTTP_REQUEST_METHODS.contains(&upper.as_str()) {
+        return Err(format!(
+            "Unsupported method '{raw}' (expected one of: {})",
+            HTTP_REQUEST_METHODS.join(", ")
+        ));
+    }
+    Method::from_bytes(upper.as_bytes()).map_err(|e| e.to_string())
+}
+
+fn build_headers(
+    spec_headers: &[(String, String)],
+    url: &Url,
+    auth: &HttpRequestAuthConfig,
+    cross_host: bool,
+) -> Result<reqwest::header::HeaderMap, String> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (name, value) in spec_headers {
+        let lower = name.trim().to_ascii_lowercase();
+        if cross_host && CROSS_HOST_STRIPPED_HEADERS.contains(&lower.as_str()) {
+            continue;
+        }
+        let name = HeaderName::from_bytes(lower.as_bytes())
+            .map_err(|_| format!("invalid header name '{name}'"))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| format!("invalid value for header '{name}'"))?;
+        headers.insert(name, value);
+    }
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    if let Some(configured) = auth.headers_for(&host) {
+        for (name, value) in configured {
+            // Validated by `HttpRequestAuthConfig::normalize`.
+            if let (Ok(name), Ok(mut value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                value.set_sensitive(true);
+                headers.insert(name, value);
+            }
+        }
+    }
+    Ok(headers)
+}
+
+/// Send `spec`, following up to 5 redirects. Every hop is checked against the
+/// URL policy; 307/308 keep the method and body, other redirects become a
+/// bodyless GET. Non-2xx responses are returned, not treated as errors.
+pub async fn send_http_request(
+    spec: &HttpRequestSpec,
+    policy: &HttpRequestPolicy,
+) -> Result<HttpResponseOutcome, String> {
+    let url_validation = resolve_url_validation_config(policy.url_validation.clone()).await?;
+    validate_web_fetch_url(&spec.url, url_validation.clone())?;
+
+    let client = http_client_no_redirect(policy.timeout_secs.max(1), &policy.proxy);
+    let origin = Url::parse(&spec.url).map_err(|e| format!("invalid URL: {e}"))?;
+    let mut current_url = origin.clone();
+    let mut method = spec.method.clone();
+    let mut body = spec.body.clone();
+    let mut redirects = 0usize;
+    let mut rate_limit_wait = Duration::ZERO;
+
+    let resp = loop {
+        rate_limit_wait += wait_for_host_slot(&current_url, &policy.host_rate).await;
+        let cross_host = current_url.host_str() != origin.host_str();
+        let headers = build_headers(&spec.headers, &current_url, &policy.auth, cross_host)?;
+        let mut request = client
+            .request(method.clone(), current_url.clone())
+            .headers(headers);
+        if let Some(body) = body.clone() {
+            request = request.body(body);
+        }
+        let resp = request.send().await.map_err(|e| e.to_string())?;
+
+        if !resp.status().is_redirection() || resp.status() == StatusCode::NOT_MODIFIED {
+            break resp;
+        }
+        let Some(location) = resp.headers().get(reqwest::header::LOCATION) else {
+            break resp;
+        };
+        if redirects >= MAX_REDIRECTS {
+            return Err(format!("too many redirects (max {MAX_REDIRECTS})"));
+        }
+        redirects += 1;
+
+        let location = location
+            .to_str()
+            .map_err(|e| format!("invalid redirect Location header: {e}"))?;
+        current_url =
+            resolve_and_validate_redirect_target(&current_url, location, &url_validation)?;
+        if !matches!(
+            resp.status(),
+            StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+        ) {
+            if method != Method::HEAD {
+                method = Method::GET;
+            }
+            body = None;
+        }
+    };
+
+    let status = resp.status();
+    let headers = resp
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.as_str().to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+
+    if let Err(failure) = validate_web_content_with_config(&text, policy.validation) {
+        warn!(
+            matched_rules = failure.rule_names.join(","),
+            "Blocked http_request response by validation"
+        );
+        return Err(failure.message());
+    }
+
+    let truncated = text.len() > MAX_BODY_BYTES;
+    let body = if truncated {
+        text[..floor_char_boundary(&text, MAX_BODY_BYTES)].to_string()
+    } else {
+        text
+    };
+
+    Ok(HttpResponseOutcome {
+        status,
+        final_url: current_url.to_string(),
+        headers,
+        body,
+        truncated,
+        rate_limit_wait,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web_domains::WebDomainPolicy;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn spec(method: Method, url: String) -> HttpRequestSpec {
+        HttpRequestSpec {
+            method,
+            url,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> String {
+        let mut buf = vec![0u8; 8192];
+        let n = stream.read(&mut buf).await.unwrap_or(0);
+        String::from_utf8_lossy(&buf[..n]).to_string()
+    }
+
+    #[test]
+    fn auth_config_normalizes_and_matches_most_specific_host() {
+        let mut auth: HttpRequestAuthConfig = serde_yaml::from_str(
+            "https://Example.com/: {Authorization: outer}\napi.example.com: {authorization: inner, x-extra: '1'}\n",
+        )
+        .unwrap();
+        auth.normalize().unwrap();
+        assert_eq!(
+            auth.headers_for("example.com").unwrap()["authorization"],
+            "outer"
+        );
+        let api = auth.headers_for("v2.api.example.com").unwrap();
+        assert_eq!(api["authorization"], "inner");
+        assert_eq!(api["x-extra"], "1");
+        assert!(auth.headers_for("notexample.com").is_none());
+        assert_eq!(auth.secret_values().count(), 3);
+
+        let mut bad: HttpRequestAuthConfig =
+            serde_yaml::from_str("example.com: {'bad header': x}\n").unwrap();
+        assert!(bad.normalize().unwrap_err().contains("invalid header name"));
+    }
+
+    #[test]
+    fn parse_method_accepts_known_methods_only() {
+        assert_eq!(parse_method(" post ").unwrap(), Method::POST);
+        assert!(parse_method("CONNECT")
+            .unwrap_err()
+            .contains("Unsupported method"));
+    }
+
+    #[tokio::test]
+    async fn sends_method_headers_body_and_configured_auth() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let req = read_request(&mut stream).await;
+            let body = "{\"error\":\"conflict\"}";
+            let response = format!(
+                "HTTP/1.1 409 Conflict\r\nContent-Type: application/json\r\nX-Request-Id: abc\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            req
+        });
+
+        let mut auth = HttpRequestAuthConfig::default();
+        auth.hosts.insert(
+            "127.0.0.1".into(),
+            BTreeMap::from([("authorization".into(), "Bearer configured".into())]),
+        );
+        let policy = HttpRequestPolicy {
+            timeout_secs: 5,
+            auth,
+            ..HttpRequestPolicy::default()
+        };
+        let mut request = spec(
+            Method::PUT,
+            format!("http://127.0.0.1:{}/items/1", addr.port()),
+        );
+        request.headers = vec![
+            ("Content-Type".into(), "application/json".into()),
+            ("Authorization".into(), "Bearer from-model".into()),
+        ];
+        request.body = Some("{\"name\":\"x\"}".into());
+
+        let outcome = send_http_request(&request, &policy).await.unwrap();
+        assert_eq!(outcome.status, StatusCode::CONFLICT);
+        assert_eq!(outcome.body, "{\"error\":\"conflict\"}");
+        let formatted = outcome.format();
+        assert!(formatted.starts_with("HTTP 409 Conflict\n"));
+        assert!(formatted.contains("x-request-id: abc"));
+
+        let req = server.await.unwrap();
+        assert!(req.starts_with("PUT /items/1 HTTP/1.1"));
+        assert!(req.contains("authorization: Bearer configured"));
+        assert!(!req.contains("from-model"));
+        assert!(req.ends_with("{\"name\":\"x\"}"));
+    }
+
+    #[tokio::test]
+    async fn redirect_downgrades_post_and_strips_credentials_across_hosts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            let (mut stream, _) = listener.accept().await.unwrap();
+            requests.push(read_request(&mut stream).await);
+            // Same server, different host name: counts as a cross-host redirect.
+            let response = format!(
+                "HTTP/1.1 303 See Other\r\nLocation: http://localhost:{}/done\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                addr.port()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().await.unwrap();
+            requests.push(read_request(&mut stream).await);
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .await;
+            requests
+        });
+
+        let policy = HttpRequestPolicy {
+            timeout_secs: 5,
+            ..HttpRequestPolicy::default()
+        };
+        let mut request = spec(
+            Method::POST,
+            format!("http://127.0.0.1:{}/submit", addr.port()),
+        );
+        request.headers = vec![
+            ("Authorization".into(), "Bearer secret".into()),
+            ("X-Trace".into(), "t1".into()),
+        ];
+        request.body = Some("payload".into());
+
+        let outcome = send_http_request(&request, &policy).await.unwrap();
+        assert_eq!(outcome.status, StatusCode::OK);
+        assert_eq!(outcome.body, "ok");
+        assert!(outcome.final_url.contains("localhost"));
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("authorization: Bearer secret"));
+        assert!(requests[1].starts_with("GET /done HTTP/1.1"));
+        assert!(!requests[1].contains("Bearer secret"));
+        assert!(requests[1].contains("x-trace: t1"));
+        assert!(!requests[1].contains("payload"));
+    }
+
+    #[tokio::test]
+    async fn rejects_urls_blocked_by_url_policy() {
+        let policy = HttpRequestPolicy {
+            timeout_secs: 5,
+            url_validation: WebFetchUrlValidationConfig {
+                denylist_hosts: vec!["example.com".into()],
+                ..WebFetchUrlValidationConfig::default()
+            },
+            ..HttpRequestPolicy::default()
+        };
+        let err = send_http_request(
+            &spec(Method::DELETE, "https://api.example.com/x".into()),
+            &policy,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("denylisted"));
+
+        let err = send_http_request(&spec(Method::GET, "file:///etc/passwd".into()), &policy)
+            .await
+            .unwrap_err();
+        assert!(err.contains("scheme"));
+    }
+
+    #[tokio::test]
+    async fn rejects_urls_outside_domain_policy() {
+        let policy = HttpRequestPolicy {
+            timeout_secs: 5,
+            url_validation: WebFetchUrlValidationConfig {
+                domain_policy: WebDomainPolicy::new(&[], &["example.com".into()]),
+                ..WebFetchUrlValidationConfig::default()
+            },
+            ..HttpRequestPolicy::default()
+        };
+        let err = send_http_request(
+            &spec(Method::GET, "https://api.example.com/x".into()),
+            &policy,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("web_domain_denylist"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Lenient repair of tool inputs before execution, driven by the tool's `input_schema`.
+//!
+//! Only unambiguous mistakes are fixed: `"42"` for an integer, `"true"` for a
+//! boolean, or a lone value where an array is expected. Anything else is left
+//! untouched so the tool still rejects it with its usual error.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ToolInputCoercionConfig {
+    /// Tools whose input is coerced; `"*"` opts in every tool. Empty disables coercion.
+    #[serde(default)]
+    pub tools: Vec<String>,
+}
+
+impl ToolInputCoercionConfig {
+    pub fn normalize(&mut self) {
+        let mut tools: Vec<String> = self
+            .tools
+            .iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        tools.sort();
+        tools.dedup();
+        self.tools = tools;
+    }
+
+    pub fn applies_to(&self, tool_name: &str) -> bool {
+        self.tools.iter().any(|t| t == "*" || t == tool_name)
+    }
+}
+
+/// Coerce `input` in place to match `schema`, returning one note per change
+/// (e.g. `limit: string -> integer`).
+pub fn coerce_tool_input(schema: &Value, input: &mut Value) -> Vec<String> {
+    let mut notes = Vec::new();
+    coerce_object(schema, input, "", &mut notes);
+    notes
+}
+
+fn coerce_object(schema: &Value, input: &mut Value, prefix: &str, notes: &mut Vec<String>) {
+    let (Some(properties), Some(obj)) = (
+        schema.get("properties").and_then(Value::as_object),
+        input.as_object_mut(),
+    ) else {
+        return;
+    };
+    for (key, value) in obj.iter_mut() {
+        if let Some(prop_schema) = properties.get(key) {
+            coerce_value(prop_schema, value, &format!("{prefix}{key}"), notes);
+        }
+    }
+}
+
+fn coerce_value(schema: &Value, value: &mut Value, path: &str, notes: &mut Vec<String>) {
+    // Union types ("type": ["string", "integer"]) have no single target; leave them alone.
+    let Some(expected) = schema.get("type").and_then(Value::as_str) else {
+        return;
+    };
+    match expected {
+        "integer" => {
+            if let Some(n) = value.as_str().and_then(|s| s.trim().parse::<i64>().ok()) {
+                *value = Value::from(n);
+                notes.push(format!("{path}: string -> integer"));
+            }
+        }
+        "number" => {
+            if let Some(n) = value
+                .as_str()
+                .and_then(|s| s.trim().parse::<f64>().ok())
+                .and_then(serde_json::Number::from_f64)
+            {
+                *value = Value::Number(n);
+                notes.push(format!("{path}: string -> number"));
+            }
+        }
+        "boolean" => {
+            let parsed = match value.as_str().map(|s| s.trim().to_ascii_lowercase()) {
+                Some(s) if s == "true" => Some(true),
+                Some(s) if s == "false" => Some(false),
+                _ => None,
+            };
+            if let Some(b) = parsed {
+                *value = Value::Bool(b);
+                notes.push(format!("{path}: string -> boolean"));
+            }
+        }
+        "array" => {
+            if matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_)) {
+                *value = Value::Array(vec![value.take()]);
+                notes.push(format!("{path}: scalar -> single-element array"));
+            }
+            if let (Some(items), Some(arr)) = (schema.get("items"), value.as_array_mut()) {
+                for (i, item) in arr.iter_mut().enumerate() {
+                    coerce_value(items, item, &format!("{path}[{i}]"), notes);
+                }
+            }
+        }
+        "object" => coerce_object(schema, value, &format!("{path}."), notes),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "limit": {"type": "integer"},
+                "ratio": {"type": "number"},
+                "recursive": {"type": "boolean"},
+                "paths": {"type": "array", "items": {"type": "string"}},
+                "ids": {"type": "array", "items": {"type": "integer"}},
+                "name": {"type": "string"},
+                "either": {"type": ["string", "integer"]},
+                "filter": {
+                    "type": "object",
+                    "properties": {"max": {"type": "integer"}}
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn coerces_obvious_mistakes() {
+        let mut input = json!({
+            "limit": " 20 ",
+            "ratio": "0.5",
+            "recursive": "True",
+            "paths": "src/main.rs",
+            "ids": ["1", 2],
+            "filter": {"max": "3"}
+        });
+        let notes = coerce_tool_input(&schema(), &mut input);
+        assert_eq!(
+            input,
+            json!({
+                "limit": 20,
+                "ratio": 0.5,
+                "recursive": true,
+                "paths": ["src/main.rs"],
+                "ids": [1, 2],
+                "filter": {"max": 3}
+            })
+        );
+        assert_eq!(notes.len(), 6);
+        assert!(notes.contains(&"paths: scalar -> single-element array".to_string()));
+        assert!(notes.contains(&"ids[0]: string -> integer".to_string()));
+        assert!(notes.contains(&"filter.max: string -> integer".to_string()));
+    }
+
+    #[test]
+    fn leaves_ambiguous_values_alone() {
+        let original = json!({
+            "limit": "2.5",
+            "recursive": "yes",
+            "paths": null,
+            "name": 5,
+            "either": "7",
+            "unknown": "1",
+            "__microclaw_auth": {"caller_chat_id": "1"}
+        });
+        let mut input = original.clone();
+        assert!(coerce_tool_input(&schema(), &mut input).is_empty());
+        assert_eq!(input, original);
+    }
+
+    #[test]
+    fn applies_to_listed_tools_or_wildcard() {
+        let mut config = ToolInputCoercionConfig {
+            tools: vec![" read_file ".into(), "".into(), "read_file".into()],
+        };
+        config.normalize();
+        assert_eq!(config.tools, vec!["read_file".to_string()]);
+        assert!(config.applies_to("read_file"));
+        assert!(!config.applies_to("bash"));
+        assert!(ToolInputCoercionConfig {
+            tools: vec!["*".into()]
+        }
+        .applies_to("bash"));
+        assert!(!ToolInputCoercionConfig::default().applies_to("bash"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Configured default arguments merged under a tool's input before execution.
+//!
+//! Defaults only fill keys the model left out (or set to `null`); a value the
+//! model passed always wins.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+/// Default input values per tool name, e.g. `bash: {timeout_secs: 300}`.
+pub type ToolInputDefaults = HashMap<String, Map<String, Value>>;
+
+/// Fill missing or `null` keys of `input` from `defaults`, returning the keys
+/// that were filled. A non-object `input` (normally `null`) becomes an object.
+pub fn apply_input_defaults(defaults: &Map<String, Value>, input: &mut Value) -> Vec<String> {
+    if defaults.is_empty() {
+        return Vec::new();
+    }
+    if !input.is_object() {
+        *input = Value::Object(Map::new());
+    }
+    let Some(obj) = input.as_object_mut() else {
+        return Vec::new();
+    };
+    let mut applied = Vec::new();
+    for (key, value) in defaults {
+        if obj.get(key).is_none_or(Value::is_null) {
+            obj.insert(key.clone(), value.clone());
+            applied.push(key.clone());
+        }
+    }
+    applied
+}
+
+/// Keys of `defaults` that `schema` does not declare. Schemas without a
+/// `properties` object accept anything, so nothing is reported for them.
+pub fn unknown_default_keys(schema: &Value, defaults: &Map<String, Value>) -> Vec<String> {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let mut unknown: Vec<String> = defaults
+        .keys()
+        .filter(|key| !properties.contains_key(*key))
+        .cloned()
+        .collect();
+    unknown.sort();
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn defaults(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_apply_input_defaults_model_wins() {
+        let defaults = defaults(json!({"timeout_secs": 300, "path": "reports", "keep_ansi": true}));
+        let mut input = json!({"command": "ls", "timeout_secs": 5, "path": null});
+        let mut applied = apply_input_defaults(&defaults, &mut input);
+        applied.sort();
+        assert_eq!(applied, ["keep_ansi", "path"]);
+        assert_eq!(
+            input,
+            json!({"command": "ls", "timeout_secs": 5, "path": "reports", "keep_ansi": true})
+        );
+
+        let mut empty = Value::Null;
+        apply_input_defaults(&defaults, &mut empty);
+        assert_eq!(empty["timeout_secs"], 300);
+    }
+
+    #[test]
+    fn test_unknown_default_keys() {
+        let schema = json!({"type": "object", "properties": {"command": {}, "timeout_secs": {}}});
+        let defaults = defaults(json!({"timeout_secs": 1, "timeout": 2, "cwd": "x"}));
+        assert_eq!(unknown_default_keys(&schema, &defaults), ["cwd", "timeout"]);
+        assert!(unknown_default_keys(&json!({"type": "object"}), &defaults).is_empty());
+    }
+}
@@ -1,12 +1,18 @@
 //! Tool runtime and built-in tool implementations for MicroClaw.
 
 pub mod command_runner;
+pub mod error_hints;
+pub mod http_request;
+pub mod input_coercion;
+pub mod input_defaults;
 pub mod path_guard;
+pub mod redaction;
 pub mod runtime;
 pub mod sandbox;
 pub mod todo_store;
 pub mod types;
 pub mod web_content_validation;
+pub mod web_domains;
 pub mod web_fetch;
 pub mod web_html;
 pub mod web_search;
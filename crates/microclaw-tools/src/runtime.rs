@@ -5,7 +5,7 @@ use microclaw_core::llm_types::ToolDefinition;
 use serde_json::json;
 
 use crate::sandbox::SandboxMode;
-use crate::types::WorkingDirIsolation;
+use crate::types::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 
 pub struct ToolResult {
     pub content: String,
@@ -103,12 +103,14 @@ pub fn tool_risk(name: &str) -> ToolRisk {
         | "send_message"
         | "sync_skills"
         | "schedule_task"
+        | "save_task_template"
         | "pause_scheduled_task"
         | "resume_scheduled_task"
         | "cancel_scheduled_task"
         | "replay_scheduled_task_dlq"
         | "structured_memory_delete"
-        | "structured_memory_update" => ToolRisk::Medium,
+        | "structured_memory_update"
+        | "http_request" => ToolRisk::Medium,
         _ => ToolRisk::Low,
     }
 }
@@ -158,16 +160,29 @@ pub struct ToolAuthContext {
     pub caller_channel: String,
     pub caller_chat_id: i64,
     pub control_chat_ids: Vec<i64>,
+    /// The sender matched the channel's `admin_user_ids` at intake.
+    pub caller_is_admin: bool,
+    /// Stable id of the member who triggered the turn; scopes user memories.
+    pub caller_sender_id: Option<String>,
+    /// Agent run the call belongs to; 0 outside a registered run. Scopes
+    /// per-run state such as scratch values.
+    pub run_id: u64,
 }
 
 impl ToolAuthContext {
+    /// True for chats listed in `control_chat_ids` and for admin senders.
     pub fn is_control_chat(&self) -> bool {
-        self.control_chat_ids.contains(&self.caller_chat_id)
+        self.caller_is_admin || self.control_chat_ids.contains(&self.caller_chat_id)
     }
 
     pub fn can_access_chat(&self, target_chat_id: i64) -> bool {
         self.is_control_chat() || self.caller_chat_id == target_chat_id
     }
+
+    /// False for turns no member triggered, such as scheduled task runs.
+    pub fn is_user_turn(&self) -> bool {
+        self.caller_sender_id.is_some()
+    }
 }
 
 const AUTH_CONTEXT_KEY: &str = "__microclaw_auth";
@@ -185,10 +200,23 @@ pub fn auth_context_from_input(input: &serde_json::Value) -> Option<ToolAuthCont
         .and_then(|v| v.as_array())
         .map(|arr| arr.iter().filter_map(|x| x.as_i64()).collect())
         .unwrap_or_default();
+    let caller_is_admin = ctx
+        .get("caller_is_admin")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let caller_sender_id = ctx
+        .get("caller_sender_id")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+    let run_id = ctx.get("run_id").and_then(|v| v.as_u64()).unwrap_or(0);
     Some(ToolAuthContext {
         caller_channel,
         caller_chat_id,
         control_chat_ids,
+        caller_is_admin,
+        caller_sender_id,
+        run_id,
     })
 }
 
@@ -215,6 +243,9 @@ pub fn inject_auth_context(input: serde_json::Value, auth: &ToolAuthContext) ->
             "caller_channel": auth.caller_channel,
             "caller_chat_id": auth.caller_chat_id,
             "control_chat_ids": auth.control_chat_ids,
+            "caller_is_admin": auth.caller_is_admin,
+            "caller_sender_id": auth.caller_sender_id,
+            "run_id": auth.run_id,
         }),
     );
     serde_json::Value::Object(obj)
@@ -264,18 +295,40 @@ fn chat_working_dir(base_working_dir: &Path, channel: &str, chat_id: i64) -> Pat
         .join(chat_segment)
 }
 
+/// Resolve the working dir for a tool call under the caller channel's root;
+/// control chats may use a different isolation strategy than regular chats.
 pub fn resolve_tool_working_dir(
-    base_working_dir: &Path,
-    isolation: WorkingDirIsolation,
+    roots: &WorkingDirRoots,
+    policy: impl Into<WorkingDirIsolationPolicy>,
     input: &serde_json::Value,
 ) -> PathBuf {
-    let resolved = match isolation {
+    match auth_context_from_input(input) {
+        Some(auth) => resolve_chat_working_dir(
+            roots.for_channel(&auth.caller_channel),
+            policy,
+            &auth.caller_channel,
+            auth.caller_chat_id,
+            auth.is_control_chat(),
+        ),
+        None => {
+            let resolved = roots.default.join("shared");
+            let _ = std::fs::create_dir_all(&resolved);
+            resolved
+        }
+    }
+}
+
+/// The working dir tools see for `chat_id` on `channel`, created if missing.
+pub fn resolve_chat_working_dir(
+    base_working_dir: &Path,
+    policy: impl Into<WorkingDirIsolationPolicy>,
+    channel: &str,
+    chat_id: i64,
+    is_control_chat: bool,
+) -> PathBuf {
+    let resolved = match policy.into().for_chat(is_control_chat) {
+        WorkingDirIsolation::Chat => chat_working_dir(base_working_dir, channel, chat_id),
         WorkingDirIsolation::Shared => base_working_dir.join("shared"),
-        WorkingDirIsolation::Chat => auth_context_from_input(input)
-            .map(|auth| {
-                chat_working_dir(base_working_dir, &auth.caller_channel, auth.caller_chat_id)
-            })
-            .unwrap_or_else(|| base_working_dir.join("shared")),
     };
     let _ = std::fs::create_dir_all(&resolved);
     resolved
@@ -319,6 +372,26 @@ pub fn require_high_risk_approval(
     }
 }
 
+/// How the high-risk approval gate treats this call, for the tool audit log:
+/// `not_required`, `approved` (explicit marker present) or `denied`.
+pub fn high_risk_approval_status(
+    name: &str,
+    auth: &ToolAuthContext,
+    input: &serde_json::Value,
+) -> &'static str {
+    if !requires_high_risk_approval(name, auth) {
+        "not_required"
+    } else if input
+        .get(HIGH_RISK_APPROVED_KEY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        "approved"
+    } else {
+        "denied"
+    }
+}
+
 pub fn schema_object(properties: serde_json::Value, required: &[&str]) -> serde_json::Value {
     json!({
         "type": "object",
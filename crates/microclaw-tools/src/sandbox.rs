@@ -7,7 +7,9 @@ use std::time::Duration;
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 
-use crate::command_runner::{build_command, shell_command};
+use crate::command_runner::{
+    argv_command, build_command, decode_output, shell_command_with, shell_join,
+};
 use serde::{Deserialize, Serialize};
 
 fn default_sandbox_mode() -> SandboxMode {
@@ -109,6 +111,73 @@ pub struct SandboxConfig {
     pub cpu_quota: Option<f64>,
     #[serde(default)]
     pub pids_limit: Option<u32>,
+    /// Replaces the built-in container invocation with a user-supplied command template.
+    #[serde(default)]
+    pub advanced: Option<SandboxAdvancedConfig>,
+}
+
+const TEMPLATE_PLACEHOLDERS: &[&str] = &["{image}", "{workdir}", "{mounts}", "{env}", "{cmd}"];
+const REQUIRED_TEMPLATE_PLACEHOLDERS: &[&str] = &["{image}", "{mounts}", "{cmd}"];
+
+/// Escape hatch for runtimes the built-in docker backend can't express (gVisor, Kata,
+/// site-specific wrappers). Each command runs as one invocation of the template.
+///
+/// The template is split on whitespace into argv; no shell is involved. Placeholders:
+/// - `{image}`: `sandbox.image`
+/// - `{workdir}`: the command's working directory (defaults to the mounted working dir)
+/// - `{mounts}`: standalone token, expands to `-v <path>:<path>:<rw|ro>` per mount
+/// - `{env}`: optional standalone token, expands to `-e <NAME>` per env var; values are
+///   passed through the invoked process's environment
+/// - `{cmd}`: the shell command, as a single argument (e.g. `sh -c {cmd}`)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxAdvancedConfig {
+    pub command_template: String,
+}
+
+impl SandboxAdvancedConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let tokens: Vec<&str> = self.command_template.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err("sandbox.advanced.command_template must not be empty".into());
+        }
+        if tokens[0].contains('{') {
+            return Err(
+                "sandbox.advanced.command_template must start with a program, not a placeholder"
+                    .into(),
+            );
+        }
+        for token in &tokens {
+            let mut rest = *token;
+            while let Some(start) = rest.find('{') {
+                let Some(len) = rest[start..].find('}') else {
+                    return Err(format!(
+                        "sandbox.advanced.command_template has an unclosed placeholder in '{token}'"
+                    ));
+                };
+                let placeholder = &rest[start..start + len + 1];
+                if !TEMPLATE_PLACEHOLDERS.contains(&placeholder) {
+                    return Err(format!(
+                        "sandbox.advanced.command_template has unknown placeholder {placeholder} (supported: {})",
+                        TEMPLATE_PLACEHOLDERS.join(", ")
+                    ));
+                }
+                if matches!(placeholder, "{mounts}" | "{env}") && *token != placeholder {
+                    return Err(format!(
+                        "sandbox.advanced.command_template: {placeholder} must be a standalone token"
+                    ));
+                }
+                rest = &rest[start + len + 1..];
+            }
+        }
+        for required in REQUIRED_TEMPLATE_PLACEHOLDERS {
+            if !tokens.iter().any(|t| t.contains(required)) {
+                return Err(format!(
+                    "sandbox.advanced.command_template must reference {required}"
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for SandboxConfig {
@@ -126,6 +195,7 @@ impl Default for SandboxConfig {
             memory_limit: None,
             cpu_quota: None,
             pids_limit: None,
+            advanced: None,
         }
     }
 }
@@ -136,8 +206,40 @@ pub struct SandboxExecOptions {
     pub working_dir: Option<PathBuf>,
     #[allow(clippy::zero_sized_map_values)]
     pub envs: HashMap<String, String>,
+    /// Shell for command strings. `None` uses `$SHELL` (or `/bin/sh`) on the host
+    /// and `sh` in containers.
+    pub shell: Option<String>,
 }
 
+/// Returned (inside `anyhow::Error`) when a command exceeds its timeout; carries
+/// whatever output was captured before it was killed.
+#[derive(Debug, Clone)]
+pub struct CommandTimedOut {
+    pub what: &'static str,
+    pub timeout: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandTimedOut {
+    pub fn produced_output(&self) -> bool {
+        !self.stdout.trim().is_empty() || !self.stderr.trim().is_empty()
+    }
+}
+
+impl std::fmt::Display for CommandTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} timed out after {} seconds",
+            self.what,
+            self.timeout.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for CommandTimedOut {}
+
 #[derive(Debug, Clone)]
 pub struct SandboxExecResult {
     pub stdout: String,
@@ -158,6 +260,21 @@ pub trait Sandbox: Send + Sync {
         command: &str,
         opts: &SandboxExecOptions,
     ) -> Result<SandboxExecResult>;
+    /// Run `argv` without a shell. The default quotes it into a command line for
+    /// backends that can only run shell strings.
+    async fn exec_argv(
+        &self,
+        session_key: &str,
+        argv: &[String],
+        opts: &SandboxExecOptions,
+    ) -> Result<SandboxExecResult> {
+        self.exec(session_key, &shell_join(argv), opts).await
+    }
+    /// Tear down whatever is left of the session after a host-side timeout, since
+    /// killing the client process does not stop work already started in a container.
+    async fn reap(&self, _session_key: &str) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub struct NoSandbox;
@@ -184,6 +301,15 @@ impl Sandbox for NoSandbox {
     ) -> Result<SandboxExecResult> {
         exec_host_command(command, opts).await
     }
+
+    async fn exec_argv(
+        &self,
+        _session_key: &str,
+        argv: &[String],
+        opts: &SandboxExecOptions,
+    ) -> Result<SandboxExecResult> {
+        exec_host_argv(argv, opts).await
+    }
 }
 
 /// An additional bind mount to expose inside the sandbox container.
@@ -193,7 +319,28 @@ pub struct ExtraMount {
     pub read_only: bool,
 }
 
+/// Extra seconds the in-container `timeout` allows beyond the host-side timeout, so
+/// the host normally reports the timeout and the container limit is only a backstop.
+const CONTAINER_TIMEOUT_GRACE_SECS: u64 = 2;
+/// Seconds `timeout` waits after SIGTERM before sending SIGKILL.
+const CONTAINER_TIMEOUT_KILL_AFTER_SECS: u64 = 5;
+/// Seconds `docker stop` waits before killing the container.
+const CONTAINER_STOP_TIMEOUT_SECS: u64 = 2;
+
+/// `timeout` prefix that bounds a command inside the container even if the
+/// host-side client is killed.
+fn container_timeout_prefix(timeout: Duration) -> Vec<String> {
+    let secs = timeout.as_secs().max(1) + CONTAINER_TIMEOUT_GRACE_SECS;
+    vec![
+        "timeout".to_string(),
+        "-k".to_string(),
+        CONTAINER_TIMEOUT_KILL_AFTER_SECS.to_string(),
+        secs.to_string(),
+    ]
+}
+
 pub struct DockerSandbox {
+    program: String,
     config: SandboxConfig,
     mount_dir: PathBuf,
     extra_mounts: Vec<ExtraMount>,
@@ -202,12 +349,19 @@ pub struct DockerSandbox {
 impl DockerSandbox {
     pub fn new(config: SandboxConfig, mount_dir: PathBuf, extra_mounts: Vec<ExtraMount>) -> Self {
         Self {
+            program: "docker".to_string(),
             config,
             mount_dir,
             extra_mounts,
         }
     }
 
+    #[cfg(test)]
+    fn with_program(mut self, program: impl Into<String>) -> Self {
+        self.program = program.into();
+        self
+    }
+
     fn container_name(&self, session_key: &str) -> String {
         format!(
             "{}-{}",
@@ -255,6 +409,38 @@ impl DockerSandbox {
         }
         args
     }
+
+    /// `docker exec` arguments up to and including the container name.
+    fn exec_args(&self, session_key: &str, opts: &SandboxExecOptions) -> Vec<String> {
+        let mut args = vec!["exec".to_string()];
+        if let Some(dir) = &opts.working_dir {
+            args.extend(["-w".to_string(), dir.display().to_string()]);
+        }
+        // Pass only names on the command line; docker reads the values from its own
+        // environment so they stay out of process listings.
+        for k in opts.envs.keys() {
+            args.extend(["-e".to_string(), k.clone()]);
+        }
+        args.push(self.container_name(session_key));
+        args.extend(container_timeout_prefix(opts.timeout));
+        args
+    }
+
+    async fn run_cli(&self, args: &[&str]) -> Result<()> {
+        let out = tokio::process::Command::new(&self.program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .output();
+        let out = tokio::time::timeout(Duration::from_secs(30), out)
+            .await
+            .with_context(|| format!("docker {} timed out", args[0]))?
+            .context("failed to run docker")?;
+        if !out.status.success() {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            bail!("docker {} failed: {}", args[0], stderr.trim());
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -265,7 +451,7 @@ impl Sandbox for DockerSandbox {
 
     async fn ensure_ready(&self, session_key: &str) -> Result<()> {
         let name = self.container_name(session_key);
-        let inspect = tokio::process::Command::new("docker")
+        let inspect = tokio::process::Command::new(&self.program)
             .args(["inspect", "--format", "{{.State.Running}}", &name])
             .output()
             .await;
@@ -297,7 +483,7 @@ impl Sandbox for DockerSandbox {
         args.push(self.config.image.clone());
         args.extend(["sleep".to_string(), "infinity".to_string()]);
 
-        let out = tokio::process::Command::new("docker")
+        let out = tokio::process::Command::new(&self.program)
             .args(&args)
             .output()
             .await
@@ -315,37 +501,130 @@ impl Sandbox for DockerSandbox {
         command: &str,
         opts: &SandboxExecOptions,
     ) -> Result<SandboxExecResult> {
-        let name = self.container_name(session_key);
-        let mut args = vec!["exec".to_string()];
+        let mut args = self.exec_args(session_key, opts);
+        let shell = opts.shell.as_deref().unwrap_or("sh");
+        args.extend([shell.to_string(), "-c".to_string(), command.to_string()]);
+        let mut cmd = tokio::process::Command::new(&self.program);
+        cmd.args(&args).envs(&opts.envs);
+        run_with_timeout(cmd, opts.timeout, "docker exec").await
+    }
 
-        if let Some(dir) = &opts.working_dir {
-            args.extend(["-w".to_string(), dir.display().to_string()]);
+    async fn exec_argv(
+        &self,
+        session_key: &str,
+        argv: &[String],
+        opts: &SandboxExecOptions,
+    ) -> Result<SandboxExecResult> {
+        if argv.is_empty() {
+            bail!("argv must not be empty");
         }
-        for (k, v) in &opts.envs {
-            args.extend(["-e".to_string(), format!("{k}={v}")]);
+        let mut args = self.exec_args(session_key, opts);
+        args.extend(argv.iter().cloned());
+        let mut cmd = tokio::process::Command::new(&self.program);
+        cmd.args(&args).envs(&opts.envs);
+        run_with_timeout(cmd, opts.timeout, "docker exec").await
+    }
+
+    /// Stops the session container (killing it if `docker stop` fails) and removes
+    /// it; the next command recreates it via `ensure_ready`.
+    async fn reap(&self, session_key: &str) -> Result<()> {
+        let name = self.container_name(session_key);
+        let stop_timeout = CONTAINER_STOP_TIMEOUT_SECS.to_string();
+        if let Err(err) = self.run_cli(&["stop", "-t", &stop_timeout, &name]).await {
+            tracing::warn!(container = %name, error = %err, "docker stop failed; killing container");
+            if let Err(err) = self.run_cli(&["kill", &name]).await {
+                tracing::warn!(container = %name, error = %err, "docker kill failed");
+            }
         }
-        args.push(name);
-        args.extend(["sh".to_string(), "-c".to_string(), command.to_string()]);
-        let child = tokio::process::Command::new("docker")
-            .args(&args)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .stdin(std::process::Stdio::null())
-            .spawn()
-            .context("failed to spawn docker exec")?;
-        match tokio::time::timeout(opts.timeout, child.wait_with_output()).await {
-            Ok(Ok(output)) => Ok(SandboxExecResult {
-                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-                exit_code: output.status.code().unwrap_or(-1),
-            }),
-            Ok(Err(e)) => bail!("docker exec failed: {e}"),
-            Err(_) => bail!(
-                "docker exec timed out after {} seconds",
-                opts.timeout.as_secs()
-            ),
+        self.run_cli(&["rm", "-f", &name]).await
+    }
+}
+
+/// Runs each command through `sandbox.advanced.command_template` instead of a
+/// long-lived docker container.
+pub struct TemplateSandbox {
+    config: SandboxConfig,
+    template: String,
+    mount_dir: PathBuf,
+    extra_mounts: Vec<ExtraMount>,
+}
+
+impl TemplateSandbox {
+    pub fn new(
+        config: SandboxConfig,
+        template: String,
+        mount_dir: PathBuf,
+        extra_mounts: Vec<ExtraMount>,
+    ) -> Self {
+        Self {
+            config,
+            template,
+            mount_dir,
+            extra_mounts,
         }
     }
+
+    fn render_args(&self, command: &str, opts: &SandboxExecOptions) -> Vec<String> {
+        let workdir = opts
+            .working_dir
+            .as_deref()
+            .unwrap_or(&self.mount_dir)
+            .display()
+            .to_string();
+        let mut args = Vec::new();
+        for token in self.template.split_whitespace() {
+            match token {
+                "{mounts}" => {
+                    let mount = self.mount_dir.display().to_string();
+                    args.extend(["-v".to_string(), format!("{mount}:{mount}:rw")]);
+                    for em in &self.extra_mounts {
+                        let p = em.host_path.display().to_string();
+                        let mode = if em.read_only { "ro" } else { "rw" };
+                        args.extend(["-v".to_string(), format!("{p}:{p}:{mode}")]);
+                    }
+                }
+                "{env}" => {
+                    for k in opts.envs.keys() {
+                        args.extend(["-e".to_string(), k.clone()]);
+                    }
+                }
+                // Substitute `{cmd}` last so placeholders inside the command stay literal.
+                _ => args.push(
+                    token
+                        .replace("{image}", &self.config.image)
+                        .replace("{workdir}", &workdir)
+                        .replace("{cmd}", command),
+                ),
+            }
+        }
+        args
+    }
+}
+
+#[async_trait]
+impl Sandbox for TemplateSandbox {
+    fn backend_name(&self) -> &'static str {
+        "template"
+    }
+
+    async fn ensure_ready(&self, _session_key: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn exec(
+        &self,
+        _session_key: &str,
+        command: &str,
+        opts: &SandboxExecOptions,
+    ) -> Result<SandboxExecResult> {
+        let args = self.render_args(command, opts);
+        let Some((program, rest)) = args.split_first() else {
+            bail!("sandbox.advanced.command_template is empty");
+        };
+        let mut cmd = tokio::process::Command::new(program);
+        cmd.args(rest).envs(&opts.envs);
+        run_with_timeout(cmd, opts.timeout, "sandbox command").await
+    }
 }
 
 pub struct SandboxRouter {
@@ -357,8 +636,18 @@ pub struct SandboxRouter {
 impl SandboxRouter {
     pub fn new(config: SandboxConfig, working_dir: &Path, extra_mounts: Vec<ExtraMount>) -> Self {
         let mount_dir = resolve_mount_dir(working_dir, &config);
-        let backend: Arc<dyn Sandbox> = match config.backend {
-            SandboxBackend::Auto | SandboxBackend::Docker => {
+        let template = config
+            .advanced
+            .as_ref()
+            .map(|advanced| advanced.command_template.clone());
+        let backend: Arc<dyn Sandbox> = match (template, config.backend) {
+            (Some(template), _) => Arc::new(TemplateSandbox::new(
+                config.clone(),
+                template,
+                mount_dir,
+                extra_mounts,
+            )),
+            (None, SandboxBackend::Auto | SandboxBackend::Docker) => {
                 if docker_available() {
                     Arc::new(DockerSandbox::new(config.clone(), mount_dir, extra_mounts))
                 } else {
@@ -394,14 +683,10 @@ impl SandboxRouter {
         self.backend.is_real()
     }
 
-    pub async fn exec(
-        &self,
-        session_key: &str,
-        command: &str,
-        opts: &SandboxExecOptions,
-    ) -> Result<SandboxExecResult> {
+    /// True when commands should run on the host instead of the backend.
+    fn runs_on_host(&self) -> Result<bool> {
         if self.config.mode == SandboxMode::Off {
-            return exec_host_command(command, opts).await;
+            return Ok(true);
         }
         if !self.backend.is_real() {
             if self.config.require_runtime {
@@ -410,10 +695,57 @@ impl SandboxRouter {
             if !self.warned_missing_runtime.swap(true, Ordering::Relaxed) {
                 tracing::warn!("sandbox enabled but docker unavailable, falling back to host");
             }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    pub async fn exec(
+        &self,
+        session_key: &str,
+        command: &str,
+        opts: &SandboxExecOptions,
+    ) -> Result<SandboxExecResult> {
+        if self.runs_on_host()? {
             return exec_host_command(command, opts).await;
         }
         self.backend.ensure_ready(session_key).await?;
-        self.backend.exec(session_key, command, opts).await
+        let result = self.backend.exec(session_key, command, opts).await;
+        self.reap_on_timeout(session_key, result).await
+    }
+
+    /// Like [`SandboxRouter::exec`], running `argv` without a shell.
+    pub async fn exec_argv(
+        &self,
+        session_key: &str,
+        argv: &[String],
+        opts: &SandboxExecOptions,
+    ) -> Result<SandboxExecResult> {
+        if self.runs_on_host()? {
+            return exec_host_argv(argv, opts).await;
+        }
+        self.backend.ensure_ready(session_key).await?;
+        let result = self.backend.exec_argv(session_key, argv, opts).await;
+        self.reap_on_timeout(session_key, result).await
+    }
+
+    async fn reap_on_timeout(
+        &self,
+        session_key: &str,
+        result: Result<SandboxExecResult>,
+    ) -> Result<SandboxExecResult> {
+        if let Err(err) = &result {
+            if err.downcast_ref::<CommandTimedOut>().is_some() {
+                if let Err(reap_err) = self.backend.reap(session_key).await {
+                    tracing::warn!(
+                        session = session_key,
+                        error = %reap_err,
+                        "failed to reap sandbox after timeout"
+                    );
+                }
+            }
+        }
+        result
     }
 }
 
@@ -421,23 +753,104 @@ pub async fn exec_host_command(
     command: &str,
     opts: &SandboxExecOptions,
 ) -> Result<SandboxExecResult> {
-    let spec = shell_command(command);
-    let mut cmd = build_command(&spec, opts.working_dir.as_deref());
+    let spec = shell_command_with(opts.shell.as_deref(), command);
+    run_host_spec(&spec, opts, "command").await
+}
+
+/// Run `argv` on the host without a shell: no word splitting, globbing or expansion.
+pub async fn exec_host_argv(
+    argv: &[String],
+    opts: &SandboxExecOptions,
+) -> Result<SandboxExecResult> {
+    let Some(spec) = argv_command(argv) else {
+        bail!("argv must not be empty");
+    };
+    run_host_spec(&spec, opts, "command").await
+}
+
+async fn run_host_spec(
+    spec: &crate::command_runner::CommandSpec,
+    opts: &SandboxExecOptions,
+    what: &'static str,
+) -> Result<SandboxExecResult> {
+    let mut cmd = build_command(spec, opts.working_dir.as_deref());
     for (k, v) in &opts.envs {
         cmd.env(k, v);
     }
+    run_with_timeout(cmd, opts.timeout, what).await
+}
+
+/// Run `cmd` with stdin on /dev/null, killing it after `timeout`. Output is read
+/// incrementally so a timeout still reports what the command printed.
+async fn run_with_timeout(
+    mut cmd: tokio::process::Command,
+    timeout: Duration,
+    what: &'static str,
+) -> Result<SandboxExecResult> {
+    use tokio::io::AsyncReadExt;
+
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
     cmd.stdin(std::process::Stdio::null());
-    let child = cmd.spawn().context("failed to start shell command")?;
-    match tokio::time::timeout(opts.timeout, child.wait_with_output()).await {
-        Ok(Ok(output)) => Ok(SandboxExecResult {
-            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
-            exit_code: output.status.code().unwrap_or(-1),
-        }),
-        Ok(Err(e)) => bail!("failed to run command: {e}"),
-        Err(_) => bail!("command timed out after {} seconds", opts.timeout.as_secs()),
+    cmd.kill_on_drop(true);
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to start {what}"))?;
+
+    fn collect<R>(
+        reader: Option<R>,
+    ) -> (Arc<std::sync::Mutex<Vec<u8>>>, tokio::task::JoinHandle<()>)
+    where
+        R: tokio::io::AsyncRead + Unpin + Send + 'static,
+    {
+        let buf = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = buf.clone();
+        let task = tokio::spawn(async move {
+            let Some(mut reader) = reader else {
+                return;
+            };
+            let mut chunk = [0u8; 8192];
+            while let Ok(n) = reader.read(&mut chunk).await {
+                if n == 0 {
+                    break;
+                }
+                sink.lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .extend_from_slice(&chunk[..n]);
+            }
+        });
+        (buf, task)
+    }
+    let text = |buf: &Arc<std::sync::Mutex<Vec<u8>>>| {
+        decode_output(&buf.lock().unwrap_or_else(|e| e.into_inner()))
+    };
+
+    let (stdout, stdout_task) = collect(child.stdout.take());
+    let (stderr, stderr_task) = collect(child.stderr.take());
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) => {
+            let _ = stdout_task.await;
+            let _ = stderr_task.await;
+            Ok(SandboxExecResult {
+                stdout: text(&stdout),
+                stderr: text(&stderr),
+                exit_code: status.code().unwrap_or(-1),
+            })
+        }
+        Ok(Err(e)) => bail!("failed to run {what}: {e}"),
+        Err(_) => {
+            let _ = child.kill().await;
+            // Background children may still hold the pipes open; keep what was read.
+            stdout_task.abort();
+            stderr_task.abort();
+            Err(CommandTimedOut {
+                what,
+                timeout,
+                stdout: text(&stdout),
+                stderr: text(&stderr),
+            }
+            .into())
+        }
     }
 }
 
@@ -624,6 +1037,7 @@ mod tests {
             timeout: Duration::from_secs(2),
             working_dir: None,
             envs: HashMap::new(),
+            shell: None,
         };
         let out = router.exec("chat-1", "printf microclaw-smoke", &opts).await;
         let out = out.expect("expected host fallback execution");
@@ -644,10 +1058,248 @@ mod tests {
             timeout: Duration::from_secs(2),
             working_dir: None,
             envs: HashMap::new(),
+            shell: None,
         };
         let err = router.exec("chat-1", "echo hi", &opts).await.unwrap_err();
         assert!(err
             .to_string()
             .contains("sandbox is enabled but no docker runtime is available"));
     }
+
+    #[test]
+    fn test_advanced_template_validation() {
+        let ok = SandboxAdvancedConfig {
+            command_template: "runsc-wrap {env} {mounts} -w {workdir} {image} sh -c {cmd}".into(),
+        };
+        assert!(ok.validate().is_ok());
+
+        for (template, expected) in [
+            ("", "must not be empty"),
+            ("{image} {mounts} {cmd}", "must start with a program"),
+            ("docker run {mounts} sh -c {cmd}", "must reference {image}"),
+            (
+                "docker run {mounts} {image} {command}",
+                "unknown placeholder {command}",
+            ),
+            (
+                "docker run --mount={mounts} {image} {cmd}",
+                "standalone token",
+            ),
+            ("docker run {mounts} {image {cmd}", "unclosed placeholder"),
+        ] {
+            let err = SandboxAdvancedConfig {
+                command_template: template.into(),
+            }
+            .validate()
+            .unwrap_err();
+            assert!(err.contains(expected), "{template}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_template_sandbox_renders_placeholders() {
+        let sandbox = TemplateSandbox::new(
+            SandboxConfig::default(),
+            "runsc-wrap {env} {mounts} -w {workdir} {image} sh -c {cmd}".into(),
+            PathBuf::from("/work"),
+            vec![ExtraMount {
+                host_path: PathBuf::from("/skills"),
+                read_only: true,
+            }],
+        );
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(2),
+            working_dir: None,
+            envs: HashMap::from([("TOKEN".to_string(), "secret".to_string())]),
+            shell: None,
+        };
+        let args = sandbox.render_args("echo {image} && ls", &opts);
+        assert_eq!(
+            args,
+            [
+                "runsc-wrap",
+                "-e",
+                "TOKEN",
+                "-v",
+                "/work:/work:rw",
+                "-v",
+                "/skills:/skills:ro",
+                "-w",
+                "/work",
+                "ubuntu:25.10",
+                "sh",
+                "-c",
+                "echo {image} && ls",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_router_runs_commands_through_advanced_template() {
+        let cfg = SandboxConfig {
+            mode: SandboxMode::All,
+            image: "img".into(),
+            // `sh -c <cmd> <arg0> <args...>`: image and mounts land in $0 and $@.
+            advanced: Some(SandboxAdvancedConfig {
+                command_template: "sh -c {cmd} {image} {mounts}".into(),
+            }),
+            ..SandboxConfig::default()
+        };
+        let router = SandboxRouter::new(cfg, Path::new("./tmp"), vec![]);
+        assert_eq!(router.backend_name(), "template");
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(2),
+            working_dir: None,
+            envs: HashMap::new(),
+            shell: None,
+        };
+        let out = router
+            .exec("chat-1", "printf '%s %s' \"$0\" \"$1\"", &opts)
+            .await
+            .unwrap();
+        assert_eq!(out.exit_code, 0);
+        assert_eq!(out.stdout, "img -v");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_router_exec_argv_skips_shell_on_host_fallback() {
+        let router =
+            SandboxRouter::with_backend_for_tests(SandboxConfig::default(), Arc::new(NoSandbox));
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(2),
+            working_dir: None,
+            envs: HashMap::new(),
+            shell: None,
+        };
+        let argv = ["printf", "%s|%s", "$HOME", "*"].map(String::from);
+        let out = router.exec_argv("chat-1", &argv, &opts).await.unwrap();
+        assert_eq!(out.stdout, "$HOME|*");
+        let err = exec_host_argv(&[], &opts).await.unwrap_err();
+        assert!(err.to_string().contains("argv must not be empty"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_template_sandbox_quotes_argv_into_cmd() {
+        let cfg = SandboxConfig {
+            mode: SandboxMode::All,
+            advanced: Some(SandboxAdvancedConfig {
+                command_template: "sh -c {cmd} {image}".into(),
+            }),
+            ..SandboxConfig::default()
+        };
+        let router = SandboxRouter::new(cfg, Path::new("./tmp"), vec![]);
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(2),
+            working_dir: None,
+            envs: HashMap::new(),
+            shell: None,
+        };
+        let argv = ["printf", "%s", "a b; echo no"].map(String::from);
+        let out = router.exec_argv("chat-1", &argv, &opts).await.unwrap();
+        assert_eq!(out.stdout, "a b; echo no");
+    }
+
+    #[test]
+    fn test_docker_exec_args_bound_command_inside_container() {
+        let sandbox = DockerSandbox::new(SandboxConfig::default(), PathBuf::from("/work"), vec![]);
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(30),
+            working_dir: Some(PathBuf::from("/work")),
+            envs: HashMap::new(),
+            shell: None,
+        };
+        assert_eq!(
+            sandbox.exec_args("chat-1", &opts),
+            [
+                "exec",
+                "-w",
+                "/work",
+                "microclaw-sandbox-chat-1",
+                "timeout",
+                "-k",
+                "5",
+                "32",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_router_reaps_container_after_forced_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A fake docker CLI that tracks running containers in a file and hangs on exec.
+        let dir = std::env::temp_dir().join(format!("microclaw_sandbox_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = dir.join("containers");
+        let log = dir.join("calls");
+        std::fs::write(&state, "").unwrap();
+        let script = dir.join("docker");
+        std::fs::write(
+            &script,
+            format!(
+                r#"#!/bin/sh
+echo "$*" >> '{log}'
+for last; do :; done
+case "$1" in
+  inspect) grep -qx "$last" '{state}' && echo true ;;
+  run) echo "$4" >> '{state}' ;;
+  exec) exec sleep 30 ;;
+  stop) exit 1 ;;
+  kill) ;;
+  rm) grep -vx "$last" '{state}' > '{state}.tmp'; mv '{state}.tmp' '{state}' ;;
+esac
+"#,
+                log = log.display(),
+                state = state.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let cfg = SandboxConfig {
+            mode: SandboxMode::All,
+            ..SandboxConfig::default()
+        };
+        let backend = DockerSandbox::new(cfg.clone(), dir.clone(), vec![])
+            .with_program(script.display().to_string());
+        let router = SandboxRouter::with_backend_for_tests(cfg, Arc::new(backend));
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(1),
+            working_dir: None,
+            envs: HashMap::new(),
+            shell: None,
+        };
+        let err = router.exec("chat-1", "sleep 30", &opts).await.unwrap_err();
+        assert!(err.downcast_ref::<CommandTimedOut>().is_some());
+
+        assert_eq!(std::fs::read_to_string(&state).unwrap().trim(), "");
+        let calls = std::fs::read_to_string(&log).unwrap();
+        assert!(calls.contains("exec microclaw-sandbox-chat-1 timeout -k 5 3 sh -c sleep 30"));
+        assert!(calls.contains("stop -t 2 microclaw-sandbox-chat-1"));
+        assert!(calls.contains("kill microclaw-sandbox-chat-1"));
+        assert!(calls.contains("rm -f microclaw-sandbox-chat-1"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_host_timeout_keeps_partial_output() {
+        let opts = SandboxExecOptions {
+            timeout: Duration::from_secs(1),
+            working_dir: None,
+            envs: HashMap::new(),
+            shell: None,
+        };
+        let err = exec_host_command("echo started; sleep 5", &opts)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out after 1 seconds"));
+        let timed_out = err.downcast_ref::<CommandTimedOut>().unwrap();
+        assert!(timed_out.produced_output());
+        assert_eq!(timed_out.stdout.trim(), "started");
+    }
 }
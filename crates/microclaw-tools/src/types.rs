@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
 
@@ -37,9 +40,75 @@ impl<'de> Deserialize<'de> for WorkingDirIsolation {
     }
 }
 
+/// Working-dir isolation resolved per caller: control chats and regular chats
+/// can use different strategies in one deployment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkingDirIsolationPolicy {
+    pub control_chats: WorkingDirIsolation,
+    pub default: WorkingDirIsolation,
+}
+
+impl WorkingDirIsolationPolicy {
+    pub fn for_chat(&self, is_control_chat: bool) -> WorkingDirIsolation {
+        if is_control_chat {
+            self.control_chats
+        } else {
+            self.default
+        }
+    }
+}
+
+/// A single isolation value is a preset applied to every chat.
+impl From<WorkingDirIsolation> for WorkingDirIsolationPolicy {
+    fn from(isolation: WorkingDirIsolation) -> Self {
+        Self {
+            control_chats: isolation,
+            default: isolation,
+        }
+    }
+}
+
+/// Roots that isolation subdivides: the global `working_dir` plus per-channel
+/// overrides keyed by registry channel name (`feishu` or `feishu.<account>`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkingDirRoots {
+    pub default: PathBuf,
+    pub channels: HashMap<String, PathBuf>,
+}
+
+impl WorkingDirRoots {
+    pub fn for_channel(&self, channel: &str) -> &Path {
+        self.channels
+            .get(channel)
+            .map(PathBuf::as_path)
+            .unwrap_or(&self.default)
+    }
+}
+
+impl From<&str> for WorkingDirRoots {
+    fn from(root: &str) -> Self {
+        PathBuf::from(root).into()
+    }
+}
+
+impl From<&Path> for WorkingDirRoots {
+    fn from(root: &Path) -> Self {
+        root.to_path_buf().into()
+    }
+}
+
+impl From<PathBuf> for WorkingDirRoots {
+    fn from(root: PathBuf) -> Self {
+        Self {
+            default: root,
+            channels: HashMap::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::WorkingDirIsolation;
+    use super::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 
     #[test]
     fn test_deserialize_bool_true_as_chat() {
@@ -58,4 +127,34 @@ mod tests {
         let v: WorkingDirIsolation = serde_json::from_str("\"chat\"").unwrap();
         assert!(matches!(v, WorkingDirIsolation::Chat));
     }
+
+    #[test]
+    fn test_policy_preset_and_mixed() {
+        let preset = WorkingDirIsolationPolicy::from(WorkingDirIsolation::Chat);
+        assert_eq!(preset.for_chat(true), WorkingDirIsolation::Chat);
+        assert_eq!(preset.for_chat(false), WorkingDirIsolation::Chat);
+
+        let mixed = WorkingDirIsolationPolicy {
+            control_chats: WorkingDirIsolation::Shared,
+            default: WorkingDirIsolation::Chat,
+        };
+        assert_eq!(mixed.for_chat(true), WorkingDirIsolation::Shared);
+        assert_eq!(mixed.for_chat(false), WorkingDirIsolation::Chat);
+    }
+
+    #[test]
+    fn test_roots_fall_back_to_default() {
+        let mut roots = WorkingDirRoots::from("/srv/work");
+        roots
+            .channels
+            .insert("feishu.sales".into(), "/mnt/sales".into());
+        assert_eq!(
+            roots.for_channel("feishu.sales"),
+            std::path::Path::new("/mnt/sales")
+        );
+        assert_eq!(
+            roots.for_channel("feishu"),
+            std::path::Path::new("/srv/work")
+        );
+    }
 }
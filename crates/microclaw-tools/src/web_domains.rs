@@ -0,0 +1,142 @@
+//! Domain allow/deny policy shared by `web_fetch` and `web_search`.
+
+use reqwest::Url;
+
+use crate::web_fetch::{host_matches_rule, normalize_host_candidate};
+use crate::web_search::WebSearchResult;
+
+/// Domains the agent may reach. A non-empty allowlist admits only the listed
+/// domains (and their subdomains); the denylist always wins over it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebDomainPolicy {
+    allowlist: Vec<String>,
+    denylist: Vec<String>,
+}
+
+impl WebDomainPolicy {
+    pub fn new(allowlist: &[String], denylist: &[String]) -> Self {
+        Self {
+            allowlist: normalize_domains(allowlist),
+            denylist: normalize_domains(denylist),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allowlist.is_empty() && self.denylist.is_empty()
+    }
+
+    /// `Err` carries the reason the host is refused.
+    pub fn check_host(&self, host: &str) -> Result<(), String> {
+        let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+        if let Some(rule) = self.denylist.iter().find(|r| host_matches_rule(&host, r)) {
+            return Err(format!(
+                "domain '{host}' is blocked by web_domain_denylist (rule '{rule}')"
+            ));
+        }
+        if !self.allowlist.is_empty() && !self.allowlist.iter().any(|r| host_matches_rule(&host, r))
+        {
+            return Err(format!(
+                "domain '{host}' is not in web_domain_allowlist (allowed: {})",
+                self.allowlist.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn check_url(&self, raw_url: &str) -> Result<(), String> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let parsed = Url::parse(raw_url).map_err(|e| format!("invalid URL: {e}"))?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| "URL must include a host".to_string())?;
+        self.check_host(host)
+    }
+
+    /// Splits search results into those the policy admits and the distinct
+    /// domains of the ones it dropped.
+    pub fn filter_results(
+        &self,
+        results: Vec<WebSearchResult>,
+    ) -> (Vec<WebSearchResult>, Vec<String>) {
+        if self.is_empty() {
+            return (results, Vec::new());
+        }
+        let mut kept = Vec::new();
+        let mut omitted: Vec<String> = Vec::new();
+        for result in results {
+            let host = Url::parse(&result.url)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_ascii_lowercase));
+            match host {
+                Some(host) if self.check_host(&host).is_ok() => kept.push(result),
+                host => {
+                    let domain = host.unwrap_or_else(|| result.url.clone());
+                    if !omitted.contains(&domain) {
+                        omitted.push(domain);
+                    }
+                }
+            }
+        }
+        (kept, omitted)
+    }
+}
+
+fn normalize_domains(domains: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for domain in domains.iter().filter_map(|d| normalize_host_candidate(d)) {
+        if !out.contains(&domain) {
+            out.push(domain);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(url: &str) -> WebSearchResult {
+        WebSearchResult::new("t".into(), url.into(), String::new(), None)
+    }
+
+    #[test]
+    fn test_allowlist_admits_listed_domains_and_subdomains() {
+        let policy = WebDomainPolicy::new(&[" *.Docs.RS ".into(), "rust-lang.org".into()], &[]);
+        assert!(policy.check_url("https://docs.rs/serde").is_ok());
+        assert!(policy.check_url("https://blog.rust-lang.org/x").is_ok());
+        let err = policy.check_url("https://example.com/").unwrap_err();
+        assert!(err.contains("not in web_domain_allowlist"));
+        assert!(err.contains("docs.rs, rust-lang.org"));
+        assert!(policy.check_url("https://notdocs.rs/").is_err());
+    }
+
+    #[test]
+    fn test_denylist_wins_over_allowlist() {
+        let policy = WebDomainPolicy::new(&["example.com".into()], &["ads.example.com".into()]);
+        assert!(policy.check_url("https://www.example.com/").is_ok());
+        let err = policy.check_url("https://x.ads.example.com/").unwrap_err();
+        assert!(err.contains("blocked by web_domain_denylist"));
+
+        let deny_only = WebDomainPolicy::new(&[], &["pinterest.com".into()]);
+        assert!(deny_only.check_url("https://anything.org").is_ok());
+        assert!(deny_only
+            .check_url("https://www.pinterest.com/pin")
+            .is_err());
+        assert!(WebDomainPolicy::default().check_url("not a url").is_ok());
+    }
+
+    #[test]
+    fn test_filter_results_reports_omitted_domains() {
+        let policy = WebDomainPolicy::new(&[], &["spam.example".into()]);
+        let (kept, omitted) = policy.filter_results(vec![
+            result("https://good.example/a"),
+            result("https://spam.example/1"),
+            result("https://www.spam.example/2"),
+        ]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].url, "https://good.example/a");
+        assert_eq!(omitted, vec!["spam.example", "www.spam.example"]);
+    }
+}
@@ -7,7 +7,11 @@ use reqwest::Url;
 use tracing::warn;
 
 use crate::web_content_validation::{validate_web_content_with_config, WebContentValidationConfig};
-use crate::web_html::{extract_primary_html, html_to_text};
+use crate::web_domains::WebDomainPolicy;
+use crate::web_html::{
+    extract_page_metadata, extract_primary_html, html_to_markdown, html_to_text,
+    looks_like_article, PageMetadata, MARKDOWN_TAGS,
+};
 
 fn http_client(timeout_secs: u64) -> reqwest::Client {
     static CLIENTS: OnceLock<Mutex<HashMap<u64, reqwest::Client>>> = OnceLock::new();
@@ -26,20 +30,27 @@ fn http_client(timeout_secs: u64) -> reqwest::Client {
     client
 }
 
-fn http_client_no_redirect(timeout_secs: u64) -> reqwest::Client {
-    static CLIENTS: OnceLock<Mutex<HashMap<u64, reqwest::Client>>> = OnceLock::new();
+pub(crate) fn http_client_no_redirect(
+    timeout_secs: u64,
+    proxy: &WebFetchProxyConfig,
+) -> reqwest::Client {
+    type ClientKey = (u64, WebFetchProxyConfig);
+    static CLIENTS: OnceLock<Mutex<HashMap<ClientKey, reqwest::Client>>> = OnceLock::new();
     let cache = CLIENTS.get_or_init(|| Mutex::new(HashMap::new()));
     let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
-    if let Some(client) = cache.get(&timeout_secs) {
+    let key = (timeout_secs, proxy.clone());
+    if let Some(client) = cache.get(&key) {
         return client.clone();
     }
-    let client = reqwest::Client::builder()
+    let builder = reqwest::Client::builder()
         .timeout(Duration::from_secs(timeout_secs))
         .redirect(reqwest::redirect::Policy::none())
-        .user_agent("MicroClaw/1.0")
+        .user_agent("MicroClaw/1.0");
+    let client = proxy
+        .apply(builder)
         .build()
         .expect("failed to build HTTP client");
-    cache.insert(timeout_secs, client.clone());
+    cache.insert(key, client.clone());
     client
 }
 
@@ -96,6 +107,10 @@ pub struct WebFetchUrlValidationConfig {
     pub denylist_hosts: Vec<String>,
     #[serde(default)]
     pub feed_sync: WebFetchFeedSyncConfig,
+    /// `web_domain_allowlist`/`web_domain_denylist`, checked on the initial URL
+    /// and every redirect hop even when `enabled` is off.
+    #[serde(skip)]
+    pub domain_policy: WebDomainPolicy,
 }
 
 struct FeedCacheEntry {
@@ -103,6 +118,326 @@ struct FeedCacheEntry {
     entries: Vec<String>,
 }
 
+/// Response cache for `web_fetch`, keyed by requested URL.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WebFetchCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds a cached page is served without contacting the origin.
+    #[serde(default = "default_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    #[serde(default = "default_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for WebFetchCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_cache_ttl_secs(),
+            max_entries: default_cache_max_entries(),
+        }
+    }
+}
+
+impl WebFetchCacheConfig {
+    pub fn normalize(&mut self) {
+        if self.max_entries == 0 {
+            self.max_entries = default_cache_max_entries();
+        }
+    }
+}
+
+/// How `web_fetch` renders HTML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebFetchRenderMode {
+    /// Markdown for article-like pages, plain text otherwise.
+    #[default]
+    Auto,
+    Markdown,
+    Text,
+}
+
+impl WebFetchRenderMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(WebFetchRenderMode::Auto),
+            "markdown" => Some(WebFetchRenderMode::Markdown),
+            "text" => Some(WebFetchRenderMode::Text),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebFetchRenderMode::Auto => "auto",
+            WebFetchRenderMode::Markdown => "markdown",
+            WebFetchRenderMode::Text => "text",
+        }
+    }
+}
+
+/// Markdown rendering for `web_fetch`. Scripts, styles and SVG are always dropped.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WebFetchMarkdownConfig {
+    #[serde(default)]
+    pub mode: WebFetchRenderMode,
+    /// Elements kept as Markdown: `h1`-`h6`, `ul`, `ol`, `li`, `blockquote`, `a`.
+    #[serde(default = "default_preserve_tags")]
+    pub preserve_tags: Vec<String>,
+}
+
+impl Default for WebFetchMarkdownConfig {
+    fn default() -> Self {
+        Self {
+            mode: WebFetchRenderMode::default(),
+            preserve_tags: default_preserve_tags(),
+        }
+    }
+}
+
+impl WebFetchMarkdownConfig {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in &self.preserve_tags {
+            let tag = tag
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_ascii_lowercase();
+            if !MARKDOWN_TAGS.contains(&tag.as_str()) {
+                return Err(format!(
+                    "web_fetch_markdown.preserve_tags: unsupported tag '{tag}', expected any of {}",
+                    MARKDOWN_TAGS.join(", ")
+                ));
+            }
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        self.preserve_tags = tags;
+        Ok(())
+    }
+
+    /// Render the primary content of `html` per `mode`.
+    pub fn render(&self, html: &str, mode: WebFetchRenderMode) -> String {
+        let primary = extract_primary_html(html);
+        let markdown = match mode {
+            WebFetchRenderMode::Auto => looks_like_article(html),
+            WebFetchRenderMode::Markdown => true,
+            WebFetchRenderMode::Text => false,
+        };
+        if markdown {
+            html_to_markdown(primary, &self.preserve_tags)
+        } else {
+            html_to_text(primary)
+        }
+    }
+}
+
+/// Outbound proxy for `web_fetch`.
+///
+/// Precedence: a configured `url` routes every request except `no_proxy` hosts.
+/// Without `url`, the standard `HTTP_PROXY` / `HTTPS_PROXY` / `ALL_PROXY` /
+/// `NO_PROXY` environment variables apply unless `use_env` is false.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct WebFetchProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` (DNS resolved by the proxy).
+    /// Credentials may be embedded as `user:pass@host`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Hosts reached directly when `url` is set: hostnames (matching subdomains),
+    /// IPs or CIDRs. Empty falls back to `NO_PROXY` when `use_env` is on.
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub use_env: bool,
+}
+
+impl Default for WebFetchProxyConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            no_proxy: Vec::new(),
+            use_env: default_enabled(),
+        }
+    }
+}
+
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+impl WebFetchProxyConfig {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.url = self
+            .url
+            .as_deref()
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string);
+        if let Some(url) = self.url.as_deref() {
+            let parsed = Url::parse(url)
+                .map_err(|e| format!("web_fetch_proxy.url: invalid URL '{url}': {e}"))?;
+            if !PROXY_SCHEMES.contains(&parsed.scheme()) {
+                return Err(format!(
+                    "web_fetch_proxy.url: unsupported scheme '{}', expected one of {}",
+                    parsed.scheme(),
+                    PROXY_SCHEMES.join(", ")
+                ));
+            }
+        }
+        self.no_proxy = self
+            .no_proxy
+            .iter()
+            .map(|h| h.trim().to_ascii_lowercase())
+            .filter(|h| !h.is_empty())
+            .collect();
+        Ok(())
+    }
+
+    fn no_proxy_rules(&self) -> Option<reqwest::NoProxy> {
+        if !self.no_proxy.is_empty() {
+            reqwest::NoProxy::from_string(&self.no_proxy.join(","))
+        } else if self.use_env {
+            reqwest::NoProxy::from_env()
+        } else {
+            None
+        }
+    }
+
+    fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        match self.url.as_deref() {
+            Some(url) => match reqwest::Proxy::all(url) {
+                Ok(proxy) => builder.proxy(proxy.no_proxy(self.no_proxy_rules())),
+                Err(e) => {
+                    warn!("Ignoring invalid web_fetch proxy '{}': {}", url, e);
+                    builder
+                }
+            },
+            // reqwest reads the proxy environment variables by default.
+            None if self.use_env => builder,
+            None => builder.no_proxy(),
+        }
+    }
+}
+
+/// Per-host pacing for `web_fetch`: a token bucket of `burst` requests that
+/// refills one request every `min_interval_ms`. Requests over the limit wait
+/// for their turn; other hosts are unaffected.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WebFetchHostRateConfig {
+    /// Spacing between requests to one host once the burst is used. 0 disables.
+    #[serde(default = "default_host_min_interval_ms")]
+    pub min_interval_ms: u64,
+    #[serde(default = "default_host_burst")]
+    pub burst: u32,
+}
+
+impl Default for WebFetchHostRateConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: default_host_min_interval_ms(),
+            burst: default_host_burst(),
+        }
+    }
+}
+
+impl WebFetchHostRateConfig {
+    pub fn disabled() -> Self {
+        Self {
+            min_interval_ms: 0,
+            ..Self::default()
+        }
+    }
+
+    pub fn normalize(&mut self) {
+        self.burst = self.burst.max(1);
+    }
+}
+
+/// Hosts tracked before expired pacing state is pruned.
+const HOST_RATE_MAX_TRACKED: usize = 1024;
+
+/// Per host, when the bucket is next full ("theoretical arrival time").
+fn host_rate_registry() -> &'static Mutex<HashMap<String, Instant>> {
+    static HOSTS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+    HOSTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Reserve a request slot for `host` and return how long to wait for it.
+fn reserve_host_slot(host: &str, rate: &WebFetchHostRateConfig, now: Instant) -> Duration {
+    if rate.min_interval_ms == 0 {
+        return Duration::ZERO;
+    }
+    let interval = Duration::from_millis(rate.min_interval_ms);
+    let tolerance = interval * rate.burst.max(1).saturating_sub(1);
+    let mut hosts = host_rate_registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if hosts.len() > HOST_RATE_MAX_TRACKED {
+        hosts.retain(|_, full_at| *full_at > now);
+    }
+    let full_at = hosts.entry(host.to_string()).or_insert(now);
+    let base = (*full_at).max(now);
+    let start = base.checked_sub(tolerance).unwrap_or(now).max(now);
+    *full_at = base + interval;
+    start - now
+}
+
+/// Wait for a request slot for `url`'s host; returns the time spent waiting.
+pub(crate) async fn wait_for_host_slot(url: &Url, rate: &WebFetchHostRateConfig) -> Duration {
+    let Some(host) = url.host_str() else {
+        return Duration::ZERO;
+    };
+    let host = host.to_ascii_lowercase();
+    let wait = reserve_host_slot(&host, rate, Instant::now());
+    if !wait.is_zero() {
+        tracing::info!(host = %host, wait_ms = wait.as_millis() as u64, "web_fetch delayed by per-host rate limit");
+        tokio::time::sleep(wait).await;
+    }
+    wait
+}
+
+/// How a `web_fetch` result was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebFetchCacheStatus {
+    /// Downloaded from the origin.
+    Fetched,
+    /// Served from the cache within its TTL.
+    Cached,
+    /// The origin answered 304 Not Modified; the cached body was reused.
+    Revalidated,
+}
+
+impl WebFetchCacheStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebFetchCacheStatus::Fetched => "fetched",
+            WebFetchCacheStatus::Cached => "cached",
+            WebFetchCacheStatus::Revalidated => "revalidated",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebFetchOutcome {
+    pub text: String,
+    /// Title, description, canonical URL and Open Graph tags of the page.
+    pub metadata: PageMetadata,
+    pub cache_status: WebFetchCacheStatus,
+    /// Time spent queued behind the per-host rate limit.
+    pub rate_limit_wait: Duration,
+}
+
+#[derive(Clone)]
+struct PageCacheEntry {
+    fetched_at: Instant,
+    text: String,
+    metadata: PageMetadata,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
 const fn default_enabled() -> bool {
     true
 }
@@ -131,6 +466,26 @@ const fn default_feed_max_entries_per_source() -> usize {
     10_000
 }
 
+const fn default_host_min_interval_ms() -> u64 {
+    1000
+}
+
+const fn default_host_burst() -> u32 {
+    2
+}
+
+const fn default_cache_ttl_secs() -> u64 {
+    300
+}
+
+const fn default_cache_max_entries() -> usize {
+    256
+}
+
+fn default_preserve_tags() -> Vec<String> {
+    MARKDOWN_TAGS.iter().map(|t| t.to_string()).collect()
+}
+
 fn default_allowed_schemes() -> Vec<String> {
     vec!["https".to_string(), "http".to_string()]
 }
@@ -174,6 +529,7 @@ impl Default for WebFetchUrlValidationConfig {
             allowlist_hosts: Vec::new(),
             denylist_hosts: Vec::new(),
             feed_sync: WebFetchFeedSyncConfig::default(),
+            domain_policy: WebDomainPolicy::default(),
         }
     }
 }
@@ -212,7 +568,7 @@ fn normalize_host_list(hosts: &mut Vec<String>) {
     *hosts = normalized;
 }
 
-fn normalize_host_candidate(input: &str) -> Option<String> {
+pub(crate) fn normalize_host_candidate(input: &str) -> Option<String> {
     let mut token = input.trim().to_string();
     if token.is_empty() {
         return None;
@@ -249,11 +605,11 @@ fn normalize_host_candidate(input: &str) -> Option<String> {
     }
 }
 
-fn host_matches_rule(host: &str, rule: &str) -> bool {
+pub(crate) fn host_matches_rule(host: &str, rule: &str) -> bool {
     host == rule || host.ends_with(&format!(".{rule}"))
 }
 
-fn resolve_and_validate_redirect_target(
+pub(crate) fn resolve_and_validate_redirect_target(
     current_url: &Url,
     location: &str,
     url_validation: &WebFetchUrlValidationConfig,
@@ -270,6 +626,34 @@ fn feed_cache() -> &'static Mutex<HashMap<String, FeedCacheEntry>> {
     CACHE.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
+fn page_cache() -> &'static Mutex<HashMap<String, PageCacheEntry>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, PageCacheEntry>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn store_page_cache_entry(url: &str, entry: PageCacheEntry, max_entries: usize) {
+    let mut cache = page_cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache.insert(url.to_string(), entry);
+    while cache.len() > max_entries {
+        let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, e)| e.fetched_at)
+            .map(|(k, _)| k.clone())
+        else {
+            break;
+        };
+        cache.remove(&oldest);
+    }
+}
+
+fn header_string(resp: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    resp.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
 fn parse_feed_entries(raw: &str, format: &WebFetchFeedFormat, max_entries: usize) -> Vec<String> {
     let mut seen = HashSet::new();
     let mut out = Vec::new();
@@ -409,6 +793,7 @@ pub fn validate_web_fetch_url(
     raw_url: &str,
     mut config: WebFetchUrlValidationConfig,
 ) -> Result<(), String> {
+    config.domain_policy.check_url(raw_url)?;
     if !config.enabled {
         return Ok(());
     }
@@ -465,19 +850,110 @@ pub async fn fetch_url_with_timeout_and_validation(
     validation: WebContentValidationConfig,
     url_validation: WebFetchUrlValidationConfig,
 ) -> Result<String, String> {
+    fetch_url_with_cache(
+        url,
+        timeout_secs,
+        validation,
+        url_validation,
+        &WebFetchCacheConfig::default(),
+        &WebFetchProxyConfig::default(),
+        &WebFetchMarkdownConfig::default(),
+        WebFetchRenderMode::Text,
+        &WebFetchHostRateConfig::disabled(),
+    )
+    .await
+    .map(|outcome| outcome.text)
+}
+
+/// Page cache key: entries are kept per render mode and per conversion and
+/// validation settings, so a config change never serves text rendered under
+/// the old one.
+fn page_cache_key(
+    url: &str,
+    mode: WebFetchRenderMode,
+    markdown: &WebFetchMarkdownConfig,
+    validation: WebContentValidationConfig,
+) -> String {
+    format!(
+        "{url}::{}::{}::{}:{}:{}",
+        mode.as_str(),
+        markdown.preserve_tags.join(","),
+        validation.enabled,
+        validation.strict_mode,
+        validation.max_scan_bytes
+    )
+}
+
+/// Fetch a URL through the page cache. A stale entry with an `ETag` or
+/// `Last-Modified` validator is revalidated with a conditional request.
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_url_with_cache(
+    url: &str,
+    timeout_secs: u64,
+    validation: WebContentValidationConfig,
+    url_validation: WebFetchUrlValidationConfig,
+    cache: &WebFetchCacheConfig,
+    proxy: &WebFetchProxyConfig,
+    markdown: &WebFetchMarkdownConfig,
+    mode: WebFetchRenderMode,
+    host_rate: &WebFetchHostRateConfig,
+) -> Result<WebFetchOutcome, String> {
     let effective_url_validation = resolve_url_validation_config(url_validation).await?;
     validate_web_fetch_url(url, effective_url_validation.clone())?;
 
-    let client = http_client_no_redirect(timeout_secs.max(1));
+    let cache_key = page_cache_key(url, mode, markdown, validation);
+    let cached = if cache.enabled {
+        let entries = page_cache().lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(&cache_key).cloned()
+    } else {
+        None
+    };
+    if let Some(entry) = cached.as_ref() {
+        if entry.fetched_at.elapsed() < Duration::from_secs(cache.ttl_secs) {
+            return Ok(WebFetchOutcome {
+                text: entry.text.clone(),
+                metadata: entry.metadata.clone(),
+                cache_status: WebFetchCacheStatus::Cached,
+                rate_limit_wait: Duration::ZERO,
+            });
+        }
+    }
+
+    let client = http_client_no_redirect(timeout_secs.max(1), proxy);
     let mut current_url = Url::parse(url).map_err(|e| format!("invalid URL: {e}"))?;
     let mut redirects = 0usize;
+    let mut rate_limit_wait = Duration::ZERO;
 
     let resp = loop {
-        let resp = client
-            .get(current_url.clone())
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+        rate_limit_wait += wait_for_host_slot(&current_url, host_rate).await;
+        let mut request = client.get(current_url.clone());
+        // Validators belong to the cached URL; redirect targets are fetched plainly.
+        let revalidating = cached.as_ref().filter(|_| redirects == 0);
+        if let Some(entry) = revalidating {
+            if let Some(etag) = entry.etag.as_deref() {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = entry.last_modified.as_deref() {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let resp = request.send().await.map_err(|e| e.to_string())?;
+
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = revalidating.cloned() {
+                entry.fetched_at = Instant::now();
+                let text = entry.text.clone();
+                let metadata = entry.metadata.clone();
+                store_page_cache_entry(&cache_key, entry, cache.max_entries);
+                return Ok(WebFetchOutcome {
+                    text,
+                    metadata,
+                    cache_status: WebFetchCacheStatus::Revalidated,
+                    rate_limit_wait,
+                });
+            }
+            return Err("HTTP 304 Not Modified without a cached copy".to_string());
+        }
 
         if !resp.status().is_redirection() {
             break resp;
@@ -505,11 +981,59 @@ pub async fn fetch_url_with_timeout_and_validation(
         return Err(format!("HTTP {}", resp.status()));
     }
 
+    let etag = header_string(&resp, reqwest::header::ETAG);
+    let last_modified = header_string(&resp, reqwest::header::LAST_MODIFIED);
     let body = resp.text().await.map_err(|e| e.to_string())?;
-    let primary = extract_primary_html(&body);
-    let text = html_to_text(primary);
+    let (text, metadata) = page_output(&body, &current_url, validation, markdown, mode)?;
+
+    if cache.enabled {
+        store_page_cache_entry(
+            &cache_key,
+            PageCacheEntry {
+                fetched_at: Instant::now(),
+                text: text.clone(),
+                metadata: metadata.clone(),
+                etag,
+                last_modified,
+            },
+            cache.max_entries,
+        );
+    }
+    Ok(WebFetchOutcome {
+        text,
+        metadata,
+        cache_status: WebFetchCacheStatus::Fetched,
+        rate_limit_wait,
+    })
+}
 
-    if let Err(failure) = validate_web_content_with_config(&text, validation) {
+/// Render `body` in `mode`, validate it and cap it at 20KB. Relative
+/// canonical links resolve against `page_url`.
+fn page_output(
+    body: &str,
+    page_url: &Url,
+    validation: WebContentValidationConfig,
+    markdown: &WebFetchMarkdownConfig,
+    mode: WebFetchRenderMode,
+) -> Result<(String, PageMetadata), String> {
+    let text = markdown.render(body, mode);
+    let mut metadata = extract_page_metadata(body);
+    metadata.canonical_url = metadata
+        .canonical_url
+        .and_then(|href| page_url.join(&href).ok())
+        .map(|u| u.to_string());
+    let metadata_text = metadata
+        .title
+        .iter()
+        .chain(&metadata.description)
+        .chain(metadata.open_graph.values())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if let Err(failure) = validate_web_content_with_config(&text, validation)
+        .and_then(|()| validate_web_content_with_config(&metadata_text, validation))
+    {
         warn!(
             matched_rules = failure.rule_names.join(","),
             "Blocked web_fetch content by validation"
@@ -518,12 +1042,62 @@ pub async fn fetch_url_with_timeout_and_validation(
     }
 
     const MAX_BYTES: usize = 20_000;
-    if text.len() > MAX_BYTES {
+    let text = if text.len() > MAX_BYTES {
         let truncated = &text[..floor_char_boundary(&text, MAX_BYTES)];
-        Ok(format!("{truncated}\n\n[Truncated at 20KB]"))
+        format!("{truncated}\n\n[Truncated at 20KB]")
     } else {
-        Ok(text)
-    }
+        text
+    };
+    Ok((text, metadata))
+}
+
+/// Why a rendered fetch produced no page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderedFetchError {
+    /// URL or content validation refused the page; a plain fetch would be refused too.
+    Refused(String),
+    /// The renderer failed (not installed, crashed, timed out).
+    Renderer(String),
+}
+
+/// Fetch a page through `render`, which loads the URL in a JavaScript-capable
+/// engine and returns the resulting DOM as HTML. The URL checks, per-host rate
+/// limit, content validation and 20KB cap are those of a plain fetch;
+/// rendered pages are not cached.
+#[allow(clippy::too_many_arguments)]
+pub async fn fetch_rendered_url<F, Fut>(
+    url: &str,
+    timeout_secs: u64,
+    validation: WebContentValidationConfig,
+    url_validation: WebFetchUrlValidationConfig,
+    markdown: &WebFetchMarkdownConfig,
+    mode: WebFetchRenderMode,
+    host_rate: &WebFetchHostRateConfig,
+    render: F,
+) -> Result<WebFetchOutcome, RenderedFetchError>
+where
+    F: FnOnce(String, u64) -> Fut,
+    Fut: std::future::Future<Output = Result<String, String>>,
+{
+    let effective_url_validation = resolve_url_validation_config(url_validation)
+        .await
+        .map_err(RenderedFetchError::Refused)?;
+    validate_web_fetch_url(url, effective_url_validation).map_err(RenderedFetchError::Refused)?;
+    let page_url =
+        Url::parse(url).map_err(|e| RenderedFetchError::Refused(format!("invalid URL: {e}")))?;
+
+    let rate_limit_wait = wait_for_host_slot(&page_url, host_rate).await;
+    let html = render(url.to_string(), timeout_secs.max(1))
+        .await
+        .map_err(RenderedFetchError::Renderer)?;
+    let (text, metadata) = page_output(&html, &page_url, validation, markdown, mode)
+        .map_err(RenderedFetchError::Refused)?;
+    Ok(WebFetchOutcome {
+        text,
+        metadata,
+        cache_status: WebFetchCacheStatus::Fetched,
+        rate_limit_wait,
+    })
 }
 
 pub async fn fetch_url(url: &str) -> Result<String, String> {
@@ -540,10 +1114,15 @@ mod tests {
     use tokio::net::TcpListener;
     use tokio::time::{timeout, Duration};
 
+    use crate::web_domains::WebDomainPolicy;
+
     use super::{
-        fetch_url_with_timeout_and_validation, resolve_and_validate_redirect_target,
-        resolve_url_validation_config, validate_web_fetch_url, WebFetchFeedFormat,
-        WebFetchFeedMode, WebFetchFeedSource, WebFetchFeedSyncConfig, WebFetchUrlValidationConfig,
+        fetch_rendered_url, fetch_url_with_cache, fetch_url_with_timeout_and_validation,
+        page_cache_key, reserve_host_slot, resolve_and_validate_redirect_target,
+        resolve_url_validation_config, validate_web_fetch_url, RenderedFetchError,
+        WebFetchCacheConfig, WebFetchCacheStatus, WebFetchFeedFormat, WebFetchFeedMode,
+        WebFetchFeedSource, WebFetchFeedSyncConfig, WebFetchHostRateConfig, WebFetchMarkdownConfig,
+        WebFetchProxyConfig, WebFetchRenderMode, WebFetchUrlValidationConfig,
     };
     use crate::web_content_validation::WebContentValidationConfig;
 
@@ -561,6 +1140,41 @@ mod tests {
         .is_ok());
     }
 
+    #[test]
+    fn host_rate_allows_burst_then_spaces_requests() {
+        let rate = WebFetchHostRateConfig {
+            min_interval_ms: 1000,
+            burst: 2,
+        };
+        let now = std::time::Instant::now();
+        let host = "burst.rate-test.example";
+        assert_eq!(reserve_host_slot(host, &rate, now), Duration::ZERO);
+        assert_eq!(reserve_host_slot(host, &rate, now), Duration::ZERO);
+        assert_eq!(
+            reserve_host_slot(host, &rate, now),
+            Duration::from_millis(1000)
+        );
+        assert_eq!(
+            reserve_host_slot(host, &rate, now),
+            Duration::from_millis(2000)
+        );
+        // Other hosts have their own bucket.
+        assert_eq!(
+            reserve_host_slot("other.rate-test.example", &rate, now),
+            Duration::ZERO
+        );
+        // After the queue drains and a full refill, the burst is available again.
+        let later = now + Duration::from_secs(5);
+        assert_eq!(reserve_host_slot(host, &rate, later), Duration::ZERO);
+        assert_eq!(reserve_host_slot(host, &rate, later), Duration::ZERO);
+        assert!(reserve_host_slot(
+            "x.rate-test.example",
+            &WebFetchHostRateConfig::disabled(),
+            now
+        )
+        .is_zero());
+    }
+
     #[test]
     fn url_validation_blocks_scheme() {
         let err =
@@ -609,6 +1223,7 @@ mod tests {
             allowlist_hosts: vec!["allowed.com".into()],
             denylist_hosts: vec!["allowed.com".into()],
             feed_sync: WebFetchFeedSyncConfig::default(),
+            domain_policy: WebDomainPolicy::default(),
         };
         assert!(validate_web_fetch_url("ftp://bad", cfg).is_ok());
     }
@@ -785,6 +1400,20 @@ mod tests {
         assert!(err.contains("denylisted"));
     }
 
+    #[test]
+    fn redirect_validation_enforces_domain_policy() {
+        let current = Url::parse("https://safe.example/start").unwrap();
+        let cfg = WebFetchUrlValidationConfig {
+            enabled: false,
+            domain_policy: WebDomainPolicy::new(&["safe.example".to_string()], &[]),
+            ..WebFetchUrlValidationConfig::default()
+        };
+        let err = resolve_and_validate_redirect_target(&current, "https://other.example/x", &cfg)
+            .unwrap_err();
+        assert!(err.contains("not in web_domain_allowlist"));
+        assert!(resolve_and_validate_redirect_target(&current, "/next", &cfg).is_ok());
+    }
+
     #[test]
     fn redirect_validation_allows_relative_target() {
         let current = Url::parse("https://safe.example/start").unwrap();
@@ -851,4 +1480,457 @@ mod tests {
             "should not request redirect target after URL policy rejection"
         );
     }
+
+    #[tokio::test]
+    async fn fetch_blocks_redirect_outside_domain_allowlist() {
+        let final_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let final_addr = final_listener.local_addr().unwrap();
+        let final_hit = Arc::new(AtomicBool::new(false));
+        let final_hit_clone = final_hit.clone();
+        let final_server = tokio::spawn(async move {
+            if let Ok(Ok((_stream, _))) =
+                timeout(Duration::from_secs(2), final_listener.accept()).await
+            {
+                final_hit_clone.store(true, Ordering::SeqCst);
+            }
+        });
+
+        let redirect_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let redirect_addr = redirect_listener.local_addr().unwrap();
+        let redirect_server = tokio::spawn(async move {
+            let (mut stream, _) = redirect_listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:{}/final\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                final_addr.port()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+
+        let start_url = format!("http://localhost:{}/start", redirect_addr.port());
+        let url_cfg = WebFetchUrlValidationConfig {
+            domain_policy: WebDomainPolicy::new(&["localhost".to_string()], &[]),
+            ..WebFetchUrlValidationConfig::default()
+        };
+        let err = fetch_url_with_timeout_and_validation(
+            &start_url,
+            5,
+            WebContentValidationConfig::default(),
+            url_cfg,
+        )
+        .await
+        .unwrap_err();
+
+        redirect_server.await.unwrap();
+        final_server.await.unwrap();
+
+        assert!(err.contains("not in web_domain_allowlist"));
+        assert!(!final_hit.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn fetch_revalidates_stale_cache_entry_with_validators() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let req = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                let response = if req.contains("if-none-match: \"v1\"")
+                    && req.contains("if-modified-since: wed, 21 oct 2026 07:28:00 gmt")
+                {
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                } else {
+                    let body = "<html><head><title>Cached</title><link rel=\"canonical\" href=\"/page?ref=1\"></head><body><p>cached page</p></body></html>";
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nETag: \"v1\"\r\nLast-Modified: Wed, 21 Oct 2026 07:28:00 GMT\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                requests.push(req);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+            requests
+        });
+
+        let url = format!("http://127.0.0.1:{}/page", addr.port());
+        let stale_cache = WebFetchCacheConfig {
+            enabled: true,
+            ttl_secs: 0,
+            max_entries: 16,
+        };
+        let first = fetch_url_with_cache(
+            &url,
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &stale_cache,
+            &WebFetchProxyConfig::default(),
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.cache_status, WebFetchCacheStatus::Fetched);
+        assert!(first.text.contains("cached page"));
+        assert_eq!(first.metadata.title.as_deref(), Some("Cached"));
+        assert_eq!(
+            first.metadata.canonical_url,
+            Some(format!("http://127.0.0.1:{}/page?ref=1", addr.port()))
+        );
+
+        let second = fetch_url_with_cache(
+            &url,
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &stale_cache,
+            &WebFetchProxyConfig::default(),
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(second.cache_status, WebFetchCacheStatus::Revalidated);
+        assert_eq!(second.text, first.text);
+        assert_eq!(second.metadata, first.metadata);
+
+        let requests = server.await.unwrap();
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match"));
+
+        // The 304 refreshed the entry, so a long TTL now serves it without a request.
+        let fresh_cache = WebFetchCacheConfig {
+            ttl_secs: 3600,
+            ..stale_cache
+        };
+        let third = fetch_url_with_cache(
+            &url,
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &fresh_cache,
+            &WebFetchProxyConfig::default(),
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(third.cache_status, WebFetchCacheStatus::Cached);
+        assert_eq!(third.text, first.text);
+        assert_eq!(third.metadata, first.metadata);
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_validators_only_to_the_cached_url() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for _ in 0..4 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let req = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                let response = if req.starts_with("get /start") {
+                    format!(
+                        "HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:{}/final\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        addr.port()
+                    )
+                } else if req.contains("if-none-match") {
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                } else {
+                    let body = "<html><body><p>final page</p></body></html>";
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                requests.push(req);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+            requests
+        });
+
+        let url = format!("http://127.0.0.1:{}/start", addr.port());
+        let stale_cache = WebFetchCacheConfig {
+            enabled: true,
+            ttl_secs: 0,
+            max_entries: 16,
+        };
+        let proxy = WebFetchProxyConfig::default();
+        let markdown = WebFetchMarkdownConfig::default();
+        let host_rate = WebFetchHostRateConfig::disabled();
+        let fetch = || {
+            fetch_url_with_cache(
+                &url,
+                5,
+                WebContentValidationConfig::default(),
+                WebFetchUrlValidationConfig::default(),
+                &stale_cache,
+                &proxy,
+                &markdown,
+                WebFetchRenderMode::Text,
+                &host_rate,
+            )
+        };
+        let first = fetch().await.unwrap();
+        assert_eq!(first.cache_status, WebFetchCacheStatus::Fetched);
+        let second = fetch().await.unwrap();
+        assert_eq!(second.cache_status, WebFetchCacheStatus::Fetched);
+        assert!(second.text.contains("final page"));
+
+        let requests = server.await.unwrap();
+        assert!(requests[2].starts_with("get /start"));
+        assert!(requests[2].contains("if-none-match: \"v1\""));
+        assert!(requests[3].starts_with("get /final"));
+        assert!(!requests[3].contains("if-none-match"));
+        assert!(!requests[3].contains("if-modified-since"));
+    }
+
+    #[test]
+    fn page_cache_key_tracks_conversion_and_validation_settings() {
+        let url = "https://example.com/page";
+        let markdown = WebFetchMarkdownConfig::default();
+        let validation = WebContentValidationConfig::default();
+        let key = page_cache_key(url, WebFetchRenderMode::Markdown, &markdown, validation);
+        assert_eq!(
+            key,
+            page_cache_key(url, WebFetchRenderMode::Markdown, &markdown, validation)
+        );
+        assert_ne!(
+            key,
+            page_cache_key(url, WebFetchRenderMode::Text, &markdown, validation)
+        );
+
+        let headings_only = WebFetchMarkdownConfig {
+            preserve_tags: vec!["h1".into()],
+            ..WebFetchMarkdownConfig::default()
+        };
+        assert_ne!(
+            key,
+            page_cache_key(
+                url,
+                WebFetchRenderMode::Markdown,
+                &headings_only,
+                validation
+            )
+        );
+
+        let lenient = WebContentValidationConfig {
+            strict_mode: false,
+            ..validation
+        };
+        assert_ne!(
+            key,
+            page_cache_key(url, WebFetchRenderMode::Markdown, &markdown, lenient)
+        );
+    }
+
+    async fn serve_once(listener: TcpListener, body: &'static str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 2048];
+        let n = stream.read(&mut buf).await.unwrap_or(0);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        String::from_utf8_lossy(&buf[..n]).into_owned()
+    }
+
+    #[tokio::test]
+    async fn fetch_routes_through_configured_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let proxy_server = tokio::spawn(serve_once(listener, "<p>via proxy</p>"));
+
+        let proxy = WebFetchProxyConfig {
+            url: Some(format!("http://{addr}")),
+            no_proxy: vec![],
+            use_env: false,
+        };
+        let outcome = fetch_url_with_cache(
+            "http://internal.example.test/page",
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &WebFetchCacheConfig::default(),
+            &proxy,
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+        )
+        .await
+        .unwrap();
+        assert!(outcome.text.contains("via proxy"));
+        let request = proxy_server.await.unwrap();
+        assert!(request.starts_with("GET http://internal.example.test/page HTTP/1.1"));
+    }
+
+    #[tokio::test]
+    async fn fetch_bypasses_proxy_for_no_proxy_hosts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let origin = tokio::spawn(serve_once(listener, "<p>direct</p>"));
+
+        // The proxy port is closed; only a direct connection can succeed.
+        let unused = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_proxy = unused.local_addr().unwrap();
+        drop(unused);
+        let proxy = WebFetchProxyConfig {
+            url: Some(format!("socks5h://{dead_proxy}")),
+            no_proxy: vec!["127.0.0.1".into()],
+            use_env: false,
+        };
+        let outcome = fetch_url_with_cache(
+            &format!("http://127.0.0.1:{}/page", addr.port()),
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &WebFetchCacheConfig::default(),
+            &proxy,
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+        )
+        .await
+        .unwrap();
+        assert!(outcome.text.contains("direct"));
+        assert!(origin.await.unwrap().starts_with("GET /page HTTP/1.1"));
+    }
+
+    #[test]
+    fn proxy_config_normalize_validates_scheme() {
+        let mut cfg = WebFetchProxyConfig {
+            url: Some("  socks5://proxy.corp:1080 ".into()),
+            no_proxy: vec![" Internal.Corp ".into(), " ".into()],
+            use_env: true,
+        };
+        cfg.normalize().unwrap();
+        assert_eq!(cfg.url.as_deref(), Some("socks5://proxy.corp:1080"));
+        assert_eq!(cfg.no_proxy, vec!["internal.corp".to_string()]);
+
+        let mut blank = WebFetchProxyConfig {
+            url: Some("   ".into()),
+            ..WebFetchProxyConfig::default()
+        };
+        blank.normalize().unwrap();
+        assert_eq!(blank.url, None);
+
+        let mut bad = WebFetchProxyConfig {
+            url: Some("ftp://proxy.corp".into()),
+            ..WebFetchProxyConfig::default()
+        };
+        assert!(bad.normalize().unwrap_err().contains("unsupported scheme"));
+    }
+
+    #[test]
+    fn test_markdown_config_render_modes() {
+        let config = WebFetchMarkdownConfig::default();
+        let article = "<body><nav>menu</nav><article><h2>Title</h2><p>Body</p></article></body>";
+        assert_eq!(
+            config.render(article, WebFetchRenderMode::Auto),
+            "## Title\n\nBody"
+        );
+        assert_eq!(
+            config.render(article, WebFetchRenderMode::Text),
+            "Title Body"
+        );
+        let plain = "<body><div><h2>Title</h2>Body</div></body>";
+        assert_eq!(config.render(plain, WebFetchRenderMode::Auto), "Title Body");
+        assert_eq!(
+            config.render(plain, WebFetchRenderMode::Markdown),
+            "## Title\n\nBody"
+        );
+    }
+
+    #[test]
+    fn test_markdown_config_normalize_tags() {
+        let mut config = WebFetchMarkdownConfig {
+            preserve_tags: vec![" H2 ".into(), "<a>".into(), "h2".into()],
+            ..WebFetchMarkdownConfig::default()
+        };
+        config.normalize().unwrap();
+        assert_eq!(
+            config.preserve_tags,
+            vec!["h2".to_string(), "a".to_string()]
+        );
+
+        config.preserve_tags = vec!["table".into()];
+        let err = config.normalize().unwrap_err();
+        assert!(err.contains("unsupported tag 'table'"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rendered_url_uses_renderer_html() {
+        let outcome = fetch_rendered_url(
+            "https://spa.example/app",
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+            |url, timeout_secs| async move {
+                assert_eq!(url, "https://spa.example/app");
+                assert_eq!(timeout_secs, 5);
+                Ok("<html><head><title>App</title></head><body><div id=\"root\"><p>Loaded by JS</p></div><script>x()</script></body></html>".to_string())
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome.text, "Loaded by JS");
+        assert_eq!(outcome.metadata.title.as_deref(), Some("App"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rendered_url_validates_before_rendering() {
+        let url_validation = WebFetchUrlValidationConfig {
+            denylist_hosts: vec!["spa.example".into()],
+            ..WebFetchUrlValidationConfig::default()
+        };
+        let err = fetch_rendered_url(
+            "https://spa.example/app",
+            5,
+            WebContentValidationConfig::default(),
+            url_validation,
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+            |_, _| async { panic!("renderer must not run for a refused URL") },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, RenderedFetchError::Refused(ref m) if m.contains("denylisted")));
+
+        let err = fetch_rendered_url(
+            "https://spa.example/app",
+            5,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+            &WebFetchMarkdownConfig::default(),
+            WebFetchRenderMode::Text,
+            &WebFetchHostRateConfig::disabled(),
+            |_, _| async { Err("agent-browser not found".to_string()) },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err,
+            RenderedFetchError::Renderer("agent-browser not found".into())
+        );
+    }
 }
@@ -1,5 +1,7 @@
 use microclaw_core::text::floor_char_boundary;
+use serde::Serialize;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub struct SearchItem {
@@ -8,13 +10,36 @@ pub struct SearchItem {
     pub snippet: String,
 }
 
+/// Longest metadata value kept, in bytes; descriptions can be whole paragraphs.
+const MAX_METADATA_VALUE_BYTES: usize = 500;
+
+/// Page-level metadata from `<head>`. Tags the page does not declare are omitted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PageMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    /// `og:*` properties keyed without the prefix, e.g. `title`, `image`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub open_graph: BTreeMap<String, String>,
+}
+
+impl PageMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.canonical_url.is_none()
+            && self.open_graph.is_empty()
+    }
+}
+
 fn strip_block(mut html: String, tag: &str) -> String {
     let open = format!("<{}", tag);
     let close = format!("</{}>", tag);
-    loop {
-        let Some(start) = find_case_insensitive(&html, &open, 0) else {
-            break;
-        };
+    while let Some(start) = find_case_insensitive(&html, &open, 0) {
         let Some(end) = find_case_insensitive(&html, &close, start) else {
             html.truncate(start);
             break;
@@ -80,6 +105,273 @@ pub fn html_to_text(html: &str) -> String {
     collapse_whitespace(&decode_html_entities(&text))
 }
 
+/// Tags `html_to_markdown` can preserve.
+pub const MARKDOWN_TAGS: &[&str] = &[
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "blockquote",
+    "a",
+];
+
+/// Elements that are always removed with their content, whatever the mode.
+const DROPPED_BLOCKS: &[&str] = &["script", "style", "svg", "noscript", "template"];
+
+/// Tags that only separate paragraphs; their markup is dropped.
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "header",
+    "footer",
+    "nav",
+    "aside",
+    "br",
+    "hr",
+    "table",
+    "tr",
+    "pre",
+    "figure",
+    "figcaption",
+    "dl",
+    "dt",
+    "dd",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "blockquote",
+];
+
+struct MarkdownWriter {
+    out: String,
+    /// `Some(n)` for an ordered list with `n` items emitted so far.
+    lists: Vec<Option<usize>>,
+    quotes: Vec<usize>,
+    links: Vec<Option<(String, usize)>>,
+}
+
+impl MarkdownWriter {
+    fn trim_trailing_spaces(&mut self) {
+        let keep = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(keep);
+    }
+
+    fn paragraph_break(&mut self) {
+        self.trim_trailing_spaces();
+        if self.out.is_empty() || self.out.ends_with("\n\n") {
+            return;
+        }
+        if !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        // Consecutive list items stay on adjacent lines.
+        if self.lists.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.trim_trailing_spaces();
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        for ch in text.chars() {
+            if ch.is_whitespace() {
+                if !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+                    self.out.push(' ');
+                }
+            } else {
+                self.out.push(ch);
+            }
+        }
+    }
+
+    fn open(&mut self, tag: &str, raw_tag: &str, keep: &dyn Fn(&str) -> bool) {
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if keep(tag) => {
+                self.paragraph_break();
+                let level = usize::from(tag.as_bytes()[1] - b'0');
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            "ul" | "ol" if keep(tag) => {
+                if self.lists.is_empty() {
+                    self.paragraph_break();
+                }
+                self.lists.push((tag == "ol").then_some(0));
+            }
+            "li" if keep(tag) => {
+                self.line_break();
+                let depth = self.lists.len().max(1);
+                self.out.push_str(&"  ".repeat(depth - 1));
+                match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        self.out.push_str(&format!("{n}. "));
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            "blockquote" if keep(tag) => {
+                self.paragraph_break();
+                self.quotes.push(self.out.len());
+            }
+            "a" => {
+                let link = extract_attr(raw_tag, "href")
+                    .map(|h| decode_html_entities(h.trim()).into_owned())
+                    .filter(|h| {
+                        keep(tag)
+                            && !h.is_empty()
+                            && !h.starts_with('#')
+                            && !h.to_ascii_lowercase().starts_with("javascript:")
+                    })
+                    .map(|href| (href, self.out.len()));
+                self.links.push(link);
+            }
+            "br" => self.line_break(),
+            _ if BLOCK_TAGS.contains(&tag) => self.paragraph_break(),
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, tag: &str, keep: &dyn Fn(&str) -> bool) {
+        match tag {
+            "ul" | "ol" if keep(tag) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.paragraph_break();
+                } else {
+                    self.line_break();
+                }
+            }
+            "blockquote" if keep(tag) => {
+                let Some(start) = self.quotes.pop() else {
+                    return;
+                };
+                let quoted = self.out.split_off(start);
+                let quoted = quoted
+                    .trim()
+                    .lines()
+                    .map(|line| {
+                        if line.trim().is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {}", line.trim_end())
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.out.push_str(&quoted);
+                self.paragraph_break();
+            }
+            "a" => {
+                if let Some(Some((href, start))) = self.links.pop() {
+                    let text = self.out.split_off(start);
+                    let leading = if text.starts_with(' ') { " " } else { "" };
+                    let label = text.trim();
+                    if !label.is_empty() {
+                        self.out.push_str(&format!("{leading}[{label}]({href})"));
+                    }
+                }
+            }
+            _ if BLOCK_TAGS.contains(&tag) => self.paragraph_break(),
+            _ => {}
+        }
+    }
+}
+
+/// Render HTML as Markdown, keeping only the structure of `preserve_tags`
+/// (see `MARKDOWN_TAGS`); all other markup is dropped and its text kept.
+pub fn html_to_markdown(html: &str, preserve_tags: &[String]) -> String {
+    let mut html = html.to_string();
+    for tag in DROPPED_BLOCKS {
+        html = strip_block(html, tag);
+    }
+    let keep = |tag: &str| preserve_tags.iter().any(|t| t == tag);
+
+    let mut writer = MarkdownWriter {
+        out: String::with_capacity(html.len() / 2),
+        lists: Vec::new(),
+        quotes: Vec::new(),
+        links: Vec::new(),
+    };
+    let mut rest = html.as_str();
+    while let Some(lt) = rest.find('<') {
+        writer.push_text(&decode_html_entities(&rest[..lt]));
+        let Some(gt) = rest[lt..].find('>') else {
+            rest = "";
+            break;
+        };
+        let raw_tag = &rest[lt + 1..lt + gt];
+        rest = &rest[lt + gt + 1..];
+        if raw_tag.starts_with('!') || raw_tag.starts_with('?') {
+            continue;
+        }
+        let (closing, body) = match raw_tag.strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, raw_tag),
+        };
+        let name = body
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if closing {
+            writer.close(&name, &keep);
+        } else {
+            writer.open(&name, raw_tag, &keep);
+        }
+    }
+    writer.push_text(&decode_html_entities(rest));
+
+    let mut markdown = String::with_capacity(writer.out.len());
+    let mut blank_run = 0;
+    for line in writer.out.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        markdown.push_str(line);
+        markdown.push('\n');
+    }
+    markdown.trim().to_string()
+}
+
+/// Heuristic for pages whose structure is worth keeping: an `<article>`/`<main>`
+/// element, or a heading followed by several paragraphs.
+pub fn looks_like_article(html: &str) -> bool {
+    if find_case_insensitive(html, "<article", 0).is_some()
+        || find_case_insensitive(html, "<main", 0).is_some()
+    {
+        return true;
+    }
+    let lower = html.to_ascii_lowercase();
+    let has_heading = ["<h1", "<h2", "<h3"].iter().any(|h| lower.contains(h));
+    has_heading && lower.matches("<p").count() >= 3
+}
+
 pub fn collapse_whitespace(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     let mut last_ws = false;
@@ -131,6 +423,81 @@ fn extract_attr(tag: &str, attr: &str) -> Option<String> {
     Some(raw[..end].to_string())
 }
 
+fn metadata_value(raw: &str) -> Option<String> {
+    let value = collapse_whitespace(&decode_html_entities(raw));
+    if value.is_empty() {
+        return None;
+    }
+    if value.len() <= MAX_METADATA_VALUE_BYTES {
+        return Some(value);
+    }
+    Some(value[..floor_char_boundary(&value, MAX_METADATA_VALUE_BYTES)].to_string())
+}
+
+/// Iterate the opening tags named `tag` (e.g. `<meta ...>`) in `html`, with
+/// the byte offset just past each one.
+fn opening_tags<'a>(html: &'a str, tag: &str) -> impl Iterator<Item = (&'a str, usize)> + 'a {
+    let open = format!("<{tag}");
+    let mut pos = 0usize;
+    std::iter::from_fn(move || loop {
+        let start = find_case_insensitive(html, &open, pos)?;
+        let end = start + html[start..].find('>')?;
+        pos = end + 1;
+        // Skip longer tag names sharing the prefix, e.g. `<metadata>` for `<meta`.
+        let next = html[start + open.len()..].chars().next();
+        if next.is_some_and(|c| c.is_whitespace() || c == '/' || c == '>') {
+            return Some((&html[start..=end], pos));
+        }
+    })
+}
+
+/// Extract `<title>`, the meta description, the canonical link and Open Graph
+/// properties. Only `<head>` is scanned when the page has one; the first
+/// occurrence of each tag wins.
+pub fn extract_page_metadata(html: &str) -> PageMetadata {
+    let head = match find_case_insensitive(html, "</head>", 0) {
+        Some(end) => &html[..end],
+        None => html,
+    };
+    let mut metadata = PageMetadata::default();
+
+    if let Some((_, content_start)) = opening_tags(head, "title").next() {
+        let content_end =
+            find_case_insensitive(head, "</title>", content_start).unwrap_or(head.len());
+        metadata.title = metadata_value(&head[content_start..content_end]);
+    }
+
+    for (tag, _) in opening_tags(head, "meta") {
+        let Some(key) = extract_attr(tag, "property").or_else(|| extract_attr(tag, "name")) else {
+            continue;
+        };
+        let Some(content) = extract_attr(tag, "content").and_then(|c| metadata_value(&c)) else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        if key == "description" {
+            metadata.description.get_or_insert(content);
+        } else if let Some(og_key) = key.strip_prefix("og:").filter(|k| !k.is_empty()) {
+            metadata
+                .open_graph
+                .entry(og_key.to_string())
+                .or_insert(content);
+        }
+    }
+
+    metadata.canonical_url = opening_tags(head, "link")
+        .map(|(tag, _)| tag)
+        .filter(|tag| {
+            extract_attr(tag, "rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("canonical"))
+            })
+        })
+        .find_map(|tag| extract_attr(tag, "href").and_then(|h| metadata_value(&h)));
+
+    metadata
+}
+
 fn extract_snippet_near(html: &str, from: usize) -> String {
     let from = floor_char_boundary(html, from.min(html.len()));
     let window_end = floor_char_boundary(html, from.saturating_add(4000).min(html.len()));
@@ -231,6 +598,43 @@ mod tests {
         assert_eq!(html_to_text(html), "Hello World");
     }
 
+    fn default_markdown_tags() -> Vec<String> {
+        MARKDOWN_TAGS.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_html_to_markdown_preserves_structure() {
+        let html = r#"<h1>Title</h1><script>alert(1)</script>
+<p>Intro with <a href="https://example.com/a?x=1&amp;y=2">a link</a>.</p>
+<svg><text>drop me</text></svg>
+<ul><li>One</li><li>Two<ol><li>Nested</li></ol></li></ul>
+<blockquote><p>Quoted</p><p>Twice</p></blockquote>
+<style>p { color: red }</style><h3>Next</h3><p>Tail</p>"#;
+        assert_eq!(
+            html_to_markdown(html, &default_markdown_tags()),
+            "# Title\n\nIntro with [a link](https://example.com/a?x=1&y=2).\n\n\
+             - One\n- Two\n  1. Nested\n\n> Quoted\n>\n> Twice\n\n### Next\n\nTail"
+        );
+    }
+
+    #[test]
+    fn test_html_to_markdown_respects_allowlist() {
+        let html =
+            r##"<h2>Head</h2><ul><li>item</li></ul><a href="/x">link</a> <a href="#top">top</a>"##;
+        let only_headings = vec!["h2".to_string()];
+        assert_eq!(
+            html_to_markdown(html, &only_headings),
+            "## Head\n\nitem\n\nlink top"
+        );
+    }
+
+    #[test]
+    fn test_looks_like_article() {
+        assert!(looks_like_article("<body><article>x</article></body>"));
+        assert!(looks_like_article("<h2>T</h2><p>a</p><p>b</p><p>c</p>"));
+        assert!(!looks_like_article("<div><a href='/'>home</a></div>"));
+    }
+
     #[test]
     fn test_extract_ddg_results() {
         let html = r#"
@@ -258,4 +662,41 @@ mod tests {
         // byte 4 is inside the multi-byte '只'
         assert_eq!(find_case_insensitive(s, "def", 4), Some(6));
     }
+
+    #[test]
+    fn test_extract_page_metadata() {
+        let html = r#"<html><head>
+            <TITLE> Launch &amp; Notes
+            </TITLE>
+            <meta name="description" content="Release notes for v2">
+            <meta property="og:title" content="Launch notes">
+            <meta property="og:image" content="https://example.com/a.png">
+            <meta property="og:image" content="https://example.com/b.png">
+            <meta property="og:description" content="">
+            <metadata property="og:type" content="ignored">
+            <link rel="stylesheet" href="/site.css">
+            <link rel="Canonical" href="https://example.com/notes">
+            </head><body><title>svg title</title><meta name="description" content="late"></body></html>"#;
+        let meta = extract_page_metadata(html);
+        assert_eq!(meta.title.as_deref(), Some("Launch & Notes"));
+        assert_eq!(meta.description.as_deref(), Some("Release notes for v2"));
+        assert_eq!(
+            meta.canonical_url.as_deref(),
+            Some("https://example.com/notes")
+        );
+        assert_eq!(meta.open_graph.len(), 2);
+        assert_eq!(meta.open_graph["title"], "Launch notes");
+        assert_eq!(meta.open_graph["image"], "https://example.com/a.png");
+    }
+
+    #[test]
+    fn test_extract_page_metadata_omits_missing_tags() {
+        let meta = extract_page_metadata("<p>no head here</p>");
+        assert!(meta.is_empty());
+        assert_eq!(serde_json::to_value(&meta).unwrap(), serde_json::json!({}));
+
+        let long = format!("<title>{}</title>", "é".repeat(400));
+        let meta = extract_page_metadata(&long);
+        assert!(meta.title.unwrap().len() <= MAX_METADATA_VALUE_BYTES);
+    }
 }
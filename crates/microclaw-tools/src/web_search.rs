@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use crate::web_html::SearchItem;
 use serde::{Deserialize, Serialize};
@@ -47,6 +48,15 @@ pub struct WebSearchConfig {
     /// Timeout in seconds for search requests (default: 15)
     #[serde(default = "default_timeout_secs")]
     pub timeout_secs: u64,
+    /// Default region, e.g. "us-en" (overridable per call)
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Default result language, e.g. "en" or "de-DE" (overridable per call)
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Default safe-search level (overridable per call)
+    #[serde(default)]
+    pub safe_search: Option<SafeSearch>,
 }
 
 impl Default for WebSearchConfig {
@@ -56,37 +66,187 @@ impl Default for WebSearchConfig {
             tavily_api_key: None,
             max_results: DEFAULT_MAX_RESULTS,
             timeout_secs: DEFAULT_TIMEOUT_SECS,
+            region: None,
+            language: None,
+            safe_search: None,
         }
     }
 }
 
+impl WebSearchConfig {
+    pub fn normalize(&mut self) {
+        self.region = normalize_locale(self.region.take());
+        self.language = normalize_locale(self.language.take());
+    }
+
+    /// Config defaults overlaid with per-call options.
+    pub fn options_with(&self, overrides: &SearchOptions) -> SearchOptions {
+        SearchOptions {
+            region: overrides.region.clone().or_else(|| self.region.clone()),
+            language: overrides.language.clone().or_else(|| self.language.clone()),
+            safe_search: overrides.safe_search.or(self.safe_search),
+        }
+    }
+}
+
+fn normalize_locale(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().replace('_', "-"))
+        .filter(|v| !v.is_empty())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SafeSearch {
+    Off,
+    Moderate,
+    Strict,
+}
+
+impl SafeSearch {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(Self::Off),
+            "moderate" => Some(Self::Moderate),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+
+    /// SearXNG `safesearch` level (0, 1, 2).
+    fn searxng_level(self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Moderate => 1,
+            Self::Strict => 2,
+        }
+    }
+}
+
+/// Locale and filtering options for a single search; unset fields use backend defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub region: Option<String>,
+    pub language: Option<String>,
+    pub safe_search: Option<SafeSearch>,
+}
+
+impl SearchOptions {
+    /// Language to request, falling back to the one implied by a `cc-ll` region.
+    fn effective_language(&self) -> Option<String> {
+        if let Some(lang) = &self.language {
+            return Some(lang.clone());
+        }
+        let (country, lang) = self.region.as_deref()?.split_once('-')?;
+        if country.len() != 2 || lang.len() != 2 || country.eq_ignore_ascii_case("wt") {
+            return None;
+        }
+        Some(format!(
+            "{}-{}",
+            lang.to_ascii_lowercase(),
+            country.to_ascii_uppercase()
+        ))
+    }
+}
+
 /// Search result from any provider
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct WebSearchResult {
     pub title: String,
     pub url: String,
+    /// Description or matching excerpt; empty when the backend has none.
     pub snippet: String,
+    /// Publication date as `YYYY-MM-DD` (or the backend's raw value if unparseable).
+    pub published_date: Option<String>,
+    /// Host of `url` without a leading `www.`.
+    pub source_domain: Option<String>,
 }
 
-impl From<SearchItem> for WebSearchResult {
-    fn from(item: SearchItem) -> Self {
+impl WebSearchResult {
+    pub fn new(title: String, url: String, snippet: String, published_date: Option<&str>) -> Self {
+        let source_domain = source_domain(&url);
         WebSearchResult {
-            title: item.title,
-            url: item.url,
-            snippet: item.snippet,
+            title,
+            url,
+            snippet,
+            published_date: published_date.and_then(normalize_published_date),
+            source_domain,
         }
     }
 }
 
+impl From<SearchItem> for WebSearchResult {
+    fn from(item: SearchItem) -> Self {
+        WebSearchResult::new(item.title, item.url, item.snippet, None)
+    }
+}
+
+fn source_domain(url: &str) -> Option<String> {
+    let host = reqwest::Url::parse(url)
+        .ok()?
+        .host_str()?
+        .to_ascii_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+/// Reduce the date formats backends use (RFC 3339, RFC 2822, naive
+/// date-times) to `YYYY-MM-DD`; other non-empty values pass through trimmed.
+fn normalize_published_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    let date = chrono::DateTime::parse_from_rfc3339(raw)
+        .or_else(|_| chrono::DateTime::parse_from_rfc2822(raw))
+        .map(|d| d.date_naive())
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f")
+                .or_else(|_| chrono::NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f"))
+                .map(|d| d.date())
+                .ok()
+        })
+        .or_else(|| chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d").ok());
+    Some(match date {
+        Some(d) => d.format("%Y-%m-%d").to_string(),
+        None => raw.to_string(),
+    })
+}
+
+/// One entry of a SearXNG or Tavily `results` array; `date_key` names the
+/// backend's publication date field.
+fn parse_json_result(item: &serde_json::Value, date_key: &str) -> Option<WebSearchResult> {
+    let title = item.get("title")?.as_str()?.to_string();
+    let url = item.get("url")?.as_str()?.to_string();
+    let snippet = item
+        .get("content")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let published_date = item.get(date_key).and_then(|v| v.as_str());
+    Some(WebSearchResult::new(title, url, snippet, published_date))
+}
+
 /// Search the web using configured providers.
 /// Priority: SearXNG -> Tavily (fallback)
 pub async fn search(query: &str, config: &WebSearchConfig) -> Result<Vec<WebSearchResult>, String> {
+    search_with_options(query, config, &SearchOptions::default()).await
+}
+
+/// Like [`search`], with per-call region/language/safe-search overriding config defaults.
+pub async fn search_with_options(
+    query: &str,
+    config: &WebSearchConfig,
+    overrides: &SearchOptions,
+) -> Result<Vec<WebSearchResult>, String> {
     let timeout_secs = config.timeout_secs.max(1);
     let max_results = config.max_results.max(1);
+    let options = config.options_with(overrides);
 
     // Try SearXNG first if configured
     if let Some(ref endpoint) = config.searxng_endpoint {
-        match search_searxng(endpoint, query, timeout_secs, max_results).await {
+        match search_searxng(endpoint, query, timeout_secs, max_results, &options).await {
             Ok(results) => return Ok(results),
             Err(e) => {
                 tracing::warn!("SearXNG search failed: {e}, trying fallback");
@@ -96,19 +256,147 @@ pub async fn search(query: &str, config: &WebSearchConfig) -> Result<Vec<WebSear
 
     // Fallback to Tavily if configured
     if let Some(ref api_key) = config.tavily_api_key {
+        if options != SearchOptions::default() {
+            // Tavily has no region/language/safe-search parameters.
+            tracing::debug!("Tavily ignores region/language/safe_search options");
+        }
         return search_tavily(api_key, query, timeout_secs, max_results).await;
     }
 
     Err("No web search provider configured. Set searxng_endpoint or tavily_api_key.".to_string())
 }
 
-/// Search using SearXNG JSON API
-pub async fn search_searxng(endpoint: &str, query: &str, timeout_secs: u64, max_results: usize) -> Result<Vec<WebSearchResult>, String> {
-    let client = http_client(timeout_secs);
+/// Most queries kept by the search cache; the oldest is evicted first.
+const SEARCH_CACHE_MAX_ENTRIES: usize = 256;
+
+/// How a [`search_with_cache`] result was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebSearchCacheStatus {
+    /// Returned by the search backend.
+    Fetched,
+    /// Served from the cache within its TTL.
+    Cached,
+}
 
-    // Build URL with query parameters
+impl WebSearchCacheStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebSearchCacheStatus::Fetched => "fetched",
+            WebSearchCacheStatus::Cached => "cached",
+        }
+    }
+}
+
+struct SearchCacheEntry {
+    fetched_at: Instant,
+    results: Vec<WebSearchResult>,
+}
+
+fn search_cache() -> &'static Mutex<HashMap<String, SearchCacheEntry>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, SearchCacheEntry>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A query as the cache sees it: lowercased, whitespace runs collapsed.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn search_cache_key(query: &str, options: &SearchOptions) -> String {
+    format!(
+        "{}\u{1f}{}\u{1f}{}\u{1f}{:?}",
+        normalize_query(query),
+        options.region.as_deref().unwrap_or_default(),
+        options.language.as_deref().unwrap_or_default(),
+        options.safe_search
+    )
+}
+
+fn cached_search_results(key: &str, ttl: Duration) -> Option<Vec<WebSearchResult>> {
+    let cache = search_cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .get(key)
+        .filter(|entry| entry.fetched_at.elapsed() < ttl)
+        .map(|entry| entry.results.clone())
+}
+
+fn store_search_results(key: String, results: Vec<WebSearchResult>) {
+    let mut cache = search_cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache.insert(
+        key,
+        SearchCacheEntry {
+            fetched_at: Instant::now(),
+            results,
+        },
+    );
+    while cache.len() > SEARCH_CACHE_MAX_ENTRIES {
+        let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, e)| e.fetched_at)
+            .map(|(k, _)| k.clone())
+        else {
+            break;
+        };
+        cache.remove(&oldest);
+    }
+}
+
+/// [`search_with_options`] behind a process-wide cache keyed by the normalized
+/// query and effective options, so a repeated search within `ttl` skips the
+/// backend. A zero `ttl` disables the cache; `fresh` skips the lookup but
+/// still stores the new results. Failures are not cached.
+pub async fn search_with_cache(
+    query: &str,
+    config: &WebSearchConfig,
+    overrides: &SearchOptions,
+    ttl: Duration,
+    fresh: bool,
+) -> Result<(Vec<WebSearchResult>, WebSearchCacheStatus), String> {
+    if ttl.is_zero() {
+        let results = search_with_options(query, config, overrides).await?;
+        return Ok((results, WebSearchCacheStatus::Fetched));
+    }
+    let key = search_cache_key(query, &config.options_with(overrides));
+    if !fresh {
+        if let Some(results) = cached_search_results(&key, ttl) {
+            return Ok((results, WebSearchCacheStatus::Cached));
+        }
+    }
+    let results = search_with_options(query, config, overrides).await?;
+    store_search_results(key, results.clone());
+    Ok((results, WebSearchCacheStatus::Fetched))
+}
+
+fn searxng_search_url(endpoint: &str, query: &str, options: &SearchOptions) -> String {
     let base = endpoint.trim_end_matches('/');
-    let url = format!("{}/search?q={}&format=json", base, urlencoding::encode(query));
+    let mut url = format!(
+        "{}/search?q={}&format=json",
+        base,
+        urlencoding::encode(query)
+    );
+    if let Some(lang) = options.effective_language() {
+        url.push_str(&format!("&language={}", urlencoding::encode(&lang)));
+    }
+    if let Some(level) = options.safe_search {
+        url.push_str(&format!("&safesearch={}", level.searxng_level()));
+    }
+    url
+}
+
+/// Search using SearXNG JSON API
+pub async fn search_searxng(
+    endpoint: &str,
+    query: &str,
+    timeout_secs: u64,
+    max_results: usize,
+    options: &SearchOptions,
+) -> Result<Vec<WebSearchResult>, String> {
+    let client = http_client(timeout_secs);
+    let url = searxng_search_url(endpoint, query, options);
 
     let resp = client
         .get(&url)
@@ -130,16 +418,7 @@ pub async fn search_searxng(endpoint: &str, query: &str, timeout_secs: u64, max_
         .and_then(|v| v.as_array())
         .map(|arr| {
             arr.iter()
-                .filter_map(|item| {
-                    let title = item.get("title")?.as_str()?.to_string();
-                    let url = item.get("url")?.as_str()?.to_string();
-                    let snippet = item
-                        .get("content")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    Some(WebSearchResult { title, url, snippet })
-                })
+                .filter_map(|item| parse_json_result(item, "publishedDate"))
                 .take(max_results)
                 .collect()
         })
@@ -180,16 +459,7 @@ pub async fn search_tavily(api_key: &str, query: &str, timeout_secs: u64, max_re
         .and_then(|v| v.as_array())
         .map(|arr| {
             arr.iter()
-                .filter_map(|item| {
-                    let title = item.get("title")?.as_str()?.to_string();
-                    let url = item.get("url")?.as_str()?.to_string();
-                    let snippet = item
-                        .get("content")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    Some(WebSearchResult { title, url, snippet })
-                })
+                .filter_map(|item| parse_json_result(item, "published_date"))
                 .collect()
         })
         .unwrap_or_default();
@@ -205,13 +475,171 @@ pub fn format_results(results: &[WebSearchResult]) -> String {
 
     let mut output = String::new();
     for (i, item) in results.iter().enumerate() {
-        output.push_str(&format!(
-            "{}. {}\n   {}\n   {}\n\n",
-            i + 1,
-            item.title,
-            item.url,
-            item.snippet
-        ));
+        output.push_str(&format!("{}. {}\n   {}\n", i + 1, item.title, item.url));
+        let source: Vec<&str> = [
+            item.source_domain.as_deref(),
+            item.published_date.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !source.is_empty() {
+            output.push_str(&format!("   {}\n", source.join(" · ")));
+        }
+        if !item.snippet.is_empty() {
+            output.push_str(&format!("   {}\n", item.snippet));
+        }
+        output.push('\n');
     }
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_cache_serves_repeats_until_fresh() {
+        assert_eq!(
+            normalize_query("  Rust\tAsync   RUNTIMES "),
+            "rust async runtimes"
+        );
+        // No backend is configured, so only a cache hit can succeed.
+        let config = WebSearchConfig::default();
+        let options = SearchOptions::default();
+        let query = format!("cache probe {}", uuid::Uuid::new_v4());
+        let hit = vec![WebSearchResult::new(
+            "Cached".into(),
+            "https://example.com".into(),
+            String::new(),
+            None,
+        )];
+        store_search_results(
+            search_cache_key(&query, &config.options_with(&options)),
+            hit.clone(),
+        );
+        let ttl = Duration::from_secs(60);
+
+        let repeat = format!("  {}  ", query.to_uppercase().replace(' ', "\n "));
+        let (results, status) = search_with_cache(&repeat, &config, &options, ttl, false)
+            .await
+            .unwrap();
+        assert_eq!(status, WebSearchCacheStatus::Cached);
+        assert_eq!(results, hit);
+
+        let strict = SearchOptions {
+            safe_search: Some(SafeSearch::Strict),
+            ..SearchOptions::default()
+        };
+        assert!(search_with_cache(&query, &config, &strict, ttl, false)
+            .await
+            .is_err());
+        assert!(search_with_cache(&query, &config, &options, ttl, true)
+            .await
+            .is_err());
+        assert!(
+            search_with_cache(&query, &config, &options, Duration::ZERO, false)
+                .await
+                .is_err()
+        );
+        assert!(cached_search_results(
+            &search_cache_key(&query, &options),
+            Duration::from_nanos(1)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_searxng_url_maps_locale_and_safe_search() {
+        let opts = SearchOptions {
+            region: Some("us-en".into()),
+            language: None,
+            safe_search: Some(SafeSearch::Strict),
+        };
+        assert_eq!(
+            searxng_search_url("https://s.example/", "rust lang", &opts),
+            "https://s.example/search?q=rust%20lang&format=json&language=en-US&safesearch=2"
+        );
+        let explicit = SearchOptions {
+            region: Some("us-en".into()),
+            language: Some("de".into()),
+            safe_search: None,
+        };
+        assert_eq!(
+            searxng_search_url("https://s.example", "x", &explicit),
+            "https://s.example/search?q=x&format=json&language=de"
+        );
+        assert_eq!(
+            searxng_search_url("https://s.example", "x", &SearchOptions::default()),
+            "https://s.example/search?q=x&format=json"
+        );
+    }
+
+    #[test]
+    fn test_json_results_carry_date_and_domain() {
+        let searxng = serde_json::json!({
+            "title": "Rust 2.0",
+            "url": "https://www.Blog.Example.com/rust",
+            "content": " Big news ",
+            "publishedDate": "2025-03-04T10:20:30"
+        });
+        let result = parse_json_result(&searxng, "publishedDate").unwrap();
+        assert_eq!(result.snippet, "Big news");
+        assert_eq!(result.published_date.as_deref(), Some("2025-03-04"));
+        assert_eq!(result.source_domain.as_deref(), Some("blog.example.com"));
+
+        let tavily = serde_json::json!({
+            "title": "t",
+            "url": "https://news.example.org/a",
+            "published_date": "Tue, 01 Jul 2025 08:00:00 GMT"
+        });
+        let result = parse_json_result(&tavily, "published_date").unwrap();
+        assert_eq!(result.published_date.as_deref(), Some("2025-07-01"));
+        assert_eq!(result.snippet, "");
+
+        let undated = serde_json::json!({"title": "t", "url": "x", "publishedDate": null});
+        let result = parse_json_result(&undated, "publishedDate").unwrap();
+        assert_eq!(result.published_date, None);
+        assert_eq!(result.source_domain, None);
+        assert_eq!(
+            normalize_published_date("last week").as_deref(),
+            Some("last week")
+        );
+    }
+
+    #[test]
+    fn test_format_results_shows_source_line_when_known() {
+        let results = vec![
+            WebSearchResult::new(
+                "A".into(),
+                "https://a.example/x".into(),
+                "snip".into(),
+                Some("2024-01-02"),
+            ),
+            WebSearchResult::new("B".into(), "not a url".into(), String::new(), None),
+        ];
+        assert_eq!(
+            format_results(&results),
+            "1. A\n   https://a.example/x\n   a.example · 2024-01-02\n   snip\n\n2. B\n   not a url\n\n"
+        );
+    }
+
+    #[test]
+    fn test_call_options_override_config_defaults() {
+        let mut config = WebSearchConfig {
+            region: Some(" de_de ".into()),
+            safe_search: Some(SafeSearch::Moderate),
+            ..WebSearchConfig::default()
+        };
+        config.normalize();
+        assert_eq!(config.region.as_deref(), Some("de-de"));
+        let merged = config.options_with(&SearchOptions {
+            safe_search: Some(SafeSearch::Off),
+            ..SearchOptions::default()
+        });
+        assert_eq!(merged.region.as_deref(), Some("de-de"));
+        assert_eq!(merged.safe_search, Some(SafeSearch::Off));
+        assert_eq!(SafeSearch::parse(" STRICT "), Some(SafeSearch::Strict));
+        assert_eq!(SafeSearch::parse("medium"), None);
+    }
+}
@@ -2,15 +2,20 @@ use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{info, warn};
+use tracing::{debug, info, warn, Instrument};
 
+use crate::config::ModelRole;
 use crate::embedding::EmbeddingProvider;
 use crate::hooks::HookOutcome;
 use crate::run_control;
 use crate::runtime::AppState;
+use crate::tools::ask_user::{ASK_USER_TOOL_NAME, AWAITING_USER_INPUT_KEY};
 use crate::tools::ToolAuthContext;
+use microclaw_channels::channel_adapter::{CardButton, CardButtonStyle, MessageCard};
+use microclaw_channels::inbound_queue::without_worker_slot;
 use microclaw_core::llm_types::{
-    ContentBlock, ImageSource, Message, MessageContent, ResponseContentBlock,
+    ContentBlock, FinishReason, ImageSource, Message, MessageContent, ResponseContentBlock,
+    ToolChoice, ToolDefinition,
 };
 use microclaw_core::text::floor_char_boundary;
 use microclaw_storage::db::{call_blocking, Database, StoredMessage};
@@ -21,7 +26,26 @@ pub struct AgentRequestContext<'a> {
     pub caller_channel: &'a str,
     pub chat_id: i64,
     pub chat_type: &'a str,
+    /// Stable id of the member who sent the triggering message, as stored in
+    /// `StoredMessage::sender_name`. `None` for scheduler and other system turns.
+    pub sender_id: Option<&'a str>,
+    /// The sender matched the channel's admin list at intake; tool calls get
+    /// control-chat privileges.
+    pub sender_is_admin: bool,
 }
+
+impl AgentRequestContext<'_> {
+    /// The sender whose history and memories this turn is scoped to, when
+    /// `group_user_isolation` applies.
+    pub(crate) fn isolated_sender(&self, config: &crate::config::Config) -> Option<&str> {
+        if config.group_user_isolation && self.chat_type == "group" {
+            self.sender_id.filter(|s| !s.is_empty())
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum AgentEvent {
     Iteration {
@@ -49,11 +73,35 @@ pub enum AgentEvent {
         content: String,
         tool_hint: bool,
     },
+    /// Interim status reported by a running tool, e.g. MCP progress
+    /// notifications.
+    ToolProgress {
+        name: String,
+        content: String,
+    },
+    /// The turn paused on a high-risk tool; the final response asks the user
+    /// to approve it. Channels with buttons can render `approval_card`.
+    ApprovalRequested {
+        tool_name: String,
+    },
     FinalResponse {
         text: String,
     },
 }
 
+/// Interactive rendering of the high-risk tool confirmation. The buttons send
+/// the same replies `is_explicit_user_approval` already understands.
+pub fn approval_card(tool_name: &str, text: &str) -> MessageCard {
+    MessageCard {
+        title: Some(format!("Approve '{tool_name}'?")),
+        body: text.to_string(),
+        buttons: vec![
+            CardButton::new("Approve", "approve", CardButtonStyle::Primary),
+            CardButton::new("Deny", "deny", CardButtonStyle::Danger),
+        ],
+    }
+}
+
 #[async_trait]
 pub trait AgentEngine: Send + Sync {
     async fn process(
@@ -110,6 +158,66 @@ pub async fn process_with_agent(
     process_with_agent_with_events(state, context, override_prompt, image_data, None).await
 }
 
+/// Whether `msg` is something the user said, as opposed to tool results the
+/// engine sends back under the user role.
+fn is_user_authored(msg: &Message) -> bool {
+    msg.role == "user"
+        && match &msg.content {
+            MessageContent::Text(_) => true,
+            MessageContent::Blocks(blocks) => !blocks
+                .iter()
+                .any(|b| matches!(b, ContentBlock::ToolResult { .. })),
+        }
+}
+
+/// Cut `messages` back to the latest user message and return the assistant
+/// turn (replies and tool exchanges) that followed it. `None` when the user
+/// message has no answer yet.
+pub(crate) fn rewind_last_assistant_turn(messages: &mut Vec<Message>) -> Option<Vec<Message>> {
+    let last_user = messages.iter().rposition(is_user_authored)?;
+    if last_user + 1 >= messages.len() {
+        return None;
+    }
+    Some(messages.split_off(last_user + 1))
+}
+
+/// Answer the latest user message again from the context before the previous
+/// answer, optionally on `model`. The previous answer stays in the chat's
+/// message history; only the model's session is rewound. `Ok(None)` when
+/// there is no answer to regenerate.
+pub async fn regenerate_last_response(
+    state: &AppState,
+    context: AgentRequestContext<'_>,
+    model: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    if context.isolated_sender(&state.config).is_some() {
+        anyhow::bail!("regenerating is not available while group_user_isolation is on");
+    }
+    let chat_id = context.chat_id;
+    let Some((json, _)) =
+        call_blocking(state.db.clone(), move |db| db.load_session(chat_id)).await?
+    else {
+        return Ok(None);
+    };
+    let mut messages: Vec<Message> = serde_json::from_str(&json).unwrap_or_default();
+    let Some(previous) = rewind_last_assistant_turn(&mut messages) else {
+        return Ok(None);
+    };
+    info!(
+        "Regenerating last answer for chat {chat_id}: rewound {} message(s){}",
+        previous.len(),
+        model.map(|m| format!(", model {m}")).unwrap_or_default()
+    );
+    let json = serde_json::to_string(&messages)?;
+    call_blocking(state.db.clone(), move |db| db.save_session(chat_id, &json)).await?;
+    if let Some(model) = model {
+        run_control::set_next_model(context.caller_channel, chat_id, model).await;
+    }
+    process_with_agent(state, context, None, None)
+        .await
+        .map(Some)
+}
+
 pub async fn process_with_agent_with_events(
     state: &AppState,
     context: AgentRequestContext<'_>,
@@ -132,6 +240,44 @@ pub async fn process_with_agent_with_events(
     let (run_id, cancelled, notify) =
         run_control::register_run(context.caller_channel, context.chat_id, source_message_id).await;
     let engine = DefaultAgentEngine;
+    let llm_context = crate::llm::LlmRequestContext::new_turn();
+    let turn_span = tracing::info_span!(
+        "agent_turn",
+        channel = context.caller_channel,
+        chat_id = context.chat_id,
+        correlation_id = %llm_context.correlation_id,
+    );
+    let turn = run_control::with_run_id(
+        run_id,
+        crate::llm::with_request_context(
+            llm_context,
+            engine.process_with_events(state, context, override_prompt, image_data, event_tx),
+        ),
+    )
+    .instrument(turn_span);
+    let serialize = state
+        .config
+        .serialize_per_chat_for_channel(context.caller_channel)
+        .unwrap_or(false);
+    let turn = async {
+        // Held until the turn ends; `/stop` also cancels a turn still waiting here.
+        let _chat_turn = if serialize {
+            let lock = run_control::lock_chat_turn(context.caller_channel, context.chat_id);
+            if run_control::is_chat_turn_busy(context.caller_channel, context.chat_id).await {
+                info!(
+                    "Chat {} on {}: waiting for the running turn to finish",
+                    context.chat_id, context.caller_channel
+                );
+                // A queued inbound job waits without taking a worker slot from other chats.
+                Some(without_worker_slot(lock).await)
+            } else {
+                Some(lock.await)
+            }
+        } else {
+            None
+        };
+        turn.await
+    };
     let result = tokio::select! {
         _ = async {
             if run_control::is_cancelled(&cancelled) {
@@ -144,12 +290,49 @@ pub async fn process_with_agent_with_events(
             }
             Ok(run_control::STOPPED_TEXT.to_string())
         }
-        out = engine.process_with_events(state, context, override_prompt, image_data, event_tx) => out,
+        out = turn => out,
     };
     run_control::unregister_run(context.caller_channel, context.chat_id, run_id).await;
+    state.tools.clear_scratch(context.chat_id, run_id);
     result
 }
 
+/// Under `serialize_per_chat`, react to an inbound message that will wait for
+/// the chat's running turn, with the channel's `serialize_ack_reaction`.
+pub async fn acknowledge_queued_message(
+    state: &AppState,
+    caller_channel: &str,
+    chat_id: i64,
+    external_chat_id: &str,
+    message_id: &str,
+) {
+    if !state
+        .config
+        .serialize_per_chat_for_channel(caller_channel)
+        .unwrap_or(false)
+    {
+        return;
+    }
+    let Some(emoji) = state
+        .config
+        .serialize_ack_reaction_for_channel(caller_channel)
+    else {
+        return;
+    };
+    if !run_control::is_chat_turn_busy(caller_channel, chat_id).await {
+        return;
+    }
+    let Some(adapter) = state.channel_registry.get(caller_channel) else {
+        return;
+    };
+    if let Err(e) = adapter
+        .add_reaction(external_chat_id, message_id, &emoji)
+        .await
+    {
+        warn!("Failed to acknowledge queued message {message_id} in chat {chat_id}: {e}");
+    }
+}
+
 fn with_high_risk_approval_marker(input: &Value) -> Value {
     let mut approved_input = input.clone();
     if let Some(obj) = approved_input.as_object_mut() {
@@ -280,6 +463,75 @@ fn is_slash_command_text(text: &str) -> bool {
     text.trim_start().starts_with('/')
 }
 
+/// Drop a forced tool choice naming a tool this turn does not offer.
+fn resolve_tool_choice(
+    choice: Option<ToolChoice>,
+    tool_defs: &[ToolDefinition],
+) -> Option<ToolChoice> {
+    match choice {
+        Some(ToolChoice::Tool(name)) if !tool_defs.iter().any(|d| d.name == name) => {
+            warn!("Ignoring tool_choice for unavailable tool '{name}'");
+            None
+        }
+        other => other,
+    }
+}
+
+/// A turn paused by `ask_user` is saved ending in the question's tool result.
+/// Once `timeout_secs` has passed since it was saved the pending turn is
+/// cancelled: the result is rewritten so the model does not read the next
+/// message as the answer. Returns whether a question was cancelled.
+fn cancel_expired_user_question(
+    messages: &mut [Message],
+    paused_at: &str,
+    timeout_secs: u64,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    let Ok(paused_at) = chrono::DateTime::parse_from_rfc3339(paused_at) else {
+        return false;
+    };
+    if now.signed_duration_since(paused_at).num_seconds() < timeout_secs as i64 {
+        return false;
+    }
+    let [.., assistant, last] = messages else {
+        return false;
+    };
+    let (MessageContent::Blocks(calls), MessageContent::Blocks(results)) =
+        (&assistant.content, &mut last.content)
+    else {
+        return false;
+    };
+    if assistant.role != "assistant" || last.role != "user" {
+        return false;
+    }
+    let question_ids: Vec<&str> = calls
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { id, name, .. } if name == ASK_USER_TOOL_NAME => {
+                Some(id.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    let mut cancelled = false;
+    for block in results.iter_mut() {
+        if let ContentBlock::ToolResult {
+            tool_use_id,
+            content,
+            is_error,
+        } = block
+        {
+            if question_ids.contains(&tool_use_id.as_str()) && is_error.is_none() {
+                *content = format!(
+                    "The question got no reply within {timeout_secs}s and was cancelled. Treat the next message as a new request, not as the answer."
+                );
+                cancelled = true;
+            }
+        }
+    }
+    cancelled
+}
+
 async fn persist_session_with_skill_envs(
     state: &AppState,
     chat_id: i64,
@@ -360,6 +612,7 @@ fn jaccard_similarity_ratio(a: &str, b: &str) -> f64 {
 async fn maybe_handle_explicit_memory_command(
     state: &AppState,
     chat_id: i64,
+    sender_id: Option<&str>,
     override_prompt: Option<&str>,
     image_data: Option<(String, String)>,
 ) -> anyhow::Result<Option<String>> {
@@ -374,7 +627,11 @@ async fn maybe_handle_explicit_memory_command(
     let Some(last_user_text) = latest_user
         .into_iter()
         .rev()
-        .find(|m| !m.is_from_bot && !is_slash_command_text(&m.content))
+        .find(|m| {
+            !m.is_from_bot
+                && !is_slash_command_text(&m.content)
+                && sender_id.is_none_or(|s| m.sender_name == s)
+        })
         .map(|m| m.content)
     else {
         return Ok(None);
@@ -390,10 +647,14 @@ async fn maybe_handle_explicit_memory_command(
         ));
     }
 
-    let existing = state
+    // An isolated sender only merges into, and supersedes, their own memories.
+    let existing: Vec<_> = state
         .memory_backend
         .get_all_memories_for_chat(Some(chat_id))
-        .await?;
+        .await?
+        .into_iter()
+        .filter(|m| sender_id.is_none() || m.sender_id.as_deref() == sender_id)
+        .collect();
     let explicit_topic = memory_quality::memory_topic_key(&explicit_content);
     if let Some(dup) = existing.iter().find(|m| {
         !m.is_archived
@@ -452,6 +713,12 @@ async fn maybe_handle_explicit_memory_command(
             0.95,
         )
         .await?;
+    if sender_id.is_some() {
+        state
+            .memory_backend
+            .set_memory_sender_id(inserted_id, sender_id)
+            .await?;
+    }
 
     #[cfg(feature = "sqlite-vec")]
     {
@@ -473,6 +740,126 @@ async fn maybe_handle_explicit_memory_command(
     )))
 }
 
+/// How many times one turn continues a reply cut at the output token limit
+/// before returning it with a truncation note.
+const MAX_TOKENS_CONTINUATIONS: usize = 2;
+
+/// Reply length limit for a turn: the chat's `/verbosity` setting (0 meaning
+/// none), else `max_response_tokens` for the channel.
+/// The chat's `/verbosity` limit wins over an active skill's
+/// `max_response_tokens`, which wins over the channel's configured limit.
+pub(crate) async fn resolve_max_response_tokens(
+    state: &AppState,
+    caller_channel: &str,
+    chat_id: i64,
+    skill_limit: Option<u32>,
+) -> Option<u32> {
+    let chat_limit = call_blocking(state.db.clone(), move |db| {
+        db.get_chat_max_response_tokens(chat_id)
+    })
+    .await
+    .ok()
+    .flatten();
+    match chat_limit {
+        Some(limit) => (limit > 0).then_some(limit),
+        None => skill_limit.or_else(|| {
+            state
+                .config
+                .max_response_tokens_for_channel(caller_channel)
+                .ok()
+                .flatten()
+        }),
+    }
+}
+
+fn reply_length_section(limit: u32) -> String {
+    format!(
+        "\n\n# Reply Length\n\nKeep each reply within about {limit} tokens (roughly {} words). Answer directly without preamble or recap; offer more detail instead of writing it out.",
+        limit * 3 / 4
+    )
+}
+
+/// True when `text` stops without closing punctuation, as a reply cut at a
+/// token limit usually does.
+fn ends_mid_sentence(text: &str) -> bool {
+    let trimmed = text.trim_end();
+    !trimmed.is_empty()
+        && !trimmed.ends_with([
+            '.', '!', '?', ':', ')', '"', '\'', '`', '*', '…', '。', '！', '？', '）', '」',
+        ])
+}
+
+/// Cost and timing of one turn, shown by `/debug on` in control chats.
+#[derive(Debug, Default)]
+struct TurnStats {
+    llm_calls: usize,
+    input_tokens: i64,
+    output_tokens: i64,
+    /// `None` once a call's model has no `model_prices` entry.
+    cost_usd: Option<f64>,
+    llm_time: std::time::Duration,
+    tool_calls: usize,
+    tool_time: std::time::Duration,
+}
+
+impl TurnStats {
+    fn new() -> Self {
+        Self {
+            cost_usd: Some(0.0),
+            ..Self::default()
+        }
+    }
+
+    fn record_llm_call(
+        &mut self,
+        elapsed: std::time::Duration,
+        tokens: Option<(i64, i64)>,
+        cost_usd: Option<f64>,
+    ) {
+        self.llm_calls += 1;
+        self.llm_time += elapsed;
+        if let Some((input, output)) = tokens {
+            self.input_tokens += input;
+            self.output_tokens += output;
+        }
+        self.cost_usd = match (self.cost_usd, cost_usd) {
+            (Some(total), Some(cost)) => Some(total + cost),
+            _ => None,
+        };
+    }
+
+    fn record_tool_call(&mut self, elapsed: std::time::Duration) {
+        self.tool_calls += 1;
+        self.tool_time += elapsed;
+    }
+
+    fn footer(&self) -> String {
+        let cost = match self.cost_usd {
+            Some(cost) => format!("~${cost:.4}"),
+            None => "cost n/a".to_string(),
+        };
+        format!(
+            "[debug] tokens {} in / {} out · {cost} · llm {}× {:.1}s · tools {}× {:.1}s",
+            self.input_tokens,
+            self.output_tokens,
+            self.llm_calls,
+            self.llm_time.as_secs_f64(),
+            self.tool_calls,
+            self.tool_time.as_secs_f64()
+        )
+    }
+}
+
+/// Whether replies in this chat end with the `/debug` footer; control chats only.
+async fn debug_footer_enabled(state: &AppState, chat_id: i64) -> bool {
+    state.config.control_chat_ids.contains(&chat_id)
+        && call_blocking(state.db.clone(), move |db| {
+            db.get_chat_debug_footer(chat_id)
+        })
+        .await
+        .unwrap_or(false)
+}
+
 pub(crate) async fn process_with_agent_impl(
     state: &AppState,
     context: AgentRequestContext<'_>,
@@ -481,16 +868,32 @@ pub(crate) async fn process_with_agent_impl(
     event_tx: Option<&UnboundedSender<AgentEvent>>,
 ) -> anyhow::Result<String> {
     let chat_id = context.chat_id;
+    let isolated_sender = context.isolated_sender(&state.config);
 
-    if let Some(reply) =
-        maybe_handle_explicit_memory_command(state, chat_id, override_prompt, image_data.clone())
-            .await?
+    if let Some(reply) = maybe_handle_explicit_memory_command(
+        state,
+        chat_id,
+        isolated_sender,
+        override_prompt,
+        image_data.clone(),
+    )
+    .await?
     {
         return Ok(reply);
     }
 
-    // Load messages first so we can use the latest user message as the relevance query
-    let mut messages = if let Some((json, updated_at)) =
+    let serialized = isolated_sender.is_none()
+        && state
+            .config
+            .serialize_per_chat_for_channel(context.caller_channel)
+            .unwrap_or(false);
+    let loaded_at = chrono::Utc::now().to_rfc3339();
+
+    // Load messages first so we can use the latest user message as the relevance query.
+    // The chat session is shared by all members, so isolated turns rebuild from DB history.
+    let mut messages = if let Some(sender_id) = isolated_sender {
+        load_sender_messages_from_db(state, chat_id, sender_id, context.caller_channel).await?
+    } else if let Some((json, updated_at)) =
         call_blocking(state.db.clone(), move |db| db.load_session(chat_id)).await?
     {
         // Session exists — deserialize and append new user messages
@@ -501,10 +904,29 @@ pub(crate) async fn process_with_agent_impl(
             // Corrupted session, fall back to DB history
             load_messages_from_db(state, chat_id, context.chat_type, context.caller_channel).await?
         } else {
-            // Get new user messages since session was last saved
-            let updated_at_cloned = updated_at.clone();
+            if cancel_expired_user_question(
+                &mut session_messages,
+                &updated_at,
+                state.config.ask_user_timeout_secs,
+                chrono::Utc::now(),
+            ) {
+                info!("Pending ask_user question for chat {chat_id} timed out; turn cancelled");
+            }
+            // Get new user messages since session was last saved. A serialized
+            // turn that ran meanwhile saved after reading its messages, so
+            // anything sent while it ran is only newer than its read.
+            let mut since = updated_at.clone();
+            if serialized {
+                if let Some(previous) =
+                    run_control::messages_loaded_at(context.caller_channel, chat_id).await
+                {
+                    if previous < since {
+                        since = previous;
+                    }
+                }
+            }
             let new_msgs = call_blocking(state.db.clone(), move |db| {
-                db.get_new_user_messages_since(chat_id, &updated_at_cloned)
+                db.get_new_user_messages_since(chat_id, &since)
             })
             .await?;
             for stored_msg in &new_msgs {
@@ -542,6 +964,9 @@ pub(crate) async fn process_with_agent_impl(
         // No session — build from DB history
         load_messages_from_db(state, chat_id, context.chat_type, context.caller_channel).await?
     };
+    if serialized {
+        run_control::note_messages_loaded(context.caller_channel, chat_id, &loaded_at).await;
+    }
 
     // If override_prompt is provided (from scheduler), add it as a user message
     if let Some(prompt) = override_prompt {
@@ -577,16 +1002,33 @@ pub(crate) async fn process_with_agent_impl(
 
     // Build system prompt
     let file_memory = state.memory.build_memory_context(chat_id);
-    let db_memory = build_db_memory_context(
-        &state.memory_backend,
-        &state.db,
-        &state.embedding,
-        chat_id,
-        &query,
-        state.config.memory_token_budget,
-    )
-    .await;
-    let memory_context = format!("{}{}", file_memory, db_memory);
+    let db_memory = if state.config.memory_auto_inject {
+        build_db_memory_context(
+            &state.memory_backend,
+            &state.db,
+            &state.embedding,
+            chat_id,
+            context.sender_id.filter(|s| !s.is_empty()),
+            &query,
+            state.config.memory_token_budget,
+            state.config.memory_auto_inject_top_k,
+        )
+        .await
+    } else {
+        String::new()
+    };
+    let pinned_memory = if state.config.memory_pinned_token_budget > 0 {
+        build_pinned_memory_context(
+            &state.memory_backend,
+            chat_id,
+            context.sender_id.filter(|s| !s.is_empty()),
+            state.config.memory_pinned_token_budget,
+        )
+        .await
+    } else {
+        String::new()
+    };
+    let memory_context = format!("{}{}{}", file_memory, pinned_memory, db_memory);
     let skills_catalog = state.skills.build_skills_catalog();
     let soul_content = load_soul_content(&state.config, chat_id);
     let bot_username = state
@@ -610,27 +1052,12 @@ pub(crate) async fn process_with_agent_impl(
     .await;
     append_plugin_context_sections(&mut system_prompt, &plugin_context);
 
-    // If image_data is present, convert the last user message to a blocks-based message with the image
     if let Some((base64_data, media_type)) = image_data {
-        if let Some(last_msg) = messages.last_mut() {
-            if last_msg.role == "user" {
-                let text_content = match &last_msg.content {
-                    MessageContent::Text(t) => t.clone(),
-                    _ => String::new(),
-                };
-                let mut blocks = vec![ContentBlock::Image {
-                    source: ImageSource {
-                        source_type: "base64".into(),
-                        media_type,
-                        data: base64_data,
-                    },
-                }];
-                if !text_content.is_empty() {
-                    blocks.push(ContentBlock::Text { text: text_content });
-                }
-                last_msg.content = MessageContent::Blocks(blocks);
-            }
-        }
+        attach_user_image(
+            &mut messages,
+            ImageSource::base64(media_type, base64_data),
+            state.config.llm_vision,
+        );
     }
 
     // Ensure we have at least one message
@@ -656,19 +1083,48 @@ pub(crate) async fn process_with_agent_impl(
         .await;
     }
 
+    if state.config.skills_live_refresh {
+        system_prompt.push_str(&updated_skill_instructions_section(
+            &state.skills,
+            &messages,
+        ));
+    }
+
+    let mut skill_overrides = SkillOverrides::from_history(&state.skills, &messages);
+    let mut response_token_cap = resolve_max_response_tokens(
+        state,
+        context.caller_channel,
+        chat_id,
+        skill_overrides.max_response_tokens,
+    )
+    .await;
+    if let Some(limit) = response_token_cap {
+        system_prompt.push_str(&reply_length_section(limit));
+    }
+
     let tool_defs = state.tools.definitions().to_vec();
     let tool_auth = ToolAuthContext {
         caller_channel: context.caller_channel.to_string(),
         caller_chat_id: chat_id,
         control_chat_ids: state.config.control_chat_ids.clone(),
+        caller_is_admin: context.sender_is_admin,
+        caller_sender_id: context.sender_id.map(str::to_string),
+        run_id: run_control::current_run_id(),
     };
 
+    let debug_footer = debug_footer_enabled(state, chat_id).await;
+    let mut turn_stats = TurnStats::new();
+
     // Agentic tool-use loop
     let mut failed_tools: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
     let mut failed_tool_details: Vec<String> = Vec::new();
     let mut seen_failed_tool_details: std::collections::HashSet<String> =
         std::collections::HashSet::new();
     let mut empty_visible_reply_retry_attempted = false;
+    // Text of replies cut at the output token limit, continued in later iterations.
+    let mut truncated_reply = String::new();
+    let mut max_tokens_continuations = 0usize;
+    let mut length_limit_completion_attempted = false;
     let mut skill_envs: HashMap<String, String> = {
         let db = state.db.clone();
         call_blocking(db, move |db| db.load_session_skill_envs(chat_id))
@@ -676,12 +1132,36 @@ pub(crate) async fn process_with_agent_impl(
             .and_then(|json| serde_json::from_str(&json).ok())
             .unwrap_or_default()
     };
-    let effective_model = state
+    // `/retry <model>` overrides the model for this turn only; otherwise an
+    // active skill's model wins over the channel's.
+    let retry_model = run_control::take_next_model(context.caller_channel, chat_id).await;
+    let channel_model = state
         .llm_model_overrides
         .get(context.caller_channel)
         .cloned()
-        .unwrap_or_else(|| state.config.model.clone());
-    for iteration in 0..state.config.max_tool_iterations {
+        .unwrap_or_else(|| state.config.model_for_role(ModelRole::Main).to_string());
+    let turn_model = |overrides: &SkillOverrides| {
+        retry_model
+            .clone()
+            .or_else(|| overrides.model.clone())
+            .unwrap_or_else(|| channel_model.clone())
+    };
+    let mut effective_model = turn_model(&skill_overrides);
+    if state.config.deterministic {
+        info!(
+            "Deterministic turn chat_id={} model={} temperature=0 seed={}",
+            chat_id, effective_model, state.config.deterministic_seed
+        );
+    }
+    // Set by `/tools` for this turn or by an activated skill for the next call.
+    let mut pending_tool_choice = resolve_tool_choice(
+        run_control::take_next_tool_choice(context.caller_channel, chat_id).await,
+        &tool_defs,
+    );
+    for iteration in 0.. {
+        if iteration >= skill_overrides.max_tool_iterations(&state.config) {
+            break;
+        }
         if let Some(tx) = event_tx {
             let _ = tx.send(AgentEvent::Iteration {
                 iteration: iteration + 1,
@@ -720,38 +1200,85 @@ pub(crate) async fn process_with_agent_impl(
                 }
             }
         }
-        let response = if let Some(tx) = event_tx {
-            let (llm_tx, mut llm_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
-            let forward_tx = tx.clone();
-            let forward_handle = tokio::spawn(async move {
-                while let Some(delta) = llm_rx.recv().await {
-                    let _ = forward_tx.send(AgentEvent::TextDelta { delta });
-                }
-            });
-            let response = state
-                .llm
-                .send_message_stream_with_model(
-                    &system_prompt,
-                    messages.clone(),
-                    Some(tool_defs.clone()),
-                    Some(&llm_tx),
-                    Some(&effective_model),
+        let tool_choice = pending_tool_choice.clone().unwrap_or_default();
+        // A forced call is consumed by one request; `none` holds for the whole turn.
+        if matches!(tool_choice, ToolChoice::Required | ToolChoice::Tool(_)) {
+            pending_tool_choice = None;
+        }
+        // The reply length limit also caps tool calls; one cut off by it is
+        // sent again without the cap so its input arrives whole.
+        let mut call_token_cap = response_token_cap;
+        let llm_started = std::time::Instant::now();
+        let response = loop {
+            let response = if let Some(tx) = event_tx {
+                let (llm_tx, mut llm_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+                let forward_tx = tx.clone();
+                let forward_handle = tokio::spawn(async move {
+                    while let Some(delta) = llm_rx.recv().await {
+                        let _ = forward_tx.send(AgentEvent::TextDelta { delta });
+                    }
+                });
+                let response = crate::llm::with_output_token_cap(
+                    call_token_cap,
+                    crate::llm::with_tool_choice(
+                        tool_choice.clone(),
+                        state.llm.send_message_stream_with_model(
+                            &system_prompt,
+                            messages.clone(),
+                            Some(tool_defs.clone()),
+                            Some(&llm_tx),
+                            Some(&effective_model),
+                        ),
+                    ),
                 )
                 .await?;
-            drop(llm_tx);
-            let _ = forward_handle.await;
-            response
-        } else {
-            state
-                .llm
-                .send_message_with_model(
-                    &system_prompt,
-                    messages.clone(),
-                    Some(tool_defs.clone()),
-                    Some(&effective_model),
+                drop(llm_tx);
+                let _ = forward_handle.await;
+                response
+            } else {
+                crate::llm::with_output_token_cap(
+                    call_token_cap,
+                    crate::llm::with_tool_choice(
+                        tool_choice.clone(),
+                        state.llm.send_message_with_model(
+                            &system_prompt,
+                            messages.clone(),
+                            Some(tool_defs.clone()),
+                            Some(&effective_model),
+                        ),
+                    ),
                 )
                 .await?
+            };
+            if call_token_cap.is_some()
+                && response.finish_reason() == FinishReason::MaxTokens
+                && response
+                    .content
+                    .iter()
+                    .any(|block| matches!(block, ResponseContentBlock::ToolUse { .. }))
+            {
+                warn!(
+                    "Tool call cut at the reply length limit; retrying without it chat_id={}",
+                    chat_id
+                );
+                call_token_cap = None;
+                continue;
+            }
+            break response;
         };
+        let usage_tokens = response
+            .usage
+            .as_ref()
+            .map(|u| (i64::from(u.input_tokens), i64::from(u.output_tokens)));
+        turn_stats.record_llm_call(
+            llm_started.elapsed(),
+            usage_tokens,
+            usage_tokens.and_then(|(input, output)| {
+                state
+                    .config
+                    .estimate_cost_usd(&effective_model, input, output)
+            }),
+        );
 
         if let Some(usage) = &response.usage {
             let channel = context.caller_channel.to_string();
@@ -774,15 +1301,28 @@ pub(crate) async fn process_with_agent_impl(
             .await;
         }
 
-        let stop_reason = response.stop_reason.as_deref().unwrap_or("end_turn");
+        let finish_reason = response.finish_reason();
         info!(
-            "Agent iteration {} stop_reason={} chat_id={}",
+            "Agent iteration {} finish_reason={} chat_id={}",
             iteration + 1,
-            stop_reason,
+            finish_reason,
             chat_id
         );
 
-        if stop_reason == "end_turn" || stop_reason == "max_tokens" {
+        surface_thinking(
+            &state.config,
+            chat_id,
+            &response_thinking(&response.content),
+            event_tx,
+        );
+
+        if matches!(
+            finish_reason,
+            FinishReason::EndTurn
+                | FinishReason::MaxTokens
+                | FinishReason::StopSequence
+                | FinishReason::ContentFilter
+        ) {
             let text = response
                 .content
                 .iter()
@@ -793,11 +1333,64 @@ pub(crate) async fn process_with_agent_impl(
                 .collect::<Vec<_>>()
                 .join("");
 
+            // Under a reply length limit, a reply cut mid-sentence gets one
+            // short completion instead of a full continuation.
+            let length_limited = finish_reason == FinishReason::MaxTokens
+                && call_token_cap.is_some()
+                && !text.trim().is_empty();
+            if length_limited && !length_limit_completion_attempted && ends_mid_sentence(&text) {
+                length_limit_completion_attempted = true;
+                info!(
+                    "Reply hit the reply length limit mid-sentence; asking for a completion chat_id={}",
+                    chat_id
+                );
+                truncated_reply.push_str(&text);
+                messages.push(Message {
+                    role: "assistant".into(),
+                    content: MessageContent::Text(text),
+                });
+                messages.push(Message {
+                    role: "user".into(),
+                    content: MessageContent::Text(
+                        "[runtime_guard]: Your previous reply reached this chat's reply length limit. Finish the sentence you were writing in a few words, continuing exactly where it stopped, and end the reply there."
+                            .to_string(),
+                    ),
+                });
+                continue;
+            }
+            // A reply cut at the output limit is continued rather than returned
+            // truncated; the pieces are joined into one final reply.
+            if finish_reason == FinishReason::MaxTokens
+                && !length_limited
+                && !text.trim().is_empty()
+                && max_tokens_continuations < MAX_TOKENS_CONTINUATIONS
+            {
+                max_tokens_continuations += 1;
+                warn!(
+                    "Reply hit the output token limit; continuing ({}/{}) chat_id={}",
+                    max_tokens_continuations, MAX_TOKENS_CONTINUATIONS, chat_id
+                );
+                truncated_reply.push_str(&text);
+                messages.push(Message {
+                    role: "assistant".into(),
+                    content: MessageContent::Text(text),
+                });
+                messages.push(Message {
+                    role: "user".into(),
+                    content: MessageContent::Text(
+                        "[runtime_guard]: Your previous reply was cut off at the output token limit. Continue exactly where it stopped, without repeating anything. If much remains, wrap up with a concise summary instead."
+                            .to_string(),
+                    ),
+                });
+                continue;
+            }
+            let reply_text = format!("{truncated_reply}{text}");
+
             // Strip <think> blocks unless show_thinking is enabled
             let display_text = if state.config.show_thinking {
-                text.clone()
+                reply_text
             } else {
-                strip_thinking(&text)
+                strip_thinking(&reply_text)
             };
             if display_text.trim().is_empty() && !empty_visible_reply_retry_attempted {
                 empty_visible_reply_retry_attempted = true;
@@ -827,13 +1420,21 @@ pub(crate) async fn process_with_agent_impl(
             persist_session_with_skill_envs(state, chat_id, &mut messages, &skill_envs).await;
 
             let final_text = if display_text.trim().is_empty() {
-                if stop_reason == "max_tokens" {
+                if finish_reason == FinishReason::MaxTokens {
                     "I reached the model output limit before producing a visible reply. Please ask me to continue."
                         .to_string()
                 } else {
                     "I couldn't produce a visible reply after an automatic retry. Please try again."
                         .to_string()
                 }
+            } else if length_limited {
+                if ends_mid_sentence(&display_text) {
+                    format!("{}…", display_text.trim_end())
+                } else {
+                    display_text
+                }
+            } else if finish_reason == FinishReason::MaxTokens {
+                format!("{display_text}\n\n(Reply cut off at the model output limit. Ask me to continue.)")
             } else {
                 display_text
             };
@@ -860,6 +1461,11 @@ pub(crate) async fn process_with_agent_impl(
                 }
                 text
             };
+            let final_text = if debug_footer {
+                format!("{final_text}\n\n{}", turn_stats.footer())
+            } else {
+                final_text
+            };
             if let Some(tx) = event_tx {
                 let _ = tx.send(AgentEvent::FinalResponse {
                     text: final_text.clone(),
@@ -868,7 +1474,7 @@ pub(crate) async fn process_with_agent_impl(
             return Ok(final_text);
         }
 
-        if stop_reason == "tool_use" {
+        if finish_reason == FinishReason::ToolUse {
             // Extract and send thinking content before tool execution
             let thinking_text: String = response
                 .content
@@ -880,23 +1486,7 @@ pub(crate) async fn process_with_agent_impl(
                 .collect::<Vec<_>>()
                 .join("");
 
-            let assistant_content: Vec<ContentBlock> = response
-                .content
-                .iter()
-                .filter_map(|block| match block {
-                    ResponseContentBlock::Text { text } => {
-                        Some(ContentBlock::Text { text: text.clone() })
-                    }
-                    ResponseContentBlock::ToolUse { id, name, input } => {
-                        Some(ContentBlock::ToolUse {
-                            id: id.clone(),
-                            name: name.clone(),
-                            input: input.clone(),
-                        })
-                    }
-                    ResponseContentBlock::Other => None,
-                })
-                .collect();
+            let assistant_content = assistant_history_blocks(&response.content);
 
             // Collect tool calls for progress notification
             let tool_calls: Vec<(&str, &serde_json::Value)> = response
@@ -940,6 +1530,7 @@ pub(crate) async fn process_with_agent_impl(
             let mut tool_results = Vec::new();
             let mut waiting_for_user_approval = false;
             let mut waiting_approval_tool: Option<String> = None;
+            let mut awaiting_user_input = false;
             for block in &response.content {
                 if let ResponseContentBlock::ToolUse { id, name, input } = block {
                     let mut effective_input = input.clone();
@@ -993,10 +1584,14 @@ pub(crate) async fn process_with_agent_impl(
                     info!("Executing tool: {} (iteration {})", name, iteration + 1);
                     let started = std::time::Instant::now();
                     let mut executed_input = effective_input.clone();
-                    let mut result = state
-                        .tools
-                        .execute_with_auth(name, executed_input.clone(), &tool_auth)
-                        .await;
+                    let mut result = execute_tool_relaying_progress(
+                        state,
+                        name,
+                        executed_input.clone(),
+                        &tool_auth,
+                        event_tx,
+                    )
+                    .await;
                     // Auto-retry on approval_required with explicit approval marker.
                     if result.is_error && result.error_type.as_deref() == Some("approval_required")
                     {
@@ -1013,17 +1608,58 @@ pub(crate) async fn process_with_agent_impl(
                             } else {
                                 info!("Auto-retrying tool '{}' after approval gate", name);
                             }
-                            result = state
-                                .tools
-                                .execute_with_auth(name, executed_input.clone(), &tool_auth)
-                                .await;
+                            result = execute_tool_relaying_progress(
+                                state,
+                                name,
+                                executed_input.clone(),
+                                &tool_auth,
+                                event_tx,
+                            )
+                            .await;
                         } else if state.config.high_risk_tool_user_confirmation_required {
                             waiting_for_user_approval = true;
                             waiting_approval_tool = Some(name.clone());
                         }
                     }
+                    turn_stats.record_tool_call(started.elapsed());
                     if name == "activate_skill" && !result.is_error {
                         if let Some(meta) = &result.metadata {
+                            let skill_choice = meta
+                                .get("tool_choice")
+                                .and_then(|v| v.as_str())
+                                .and_then(ToolChoice::parse);
+                            if skill_choice.is_some() {
+                                pending_tool_choice = resolve_tool_choice(skill_choice, &tool_defs);
+                            }
+                            let activated = SkillOverrides::from_activation(meta);
+                            if activated != SkillOverrides::default() {
+                                info!(
+                                    "Skill runtime overrides chat_id={} {:?}",
+                                    chat_id, activated
+                                );
+                                let new_limit = activated.max_response_tokens.is_some();
+                                skill_overrides.merge(activated);
+                                effective_model = turn_model(&skill_overrides);
+                                if new_limit {
+                                    let cap = resolve_max_response_tokens(
+                                        state,
+                                        context.caller_channel,
+                                        chat_id,
+                                        skill_overrides.max_response_tokens,
+                                    )
+                                    .await;
+                                    if cap != response_token_cap {
+                                        if let Some(old) = response_token_cap {
+                                            system_prompt = system_prompt
+                                                .replace(&reply_length_section(old), "");
+                                        }
+                                        if let Some(limit) = cap {
+                                            system_prompt.push_str(&reply_length_section(limit));
+                                        }
+                                        response_token_cap = cap;
+                                    }
+                                }
+                            }
                             if let Some(envs) = meta.get("skill_envs").and_then(|v| v.as_object()) {
                                 for (k, v) in envs {
                                     if let Some(s) = v.as_str() {
@@ -1136,9 +1772,29 @@ pub(crate) async fn process_with_agent_impl(
                             error_type: result.error_type.clone(),
                         });
                     }
+                    if name == ASK_USER_TOOL_NAME
+                        && !result.is_error
+                        && result
+                            .metadata
+                            .as_ref()
+                            .and_then(|m| m.get(AWAITING_USER_INPUT_KEY))
+                            .and_then(|v| v.as_bool())
+                            == Some(true)
+                    {
+                        awaiting_user_input = true;
+                    }
+                    // Raw error text stays in logs/events; the model also gets a hint.
+                    let content = if result.is_error {
+                        state
+                            .config
+                            .tool_error_hints
+                            .annotate(&result.content, result.error_type.as_deref())
+                    } else {
+                        result.content
+                    };
                     tool_results.push(ContentBlock::ToolResult {
                         tool_use_id: id.clone(),
-                        content: result.content,
+                        content,
                         is_error: if result.is_error { Some(true) } else { None },
                     });
                 }
@@ -1155,15 +1811,22 @@ pub(crate) async fn process_with_agent_impl(
                     "High-risk tool '{tool_name}' is waiting for your confirmation. Reply with \"批准\" or \"approve\" to continue."
                 );
                 if let Some(tx) = event_tx {
+                    let _ = tx.send(AgentEvent::ApprovalRequested { tool_name });
                     let _ = tx.send(AgentEvent::FinalResponse { text: text.clone() });
                 }
                 return Ok(text);
             }
+            if awaiting_user_input {
+                // The question is already in the chat; the reply resumes this session.
+                persist_session_with_skill_envs(state, chat_id, &mut messages, &skill_envs).await;
+                info!("Turn for chat {chat_id} paused awaiting the user's reply");
+                return Ok(String::new());
+            }
 
             continue;
         }
 
-        // Unknown stop reason
+        // Other finish reasons (e.g. Anthropic pause_turn)
         let text = response
             .content
             .iter()
@@ -1200,6 +1863,11 @@ pub(crate) async fn process_with_agent_impl(
         content: MessageContent::Text(max_iter_msg.clone()),
     });
     persist_session_with_skill_envs(state, chat_id, &mut messages, &skill_envs).await;
+    let max_iter_msg = if debug_footer {
+        format!("{max_iter_msg}\n\n{}", turn_stats.footer())
+    } else {
+        max_iter_msg
+    };
 
     if let Some(tx) = event_tx {
         let _ = tx.send(AgentEvent::FinalResponse {
@@ -1245,6 +1913,52 @@ pub(crate) async fn load_messages_from_db(
     Ok(history_to_claude_messages(&filtered, &bot_username))
 }
 
+/// Load history for one member of a group chat: their own messages plus the bot
+/// replies that directly follow them, so other members' threads stay out of context.
+pub(crate) async fn load_sender_messages_from_db(
+    state: &AppState,
+    chat_id: i64,
+    sender_id: &str,
+    caller_channel: &str,
+) -> Result<Vec<Message>, anyhow::Error> {
+    let max_history = state.config.max_history_messages;
+    let history = call_blocking(state.db.clone(), move |db| {
+        db.get_recent_messages(chat_id, max_history.saturating_mul(4))
+    })
+    .await?;
+    let mut filtered = Vec::new();
+    for msg in filter_history_for_sender(history, sender_id) {
+        if !msg.is_from_bot
+            && (is_slash_command_text(&msg.content)
+                || run_control::is_aborted_source_message(caller_channel, chat_id, &msg.id).await)
+        {
+            continue;
+        }
+        filtered.push(msg);
+    }
+    let skip = filtered.len().saturating_sub(max_history);
+    let bot_username = state.config.bot_username_for_channel(caller_channel);
+    Ok(history_to_claude_messages(&filtered[skip..], &bot_username))
+}
+
+fn filter_history_for_sender(history: Vec<StoredMessage>, sender_id: &str) -> Vec<StoredMessage> {
+    let mut out = Vec::new();
+    let mut following_sender = false;
+    for msg in history {
+        if msg.is_from_bot {
+            if following_sender {
+                out.push(msg);
+            }
+        } else {
+            following_sender = msg.sender_name == sender_id;
+            if following_sender {
+                out.push(msg);
+            }
+        }
+    }
+    out
+}
+
 fn is_cjk(c: char) -> bool {
     matches!(
         c as u32,
@@ -1301,31 +2015,95 @@ fn score_relevance_with_cache(
         .count()
 }
 
+fn memory_scope_label(m: &microclaw_storage::db::Memory) -> &'static str {
+    if m.chat_id.is_none() {
+        "global"
+    } else if m.sender_id.is_some() {
+        "user"
+    } else {
+        "chat"
+    }
+}
+
+/// `<pinned_memories>` for a turn: every pinned memory visible here, oldest
+/// first, within its own `token_budget`. Relevance plays no part.
+pub(crate) async fn build_pinned_memory_context(
+    memory_backend: &std::sync::Arc<crate::memory_backend::MemoryBackend>,
+    chat_id: i64,
+    sender_id: Option<&str>,
+    token_budget: usize,
+) -> String {
+    let mut pinned = match memory_backend.get_pinned_memories(chat_id).await {
+        Ok(m) => m,
+        Err(_) => return String::new(),
+    };
+    pinned.retain(|m| m.visible_to(chat_id, sender_id));
+    if pinned.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("<pinned_memories>\n");
+    let mut used_tokens = 0usize;
+    let mut omitted = 0usize;
+    for (idx, m) in pinned.iter().enumerate() {
+        let estimated_tokens = (m.content.len() / 4) + 10;
+        if used_tokens + estimated_tokens > token_budget {
+            omitted = pinned.len() - idx;
+            break;
+        }
+        used_tokens += estimated_tokens;
+        out.push_str(&format!(
+            "[{}] [{}] {}\n",
+            m.category,
+            memory_scope_label(m),
+            m.content
+        ));
+    }
+    if omitted > 0 {
+        out.push_str(&format!("(+{omitted} pinned memories omitted)\n"));
+    }
+    out.push_str("</pinned_memories>\n");
+    out
+}
+
+/// `<structured_memories>` for a turn: the memories most relevant to `query`,
+/// at most `top_k` of them (0 for no limit) within `token_budget`.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn build_db_memory_context(
     memory_backend: &std::sync::Arc<crate::memory_backend::MemoryBackend>,
     db: &std::sync::Arc<Database>,
     embedding: &Option<std::sync::Arc<dyn EmbeddingProvider>>,
     chat_id: i64,
+    sender_id: Option<&str>,
     query: &str,
     token_budget: usize,
+    top_k: usize,
 ) -> String {
-    let memories = match memory_backend.get_memories_for_context(chat_id, 100).await {
+    let mut memories = match memory_backend.get_memories_for_context(chat_id, 100).await {
         Ok(m) => m,
         Err(_) => return String::new(),
     };
+    // Pinned memories get their own block and budget.
+    memories.retain(|m| !m.pinned && m.visible_to(chat_id, sender_id));
 
     if memories.is_empty() {
         return String::new();
     }
 
     let mut ordered: Vec<&microclaw_storage::db::Memory> = Vec::new();
-    #[cfg(feature = "sqlite-vec")]
     let mut retrieval_method = "keyword";
-    #[cfg(not(feature = "sqlite-vec"))]
-    let retrieval_method = "keyword";
+
+    if let Some(hits) = memory_backend.vector_search(Some(chat_id), query, 20).await {
+        let by_id: std::collections::HashMap<i64, &microclaw_storage::db::Memory> =
+            memories.iter().map(|m| (m.id, m)).collect();
+        ordered.extend(hits.iter().filter_map(|(id, _)| by_id.get(id).copied()));
+        if !ordered.is_empty() {
+            retrieval_method = "vector";
+        }
+    }
 
     #[cfg(feature = "sqlite-vec")]
-    {
+    if ordered.is_empty() {
         if let Some(provider) = embedding {
             if memory_backend.prefers_mcp() {
                 // memory backend is external; local sqlite-vec cannot rank remote rows reliably.
@@ -1385,18 +2163,18 @@ pub(crate) async fn build_db_memory_context(
 
     for (idx, m) in ordered.iter().enumerate() {
         let estimated_tokens = (m.content.len() / 4) + 10;
-        if used_tokens + estimated_tokens > budget {
+        if (top_k > 0 && idx >= top_k) || used_tokens + estimated_tokens > budget {
             omitted = ordered.len().saturating_sub(idx);
             break;
         }
 
         used_tokens += estimated_tokens;
-        let scope = if m.chat_id.is_none() {
-            "global"
-        } else {
-            "chat"
-        };
-        out.push_str(&format!("[{}] [{}] {}\n", m.category, scope, m.content));
+        out.push_str(&format!(
+            "[{}] [{}] {}\n",
+            m.category,
+            memory_scope_label(m),
+            m.content
+        ));
     }
     if omitted > 0 {
         out.push_str(&format!("(+{omitted} memories omitted)\n"));
@@ -1520,10 +2298,12 @@ You have access to the following capabilities:
 - Get current date/time with timezone awareness (`get_current_time`)
 - Compare two timestamps and compute their delta (`compare_time`)
 - Evaluate basic arithmetic expressions (`calculate`)
+- Pause before re-checking something, e.g. while polling a build or deploy (`wait`) — use this instead of `sleep` in bash
 - Send messages mid-conversation (`send_message`) — use this to send intermediate updates
 - Schedule tasks (`schedule_task`, `list_scheduled_tasks`, `pause/resume/cancel_scheduled_task`, `get_task_history`)
 - Export chat history to markdown (`export_chat`)
 - Understand images sent by users (they appear as image content blocks)
+- Work with files users send: each appears as an `[attachment] ... saved_path=...` line; open it from that path with your file tools
 - Delegate self-contained sub-tasks to a parallel agent (`sub_agent`)
 - Activate agent skills (`activate_skill`) for specialized tasks
 - Install skills from repos (`sync_skills`, `clawhub_install`, `clawhub_search`) — use these instead of manually writing SKILL.md files. Skills go in ~/.microclaw/skills/ (or configured skills dir).
@@ -1613,6 +2393,144 @@ Built-in execution playbook:
     prompt
 }
 
+/// Skills successfully activated in `messages`, mapped to the latest activation result.
+pub(crate) fn activated_skills(messages: &[Message]) -> HashMap<String, String> {
+    activated_skills_in_order(messages).into_iter().collect()
+}
+
+/// Like [`activated_skills`], ordered by each skill's latest activation.
+fn activated_skills_in_order(messages: &[Message]) -> Vec<(String, String)> {
+    let mut activations: HashMap<String, String> = HashMap::new();
+    let mut results: Vec<(String, String)> = Vec::new();
+    for message in messages {
+        let MessageContent::Blocks(blocks) = &message.content else {
+            continue;
+        };
+        for block in blocks {
+            match block {
+                ContentBlock::ToolUse { id, name, input } if name == "activate_skill" => {
+                    if let Some(skill) = input.get("skill_name").and_then(|v| v.as_str()) {
+                        activations.insert(id.clone(), skill.to_string());
+                    }
+                }
+                ContentBlock::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } if is_error != &Some(true) => {
+                    if let Some(skill) = activations.get(tool_use_id) {
+                        results.retain(|(name, _)| name != skill);
+                        results.push((skill.clone(), content.clone()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    results
+}
+
+/// Runtime settings declared by active skills' SKILL.md. When several active
+/// skills set the same one, the most recently activated wins.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct SkillOverrides {
+    model: Option<String>,
+    max_tool_iterations: Option<usize>,
+    max_response_tokens: Option<u32>,
+}
+
+impl SkillOverrides {
+    /// Overrides of the skills activated in `messages`, read from their current
+    /// SKILL.md. They lapse once the activation leaves the session (`/reset`
+    /// or compaction).
+    fn from_history(skills: &crate::skills::SkillManager, messages: &[Message]) -> Self {
+        let mut overrides = Self::default();
+        for (name, _) in activated_skills_in_order(messages) {
+            if let Ok((meta, _)) = skills.load_skill_checked(&name) {
+                overrides.merge(Self {
+                    model: meta.model,
+                    max_tool_iterations: meta.max_tool_iterations,
+                    max_response_tokens: meta.max_response_tokens,
+                });
+            }
+        }
+        overrides
+    }
+
+    /// Overrides reported in an `activate_skill` result's metadata.
+    fn from_activation(metadata: &serde_json::Value) -> Self {
+        Self {
+            model: metadata
+                .get("model")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            max_tool_iterations: metadata
+                .get("max_tool_iterations")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize),
+            max_response_tokens: metadata
+                .get("max_response_tokens")
+                .and_then(|v| v.as_u64())
+                .and_then(|n| u32::try_from(n).ok()),
+        }
+    }
+
+    /// The turn's tool iteration budget. A skill may only lower the global
+    /// budget unless `allow_skill_budget_increase` is set.
+    fn max_tool_iterations(&self, config: &crate::config::Config) -> usize {
+        match self.max_tool_iterations {
+            Some(n) if config.allow_skill_budget_increase => n,
+            Some(n) => n.min(config.max_tool_iterations),
+            None => config.max_tool_iterations,
+        }
+    }
+
+    fn merge(&mut self, later: Self) {
+        if later.model.is_some() {
+            self.model = later.model;
+        }
+        if later.max_tool_iterations.is_some() {
+            self.max_tool_iterations = later.max_tool_iterations;
+        }
+        if later.max_response_tokens.is_some() {
+            self.max_response_tokens = later.max_response_tokens;
+        }
+    }
+}
+
+/// Model chosen by the skills active in `messages`, as the next turn would use it.
+pub(crate) fn active_skill_model(
+    skills: &crate::skills::SkillManager,
+    messages: &[Message],
+) -> Option<String> {
+    SkillOverrides::from_history(skills, messages).model
+}
+
+/// Current instructions of skills activated earlier in `messages` whose SKILL.md
+/// body no longer matches what the activation returned; empty when none changed.
+fn updated_skill_instructions_section(
+    skills: &crate::skills::SkillManager,
+    messages: &[Message],
+) -> String {
+    let results = activated_skills(messages);
+    let mut names: Vec<&String> = results.keys().collect();
+    names.sort();
+    let mut section = String::new();
+    for name in names {
+        let Ok((meta, body)) = skills.load_skill_checked(name) else {
+            continue;
+        };
+        if results[name].contains(body.trim()) {
+            continue;
+        }
+        if section.is_empty() {
+            section.push_str("\n# Updated Skill Instructions\n\nThese skills changed on disk after you activated them in this conversation. Follow these instructions instead of the earlier ones.\n\n");
+        }
+        section.push_str(&format!("## {}\n\n{}\n\n", meta.name, body.trim()));
+    }
+    section
+}
+
 fn append_plugin_context_sections(
     system_prompt: &mut String,
     injections: &[crate::plugins::PluginContextInjection],
@@ -1720,6 +2638,121 @@ pub(crate) fn strip_thinking(text: &str) -> String {
     result.trim().to_string()
 }
 
+/// Reasoning the model returned as separate thinking blocks.
+pub(crate) fn response_thinking(content: &[ResponseContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ResponseContentBlock::Thinking { thinking, .. } => Some(thinking.trim()),
+            _ => None,
+        })
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Assistant blocks kept in history for a tool-use turn. Signed Anthropic thinking
+/// is kept verbatim, as extended thinking requires it back within the tool loop.
+/// Other reasoning is replayed as text only when the turn has no visible text, so
+/// providers that need it back (the DeepSeek `reasoning_content` bridge) still
+/// receive it.
+pub(crate) fn assistant_history_blocks(content: &[ResponseContentBlock]) -> Vec<ContentBlock> {
+    let has_text = content
+        .iter()
+        .any(|block| matches!(block, ResponseContentBlock::Text { text } if !text.is_empty()));
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ResponseContentBlock::Text { text } => Some(ContentBlock::Text { text: text.clone() }),
+            ResponseContentBlock::Thinking {
+                thinking,
+                signature: Some(signature),
+            } => Some(ContentBlock::Thinking {
+                thinking: thinking.clone(),
+                signature: Some(signature.clone()),
+            }),
+            ResponseContentBlock::RedactedThinking { data } => {
+                Some(ContentBlock::RedactedThinking { data: data.clone() })
+            }
+            ResponseContentBlock::Thinking { thinking, .. }
+                if !has_text && !thinking.is_empty() =>
+            {
+                Some(ContentBlock::Text {
+                    text: thinking.clone(),
+                })
+            }
+            ResponseContentBlock::ToolUse { id, name, input } => Some(ContentBlock::ToolUse {
+                id: id.clone(),
+                name: name.clone(),
+                input: input.clone(),
+            }),
+            ResponseContentBlock::Thinking { .. } | ResponseContentBlock::Other => None,
+        })
+        .collect()
+}
+
+const THINKING_SUMMARY_MAX_CHARS: usize = 400;
+
+/// Reasoning never reaches the reply. It is written to the debug log when
+/// `log_thinking` is set, and a short summary is sent as progress when
+/// `show_thinking` is set.
+fn surface_thinking(
+    config: &crate::config::Config,
+    chat_id: i64,
+    thinking: &str,
+    event_tx: Option<&UnboundedSender<AgentEvent>>,
+) {
+    if thinking.is_empty() {
+        return;
+    }
+    if config.log_thinking {
+        debug!(chat_id, "Model thinking:\n{}", thinking);
+    }
+    if !config.show_thinking {
+        return;
+    }
+    if let Some(tx) = event_tx {
+        let _ = tx.send(AgentEvent::Progress {
+            content: thinking_summary(thinking),
+            tool_hint: false,
+        });
+    }
+}
+
+/// Execute a tool call, forwarding its interim status as `ToolProgress` events.
+async fn execute_tool_relaying_progress(
+    state: &AppState,
+    name: &str,
+    input: Value,
+    auth: &ToolAuthContext,
+    event_tx: Option<&UnboundedSender<AgentEvent>>,
+) -> crate::tools::ToolResult {
+    let Some(event_tx) = event_tx else {
+        return state.tools.execute_with_auth(name, input, auth).await;
+    };
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let execute =
+        crate::tools::with_tool_progress(tx, state.tools.execute_with_auth(name, input, auth));
+    let relay = async {
+        while let Some(content) = rx.recv().await {
+            let _ = event_tx.send(AgentEvent::ToolProgress {
+                name: name.to_string(),
+                content,
+            });
+        }
+    };
+    let (result, ()) = tokio::join!(execute, relay);
+    result
+}
+
+fn thinking_summary(thinking: &str) -> String {
+    if thinking.len() <= THINKING_SUMMARY_MAX_CHARS {
+        return thinking.to_string();
+    }
+    let end = floor_char_boundary(thinking, THINKING_SUMMARY_MAX_CHARS);
+    format!("{}…", thinking[..end].trim_end())
+}
+
 /// Format tool calls as a concise hint string.
 /// Example output: `web_search("query")`, `bash("ls -la")`, `read_file("path")`
 fn format_tool_hint(tool_calls: &[(&str, &serde_json::Value)]) -> String {
@@ -1812,6 +2845,7 @@ pub(crate) fn message_to_text(msg: &Message) -> String {
                     ContentBlock::Image { .. } => {
                         parts.push("[image]".into());
                     }
+                    ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {}
                 }
             }
             parts.join("\n")
@@ -1819,6 +2853,31 @@ pub(crate) fn message_to_text(msg: &Message) -> String {
     }
 }
 
+/// Note given to text-only models in place of an image the user sent.
+const IMAGE_OMITTED_NOTE: &str = "[image omitted: the current model does not accept image input]";
+
+/// Add `image` to the latest user message, or a text note when the model has no vision.
+fn attach_user_image(messages: &mut [Message], image: ImageSource, vision: bool) {
+    let Some(last_msg) = messages.last_mut().filter(|m| m.role == "user") else {
+        return;
+    };
+    let text_content = match &last_msg.content {
+        MessageContent::Text(t) => t.clone(),
+        _ => String::new(),
+    };
+    let mut blocks = if vision {
+        vec![ContentBlock::Image { source: image }]
+    } else {
+        vec![ContentBlock::Text {
+            text: IMAGE_OMITTED_NOTE.into(),
+        }]
+    };
+    if !text_content.is_empty() {
+        blocks.push(ContentBlock::Text { text: text_content });
+    }
+    last_msg.content = MessageContent::Blocks(blocks);
+}
+
 /// Replace Image content blocks with text placeholders to avoid storing base64 data in sessions.
 pub(crate) fn strip_images_for_session(messages: &mut [Message]) {
     for msg in messages.iter_mut() {
@@ -1911,11 +2970,14 @@ async fn compact_messages(
         role: "user".into(),
         content: MessageContent::Text(format!("{summarize_prompt}\n\n---\n\n{summary_input}")),
     }];
-    let effective_model = state
-        .llm_model_overrides
-        .get(caller_channel)
-        .cloned()
-        .unwrap_or_else(|| state.config.model.clone());
+    let effective_model = match state.config.model_roles.get(ModelRole::Compaction) {
+        Some(model) => model.to_string(),
+        None => state
+            .llm_model_overrides
+            .get(caller_channel)
+            .cloned()
+            .unwrap_or_else(|| state.config.model.clone()),
+    };
 
     let timeout_secs = state.config.compaction_timeout_secs;
     let summary = match tokio::time::timeout(
@@ -2015,8 +3077,11 @@ async fn compact_messages(
 #[cfg(test)]
 mod tests {
     use super::{
-        build_db_memory_context, history_to_claude_messages, process_with_agent,
-        AgentRequestContext,
+        assistant_history_blocks, build_db_memory_context, build_pinned_memory_context,
+        cancel_expired_user_question, ends_mid_sentence, filter_history_for_sender,
+        history_to_claude_messages, process_with_agent, process_with_agent_with_events,
+        regenerate_last_response, reply_length_section, resolve_tool_choice, response_thinking,
+        rewind_last_assistant_turn, thinking_summary, AgentRequestContext, TurnStats,
     };
     use crate::config::{Config, WorkingDirIsolation};
     use crate::llm::LlmProvider;
@@ -2027,7 +3092,8 @@ mod tests {
     use microclaw_channels::channel_adapter::ChannelRegistry;
     use microclaw_core::error::MicroClawError;
     use microclaw_core::llm_types::{
-        Message, MessagesResponse, ResponseContentBlock, ToolDefinition,
+        ContentBlock, ImageSource, Message, MessageContent, MessagesResponse, ResponseContentBlock,
+        ToolChoice, ToolDefinition,
     };
     use microclaw_storage::db::{Database, StoredMessage};
     use serde_json::json;
@@ -2237,6 +3303,7 @@ mod tests {
             embedding: None,
             memory_backend: memory_backend.clone(),
             tools: ToolRegistry::new(&cfg, channel_registry, db, memory_backend),
+            mcp_manager: tokio::sync::Mutex::new(crate::mcp::McpManager::default()),
         })
     }
 
@@ -2267,7 +3334,8 @@ mod tests {
             .unwrap();
 
         let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
-        let context = build_db_memory_context(&memory_backend, &db, &None, 100, "short", 20).await;
+        let context =
+            build_db_memory_context(&memory_backend, &db, &None, 100, None, "short", 20, 0).await;
         assert!(context.contains("<structured_memories>"));
         assert!(context.contains("(+"));
         assert!(context.contains("memories omitted"));
@@ -2286,7 +3354,8 @@ mod tests {
 
         let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
         let context =
-            build_db_memory_context(&memory_backend, &db, &None, 100, "likes", 10_000).await;
+            build_db_memory_context(&memory_backend, &db, &None, 100, None, "likes", 10_000, 0)
+                .await;
         assert!(context.contains("user likes rust"));
         assert!(context.contains("user likes coffee"));
         assert!(!context.contains("memories omitted"));
@@ -2294,6 +3363,28 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[tokio::test]
+    async fn test_build_db_memory_context_top_k_limits_injected_memories() {
+        let (db, dir) = test_db();
+        db.insert_memory(Some(100), "user likes rust", "PROFILE")
+            .unwrap();
+        db.insert_memory(Some(100), "user likes coffee", "PROFILE")
+            .unwrap();
+        db.insert_memory(Some(100), "team meets on monday", "EVENT")
+            .unwrap();
+
+        let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
+        let context =
+            build_db_memory_context(&memory_backend, &db, &None, 100, None, "likes", 10_000, 2)
+                .await;
+        assert!(context.contains("user likes rust"));
+        assert!(context.contains("user likes coffee"));
+        assert!(!context.contains("team meets on monday"));
+        assert!(context.contains("(+1 memories omitted)"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
     #[tokio::test]
     async fn test_build_db_memory_context_cjk_relevance() {
         let (db, dir) = test_db();
@@ -2303,8 +3394,17 @@ mod tests {
             .unwrap();
 
         let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
-        let context =
-            build_db_memory_context(&memory_backend, &db, &None, 100, "喜欢 咖啡", 10_000).await;
+        let context = build_db_memory_context(
+            &memory_backend,
+            &db,
+            &None,
+            100,
+            None,
+            "喜欢 咖啡",
+            10_000,
+            0,
+        )
+        .await;
         let first_line = context
             .lines()
             .find(|line| line.starts_with('['))
@@ -2314,6 +3414,167 @@ mod tests {
         let _ = std::fs::remove_dir_all(dir);
     }
 
+    #[tokio::test]
+    async fn test_build_db_memory_context_scopes_user_memories_to_sender() {
+        let (db, dir) = test_db();
+        db.insert_memory(Some(100), "team ships on friday", "KNOWLEDGE")
+            .unwrap();
+        let alice = db
+            .insert_memory(Some(100), "alice prefers tea", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(alice, Some("ou_alice")).unwrap();
+        let bob = db
+            .insert_memory(Some(100), "bob prefers coffee", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(bob, Some("ou_bob")).unwrap();
+
+        let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
+        let context = build_db_memory_context(
+            &memory_backend,
+            &db,
+            &None,
+            100,
+            Some("ou_alice"),
+            "prefers",
+            10_000,
+            0,
+        )
+        .await;
+        assert!(context.contains("[chat] team ships on friday"));
+        assert!(context.contains("[user] alice prefers tea"));
+        assert!(!context.contains("bob prefers coffee"));
+
+        // Without a sender, user-scoped memories stay hidden.
+        let shared =
+            build_db_memory_context(&memory_backend, &db, &None, 100, None, "prefers", 10_000, 0)
+                .await;
+        assert!(shared.contains("[chat] team ships on friday"));
+        assert!(!shared.contains("alice prefers tea"));
+        assert!(!shared.contains("bob prefers coffee"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_memories_injected_separately_within_budget() {
+        let (db, dir) = test_db();
+        let rule = db
+            .insert_memory(Some(100), "always answer in British English", "PROFILE")
+            .unwrap();
+        db.set_memory_pinned(rule, true).unwrap();
+        let global = db
+            .insert_memory(None, "deploys freeze on fridays", "KNOWLEDGE")
+            .unwrap();
+        db.set_memory_pinned(global, true).unwrap();
+        let bob = db
+            .insert_memory(Some(100), "bob wants terse replies", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(bob, Some("ou_bob")).unwrap();
+        db.set_memory_pinned(bob, true).unwrap();
+        db.insert_memory(Some(100), "user likes rust", "PROFILE")
+            .unwrap();
+
+        let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
+        let pinned =
+            build_pinned_memory_context(&memory_backend, 100, Some("ou_alice"), 10_000).await;
+        assert!(pinned.contains("[PROFILE] [chat] always answer in British English"));
+        assert!(pinned.contains("[KNOWLEDGE] [global] deploys freeze on fridays"));
+        assert!(!pinned.contains("bob wants terse replies"));
+        assert!(!pinned.contains("user likes rust"));
+
+        let tight = build_pinned_memory_context(&memory_backend, 100, Some("ou_alice"), 20).await;
+        assert!(tight.contains("always answer in British English"));
+        assert!(tight.contains("(+1 pinned memories omitted)"));
+
+        let sender_less = build_pinned_memory_context(&memory_backend, 100, None, 10_000).await;
+        assert!(sender_less.contains("always answer in British English"));
+        assert!(!sender_less.contains("bob wants terse replies"));
+
+        let ranked = build_db_memory_context(
+            &memory_backend,
+            &db,
+            &None,
+            100,
+            None,
+            "answer english rust",
+            10_000,
+            0,
+        )
+        .await;
+        assert!(ranked.contains("user likes rust"));
+        assert!(!ranked.contains("British English"));
+
+        assert_eq!(
+            build_pinned_memory_context(&memory_backend, 200, None, 10_000).await,
+            "<pinned_memories>\n[KNOWLEDGE] [global] deploys freeze on fridays\n</pinned_memories>\n"
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_filter_history_for_sender_keeps_own_turns_and_replies() {
+        let msg = |sender: &str, content: &str, is_from_bot: bool| StoredMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            chat_id: 1,
+            sender_name: sender.to_string(),
+            content: content.to_string(),
+            is_from_bot,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let history = vec![
+            msg("ou_alice", "alice asks", false),
+            msg("bot", "reply to alice", true),
+            msg("ou_bob", "bob asks", false),
+            msg("bot", "reply to bob", true),
+            msg("ou_alice", "alice again", false),
+        ];
+        let kept: Vec<String> = filter_history_for_sender(history, "ou_alice")
+            .into_iter()
+            .map(|m| m.content)
+            .collect();
+        assert_eq!(kept, vec!["alice asks", "reply to alice", "alice again"]);
+    }
+
+    #[test]
+    fn test_resolve_tool_choice_drops_unknown_tool() {
+        let defs = vec![ToolDefinition {
+            name: "web_search".into(),
+            description: String::new(),
+            input_schema: serde_json::json!({"type": "object"}),
+        }];
+        let known = Some(ToolChoice::Tool("web_search".into()));
+        assert_eq!(resolve_tool_choice(known.clone(), &defs), known);
+        assert_eq!(
+            resolve_tool_choice(Some(ToolChoice::Tool("missing".into())), &defs),
+            None
+        );
+        assert_eq!(
+            resolve_tool_choice(Some(ToolChoice::None), &defs),
+            Some(ToolChoice::None)
+        );
+    }
+
+    #[test]
+    fn test_isolated_sender_requires_flag_and_group_chat() {
+        let mut config = Config::test_defaults();
+        let group = AgentRequestContext {
+            caller_channel: "feishu",
+            chat_id: 1,
+            chat_type: "group",
+            sender_id: Some("ou_alice"),
+            sender_is_admin: false,
+        };
+        assert_eq!(group.isolated_sender(&config), None);
+        config.group_user_isolation = true;
+        assert_eq!(group.isolated_sender(&config), Some("ou_alice"));
+        let private = AgentRequestContext {
+            chat_type: "private",
+            ..group
+        };
+        assert_eq!(private.isolated_sender(&config), None);
+    }
+
     #[tokio::test]
     async fn test_explicit_memory_fast_path_works_across_channels_and_recall_after_restart() {
         let cases = vec![
@@ -2359,6 +3620,8 @@ mod tests {
                     caller_channel,
                     chat_id,
                     chat_type,
+                    sender_id: None,
+                    sender_is_admin: false,
                 },
                 None,
                 None,
@@ -2381,8 +3644,10 @@ mod tests {
                 &restarted.db,
                 &None,
                 chat_id,
+                None,
                 "database port",
                 1500,
+                0,
             )
             .await;
             assert!(
@@ -2417,6 +3682,8 @@ mod tests {
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -2439,6 +3706,8 @@ mod tests {
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -2467,18 +3736,151 @@ mod tests {
         let _ = std::fs::remove_dir_all(&base_dir);
     }
 
-    #[tokio::test]
-    async fn test_empty_visible_reply_auto_retries_once() {
-        let base_dir =
-            std::env::temp_dir().join(format!("mc_agent_empty_retry_{}", uuid::Uuid::new_v4()));
-        std::fs::create_dir_all(&base_dir).unwrap();
-        let calls = Arc::new(AtomicUsize::new(0));
-        let llm = EmptyVisibleThenNormalLlm {
-            calls: calls.clone(),
-        };
-        let state = test_state_with_llm(&base_dir, Box::new(llm));
-        let chat_id = state
-            .db
+    #[test]
+    fn test_thinking_is_separated_from_history_text() {
+        let with_text = vec![
+            ResponseContentBlock::Thinking {
+                thinking: " plan ".into(),
+                signature: None,
+            },
+            ResponseContentBlock::Text {
+                text: "Checking.".into(),
+            },
+            ResponseContentBlock::ToolUse {
+                id: "t1".into(),
+                name: "bash".into(),
+                input: json!({"command": "ls"}),
+            },
+        ];
+        assert_eq!(response_thinking(&with_text), "plan");
+        let blocks = assistant_history_blocks(&with_text);
+        assert_eq!(blocks.len(), 2);
+        assert!(matches!(
+            &blocks[0],
+            microclaw_core::llm_types::ContentBlock::Text { text } if text == "Checking."
+        ));
+
+        // Without visible text the reasoning is replayed for reasoning-bridge providers.
+        let blocks = assistant_history_blocks(&with_text[..1]);
+        assert!(matches!(
+            &blocks[0],
+            microclaw_core::llm_types::ContentBlock::Text { text } if text == " plan "
+        ));
+
+        // Signed Anthropic thinking is replayed verbatim, ahead of the tool call.
+        let signed = vec![
+            ResponseContentBlock::Thinking {
+                thinking: "plan".into(),
+                signature: Some("sig".into()),
+            },
+            ResponseContentBlock::RedactedThinking {
+                data: "opaque".into(),
+            },
+            with_text[2].clone(),
+        ];
+        let blocks = assistant_history_blocks(&signed);
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(
+            &blocks[0],
+            microclaw_core::llm_types::ContentBlock::Thinking { thinking, signature }
+                if thinking == "plan" && signature.as_deref() == Some("sig")
+        ));
+        assert!(matches!(
+            &blocks[1],
+            microclaw_core::llm_types::ContentBlock::RedactedThinking { data } if data == "opaque"
+        ));
+        assert!(matches!(
+            &blocks[2],
+            microclaw_core::llm_types::ContentBlock::ToolUse { .. }
+        ));
+
+        let long = "é".repeat(300);
+        let summary = thinking_summary(&long);
+        assert!(summary.ends_with('…'));
+        assert!(summary.len() <= 400 + '…'.len_utf8());
+    }
+
+    struct ThinkingThenAnswerLlm;
+
+    #[async_trait::async_trait]
+    impl LlmProvider for ThinkingThenAnswerLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            Ok(MessagesResponse {
+                content: vec![
+                    ResponseContentBlock::Thinking {
+                        thinking: "internal chain of thought".into(),
+                        signature: None,
+                    },
+                    ResponseContentBlock::Text {
+                        text: "Final answer.".into(),
+                    },
+                ],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_thinking_blocks_never_reach_reply() {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_thinking_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let state = test_state_with_llm(&base_dir, Box::new(ThinkingThenAnswerLlm));
+        let chat_id = state
+            .db
+            .resolve_or_create_chat_id("web", "thinking-chat", Some("thinking"), "web")
+            .unwrap();
+        store_user_message(&state.db, chat_id, "hello");
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let reply = process_with_agent_with_events(
+            &state,
+            AgentRequestContext {
+                caller_channel: "web",
+                chat_id,
+                chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
+            },
+            None,
+            None,
+            Some(&tx),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reply, "Final answer.");
+        while let Ok(event) = rx.try_recv() {
+            if let super::AgentEvent::Progress { content, .. } = event {
+                assert!(
+                    !content.contains("chain of thought"),
+                    "show_thinking is off"
+                );
+            }
+        }
+
+        drop(state);
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[tokio::test]
+    async fn test_empty_visible_reply_auto_retries_once() {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_empty_retry_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let llm = EmptyVisibleThenNormalLlm {
+            calls: calls.clone(),
+        };
+        let state = test_state_with_llm(&base_dir, Box::new(llm));
+        let chat_id = state
+            .db
             .resolve_or_create_chat_id("web", "empty-retry-chat", Some("empty"), "web")
             .unwrap();
         store_user_message(&state.db, chat_id, "hello");
@@ -2489,6 +3891,8 @@ mod tests {
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -2527,6 +3931,8 @@ mod tests {
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -2660,6 +4066,8 @@ mod tests {
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -2674,6 +4082,659 @@ mod tests {
         let _ = std::fs::remove_dir_all(&base_dir);
     }
 
+    struct TruncatingLlm {
+        calls: Arc<AtomicUsize>,
+        truncated_calls: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for TruncatingLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            let idx = self.calls.fetch_add(1, Ordering::SeqCst);
+            let truncated = idx < self.truncated_calls;
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text {
+                    text: format!("part {} ", idx + 1),
+                }],
+                stop_reason: Some(if truncated { "length" } else { "stop" }.to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    async fn run_truncating_turn(
+        truncated_calls: usize,
+        chat_limit: Option<u32>,
+    ) -> (String, usize) {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_truncated_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let state = test_state_with_llm(
+            &base_dir,
+            Box::new(TruncatingLlm {
+                calls: calls.clone(),
+                truncated_calls,
+            }),
+        );
+        let chat_id = state
+            .db
+            .resolve_or_create_chat_id("web", "truncated-chat", Some("t"), "web")
+            .unwrap();
+        state
+            .db
+            .set_chat_max_response_tokens(chat_id, chat_limit)
+            .unwrap();
+        store_user_message(&state.db, chat_id, "write a long essay");
+        let reply = process_with_agent(
+            &state,
+            AgentRequestContext {
+                caller_channel: "web",
+                chat_id,
+                chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
+            },
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        drop(state);
+        let _ = std::fs::remove_dir_all(&base_dir);
+        (reply, calls.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_max_tokens_reply_is_continued() {
+        let (reply, calls) = run_truncating_turn(1, None).await;
+        assert_eq!(reply.trim(), "part 1 part 2");
+        assert_eq!(calls, 2);
+
+        let (reply, calls) = run_truncating_turn(usize::MAX, None).await;
+        assert_eq!(calls, 3);
+        assert!(reply.starts_with("part 1 part 2 part 3"));
+        assert!(reply.contains("cut off at the model output limit"));
+    }
+
+    #[tokio::test]
+    async fn test_reply_length_limit_completes_sentence_once() {
+        let (reply, calls) = run_truncating_turn(1, Some(200)).await;
+        assert_eq!(reply.trim(), "part 1 part 2");
+        assert_eq!(calls, 2);
+
+        // Still cut after the completion: returned with an ellipsis, no note.
+        let (reply, calls) = run_truncating_turn(usize::MAX, Some(200)).await;
+        assert_eq!(calls, 2);
+        assert!(reply.ends_with("part 1 part 2…"), "{reply}");
+
+        // `/verbosity off` (0) lifts a limit, so the usual continuation applies.
+        let (_, calls) = run_truncating_turn(usize::MAX, Some(0)).await;
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_ends_mid_sentence() {
+        assert!(ends_mid_sentence("The deploy runs every"));
+        assert!(!ends_mid_sentence("The deploy runs nightly.  "));
+        assert!(!ends_mid_sentence("部署完成。"));
+        assert!(!ends_mid_sentence(""));
+        assert!(reply_length_section(400).contains("about 400 tokens (roughly 300 words)"));
+    }
+
+    #[test]
+    fn test_turn_stats_footer() {
+        let mut stats = TurnStats::new();
+        stats.record_llm_call(
+            std::time::Duration::from_millis(1200),
+            Some((1000, 200)),
+            Some(0.0045),
+        );
+        stats.record_tool_call(std::time::Duration::from_millis(300));
+        stats.record_llm_call(
+            std::time::Duration::from_millis(800),
+            Some((1500, 100)),
+            Some(0.005),
+        );
+        assert_eq!(
+            stats.footer(),
+            "[debug] tokens 2500 in / 300 out · ~$0.0095 · llm 2× 2.0s · tools 1× 0.3s"
+        );
+
+        // One call without a price makes the total unknown.
+        stats.record_llm_call(std::time::Duration::ZERO, None, None);
+        assert!(stats.footer().contains("· cost n/a ·"));
+    }
+
+    #[test]
+    fn test_rewind_last_assistant_turn_keeps_tool_results_in_the_turn() {
+        let text = |role: &str, text: &str| Message {
+            role: role.to_string(),
+            content: MessageContent::Text(text.to_string()),
+        };
+        let mut messages = vec![
+            text("user", "first"),
+            text("assistant", "one"),
+            text("user", "second"),
+            Message {
+                role: "assistant".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "bash".into(),
+                    input: json!({"command": "ls"}),
+                }]),
+            },
+            Message {
+                role: "user".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                    tool_use_id: "t1".into(),
+                    content: "a.txt".into(),
+                    is_error: None,
+                }]),
+            },
+            text("assistant", "two"),
+        ];
+        let previous = rewind_last_assistant_turn(&mut messages).unwrap();
+        assert_eq!(previous.len(), 3);
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(&messages[2].content, MessageContent::Text(t) if t == "second"));
+        assert!(rewind_last_assistant_turn(&mut messages).is_none());
+    }
+
+    /// Model override and message count of each call.
+    type SeenCalls = Arc<std::sync::Mutex<Vec<(Option<String>, usize)>>>;
+
+    struct CountingModelLlm {
+        calls: Arc<AtomicUsize>,
+        seen: SeenCalls,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for CountingModelLlm {
+        async fn send_message(
+            &self,
+            system: &str,
+            messages: Vec<Message>,
+            tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            self.send_message_with_model(system, messages, tools, None)
+                .await
+        }
+
+        async fn send_message_with_model(
+            &self,
+            _system: &str,
+            messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+            model_override: Option<&str>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            let idx = self.calls.fetch_add(1, Ordering::SeqCst);
+            self.seen
+                .lock()
+                .unwrap()
+                .push((model_override.map(str::to_string), messages.len()));
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text {
+                    text: format!("answer {}", idx + 1),
+                }],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_last_response_reanswers_with_override_model() {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_retry_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let state = test_state_with_llm(
+            &base_dir,
+            Box::new(CountingModelLlm {
+                calls: Arc::new(AtomicUsize::new(0)),
+                seen: seen.clone(),
+            }),
+        );
+        let chat_id = state
+            .db
+            .resolve_or_create_chat_id("web", "retry-chat", Some("r"), "web")
+            .unwrap();
+        let context = AgentRequestContext {
+            caller_channel: "web",
+            chat_id,
+            chat_type: "web",
+            sender_id: None,
+            sender_is_admin: false,
+        };
+        assert_eq!(
+            regenerate_last_response(&state, context, None)
+                .await
+                .unwrap(),
+            None
+        );
+
+        store_user_message(&state.db, chat_id, "pick a name");
+        let first = process_with_agent(&state, context, None, None)
+            .await
+            .unwrap();
+        assert_eq!(first, "answer 1");
+        let second = regenerate_last_response(&state, context, Some("alt-model"))
+            .await
+            .unwrap();
+        assert_eq!(second.as_deref(), Some("answer 2"));
+
+        let seen = seen.lock().unwrap().clone();
+        assert_eq!(seen.len(), 2);
+        assert_ne!(seen[0].0.as_deref(), Some("alt-model"));
+        // The retry sees the question again, not the first answer.
+        assert_eq!(seen[1], (Some("alt-model".to_string()), seen[0].1));
+
+        let (json, _) = state.db.load_session(chat_id).unwrap().unwrap();
+        let session: Vec<Message> = serde_json::from_str(&json).unwrap();
+        assert_eq!(session.len(), 2);
+        assert!(matches!(&session[1].content, MessageContent::Text(t) if t == "answer 2"));
+
+        drop(state);
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    /// Activates `deep-research` on its first call, then keeps calling a
+    /// harmless tool so only the iteration budget ends the turn.
+    struct SkillLoopLlm {
+        calls: Arc<AtomicUsize>,
+        seen: SeenCalls,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for SkillLoopLlm {
+        async fn send_message(
+            &self,
+            system: &str,
+            messages: Vec<Message>,
+            tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            self.send_message_with_model(system, messages, tools, None)
+                .await
+        }
+
+        async fn send_message_with_model(
+            &self,
+            _system: &str,
+            messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+            model_override: Option<&str>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            let idx = self.calls.fetch_add(1, Ordering::SeqCst);
+            self.seen
+                .lock()
+                .unwrap()
+                .push((model_override.map(str::to_string), messages.len()));
+            let (name, input) = if idx == 0 {
+                ("activate_skill", json!({"skill_name": "deep-research"}))
+            } else {
+                ("get_current_time", json!({}))
+            };
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::ToolUse {
+                    id: format!("tool-{idx}"),
+                    name: name.to_string(),
+                    input,
+                }],
+                stop_reason: Some("tool_use".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_active_skill_overrides_model_and_iteration_budget() {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_skill_rt_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let state = test_state_with_llm(
+            &base_dir,
+            Box::new(SkillLoopLlm {
+                calls: Arc::new(AtomicUsize::new(0)),
+                seen: seen.clone(),
+            }),
+        );
+        let skill_dir =
+            std::path::PathBuf::from(state.config.skills_data_dir()).join("deep-research");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        std::fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\nname: deep-research\ndescription: Research\nmodel: heavy-model\nmax_tool_iterations: 3\n---\nDig.\n",
+        )
+        .unwrap();
+        let chat_id = state
+            .db
+            .resolve_or_create_chat_id("web", "skill-rt-chat", Some("s"), "web")
+            .unwrap();
+        let context = AgentRequestContext {
+            caller_channel: "web",
+            chat_id,
+            chat_type: "web",
+            sender_id: None,
+            sender_is_admin: false,
+        };
+
+        store_user_message(&state.db, chat_id, "research this");
+        let reply = process_with_agent(&state, context, None, None)
+            .await
+            .unwrap();
+        assert!(reply.contains("maximum number of tool iterations"));
+        let models: Vec<Option<String>> =
+            seen.lock().unwrap().iter().map(|c| c.0.clone()).collect();
+        // The skill applies from the call after its activation.
+        assert_eq!(models.len(), 3);
+        assert_ne!(models[0].as_deref(), Some("heavy-model"));
+        assert_eq!(models[1].as_deref(), Some("heavy-model"));
+        assert_eq!(models[2].as_deref(), Some("heavy-model"));
+
+        // Still active next turn, from the session history.
+        seen.lock().unwrap().clear();
+        store_user_message(&state.db, chat_id, "keep going");
+        process_with_agent(&state, context, None, None)
+            .await
+            .unwrap();
+        let seen_now = seen.lock().unwrap().clone();
+        assert_eq!(seen_now.len(), 3);
+        assert!(seen_now
+            .iter()
+            .all(|c| c.0.as_deref() == Some("heavy-model")));
+
+        // `/retry <model>` still wins for its turn.
+        seen.lock().unwrap().clear();
+        crate::run_control::set_next_model("web", chat_id, "alt-model").await;
+        store_user_message(&state.db, chat_id, "once more");
+        process_with_agent(&state, context, None, None)
+            .await
+            .unwrap();
+        assert_eq!(seen.lock().unwrap()[0].0.as_deref(), Some("alt-model"));
+
+        drop(state);
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_skill_iteration_budget_capped_by_config() {
+        let mut cfg = Config::test_defaults();
+        cfg.max_tool_iterations = 20;
+        let skill = |n: Option<usize>| super::SkillOverrides {
+            max_tool_iterations: n,
+            ..Default::default()
+        };
+        assert_eq!(skill(None).max_tool_iterations(&cfg), 20);
+        assert_eq!(skill(Some(5)).max_tool_iterations(&cfg), 5);
+        assert_eq!(skill(Some(200)).max_tool_iterations(&cfg), 20);
+
+        cfg.allow_skill_budget_increase = true;
+        assert_eq!(skill(Some(200)).max_tool_iterations(&cfg), 200);
+    }
+
+    struct OverlapTrackingLlm {
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+        last_user_texts: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for OverlapTrackingLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            let last_user = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == "user")
+                .map(|m| match &m.content {
+                    MessageContent::Text(t) => t.clone(),
+                    MessageContent::Blocks(_) => String::new(),
+                })
+                .unwrap_or_default();
+            self.last_user_texts.lock().unwrap().push(last_user);
+            tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text {
+                    text: "done".to_string(),
+                }],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serialize_per_chat_queues_second_turn_with_its_message() {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_serialize_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let last_user_texts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut state = test_state_with_llm(
+            &base_dir,
+            Box::new(OverlapTrackingLlm {
+                in_flight: Arc::new(AtomicUsize::new(0)),
+                max_in_flight: max_in_flight.clone(),
+                last_user_texts: last_user_texts.clone(),
+            }),
+        );
+        Arc::get_mut(&mut state).unwrap().config.serialize_per_chat = true;
+        let chat_id = state
+            .db
+            .resolve_or_create_chat_id("web", "serialize-chat", Some("s"), "web")
+            .unwrap();
+        let run = |state: Arc<AppState>| async move {
+            process_with_agent(
+                &state,
+                AgentRequestContext {
+                    caller_channel: "web",
+                    chat_id,
+                    chat_type: "web",
+                    sender_id: None,
+                    sender_is_admin: false,
+                },
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+        };
+
+        store_user_message(&state.db, chat_id, "first request");
+        let first = tokio::spawn(run(state.clone()));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        store_user_message(&state.db, chat_id, "second request");
+        let second = tokio::spawn(run(state.clone()));
+        assert_eq!(first.await.unwrap(), "done");
+        assert_eq!(second.await.unwrap(), "done");
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+        let texts = last_user_texts.lock().unwrap().clone();
+        assert_eq!(texts.len(), 2);
+        assert!(texts[0].contains("first request"));
+        assert!(!texts[0].contains("second request"));
+        // Sent while the first turn ran, after the session it saved was read.
+        assert!(texts[1].contains("second request"), "{}", texts[1]);
+
+        drop(state);
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    struct AskUserThenAnswerLlm {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for AskUserThenAnswerLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Ok(MessagesResponse {
+                    content: vec![ResponseContentBlock::ToolUse {
+                        id: "tool-ask".to_string(),
+                        name: "ask_user".to_string(),
+                        input: json!({"question": "Which region?", "options": ["eu", "us"]}),
+                    }],
+                    stop_reason: Some("tool_use".to_string()),
+                    usage: None,
+                });
+            }
+            let answered = matches!(
+                messages.as_slice(),
+                [.., Message { content: MessageContent::Blocks(results), .. }, Message { content: MessageContent::Text(reply), .. }]
+                    if reply.contains("eu")
+                        && results.iter().any(|b| matches!(b, ContentBlock::ToolResult { tool_use_id, .. } if tool_use_id == "tool-ask"))
+            );
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text {
+                    text: if answered {
+                        "deploying to eu"
+                    } else {
+                        "lost the question"
+                    }
+                    .to_string(),
+                }],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ask_user_pauses_turn_until_reply() {
+        let base_dir =
+            std::env::temp_dir().join(format!("mc_agent_ask_user_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&base_dir).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let state = test_state_with_llm(
+            &base_dir,
+            Box::new(AskUserThenAnswerLlm {
+                calls: calls.clone(),
+            }),
+        );
+        let chat_id = state
+            .db
+            .resolve_or_create_chat_id("mock", "ask-chat", Some("ask"), "mock_private")
+            .unwrap();
+        let context = AgentRequestContext {
+            caller_channel: "mock",
+            chat_id,
+            chat_type: "mock_private",
+            sender_id: Some("tester"),
+            sender_is_admin: false,
+        };
+        store_user_message(&state.db, chat_id, "deploy the app");
+
+        let reply = process_with_agent(&state, context, None, None)
+            .await
+            .unwrap();
+        assert_eq!(reply, "");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let history = state.db.get_all_messages(chat_id).unwrap();
+        assert!(history
+            .iter()
+            .any(|m| m.is_from_bot && m.content.starts_with("Which region?")));
+
+        store_user_message(&state.db, chat_id, "eu");
+        let reply = process_with_agent(&state, context, None, None)
+            .await
+            .unwrap();
+        assert_eq!(reply, "deploying to eu");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        drop(state);
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_cancel_expired_user_question_rewrites_pending_result() {
+        let paused = vec![
+            Message {
+                role: "assistant".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                    id: "ask-1".into(),
+                    name: "ask_user".into(),
+                    input: json!({"question": "Which region?"}),
+                }]),
+            },
+            Message {
+                role: "user".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                    tool_use_id: "ask-1".into(),
+                    content: "Question sent.".into(),
+                    is_error: None,
+                }]),
+            },
+        ];
+        let paused_at = "2026-01-01T00:00:00+00:00";
+        let at = |secs: i64| {
+            chrono::DateTime::parse_from_rfc3339(paused_at)
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+                + chrono::Duration::seconds(secs)
+        };
+
+        let mut messages = paused.clone();
+        assert!(!cancel_expired_user_question(
+            &mut messages,
+            paused_at,
+            600,
+            at(599)
+        ));
+        assert!(cancel_expired_user_question(
+            &mut messages,
+            paused_at,
+            600,
+            at(600)
+        ));
+        let MessageContent::Blocks(blocks) = &messages[1].content else {
+            panic!("expected blocks");
+        };
+        assert!(
+            matches!(&blocks[0], ContentBlock::ToolResult { content, .. } if content.contains("was cancelled"))
+        );
+
+        let mut finished = paused;
+        finished.push(Message {
+            role: "assistant".into(),
+            content: MessageContent::Text("done".into()),
+        });
+        assert!(!cancel_expired_user_question(
+            &mut finished,
+            paused_at,
+            600,
+            at(9999)
+        ));
+    }
+
+    #[test]
+    fn test_approval_card_buttons_map_to_approval_replies() {
+        let text = "High-risk tool 'bash' is waiting for your confirmation.";
+        let card = super::approval_card("bash", text);
+        assert_eq!(card.body, text);
+        assert_eq!(card.buttons.len(), 2);
+        assert!(super::is_explicit_user_approval(&card.buttons[0].value));
+        assert!(!super::is_explicit_user_approval(&card.buttons[1].value));
+    }
+
     #[tokio::test]
     async fn test_failed_tool_note_includes_bash_command_details() {
         let base_dir = std::env::temp_dir().join(format!(
@@ -2698,6 +4759,8 @@ mod tests {
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -2753,6 +4816,83 @@ mod tests {
         assert!(prompt.contains("avoid `/tmp` unless the user explicitly asks for it"));
     }
 
+    #[test]
+    fn test_attach_user_image_uses_image_block_or_note() {
+        let user = |text: &str| Message {
+            role: "user".into(),
+            content: MessageContent::Text(text.into()),
+        };
+        let image = ImageSource::base64("image/png", "AAAA");
+
+        let mut messages = vec![user("what is this?")];
+        super::attach_user_image(&mut messages, image.clone(), true);
+        let MessageContent::Blocks(blocks) = &messages[0].content else {
+            panic!("expected blocks");
+        };
+        assert!(matches!(&blocks[0], ContentBlock::Image { source } if source.data == "AAAA"));
+        assert!(matches!(&blocks[1], ContentBlock::Text { text } if text == "what is this?"));
+
+        let mut messages = vec![user("what is this?")];
+        super::attach_user_image(&mut messages, image, false);
+        let MessageContent::Blocks(blocks) = &messages[0].content else {
+            panic!("expected blocks");
+        };
+        assert_eq!(blocks.len(), 2);
+        assert!(matches!(
+            &blocks[0],
+            ContentBlock::Text { text } if text == super::IMAGE_OMITTED_NOTE
+        ));
+    }
+
+    #[test]
+    fn test_updated_skill_instructions_section_only_for_changed_active_skills() {
+        let dir = std::env::temp_dir().join(format!("mc_skill_refresh_{}", uuid::Uuid::new_v4()));
+        let write_skill = |name: &str, body: &str| {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(
+                dir.join(name).join("SKILL.md"),
+                format!("---\nname: {name}\ndescription: d\n---\n{body}\n"),
+            )
+            .unwrap();
+        };
+        write_skill("alpha", "old alpha");
+        write_skill("beta", "beta steps");
+        let skills = SkillManager::from_skills_dir(dir.to_str().unwrap());
+        let activation = |id: &str, skill: &str, body: &str| {
+            vec![
+                Message {
+                    role: "assistant".into(),
+                    content: MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                        id: id.into(),
+                        name: "activate_skill".into(),
+                        input: json!({"skill_name": skill}),
+                    }]),
+                },
+                Message {
+                    role: "user".into(),
+                    content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                        tool_use_id: id.into(),
+                        content: format!("# Skill: {skill}\n\n## Instructions\n\n{body}"),
+                        is_error: None,
+                    }]),
+                },
+            ]
+        };
+        let mut messages = activation("t1", "alpha", "old alpha");
+        messages.extend(activation("t2", "beta", "beta steps"));
+        assert_eq!(
+            super::updated_skill_instructions_section(&skills, &messages),
+            ""
+        );
+
+        write_skill("alpha", "new alpha");
+        let section = super::updated_skill_instructions_section(&skills, &messages);
+        assert!(section.contains("# Updated Skill Instructions"));
+        assert!(section.contains("## alpha\n\nnew alpha"));
+        assert!(!section.contains("## beta"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_is_explicit_user_approval() {
         assert!(super::is_explicit_user_approval(
@@ -2920,6 +5060,8 @@ timeout_ms: 1000
                 caller_channel: "web",
                 chat_id,
                 chat_type: "web",
+                sender_id: None,
+                sender_is_admin: false,
             },
             None,
             None,
@@ -0,0 +1,190 @@
+//! `microclaw audit`: query the tool execution audit log.
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use microclaw_storage::db::{ToolAuditFilter, ToolAuditRecord};
+
+use crate::config::Config;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "microclaw audit",
+    about = "Query the tool execution audit log (newest first)",
+    disable_help_subcommand = true
+)]
+struct AuditCli {
+    /// Only executions of this tool
+    #[arg(long)]
+    tool: Option<String>,
+    /// Only calls from this channel (e.g. feishu, web)
+    #[arg(long)]
+    channel: Option<String>,
+    /// Only calls from this chat id
+    #[arg(long)]
+    chat_id: Option<i64>,
+    /// Only failed executions
+    #[arg(long)]
+    errors: bool,
+    /// Only records since an RFC 3339 time or a relative age such as 30m, 24h, 7d
+    #[arg(long)]
+    since: Option<String>,
+    /// Maximum records to print
+    #[arg(long, default_value_t = 50)]
+    limit: usize,
+    /// Print one JSON object per line
+    #[arg(long)]
+    json: bool,
+}
+
+pub async fn handle_audit_cli(args: &[String]) -> Result<()> {
+    let cli = match AuditCli::try_parse_from(
+        std::iter::once("audit").chain(args.iter().map(std::string::String::as_str)),
+    ) {
+        Ok(cli) => cli,
+        Err(err)
+            if matches!(
+                err.kind(),
+                clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion
+            ) =>
+        {
+            err.print()?;
+            return Ok(());
+        }
+        Err(err) => return Err(anyhow!(err.to_string())),
+    };
+    let filter = ToolAuditFilter {
+        channel: cli.channel,
+        chat_id: cli.chat_id,
+        tool_name: cli.tool,
+        status: cli.errors.then(|| "error".to_string()),
+        since: cli
+            .since
+            .as_deref()
+            .map(|v| parse_since(v, chrono::Utc::now()))
+            .transpose()?,
+        limit: cli.limit.max(1),
+    };
+
+    let config = Config::load()?;
+    let db = config.open_database()?;
+    let records = db.list_tool_audit_logs(&filter)?;
+    if records.is_empty() && !cli.json {
+        println!("No tool executions recorded.");
+        return Ok(());
+    }
+    for record in &records {
+        if cli.json {
+            println!("{}", record_json(record));
+        } else {
+            println!("{}", format_record(record));
+        }
+    }
+    Ok(())
+}
+
+/// Resolve `--since` to an RFC 3339 lower bound.
+fn parse_since(value: &str, now: chrono::DateTime<chrono::Utc>) -> Result<String> {
+    let value = value.trim();
+    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&chrono::Utc).to_rfc3339());
+    }
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow!("invalid --since '{value}': use RFC 3339 or e.g. 30m, 24h, 7d"))?;
+    let age = match unit {
+        "s" => chrono::Duration::seconds(amount),
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        _ => {
+            return Err(anyhow!(
+                "invalid --since '{value}': use RFC 3339 or e.g. 30m, 24h, 7d"
+            ))
+        }
+    };
+    Ok((now - age).to_rfc3339())
+}
+
+fn format_record(r: &ToolAuditRecord) -> String {
+    let outcome = match &r.error_type {
+        Some(t) if r.status == "error" => format!("error:{t}"),
+        _ => r.status.clone(),
+    };
+    format!(
+        "{}\t{}:{}\t{}\trisk={}\tapproval={}\t{}\t{}ms\t{}",
+        r.created_at,
+        r.channel,
+        r.chat_id,
+        r.tool_name,
+        r.risk,
+        r.approval,
+        outcome,
+        r.duration_ms,
+        r.input
+    )
+}
+
+fn record_json(r: &ToolAuditRecord) -> serde_json::Value {
+    serde_json::json!({
+        "id": r.id,
+        "created_at": r.created_at,
+        "channel": r.channel,
+        "chat_id": r.chat_id,
+        "tool_name": r.tool_name,
+        "input": r.input,
+        "risk": r.risk,
+        "approval": r.approval,
+        "status": r.status,
+        "error_type": r.error_type,
+        "duration_ms": r.duration_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since_relative_and_absolute() {
+        let now = chrono::DateTime::parse_from_rfc3339("2025-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            parse_since("90m", now).unwrap(),
+            "2025-03-10T10:30:00+00:00"
+        );
+        assert_eq!(parse_since("7d", now).unwrap(), "2025-03-03T12:00:00+00:00");
+        assert_eq!(
+            parse_since("2025-03-01T08:00:00+08:00", now).unwrap(),
+            "2025-03-01T00:00:00+00:00"
+        );
+        assert!(parse_since("yesterday", now).is_err());
+        assert!(parse_since("5w", now).is_err());
+    }
+
+    #[test]
+    fn test_format_record_shows_error_type() {
+        let record = ToolAuditRecord {
+            id: 1,
+            created_at: "2025-03-10T12:00:00+00:00".into(),
+            channel: "feishu".into(),
+            chat_id: 9,
+            tool_name: "bash".into(),
+            input: r#"{"command":"false"}"#.into(),
+            risk: "high".into(),
+            approval: "not_required".into(),
+            status: "error".into(),
+            error_type: Some("process_exit".into()),
+            duration_ms: 5,
+        };
+        assert_eq!(
+            format_record(&record),
+            "2025-03-10T12:00:00+00:00\tfeishu:9\tbash\trisk=high\tapproval=not_required\terror:process_exit\t5ms\t{\"command\":\"false\"}"
+        );
+        assert_eq!(record_json(&record)["error_type"], "process_exit");
+    }
+}
@@ -0,0 +1,157 @@
+//! `microclaw db backup`: a redacted config snapshot plus an optional
+//! consistent copy of the SQLite database, packed as a zip archive.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::config::Config;
+use microclaw_core::error::MicroClawError;
+use microclaw_storage::db::{call_blocking, Database};
+
+pub const CONFIG_ENTRY: &str = "microclaw.config.redacted.yaml";
+pub const DB_ENTRY: &str = "microclaw.db";
+
+/// Build the archive in memory. The database is copied with SQLite's online
+/// backup API into a scratch file first, never by copying `microclaw.db` itself.
+pub async fn build_backup_archive(
+    config: &Config,
+    db: Option<Arc<Database>>,
+) -> Result<Vec<u8>, MicroClawError> {
+    let config_yaml = config.redacted_yaml()?;
+    let db_bytes = match db {
+        Some(db) => Some(call_blocking(db, snapshot_database).await?),
+        None => None,
+    };
+
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let zip_err = |e: zip::result::ZipError| MicroClawError::ToolExecution(e.to_string());
+    writer.start_file(CONFIG_ENTRY, options).map_err(zip_err)?;
+    writer.write_all(config_yaml.as_bytes())?;
+    if let Some(bytes) = db_bytes {
+        writer.start_file(DB_ENTRY, options).map_err(zip_err)?;
+        writer.write_all(&bytes)?;
+    }
+    Ok(writer.finish().map_err(zip_err)?.into_inner())
+}
+
+fn snapshot_database(db: &Database) -> Result<Vec<u8>, MicroClawError> {
+    let path = std::env::temp_dir().join(format!(
+        "microclaw-backup-{}.db",
+        uuid::Uuid::new_v4().simple()
+    ));
+    let result = db.backup_to(&path).and_then(|_| Ok(std::fs::read(&path)?));
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+pub fn backup_file_name(now: chrono::DateTime<chrono::Utc>) -> String {
+    format!("microclaw-backup-{}.zip", now.format("%Y%m%d-%H%M%S"))
+}
+
+/// Write a backup archive to `output`, or to `microclaw-backup-<timestamp>.zip`
+/// in the current directory, and return the path written.
+pub async fn write_backup(
+    config: &Config,
+    include_db: bool,
+    output: Option<PathBuf>,
+) -> Result<PathBuf, MicroClawError> {
+    let db = if include_db {
+        Some(Arc::new(config.open_database()?))
+    } else {
+        None
+    };
+    let bytes = build_backup_archive(config, db).await?;
+    let path = output.unwrap_or_else(|| PathBuf::from(backup_file_name(chrono::Utc::now())));
+    std::fs::write(&path, &bytes)?;
+    info!(
+        "Backup written to {} ({} bytes, include_db={include_db})",
+        path.display(),
+        bytes.len()
+    );
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read_entry(archive: &[u8], name: &str) -> Option<Vec<u8>> {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut file = zip.by_name(name).ok()?;
+        let mut out = Vec::new();
+        file.read_to_end(&mut out).unwrap();
+        Some(out)
+    }
+
+    #[tokio::test]
+    async fn test_backup_archive_masks_secrets_and_snapshots_db() {
+        let dir = std::env::temp_dir().join(format!("mc_backup_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        db.insert_memory(Some(1), "backup me", "KNOWLEDGE").unwrap();
+
+        let mut config = Config::test_defaults();
+        config.api_key = "sk-live-super-secret".into();
+        config.channels.insert(
+            "feishu".into(),
+            serde_yaml::from_str("app_id: cli_a\napp_secret: feishu-secret-value\n").unwrap(),
+        );
+
+        let archive = build_backup_archive(&config, Some(db)).await.unwrap();
+        let yaml = String::from_utf8(read_entry(&archive, CONFIG_ENTRY).unwrap()).unwrap();
+        assert!(!yaml.contains("sk-live-super-secret"));
+        assert!(!yaml.contains("feishu-secret-value"));
+        assert!(yaml.contains("cli_a"));
+        assert!(yaml.contains(microclaw_tools::redaction::REDACTED_PLACEHOLDER));
+
+        let db_bytes = read_entry(&archive, DB_ENTRY).unwrap();
+        let restored_dir = dir.join("restored");
+        std::fs::create_dir_all(&restored_dir).unwrap();
+        std::fs::write(restored_dir.join("microclaw.db"), db_bytes).unwrap();
+        let restored = Database::new(restored_dir.to_str().unwrap()).unwrap();
+        let memories = restored.get_all_memories_for_chat(Some(1)).unwrap();
+        assert!(memories.iter().any(|m| m.content == "backup me"));
+
+        let config_only = build_backup_archive(&config, None).await.unwrap();
+        assert!(read_entry(&config_only, DB_ENTRY).is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_write_backup_to_file() {
+        let dir = std::env::temp_dir().join(format!("mc_backup_{}", uuid::Uuid::new_v4()));
+        let mut config = Config::test_defaults();
+        config.data_dir = dir.to_string_lossy().to_string();
+        config
+            .open_database()
+            .unwrap()
+            .insert_memory(Some(1), "backup me", "KNOWLEDGE")
+            .unwrap();
+
+        let output = dir.join("out.zip");
+        let path = write_backup(&config, true, Some(output.clone()))
+            .await
+            .unwrap();
+        assert_eq!(path, output);
+        let archive = std::fs::read(&path).unwrap();
+        assert!(read_entry(&archive, CONFIG_ENTRY).is_some());
+        assert!(read_entry(&archive, DB_ENTRY).is_some());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_backup_file_name() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-04T05:06:07Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            backup_file_name(now),
+            "microclaw-backup-20260304-050607.zip"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Store files users send into the chat's working dir so the agent's tools can
+//! open them, and describe each one in the agent turn.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use microclaw_channels::attachment::{AttachmentKind, InboundAttachment};
+use microclaw_tools::path_guard;
+use microclaw_tools::runtime::resolve_chat_working_dir;
+
+/// Largest image passed to the model as a vision block; bigger ones are only saved.
+const VISION_IMAGE_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+const ATTACHMENT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Attachment as sent in a webhook payload: inline base64 or a URL to fetch.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebhookAttachment {
+    #[serde(default, alias = "filename")]
+    pub file_name: String,
+    #[serde(default, alias = "content_type")]
+    pub media_type: Option<String>,
+    #[serde(default)]
+    pub content_base64: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// What the agent turn gets for a message's attachments.
+#[derive(Debug, Default)]
+pub struct IngestedAttachments {
+    /// One `[attachment] ...` line per attachment, saved or not.
+    pub notes: Vec<String>,
+    /// First image, if small enough, as (base64, media_type) for vision-capable models.
+    pub image_data: Option<(String, String)>,
+}
+
+impl IngestedAttachments {
+    /// `text` with the attachment notes appended; JSON placeholders some
+    /// platforms send as the text of a file message are replaced.
+    pub fn annotate(&self, text: &str) -> String {
+        let notes = self.notes.join("\n");
+        let text = text.trim();
+        if notes.is_empty() {
+            text.to_string()
+        } else if text.is_empty() || text.starts_with('{') {
+            notes
+        } else {
+            format!("{text}\n\n{notes}")
+        }
+    }
+}
+
+fn max_attachment_bytes(config: &Config) -> u64 {
+    config
+        .max_document_size_mb
+        .saturating_mul(1024)
+        .saturating_mul(1024)
+}
+
+/// Save `attachments` under `uploads/` in the chat's working dir, size-limited by
+/// `max_document_size_mb` and checked against the path guard.
+pub async fn ingest_attachments(
+    config: &Config,
+    channel: &str,
+    chat_id: i64,
+    is_control_chat: bool,
+    attachments: Vec<InboundAttachment>,
+) -> IngestedAttachments {
+    let mut out = IngestedAttachments::default();
+    if attachments.is_empty() {
+        return out;
+    }
+    let max_bytes = max_attachment_bytes(config);
+    let upload_dir = resolve_chat_working_dir(
+        &config.working_dir_for_channel(channel),
+        config.resolved_working_dir_isolation(),
+        channel,
+        chat_id,
+        is_control_chat,
+    )
+    .join("uploads");
+
+    for attachment in attachments {
+        let kind = attachment.kind();
+        let media_type = attachment.effective_media_type();
+        let mut note = format!(
+            "[attachment] kind={} name={}",
+            kind.as_str(),
+            attachment.safe_file_name()
+        );
+        if let Some(m) = &media_type {
+            note.push_str(&format!(" type={m}"));
+        }
+        note.push_str(&format!(" bytes={}", attachment.data.len()));
+
+        if attachment.data.len() as u64 > max_bytes {
+            note.push_str(&format!(
+                " not saved: larger than max_document_size_mb ({} MB)",
+                config.max_document_size_mb
+            ));
+            out.notes.push(note);
+            continue;
+        }
+        match save_attachment(&upload_dir, &attachment).await {
+            Ok(path) => {
+                info!(
+                    channel,
+                    chat_id,
+                    kind = kind.as_str(),
+                    bytes = attachment.data.len(),
+                    "Saved inbound attachment to {}",
+                    path.display()
+                );
+                note.push_str(&format!(" saved_path={}", path.display()));
+            }
+            Err(e) => {
+                warn!(channel, chat_id, "Failed to save inbound attachment: {e}");
+                note.push_str(&format!(" not saved: {e}"));
+            }
+        }
+        out.notes.push(note);
+
+        if kind == AttachmentKind::Image
+            && config.attachment_image_vision
+            && out.image_data.is_none()
+            && attachment.data.len() <= VISION_IMAGE_MAX_BYTES
+        {
+            if let Some(media_type) = media_type {
+                use base64::Engine;
+                let encoded = base64::engine::general_purpose::STANDARD.encode(&attachment.data);
+                out.image_data = Some((encoded, media_type));
+            }
+        }
+    }
+    out
+}
+
+async fn save_attachment(dir: &Path, attachment: &InboundAttachment) -> Result<PathBuf, String> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("cannot create {}: {e}", dir.display()))?;
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let ts = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let name = attachment.safe_file_name();
+    let mut path = dir.join(format!("{ts}-{name}"));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{ts}-{n}-{name}"));
+        n += 1;
+    }
+    path_guard::check_path(&path.to_string_lossy())?;
+    tokio::fs::write(&path, &attachment.data)
+        .await
+        .map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Decode or download webhook attachments. Ones that cannot be fetched come
+/// back as notes instead, so the agent still learns they were sent.
+pub async fn fetch_webhook_attachments(
+    config: &Config,
+    attachments: &[WebhookAttachment],
+) -> (Vec<InboundAttachment>, Vec<String>) {
+    let max_bytes = max_attachment_bytes(config);
+    let mut fetched = Vec::new();
+    let mut failures = Vec::new();
+    for attachment in attachments {
+        let data = match (&attachment.content_base64, &attachment.url) {
+            (Some(encoded), _) => {
+                use base64::Engine;
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .map_err(|e| format!("invalid base64: {e}"))
+            }
+            (None, Some(url)) => download_attachment(url, max_bytes).await,
+            (None, None) => Err("no content_base64 or url".to_string()),
+        };
+        match data {
+            Ok(data) => fetched.push(InboundAttachment::new(
+                attachment.file_name.clone(),
+                attachment.media_type.clone(),
+                data,
+            )),
+            Err(e) => failures.push(format!(
+                "[attachment] name={} not saved: {e}",
+                InboundAttachment::new(attachment.file_name.clone(), None, Vec::new())
+                    .safe_file_name()
+            )),
+        }
+    }
+    (fetched, failures)
+}
+
+async fn download_attachment(url: &str, max_bytes: u64) -> Result<Vec<u8>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(ATTACHMENT_DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| format!("download failed: {e}"))?;
+    let mut resp = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("download failed: {e}"))?;
+    let too_large = || format!("larger than max_document_size_mb ({max_bytes} bytes)");
+    if resp.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("download failed: {e}"))?
+    {
+        if (data.len() + chunk.len()) as u64 > max_bytes {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WorkingDirIsolation;
+
+    fn test_config(dir: &Path) -> Config {
+        let mut config = Config::test_defaults();
+        config.working_dir = dir.to_string_lossy().to_string();
+        config.working_dir_isolation = WorkingDirIsolation::Chat;
+        config.max_document_size_mb = 1;
+        config
+    }
+
+    #[tokio::test]
+    async fn saves_into_chat_uploads_and_passes_first_image_to_vision() {
+        let dir = std::env::temp_dir().join(format!("mc_attach_{}", uuid::Uuid::new_v4()));
+        let config = test_config(&dir);
+        let png = vec![0x89, b'P', b'N', b'G', 1, 2, 3];
+        let ingested = ingest_attachments(
+            &config,
+            "feishu",
+            42,
+            false,
+            vec![
+                InboundAttachment::new("../shot.png", None, png.clone()),
+                InboundAttachment::new("notes.txt", Some("text/plain".into()), b"hi".to_vec()),
+                InboundAttachment::new("big.bin", None, vec![0; 1024 * 1024 + 1]),
+            ],
+        )
+        .await;
+
+        assert_eq!(ingested.notes.len(), 3);
+        assert!(ingested.notes[0].starts_with("[attachment] kind=image name=shot.png"));
+        let saved = ingested.notes[1]
+            .split(" saved_path=")
+            .nth(1)
+            .map(PathBuf::from)
+            .unwrap();
+        assert!(saved.starts_with(dir.join("chat").join("feishu").join("42").join("uploads")));
+        assert_eq!(std::fs::read(&saved).unwrap(), b"hi");
+        assert!(ingested.notes[2].contains("not saved: larger than max_document_size_mb"));
+        let (_, media_type) = ingested.image_data.as_ref().unwrap();
+        assert_eq!(media_type, "image/png");
+
+        let annotated = ingested.annotate("{\"file_key\":\"x\"}");
+        assert!(annotated.starts_with("[attachment] kind=image"));
+        assert!(ingested
+            .annotate("look")
+            .starts_with("look\n\n[attachment]"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn vision_can_be_disabled() {
+        let dir = std::env::temp_dir().join(format!("mc_attach_{}", uuid::Uuid::new_v4()));
+        let mut config = test_config(&dir);
+        config.attachment_image_vision = false;
+        let ingested = ingest_attachments(
+            &config,
+            "email",
+            7,
+            false,
+            vec![InboundAttachment::new(
+                "a.jpg",
+                None,
+                vec![0xFF, 0xD8, 0xFF, 0],
+            )],
+        )
+        .await;
+        assert!(ingested.image_data.is_none());
+        assert!(ingested.notes[0].contains("saved_path="));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn webhook_attachments_decode_base64_and_report_failures() {
+        let config = Config::test_defaults();
+        let (fetched, failures) = fetch_webhook_attachments(
+            &config,
+            &[
+                WebhookAttachment {
+                    file_name: "a.txt".into(),
+                    media_type: Some("text/plain".into()),
+                    content_base64: Some("aGVsbG8=".into()),
+                    url: None,
+                },
+                WebhookAttachment {
+                    file_name: "b.txt".into(),
+                    ..Default::default()
+                },
+            ],
+        )
+        .await;
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].data, b"hello");
+        assert_eq!(
+            failures,
+            vec!["[attachment] name=b.txt not saved: no content_base64 or url".to_string()]
+        );
+    }
+}
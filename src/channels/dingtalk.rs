@@ -4,19 +4,23 @@ use std::sync::Arc;
 use axum::http::HeaderMap;
 use axum::{Json, Router};
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::agent_engine::process_with_agent_with_events;
 use crate::agent_engine::{AgentEvent, AgentRequestContext};
+use crate::channels::attachments::{
+    fetch_webhook_attachments, ingest_attachments, WebhookAttachment,
+};
 use crate::channels::startup_guard::{
     mark_channel_started, parse_epoch_ms_from_seconds_str, parse_epoch_ms_from_str,
-    should_drop_pre_start_message, should_drop_recent_duplicate_message,
+    should_drop_duplicate_delivery, should_drop_pre_start_message,
 };
+use crate::channels::user_access::{SenderAccess, SenderAccessPolicy};
 use crate::chat_commands::{handle_chat_command, is_slash_command, unknown_command_response};
 use crate::runtime::AppState;
 use crate::setup_def::{ChannelFieldDef, DynamicChannelDef};
-use microclaw_channels::channel::ConversationKind;
-use microclaw_channels::channel_adapter::ChannelAdapter;
+use microclaw_channels::channel::{record_send_result, ConversationKind};
+use microclaw_channels::channel_adapter::{apply_mentions, ChannelAdapter, ResolvedMention};
 use microclaw_storage::db::{call_blocking, StoredMessage};
 
 pub const SETUP_DEF: DynamicChannelDef = DynamicChannelDef {
@@ -66,6 +70,7 @@ pub const SETUP_DEF: DynamicChannelDef = DynamicChannelDef {
             required: false,
         },
     ],
+    validate: None,
 };
 
 fn default_enabled() -> bool {
@@ -82,6 +87,12 @@ pub struct DingTalkAccountConfig {
     pub robot_webhook_url: String,
     #[serde(default)]
     pub allowed_chat_ids: String,
+    /// Sender id patterns (csv) allowed to talk to the bot; empty allows everyone.
+    #[serde(default)]
+    pub allowed_user_ids: String,
+    /// Sender id patterns (csv) whose tool calls get control-chat privileges.
+    #[serde(default)]
+    pub admin_user_ids: String,
     #[serde(default)]
     pub webhook_token: String,
     #[serde(default)]
@@ -98,6 +109,12 @@ pub struct DingTalkChannelConfig {
     pub robot_webhook_url: String,
     #[serde(default)]
     pub allowed_chat_ids: String,
+    /// Sender id patterns (csv) allowed to talk to the bot; empty allows everyone.
+    #[serde(default)]
+    pub allowed_user_ids: String,
+    /// Sender id patterns (csv) whose tool calls get control-chat privileges.
+    #[serde(default)]
+    pub admin_user_ids: String,
     #[serde(default = "default_webhook_path")]
     pub webhook_path: String,
     #[serde(default)]
@@ -114,6 +131,7 @@ pub struct DingTalkChannelConfig {
 struct DingTalkWebhookPayload {
     chat_id: String,
     sender_id: String,
+    #[serde(default)]
     text: String,
     #[serde(default)]
     message_id: String,
@@ -123,6 +141,9 @@ struct DingTalkWebhookPayload {
     timestamp: Option<String>,
     #[serde(default)]
     timestamp_ms: Option<i64>,
+    /// Pictures and files in the message, inline (`content_base64`) or by `url`.
+    #[serde(default)]
+    attachments: Vec<WebhookAttachment>,
 }
 
 #[derive(Debug, Clone)]
@@ -130,6 +151,7 @@ pub struct DingTalkRuntimeContext {
     pub channel_name: String,
     pub robot_webhook_url: String,
     pub allowed_chat_ids: Vec<String>,
+    pub sender_access: SenderAccessPolicy,
     pub webhook_token: String,
     pub bot_username: String,
     pub model: Option<String>,
@@ -214,6 +236,11 @@ pub fn build_dingtalk_runtime_contexts(
             channel_name,
             robot_webhook_url,
             allowed_chat_ids: parse_csv(&account_cfg.allowed_chat_ids),
+            sender_access: SenderAccessPolicy {
+                allowed: parse_csv(&account_cfg.allowed_user_ids),
+                admins: parse_csv(&account_cfg.admin_user_ids),
+                ignore_case: false,
+            },
             webhook_token,
             bot_username,
             model,
@@ -224,6 +251,11 @@ pub fn build_dingtalk_runtime_contexts(
             channel_name: "dingtalk".to_string(),
             robot_webhook_url: dt_cfg.robot_webhook_url.trim().to_string(),
             allowed_chat_ids: parse_csv(&dt_cfg.allowed_chat_ids),
+            sender_access: SenderAccessPolicy {
+                allowed: parse_csv(&dt_cfg.allowed_user_ids),
+                admins: parse_csv(&dt_cfg.admin_user_ids),
+                ignore_case: false,
+            },
             webhook_token: dt_cfg.webhook_token.trim().to_string(),
             bot_username: config.bot_username_for_channel("dingtalk"),
             model: dt_cfg
@@ -251,26 +283,18 @@ impl DingTalkAdapter {
             http_client: reqwest::Client::new(),
         }
     }
-}
-
-#[async_trait::async_trait]
-impl ChannelAdapter for DingTalkAdapter {
-    fn name(&self) -> &str {
-        &self.name
-    }
 
-    fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
-        vec![("dingtalk_group", ConversationKind::Group)]
-    }
-
-    async fn send_text(&self, _external_chat_id: &str, text: &str) -> Result<(), String> {
+    async fn post_text(&self, text: &str, at_user_ids: &[String]) -> Result<(), String> {
         if self.robot_webhook_url.trim().is_empty() {
             return Err("dingtalk.robot_webhook_url is empty".to_string());
         }
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "msgtype": "text",
             "text": { "content": text }
         });
+        if !at_user_ids.is_empty() {
+            body["at"] = serde_json::json!({ "atUserIds": at_user_ids });
+        }
         let resp = self
             .http_client
             .post(self.robot_webhook_url.trim())
@@ -287,6 +311,50 @@ impl ChannelAdapter for DingTalkAdapter {
     }
 }
 
+#[async_trait::async_trait]
+impl ChannelAdapter for DingTalkAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
+        vec![("dingtalk_group", ConversationKind::Group)]
+    }
+
+    async fn send_text(&self, _external_chat_id: &str, text: &str) -> Result<(), String> {
+        self.post_text(text, &[]).await
+    }
+
+    /// Robot webhooks have no member directory, so only sender ids already seen
+    /// in the chat can be pinged.
+    async fn resolve_mention(
+        &self,
+        _external_chat_id: &str,
+        name: &str,
+        known_ids: &[String],
+    ) -> Option<ResolvedMention> {
+        known_ids
+            .iter()
+            .any(|id| id == name)
+            .then(|| ResolvedMention {
+                name: name.to_string(),
+                user_id: name.to_string(),
+                display: name.to_string(),
+            })
+    }
+
+    async fn send_text_with_mentions(
+        &self,
+        _external_chat_id: &str,
+        text: &str,
+        mentions: &[ResolvedMention],
+    ) -> Result<(), String> {
+        let text = apply_mentions(text, mentions, |m| format!("@{}", m.user_id));
+        let user_ids: Vec<String> = mentions.iter().map(|m| m.user_id.clone()).collect();
+        self.post_text(&text, &user_ids).await
+    }
+}
+
 pub async fn start_dingtalk_bot(_app_state: Arc<AppState>, runtime: DingTalkRuntimeContext) {
     mark_channel_started(&runtime.channel_name);
     info!("DingTalk adapter '{}' is ready", runtime.channel_name);
@@ -339,7 +407,7 @@ async fn dingtalk_webhook_handler(
     }
     let chat_id_external = payload.chat_id.trim();
     let text = payload.text.trim();
-    if chat_id_external.is_empty() || text.is_empty() {
+    if chat_id_external.is_empty() || (text.is_empty() && payload.attachments.is_empty()) {
         return axum::http::StatusCode::BAD_REQUEST;
     }
     if !runtime_ctx.allowed_chat_ids.is_empty()
@@ -350,12 +418,20 @@ async fn dingtalk_webhook_handler(
     {
         return axum::http::StatusCode::FORBIDDEN;
     }
-    tokio::spawn({
+    let sender_access = runtime_ctx
+        .sender_access
+        .resolve(payload.sender_id.trim(), &app_state.config.user_groups);
+    if sender_access == SenderAccess::Denied {
+        return axum::http::StatusCode::FORBIDDEN;
+    }
+    let sender_is_admin = sender_access.is_admin();
+    let queues = app_state.channel_registry.inbound_queues().clone();
+    queues.submit(&runtime_ctx.channel_name, {
         let state = app_state.clone();
         let runtime = runtime_ctx.clone();
         let payload = payload.clone();
         async move {
-            process_dingtalk_webhook_message(state, runtime, payload).await;
+            process_dingtalk_webhook_message(state, runtime, payload, sender_is_admin).await;
         }
     });
     axum::http::StatusCode::OK
@@ -365,6 +441,7 @@ async fn process_dingtalk_webhook_message(
     app_state: Arc<AppState>,
     runtime_ctx: DingTalkRuntimeContext,
     payload: DingTalkWebhookPayload,
+    sender_is_admin: bool,
 ) {
     let chat_id_external = payload.chat_id.trim().to_string();
     let text = payload.text.trim().to_string();
@@ -418,7 +495,13 @@ async fn process_dingtalk_webhook_message(
     ) {
         return;
     }
-    if should_drop_recent_duplicate_message(&runtime_ctx.channel_name, &inbound_message_id) {
+    if should_drop_duplicate_delivery(
+        &app_state.db,
+        &runtime_ctx.channel_name,
+        &inbound_message_id,
+    )
+    .await
+    {
         return;
     }
     if is_slash_command(&text) {
@@ -428,6 +511,7 @@ async fn process_dingtalk_webhook_message(
             &runtime_ctx.channel_name,
             &text,
             Some(payload.sender_id.as_str()),
+            sender_is_admin,
         )
         .await
         {
@@ -447,11 +531,27 @@ async fn process_dingtalk_webhook_message(
             .await;
         return;
     }
+    let (fetched, failures) =
+        fetch_webhook_attachments(&app_state.config, &payload.attachments).await;
+    let mut ingested = ingest_attachments(
+        &app_state.config,
+        &runtime_ctx.channel_name,
+        chat_id,
+        sender_is_admin || app_state.config.control_chat_ids.contains(&chat_id),
+        fetched,
+    )
+    .await;
+    ingested.notes.extend(failures);
+    let content = if ingested.notes.is_empty() {
+        text.to_string()
+    } else {
+        ingested.annotate(&text)
+    };
     let stored = StoredMessage {
         id: inbound_message_id.clone(),
         chat_id,
         sender_name: payload.sender_id.clone(),
-        content: text.to_string(),
+        content,
         is_from_bot: false,
         timestamp: chrono::Utc::now().to_rfc3339(),
     };
@@ -467,20 +567,41 @@ async fn process_dingtalk_webhook_message(
         );
         return;
     }
+    if let Some(remaining) = app_state
+        .channel_registry
+        .health()
+        .backoff_remaining(&runtime_ctx.channel_name)
+    {
+        warn!(
+            "DingTalk: '{}' is backing off after repeated send failures ({}s left); skipping chat {}",
+            runtime_ctx.channel_name,
+            remaining.as_secs(),
+            chat_id
+        );
+        return;
+    }
     let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
-    match process_with_agent_with_events(
+    let typing = app_state
+        .channel_registry
+        .start_typing(&runtime_ctx.channel_name, &chat_id_external);
+    let result = process_with_agent_with_events(
         &app_state,
         AgentRequestContext {
             caller_channel: &runtime_ctx.channel_name,
             chat_id,
             chat_type: "group",
+            sender_id: Some(&payload.sender_id),
+            sender_is_admin,
         },
         None,
-        None,
+        ingested.image_data,
         Some(&event_tx),
     )
-    .await
-    {
+    .await;
+    if let Some(typing) = typing {
+        typing.stop().await;
+    }
+    match result {
         Ok(response) => {
             drop(event_tx);
             let mut used_send_message_tool = false;
@@ -503,7 +624,18 @@ async fn process_dingtalk_webhook_message(
                     );
                 }
             } else if !response.is_empty() {
-                if let Err(e) = adapter.send_text(&chat_id_external, &response).await {
+                let outgoing = app_state
+                    .channel_registry
+                    .decorate_reply(&runtime_ctx.channel_name, &response);
+                let send_result = adapter.send_text(&chat_id_external, &outgoing).await;
+                record_send_result(
+                    &app_state.channel_registry,
+                    app_state.db.clone(),
+                    &runtime_ctx.channel_name,
+                    &send_result,
+                )
+                .await;
+                if let Err(e) = send_result {
                     error!("DingTalk: failed to send response: {e}");
                 }
                 let bot_msg = StoredMessage {
@@ -517,12 +649,18 @@ async fn process_dingtalk_webhook_message(
                 let _ =
                     call_blocking(app_state.db.clone(), move |db| db.store_message(&bot_msg)).await;
             } else {
-                let _ = adapter
-                    .send_text(
-                        &chat_id_external,
-                        "I couldn't produce a visible reply after an automatic retry. Please try again.",
-                    )
-                    .await;
+                let outgoing = app_state.channel_registry.decorate_reply(
+                    &runtime_ctx.channel_name,
+                    "I couldn't produce a visible reply after an automatic retry. Please try again.",
+                );
+                let send_result = adapter.send_text(&chat_id_external, &outgoing).await;
+                record_send_result(
+                    &app_state.channel_registry,
+                    app_state.db.clone(),
+                    &runtime_ctx.channel_name,
+                    &send_result,
+                )
+                .await;
             }
         }
         Err(e) => {
@@ -1,23 +1,30 @@
 use std::collections::HashMap;
 use std::io::Write;
+use std::path::Path;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
 
 use axum::response::IntoResponse;
 use axum::{http::HeaderMap, Json, Router};
 use serde::Deserialize;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::agent_engine::process_with_agent_with_events;
 use crate::agent_engine::{AgentEvent, AgentRequestContext};
+use crate::channels::attachments::{
+    fetch_webhook_attachments, ingest_attachments, WebhookAttachment,
+};
 use crate::channels::startup_guard::{
     mark_channel_started, parse_epoch_ms_from_seconds_str, parse_epoch_ms_from_str,
-    should_drop_pre_start_message, should_drop_recent_duplicate_message,
+    should_drop_duplicate_delivery, should_drop_pre_start_message,
 };
+use crate::channels::user_access::{SenderAccess, SenderAccessPolicy};
 use crate::chat_commands::{handle_chat_command, is_slash_command, unknown_command_response};
 use crate::runtime::AppState;
-use crate::setup_def::{ChannelFieldDef, DynamicChannelDef};
-use microclaw_channels::channel::ConversationKind;
+use crate::setup_def::{
+    ChannelAccountCheck, ChannelFieldDef, ChannelValidationFuture, DynamicChannelDef,
+};
+use microclaw_channels::channel::{record_send_result, ConversationKind};
 use microclaw_channels::channel_adapter::ChannelAdapter;
 use microclaw_core::text::split_text;
 use microclaw_storage::db::{call_blocking, StoredMessage};
@@ -76,8 +83,32 @@ pub const SETUP_DEF: DynamicChannelDef = DynamicChannelDef {
             required: false,
         },
     ],
+    validate: Some(validate_accounts),
 };
 
+/// `doctor channels` hook. Email has no remote identity, so this confirms the
+/// sendmail binary each account sends through is present.
+fn validate_accounts(config: &crate::config::Config) -> ChannelValidationFuture {
+    let checks = build_email_runtime_contexts(config)
+        .into_iter()
+        .map(|runtime| {
+            let result = if Path::new(&runtime.sendmail_path).is_file() {
+                Ok(format!(
+                    "{} via {}",
+                    runtime.from_address, runtime.sendmail_path
+                ))
+            } else {
+                Err(format!("sendmail not found at {}", runtime.sendmail_path))
+            };
+            ChannelAccountCheck {
+                channel_name: runtime.channel_name,
+                result,
+            }
+        })
+        .collect();
+    Box::pin(std::future::ready(checks))
+}
+
 fn default_enabled() -> bool {
     true
 }
@@ -95,8 +126,12 @@ pub struct EmailAccountConfig {
     pub from_address: String,
     #[serde(default = "default_sendmail_path")]
     pub sendmail_path: String,
+    /// Sender address patterns (csv) allowed to email the bot; empty allows everyone.
     #[serde(default)]
     pub allowed_senders: String,
+    /// Sender address patterns (csv) whose tool calls get control-chat privileges.
+    #[serde(default)]
+    pub admin_senders: String,
     #[serde(default)]
     pub webhook_token: String,
     #[serde(default)]
@@ -113,8 +148,12 @@ pub struct EmailChannelConfig {
     pub from_address: String,
     #[serde(default = "default_sendmail_path")]
     pub sendmail_path: String,
+    /// Sender address patterns (csv) allowed to email the bot; empty allows everyone.
     #[serde(default)]
     pub allowed_senders: String,
+    /// Sender address patterns (csv) whose tool calls get control-chat privileges.
+    #[serde(default)]
+    pub admin_senders: String,
     #[serde(default = "default_webhook_path")]
     pub webhook_path: String,
     #[serde(default)]
@@ -132,7 +171,7 @@ pub struct EmailRuntimeContext {
     pub channel_name: String,
     pub from_address: String,
     pub sendmail_path: String,
-    pub allowed_senders: Vec<String>,
+    pub sender_access: SenderAccessPolicy,
     pub webhook_token: String,
     pub bot_username: String,
     pub model: Option<String>,
@@ -145,6 +184,7 @@ struct EmailWebhookPayload {
     reply_to: String,
     #[serde(default)]
     subject: String,
+    #[serde(default)]
     text: String,
     #[serde(default)]
     message_id: String,
@@ -154,6 +194,9 @@ struct EmailWebhookPayload {
     timestamp_ms: Option<i64>,
     #[serde(default)]
     sent_at: Option<String>,
+    /// Files attached to the email, inline (`content_base64`) or by `url`.
+    #[serde(default)]
+    attachments: Vec<WebhookAttachment>,
 }
 
 fn pick_default_account_id(
@@ -183,6 +226,14 @@ fn parse_csv(raw: &str) -> Vec<String> {
         .collect()
 }
 
+fn sender_access_policy(allowed: &str, admins: &str) -> SenderAccessPolicy {
+    SenderAccessPolicy {
+        allowed: parse_csv(allowed),
+        admins: parse_csv(admins),
+        ignore_case: true,
+    }
+}
+
 pub fn build_email_runtime_contexts(config: &crate::config::Config) -> Vec<EmailRuntimeContext> {
     let Some(email_cfg) = config.channel_config::<EmailChannelConfig>("email") else {
         return Vec::new();
@@ -235,7 +286,10 @@ pub fn build_email_runtime_contexts(config: &crate::config::Config) -> Vec<Email
             channel_name,
             from_address: account_cfg.from_address.trim().to_string(),
             sendmail_path,
-            allowed_senders: parse_csv(&account_cfg.allowed_senders),
+            sender_access: sender_access_policy(
+                &account_cfg.allowed_senders,
+                &account_cfg.admin_senders,
+            ),
             webhook_token,
             bot_username,
             model,
@@ -251,7 +305,10 @@ pub fn build_email_runtime_contexts(config: &crate::config::Config) -> Vec<Email
             } else {
                 email_cfg.sendmail_path.trim().to_string()
             },
-            allowed_senders: parse_csv(&email_cfg.allowed_senders),
+            sender_access: sender_access_policy(
+                &email_cfg.allowed_senders,
+                &email_cfg.admin_senders,
+            ),
             webhook_token: email_cfg.webhook_token.trim().to_string(),
             bot_username: config.bot_username_for_channel("email"),
             model: email_cfg
@@ -407,7 +464,7 @@ async fn email_webhook_handler(
             channel_name: "email".to_string(),
             from_address: String::new(),
             sendmail_path: default_sendmail_path(),
-            allowed_senders: Vec::new(),
+            sender_access: SenderAccessPolicy::default(),
             webhook_token: String::new(),
             bot_username: String::new(),
             model: None,
@@ -420,24 +477,24 @@ async fn email_webhook_handler(
     }
 
     let from = payload.from.trim();
-    if from.is_empty() || payload.text.trim().is_empty() {
+    if from.is_empty() || (payload.text.trim().is_empty() && payload.attachments.is_empty()) {
         return axum::http::StatusCode::BAD_REQUEST;
     }
 
-    if !runtime_ctx.allowed_senders.is_empty()
-        && !runtime_ctx
-            .allowed_senders
-            .iter()
-            .any(|sender| sender.eq_ignore_ascii_case(from))
-    {
+    let sender_access = runtime_ctx
+        .sender_access
+        .resolve(from, &app_state.config.user_groups);
+    if sender_access == SenderAccess::Denied {
         return axum::http::StatusCode::FORBIDDEN;
     }
-    tokio::spawn({
+    let sender_is_admin = sender_access.is_admin();
+    let queues = app_state.channel_registry.inbound_queues().clone();
+    queues.submit(&runtime_ctx.channel_name, {
         let state = app_state.clone();
         let runtime = runtime_ctx.clone();
         let payload = payload.clone();
         async move {
-            process_email_webhook_message(state, runtime, payload).await;
+            process_email_webhook_message(state, runtime, payload, sender_is_admin).await;
         }
     });
     axum::http::StatusCode::OK
@@ -447,6 +504,7 @@ async fn process_email_webhook_message(
     app_state: Arc<AppState>,
     runtime_ctx: EmailRuntimeContext,
     payload: EmailWebhookPayload,
+    sender_is_admin: bool,
 ) {
     let from = payload.from.trim().to_string();
     let trimmed_text = payload.text.trim().to_string();
@@ -503,7 +561,13 @@ async fn process_email_webhook_message(
     ) {
         return;
     }
-    if should_drop_recent_duplicate_message(&runtime_ctx.channel_name, &inbound_message_id) {
+    if should_drop_duplicate_delivery(
+        &app_state.db,
+        &runtime_ctx.channel_name,
+        &inbound_message_id,
+    )
+    .await
+    {
         return;
     }
 
@@ -515,6 +579,7 @@ async fn process_email_webhook_message(
             &runtime_ctx.channel_name,
             trimmed,
             Some(from.as_str()),
+            sender_is_admin,
         )
         .await
         {
@@ -546,11 +611,27 @@ async fn process_email_webhook_message(
         );
         return;
     }
+    let (fetched, failures) =
+        fetch_webhook_attachments(&app_state.config, &payload.attachments).await;
+    let mut ingested = ingest_attachments(
+        &app_state.config,
+        &runtime_ctx.channel_name,
+        chat_id,
+        sender_is_admin || app_state.config.control_chat_ids.contains(&chat_id),
+        fetched,
+    )
+    .await;
+    ingested.notes.extend(failures);
+    let content = if ingested.notes.is_empty() {
+        payload.text.clone()
+    } else {
+        ingested.annotate(&payload.text)
+    };
     let stored = StoredMessage {
         id: inbound_message_id.clone(),
         chat_id,
         sender_name: from.to_string(),
-        content: payload.text.clone(),
+        content,
         is_from_bot: false,
         timestamp: chrono::Utc::now().to_rfc3339(),
     };
@@ -566,21 +647,42 @@ async fn process_email_webhook_message(
         );
         return;
     }
+    if let Some(remaining) = app_state
+        .channel_registry
+        .health()
+        .backoff_remaining(&runtime_ctx.channel_name)
+    {
+        warn!(
+            "Email: '{}' is backing off after repeated send failures ({}s left); skipping chat {}",
+            runtime_ctx.channel_name,
+            remaining.as_secs(),
+            chat_id
+        );
+        return;
+    }
 
     let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
-    match process_with_agent_with_events(
+    let typing = app_state
+        .channel_registry
+        .start_typing(&runtime_ctx.channel_name, &external_chat_id);
+    let result = process_with_agent_with_events(
         &app_state,
         AgentRequestContext {
             caller_channel: &runtime_ctx.channel_name,
             chat_id,
             chat_type: "private",
+            sender_id: Some(&from),
+            sender_is_admin,
         },
         None,
-        None,
+        ingested.image_data,
         Some(&event_tx),
     )
-    .await
-    {
+    .await;
+    if let Some(typing) = typing {
+        typing.stop().await;
+    }
+    match result {
         Ok(response) => {
             drop(event_tx);
             let mut used_send_message_tool = false;
@@ -608,17 +710,28 @@ async fn process_email_webhook_message(
                 if !payload.subject.trim().is_empty() {
                     email_body.push_str(&format!("Re: {}\n\n", payload.subject.trim()));
                 }
-                for chunk in split_text(&response, 8_000) {
+                let outgoing = app_state
+                    .channel_registry
+                    .decorate_reply(&runtime_ctx.channel_name, &response);
+                for chunk in split_text(&outgoing, 8_000) {
                     email_body.push_str(&chunk);
                     email_body.push('\n');
                 }
-                if let Err(e) = send_email_via_sendmail(
+                let send_result = send_email_via_sendmail(
                     &runtime_ctx.sendmail_path,
                     &runtime_ctx.from_address,
                     &target,
                     "MicroClaw reply",
                     &email_body,
-                ) {
+                );
+                record_send_result(
+                    &app_state.channel_registry,
+                    app_state.db.clone(),
+                    &runtime_ctx.channel_name,
+                    &send_result,
+                )
+                .await;
+                if let Err(e) = send_result {
                     error!("Email: failed to send response: {e}");
                 }
                 let bot_msg = StoredMessage {
@@ -632,15 +745,24 @@ async fn process_email_webhook_message(
                 let _ =
                     call_blocking(app_state.db.clone(), move |db| db.store_message(&bot_msg)).await;
             } else {
-                let fallback =
-                    "I couldn't produce a visible reply after an automatic retry. Please try again.";
-                let _ = send_email_via_sendmail(
+                let fallback = app_state.channel_registry.decorate_reply(
+                    &runtime_ctx.channel_name,
+                    "I couldn't produce a visible reply after an automatic retry. Please try again.",
+                );
+                let send_result = send_email_via_sendmail(
                     &runtime_ctx.sendmail_path,
                     &runtime_ctx.from_address,
                     &target,
                     "MicroClaw reply",
-                    fallback,
+                    &fallback,
                 );
+                record_send_result(
+                    &app_state.channel_registry,
+                    app_state.db.clone(),
+                    &runtime_ctx.channel_name,
+                    &send_result,
+                )
+                .await;
             }
         }
         Err(e) => {
@@ -9,17 +9,28 @@ use tokio::sync::RwLock;
 use tokio_tungstenite::tungstenite::Message as WsMessage;
 use tracing::{error, info, warn};
 
-use crate::agent_engine::process_with_agent_with_events;
+use crate::agent_engine::approval_card;
+use crate::agent_engine::{acknowledge_queued_message, process_with_agent_with_events};
 use crate::agent_engine::should_suppress_user_error;
 use crate::agent_engine::AgentEvent;
 use crate::agent_engine::AgentRequestContext;
-use crate::channels::startup_guard::should_drop_recent_duplicate_message;
+use crate::channels::attachments::ingest_attachments;
+use crate::channels::startup_guard::should_drop_duplicate_delivery;
+use crate::channels::user_access::{SenderAccess, SenderAccessPolicy};
 use crate::chat_commands::maybe_handle_plugin_command;
-use crate::chat_commands::{handle_chat_command, is_slash_command, unknown_command_response};
+use crate::chat_commands::{
+    handle_chat_command, handle_reaction_command, is_slash_command, reaction_action,
+    unknown_command_response,
+};
 use crate::runtime::AppState;
-use crate::setup_def::{ChannelFieldDef, DynamicChannelDef};
-use microclaw_channels::channel::ConversationKind;
-use microclaw_channels::channel_adapter::ChannelAdapter;
+use crate::setup_def::{
+    ChannelAccountCheck, ChannelFieldDef, ChannelValidationFuture, DynamicChannelDef,
+};
+use microclaw_channels::attachment::{sniff_image_media_type, InboundAttachment};
+use microclaw_channels::channel::{record_send_result, ConversationKind};
+use microclaw_channels::channel_adapter::{
+    apply_mentions, CardButtonStyle, ChannelAdapter, InboundReaction, MessageCard, ResolvedMention,
+};
 use microclaw_storage::db::call_blocking;
 use microclaw_storage::db::StoredMessage;
 
@@ -89,6 +100,7 @@ pub const SETUP_DEF: DynamicChannelDef = DynamicChannelDef {
             required: false,
         },
     ],
+    validate: Some(validate_accounts),
 };
 
 // ---------------------------------------------------------------------------
@@ -115,6 +127,12 @@ pub struct FeishuAccountConfig {
     pub domain: String,
     #[serde(default)]
     pub allowed_chats: Vec<String>,
+    /// Sender open_id patterns allowed to talk to the bot; empty allows everyone.
+    #[serde(default)]
+    pub allowed_user_ids: Vec<String>,
+    /// Sender open_id patterns whose tool calls get control-chat privileges.
+    #[serde(default)]
+    pub admin_user_ids: Vec<String>,
     #[serde(default = "default_webhook_path")]
     pub webhook_path: String,
     #[serde(default)]
@@ -149,6 +167,12 @@ pub struct FeishuChannelConfig {
     pub domain: String,
     #[serde(default)]
     pub allowed_chats: Vec<String>,
+    /// Sender open_id patterns allowed to talk to the bot; empty allows everyone.
+    #[serde(default)]
+    pub allowed_user_ids: Vec<String>,
+    /// Sender open_id patterns whose tool calls get control-chat privileges.
+    #[serde(default)]
+    pub admin_user_ids: Vec<String>,
     #[serde(default = "default_webhook_path")]
     pub webhook_path: String,
     #[serde(default)]
@@ -167,6 +191,16 @@ pub struct FeishuChannelConfig {
     pub default_account: Option<String>,
 }
 
+impl FeishuChannelConfig {
+    fn sender_access_policy(&self) -> SenderAccessPolicy {
+        SenderAccessPolicy {
+            allowed: self.allowed_user_ids.clone(),
+            admins: self.admin_user_ids.clone(),
+            ignore_case: false,
+        }
+    }
+}
+
 fn pick_default_account_id(
     configured: Option<&str>,
     accounts: &HashMap<String, FeishuAccountConfig>,
@@ -222,6 +256,8 @@ pub fn build_feishu_runtime_contexts(config: &crate::config::Config) -> Vec<Feis
             connection_mode: account_cfg.connection_mode.clone(),
             domain: account_cfg.domain.clone(),
             allowed_chats: account_cfg.allowed_chats.clone(),
+            allowed_user_ids: account_cfg.allowed_user_ids.clone(),
+            admin_user_ids: account_cfg.admin_user_ids.clone(),
             webhook_path: account_cfg.webhook_path.clone(),
             verification_token: account_cfg.verification_token.clone(),
             encrypt_key: account_cfg.encrypt_key.clone(),
@@ -434,6 +470,83 @@ impl FeishuAdapter {
 
         Ok(token)
     }
+
+    /// List `(open_id, name)` for every member of a chat.
+    async fn fetch_chat_members(
+        &self,
+        external_chat_id: &str,
+    ) -> Result<Vec<(String, String)>, String> {
+        let token = self.ensure_token().await?;
+        let url = format!(
+            "{}/open-apis/im/v1/chats/{}/members",
+            self.base_url, external_chat_id
+        );
+        let mut members = Vec::new();
+        let mut page_token = String::new();
+        loop {
+            let mut query = vec![("member_id_type", "open_id"), ("page_size", "100")];
+            if !page_token.is_empty() {
+                query.push(("page_token", page_token.as_str()));
+            }
+            let resp: serde_json::Value = self
+                .http_client
+                .get(&url)
+                .header(reqwest::header::AUTHORIZATION, format!("Bearer {token}"))
+                .query(&query)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to list Feishu chat members: {e}"))?
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse Feishu chat members: {e}"))?;
+            let code = resp.get("code").and_then(|v| v.as_i64()).unwrap_or(-1);
+            if code != 0 {
+                let msg = resp
+                    .get("msg")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown");
+                return Err(format!("Feishu chat members error: code={code} msg={msg}"));
+            }
+            let data = resp.get("data");
+            members.extend(parse_chat_members(data));
+            let has_more = data
+                .and_then(|d| d.get("has_more"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            page_token = data
+                .and_then(|d| d.get("page_token"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            if !has_more || page_token.is_empty() {
+                return Ok(members);
+            }
+        }
+    }
+}
+
+fn parse_chat_members(data: Option<&serde_json::Value>) -> Vec<(String, String)> {
+    data.and_then(|d| d.get("items"))
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let id = item.get("member_id")?.as_str()?;
+                    let name = item.get("name").and_then(|v| v.as_str()).unwrap_or(id);
+                    Some((id.to_string(), name.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Feishu text-message markup that pings a user.
+fn feishu_mention_markup(mention: &ResolvedMention) -> String {
+    format!(
+        "<at user_id=\"{}\">{}</at>",
+        mention.user_id, mention.display
+    )
 }
 
 #[async_trait::async_trait]
@@ -488,6 +601,50 @@ impl ChannelAdapter for FeishuAdapter {
         Ok(())
     }
 
+    async fn resolve_mention(
+        &self,
+        external_chat_id: &str,
+        name: &str,
+        known_ids: &[String],
+    ) -> Option<ResolvedMention> {
+        let members = match self.fetch_chat_members(external_chat_id).await {
+            Ok(members) => members,
+            Err(e) => {
+                warn!("Feishu mention lookup failed for '{name}': {e}");
+                Vec::new()
+            }
+        };
+        if let Some((id, display)) = members
+            .iter()
+            .find(|(id, display)| id == name || display.eq_ignore_ascii_case(name))
+        {
+            return Some(ResolvedMention {
+                name: name.to_string(),
+                user_id: id.clone(),
+                display: display.clone(),
+            });
+        }
+        // Senders are stored by open_id, so a known id is still pingable when the
+        // member list is unavailable.
+        (name.starts_with("ou_") && known_ids.iter().any(|id| id == name)).then(|| {
+            ResolvedMention {
+                name: name.to_string(),
+                user_id: name.to_string(),
+                display: name.to_string(),
+            }
+        })
+    }
+
+    async fn send_text_with_mentions(
+        &self,
+        external_chat_id: &str,
+        text: &str,
+        mentions: &[ResolvedMention],
+    ) -> Result<(), String> {
+        let text = apply_mentions(text, mentions, feishu_mention_markup);
+        self.send_text(external_chat_id, &text).await
+    }
+
     async fn send_attachment(
         &self,
         external_chat_id: &str,
@@ -663,6 +820,71 @@ impl ChannelAdapter for FeishuAdapter {
             None => format!("[attachment:{}]", file_path.display()),
         })
     }
+
+    async fn delete_message(
+        &self,
+        _external_chat_id: &str,
+        message_id: &str,
+    ) -> Result<(), String> {
+        let token = self.ensure_token().await?;
+        let url = format!("{}/open-apis/im/v1/messages/{message_id}", self.base_url);
+        let resp = self
+            .http_client
+            .delete(&url)
+            .header(reqwest::header::AUTHORIZATION, format!("Bearer {token}"))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to delete Feishu message: {e}"))?;
+        let resp_json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Feishu delete response: {e}"))?;
+        let code = resp_json.get("code").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if code != 0 {
+            let msg = resp_json
+                .get("msg")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            return Err(format!(
+                "Feishu delete message error: code={code} msg={msg}"
+            ));
+        }
+        Ok(())
+    }
+
+    async fn add_reaction(
+        &self,
+        _external_chat_id: &str,
+        message_id: &str,
+        emoji: &str,
+    ) -> Result<(), String> {
+        let token = self.ensure_token().await?;
+        let url = format!(
+            "{}/open-apis/im/v1/messages/{message_id}/reactions",
+            self.base_url
+        );
+        let resp = self
+            .http_client
+            .post(&url)
+            .header(reqwest::header::AUTHORIZATION, format!("Bearer {token}"))
+            .json(&serde_json::json!({ "reaction_type": { "emoji_type": emoji } }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to add Feishu reaction: {e}"))?;
+        let resp_json: serde_json::Value = resp
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Feishu reaction response: {e}"))?;
+        let code = resp_json.get("code").and_then(|v| v.as_i64()).unwrap_or(-1);
+        if code != 0 {
+            let msg = resp_json
+                .get("msg")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            return Err(format!("Feishu add reaction error: code={code} msg={msg}"));
+        }
+        Ok(())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -985,6 +1207,133 @@ async fn send_feishu_response(
     Ok(())
 }
 
+fn feishu_button_type(style: CardButtonStyle) -> &'static str {
+    match style {
+        CardButtonStyle::Default => "default",
+        CardButtonStyle::Primary => "primary",
+        CardButtonStyle::Danger => "danger",
+    }
+}
+
+/// Render a [`MessageCard`] as Feishu interactive card JSON. Each button
+/// carries `{"text": value}`, which comes back in `card.action.trigger`.
+fn build_feishu_card(card: &MessageCard) -> serde_json::Value {
+    let mut elements = vec![serde_json::json!({ "tag": "markdown", "content": card.body })];
+    if !card.buttons.is_empty() {
+        let actions: Vec<serde_json::Value> = card
+            .buttons
+            .iter()
+            .map(|b| {
+                serde_json::json!({
+                    "tag": "button",
+                    "text": { "tag": "plain_text", "content": b.label },
+                    "type": feishu_button_type(b.style),
+                    "value": { "text": b.value },
+                })
+            })
+            .collect();
+        elements.push(serde_json::json!({ "tag": "action", "actions": actions }));
+    }
+    let mut out = serde_json::json!({
+        "config": { "wide_screen_mode": true },
+        "elements": elements,
+    });
+    if let Some(title) = card.title.as_deref().filter(|t| !t.trim().is_empty()) {
+        out["header"] = serde_json::json!({
+            "title": { "tag": "plain_text", "content": title },
+            "template": "blue",
+        });
+    }
+    out
+}
+
+/// Send an interactive card to a chat, or as a thread reply when
+/// `reply_to_message_id` is set.
+async fn send_feishu_card(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    chat_id: &str,
+    card: &MessageCard,
+    reply_to_message_id: Option<&str>,
+) -> Result<(), String> {
+    let content = build_feishu_card(card).to_string();
+    let (url, body) = match reply_to_message_id.filter(|id| !id.is_empty()) {
+        Some(message_id) => (
+            format!("{base_url}/open-apis/im/v1/messages/{message_id}/reply"),
+            serde_json::json!({
+                "msg_type": "interactive",
+                "content": content,
+                "reply_in_thread": true,
+            }),
+        ),
+        None => (
+            format!("{base_url}/open-apis/im/v1/messages?receive_id_type=chat_id"),
+            serde_json::json!({
+                "receive_id": chat_id,
+                "msg_type": "interactive",
+                "content": content,
+            }),
+        ),
+    };
+
+    let resp = http_client
+        .post(&url)
+        .header(reqwest::header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send Feishu card: {e}"))?;
+
+    let resp_json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Feishu card response: {e}"))?;
+    let code = resp_json.get("code").and_then(|v| v.as_i64()).unwrap_or(-1);
+    if code != 0 {
+        let msg = resp_json
+            .get("msg")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        return Err(format!("Feishu card send error: code={code} msg={msg}"));
+    }
+    Ok(())
+}
+
+/// Send the final agent reply. Approval prompts go out as a card with
+/// Approve/Deny buttons; any card failure falls back to the plain text.
+#[allow(clippy::too_many_arguments)]
+async fn send_feishu_reply(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    chat_id: &str,
+    text: &str,
+    message_id: &str,
+    topic_mode: bool,
+    approval_tool: Option<&str>,
+) -> Result<(), String> {
+    if let Some(tool_name) = approval_tool {
+        let card = approval_card(tool_name, text);
+        let reply_to = topic_mode.then_some(message_id);
+        match send_feishu_card(http_client, base_url, token, chat_id, &card, reply_to).await {
+            Ok(()) => return Ok(()),
+            Err(e) => warn!("Feishu: approval card failed, sending text instead: {e}"),
+        }
+    }
+    send_feishu_response(
+        http_client,
+        base_url,
+        token,
+        chat_id,
+        text,
+        message_id,
+        topic_mode,
+    )
+    .await
+}
+
 async fn reply_feishu_thread(
     http_client: &reqwest::Client,
     base_url: &str,
@@ -1265,25 +1614,6 @@ async fn download_feishu_resource(
         .map_err(|e| format!("Failed to read Feishu resource bytes: {e}"))
 }
 
-fn base64_encode(data: &[u8]) -> String {
-    use base64::Engine;
-    base64::engine::general_purpose::STANDARD.encode(data)
-}
-
-fn guess_image_media_type(data: &[u8]) -> String {
-    if data.starts_with(&[0x89, 0x50, 0x4E, 0x47]) {
-        "image/png".into()
-    } else if data.starts_with(&[0xFF, 0xD8]) {
-        "image/jpeg".into()
-    } else if data.starts_with(b"GIF") {
-        "image/gif".into()
-    } else if data.starts_with(b"RIFF") && data.len() >= 12 && &data[8..12] == b"WEBP" {
-        "image/webp".into()
-    } else {
-        "image/jpeg".into()
-    }
-}
-
 /// Resolve the bot's own open_id via GET /open-apis/bot/v3/info.
 async fn resolve_bot_open_id(
     http_client: &reqwest::Client,
@@ -1379,6 +1709,63 @@ fn extract_service_id(url: &str) -> i32 {
         .unwrap_or(0)
 }
 
+// ---------------------------------------------------------------------------
+// Credential check (`microclaw doctor channels`)
+// ---------------------------------------------------------------------------
+
+fn validate_accounts(config: &crate::config::Config) -> ChannelValidationFuture {
+    let runtimes = build_feishu_runtime_contexts(config);
+    Box::pin(async move {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        let mut checks = Vec::new();
+        for runtime in runtimes {
+            let result = fetch_bot_identity(&http_client, &runtime.config).await;
+            checks.push(ChannelAccountCheck {
+                channel_name: runtime.channel_name,
+                result,
+            });
+        }
+        checks
+    })
+}
+
+/// The bot's name and open_id from `bot/v3/info`, proving the app credentials work.
+async fn fetch_bot_identity(
+    http_client: &reqwest::Client,
+    config: &FeishuChannelConfig,
+) -> Result<String, String> {
+    let base_url = resolve_domain(&config.domain);
+    let token = get_token(http_client, &base_url, &config.app_id, &config.app_secret).await?;
+    let json: serde_json::Value = http_client
+        .get(format!("{base_url}/open-apis/bot/v3/info"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to get bot info: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse bot info: {e}"))?;
+    let code = json.get("code").and_then(|v| v.as_i64()).unwrap_or(-1);
+    if code != 0 {
+        let msg = json
+            .get("msg")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        return Err(format!("bot info error: code={code} msg={msg}"));
+    }
+    let bot = json.get("bot");
+    let field = |key: &str| {
+        bot.and_then(|b| b.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    Ok(format!("{} ({})", field("app_name"), field("open_id")))
+}
+
 // ---------------------------------------------------------------------------
 // Ensure token helper for standalone functions
 // ---------------------------------------------------------------------------
@@ -1598,7 +1985,8 @@ async fn run_ws_connection(
                     let cfg = feishu_cfg.clone();
                     let base = base_url.to_string();
                     let runtime_ctx = runtime.clone();
-                    tokio::spawn(async move {
+                    let queues = app_state.channel_registry.inbound_queues().clone();
+                    queues.submit(&runtime.channel_name, async move {
                         handle_feishu_event(state, runtime_ctx, &cfg, &base, &bot_id, &event).await;
                     });
                 } else if frame.method == FRAME_METHOD_CONTROL {
@@ -1654,7 +2042,8 @@ async fn send_ack(write: &WsSink, request_frame: &pb::Frame) {
 // Event handling (shared by WS and webhook)
 // ---------------------------------------------------------------------------
 
-/// Handle a Feishu event envelope. Dispatches im.message.receive_v1 events.
+/// Handle a Feishu event envelope. Dispatches im.message.receive_v1, card
+/// actions and im.message.reaction.created_v1 events.
 async fn handle_feishu_event(
     app_state: Arc<AppState>,
     runtime: FeishuRuntimeContext,
@@ -1685,6 +2074,28 @@ async fn handle_feishu_event(
         .and_then(|v| v.as_str())
         .unwrap_or("");
 
+    if event_type == "card.action.trigger" {
+        if let Some(action) = parse_card_action(event) {
+            handle_feishu_card_action(
+                app_state,
+                runtime,
+                feishu_cfg,
+                base_url,
+                bot_open_id,
+                action,
+            )
+            .await;
+        }
+        return;
+    }
+
+    if event_type == "im.message.reaction.created_v1" {
+        if let Some(reaction) = parse_reaction_event(event) {
+            handle_feishu_reaction(app_state, runtime, feishu_cfg, base_url, reaction).await;
+        }
+        return;
+    }
+
     if event_type != "im.message.receive_v1" {
         return;
     }
@@ -1734,7 +2145,7 @@ async fn handle_feishu_event(
         "Feishu: received message chat_id={} message_id={} sender_open_id={} type={} content={}",
         chat_id_str, message_id, sender_open_id, message_type, content_raw
     );
-    if should_drop_recent_duplicate_message(&runtime.channel_name, message_id) {
+    if should_drop_duplicate_delivery(&app_state.db, &runtime.channel_name, message_id).await {
         return;
     }
 
@@ -1806,6 +2217,303 @@ async fn handle_feishu_event(
     .await;
 }
 
+/// A button press on one of our interactive cards.
+#[derive(Debug, PartialEq, Eq)]
+struct FeishuCardAction {
+    event_id: String,
+    chat_id: String,
+    operator_open_id: String,
+    message_id: String,
+    text: String,
+}
+
+/// Extract a `card.action.trigger` callback. Only buttons built by
+/// `build_feishu_card` (value `{"text": ...}`) are recognised.
+fn parse_card_action(event: &serde_json::Value) -> Option<FeishuCardAction> {
+    let str_at = |path: &str| {
+        event
+            .pointer(path)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let action = FeishuCardAction {
+        event_id: str_at("/header/event_id"),
+        chat_id: str_at("/event/context/open_chat_id"),
+        operator_open_id: str_at("/event/operator/open_id"),
+        message_id: str_at("/event/context/open_message_id"),
+        text: str_at("/event/action/value/text"),
+    };
+    (!action.chat_id.is_empty()
+        && !action.operator_open_id.is_empty()
+        && !action.text.trim().is_empty())
+    .then_some(action)
+}
+
+/// Route a card button press into the normal message flow, as though the
+/// operator had sent the button's text: slash commands run as commands and
+/// approval replies resume the paused tool call.
+async fn handle_feishu_card_action(
+    app_state: Arc<AppState>,
+    runtime: FeishuRuntimeContext,
+    feishu_cfg: &FeishuChannelConfig,
+    base_url: &str,
+    bot_open_id: &str,
+    action: FeishuCardAction,
+) {
+    info!(
+        "Feishu: card action chat_id={} operator={} text={}",
+        action.chat_id, action.operator_open_id, action.text
+    );
+    let dedupe_key = if action.event_id.is_empty() {
+        format!("card:{}:{}", action.message_id, action.text)
+    } else {
+        action.event_id.clone()
+    };
+    if should_drop_duplicate_delivery(&app_state.db, &runtime.channel_name, &dedupe_key).await {
+        return;
+    }
+    if !feishu_cfg.allowed_chats.is_empty()
+        && !feishu_cfg
+            .allowed_chats
+            .iter()
+            .any(|c| c == &action.chat_id)
+    {
+        return;
+    }
+
+    // Callbacks carry no chat type, so reuse what the chat was stored as.
+    let is_dm = call_blocking(app_state.db.clone(), {
+        let channel_name = runtime.channel_name.clone();
+        let title = format!("feishu-{}", action.chat_id);
+        move |db| {
+            Ok(db
+                .get_chat_id_by_channel_and_title(&channel_name, &title)?
+                .map(|chat_id| db.get_chat_type(chat_id))
+                .transpose()?
+                .flatten())
+        }
+    })
+    .await
+    .ok()
+    .flatten()
+    .is_some_and(|chat_type| chat_type == "feishu_dm");
+
+    let content_raw = serde_json::json!({ "text": action.text }).to_string();
+    handle_feishu_message(
+        app_state,
+        runtime,
+        feishu_cfg,
+        base_url,
+        bot_open_id,
+        &action.chat_id,
+        &action.operator_open_id,
+        &action.text,
+        is_dm,
+        true,
+        &action.message_id,
+        "text",
+        &content_raw,
+    )
+    .await;
+}
+
+/// A user's emoji reaction, from `im.message.reaction.created_v1`.
+#[derive(Debug, PartialEq, Eq)]
+struct FeishuReaction {
+    event_id: String,
+    message_id: String,
+    emoji_type: String,
+    operator_open_id: String,
+}
+
+/// Extract a reaction added by a user. Reactions added by apps are ignored.
+fn parse_reaction_event(event: &serde_json::Value) -> Option<FeishuReaction> {
+    let str_at = |path: &str| {
+        event
+            .pointer(path)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    if str_at("/event/operator_type") != "user" {
+        return None;
+    }
+    let reaction = FeishuReaction {
+        event_id: str_at("/header/event_id"),
+        message_id: str_at("/event/message_id"),
+        emoji_type: str_at("/event/reaction_type/emoji_type"),
+        operator_open_id: str_at("/event/user_id/open_id"),
+    };
+    (!reaction.message_id.is_empty()
+        && !reaction.emoji_type.is_empty()
+        && !reaction.operator_open_id.is_empty())
+    .then_some(reaction)
+}
+
+/// The parts of a fetched message a reaction command needs.
+struct FeishuFetchedMessage {
+    chat_id: String,
+    sender_id: String,
+    sender_type: String,
+    text: String,
+}
+
+async fn fetch_feishu_message(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    token: &str,
+    message_id: &str,
+) -> Result<FeishuFetchedMessage, String> {
+    let url = format!("{base_url}/open-apis/im/v1/messages/{message_id}");
+    let resp = http_client
+        .get(&url)
+        .header(reqwest::header::AUTHORIZATION, format!("Bearer {token}"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch Feishu message: {e}"))?;
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Feishu message response: {e}"))?;
+    let code = json.get("code").and_then(|v| v.as_i64()).unwrap_or(-1);
+    if code != 0 {
+        let msg = json
+            .get("msg")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown");
+        return Err(format!("Feishu get message error: code={code} msg={msg}"));
+    }
+    let item = json
+        .pointer("/data/items/0")
+        .ok_or_else(|| "Feishu get message returned no item".to_string())?;
+    let str_at = |path: &str| {
+        item.pointer(path)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    Ok(FeishuFetchedMessage {
+        chat_id: str_at("/chat_id"),
+        sender_id: str_at("/sender/id"),
+        sender_type: str_at("/sender/sender_type"),
+        text: parse_message_content(&str_at("/body/content"), &str_at("/msg_type")),
+    })
+}
+
+/// Run the `reaction_commands` entry for a reaction on one of our messages.
+async fn handle_feishu_reaction(
+    app_state: Arc<AppState>,
+    runtime: FeishuRuntimeContext,
+    feishu_cfg: &FeishuChannelConfig,
+    base_url: &str,
+    reaction: FeishuReaction,
+) {
+    // Most reactions are plain acknowledgements; skip them before any API call.
+    if reaction_action(&app_state.config.reaction_commands, &reaction.emoji_type).is_none() {
+        return;
+    }
+    let dedupe_key = if reaction.event_id.is_empty() {
+        format!(
+            "reaction:{}:{}:{}",
+            reaction.message_id, reaction.emoji_type, reaction.operator_open_id
+        )
+    } else {
+        reaction.event_id.clone()
+    };
+    if should_drop_duplicate_delivery(&app_state.db, &runtime.channel_name, &dedupe_key).await {
+        return;
+    }
+    let sender_access = feishu_cfg
+        .sender_access_policy()
+        .resolve(&reaction.operator_open_id, &app_state.config.user_groups);
+    if sender_access == SenderAccess::Denied {
+        return;
+    }
+
+    let http_client = reqwest::Client::new();
+    let token = match get_token(
+        &http_client,
+        base_url,
+        &feishu_cfg.app_id,
+        &feishu_cfg.app_secret,
+    )
+    .await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            error!("Feishu: failed to get token for reaction: {e}");
+            return;
+        }
+    };
+    let message =
+        match fetch_feishu_message(&http_client, base_url, &token, &reaction.message_id).await {
+            Ok(message) => message,
+            Err(e) => {
+                warn!(
+                    "Feishu: cannot read reacted message {}: {e}",
+                    reaction.message_id
+                );
+                return;
+            }
+        };
+    // Only reactions on this bot's own messages are commands.
+    if message.sender_type != "app" || message.sender_id != feishu_cfg.app_id {
+        return;
+    }
+    if !feishu_cfg.allowed_chats.is_empty()
+        && !feishu_cfg
+            .allowed_chats
+            .iter()
+            .any(|c| c == &message.chat_id)
+    {
+        return;
+    }
+    let chat_id = call_blocking(app_state.db.clone(), {
+        let channel_name = runtime.channel_name.clone();
+        let title = format!("feishu-{}", message.chat_id);
+        move |db| db.get_chat_id_by_channel_and_title(&channel_name, &title)
+    })
+    .await
+    .ok()
+    .flatten();
+    let Some(chat_id) = chat_id else {
+        return;
+    };
+
+    info!(
+        "Feishu: reaction {} on {} by {} in {}",
+        reaction.emoji_type, reaction.message_id, reaction.operator_open_id, message.chat_id
+    );
+    let inbound = InboundReaction {
+        external_chat_id: message.chat_id.clone(),
+        message_id: reaction.message_id.clone(),
+        emoji: reaction.emoji_type.clone(),
+        sender_id: reaction.operator_open_id.clone(),
+        message_text: Some(message.text),
+    };
+    if let Some(reply) = handle_reaction_command(
+        &app_state,
+        chat_id,
+        &runtime.channel_name,
+        &inbound,
+        sender_access.is_admin(),
+    )
+    .await
+    {
+        let _ = send_feishu_response(
+            &http_client,
+            base_url,
+            &token,
+            &message.chat_id,
+            &reply,
+            &reaction.message_id,
+            feishu_cfg.topic_mode,
+        )
+        .await;
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn handle_feishu_message(
     app_state: Arc<AppState>,
@@ -1822,6 +2530,17 @@ async fn handle_feishu_message(
     message_type: &str,
     content_raw: &str,
 ) {
+    let sender_access = feishu_cfg
+        .sender_access_policy()
+        .resolve(user, &app_state.config.user_groups);
+    if sender_access == SenderAccess::Denied {
+        info!(
+            "Feishu: ignoring message from {user} in {external_chat_id}: sender not in allowed_user_ids"
+        );
+        return;
+    }
+    let sender_is_admin = sender_access.is_admin();
+
     let chat_type = if is_dm { "feishu_dm" } else { "feishu_group" };
     let title = format!("feishu-{external_chat_id}");
 
@@ -1858,132 +2577,51 @@ async fn handle_feishu_message(
     };
 
     let mut text = text.to_string();
-    let mut image_data: Option<(String, String)> = None;
+    let mut attachments: Vec<InboundAttachment> = Vec::new();
+    let mut attachment_failures: Vec<String> = Vec::new();
 
     match message_type {
-        "image" => {
-            if let Ok(v) = serde_json::from_str::<serde_json::Value>(content_raw) {
-                if let Some(image_key) = v.get("image_key").and_then(|k| k.as_str()) {
-                    match download_feishu_resource(
-                        &http_client,
-                        base_url,
-                        &token,
-                        message_id,
-                        image_key,
-                        "image",
-                    )
-                    .await
-                    {
-                        Ok(bytes) => {
-                            let media_type = guess_image_media_type(&bytes);
-                            image_data = Some((base64_encode(&bytes), media_type));
-                            if text.trim().is_empty() || text.trim().starts_with('{') {
-                                text = "[image]".to_string();
-                            }
-                        }
-                        Err(e) => {
-                            error!("Feishu: failed to download image {image_key}: {e}");
-                        }
+        "image" | "file" | "media" => {
+            let content =
+                serde_json::from_str::<serde_json::Value>(content_raw).unwrap_or_default();
+            let (key_field, resource_type) = if message_type == "image" {
+                ("image_key", "image")
+            } else {
+                ("file_key", "file")
+            };
+            let key = content
+                .get(key_field)
+                .and_then(|k| k.as_str())
+                .unwrap_or("");
+            let file_name = content
+                .get("file_name")
+                .and_then(|k| k.as_str())
+                .unwrap_or_default();
+            if !key.is_empty() {
+                match download_feishu_resource(
+                    &http_client,
+                    base_url,
+                    &token,
+                    message_id,
+                    key,
+                    resource_type,
+                )
+                .await
+                {
+                    Ok(bytes) => {
+                        // Feishu image messages are always images; assume JPEG when unsniffable.
+                        let media_type = (message_type == "image").then(|| {
+                            sniff_image_media_type(&bytes)
+                                .unwrap_or("image/jpeg")
+                                .to_string()
+                        });
+                        attachments.push(InboundAttachment::new(file_name, media_type, bytes));
                     }
-                }
-            }
-        }
-        "file" => {
-            if let Ok(v) = serde_json::from_str::<serde_json::Value>(content_raw) {
-                let file_key = v.get("file_key").and_then(|k| k.as_str()).unwrap_or("");
-                let file_name = v
-                    .get("file_name")
-                    .and_then(|k| k.as_str())
-                    .unwrap_or("feishu-file.bin");
-
-                if !file_key.is_empty() {
-                    match download_feishu_resource(
-                        &http_client,
-                        base_url,
-                        &token,
-                        message_id,
-                        file_key,
-                        "file",
-                    )
-                    .await
-                    {
-                        Ok(bytes) => {
-                            let max_bytes = app_state
-                                .config
-                                .max_document_size_mb
-                                .saturating_mul(1024)
-                                .saturating_mul(1024);
-                            if (bytes.len() as u64) > max_bytes {
-                                let _ = send_feishu_response(
-                                    &http_client,
-                                    base_url,
-                                    &token,
-                                    external_chat_id,
-                                    &format!(
-                                        "File is too large ({} bytes). Max allowed is {} MB.",
-                                        bytes.len(),
-                                        app_state.config.max_document_size_mb
-                                    ),
-                                    message_id,
-                                    feishu_cfg.topic_mode,
-                                )
-                                .await;
-                                return;
-                            }
-
-                            let safe_name: String = file_name
-                                .chars()
-                                .map(|c| match c {
-                                    'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
-                                    _ => '_',
-                                })
-                                .collect();
-
-                            let dir = std::path::Path::new(&app_state.config.working_dir)
-                                .join("uploads")
-                                .join(runtime.channel_name.replace('/', "_"))
-                                .join(external_chat_id);
-                            let mut document_saved_path: Option<String> = None;
-                            if let Err(e) = std::fs::create_dir_all(&dir) {
-                                error!("Failed to create upload dir {}: {e}", dir.display());
-                            } else {
-                                let ts = chrono::Utc::now().format("%Y%m%d-%H%M%S");
-                                let path = dir.join(format!("{}-{}", ts, safe_name));
-                                match tokio::fs::write(&path, &bytes).await {
-                                    Ok(()) => {
-                                        document_saved_path = Some(path.display().to_string());
-                                    }
-                                    Err(e) => {
-                                        error!(
-                                            "Failed to save feishu file {}: {e}",
-                                            path.display()
-                                        );
-                                    }
-                                }
-                            }
-
-                            let file_note = format!(
-                                "[document] filename={} bytes={}{}",
-                                file_name,
-                                bytes.len(),
-                                document_saved_path
-                                    .as_ref()
-                                    .map(|p| format!(" saved_path={}", p))
-                                    .unwrap_or_default(),
-                            );
-
-                            if text.trim().is_empty() || text.trim().starts_with('{') {
-                                text = file_note;
-                            } else {
-                                text = format!("{}\n\n{}", text.trim(), file_note);
-                            }
-                        }
-                        Err(e) => {
-                            error!("Feishu: failed to download file {file_key}: {e}");
-                            if text.trim().is_empty() || text.trim().starts_with('{') {
-                                text = format!("[document] download failed: {e}");
-                            }
-                        }
+                    Err(e) => {
+                        error!("Feishu: failed to download {message_type} {key}: {e}");
+                        attachment_failures.push(format!(
+                            "[attachment] kind={message_type} not saved: download failed: {e}"
+                        ));
                     }
                 }
             }
@@ -1998,14 +2636,26 @@ async fn handle_feishu_message(
                 }
             }
         }
-        "media" | "sticker" => {
-            if text.trim().is_empty() || text.trim().starts_with('{') {
-                text = format!("[{}]", message_type);
-            }
+        "sticker" if text.trim().is_empty() || text.trim().starts_with('{') => {
+            text = format!("[{}]", message_type);
         }
         _ => {}
     }
 
+    let mut ingested = ingest_attachments(
+        &app_state.config,
+        &runtime.channel_name,
+        chat_id,
+        sender_is_admin || app_state.config.control_chat_ids.contains(&chat_id),
+        attachments,
+    )
+    .await;
+    ingested.notes.extend(attachment_failures);
+    if !ingested.notes.is_empty() {
+        text = ingested.annotate(&text);
+    }
+    let image_data = ingested.image_data;
+
     let trimmed = text.trim();
     let should_respond = is_dm || is_mentioned;
     let topic_mode = feishu_cfg.topic_mode;
@@ -2046,6 +2696,7 @@ async fn handle_feishu_message(
             &runtime.channel_name,
             trimmed,
             Some(user),
+            sender_is_admin,
         )
         .await
         {
@@ -2098,12 +2749,34 @@ async fn handle_feishu_message(
         return;
     }
 
+    if let Some(remaining) = app_state
+        .channel_registry
+        .health()
+        .backoff_remaining(&runtime.channel_name)
+    {
+        warn!(
+            "Feishu: '{}' is backing off after repeated send failures ({}s left); skipping chat {}",
+            runtime.channel_name,
+            remaining.as_secs(),
+            chat_id
+        );
+        return;
+    }
+
     info!(
         "Feishu message from {} in {}: {}",
         user,
         external_chat_id,
         text.chars().take(100).collect::<String>()
     );
+    acknowledge_queued_message(
+        &app_state,
+        &runtime.channel_name,
+        chat_id,
+        external_chat_id,
+        message_id,
+    )
+    .await;
 
     let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel::<AgentEvent>();
 
@@ -2120,6 +2793,7 @@ async fn handle_feishu_message(
 
         struct ProgressState {
             used_send_message_tool: bool,
+            approval_tool: Option<String>,
         }
 
         let (progress_done_tx, progress_done_rx) = tokio::sync::oneshot::channel::<ProgressState>();
@@ -2129,6 +2803,7 @@ async fn handle_feishu_message(
             let mut lines: Vec<String> = Vec::new();
             let mut dirty = false;
             let mut used_send_message_tool = false;
+            let mut approval_tool: Option<String> = None;
             // Feishu: max 20 edits per message; reserve 1 for the final status
             const MAX_EDITS: u32 = 19;
             let mut edit_count: u32 = 0;
@@ -2176,6 +2851,13 @@ async fn handle_feishu_message(
                         }
                         dirty = true;
                     }
+                    Ok(Some(AgentEvent::ToolProgress { name, content })) => {
+                        lines.push(format!("… {}: {}", name, content));
+                        dirty = true;
+                    }
+                    Ok(Some(AgentEvent::ApprovalRequested { tool_name })) => {
+                        approval_tool = Some(tool_name);
+                    }
                     Ok(Some(AgentEvent::Iteration { iteration })) => {
                         if iteration > 1 {
                             lines.push(format!("── iteration {} ──", iteration));
@@ -2262,29 +2944,38 @@ async fn handle_feishu_message(
 
             let _ = progress_done_tx.send(ProgressState {
                 used_send_message_tool,
+                approval_tool,
             });
         });
 
-        match process_with_agent_with_events(
+        let typing = app_state
+            .channel_registry
+            .start_typing(&runtime.channel_name, external_chat_id);
+        let result = process_with_agent_with_events(
             &app_state,
             AgentRequestContext {
                 caller_channel: &runtime.channel_name,
                 chat_id,
                 chat_type: if is_dm { "private" } else { "group" },
+                sender_id: Some(user),
+                sender_is_admin,
             },
             None,
             image_data,
             Some(&event_tx),
         )
-        .await
-        {
+        .await;
+        if let Some(typing) = typing {
+            typing.stop().await;
+        }
+        match result {
             Ok(response) => {
                 drop(event_tx);
                 let _ = progress_handle.await;
-                let used_send_message_tool = progress_done_rx
+                let (used_send_message_tool, approval_tool) = progress_done_rx
                     .await
-                    .map(|s| s.used_send_message_tool)
-                    .unwrap_or(false);
+                    .map(|s| (s.used_send_message_tool, s.approval_tool))
+                    .unwrap_or((false, None));
 
                 if used_send_message_tool {
                     if !response.is_empty() {
@@ -2294,17 +2985,28 @@ async fn handle_feishu_message(
                         );
                     }
                 } else if !response.is_empty() {
-                    if let Err(e) = send_feishu_response(
+                    let outgoing = app_state
+                        .channel_registry
+                        .decorate_reply(&runtime.channel_name, &response);
+                    let send_result = send_feishu_reply(
                         &http_client,
                         base_url,
                         &token,
                         external_chat_id,
-                        &response,
+                        &outgoing,
                         message_id,
                         topic_mode,
+                        approval_tool.as_deref(),
                     )
-                    .await
-                    {
+                    .await;
+                    record_send_result(
+                        &app_state.channel_registry,
+                        app_state.db.clone(),
+                        &runtime.channel_name,
+                        &send_result,
+                    )
+                    .await;
+                    if let Err(e) = send_result {
                         error!("Feishu: failed to send response: {e}");
                     }
 
@@ -2322,16 +3024,26 @@ async fn handle_feishu_message(
                 } else {
                     let fallback =
                         "I couldn't produce a visible reply after an automatic retry. Please try again.";
-                    let _ = send_feishu_response(
+                    let outgoing = app_state
+                        .channel_registry
+                        .decorate_reply(&runtime.channel_name, fallback);
+                    let send_result = send_feishu_response(
                         &http_client,
                         base_url,
                         &token,
                         external_chat_id,
-                        fallback,
+                        &outgoing,
                         message_id,
                         topic_mode,
                     )
                     .await;
+                    record_send_result(
+                        &app_state.channel_registry,
+                        app_state.db.clone(),
+                        &runtime.channel_name,
+                        &send_result,
+                    )
+                    .await;
 
                     let bot_msg = StoredMessage {
                         id: uuid::Uuid::new_v4().to_string(),
@@ -2351,7 +3063,7 @@ async fn handle_feishu_message(
                 let _ = progress_handle.await;
                 error!("Error processing Feishu message: {e}");
                 if !should_suppress_user_error(&e) {
-                    let _ = send_feishu_response(
+                    let send_result = send_feishu_response(
                         &http_client,
                         base_url,
                         &token,
@@ -2361,31 +3073,51 @@ async fn handle_feishu_message(
                         topic_mode,
                     )
                     .await;
+                    record_send_result(
+                        &app_state.channel_registry,
+                        app_state.db.clone(),
+                        &runtime.channel_name,
+                        &send_result,
+                    )
+                    .await;
                 }
             }
         }
     } else {
-        match process_with_agent_with_events(
+        let typing = app_state
+            .channel_registry
+            .start_typing(&runtime.channel_name, external_chat_id);
+        let result = process_with_agent_with_events(
             &app_state,
             AgentRequestContext {
                 caller_channel: &runtime.channel_name,
                 chat_id,
                 chat_type: if is_dm { "private" } else { "group" },
+                sender_id: Some(user),
+                sender_is_admin,
             },
             None,
             image_data,
             Some(&event_tx),
         )
-        .await
-        {
+        .await;
+        if let Some(typing) = typing {
+            typing.stop().await;
+        }
+        match result {
             Ok(response) => {
                 drop(event_tx);
                 let mut used_send_message_tool = false;
+                let mut approval_tool: Option<String> = None;
                 while let Some(event) = event_rx.recv().await {
-                    if let AgentEvent::ToolStart { name, .. } = event {
-                        if name == "send_message" {
+                    match event {
+                        AgentEvent::ToolStart { name, .. } if name == "send_message" => {
                             used_send_message_tool = true;
                         }
+                        AgentEvent::ApprovalRequested { tool_name } => {
+                            approval_tool = Some(tool_name);
+                        }
+                        _ => {}
                     }
                 }
 
@@ -2397,17 +3129,28 @@ async fn handle_feishu_message(
                         );
                     }
                 } else if !response.is_empty() {
-                    if let Err(e) = send_feishu_response(
+                    let outgoing = app_state
+                        .channel_registry
+                        .decorate_reply(&runtime.channel_name, &response);
+                    let send_result = send_feishu_reply(
                         &http_client,
                         base_url,
                         &token,
                         external_chat_id,
-                        &response,
+                        &outgoing,
                         message_id,
                         topic_mode,
+                        approval_tool.as_deref(),
                     )
-                    .await
-                    {
+                    .await;
+                    record_send_result(
+                        &app_state.channel_registry,
+                        app_state.db.clone(),
+                        &runtime.channel_name,
+                        &send_result,
+                    )
+                    .await;
+                    if let Err(e) = send_result {
                         error!("Feishu: failed to send response: {e}");
                     }
 
@@ -2425,16 +3168,26 @@ async fn handle_feishu_message(
                 } else {
                     let fallback =
                         "I couldn't produce a visible reply after an automatic retry. Please try again.";
-                    let _ = send_feishu_response(
+                    let outgoing = app_state
+                        .channel_registry
+                        .decorate_reply(&runtime.channel_name, fallback);
+                    let send_result = send_feishu_response(
                         &http_client,
                         base_url,
                         &token,
                         external_chat_id,
-                        fallback,
+                        &outgoing,
                         message_id,
                         topic_mode,
                     )
                     .await;
+                    record_send_result(
+                        &app_state.channel_registry,
+                        app_state.db.clone(),
+                        &runtime.channel_name,
+                        &send_result,
+                    )
+                    .await;
 
                     let bot_msg = StoredMessage {
                         id: uuid::Uuid::new_v4().to_string(),
@@ -2452,7 +3205,7 @@ async fn handle_feishu_message(
             Err(e) => {
                 error!("Error processing Feishu message: {e}");
                 if !should_suppress_user_error(&e) {
-                    let _ = send_feishu_response(
+                    let send_result = send_feishu_response(
                         &http_client,
                         base_url,
                         &token,
@@ -2462,6 +3215,13 @@ async fn handle_feishu_message(
                         topic_mode,
                     )
                     .await;
+                    record_send_result(
+                        &app_state.channel_registry,
+                        app_state.db.clone(),
+                        &runtime.channel_name,
+                        &send_result,
+                    )
+                    .await;
                 }
             }
         }
@@ -2524,7 +3284,9 @@ pub fn register_feishu_webhook(router: axum::Router, app_state: Arc<AppState>) -
 
                     // Process the event
                     let event = body.0;
-                    tokio::spawn(async move {
+                    let queues = state.channel_registry.inbound_queues().clone();
+                    let channel_name = runtime_ctx.channel_name.clone();
+                    queues.submit(&channel_name, async move {
                         handle_feishu_event(state, runtime_ctx, &cfg, &base, &bot_id, &event).await;
                     });
 
@@ -2565,6 +3327,133 @@ commands:
         let _ = std::fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn test_parse_chat_members_and_mention_markup() {
+        let data = serde_json::json!({
+            "items": [
+                {"member_id": "ou_a", "name": "Alice"},
+                {"member_id": "ou_b"},
+                {"name": "no id"}
+            ]
+        });
+        let members = parse_chat_members(Some(&data));
+        assert_eq!(
+            members,
+            vec![
+                ("ou_a".to_string(), "Alice".to_string()),
+                ("ou_b".to_string(), "ou_b".to_string())
+            ]
+        );
+        let mention = ResolvedMention {
+            name: "alice".to_string(),
+            user_id: "ou_a".to_string(),
+            display: "Alice".to_string(),
+        };
+        assert_eq!(
+            feishu_mention_markup(&mention),
+            "<at user_id=\"ou_a\">Alice</at>"
+        );
+    }
+
+    #[test]
+    fn test_build_feishu_card_renders_header_body_and_buttons() {
+        let card = approval_card("bash", "Run `rm -rf build`?");
+        let json = build_feishu_card(&card);
+        assert_eq!(json["header"]["title"]["content"], "Approve 'bash'?");
+        assert_eq!(json["elements"][0]["tag"], "markdown");
+        assert_eq!(json["elements"][0]["content"], "Run `rm -rf build`?");
+        let actions = json["elements"][1]["actions"].as_array().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["type"], "primary");
+        assert_eq!(actions[0]["value"]["text"], "approve");
+        assert_eq!(actions[1]["type"], "danger");
+        assert_eq!(actions[1]["value"]["text"], "deny");
+
+        let plain = build_feishu_card(&MessageCard {
+            body: "hi".into(),
+            ..MessageCard::default()
+        });
+        assert!(plain.get("header").is_none());
+        assert_eq!(plain["elements"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_card_action() {
+        let event = serde_json::json!({
+            "schema": "2.0",
+            "header": { "event_id": "evt_1", "event_type": "card.action.trigger" },
+            "event": {
+                "operator": { "open_id": "ou_user" },
+                "action": { "tag": "button", "value": { "text": "approve" } },
+                "context": { "open_message_id": "om_card", "open_chat_id": "oc_chat" }
+            }
+        });
+        assert_eq!(
+            parse_card_action(&event),
+            Some(FeishuCardAction {
+                event_id: "evt_1".into(),
+                chat_id: "oc_chat".into(),
+                operator_open_id: "ou_user".into(),
+                message_id: "om_card".into(),
+                text: "approve".into(),
+            })
+        );
+
+        let mut foreign = event.clone();
+        foreign["event"]["action"]["value"] = serde_json::json!({ "key": "other" });
+        assert_eq!(parse_card_action(&foreign), None);
+    }
+
+    #[test]
+    fn test_parse_reaction_event() {
+        let event = serde_json::json!({
+            "schema": "2.0",
+            "header": { "event_id": "evt_r", "event_type": "im.message.reaction.created_v1" },
+            "event": {
+                "message_id": "om_bot",
+                "reaction_type": { "emoji_type": "THUMBSUP" },
+                "operator_type": "user",
+                "user_id": { "open_id": "ou_user" }
+            }
+        });
+        assert_eq!(
+            parse_reaction_event(&event),
+            Some(FeishuReaction {
+                event_id: "evt_r".into(),
+                message_id: "om_bot".into(),
+                emoji_type: "THUMBSUP".into(),
+                operator_open_id: "ou_user".into(),
+            })
+        );
+
+        let mut by_app = event.clone();
+        by_app["event"]["operator_type"] = serde_json::json!("app");
+        assert_eq!(parse_reaction_event(&by_app), None);
+    }
+
+    #[test]
+    fn test_default_reaction_commands_match_feishu_names() {
+        use crate::chat_commands::ReactionAction;
+
+        let commands = crate::config::Config::test_defaults().reaction_commands;
+        let action = |emoji_type: &str| {
+            let event = serde_json::json!({
+                "header": { "event_id": "evt_r" },
+                "event": {
+                    "message_id": "om_bot",
+                    "reaction_type": { "emoji_type": emoji_type },
+                    "operator_type": "user",
+                    "user_id": { "open_id": "ou_user" }
+                }
+            });
+            let reaction = parse_reaction_event(&event).unwrap();
+            reaction_action(&commands, &reaction.emoji_type)
+        };
+        assert_eq!(action("Pin"), Some(ReactionAction::Remember));
+        assert_eq!(action("ThumbsDown"), Some(ReactionAction::Regenerate));
+        assert_eq!(action("THUMBSUP"), None);
+    }
+
     #[test]
     fn test_build_runtime_inherits_channel_topic_mode_when_account_not_set() {
         let mut cfg = crate::config::Config::test_defaults();
@@ -2588,4 +3477,52 @@ accounts:
         assert_eq!(runtimes.len(), 1);
         assert!(runtimes[0].config.topic_mode);
     }
+
+    #[tokio::test]
+    async fn test_fetch_bot_identity_uses_tenant_token() {
+        use axum::routing::{get, post};
+
+        let app = axum::Router::new()
+            .route(
+                "/open-apis/auth/v3/tenant_access_token/internal",
+                post(|axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    let ok = body["app_secret"] == "good";
+                    axum::Json(if ok {
+                        serde_json::json!({"code": 0, "tenant_access_token": "t-1"})
+                    } else {
+                        serde_json::json!({"code": 10014, "msg": "app secret invalid"})
+                    })
+                }),
+            )
+            .route(
+                "/open-apis/bot/v3/info",
+                get(|headers: axum::http::HeaderMap| async move {
+                    assert_eq!(headers["authorization"], "Bearer t-1");
+                    axum::Json(serde_json::json!({
+                        "code": 0,
+                        "bot": {"app_name": "Helper", "open_id": "ou_bot"}
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut config: FeishuChannelConfig = serde_yaml::from_str(&format!(
+            "app_id: cli_a\napp_secret: good\ndomain: http://{addr}"
+        ))
+        .unwrap();
+        let http_client = reqwest::Client::new();
+        assert_eq!(
+            fetch_bot_identity(&http_client, &config).await.unwrap(),
+            "Helper (ou_bot)"
+        );
+        config.app_secret = "bad".into();
+        let err = fetch_bot_identity(&http_client, &config)
+            .await
+            .unwrap_err();
+        assert!(err.contains("app secret invalid"), "{err}");
+    }
 }
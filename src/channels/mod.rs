@@ -1,9 +1,13 @@
+pub mod attachments;
 pub mod dingtalk;
 pub mod email;
 pub mod feishu;
 pub mod startup_guard;
+pub mod user_access;
+pub mod webhook;
 
 // Re-export adapter types
 pub use dingtalk::DingTalkAdapter;
 pub use email::EmailAdapter;
-pub use feishu::FeishuAdapter;
\ No newline at end of file
+pub use feishu::FeishuAdapter;
+pub use webhook::WebhookAdapter;
\ No newline at end of file
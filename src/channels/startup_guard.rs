@@ -1,7 +1,8 @@
 use std::collections::HashMap;
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 
-use tracing::info;
+use microclaw_storage::db::{call_blocking, Database};
+use tracing::{info, warn};
 
 static CHANNEL_START_MS: OnceLock<Mutex<HashMap<String, i64>>> = OnceLock::new();
 static CHANNEL_RECENT_MESSAGE_IDS: OnceLock<Mutex<HashMap<String, HashMap<String, i64>>>> =
@@ -81,6 +82,47 @@ pub fn should_drop_recent_duplicate_message(channel_name: &str, message_id: &str
     false
 }
 
+/// [`should_drop_recent_duplicate_message`] backed by the database, so a
+/// message redelivered after a restart within the same window is still
+/// dropped. Database errors let the message through.
+pub async fn should_drop_duplicate_delivery(
+    db: &Arc<Database>,
+    channel_name: &str,
+    message_id: &str,
+) -> bool {
+    if should_drop_recent_duplicate_message(channel_name, message_id) {
+        return true;
+    }
+    let message_id = message_id.trim().to_string();
+    if message_id.is_empty() {
+        return false;
+    }
+    let channel = channel_name.to_string();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let claim_id = message_id.clone();
+    match call_blocking(db.clone(), move |db| {
+        db.claim_inbound_message(&channel, &claim_id, now_ms, RECENT_DUPLICATE_TTL_MS)
+    })
+    .await
+    {
+        Ok(true) => false,
+        Ok(false) => {
+            info!(
+                "Channel duplicate guard: dropping redelivered message channel={} message_id={}",
+                channel_name, message_id
+            );
+            true
+        }
+        Err(e) => {
+            warn!(
+                "Channel duplicate guard: failed to record message channel={} message_id={}: {}",
+                channel_name, message_id, e
+            );
+            false
+        }
+    }
+}
+
 pub fn parse_epoch_ms_from_str(raw: &str) -> Option<i64> {
     raw.trim().parse::<i64>().ok()
 }
@@ -96,7 +138,7 @@ pub fn parse_epoch_ms_from_seconds_fraction(raw: &str) -> Option<i64> {
 
 #[cfg(test)]
 mod tests {
-    use super::should_drop_recent_duplicate_message;
+    use super::*;
 
     #[test]
     fn test_recent_duplicate_message_guard() {
@@ -105,4 +147,24 @@ mod tests {
         assert!(!should_drop_recent_duplicate_message(channel, message));
         assert!(should_drop_recent_duplicate_message(channel, message));
     }
+
+    #[tokio::test]
+    async fn test_duplicate_delivery_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("microclaw_dedup_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let channel = "test.startup_guard.redelivery";
+
+        assert!(!should_drop_duplicate_delivery(&db, channel, "om_1").await);
+        assert!(should_drop_duplicate_delivery(&db, channel, "om_1").await);
+        assert!(!should_drop_duplicate_delivery(&db, channel, "om_2").await);
+
+        // A restart loses the in-memory ids; the platform redelivers om_1.
+        recent_message_registry().lock().unwrap().remove(channel);
+        drop(db);
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        assert!(should_drop_duplicate_delivery(&db, channel, "om_1").await);
+        assert!(!should_drop_duplicate_delivery(&db, channel, "om_3").await);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
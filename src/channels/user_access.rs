@@ -0,0 +1,280 @@
+//! Sender allowlists and admin lists shared by channel configs.
+//!
+//! Each entry is one of:
+//! - `*` — any sender
+//! - `re:<regex>` — a regex that must match the whole sender id
+//! - `group:<name>` — every entry of `user_groups.<name>` in the top-level config
+//! - a pattern with `*` / `?` wildcards, e.g. `ou_*` or `*@example.com`
+//! - otherwise an exact sender id
+
+use std::collections::HashMap;
+
+use regex::{Regex, RegexBuilder};
+
+/// Channel list keys whose entries are sender patterns.
+const PATTERN_LIST_KEYS: &[&str] = &[
+    "allowed_user_ids",
+    "admin_user_ids",
+    "allowed_senders",
+    "admin_senders",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderAccess {
+    Denied,
+    Allowed,
+    /// Allowed, with control-chat privileges for tool calls.
+    Admin,
+}
+
+impl SenderAccess {
+    pub fn is_admin(self) -> bool {
+        self == SenderAccess::Admin
+    }
+}
+
+/// Allowed and admin sender patterns for one channel account.
+#[derive(Debug, Clone, Default)]
+pub struct SenderAccessPolicy {
+    pub allowed: Vec<String>,
+    pub admins: Vec<String>,
+    /// Compare ids case-insensitively (email addresses).
+    pub ignore_case: bool,
+}
+
+impl SenderAccessPolicy {
+    /// Admins are always allowed; an empty allowlist allows everyone.
+    pub fn resolve(&self, sender_id: &str, groups: &HashMap<String, Vec<String>>) -> SenderAccess {
+        if matches_any(&self.admins, sender_id, groups, self.ignore_case) {
+            SenderAccess::Admin
+        } else if self.allowed.is_empty()
+            || matches_any(&self.allowed, sender_id, groups, self.ignore_case)
+        {
+            SenderAccess::Allowed
+        } else {
+            SenderAccess::Denied
+        }
+    }
+}
+
+fn matches_any(
+    patterns: &[String],
+    sender_id: &str,
+    groups: &HashMap<String, Vec<String>>,
+    ignore_case: bool,
+) -> bool {
+    patterns.iter().any(|pattern| {
+        if let Some(name) = pattern.trim().strip_prefix("group:") {
+            groups.get(name.trim()).is_some_and(|members| {
+                members
+                    .iter()
+                    .any(|member| pattern_matches(member, sender_id, ignore_case))
+            })
+        } else {
+            pattern_matches(pattern, sender_id, ignore_case)
+        }
+    })
+}
+
+fn pattern_matches(pattern: &str, sender_id: &str, ignore_case: bool) -> bool {
+    let pattern = pattern.trim();
+    if pattern == "*" {
+        return true;
+    }
+    if !pattern.starts_with("re:") && !pattern.contains(['*', '?']) {
+        return if ignore_case {
+            pattern.eq_ignore_ascii_case(sender_id)
+        } else {
+            pattern == sender_id
+        };
+    }
+    match compile_pattern(pattern, ignore_case) {
+        Ok(re) => re.is_match(sender_id),
+        Err(e) => {
+            tracing::warn!("Ignoring invalid sender pattern '{pattern}': {e}");
+            false
+        }
+    }
+}
+
+fn compile_pattern(pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+    let source = match pattern.strip_prefix("re:") {
+        Some(re) => format!("^(?:{re})$"),
+        None => {
+            let mut source = String::from("^");
+            for c in pattern.chars() {
+                match c {
+                    '*' => source.push_str(".*"),
+                    '?' => source.push('.'),
+                    other => source.push_str(&regex::escape(&other.to_string())),
+                }
+            }
+            source.push('$');
+            source
+        }
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Check one pattern; group references must name a configured group.
+fn validate_pattern(
+    pattern: &str,
+    groups: Option<&HashMap<String, Vec<String>>>,
+) -> Result<(), String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("empty sender pattern".to_string());
+    }
+    if let Some(name) = pattern.strip_prefix("group:") {
+        return match groups {
+            None => Err(format!(
+                "'{pattern}': user_groups entries cannot reference groups"
+            )),
+            Some(groups) if groups.contains_key(name.trim()) => Ok(()),
+            Some(_) => Err(format!("'{pattern}': unknown user group '{}'", name.trim())),
+        };
+    }
+    if pattern.starts_with("re:") || pattern.contains(['*', '?']) {
+        compile_pattern(pattern, false).map_err(|e| format!("'{pattern}': {e}"))?;
+    }
+    Ok(())
+}
+
+pub fn validate_user_groups(groups: &HashMap<String, Vec<String>>) -> Result<(), String> {
+    for (name, members) in groups {
+        for member in members {
+            validate_pattern(member, None).map_err(|e| format!("user_groups.{name}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Validate sender pattern lists in `channels.<name>` and `channels.<name>.accounts.<id>`.
+/// Lists may be YAML sequences or comma-separated strings.
+pub fn validate_channel_user_lists(
+    channels: &HashMap<String, serde_yaml::Value>,
+    groups: &HashMap<String, Vec<String>>,
+) -> Result<(), String> {
+    fn check_block(
+        path: &str,
+        block: &serde_yaml::Value,
+        groups: &HashMap<String, Vec<String>>,
+    ) -> Result<(), String> {
+        for key in PATTERN_LIST_KEYS {
+            let entries: Vec<String> = match block.get(*key) {
+                Some(serde_yaml::Value::Sequence(items)) => items
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect(),
+                Some(serde_yaml::Value::String(csv)) => csv
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                _ => continue,
+            };
+            for entry in &entries {
+                validate_pattern(entry, Some(groups)).map_err(|e| format!("{path}.{key}: {e}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    for (name, block) in channels {
+        let path = format!("channels.{name}");
+        check_block(&path, block, groups)?;
+        if let Some(serde_yaml::Value::Mapping(accounts)) = block.get("accounts") {
+            for (account_id, account) in accounts {
+                let account_id = account_id.as_str().unwrap_or_default();
+                check_block(&format!("{path}.accounts.{account_id}"), account, groups)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups() -> HashMap<String, Vec<String>> {
+        HashMap::from([(
+            "ops".to_string(),
+            vec!["ou_ops_*".to_string(), "ou_oncall".to_string()],
+        )])
+    }
+
+    fn policy(allowed: &[&str], admins: &[&str]) -> SenderAccessPolicy {
+        SenderAccessPolicy {
+            allowed: allowed.iter().map(|s| s.to_string()).collect(),
+            admins: admins.iter().map(|s| s.to_string()).collect(),
+            ignore_case: false,
+        }
+    }
+
+    #[test]
+    fn test_resolve_exact_wildcard_regex_and_group() {
+        let groups = groups();
+        let p = policy(&["ou_alice", "ou_team_*", "re:ou_[0-9]+"], &["group:ops"]);
+        assert_eq!(p.resolve("ou_alice", &groups), SenderAccess::Allowed);
+        assert_eq!(p.resolve("ou_team_x", &groups), SenderAccess::Allowed);
+        assert_eq!(p.resolve("ou_42", &groups), SenderAccess::Allowed);
+        assert_eq!(p.resolve("ou_42x", &groups), SenderAccess::Denied);
+        assert_eq!(p.resolve("ou_bob", &groups), SenderAccess::Denied);
+        assert_eq!(p.resolve("ou_ops_1", &groups), SenderAccess::Admin);
+        assert_eq!(p.resolve("ou_oncall", &groups), SenderAccess::Admin);
+    }
+
+    #[test]
+    fn test_empty_allowlist_allows_everyone_and_ignore_case() {
+        let groups = HashMap::new();
+        assert_eq!(
+            policy(&[], &[]).resolve("anyone", &groups),
+            SenderAccess::Allowed
+        );
+        let email = SenderAccessPolicy {
+            allowed: vec!["*@Example.com".to_string()],
+            admins: vec!["Boss@example.com".to_string()],
+            ignore_case: true,
+        };
+        assert_eq!(
+            email.resolve("dev@example.COM", &groups),
+            SenderAccess::Allowed
+        );
+        assert_eq!(
+            email.resolve("boss@EXAMPLE.com", &groups),
+            SenderAccess::Admin
+        );
+        assert_eq!(email.resolve("x@other.com", &groups), SenderAccess::Denied);
+    }
+
+    #[test]
+    fn test_validate_channel_user_lists() {
+        let groups = groups();
+        let channels: HashMap<String, serde_yaml::Value> = serde_yaml::from_str(
+            r#"
+feishu:
+  allowed_user_ids: ["ou_*", "group:ops"]
+  accounts:
+    sales:
+      admin_user_ids: ["re:("]
+"#,
+        )
+        .unwrap();
+        let err = validate_channel_user_lists(&channels, &groups).unwrap_err();
+        assert!(err.starts_with("channels.feishu.accounts.sales.admin_user_ids"));
+
+        let channels: HashMap<String, serde_yaml::Value> =
+            serde_yaml::from_str("dingtalk:\n  allowed_user_ids: \"u1, group:missing\"\n").unwrap();
+        let err = validate_channel_user_lists(&channels, &groups).unwrap_err();
+        assert!(err.contains("unknown user group 'missing'"));
+
+        let nested = HashMap::from([("a".to_string(), vec!["group:b".to_string()])]);
+        assert!(validate_user_groups(&nested).is_err());
+        assert!(validate_user_groups(&groups).is_ok());
+    }
+}
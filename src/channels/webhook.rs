@@ -0,0 +1,959 @@
+//! Generic HTTP integration channel.
+//!
+//! The channel serves its own HTTP listener on `listen_addr`. Other systems
+//! POST `{chat_id, text}` to `webhook_path` with the configured bearer token
+//! and get the agent's reply back in the response. Long turns
+//! either go to a per-request `callback_url` or, when the synchronous wait
+//! runs out, to the channel's `callback_url`. Proactive sends (scheduled
+//! tasks, `send_message`) are posted to the channel's `callback_url`.
+//!
+//! Callers are trusted with the channel, not with admin rights: the payload's
+//! `sender_id` only selects the sender's history and memories. Only the
+//! configured `callback_url` receives `callback_token`; per-request callbacks
+//! pass the web_fetch URL policy and are posted without credentials.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use crate::agent_engine::{process_with_agent, AgentRequestContext};
+use crate::channels::attachments::{
+    fetch_webhook_attachments, ingest_attachments, WebhookAttachment,
+};
+use crate::channels::startup_guard::mark_channel_started;
+use crate::channels::user_access::{SenderAccess, SenderAccessPolicy};
+use crate::chat_commands::{handle_chat_command, is_slash_command, unknown_command_response};
+use crate::runtime::AppState;
+use crate::setup_def::{ChannelFieldDef, DynamicChannelDef};
+use crate::web_tokens::is_valid_api_token;
+use microclaw_channels::channel::ConversationKind;
+use microclaw_channels::channel_adapter::ChannelAdapter;
+use microclaw_storage::db::{call_blocking, StoredMessage};
+
+pub const SETUP_DEF: DynamicChannelDef = DynamicChannelDef {
+    name: "webhook",
+    presence_keys: &["auth_token"],
+    fields: &[
+        ChannelFieldDef {
+            yaml_key: "auth_token",
+            label: "Webhook bearer token callers must send",
+            default: "",
+            secret: true,
+            required: true,
+        },
+        ChannelFieldDef {
+            yaml_key: "listen_addr",
+            label: "Address the webhook listener binds (default 127.0.0.1:10962)",
+            default: "127.0.0.1:10962",
+            secret: false,
+            required: false,
+        },
+        ChannelFieldDef {
+            yaml_key: "webhook_path",
+            label: "Webhook path (default /webhook/message)",
+            default: "/webhook/message",
+            secret: false,
+            required: false,
+        },
+        ChannelFieldDef {
+            yaml_key: "callback_url",
+            label: "Callback URL for proactive and late replies (optional)",
+            default: "",
+            secret: false,
+            required: false,
+        },
+        ChannelFieldDef {
+            yaml_key: "callback_token",
+            label: "Bearer token sent to the callback URL (optional)",
+            default: "",
+            secret: true,
+            required: false,
+        },
+        ChannelFieldDef {
+            yaml_key: "bot_username",
+            label: "Webhook bot username override (optional)",
+            default: "",
+            secret: false,
+            required: false,
+        },
+        ChannelFieldDef {
+            yaml_key: "model",
+            label: "Webhook bot model override (optional)",
+            default: "",
+            secret: false,
+            required: false,
+        },
+    ],
+    validate: None,
+};
+
+const CHAT_TYPE: &str = "webhook";
+const DEFAULT_SENDER_ID: &str = "webhook";
+
+fn default_listen_addr() -> String {
+    "127.0.0.1:10962".to_string()
+}
+
+fn default_webhook_path() -> String {
+    "/webhook/message".to_string()
+}
+
+fn default_reply_timeout_secs() -> u64 {
+    120
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookChannelConfig {
+    /// Bearer token every request must carry.
+    #[serde(default)]
+    pub auth_token: String,
+    /// `host:port` the channel's HTTP listener binds.
+    #[serde(default = "default_listen_addr")]
+    pub listen_addr: String,
+    #[serde(default = "default_webhook_path")]
+    pub webhook_path: String,
+    /// Where proactive messages and replies that outlive the synchronous wait go.
+    #[serde(default)]
+    pub callback_url: String,
+    #[serde(default)]
+    pub callback_token: String,
+    /// How long a request waits for the reply before answering 202 and
+    /// handing the reply to the callback.
+    #[serde(default = "default_reply_timeout_secs")]
+    pub reply_timeout_secs: u64,
+    #[serde(default)]
+    pub allowed_chat_ids: String,
+    /// Sender id patterns (csv) allowed to talk to the bot; empty allows everyone.
+    #[serde(default)]
+    pub allowed_user_ids: String,
+    #[serde(default)]
+    pub bot_username: String,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl WebhookChannelConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.auth_token.trim().is_empty() {
+            return Err("channels.webhook.auth_token is required".into());
+        }
+        if self.listen_addr.trim().parse::<SocketAddr>().is_err() {
+            return Err(
+                "channels.webhook.listen_addr must be host:port, e.g. 127.0.0.1:10962".into(),
+            );
+        }
+        if !self.webhook_path.trim().starts_with('/') {
+            return Err("channels.webhook.webhook_path must start with '/'".into());
+        }
+        if self.reply_timeout_secs == 0 {
+            return Err("channels.webhook.reply_timeout_secs must be >= 1".into());
+        }
+        let callback = self.callback_url.trim();
+        if !callback.is_empty() && !is_http_url(callback) {
+            return Err("channels.webhook.callback_url must be an http(s) URL".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WebhookRequest {
+    chat_id: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    sender_id: String,
+    #[serde(default)]
+    message_id: String,
+    /// Answer 202 right away and post the reply here when the turn ends.
+    #[serde(default)]
+    callback_url: Option<String>,
+    #[serde(default)]
+    attachments: Vec<WebhookAttachment>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookRuntimeContext {
+    pub channel_name: String,
+    pub listen_addr: String,
+    pub auth_token: String,
+    pub callback_url: String,
+    pub callback_token: String,
+    pub reply_timeout: Duration,
+    pub allowed_chat_ids: Vec<String>,
+    pub sender_access: SenderAccessPolicy,
+    pub bot_username: String,
+    pub model: Option<String>,
+}
+
+fn parse_csv(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn is_http_url(raw: &str) -> bool {
+    reqwest::Url::parse(raw).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+pub fn build_webhook_runtime_contexts(
+    config: &crate::config::Config,
+) -> Vec<WebhookRuntimeContext> {
+    let Some(cfg) = config.channel_config::<WebhookChannelConfig>("webhook") else {
+        return Vec::new();
+    };
+    let bot_username = if cfg.bot_username.trim().is_empty() {
+        config.bot_username_for_channel("webhook")
+    } else {
+        cfg.bot_username.trim().to_string()
+    };
+    vec![WebhookRuntimeContext {
+        channel_name: "webhook".to_string(),
+        listen_addr: cfg.listen_addr.trim().to_string(),
+        auth_token: cfg.auth_token.trim().to_string(),
+        callback_url: cfg.callback_url.trim().to_string(),
+        callback_token: cfg.callback_token.trim().to_string(),
+        reply_timeout: Duration::from_secs(cfg.reply_timeout_secs.max(1)),
+        allowed_chat_ids: parse_csv(&cfg.allowed_chat_ids),
+        sender_access: SenderAccessPolicy {
+            allowed: parse_csv(&cfg.allowed_user_ids),
+            // `sender_id` is whatever the caller claims, so it never grants admin.
+            admins: Vec::new(),
+            ignore_case: false,
+        },
+        bot_username,
+        model: cfg
+            .model
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(ToOwned::to_owned),
+    }]
+}
+
+pub struct WebhookAdapter {
+    name: String,
+    callback_url: String,
+    callback_token: String,
+    http_client: reqwest::Client,
+}
+
+impl WebhookAdapter {
+    pub fn new(name: String, callback_url: String, callback_token: String) -> Self {
+        Self {
+            name,
+            callback_url,
+            callback_token,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// POST `body` to the configured `callback_url` with `callback_token`.
+    async fn post_to_callback(&self, body: &serde_json::Value) -> Result<(), String> {
+        let mut request = self.http_client.post(&self.callback_url).json(body);
+        if !self.callback_token.is_empty() {
+            request = request.bearer_auth(&self.callback_token);
+        }
+        check_callback_response(request.send().await).await
+    }
+}
+
+async fn check_callback_response(
+    sent: Result<reqwest::Response, reqwest::Error>,
+) -> Result<(), String> {
+    let resp = sent.map_err(|e| format!("webhook callback failed: {e}"))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("webhook callback error {status}: {body}"));
+    }
+    Ok(())
+}
+
+/// Check a per-request `callback_url` against the web_fetch URL policy
+/// (`web_fetch_url_validation` and the web domain lists).
+async fn validate_request_callback_url(
+    config: &crate::config::Config,
+    url: &str,
+) -> Result<(), String> {
+    if !is_http_url(url) {
+        return Err("callback_url must be http(s)".to_string());
+    }
+    let mut url_validation = config.web_fetch_url_validation.clone();
+    url_validation.domain_policy = config.web_domain_policy();
+    let url_validation =
+        microclaw_tools::web_fetch::resolve_url_validation_config(url_validation).await?;
+    microclaw_tools::web_fetch::validate_web_fetch_url(url, url_validation)
+}
+
+/// POST `body` to a caller-supplied URL: no credentials and no redirects, so
+/// the validated host is the only one contacted.
+async fn post_to_request_callback(url: &str, body: &serde_json::Value) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("webhook callback failed: {e}"))?;
+    check_callback_response(client.post(url).json(body).send().await).await
+}
+
+#[async_trait::async_trait]
+impl ChannelAdapter for WebhookAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
+        vec![(CHAT_TYPE, ConversationKind::Private)]
+    }
+
+    async fn send_text(&self, external_chat_id: &str, text: &str) -> Result<(), String> {
+        if self.callback_url.is_empty() {
+            return Err(
+                "webhook.callback_url is not set; proactive messages cannot be delivered"
+                    .to_string(),
+            );
+        }
+        let body = json!({ "chat_id": external_chat_id, "text": text });
+        self.post_to_callback(&body).await
+    }
+}
+
+pub async fn start_webhook_bot(app_state: Arc<AppState>, runtime: WebhookRuntimeContext) {
+    let listener = match tokio::net::TcpListener::bind(&runtime.listen_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Webhook: failed to listen on {}: {e}", runtime.listen_addr);
+            return;
+        }
+    };
+    mark_channel_started(&runtime.channel_name);
+    info!(
+        "Webhook adapter '{}' listening on {} (callback: {})",
+        runtime.channel_name,
+        runtime.listen_addr,
+        if runtime.callback_url.is_empty() {
+            "none"
+        } else {
+            runtime.callback_url.as_str()
+        }
+    );
+    if let Err(e) = serve_webhook(listener, app_state).await {
+        error!("Webhook: listener stopped: {e}");
+    }
+}
+
+/// Serve the webhook route on `listener` until the server fails.
+async fn serve_webhook(
+    listener: tokio::net::TcpListener,
+    app_state: Arc<AppState>,
+) -> std::io::Result<()> {
+    axum::serve(listener, register_webhook_channel(Router::new(), app_state)).await
+}
+
+pub fn register_webhook_channel(router: Router, app_state: Arc<AppState>) -> Router {
+    let Some(cfg) = app_state
+        .config
+        .channel_config::<WebhookChannelConfig>("webhook")
+    else {
+        return router;
+    };
+    if !app_state.config.channel_enabled("webhook") {
+        return router;
+    }
+    let path = cfg.webhook_path.trim();
+    if path.is_empty() {
+        return router;
+    }
+    router.route(
+        path,
+        axum::routing::post(
+            move |headers: HeaderMap, Json(payload): Json<WebhookRequest>| {
+                let state = app_state.clone();
+                async move { webhook_handler(state, headers, payload).await }
+            },
+        ),
+    )
+}
+
+/// Compares in time independent of where the inputs first differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Accepts `Authorization: Bearer <token>` or `x-webhook-token: <token>`.
+fn is_authorized(headers: &HeaderMap, expected: &str) -> bool {
+    if expected.is_empty() {
+        return false;
+    }
+    let bearer = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().strip_prefix("Bearer "))
+        .map(str::trim);
+    let header_token = headers
+        .get("x-webhook-token")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim);
+    bearer
+        .or(header_token)
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+}
+
+fn error_response(status: StatusCode, message: &str) -> (StatusCode, Json<serde_json::Value>) {
+    (status, Json(json!({ "error": message })))
+}
+
+async fn webhook_handler(
+    app_state: Arc<AppState>,
+    headers: HeaderMap,
+    payload: WebhookRequest,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let Some(runtime_ctx) = build_webhook_runtime_contexts(&app_state.config)
+        .into_iter()
+        .next()
+    else {
+        return error_response(StatusCode::NOT_FOUND, "webhook channel is not configured");
+    };
+    // The channel's `auth_token`, or an API token from `microclaw web token-create`.
+    let authorized = is_authorized(&headers, &runtime_ctx.auth_token) || {
+        let headers = headers.clone();
+        call_blocking(app_state.db.clone(), move |db| {
+            Ok(is_valid_api_token(db, &headers))
+        })
+        .await
+        .unwrap_or(false)
+    };
+    if !authorized {
+        return error_response(StatusCode::UNAUTHORIZED, "missing or invalid token");
+    }
+    let chat_id_external = payload.chat_id.trim().to_string();
+    if chat_id_external.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "chat_id is required");
+    }
+    if payload.text.trim().is_empty() && payload.attachments.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "text or attachments required");
+    }
+    let callback_url = payload
+        .callback_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned);
+    if let Some(url) = callback_url.as_deref() {
+        if let Err(e) = validate_request_callback_url(&app_state.config, url).await {
+            return error_response(StatusCode::BAD_REQUEST, &format!("callback_url: {e}"));
+        }
+    }
+    if !runtime_ctx.allowed_chat_ids.is_empty()
+        && !runtime_ctx.allowed_chat_ids.contains(&chat_id_external)
+    {
+        return error_response(StatusCode::FORBIDDEN, "chat_id is not allowed");
+    }
+    let sender_id = match payload.sender_id.trim() {
+        "" => DEFAULT_SENDER_ID.to_string(),
+        id => id.to_string(),
+    };
+    let sender_access = runtime_ctx
+        .sender_access
+        .resolve(&sender_id, &app_state.config.user_groups);
+    if sender_access == SenderAccess::Denied {
+        return error_response(StatusCode::FORBIDDEN, "sender is not allowed");
+    }
+    let message_id = match payload.message_id.trim() {
+        "" => uuid::Uuid::new_v4().to_string(),
+        id => id.to_string(),
+    };
+
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel::<Result<String, String>>();
+    let queues = app_state.channel_registry.inbound_queues().clone();
+    queues.submit(&runtime_ctx.channel_name, {
+        let state = app_state.clone();
+        let runtime = runtime_ctx.clone();
+        let turn = WebhookTurn {
+            chat_id_external: chat_id_external.clone(),
+            sender_id,
+            message_id: message_id.clone(),
+            text: payload.text.trim().to_string(),
+            attachments: payload.attachments,
+        };
+        let callback_url = callback_url.clone();
+        async move {
+            let result = run_webhook_turn(&state, &runtime, &turn).await;
+            if let Some(url) = callback_url {
+                deliver_to_callback(&runtime, Some(&url), &turn, result).await;
+            } else if let Err(result) = reply_tx.send(result) {
+                // The request stopped waiting; fall back to the channel callback.
+                if runtime.callback_url.is_empty() {
+                    info!(
+                        "Webhook: reply for message {} finished after the caller left; kept in chat history only",
+                        turn.message_id
+                    );
+                } else {
+                    deliver_to_callback(&runtime, None, &turn, result).await;
+                }
+            }
+        }
+    });
+
+    let accepted = |note: &str| {
+        (
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "status": "accepted",
+                "chat_id": chat_id_external,
+                "message_id": message_id,
+                "note": note,
+            })),
+        )
+    };
+    if callback_url.is_some() {
+        return accepted("the reply will be posted to callback_url");
+    }
+    match tokio::time::timeout(runtime_ctx.reply_timeout, reply_rx).await {
+        Ok(Ok(Ok(reply))) => (
+            StatusCode::OK,
+            Json(json!({
+                "chat_id": chat_id_external,
+                "message_id": message_id,
+                "reply": reply,
+            })),
+        ),
+        Ok(Ok(Err(e))) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+        // The inbound queue dropped the job before it ran.
+        Ok(Err(_)) => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "channel queue is full; retry later",
+        ),
+        Err(_) if runtime_ctx.callback_url.is_empty() => {
+            accepted("still running; the reply will be stored in chat history")
+        }
+        Err(_) => accepted("still running; the reply will be posted to the channel callback_url"),
+    }
+}
+
+struct WebhookTurn {
+    chat_id_external: String,
+    sender_id: String,
+    message_id: String,
+    text: String,
+    attachments: Vec<WebhookAttachment>,
+}
+
+/// Post the turn's result to the request's own `callback_url`, or to the
+/// channel's (with `callback_token`) when `request_url` is `None`.
+async fn deliver_to_callback(
+    runtime: &WebhookRuntimeContext,
+    request_url: Option<&str>,
+    turn: &WebhookTurn,
+    result: Result<String, String>,
+) {
+    let body = match result {
+        Ok(reply) => json!({
+            "chat_id": turn.chat_id_external,
+            "message_id": turn.message_id,
+            "reply": reply,
+        }),
+        Err(e) => json!({
+            "chat_id": turn.chat_id_external,
+            "message_id": turn.message_id,
+            "error": e,
+        }),
+    };
+    let sent = match request_url {
+        Some(url) => post_to_request_callback(url, &body).await,
+        None => {
+            WebhookAdapter::new(
+                runtime.channel_name.clone(),
+                runtime.callback_url.clone(),
+                runtime.callback_token.clone(),
+            )
+            .post_to_callback(&body)
+            .await
+        }
+    };
+    if let Err(e) = sent {
+        error!(
+            "Webhook: failed to deliver reply for message {}: {e}",
+            turn.message_id
+        );
+    }
+}
+
+/// Runs one inbound message through the agent and returns the visible reply.
+async fn run_webhook_turn(
+    app_state: &Arc<AppState>,
+    runtime_ctx: &WebhookRuntimeContext,
+    turn: &WebhookTurn,
+) -> Result<String, String> {
+    let chat_id = call_blocking(app_state.db.clone(), {
+        let channel_name = runtime_ctx.channel_name.clone();
+        let title = format!("webhook-{}", turn.chat_id_external);
+        let external_chat_id = turn.chat_id_external.clone();
+        move |db| {
+            db.resolve_or_create_chat_id(&channel_name, &external_chat_id, Some(&title), CHAT_TYPE)
+        }
+    })
+    .await
+    .map_err(|e| format!("failed to resolve chat: {e}"))?;
+
+    if is_slash_command(&turn.text) {
+        let reply = handle_chat_command(
+            app_state,
+            chat_id,
+            &runtime_ctx.channel_name,
+            &turn.text,
+            Some(turn.sender_id.as_str()),
+            false,
+        )
+        .await
+        .unwrap_or_else(unknown_command_response);
+        return Ok(reply);
+    }
+
+    let (fetched, failures) = fetch_webhook_attachments(&app_state.config, &turn.attachments).await;
+    let mut ingested = ingest_attachments(
+        &app_state.config,
+        &runtime_ctx.channel_name,
+        chat_id,
+        app_state.config.control_chat_ids.contains(&chat_id),
+        fetched,
+    )
+    .await;
+    ingested.notes.extend(failures);
+    let content = if ingested.notes.is_empty() {
+        turn.text.clone()
+    } else {
+        ingested.annotate(&turn.text)
+    };
+    let stored = StoredMessage {
+        id: turn.message_id.clone(),
+        chat_id,
+        sender_name: turn.sender_id.clone(),
+        content,
+        is_from_bot: false,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let inserted = call_blocking(app_state.db.clone(), move |db| {
+        db.store_message_if_new(&stored)
+    })
+    .await
+    .map_err(|e| format!("failed to store message: {e}"))?;
+    if !inserted {
+        return Err(format!("duplicate message_id '{}'", turn.message_id));
+    }
+
+    let response = process_with_agent(
+        app_state,
+        AgentRequestContext {
+            caller_channel: &runtime_ctx.channel_name,
+            chat_id,
+            chat_type: "private",
+            sender_id: Some(&turn.sender_id),
+            sender_is_admin: false,
+        },
+        None,
+        ingested.image_data,
+    )
+    .await
+    .map_err(|e| {
+        warn!("Webhook: error processing message for chat {chat_id}: {e}");
+        format!("agent error: {e}")
+    })?;
+    if response.is_empty() {
+        return Ok(app_state.channel_registry.decorate_reply(
+            &runtime_ctx.channel_name,
+            "I couldn't produce a visible reply after an automatic retry. Please try again.",
+        ));
+    }
+    let bot_msg = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        chat_id,
+        sender_name: runtime_ctx.bot_username.clone(),
+        content: response.clone(),
+        is_from_bot: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(app_state.db.clone(), move |db| db.store_message(&bot_msg)).await;
+    Ok(app_state
+        .channel_registry
+        .decorate_reply(&runtime_ctx.channel_name, &response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> WebhookChannelConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let cfg = config("auth_token: s3cret\n");
+        assert_eq!(cfg.listen_addr, "127.0.0.1:10962");
+        assert_eq!(cfg.webhook_path, "/webhook/message");
+        assert_eq!(cfg.reply_timeout_secs, 120);
+        assert!(cfg.validate().is_ok());
+
+        assert!(config("webhook_path: /x\n")
+            .validate()
+            .unwrap_err()
+            .contains("auth_token is required"));
+        assert!(config("auth_token: t\ncallback_url: ftp://host/x\n")
+            .validate()
+            .unwrap_err()
+            .contains("callback_url"));
+        assert!(config("auth_token: t\nlisten_addr: localhost\n")
+            .validate()
+            .unwrap_err()
+            .contains("listen_addr"));
+        assert!(config("auth_token: t\nreply_timeout_secs: 0\n")
+            .validate()
+            .unwrap_err()
+            .contains("reply_timeout_secs"));
+    }
+
+    #[test]
+    fn test_is_authorized_accepts_bearer_or_header_token() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_authorized(&headers, "s3cret"));
+        headers.insert("authorization", "Bearer s3cret".parse().unwrap());
+        assert!(is_authorized(&headers, "s3cret"));
+        assert!(!is_authorized(&headers, "other"));
+        assert!(!is_authorized(&headers, ""));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-webhook-token", " s3cret ".parse().unwrap());
+        assert!(is_authorized(&headers, "s3cret"));
+        assert!(!is_authorized(&headers, "s3cre"));
+        assert!(!is_authorized(&headers, "s3cres"));
+    }
+
+    #[test]
+    fn test_request_payload_defaults() {
+        let req: WebhookRequest =
+            serde_json::from_value(json!({"chat_id": "ticket-42", "text": "hi"})).unwrap();
+        assert_eq!(req.chat_id, "ticket-42");
+        assert!(req.sender_id.is_empty());
+        assert!(req.callback_url.is_none());
+        assert!(req.attachments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_adapter_requires_callback_for_proactive_sends() {
+        let adapter = WebhookAdapter::new("webhook".into(), String::new(), String::new());
+        let err = adapter.send_text("ticket-42", "hello").await.unwrap_err();
+        assert!(err.contains("callback_url is not set"));
+    }
+
+    struct FixedReplyLlm;
+
+    #[async_trait::async_trait]
+    impl crate::llm::LlmProvider for FixedReplyLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<microclaw_core::llm_types::Message>,
+            _tools: Option<Vec<microclaw_core::llm_types::ToolDefinition>>,
+        ) -> Result<
+            microclaw_core::llm_types::MessagesResponse,
+            microclaw_core::error::MicroClawError,
+        > {
+            Ok(microclaw_core::llm_types::MessagesResponse {
+                content: vec![microclaw_core::llm_types::ResponseContentBlock::Text {
+                    text: "pong from agent".to_string(),
+                }],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    fn test_state(base_dir: &std::path::Path, webhook_yaml: &str) -> Arc<AppState> {
+        let runtime_dir = base_dir.join("runtime");
+        std::fs::create_dir_all(&runtime_dir).unwrap();
+        let mut cfg = crate::config::Config::test_defaults();
+        cfg.data_dir = base_dir.to_string_lossy().to_string();
+        cfg.working_dir = base_dir.join("tmp").to_string_lossy().to_string();
+        cfg.web_fetch_url_validation.denylist_hosts = vec!["blocked.example".into()];
+        cfg.channels.insert(
+            "webhook".into(),
+            serde_yaml::from_str(&format!(
+                "enabled: true\nauth_token: s3cret\n{webhook_yaml}"
+            ))
+            .unwrap(),
+        );
+        let db =
+            Arc::new(microclaw_storage::db::Database::new(runtime_dir.to_str().unwrap()).unwrap());
+        let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
+        let mut registry = microclaw_channels::channel_adapter::ChannelRegistry::new();
+        registry.register(Arc::new(WebhookAdapter::new(
+            "webhook".into(),
+            String::new(),
+            String::new(),
+        )));
+        let channel_registry = Arc::new(registry);
+        Arc::new(AppState {
+            config: cfg.clone(),
+            channel_registry: channel_registry.clone(),
+            db: db.clone(),
+            memory: crate::memory::MemoryManager::new(runtime_dir.to_str().unwrap()),
+            skills: crate::skills::SkillManager::from_skills_dir(&cfg.skills_data_dir()),
+            hooks: Arc::new(crate::hooks::HookManager::from_config(&cfg)),
+            llm: Box::new(FixedReplyLlm),
+            llm_model_overrides: std::collections::HashMap::new(),
+            embedding: None,
+            memory_backend: memory_backend.clone(),
+            tools: crate::tools::ToolRegistry::new(&cfg, channel_registry, db, memory_backend),
+            mcp_manager: tokio::sync::Mutex::new(crate::mcp::McpManager::default()),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_listener_serves_webhook_requests_end_to_end() {
+        let base_dir = std::env::temp_dir().join(format!("mc_webhook_{}", uuid::Uuid::new_v4()));
+        let state = test_state(&base_dir, "");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_webhook(listener, state));
+
+        let url = format!("http://{addr}/webhook/message");
+        let client = reqwest::Client::new();
+        let body = json!({"chat_id": "ticket-42", "sender_id": "alice", "text": "ping"});
+        let resp = client.post(&url).json(&body).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let resp = client
+            .post(&url)
+            .bearer_auth("s3cret")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let reply: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(reply["chat_id"], "ticket-42");
+        assert!(
+            reply["reply"].as_str().unwrap().contains("pong from agent"),
+            "{reply}"
+        );
+
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[tokio::test]
+    async fn test_listener_accepts_api_tokens() {
+        let base_dir = std::env::temp_dir().join(format!("mc_webhook_{}", uuid::Uuid::new_v4()));
+        let state = test_state(&base_dir, "");
+        let (id, token) = crate::web_tokens::create_api_token(&state.db, "ci", None).unwrap();
+        let db = state.db.clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_webhook(listener, state));
+
+        let url = format!("http://{addr}/webhook/message");
+        let client = reqwest::Client::new();
+        let body = json!({"chat_id": "ticket-9", "text": "ping"});
+        let resp = client
+            .post(&url)
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        assert!(db.revoke_api_key(id).unwrap());
+        let resp = client
+            .post(&url)
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[tokio::test]
+    async fn test_request_callback_is_validated_and_gets_no_channel_token() {
+        let (seen_tx, mut seen_rx) =
+            tokio::sync::mpsc::unbounded_channel::<(Option<String>, serde_json::Value)>();
+        let receiver = Router::new().route(
+            "/cb",
+            axum::routing::post(
+                move |headers: HeaderMap, Json(body): Json<serde_json::Value>| {
+                    let seen_tx = seen_tx.clone();
+                    async move {
+                        let auth = headers
+                            .get(axum::http::header::AUTHORIZATION)
+                            .and_then(|v| v.to_str().ok())
+                            .map(ToOwned::to_owned);
+                        let _ = seen_tx.send((auth, body));
+                        StatusCode::OK
+                    }
+                },
+            ),
+        );
+        let receiver_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_addr = receiver_listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(receiver_listener, receiver).await });
+
+        let base_dir = std::env::temp_dir().join(format!("mc_webhook_{}", uuid::Uuid::new_v4()));
+        let state = test_state(
+            &base_dir,
+            &format!(
+                "callback_url: http://{receiver_addr}/channel\ncallback_token: channel-secret\n"
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_webhook(listener, state));
+        let url = format!("http://{addr}/webhook/message");
+        let client = reqwest::Client::new();
+
+        let resp = client
+            .post(&url)
+            .bearer_auth("s3cret")
+            .json(&json!({
+                "chat_id": "ticket-7",
+                "text": "ping",
+                "callback_url": "http://blocked.example/cb",
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let resp = client
+            .post(&url)
+            .bearer_auth("s3cret")
+            .json(&json!({
+                "chat_id": "ticket-7",
+                "text": "ping",
+                "callback_url": format!("http://{receiver_addr}/cb"),
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::ACCEPTED);
+        let (auth, body) = tokio::time::timeout(Duration::from_secs(10), seen_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(auth, None);
+        assert!(body["reply"].as_str().unwrap().contains("pong from agent"));
+
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+}
@@ -1,13 +1,23 @@
 use std::sync::Arc;
 
-use crate::agent_engine::archive_conversation;
-use crate::config::Config;
+use crate::agent_engine::{
+    activated_skills, active_skill_model, archive_conversation, regenerate_last_response,
+    AgentRequestContext,
+};
+use crate::config::{Config, ModelRole};
 use crate::run_control;
 use crate::runtime::AppState;
-use microclaw_core::llm_types::Message;
-use microclaw_storage::db::{call_blocking, Database};
+use microclaw_channels::channel::ConversationKind;
+use microclaw_channels::channel_adapter::InboundReaction;
+use microclaw_channels::health::ChannelHealth;
+use microclaw_channels::inbound_queue::ChannelQueueStats;
+use microclaw_core::llm_types::{Message, ToolChoice};
+use microclaw_core::text::floor_char_boundary;
+use microclaw_storage::db::{call_blocking, Database, Memory, StoredMessage};
 use microclaw_storage::usage::build_usage_report;
+use microclaw_tools::runtime::ToolAuthContext;
 use microclaw_tools::todo_store::clear_todos;
+use microclaw_tools::types::WorkingDirIsolation;
 use tracing::warn;
 
 pub fn is_slash_command(text: &str) -> bool {
@@ -51,6 +61,7 @@ pub async fn handle_chat_command(
     caller_channel: &str,
     command_text: &str,
     sender_id: Option<&str>,
+    sender_is_admin: bool,
 ) -> Option<String> {
     let trimmed = normalized_slash_command(command_text)?.trim();
 
@@ -80,6 +91,17 @@ pub async fn handle_chat_command(
         return Some(format!("Reloaded {count} skills from disk."));
     }
 
+    if trimmed == "/reload" {
+        if !state.config.control_chat_ids.contains(&chat_id) {
+            return Some("Reload requires control chat permission.".to_string());
+        }
+        let report = crate::runtime::reload_mcp_tools(state).await;
+        return Some(format!(
+            "Reloaded MCP servers ({}). Plugin changes apply without reload.",
+            report.summary()
+        ));
+    }
+
     if trimmed == "/archive" {
         if let Ok(Some((json, _))) =
             call_blocking(state.db.clone(), move |db| db.load_session(chat_id)).await
@@ -108,6 +130,8 @@ pub async fn handle_chat_command(
                 state.db.clone(),
                 &state.config,
                 &state.llm_model_overrides,
+                state.channel_registry.health(),
+                &state.channel_registry.inbound_queues().snapshot(),
                 chat_id,
                 caller_channel,
             )
@@ -115,6 +139,37 @@ pub async fn handle_chat_command(
         );
     }
 
+    if trimmed == "/whoami" {
+        let auth = ToolAuthContext {
+            caller_channel: caller_channel.to_string(),
+            caller_chat_id: chat_id,
+            control_chat_ids: state.config.control_chat_ids.clone(),
+            caller_is_admin: sender_is_admin,
+            caller_sender_id: sender_id.map(str::to_string),
+            run_id: 0,
+        };
+        return Some(build_whoami_response(&auth));
+    }
+
+    if trimmed == "/context" {
+        let is_control_chat = sender_is_admin || state.config.control_chat_ids.contains(&chat_id);
+        let isolation = state
+            .config
+            .resolved_working_dir_isolation()
+            .for_chat(is_control_chat);
+        let session = call_blocking(state.db.clone(), move |db| db.load_session(chat_id)).await;
+        let session_json = match &session {
+            Ok(Some((json, _))) => Some(json.as_str()),
+            Ok(None) => None,
+            Err(e) => return Some(format!("Context: session unavailable ({e})")),
+        };
+        return Some(build_context_response(
+            current_model(&state.config, &state.llm_model_overrides, caller_channel),
+            isolation,
+            session_json,
+        ));
+    }
+
     if trimmed == "/start" {
         if let Some(id) = sender_id.map(str::trim).filter(|v| !v.is_empty()) {
             return Some(format!("Hello MicroClaw :) Your ID: {id}"));
@@ -123,30 +178,580 @@ pub async fn handle_chat_command(
     }
 
     if trimmed == "/model" || trimmed.starts_with("/model ") {
+        let skill_model =
+            match call_blocking(state.db.clone(), move |db| db.load_session(chat_id)).await {
+                Ok(Some((json, _))) => {
+                    let messages: Vec<Message> = serde_json::from_str(&json).unwrap_or_default();
+                    active_skill_model(&state.skills, &messages)
+                }
+                _ => None,
+            };
         return Some(build_model_response(
             &state.config,
             &state.llm_model_overrides,
             caller_channel,
+            skill_model.as_deref(),
             trimmed,
         ));
     }
 
+    if trimmed == "/retry" || trimmed.starts_with("/retry ") {
+        let model = trimmed.strip_prefix("/retry").unwrap_or("").trim();
+        return Some(
+            retry_last_response(
+                state,
+                chat_id,
+                caller_channel,
+                sender_id,
+                sender_is_admin,
+                (!model.is_empty()).then_some(model),
+            )
+            .await,
+        );
+    }
+
+    if trimmed == "/tools" || trimmed.starts_with("/tools ") {
+        let tool_names: Vec<String> = state
+            .tools
+            .definitions()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        let requested = trimmed.strip_prefix("/tools").unwrap_or("").trim();
+        if requested.is_empty() {
+            let pending = run_control::peek_next_tool_choice(caller_channel, chat_id).await;
+            return Some(build_tools_response(None, pending.as_ref(), &tool_names));
+        }
+        let parsed = ToolChoice::parse(requested);
+        let response = build_tools_response(parsed.as_ref(), None, &tool_names);
+        if let Some(choice) = parsed.filter(|c| tool_choice_is_known(c, &tool_names)) {
+            run_control::set_next_tool_choice(caller_channel, chat_id, choice).await;
+        }
+        return Some(response);
+    }
+
+    if trimmed == "/verbosity" || trimmed.starts_with("/verbosity ") {
+        let requested = trimmed.strip_prefix("/verbosity").unwrap_or("").trim();
+        if requested.is_empty() {
+            let limit = crate::agent_engine::resolve_max_response_tokens(
+                state,
+                caller_channel,
+                chat_id,
+                None,
+            )
+            .await;
+            let source = match call_blocking(state.db.clone(), move |db| {
+                db.get_chat_max_response_tokens(chat_id)
+            })
+            .await
+            {
+                Ok(Some(_)) => "set for this chat",
+                _ => "channel default",
+            };
+            return Some(format!(
+                "Reply length: {} ({source}). {VERBOSITY_USAGE}",
+                describe_reply_limit(limit)
+            ));
+        }
+        let Some(setting) = parse_verbosity(requested) else {
+            return Some(format!(
+                "Unknown verbosity '{requested}'. {VERBOSITY_USAGE}"
+            ));
+        };
+        let stored = call_blocking(state.db.clone(), move |db| {
+            db.set_chat_max_response_tokens(chat_id, setting)
+        })
+        .await;
+        return Some(match stored {
+            Ok(true) => {
+                let limit = crate::agent_engine::resolve_max_response_tokens(
+                    state,
+                    caller_channel,
+                    chat_id,
+                    None,
+                )
+                .await;
+                match setting {
+                    Some(_) => format!(
+                        "Reply length for this chat: {}.",
+                        describe_reply_limit(limit)
+                    ),
+                    None => format!(
+                        "Reply length reset to the channel default: {}.",
+                        describe_reply_limit(limit)
+                    ),
+                }
+            }
+            Ok(false) => "Verbosity command failed: chat not found".to_string(),
+            Err(e) => format!("Verbosity command failed: {e}"),
+        });
+    }
+
+    if trimmed == "/debug" || trimmed.starts_with("/debug ") {
+        if !state.config.control_chat_ids.contains(&chat_id) {
+            return Some("The debug footer requires control chat permission.".to_string());
+        }
+        let enabled = match trimmed.strip_prefix("/debug").unwrap_or("").trim() {
+            "" | "status" => {
+                let on = call_blocking(state.db.clone(), move |db| {
+                    db.get_chat_debug_footer(chat_id)
+                })
+                .await
+                .unwrap_or(false);
+                return Some(format!(
+                    "Debug footer is {}. Usage: /debug [on|off]",
+                    if on { "on" } else { "off" }
+                ));
+            }
+            "on" => true,
+            "off" => false,
+            _ => return Some("Usage: /debug [on|off]".to_string()),
+        };
+        let stored = call_blocking(state.db.clone(), move |db| {
+            db.set_chat_debug_footer(chat_id, enabled)
+        })
+        .await;
+        return Some(match stored {
+            Ok(true) if enabled => {
+                "Debug footer on: replies here end with tokens, estimated cost, LLM and tool timings."
+                    .to_string()
+            }
+            Ok(true) => "Debug footer off.".to_string(),
+            Ok(false) => "Debug command failed: chat not found".to_string(),
+            Err(e) => format!("Debug command failed: {e}"),
+        });
+    }
+
+    if trimmed == "/remember" || trimmed.starts_with("/remember ") {
+        let fact = trimmed.strip_prefix("/remember").unwrap_or("").trim();
+        if fact.is_empty() {
+            return Some(
+                match state.memory_backend.get_pinned_memories(chat_id).await {
+                    Ok(pinned) => format_pinned_memories(
+                        pinned
+                            .iter()
+                            .filter(|m| m.visible_to(chat_id, sender_id))
+                            .collect(),
+                    ),
+                    Err(e) => format!("Failed to load pinned memories: {e}"),
+                },
+            );
+        }
+        if fact.chars().count() > MAX_PINNED_CHARS {
+            return Some(format!(
+                "Pinned memories are at most {MAX_PINNED_CHARS} characters; shorten it and try again."
+            ));
+        }
+        let id = match state
+            .memory_backend
+            .insert_memory_deduped(Some(chat_id), fact, "KNOWLEDGE", "remember_command", 0.95)
+            .await
+        {
+            Ok(outcome) => outcome.memory_id(),
+            Err(e) => return Some(format!("Failed to save memory: {e}")),
+        };
+        return Some(
+            match state.memory_backend.set_memory_pinned(id, true).await {
+                Ok(true) => format!(
+                    "Pinned (#{id}). It is included in every turn here; /unpin {id} to stop."
+                ),
+                Ok(false) => format!("Saved to memory (#{id}), but it could not be pinned."),
+                Err(e) => format!("Saved to memory (#{id}), but pinning failed: {e}"),
+            },
+        );
+    }
+
+    if let Some(arg) = trimmed.strip_prefix("/unpin") {
+        if !arg.is_empty() && !arg.starts_with(' ') {
+            return None;
+        }
+        let Ok(id) = arg.trim().parse::<i64>() else {
+            return Some("Usage: /unpin <id> (see /remember for ids)".to_string());
+        };
+        let memory = match state.memory_backend.get_memory_by_id(id).await {
+            Ok(Some(m)) if m.visible_to(chat_id, sender_id) => m,
+            Ok(_) => return Some(format!("No memory #{id} in this chat.")),
+            Err(e) => return Some(format!("Unpin failed: {e}")),
+        };
+        if memory.chat_id.is_none() && !state.config.control_chat_ids.contains(&chat_id) {
+            return Some("Unpinning a global memory requires control chat permission.".to_string());
+        }
+        return Some(
+            match state.memory_backend.set_memory_pinned(id, false).await {
+                Ok(true) => {
+                    format!("Unpinned #{id}; it stays in memory and is recalled by relevance.")
+                }
+                Ok(false) => format!("No memory #{id} in this chat."),
+                Err(e) => format!("Unpin failed: {e}"),
+            },
+        );
+    }
+
+    if trimmed == "/scheduler" || trimmed.starts_with("/scheduler ") {
+        let action = trimmed.strip_prefix("/scheduler").unwrap_or("").trim();
+        if matches!(action, "pause" | "resume") && !state.config.control_chat_ids.contains(&chat_id)
+        {
+            return Some("Pausing the scheduler requires control chat permission.".to_string());
+        }
+        let timezone = state.config.timezone.clone();
+        let result = match action {
+            "" | "status" => {
+                call_blocking(state.db.clone(), crate::scheduler::scheduler_status_text).await
+            }
+            "pause" => {
+                call_blocking(state.db.clone(), |db| {
+                    crate::scheduler::pause_scheduler(db).map(crate::scheduler::pause_response)
+                })
+                .await
+            }
+            "resume" => {
+                call_blocking(state.db.clone(), move |db| {
+                    crate::scheduler::resume_scheduler(db, &timezone)
+                        .map(crate::scheduler::resume_response)
+                })
+                .await
+            }
+            _ => return Some("Usage: /scheduler [status|pause|resume]".to_string()),
+        };
+        return Some(result.unwrap_or_else(|e| format!("Scheduler command failed: {e}")));
+    }
+
     None
 }
 
+/// Longest fact `/remember` pins, matching the structured memory limit.
+const MAX_PINNED_CHARS: usize = 300;
+
+fn format_pinned_memories(pinned: Vec<&Memory>) -> String {
+    if pinned.is_empty() {
+        return "No pinned memories. Use /remember <fact> to pin one.".to_string();
+    }
+    let mut out = String::from("Pinned memories (included in every turn):");
+    for m in pinned {
+        let scope = if m.chat_id.is_none() {
+            "global"
+        } else if m.sender_id.is_some() {
+            "user"
+        } else {
+            "chat"
+        };
+        out.push_str(&format!("\n#{} [{}] {}", m.id, scope, m.content));
+    }
+    out
+}
+
+/// What a `reaction_commands` entry does when a user reacts to a bot message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReactionAction {
+    /// Answer the previous request again.
+    Regenerate,
+    /// Save the reacted message as a chat memory.
+    Remember,
+    /// Delete the reacted message.
+    Delete,
+    /// Run a slash command, as if the user had sent it.
+    Command(String),
+}
+
+impl ReactionAction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        match raw {
+            "regenerate" => Some(Self::Regenerate),
+            "remember" => Some(Self::Remember),
+            "delete" => Some(Self::Delete),
+            _ if raw.len() > 1 && raw.starts_with('/') => Some(Self::Command(raw.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Emoji compare without variation selectors, so `🗑️` and `🗑` are one key.
+pub fn normalize_reaction_emoji(emoji: &str) -> String {
+    emoji.trim().replace('\u{fe0f}', "")
+}
+
+pub fn reaction_action(
+    reaction_commands: &std::collections::HashMap<String, String>,
+    emoji: &str,
+) -> Option<ReactionAction> {
+    let emoji = normalize_reaction_emoji(emoji);
+    reaction_commands
+        .iter()
+        .find(|(key, _)| normalize_reaction_emoji(key) == emoji)
+        .and_then(|(_, action)| ReactionAction::parse(action))
+}
+
+/// Longest reacted message saved by `remember`.
+const MAX_REMEMBERED_CHARS: usize = 2000;
+
+/// Run the `reaction_commands` entry for `reaction` in `chat_id`. Returns text
+/// to send back, or `None` when the emoji is unmapped or the action is silent.
+pub async fn handle_reaction_command(
+    state: &AppState,
+    chat_id: i64,
+    caller_channel: &str,
+    reaction: &InboundReaction,
+    sender_is_admin: bool,
+) -> Option<String> {
+    let action = reaction_action(&state.config.reaction_commands, &reaction.emoji)?;
+    match action {
+        ReactionAction::Command(command) => {
+            let reply = handle_chat_command(
+                state,
+                chat_id,
+                caller_channel,
+                &command,
+                Some(&reaction.sender_id),
+                sender_is_admin,
+            )
+            .await;
+            Some(reply.unwrap_or_else(unknown_command_response))
+        }
+        ReactionAction::Remember => {
+            let Some(text) = reaction
+                .message_text
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+            else {
+                return Some("Couldn't read that message, so nothing was saved.".to_string());
+            };
+            let content = &text[..floor_char_boundary(text, MAX_REMEMBERED_CHARS)];
+            let content = content.to_string();
+            let saved = call_blocking(state.db.clone(), move |db| {
+                db.insert_memory_with_metadata(
+                    Some(chat_id),
+                    &content,
+                    "KNOWLEDGE",
+                    "reaction",
+                    0.9,
+                )
+            })
+            .await;
+            Some(match saved {
+                Ok(id) => format!("Saved to memory (#{id})."),
+                Err(e) => format!("Failed to save memory: {e}"),
+            })
+        }
+        ReactionAction::Delete => {
+            let adapter = state.channel_registry.get(caller_channel)?;
+            match adapter
+                .delete_message(&reaction.external_chat_id, &reaction.message_id)
+                .await
+            {
+                Ok(()) => None,
+                Err(e) => Some(format!("Could not delete that message: {e}")),
+            }
+        }
+        ReactionAction::Regenerate => Some(
+            retry_last_response(
+                state,
+                chat_id,
+                caller_channel,
+                Some(&reaction.sender_id),
+                sender_is_admin,
+                None,
+            )
+            .await,
+        ),
+    }
+}
+
+/// Regenerate the last answer in `chat_id` (`/retry` and the `regenerate`
+/// reaction). The new answer is stored next to the old one in chat history.
+async fn retry_last_response(
+    state: &AppState,
+    chat_id: i64,
+    caller_channel: &str,
+    sender_id: Option<&str>,
+    sender_is_admin: bool,
+    model: Option<&str>,
+) -> String {
+    let db_chat_type = call_blocking(state.db.clone(), move |db| db.get_chat_type(chat_id))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let chat_type = match state.channel_registry.resolve_routing(&db_chat_type) {
+        Some((_, ConversationKind::Group)) => "group",
+        _ => "private",
+    };
+    let context = AgentRequestContext {
+        caller_channel,
+        chat_id,
+        chat_type,
+        sender_id,
+        sender_is_admin,
+    };
+    let response = match regenerate_last_response(state, context, model).await {
+        Ok(Some(response)) if !response.trim().is_empty() => response,
+        Ok(Some(_)) => return "I couldn't produce a new answer.".to_string(),
+        Ok(None) => return "Nothing to retry yet.".to_string(),
+        Err(e) => {
+            warn!("Retry failed for chat {chat_id}: {e}");
+            return format!("Retry failed: {e}");
+        }
+    };
+    let bot_msg = StoredMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        chat_id,
+        sender_name: state.config.bot_username.clone(),
+        content: response.clone(),
+        is_from_bot: true,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    let _ = call_blocking(state.db.clone(), move |db| db.store_message(&bot_msg)).await;
+    state
+        .channel_registry
+        .decorate_reply(caller_channel, &response)
+}
+
+const VERBOSITY_USAGE: &str = "Usage: /verbosity <short|normal|long|tokens|off|default>";
+
+/// Smallest token count `/verbosity <tokens>` accepts; less cannot hold a sentence.
+const MIN_VERBOSITY_TOKENS: u32 = 32;
+
+/// A `/verbosity` argument as the chat's stored limit: `Some(0)` lifts any
+/// limit, `None` falls back to the channel's `max_response_tokens`.
+fn parse_verbosity(requested: &str) -> Option<Option<u32>> {
+    match requested.trim().to_ascii_lowercase().as_str() {
+        "short" => Some(Some(150)),
+        "normal" => Some(Some(400)),
+        "long" => Some(Some(1000)),
+        "off" => Some(Some(0)),
+        "default" => Some(None),
+        other => other
+            .trim_end_matches("tokens")
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|n| *n >= MIN_VERBOSITY_TOKENS)
+            .map(Some),
+    }
+}
+
+fn describe_reply_limit(limit: Option<u32>) -> String {
+    match limit {
+        Some(limit) => format!("about {limit} tokens"),
+        None => "no limit".to_string(),
+    }
+}
+
+fn tool_choice_is_known(choice: &ToolChoice, tool_names: &[String]) -> bool {
+    match choice {
+        ToolChoice::Tool(name) => tool_names.iter().any(|t| t == name),
+        _ => true,
+    }
+}
+
+/// `/tools` reply: with `requested` it confirms (or rejects) the override for
+/// the next turn, otherwise it reports the pending one.
+fn build_tools_response(
+    requested: Option<&ToolChoice>,
+    pending: Option<&ToolChoice>,
+    tool_names: &[String],
+) -> String {
+    const USAGE: &str = "Usage: /tools <auto|none|required|tool name>";
+    match requested {
+        Some(choice) if !tool_choice_is_known(choice, tool_names) => {
+            format!(
+                "Unknown tool '{}'. {USAGE}",
+                choice.to_string().trim_start_matches("tool:")
+            )
+        }
+        Some(ToolChoice::Auto) => {
+            "Tool choice for the next turn: auto (model decides).".to_string()
+        }
+        Some(ToolChoice::None) => {
+            "Tool choice for the next turn: none (tools disabled).".to_string()
+        }
+        Some(ToolChoice::Required) => {
+            "Tool choice for the next turn: required (first reply must call a tool).".to_string()
+        }
+        Some(ToolChoice::Tool(name)) => {
+            format!("Tool choice for the next turn: first reply must call `{name}`.")
+        }
+        None => match pending {
+            Some(choice) => format!("Tool choice for the next turn: {choice}. {USAGE}"),
+            None => format!("Tool choice: auto. {USAGE}"),
+        },
+    }
+}
+
+/// `/whoami` reply: how permission checks see the caller in this chat.
+fn build_whoami_response(auth: &ToolAuthContext) -> String {
+    let role = if auth.caller_is_admin {
+        "admin"
+    } else {
+        "member"
+    };
+    let control = if auth.control_chat_ids.contains(&auth.caller_chat_id) {
+        "yes"
+    } else if auth.caller_is_admin {
+        "yes (admin sender)"
+    } else {
+        "no"
+    };
+    format!(
+        "Who am I\nChannel: {}\nChat ID: {}\nSender: {}\nRole: {role}\nControl chat: {control}",
+        auth.caller_channel,
+        auth.caller_chat_id,
+        auth.caller_sender_id.as_deref().unwrap_or("unknown")
+    )
+}
+
+/// `/context` reply: what the next turn will run with. Token usage is estimated
+/// from the stored session at ~4 bytes per token.
+fn build_context_response(
+    model: &str,
+    isolation: WorkingDirIsolation,
+    session_json: Option<&str>,
+) -> String {
+    let messages: Vec<Message> = session_json
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    let mut skills: Vec<String> = activated_skills(&messages).into_keys().collect();
+    skills.sort();
+    let skills = if skills.is_empty() {
+        "none".to_string()
+    } else {
+        skills.join(", ")
+    };
+    let isolation = match isolation {
+        WorkingDirIsolation::Chat => "chat",
+        WorkingDirIsolation::Shared => "shared",
+    };
+    let tokens = session_json.map_or(0, |json| json.len() / 4);
+    format!(
+        "Context\nModel: {model}\nActive skills: {skills}\nWorking dir isolation: {isolation}\nSession: {} messages, ~{tokens} tokens",
+        messages.len()
+    )
+}
+
+fn current_model<'a>(
+    config: &'a Config,
+    llm_model_overrides: &'a std::collections::HashMap<String, String>,
+    caller_channel: &str,
+) -> &'a str {
+    llm_model_overrides
+        .get(caller_channel)
+        .map(String::as_str)
+        .unwrap_or(config.model_for_role(ModelRole::Main))
+        .trim()
+}
+
 pub async fn build_status_response(
     db: Arc<Database>,
     config: &Config,
     llm_model_overrides: &std::collections::HashMap<String, String>,
+    channel_health: &ChannelHealth,
+    queue_stats: &[ChannelQueueStats],
     chat_id: i64,
     caller_channel: &str,
 ) -> String {
     let provider = config.llm_provider.trim();
-    let model = llm_model_overrides
-        .get(caller_channel)
-        .map(String::as_str)
-        .unwrap_or(config.model.as_str())
-        .trim();
+    let model = current_model(config, llm_model_overrides, caller_channel);
 
     let session_line = match call_blocking(db.clone(), move |db| db.load_session(chat_id)).await {
         Ok(Some((json, updated_at))) => {
@@ -161,6 +766,10 @@ pub async fn build_status_response(
         Err(e) => format!("Session: unavailable ({e})"),
     };
 
+    let global_pause = match call_blocking(db.clone(), |db| db.scheduler_paused_at()).await {
+        Ok(Some(_)) => " (scheduler paused globally)",
+        _ => "",
+    };
     let task_line = match call_blocking(db.clone(), move |db| db.get_tasks_for_chat(chat_id)).await
     {
         Ok(tasks) => {
@@ -183,34 +792,72 @@ pub async fn build_status_response(
             let total = active + paused + completed + cancelled + other;
             if other == 0 {
                 format!(
-                    "Scheduled tasks: total={total}, active={active}, paused={paused}, completed={completed}, cancelled={cancelled}"
+                    "Scheduled tasks: total={total}, active={active}, paused={paused}, completed={completed}, cancelled={cancelled}{global_pause}"
                 )
             } else {
                 format!(
-                    "Scheduled tasks: total={total}, active={active}, paused={paused}, completed={completed}, cancelled={cancelled}, other={other}"
+                    "Scheduled tasks: total={total}, active={active}, paused={paused}, completed={completed}, cancelled={cancelled}, other={other}{global_pause}"
                 )
             }
         }
         Err(e) => format!("Scheduled tasks: unavailable ({e})"),
     };
 
+    let delivery_line = build_delivery_line(channel_health);
+    let queue_line = build_queue_line(queue_stats);
+    let circuit_line = crate::llm_circuit::circuit_status_line();
+
     format!(
-        "Status\nChannel: {caller_channel}\nProvider: {provider}\nModel: {model}\n{session_line}\n{task_line}"
+        "Status\nChannel: {caller_channel}\nProvider: {provider}\nModel: {model}\n{session_line}\n{task_line}\n{delivery_line}\n{queue_line}\n{circuit_line}"
     )
 }
 
+fn build_queue_line(queue_stats: &[ChannelQueueStats]) -> String {
+    let items: Vec<String> = queue_stats
+        .iter()
+        .filter(|s| s.depth > 0 || s.dropped > 0)
+        .map(|s| format!("{} (pending={}, dropped={})", s.channel, s.depth, s.dropped))
+        .collect();
+    if items.is_empty() {
+        return "Inbound queues: idle".to_string();
+    }
+    format!("Inbound queues: {}", items.join("; "))
+}
+
+fn build_delivery_line(channel_health: &ChannelHealth) -> String {
+    let failing = channel_health.failing_channels();
+    if failing.is_empty() {
+        return "Delivery: ok".to_string();
+    }
+    let items: Vec<String> = failing
+        .iter()
+        .map(|s| {
+            format!(
+                "{} ({} consecutive failures, last error: {})",
+                s.channel,
+                s.consecutive_failures,
+                s.last_error.as_deref().unwrap_or("unknown")
+            )
+        })
+        .collect();
+    format!("Delivery: failing channels: {}", items.join("; "))
+}
+
 pub fn build_model_response(
     config: &Config,
     llm_model_overrides: &std::collections::HashMap<String, String>,
     caller_channel: &str,
+    skill_model: Option<&str>,
     command_text: &str,
 ) -> String {
     let provider = config.llm_provider.trim();
-    let model = llm_model_overrides
-        .get(caller_channel)
-        .map(String::as_str)
-        .unwrap_or(config.model.as_str())
-        .trim();
+    let channel_model = current_model(config, llm_model_overrides, caller_channel);
+    let model = match skill_model.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(skill_model) if skill_model != channel_model => {
+            format!("{skill_model} (set by an active skill; default {channel_model})")
+        }
+        _ => channel_model.to_string(),
+    };
     let requested = command_text
         .trim()
         .strip_prefix("/model")
@@ -241,7 +888,16 @@ pub async fn maybe_handle_plugin_command(
 
 #[cfg(test)]
 mod tests {
-    use super::is_slash_command;
+    use super::{
+        build_context_response, build_delivery_line, build_model_response, build_queue_line,
+        build_tools_response, build_whoami_response, describe_reply_limit, format_pinned_memories,
+        is_slash_command, parse_verbosity, reaction_action, Database, ReactionAction,
+    };
+    use microclaw_channels::health::{ChannelHealth, SendFailurePolicy};
+    use microclaw_channels::inbound_queue::ChannelQueueStats;
+    use microclaw_core::llm_types::{ContentBlock, Message, MessageContent, ToolChoice};
+    use microclaw_tools::runtime::ToolAuthContext;
+    use microclaw_tools::types::WorkingDirIsolation;
 
     #[test]
     fn test_is_slash_command_with_leading_mentions() {
@@ -251,4 +907,196 @@ mod tests {
         assert!(is_slash_command(" <@U123>   @bot   /status"));
         assert!(!is_slash_command("@bot hello"));
     }
+
+    #[test]
+    fn test_build_delivery_line_lists_failing_channels() {
+        let health = ChannelHealth::new(SendFailurePolicy {
+            alert_threshold: 1,
+            ..SendFailurePolicy::default()
+        });
+        assert_eq!(build_delivery_line(&health), "Delivery: ok");
+        health.record_failure("feishu.work", "token revoked");
+        let line = build_delivery_line(&health);
+        assert!(line.contains("feishu.work"));
+        assert!(line.contains("token revoked"));
+    }
+
+    #[test]
+    fn test_build_queue_line_reports_backlog_and_drops() {
+        let stats = |channel: &str, depth, dropped| ChannelQueueStats {
+            channel: channel.to_string(),
+            depth,
+            in_flight: 1,
+            processed: 10,
+            dropped,
+        };
+        assert_eq!(
+            build_queue_line(&[stats("email", 0, 0)]),
+            "Inbound queues: idle"
+        );
+        assert_eq!(
+            build_queue_line(&[stats("email", 0, 0), stats("feishu.work", 4, 2)]),
+            "Inbound queues: feishu.work (pending=4, dropped=2)"
+        );
+    }
+
+    #[test]
+    fn test_parse_verbosity() {
+        assert_eq!(parse_verbosity("Short"), Some(Some(150)));
+        assert_eq!(parse_verbosity("250 tokens"), Some(Some(250)));
+        assert_eq!(parse_verbosity("off"), Some(Some(0)));
+        assert_eq!(parse_verbosity("default"), Some(None));
+        assert_eq!(parse_verbosity("5"), None);
+        assert_eq!(parse_verbosity("chatty"), None);
+        assert_eq!(describe_reply_limit(None), "no limit");
+    }
+
+    #[test]
+    fn test_build_tools_response() {
+        let tools = vec!["bash".to_string(), "web_search".to_string()];
+        assert!(
+            build_tools_response(Some(&ToolChoice::None), None, &tools).contains("tools disabled")
+        );
+        assert!(
+            build_tools_response(Some(&ToolChoice::Tool("web_search".into())), None, &tools)
+                .contains("`web_search`")
+        );
+        assert!(
+            build_tools_response(Some(&ToolChoice::Tool("nope".into())), None, &tools)
+                .starts_with("Unknown tool 'nope'")
+        );
+        assert!(
+            build_tools_response(None, Some(&ToolChoice::Required), &tools)
+                .starts_with("Tool choice for the next turn: required")
+        );
+        assert!(build_tools_response(None, None, &tools).starts_with("Tool choice: auto."));
+    }
+
+    #[test]
+    fn test_format_pinned_memories() {
+        assert!(format_pinned_memories(Vec::new()).starts_with("No pinned memories"));
+        let dir = std::env::temp_dir().join(format!("mc_pinned_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+        let global = db
+            .insert_memory(None, "deploys freeze on fridays", "KNOWLEDGE")
+            .unwrap();
+        let chat = db
+            .insert_memory(Some(7), "reply in British English", "PROFILE")
+            .unwrap();
+        db.set_memory_pinned(global, true).unwrap();
+        db.set_memory_pinned(chat, true).unwrap();
+        let pinned = db.get_pinned_memories(7).unwrap();
+        assert_eq!(
+            format_pinned_memories(pinned.iter().collect()),
+            format!(
+                "Pinned memories (included in every turn):\n#{global} [global] deploys freeze on fridays\n#{chat} [chat] reply in British English"
+            )
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_build_whoami_response_reports_role_and_control_chat() {
+        let mut auth = ToolAuthContext {
+            caller_channel: "feishu.work".into(),
+            caller_chat_id: 42,
+            control_chat_ids: vec![7],
+            caller_is_admin: false,
+            caller_sender_id: Some("ou_123".into()),
+            run_id: 0,
+        };
+        let text = build_whoami_response(&auth);
+        assert!(text.contains("Channel: feishu.work"));
+        assert!(text.contains("Chat ID: 42"));
+        assert!(text.contains("Sender: ou_123"));
+        assert!(text.contains("Role: member"));
+        assert!(text.contains("Control chat: no"));
+
+        auth.caller_is_admin = true;
+        let text = build_whoami_response(&auth);
+        assert!(text.contains("Role: admin"));
+        assert!(text.contains("Control chat: yes (admin sender)"));
+
+        auth.caller_chat_id = 7;
+        assert!(build_whoami_response(&auth).ends_with("Control chat: yes"));
+    }
+
+    #[test]
+    fn test_build_context_response_lists_active_skills() {
+        let messages = vec![
+            Message {
+                role: "assistant".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "activate_skill".into(),
+                    input: serde_json::json!({"skill_name": "pdf"}),
+                }]),
+            },
+            Message {
+                role: "user".into(),
+                content: MessageContent::Blocks(vec![ContentBlock::ToolResult {
+                    tool_use_id: "t1".into(),
+                    content: "PDF instructions".into(),
+                    is_error: None,
+                }]),
+            },
+        ];
+        let json = serde_json::to_string(&messages).unwrap();
+        let text = build_context_response("gpt-5", WorkingDirIsolation::Chat, Some(&json));
+        assert!(text.contains("Model: gpt-5"));
+        assert!(text.contains("Active skills: pdf"));
+        assert!(text.contains("Working dir isolation: chat"));
+        assert!(text.contains(&format!("Session: 2 messages, ~{} tokens", json.len() / 4)));
+
+        let text = build_context_response("gpt-5", WorkingDirIsolation::Shared, None);
+        assert!(text.contains("Active skills: none"));
+        assert!(text.ends_with("Session: 0 messages, ~0 tokens"));
+    }
+
+    #[test]
+    fn test_build_model_response_reports_skill_model() {
+        let mut config = crate::config::Config::test_defaults();
+        config.model = "gpt-5".into();
+        let overrides = std::collections::HashMap::new();
+        assert_eq!(
+            build_model_response(&config, &overrides, "web", None, "/model"),
+            "Current provider/model: anthropic / gpt-5"
+        );
+        assert_eq!(
+            build_model_response(&config, &overrides, "web", Some("heavy-model"), "/model"),
+            "Current provider/model: anthropic / heavy-model (set by an active skill; default gpt-5)"
+        );
+        assert_eq!(
+            build_model_response(&config, &overrides, "web", Some("gpt-5"), "/model"),
+            "Current provider/model: anthropic / gpt-5"
+        );
+    }
+
+    #[test]
+    fn test_reaction_action_mapping() {
+        assert_eq!(
+            ReactionAction::parse(" regenerate "),
+            Some(ReactionAction::Regenerate)
+        );
+        assert_eq!(
+            ReactionAction::parse("/usage"),
+            Some(ReactionAction::Command("/usage".into()))
+        );
+        assert_eq!(ReactionAction::parse("/"), None);
+        assert_eq!(ReactionAction::parse("explode"), None);
+
+        let map = std::collections::HashMap::from([
+            ("🗑️".to_string(), "delete".to_string()),
+            ("📌".to_string(), "remember".to_string()),
+            ("THUMBSUP".to_string(), "/status".to_string()),
+        ]);
+        assert_eq!(reaction_action(&map, "🗑"), Some(ReactionAction::Delete));
+        assert_eq!(reaction_action(&map, "📌"), Some(ReactionAction::Remember));
+        assert_eq!(
+            reaction_action(&map, "THUMBSUP"),
+            Some(ReactionAction::Command("/status".into()))
+        );
+        assert_eq!(reaction_action(&map, "🔁"), None);
+    }
 }
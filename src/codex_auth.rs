@@ -21,7 +21,7 @@ struct CodexAuthTokens {
     account_id: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CodexAuthResolved {
     pub bearer_token: String,
     pub account_id: Option<String>,
@@ -238,47 +238,104 @@ fn expand_tilde(input: &str) -> String {
     input.to_string()
 }
 
+const CODEX_OAUTH_CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
+const CODEX_REFRESH_TOKEN_URL: &str = "https://auth.openai.com/oauth/token";
+
 #[derive(Debug, Deserialize)]
 struct CodexRefreshResponse {
     access_token: String,
     refresh_token: Option<String>,
+    id_token: Option<String>,
 }
 
-pub fn refresh_openai_codex_auth_if_needed() -> Result<(), MicroClawError> {
-    let auth_path = default_codex_auth_path();
-    if !auth_path.exists() {
-        return Ok(());
-    }
-    let content = std::fs::read_to_string(&auth_path).map_err(|e| {
+fn codex_refresh_token_url() -> String {
+    std::env::var("CODEX_REFRESH_TOKEN_URL_OVERRIDE")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| CODEX_REFRESH_TOKEN_URL.to_string())
+}
+
+fn codex_login_required(reason: &str) -> MicroClawError {
+    MicroClawError::Config(format!(
+        "OpenAI Codex token refresh is not possible: {reason}. Run `codex login` to re-authenticate."
+    ))
+}
+
+fn read_codex_auth_value(auth_path: &Path) -> Result<serde_json::Value, MicroClawError> {
+    let content = std::fs::read_to_string(auth_path).map_err(|e| {
         MicroClawError::Config(format!(
             "Failed to read Codex auth file {}: {e}",
             auth_path.display()
         ))
     })?;
-    let mut parsed: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+    serde_json::from_str(&content).map_err(|e| {
         MicroClawError::Config(format!(
             "Failed to parse Codex auth file {}: {e}",
             auth_path.display()
         ))
-    })?;
+    })
+}
 
-    let tokens = parsed
+fn codex_token_field(parsed: &serde_json::Value, key: &str) -> String {
+    parsed
         .get("tokens")
-        .and_then(|t| t.as_object())
-        .cloned()
-        .unwrap_or_default();
-    let access = tokens
-        .get("access_token")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .trim()
-        .to_string();
-    let refresh = tokens
-        .get("refresh_token")
+        .and_then(|t| t.get(key))
         .and_then(|v| v.as_str())
         .unwrap_or("")
         .trim()
-        .to_string();
+        .to_string()
+}
+
+fn codex_refresh_body(refresh_token: &str) -> serde_json::Value {
+    serde_json::json!({
+        "grant_type": "refresh_token",
+        "refresh_token": refresh_token,
+        "client_id": CODEX_OAUTH_CLIENT_ID,
+    })
+}
+
+/// Merge refreshed tokens into the parsed auth file and write it back.
+fn persist_refreshed_tokens(
+    auth_path: &Path,
+    parsed: &mut serde_json::Value,
+    refreshed: CodexRefreshResponse,
+) -> Result<(), MicroClawError> {
+    if !parsed.get("tokens").is_some_and(|t| t.is_object()) {
+        parsed["tokens"] = serde_json::json!({});
+    }
+    if let Some(tokens_obj) = parsed.get_mut("tokens").and_then(|t| t.as_object_mut()) {
+        tokens_obj.insert(
+            "access_token".to_string(),
+            serde_json::Value::String(refreshed.access_token),
+        );
+        for (key, value) in [
+            ("refresh_token", refreshed.refresh_token),
+            ("id_token", refreshed.id_token),
+        ] {
+            if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+                tokens_obj.insert(key.to_string(), serde_json::Value::String(value));
+            }
+        }
+    }
+    parsed["last_refresh"] = serde_json::Value::String(chrono::Utc::now().to_rfc3339());
+    std::fs::write(
+        auth_path,
+        serde_json::to_string_pretty(parsed).map_err(|e| {
+            MicroClawError::Config(format!("Failed to serialize refreshed Codex auth: {e}"))
+        })?,
+    )?;
+    Ok(())
+}
+
+pub fn refresh_openai_codex_auth_if_needed() -> Result<(), MicroClawError> {
+    let auth_path = default_codex_auth_path();
+    if !auth_path.exists() {
+        return Ok(());
+    }
+    let mut parsed = read_codex_auth_value(&auth_path)?;
+    let access = codex_token_field(&parsed, "access_token");
+    let refresh = codex_token_field(&parsed, "refresh_token");
     if access.is_empty() || refresh.is_empty() {
         return Ok(());
     }
@@ -289,15 +346,10 @@ pub fn refresh_openai_codex_auth_if_needed() -> Result<(), MicroClawError> {
     let client = reqwest::blocking::Client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .build()?;
-    let body = serde_json::json!({
-        "grant_type": "refresh_token",
-        "refresh_token": refresh,
-        "client_id": "app_EMoamEEZ73f0CkXaXp7hrann",
-    });
     let resp = client
-        .post("https://auth.openai.com/oauth/token")
+        .post(codex_refresh_token_url())
         .header("content-type", "application/json")
-        .body(body.to_string())
+        .body(codex_refresh_body(&refresh).to_string())
         .send()?;
     if !resp.status().is_success() {
         return Ok(());
@@ -310,29 +362,82 @@ pub fn refresh_openai_codex_auth_if_needed() -> Result<(), MicroClawError> {
     if parsed_resp.access_token.trim().is_empty() {
         return Ok(());
     }
+    persist_refreshed_tokens(&auth_path, &mut parsed, parsed_resp)
+}
 
-    if let Some(tokens_obj) = parsed.get_mut("tokens").and_then(|t| t.as_object_mut()) {
-        tokens_obj.insert(
-            "access_token".to_string(),
-            serde_json::Value::String(parsed_resp.access_token),
-        );
-        if let Some(refresh_token) = parsed_resp.refresh_token {
-            if !refresh_token.trim().is_empty() {
-                tokens_obj.insert(
-                    "refresh_token".to_string(),
-                    serde_json::Value::String(refresh_token),
-                );
-            }
-        }
+/// Refresh the Codex OAuth access token using the refresh token in `auth.json`.
+///
+/// Without `force`, the token is only refreshed when its JWT `exp` has passed and
+/// `Ok(None)` is returned otherwise. With `force` (e.g. after an HTTP 401) the refresh
+/// always runs. The refreshed token is persisted back to `auth.json`. When refresh is
+/// impossible the error tells the user to run `codex login`.
+pub async fn refresh_openai_codex_auth(
+    force: bool,
+) -> Result<Option<CodexAuthResolved>, MicroClawError> {
+    if std::env::var("OPENAI_CODEX_ACCESS_TOKEN")
+        .map(|v| !v.trim().is_empty())
+        .unwrap_or(false)
+    {
+        return Err(codex_login_required(
+            "OPENAI_CODEX_ACCESS_TOKEN is set and cannot be refreshed automatically",
+        ));
     }
-    parsed["last_refresh"] = serde_json::Value::String(chrono::Utc::now().to_rfc3339());
-    std::fs::write(
-        &auth_path,
-        serde_json::to_string_pretty(&parsed).map_err(|e| {
-            MicroClawError::Config(format!("Failed to serialize refreshed Codex auth: {e}"))
-        })?,
-    )?;
-    Ok(())
+    let auth_path = default_codex_auth_path();
+    if !auth_path.exists() {
+        return Err(codex_login_required(&format!(
+            "{} not found",
+            auth_path.display()
+        )));
+    }
+    let mut parsed = read_codex_auth_value(&auth_path)?;
+    let access = codex_token_field(&parsed, "access_token");
+    let refresh = codex_token_field(&parsed, "refresh_token");
+    if !force && !access.is_empty() && !is_jwt_expired(&access) {
+        return Ok(None);
+    }
+    if refresh.is_empty() {
+        return Err(codex_login_required(&format!(
+            "no refresh_token in {}",
+            auth_path.display()
+        )));
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let resp = client
+        .post(codex_refresh_token_url())
+        .json(&codex_refresh_body(&refresh))
+        .send()
+        .await
+        .map_err(|e| codex_login_required(&format!("refresh request failed ({e})")))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(codex_login_required(&format!(
+            "refresh endpoint returned HTTP {status}"
+        )));
+    }
+    let refreshed: CodexRefreshResponse = resp.json().await.map_err(|e| {
+        MicroClawError::Config(format!(
+            "Failed to parse OpenAI Codex refresh response: {e}"
+        ))
+    })?;
+    if refreshed.access_token.trim().is_empty() {
+        return Err(codex_login_required(
+            "refresh endpoint returned an empty access token",
+        ));
+    }
+    let bearer_token = refreshed.access_token.trim().to_string();
+    persist_refreshed_tokens(&auth_path, &mut parsed, refreshed)?;
+    Ok(Some(CodexAuthResolved {
+        bearer_token,
+        account_id: Some(codex_token_field(&parsed, "account_id")).filter(|id| !id.is_empty()),
+    }))
+}
+
+/// Whether a Codex bearer token is a JWT whose `exp` claim has passed.
+pub fn codex_token_expired(token: &str) -> bool {
+    is_jwt_expired(token)
 }
 
 fn is_jwt_expired(token: &str) -> bool {
@@ -478,6 +583,59 @@ mod tests {
         assert!(auth.account_id.is_none());
     }
 
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn test_refresh_openai_codex_auth_without_refresh_token_asks_for_login() {
+        let _guard = env_lock();
+        let prev_codex_home = std::env::var("CODEX_HOME").ok();
+        let prev_access = std::env::var("OPENAI_CODEX_ACCESS_TOKEN").ok();
+        std::env::remove_var("OPENAI_CODEX_ACCESS_TOKEN");
+
+        let auth_dir = std::env::temp_dir().join(format!(
+            "microclaw-codex-auth-no-refresh-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&auth_dir).unwrap();
+        std::fs::write(
+            auth_dir.join("auth.json"),
+            r#"{"tokens":{"access_token":"expired"}}"#,
+        )
+        .unwrap();
+        std::env::set_var("CODEX_HOME", &auth_dir);
+
+        let result = refresh_openai_codex_auth(true).await;
+
+        if let Some(prev) = prev_codex_home {
+            std::env::set_var("CODEX_HOME", prev);
+        } else {
+            std::env::remove_var("CODEX_HOME");
+        }
+        if let Some(prev) = prev_access {
+            std::env::set_var("OPENAI_CODEX_ACCESS_TOKEN", prev);
+        }
+        let _ = std::fs::remove_file(auth_dir.join("auth.json"));
+        let _ = std::fs::remove_dir(auth_dir);
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("codex login"), "{err}");
+    }
+
+    #[test]
+    fn test_is_jwt_expired_reads_exp_claim() {
+        let encode = |payload: &str| {
+            format!(
+                "h.{}.s",
+                base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(payload)
+            )
+        };
+        assert!(codex_token_expired(&encode(r#"{"exp":1}"#)));
+        assert!(!codex_token_expired(&encode(r#"{"exp":99999999999}"#)));
+        assert!(!codex_token_expired("not-a-jwt"));
+    }
+
     #[test]
     fn test_parse_codex_config_default_openai_base_url() {
         let content = r#"
@@ -8,11 +8,24 @@ use crate::codex_auth::{
     codex_auth_file_has_access_token, is_openai_codex_provider, provider_allows_empty_api_key,
 };
 use crate::plugins::PluginsConfig;
+use crate::vector_store::QdrantConfig;
+use microclaw_channels::channel_adapter::ReplyDecoration;
+use microclaw_channels::inbound_queue::QueueDropPolicy;
+use microclaw_channels::quiet_hours::QuietHours;
 use microclaw_core::error::MicroClawError;
+pub use microclaw_tools::error_hints::ToolErrorHintsConfig;
+use microclaw_tools::http_request::HttpRequestAuthConfig;
+pub use microclaw_tools::input_coercion::ToolInputCoercionConfig;
+pub use microclaw_tools::input_defaults::ToolInputDefaults;
+pub use microclaw_tools::redaction::ToolResultRedactionConfig;
 pub use microclaw_tools::sandbox::{SandboxBackend, SandboxConfig, SandboxMode, SecurityProfile};
-pub use microclaw_tools::types::WorkingDirIsolation;
+pub use microclaw_tools::types::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_tools::web_content_validation::WebContentValidationConfig;
-use microclaw_tools::web_fetch::WebFetchUrlValidationConfig;
+use microclaw_tools::web_domains::WebDomainPolicy;
+use microclaw_tools::web_fetch::{
+    WebFetchCacheConfig, WebFetchHostRateConfig, WebFetchMarkdownConfig, WebFetchProxyConfig,
+    WebFetchUrlValidationConfig,
+};
 
 fn default_bot_username() -> String {
     String::new()
@@ -29,6 +42,14 @@ fn default_model() -> String {
 fn default_max_tokens() -> u32 {
     8192
 }
+fn default_deterministic_seed() -> u64 {
+    42
+}
+/// Smallest `budget_tokens` the Anthropic API accepts.
+const MIN_ANTHROPIC_THINKING_BUDGET_TOKENS: u32 = 1024;
+fn default_anthropic_thinking_budget_tokens() -> u32 {
+    4096
+}
 fn default_max_tool_iterations() -> usize {
     100
 }
@@ -44,6 +65,9 @@ fn default_max_document_size_mb() -> u64 {
 fn default_memory_token_budget() -> usize {
     1500
 }
+fn default_memory_pinned_token_budget() -> usize {
+    300
+}
 fn default_data_dir() -> String {
     default_data_root().to_string_lossy().to_string()
 }
@@ -71,6 +95,15 @@ fn default_working_dir_isolation() -> WorkingDirIsolation {
 fn default_high_risk_tool_user_confirmation_required() -> bool {
     true
 }
+fn default_bash_no_tty() -> bool {
+    true
+}
+fn default_ask_user_timeout_secs() -> u64 {
+    600
+}
+fn default_tool_audit_log() -> bool {
+    true
+}
 fn default_sandbox_image() -> String {
     "ubuntu:25.10".into()
 }
@@ -89,6 +122,12 @@ fn default_compact_keep_recent() -> usize {
 fn default_tool_timeout_secs() -> u64 {
     30
 }
+fn default_wait_tool_max_secs() -> u64 {
+    60
+}
+fn default_web_search_cache_ttl_secs() -> u64 {
+    300
+}
 fn default_mcp_request_timeout_secs() -> u64 {
     120
 }
@@ -98,6 +137,34 @@ fn default_control_chat_ids() -> Vec<i64> {
 fn default_allow_group_slash_without_mention() -> bool {
     false
 }
+fn default_reaction_commands() -> HashMap<String, String> {
+    HashMap::from([
+        ("🔁".to_string(), "regenerate".to_string()),
+        ("📌".to_string(), "remember".to_string()),
+        ("🗑".to_string(), "delete".to_string()),
+        // Feishu reports reactions by `emoji_type` name instead of Unicode.
+        ("ThumbsDown".to_string(), "regenerate".to_string()),
+        ("Pin".to_string(), "remember".to_string()),
+    ])
+}
+fn default_channel_send_failure_alert_threshold() -> u32 {
+    3
+}
+fn default_llm_circuit_breaker_threshold() -> u32 {
+    5
+}
+fn default_llm_circuit_breaker_cooldown_secs() -> u64 {
+    30
+}
+fn default_channel_queue_capacity() -> usize {
+    100
+}
+fn default_channel_queue_drop_policy() -> String {
+    "drop_oldest".into()
+}
+fn default_channel_queue_max_concurrency() -> usize {
+    8
+}
 
 fn default_model_prices() -> Vec<ModelPrice> {
     Vec::new()
@@ -108,6 +175,12 @@ fn default_reflector_enabled() -> bool {
 fn default_reflector_interval_mins() -> u64 {
     15
 }
+fn default_memory_backend() -> String {
+    "sqlite".into()
+}
+fn default_memory_dedup_similarity_threshold() -> f64 {
+    0.85
+}
 fn default_soul_path() -> Option<String> {
     None
 }
@@ -183,6 +256,330 @@ impl Default for LoggingConfig {
     }
 }
 
+/// SQLCipher encryption at rest for the runtime database. The key is never
+/// stored in config: it comes from an environment variable or a key file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DbEncryptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Environment variable holding the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_env: Option<String>,
+    /// File whose (trimmed) contents are the key; used when `key_env` is unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
+}
+
+/// Per-context overrides for `working_dir_isolation`; unset entries use the preset.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkingDirIsolationOverrides {
+    /// Isolation for chats listed in `control_chat_ids`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_chats: Option<WorkingDirIsolation>,
+    /// Isolation for all other chats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<WorkingDirIsolation>,
+}
+
+/// Models used by specific LLM call sites; unset roles use the top-level `model`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelRoleOverrides {
+    /// User-facing agent turns. A channel account's own `model` still wins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main: Option<String>,
+    /// Background memory reflector.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflector: Option<String>,
+    /// `sub_agent` tool runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub_agent: Option<String>,
+    /// Session compaction summaries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelRole {
+    Main,
+    Reflector,
+    SubAgent,
+    Compaction,
+}
+
+impl ModelRoleOverrides {
+    pub fn get(&self, role: ModelRole) -> Option<&str> {
+        match role {
+            ModelRole::Main => self.main.as_deref(),
+            ModelRole::Reflector => self.reflector.as_deref(),
+            ModelRole::SubAgent => self.sub_agent.as_deref(),
+            ModelRole::Compaction => self.compaction.as_deref(),
+        }
+    }
+
+    fn normalize(&mut self) {
+        for model in [
+            &mut self.main,
+            &mut self.reflector,
+            &mut self.sub_agent,
+            &mut self.compaction,
+        ] {
+            *model = model
+                .take()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty());
+        }
+    }
+}
+
+/// Capability flags set for models matching a `model_capabilities` pattern.
+/// Unset flags keep what the built-in registry says.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilityOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vision: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_mode: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_toggle: Option<bool>,
+}
+
+/// Sampling parameters for LLM requests.
+///
+/// Each field is tri-state: omitted inherits from the broader scope, `null`
+/// unsets a value inherited from a broader scope, and a value overrides it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplingParams {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub temperature: Option<Option<f64>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub top_p: Option<Option<f64>>,
+    /// Output token limit. Unset falls back to the top-level `max_tokens`.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_tokens: Option<Option<u32>>,
+    /// `minimal`, `low`, `medium` or `high`. Only sent to OpenAI-compatible
+    /// models that reason (`reasoning_effort` or `reasoning.effort`).
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reasoning_effort: Option<Option<String>>,
+    /// `low`, `medium` or `high` answer length. Only sent to models that accept
+    /// it (`verbosity` or `text.verbosity`, the GPT-5 family).
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub verbosity: Option<Option<String>>,
+    /// Turn extended thinking on or off: Anthropic's `thinking` block (with
+    /// `anthropic_thinking_budget_tokens`) or the `thinking` flag of models that
+    /// accept one (DeepSeek, GLM). Unset follows `anthropic_thinking`.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub thinking: Option<Option<bool>>,
+    /// Sequences that end generation. Sent as `stop` (OpenAI-compatible chat) or
+    /// `stop_sequences` (Anthropic); the Responses API has no equivalent.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stop: Option<Option<Vec<String>>>,
+    /// `text` or `json_object`. Providers without a native JSON mode get a
+    /// JSON-only instruction appended to the system prompt instead.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub response_format: Option<Option<ResponseFormat>>,
+    /// Sampling seed for reproducible output. Sent as `seed` to OpenAI-compatible
+    /// chat providers; Anthropic and the Responses API have no equivalent.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub seed: Option<Option<u64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    JsonObject,
+}
+
+const REASONING_EFFORT_VALUES: &[&str] = &["minimal", "low", "medium", "high"];
+const VERBOSITY_VALUES: &[&str] = &["low", "medium", "high"];
+
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl SamplingParams {
+    /// Layer `other` on top of `self`; fields present in `other` (including `null`) win.
+    pub fn merged_with(&self, other: &SamplingParams) -> SamplingParams {
+        SamplingParams {
+            temperature: other.temperature.or(self.temperature),
+            top_p: other.top_p.or(self.top_p),
+            max_tokens: other.max_tokens.or(self.max_tokens),
+            reasoning_effort: other
+                .reasoning_effort
+                .clone()
+                .or_else(|| self.reasoning_effort.clone()),
+            verbosity: other.verbosity.clone().or_else(|| self.verbosity.clone()),
+            thinking: other.thinking.or(self.thinking),
+            stop: other.stop.clone().or_else(|| self.stop.clone()),
+            response_format: other.response_format.or(self.response_format),
+            seed: other.seed.or(self.seed),
+        }
+    }
+
+    /// Per-call overrides that request a JSON object response.
+    pub fn json_object() -> SamplingParams {
+        SamplingParams {
+            response_format: Some(Some(ResponseFormat::JsonObject)),
+            ..SamplingParams::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &SamplingParams::default()
+    }
+
+    pub fn temperature(&self) -> Option<f64> {
+        self.temperature.flatten()
+    }
+
+    pub fn top_p(&self) -> Option<f64> {
+        self.top_p.flatten()
+    }
+
+    pub fn max_tokens(&self) -> Option<u32> {
+        self.max_tokens.flatten()
+    }
+
+    pub fn reasoning_effort(&self) -> Option<&str> {
+        self.reasoning_effort.as_ref().and_then(|v| v.as_deref())
+    }
+
+    pub fn verbosity(&self) -> Option<&str> {
+        self.verbosity.as_ref().and_then(|v| v.as_deref())
+    }
+
+    pub fn thinking(&self) -> Option<bool> {
+        self.thinking.flatten()
+    }
+
+    pub fn stop(&self) -> Option<&[String]> {
+        self.stop
+            .as_ref()
+            .and_then(|v| v.as_deref())
+            .filter(|v| !v.is_empty())
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed.flatten()
+    }
+
+    pub fn json_mode(&self) -> bool {
+        self.response_format.flatten() == Some(ResponseFormat::JsonObject)
+    }
+
+    fn normalize(&mut self, scope: &str) -> Result<(), MicroClawError> {
+        if let Some(Some(v)) = self.temperature {
+            if !(v.is_finite() && (0.0..=2.0).contains(&v)) {
+                return Err(MicroClawError::Config(format!(
+                    "{scope}.temperature must be between 0 and 2"
+                )));
+            }
+        }
+        if let Some(Some(v)) = self.top_p {
+            if !(v.is_finite() && v > 0.0 && v <= 1.0) {
+                return Err(MicroClawError::Config(format!(
+                    "{scope}.top_p must be greater than 0 and at most 1"
+                )));
+            }
+        }
+        if let Some(Some(0)) = self.max_tokens {
+            return Err(MicroClawError::Config(format!(
+                "{scope}.max_tokens must be greater than 0"
+            )));
+        }
+        if let Some(Some(effort)) = self.reasoning_effort.as_mut() {
+            *effort = effort.trim().to_ascii_lowercase();
+            if !REASONING_EFFORT_VALUES.contains(&effort.as_str()) {
+                return Err(MicroClawError::Config(format!(
+                    "{scope}.reasoning_effort must be one of: {}",
+                    REASONING_EFFORT_VALUES.join(", ")
+                )));
+            }
+        }
+        if let Some(Some(verbosity)) = self.verbosity.as_mut() {
+            *verbosity = verbosity.trim().to_ascii_lowercase();
+            if !VERBOSITY_VALUES.contains(&verbosity.as_str()) {
+                return Err(MicroClawError::Config(format!(
+                    "{scope}.verbosity must be one of: {}",
+                    VERBOSITY_VALUES.join(", ")
+                )));
+            }
+        }
+        if let Some(Some(stop)) = self.stop.as_ref() {
+            if stop.iter().any(|s| s.is_empty()) {
+                return Err(MicroClawError::Config(format!(
+                    "{scope}.stop must not contain empty sequences"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolve sampling parameters with precedence global < provider < model.
+pub fn resolve_sampling_params(
+    global: &SamplingParams,
+    by_provider: &HashMap<String, SamplingParams>,
+    by_model: &HashMap<String, SamplingParams>,
+    provider: &str,
+    model: &str,
+) -> SamplingParams {
+    let mut resolved = global.clone();
+    if let Some(p) = by_provider.get(&provider.to_ascii_lowercase()) {
+        resolved = resolved.merged_with(p);
+    }
+    if let Some(m) = by_model.get(model) {
+        resolved = resolved.merged_with(m);
+    }
+    resolved
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ModelPrice {
     pub model: String,
@@ -199,20 +596,71 @@ pub struct Config {
     pub api_key: String,
     #[serde(default = "default_model")]
     pub model: String,
+    /// Per-role models (`main`, `reflector`, `sub_agent`, `compaction`).
+    #[serde(default)]
+    pub model_roles: ModelRoleOverrides,
     #[serde(default)]
     pub llm_base_url: Option<String>,
+    /// Tag provider requests with the agent turn's correlation id (OpenAI `user`,
+    /// Anthropic `metadata.user_id`, and an `x-client-request-id` header).
+    #[serde(default = "default_true")]
+    pub llm_correlation_id: bool,
+    /// Send `tool_choice` natively to OpenAI-compatible backends. When false (and
+    /// always for Ollama) a forced choice is stated in the system prompt instead.
+    #[serde(default = "default_true")]
+    pub llm_native_tool_choice: bool,
+    /// Whether the model accepts image input. When false, images users send are
+    /// replaced with a text note (their saved files stay available to tools).
+    #[serde(default = "default_true")]
+    pub llm_vision: bool,
+    /// Consecutive provider failures (network errors, 429, 5xx) before LLM calls
+    /// fail fast for `llm_circuit_breaker_cooldown_secs`. 0 disables the breaker.
+    #[serde(default = "default_llm_circuit_breaker_threshold")]
+    pub llm_circuit_breaker_threshold: u32,
+    /// How long an open breaker rejects calls before letting one probe through.
+    #[serde(default = "default_llm_circuit_breaker_cooldown_secs")]
+    pub llm_circuit_breaker_cooldown_secs: u64,
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
+    /// Reply length limit in tokens: the model is told to stay within it and
+    /// agent-loop calls are capped at it. `channels.<name>` (and its accounts)
+    /// and the per-chat `/verbosity` command override it; 0 means no limit.
+    #[serde(default)]
+    pub max_response_tokens: Option<u32>,
     #[serde(default = "default_max_tool_iterations")]
     pub max_tool_iterations: usize,
+    /// Let an active skill's `max_tool_iterations` exceed `max_tool_iterations`;
+    /// otherwise a skill can only lower the budget.
+    #[serde(default)]
+    pub allow_skill_budget_increase: bool,
     #[serde(default = "default_compaction_timeout_secs")]
     pub compaction_timeout_secs: u64,
     #[serde(default = "default_max_history_messages")]
     pub max_history_messages: usize,
+    /// In group chats, build each turn's history and structured-memory context
+    /// from the current sender's messages only.
+    #[serde(default)]
+    pub group_user_isolation: bool,
     #[serde(default = "default_max_document_size_mb")]
     pub max_document_size_mb: u64,
+    /// Also show the first image a user sends to the model as a vision input,
+    /// besides saving it to the chat's working dir.
+    #[serde(default = "default_true")]
+    pub attachment_image_vision: bool,
     #[serde(default = "default_memory_token_budget")]
     pub memory_token_budget: usize,
+    /// Inject the structured memories most relevant to the incoming message
+    /// into each turn's system prompt. The memory tools work either way.
+    #[serde(default = "default_true")]
+    pub memory_auto_inject: bool,
+    /// Most memories injected per turn, within `memory_token_budget`; 0 means
+    /// only the budget limits it.
+    #[serde(default)]
+    pub memory_auto_inject_top_k: usize,
+    /// Tokens reserved for pinned memories, injected every turn ahead of (and
+    /// outside) `memory_token_budget`; 0 stops injecting them.
+    #[serde(default = "default_memory_pinned_token_budget")]
+    pub memory_pinned_token_budget: usize,
     #[serde(default = "default_max_session_messages")]
     pub max_session_messages: usize,
     #[serde(default = "default_compact_keep_recent")]
@@ -221,10 +669,27 @@ pub struct Config {
     pub default_tool_timeout_secs: u64,
     #[serde(default)]
     pub tool_timeout_overrides: HashMap<String, u64>,
+    /// Upper bound for `default_tool_timeout_secs` and every override; 0 disables the bound.
+    #[serde(default)]
+    pub max_tool_timeout_secs: u64,
+    /// Wall-clock budget for one `sub_agent` call; 0 (the default) disables the bound.
+    #[serde(default)]
+    pub sub_agent_max_duration_secs: u64,
+    /// Input plus output tokens one `sub_agent` call may consume; 0 disables the bound.
+    #[serde(default)]
+    pub sub_agent_max_tokens: u64,
+    /// Longest single `wait` tool call; longer requests are capped to it.
+    #[serde(default = "default_wait_tool_max_secs")]
+    pub wait_tool_max_secs: u64,
     #[serde(default = "default_mcp_request_timeout_secs")]
     pub default_mcp_request_timeout_secs: u64,
+    /// Show model reasoning: keep inline `<think>` blocks in replies and send a short
+    /// summary of separate thinking blocks as progress. Off by default.
     #[serde(default)]
     pub show_thinking: bool,
+    /// Write model reasoning to the debug log. It is never sent to channels.
+    #[serde(default)]
+    pub log_thinking: bool,
     /// OpenAI-compatible request-body overrides applied for all models/providers.
     /// Set a key to `null` to remove that field from the outgoing JSON body.
     #[serde(default)]
@@ -237,36 +702,179 @@ pub struct Config {
     /// OpenAI-compatible request-body overrides keyed by model name.
     #[serde(default)]
     pub openai_compat_body_overrides_by_model: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// Sampling parameters (`temperature`, `top_p`, `max_tokens`, `reasoning_effort`,
+    /// `verbosity`, `thinking`, ...) applied to every request. Set a key to `null`
+    /// to unset it. These win over `openai_compat_body_overrides` for the same knob.
+    #[serde(default)]
+    pub sampling_params: SamplingParams,
+    /// Sampling parameters keyed by provider name; override `sampling_params`.
+    #[serde(default)]
+    pub sampling_params_by_provider: HashMap<String, SamplingParams>,
+    /// Sampling parameters keyed by model name; override provider and global values.
+    #[serde(default)]
+    pub sampling_params_by_model: HashMap<String, SamplingParams>,
+    /// Capability overrides keyed by model glob (`*` matches any run), for
+    /// models the built-in registry does not know, such as Azure deployment
+    /// names. The longest matching pattern wins.
+    #[serde(default)]
+    pub model_capabilities: HashMap<String, ModelCapabilityOverride>,
+    /// Reproducibility mode: force temperature 0 and `deterministic_seed` at every
+    /// sampling scope. Providers without seed support only get temperature 0.
+    #[serde(default)]
+    pub deterministic: bool,
+    #[serde(default = "default_deterministic_seed")]
+    pub deterministic_seed: u64,
+    /// Anthropic extended thinking: requests to the `anthropic` provider carry a
+    /// `thinking` block with `anthropic_thinking_budget_tokens`.
+    #[serde(default)]
+    pub anthropic_thinking: bool,
+    /// Reasoning token budget; at least 1024 and below `max_tokens`.
+    #[serde(default = "default_anthropic_thinking_budget_tokens")]
+    pub anthropic_thinking_budget_tokens: u32,
+    /// Let the model also think between tool calls (Anthropic interleaved-thinking beta).
+    #[serde(default)]
+    pub anthropic_interleaved_thinking: bool,
 
     // --- Paths & environment ---
     #[serde(default = "default_data_dir")]
     pub data_dir: String,
+    /// Encrypt the runtime database with SQLCipher (requires the `sqlcipher` feature).
+    #[serde(default)]
+    pub db_encryption: DbEncryptionConfig,
     #[serde(default)]
     pub skills_dir: Option<String>,
+    /// Re-inject a skill's instructions mid-session when its SKILL.md changes after activation.
+    #[serde(default)]
+    pub skills_live_refresh: bool,
     #[serde(default = "default_working_dir")]
     pub working_dir: String,
+    /// Preset isolation applied to every chat unless `working_dir_isolation_policy` overrides it.
     #[serde(default = "default_working_dir_isolation")]
     pub working_dir_isolation: WorkingDirIsolation,
+    #[serde(default)]
+    pub working_dir_isolation_policy: WorkingDirIsolationOverrides,
     #[serde(default = "default_high_risk_tool_user_confirmation_required")]
     pub high_risk_tool_user_confirmation_required: bool,
+    /// Let turns without a triggering user (scheduled runs) message other chats
+    /// and create scheduled tasks. Off: the bot only speaks when spoken to or
+    /// when a user-created task fires, and only in that task's chat.
+    #[serde(default)]
+    pub allow_proactive_messaging: bool,
+    /// How long a turn paused by `ask_user` waits for the reply. A later
+    /// message resumes the turn only within this window; after it the pending
+    /// question is cancelled and the message starts fresh.
+    #[serde(default = "default_ask_user_timeout_secs")]
+    pub ask_user_timeout_secs: u64,
+    /// Run bash commands without a terminal: non-interactive env defaults
+    /// (DEBIAN_FRONTEND, GIT_TERMINAL_PROMPT, PAGER) and a hint when a timeout
+    /// looks like a command blocked on input.
+    #[serde(default = "default_bash_no_tty")]
+    pub bash_no_tty: bool,
+    /// Shell the bash tool runs command strings with (`sh`, `bash`, or a path).
+    /// Unset uses `$SHELL` (or `/bin/sh`) on the host and `sh` in the sandbox.
+    #[serde(default)]
+    pub bash_shell: Option<String>,
+    /// When non-empty, `bash` only runs these programs (bare names, or exact
+    /// paths) and refuses command strings with `;`, `|`, `&`, backticks or
+    /// `$(...)`. Empty disables the allowlist.
+    #[serde(default)]
+    pub bash_command_allowlist: Vec<String>,
+    /// Record every tool execution (chat, tool, redacted input, risk, approval,
+    /// outcome, duration) in the append-only `tool_audit_logs` table.
+    /// Query it with `microclaw audit`.
+    #[serde(default = "default_tool_audit_log")]
+    pub tool_audit_log: bool,
+    /// Refuse to start when the startup self-test finds a tool's external
+    /// dependency missing (sandbox runtime, agent-browser, search backend).
+    /// When false, affected tools are disabled with a warning instead.
+    #[serde(default)]
+    pub fail_fast_on_missing_tool_deps: bool,
     #[serde(default)]
     pub sandbox: SandboxConfig,
     #[serde(default = "default_timezone")]
     pub timezone: String,
     #[serde(default = "default_control_chat_ids")]
     pub control_chat_ids: Vec<i64>,
+    /// Named sender lists that channel `allowed_user_ids` / `admin_user_ids`
+    /// entries can reference as `group:<name>`.
+    #[serde(default)]
+    pub user_groups: HashMap<String, Vec<String>>,
     #[serde(default = "default_allow_group_slash_without_mention")]
     pub allow_group_slash_without_mention: bool,
+    /// Emoji reactions on the bot's messages that trigger an action:
+    /// `regenerate`, `remember`, `delete`, or a slash command such as `/usage`.
+    /// Keys are emoji as the channel reports them. Empty disables reactions.
+    #[serde(default = "default_reaction_commands")]
+    pub reaction_commands: HashMap<String, String>,
+    /// Consecutive send failures before a channel account is flagged as failing and
+    /// control chats are alerted. 0 disables delivery health alerts.
+    #[serde(default = "default_channel_send_failure_alert_threshold")]
+    pub channel_send_failure_alert_threshold: u32,
+    /// Seconds to pause agent processing on a failing channel account after its last
+    /// send failure. 0 keeps processing messages.
+    #[serde(default)]
+    pub channel_send_failure_backoff_secs: u64,
+    /// Pending inbound messages kept per channel account before the drop policy applies.
+    /// 0 means unbounded.
+    #[serde(default = "default_channel_queue_capacity")]
+    pub channel_queue_capacity: usize,
+    /// What to discard when a channel queue is full: "drop_oldest" or "drop_newest".
+    #[serde(default = "default_channel_queue_drop_policy")]
+    pub channel_queue_drop_policy: String,
+    /// Inbound messages processed concurrently across all channels, shared round-robin.
+    #[serde(default = "default_channel_queue_max_concurrency")]
+    pub channel_queue_max_concurrency: usize,
+    /// Run one turn at a time per chat: a message arriving mid-turn waits for the
+    /// running turn to finish. `channels.<name>` (and its accounts) override it.
+    #[serde(default)]
+    pub serialize_per_chat: bool,
+    /// Secret redaction applied to tool results before they reach the model and logs.
+    #[serde(default)]
+    pub tool_result_redaction: ToolResultRedactionConfig,
+    /// Guidance appended to failed tool results (by `error_type`) before the model sees them.
+    #[serde(default)]
+    pub tool_error_hints: ToolErrorHintsConfig,
+    /// Tools whose inputs get lenient schema coercion (e.g. `"5"` -> `5`) before execution.
+    #[serde(default)]
+    pub tool_input_coercion: ToolInputCoercionConfig,
+    /// Default arguments per tool, merged under the model's input before
+    /// execution; a value the model passes wins.
+    #[serde(default)]
+    pub tool_input_defaults: ToolInputDefaults,
 
     // --- Web Fetch ---
     #[serde(default)]
     pub web_fetch_validation: WebContentValidationConfig,
     #[serde(default)]
     pub web_fetch_url_validation: WebFetchUrlValidationConfig,
+    /// Page cache for `web_fetch`; stale entries are revalidated with ETag/Last-Modified.
+    #[serde(default)]
+    pub web_fetch_cache: WebFetchCacheConfig,
+    /// Outbound proxy for `web_fetch` (HTTP or SOCKS5); falls back to the proxy env vars.
+    #[serde(default)]
+    pub web_fetch_proxy: WebFetchProxyConfig,
+    /// Markdown rendering for `web_fetch` (`auto` uses it on article-like pages).
+    #[serde(default)]
+    pub web_fetch_markdown: WebFetchMarkdownConfig,
+    /// Per-host request pacing for `web_fetch`; requests over the limit queue.
+    #[serde(default)]
+    pub web_fetch_per_host_rate: WebFetchHostRateConfig,
+    /// Headers `http_request` adds per host (and subdomains), e.g. API tokens.
+    #[serde(default)]
+    pub http_request_auth: HttpRequestAuthConfig,
 
     // --- Web Search ---
     #[serde(default)]
     pub web_search: WebSearchConfig,
+    /// Seconds a `web_search` result is reused for an identical query; 0 disables the cache.
+    #[serde(default = "default_web_search_cache_ttl_secs")]
+    pub web_search_cache_ttl_secs: u64,
+    /// Domains `web_fetch` and `web_search` may reach (subdomains included); empty allows all.
+    #[serde(default)]
+    pub web_domain_allowlist: Vec<String>,
+    /// Domains `web_fetch` refuses and `web_search` drops from results; wins over the allowlist.
+    #[serde(default)]
+    pub web_domain_denylist: Vec<String>,
 
     // --- Embedding ---
     #[serde(default)]
@@ -291,6 +899,16 @@ pub struct Config {
     pub reflector_enabled: bool,
     #[serde(default = "default_reflector_interval_mins")]
     pub reflector_interval_mins: u64,
+    /// Cosine similarity at or above which a new memory counts as a near-duplicate of an
+    /// existing one (needs embeddings). Higher values keep more nuance.
+    #[serde(default = "default_memory_dedup_similarity_threshold")]
+    pub memory_dedup_similarity_threshold: f64,
+    /// Where memory embeddings are indexed: `sqlite` (sqlite-vec, when built with it)
+    /// or `qdrant` (see `qdrant`). Memory rows always stay in SQLite.
+    #[serde(default = "default_memory_backend")]
+    pub memory_backend: String,
+    #[serde(default)]
+    pub qdrant: QdrantConfig,
 
     // --- Soul ---
     /// Path to a SOUL.md file that defines the bot's personality, voice, and values.
@@ -327,6 +945,15 @@ pub struct Config {
     #[serde(default = "default_send_tool_hints")]
     pub send_tool_hints: bool,
 
+    // --- Reply branding ---
+    /// Text put before every bot reply. `channels.<name>` and its `accounts.<id>` entries can
+    /// override it. `{bot_username}`, `{model}` and `{channel}` are filled in.
+    #[serde(default)]
+    pub reply_prefix: String,
+    /// Text put after every bot reply (e.g. a disclaimer); overridable like `reply_prefix`.
+    #[serde(default)]
+    pub reply_suffix: String,
+
     // --- Channel registry (new dynamic config) ---
     /// Per-channel configuration. Keys are channel names (e.g. "feishu", "web").
     /// Each value is channel-specific config deserialized by the adapter.
@@ -340,6 +967,24 @@ pub struct Config {
     pub allowed_groups: Vec<i64>,
 }
 
+/// Tool-keyed config entries that match no built-in or `extra_known` tool.
+/// `mcp_*` keys are skipped since MCP tools are only known after servers connect.
+fn unknown_tool_names<'a>(
+    keys: impl Iterator<Item = &'a String>,
+    extra_known: &[String],
+) -> Vec<String> {
+    let mut unknown: Vec<String> = keys
+        .filter(|key| {
+            !key.starts_with("mcp_")
+                && !crate::tools::BUILTIN_TOOL_NAMES.contains(&key.as_str())
+                && !extra_known.iter().any(|k| k.eq_ignore_ascii_case(key))
+        })
+        .cloned()
+        .collect();
+    unknown.sort();
+    unknown
+}
+
 impl Config {
     fn channel_default_account_id(&self, channel: &str) -> Option<String> {
         let channel_cfg = self.channels.get(channel)?;
@@ -414,6 +1059,190 @@ impl Config {
         }
     }
 
+    /// Base channel and account of a registry channel name; a bare channel
+    /// name means its default account.
+    fn split_channel_account<'a>(&self, channel: &'a str) -> (&'a str, Option<String>) {
+        match channel.split_once('.') {
+            Some((base, account)) => (base, Some(account.to_string())),
+            None => (channel, self.channel_default_account_id(channel)),
+        }
+    }
+
+    /// `key` for a registry channel name (`feishu` or `feishu.<account>`): the
+    /// account's value, then the channel's. Blank values count as unset.
+    fn channel_scoped_setting(&self, channel: &str, key: &str) -> Option<String> {
+        let (base_channel, account_id) = self.split_channel_account(channel);
+        let channel_cfg = self.channels.get(base_channel)?;
+        let non_blank = |v: Option<&serde_yaml::Value>| {
+            v.and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToOwned::to_owned)
+        };
+        account_id
+            .and_then(|id| {
+                non_blank(
+                    channel_cfg
+                        .get("accounts")
+                        .and_then(|v| v.get(id.as_str()))
+                        .and_then(|v| v.get(key)),
+                )
+            })
+            .or_else(|| non_blank(channel_cfg.get(key)))
+    }
+
+    /// Reply prefix/suffix for a registry channel name, with `{bot_username}`,
+    /// `{model}` and `{channel}` filled in.
+    pub fn reply_decoration_for_channel(&self, channel: &str, model: &str) -> ReplyDecoration {
+        let bot_username = self.bot_username_for_channel(channel);
+        let render = |key: &str, global: &str| {
+            let template = self
+                .channel_scoped_setting(channel, key)
+                .unwrap_or_else(|| global.trim().to_string());
+            template
+                .replace("{bot_username}", &bot_username)
+                .replace("{model}", model)
+                .replace("{channel}", channel)
+        };
+        ReplyDecoration {
+            prefix: render("reply_prefix", &self.reply_prefix),
+            suffix: render("reply_suffix", &self.reply_suffix),
+        }
+    }
+
+    /// Tool working-dir root for a registry channel name: `working_dir` from the
+    /// channel's account or the channel itself, else the global `working_dir`.
+    pub fn working_dir_for_channel(&self, channel: &str) -> PathBuf {
+        PathBuf::from(
+            self.channel_scoped_setting(channel, "working_dir")
+                .unwrap_or_else(|| self.working_dir.clone()),
+        )
+    }
+
+    /// `quiet_hours` (`start`/`end` as `HH:MM`, optional `timezone`) for a
+    /// registry channel name: the account's window, else the channel's. The
+    /// window's timezone defaults to the global `timezone`.
+    pub fn quiet_hours_for_channel(&self, channel: &str) -> Result<Option<QuietHours>, String> {
+        let (base_channel, account_id) = self.split_channel_account(channel);
+        let Some(channel_cfg) = self.channels.get(base_channel) else {
+            return Ok(None);
+        };
+        let value = account_id
+            .and_then(|id| {
+                channel_cfg
+                    .get("accounts")
+                    .and_then(|v| v.get(id.as_str()))
+                    .and_then(|v| v.get("quiet_hours"))
+            })
+            .or_else(|| channel_cfg.get("quiet_hours"))
+            .filter(|v| !v.is_null());
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let field = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let (Some(start), Some(end)) = (field("start"), field("end")) else {
+            return Err(format!(
+                "channels.{channel}.quiet_hours needs start and end as HH:MM"
+            ));
+        };
+        QuietHours::parse(start, end, field("timezone").unwrap_or(&self.timezone))
+            .map(Some)
+            .map_err(|e| format!("channels.{channel}: {e}"))
+    }
+
+    /// `max_response_tokens` for a registry channel name: the account's value,
+    /// then the channel's, then the global one. `None` means no limit.
+    pub fn max_response_tokens_for_channel(&self, channel: &str) -> Result<Option<u32>, String> {
+        let (base_channel, account_id) = self.split_channel_account(channel);
+        let channel_cfg = self.channels.get(base_channel);
+        let value = channel_cfg
+            .and_then(|cfg| {
+                account_id.and_then(|id| {
+                    cfg.get("accounts")
+                        .and_then(|v| v.get(id.as_str()))
+                        .and_then(|v| v.get("max_response_tokens"))
+                })
+            })
+            .or_else(|| channel_cfg.and_then(|cfg| cfg.get("max_response_tokens")))
+            .filter(|v| !v.is_null());
+        let limit = match value {
+            Some(v) => v
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| {
+                    format!("channels.{channel}.max_response_tokens must be a non-negative integer")
+                })?,
+            None => self.max_response_tokens.unwrap_or(0),
+        };
+        Ok((limit > 0).then_some(limit))
+    }
+
+    /// `serialize_per_chat` for a registry channel name: the account's value,
+    /// then the channel's, then the global one.
+    pub fn serialize_per_chat_for_channel(&self, channel: &str) -> Result<bool, String> {
+        let (base_channel, account_id) = self.split_channel_account(channel);
+        let channel_cfg = self.channels.get(base_channel);
+        let value = channel_cfg
+            .and_then(|cfg| {
+                account_id.and_then(|id| {
+                    cfg.get("accounts")
+                        .and_then(|v| v.get(id.as_str()))
+                        .and_then(|v| v.get("serialize_per_chat"))
+                })
+            })
+            .or_else(|| channel_cfg.and_then(|cfg| cfg.get("serialize_per_chat")))
+            .filter(|v| !v.is_null());
+        match value {
+            Some(v) => v.as_bool().ok_or_else(|| {
+                format!("channels.{channel}.serialize_per_chat must be true or false")
+            }),
+            None => Ok(self.serialize_per_chat),
+        }
+    }
+
+    /// Reaction added to a message that waits behind a running turn under
+    /// `serialize_per_chat`, in the channel's own emoji naming (Feishu: `OnIt`).
+    pub fn serialize_ack_reaction_for_channel(&self, channel: &str) -> Option<String> {
+        self.channel_scoped_setting(channel, "serialize_ack_reaction")
+    }
+
+    /// Every registry channel name the config can address: each channel and
+    /// each of its `accounts` as `<channel>.<account>`.
+    fn configured_channel_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for (channel, channel_cfg) in &self.channels {
+            names.push(channel.clone());
+            names.extend(
+                channel_cfg
+                    .get("accounts")
+                    .and_then(|v| v.as_mapping())
+                    .into_iter()
+                    .flat_map(|m| m.keys())
+                    .filter_map(|k| k.as_str())
+                    .map(|id| format!("{channel}.{id}")),
+            );
+        }
+        names
+    }
+
+    /// The global `working_dir` plus every channel and account override, keyed
+    /// by registry channel name.
+    pub fn working_dir_roots(&self) -> WorkingDirRoots {
+        let mut roots = WorkingDirRoots::from(self.working_dir.as_str());
+        for name in self.configured_channel_names() {
+            if let Some(dir) = self.channel_scoped_setting(&name, "working_dir") {
+                roots.channels.insert(name, PathBuf::from(dir));
+            }
+        }
+        roots
+    }
+
     pub fn bot_username_overrides(&self) -> HashMap<String, String> {
         let mut overrides: HashMap<String, String> = self
             .channels
@@ -467,36 +1296,92 @@ impl Config {
             llm_provider: "anthropic".into(),
             api_key: "key".into(),
             model: "claude-sonnet-4-5-20250929".into(),
+            model_roles: ModelRoleOverrides::default(),
             llm_base_url: None,
+            llm_correlation_id: true,
+            llm_native_tool_choice: true,
+            llm_vision: true,
+            llm_circuit_breaker_threshold: 5,
+            llm_circuit_breaker_cooldown_secs: 30,
             max_tokens: 8192,
+            max_response_tokens: None,
             max_tool_iterations: 100,
+            allow_skill_budget_increase: false,
             compaction_timeout_secs: 180,
             max_history_messages: 50,
+            group_user_isolation: false,
             max_document_size_mb: 100,
+            attachment_image_vision: true,
             memory_token_budget: 1500,
+            memory_auto_inject: true,
+            memory_auto_inject_top_k: 0,
+            memory_pinned_token_budget: 300,
             data_dir: default_data_dir(),
             skills_dir: None,
+            skills_live_refresh: false,
             working_dir: default_working_dir(),
             working_dir_isolation: WorkingDirIsolation::Chat,
+            working_dir_isolation_policy: WorkingDirIsolationOverrides::default(),
             high_risk_tool_user_confirmation_required: true,
+            allow_proactive_messaging: false,
+            ask_user_timeout_secs: 600,
+            bash_no_tty: true,
+            bash_shell: None,
+            bash_command_allowlist: Vec::new(),
+            tool_audit_log: true,
+            fail_fast_on_missing_tool_deps: false,
             sandbox: SandboxConfig::default(),
             openai_api_key: None,
             timezone: "UTC".into(),
             allowed_groups: vec![],
             control_chat_ids: vec![],
+            user_groups: HashMap::new(),
             max_session_messages: 40,
             compact_keep_recent: 20,
             default_tool_timeout_secs: default_tool_timeout_secs(),
             tool_timeout_overrides: HashMap::new(),
+            max_tool_timeout_secs: 0,
+            sub_agent_max_duration_secs: 0,
+            sub_agent_max_tokens: 0,
+            wait_tool_max_secs: default_wait_tool_max_secs(),
             default_mcp_request_timeout_secs: default_mcp_request_timeout_secs(),
             allow_group_slash_without_mention: false,
+            reaction_commands: default_reaction_commands(),
+            channel_send_failure_alert_threshold: default_channel_send_failure_alert_threshold(),
+            channel_send_failure_backoff_secs: 0,
+            channel_queue_capacity: default_channel_queue_capacity(),
+            channel_queue_drop_policy: default_channel_queue_drop_policy(),
+            channel_queue_max_concurrency: default_channel_queue_max_concurrency(),
+            serialize_per_chat: false,
             show_thinking: false,
+            log_thinking: false,
             openai_compat_body_overrides: HashMap::new(),
             openai_compat_body_overrides_by_provider: HashMap::new(),
             openai_compat_body_overrides_by_model: HashMap::new(),
+            sampling_params: SamplingParams::default(),
+            sampling_params_by_provider: HashMap::new(),
+            sampling_params_by_model: HashMap::new(),
+            model_capabilities: HashMap::new(),
+            deterministic: false,
+            deterministic_seed: 42,
+            anthropic_thinking: false,
+            anthropic_thinking_budget_tokens: 4096,
+            anthropic_interleaved_thinking: false,
+            tool_result_redaction: ToolResultRedactionConfig::default(),
+            tool_error_hints: ToolErrorHintsConfig::default(),
+            tool_input_coercion: ToolInputCoercionConfig::default(),
+            tool_input_defaults: ToolInputDefaults::new(),
             web_fetch_validation: WebContentValidationConfig::default(),
             web_fetch_url_validation: WebFetchUrlValidationConfig::default(),
+            web_fetch_cache: WebFetchCacheConfig::default(),
+            web_fetch_proxy: WebFetchProxyConfig::default(),
+            web_fetch_markdown: WebFetchMarkdownConfig::default(),
+            web_fetch_per_host_rate: WebFetchHostRateConfig::default(),
+            http_request_auth: HttpRequestAuthConfig::default(),
             web_search: WebSearchConfig::default(),
+            web_search_cache_ttl_secs: default_web_search_cache_ttl_secs(),
+            web_domain_allowlist: vec![],
+            web_domain_denylist: vec![],
             model_prices: vec![],
             embedding_provider: None,
             embedding_api_key: None,
@@ -505,6 +1390,9 @@ impl Config {
             embedding_dim: None,
             reflector_enabled: true,
             reflector_interval_mins: 15,
+            memory_dedup_similarity_threshold: default_memory_dedup_similarity_threshold(),
+            memory_backend: default_memory_backend(),
+            qdrant: QdrantConfig::default(),
             soul_path: None,
             clawhub: ClawHubConfig::default(),
             plugins: PluginsConfig::default(),
@@ -513,7 +1401,10 @@ impl Config {
             channels: HashMap::new(),
             send_progress: false,
             send_tool_hints: false,
+            reply_prefix: String::new(),
+            reply_suffix: String::new(),
             logging: LoggingConfig::default(),
+            db_encryption: DbEncryptionConfig::default(),
         }
     }
 
@@ -530,6 +1421,40 @@ impl Config {
             .to_string()
     }
 
+    /// Key for the runtime database, or `None` when `db_encryption` is off.
+    pub fn db_encryption_key(&self) -> Result<Option<String>, MicroClawError> {
+        let enc = &self.db_encryption;
+        if !enc.enabled {
+            return Ok(None);
+        }
+        let key = if let Some(var) = enc.key_env.as_deref() {
+            std::env::var(var).map_err(|_| {
+                MicroClawError::Config(format!(
+                    "db_encryption.key_env: environment variable {var} is not set"
+                ))
+            })?
+        } else if let Some(path) = enc.key_file.as_deref() {
+            std::fs::read_to_string(path).map_err(|e| {
+                MicroClawError::Config(format!("db_encryption.key_file: cannot read {path}: {e}"))
+            })?
+        } else {
+            return Err(MicroClawError::Config(
+                "db_encryption is enabled but neither key_env nor key_file is set".into(),
+            ));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(MicroClawError::Config("db_encryption key is empty".into()));
+        }
+        Ok(Some(key.to_string()))
+    }
+
+    /// Open the runtime database, unlocking it when `db_encryption` is enabled.
+    pub fn open_database(&self) -> Result<microclaw_storage::db::Database, MicroClawError> {
+        let key = self.db_encryption_key()?;
+        microclaw_storage::db::Database::open(&self.runtime_data_dir(), key.as_deref())
+    }
+
     /// Skills directory. Priority: MICROCLAW_SKILLS_DIR env var > skills_dir config > <data_dir>/skills
     pub fn skills_data_dir(&self) -> String {
         // 1. Check env var first
@@ -607,6 +1532,10 @@ impl Config {
             let mut config: Config = serde_yaml::from_str(&content)
                 .map_err(|e| MicroClawError::Config(format!("Failed to parse {path_str}: {e}")))?;
             config.post_deserialize()?;
+            for warning in config.load_warnings() {
+                // Logging is not initialized yet at load time.
+                eprintln!("Warning: {warning}");
+            }
             return Ok(config);
         }
 
@@ -616,6 +1545,42 @@ impl Config {
         ))
     }
 
+    /// Sampling overrides applied everywhere when `deterministic` is on.
+    pub fn deterministic_sampling(&self) -> Option<SamplingParams> {
+        self.deterministic.then(|| SamplingParams {
+            temperature: Some(Some(0.0)),
+            seed: Some(Some(self.deterministic_seed)),
+            ..SamplingParams::default()
+        })
+    }
+
+    /// Settings that load fine but have no effect, as human-readable messages.
+    pub fn load_warnings(&self) -> Vec<String> {
+        let plugin_tools: Vec<String> = crate::plugins::load_plugin_tools(self)
+            .into_iter()
+            .map(|t| t.spec.name)
+            .collect();
+        let mut warnings: Vec<String> = self
+            .unknown_tool_timeout_keys(&plugin_tools)
+            .into_iter()
+            .map(|key| {
+                format!(
+                    "tool_timeout_overrides key '{key}' does not match any known tool and has no effect"
+                )
+            })
+            .collect();
+        warnings.extend(
+            unknown_tool_names(self.tool_input_defaults.keys(), &plugin_tools)
+                .into_iter()
+                .map(|key| {
+                    format!(
+                        "tool_input_defaults key '{key}' does not match any known tool and has no effect"
+                    )
+                }),
+        );
+        warnings
+    }
+
     /// Apply post-deserialization normalization and validation.
     pub(crate) fn post_deserialize(&mut self) -> Result<(), MicroClawError> {
         self.llm_provider = self.llm_provider.trim().to_lowercase();
@@ -629,12 +1594,72 @@ impl Config {
                 _ => "gpt-5.2".into(),
             };
         }
+        self.model_roles.normalize();
+
+        let enc = &self.db_encryption;
+        if enc.enabled && enc.key_env.is_none() && enc.key_file.is_none() {
+            return Err(MicroClawError::Config(
+                "db_encryption.enabled requires db_encryption.key_env or db_encryption.key_file"
+                    .into(),
+            ));
+        }
 
         // Validate timezone
         self.timezone
             .parse::<chrono_tz::Tz>()
             .map_err(|_| MicroClawError::Config(format!("Invalid timezone: {}", self.timezone)))?;
 
+        self.bash_shell = self
+            .bash_shell
+            .take()
+            .map(|shell| shell.trim().to_string())
+            .filter(|shell| !shell.is_empty());
+        self.bash_command_allowlist = self
+            .bash_command_allowlist
+            .iter()
+            .map(|program| program.trim().to_string())
+            .filter(|program| !program.is_empty())
+            .collect();
+        if let Some((emoji, action)) = self
+            .reaction_commands
+            .iter()
+            .find(|(_, action)| crate::chat_commands::ReactionAction::parse(action).is_none())
+        {
+            return Err(MicroClawError::Config(format!(
+                "reaction_commands['{emoji}'] = '{action}' must be regenerate, remember, delete, or a /command"
+            )));
+        }
+
+        crate::channels::user_access::validate_user_groups(&self.user_groups)
+            .and_then(|_| {
+                crate::channels::user_access::validate_channel_user_lists(
+                    &self.channels,
+                    &self.user_groups,
+                )
+            })
+            .map_err(MicroClawError::Config)?;
+        for channel in self.configured_channel_names() {
+            self.quiet_hours_for_channel(&channel)
+                .map_err(MicroClawError::Config)?;
+            self.max_response_tokens_for_channel(&channel)
+                .map_err(MicroClawError::Config)?;
+            self.serialize_per_chat_for_channel(&channel)
+                .map_err(MicroClawError::Config)?;
+        }
+
+        self.channel_queue_drop_policy = self.channel_queue_drop_policy.trim().to_lowercase();
+        if QueueDropPolicy::parse(&self.channel_queue_drop_policy).is_none() {
+            return Err(MicroClawError::Config(format!(
+                "channel_queue_drop_policy must be 'drop_oldest' or 'drop_newest', got '{}'",
+                self.channel_queue_drop_policy
+            )));
+        }
+        if self.channel_queue_max_concurrency == 0 {
+            return Err(MicroClawError::Config(
+                "channel_queue_max_concurrency must be at least 1".into(),
+            ));
+        }
+
         // Filter empty llm_base_url
         if let Some(ref url) = self.llm_base_url {
             if url.trim().is_empty() {
@@ -668,6 +1693,10 @@ impl Config {
         if self.sandbox.container_prefix.is_empty() {
             self.sandbox.container_prefix = default_sandbox_container_prefix();
         }
+        if let Some(advanced) = &mut self.sandbox.advanced {
+            advanced.command_template = advanced.command_template.trim().to_string();
+            advanced.validate().map_err(MicroClawError::Config)?;
+        }
         if let Some(provider) = &self.embedding_provider {
             let p = provider.trim().to_lowercase();
             self.embedding_provider = if p.is_empty() { None } else { Some(p) };
@@ -691,8 +1720,50 @@ impl Config {
                 self.embedding_dim = None;
             }
         }
+        self.tool_result_redaction
+            .normalize()
+            .map_err(MicroClawError::Config)?;
+        self.tool_error_hints.normalize();
+        self.tool_input_coercion.normalize();
+        self.web_search.normalize();
         self.web_fetch_validation.normalize();
         self.web_fetch_url_validation.normalize();
+        self.web_fetch_cache.normalize();
+        self.web_fetch_proxy
+            .normalize()
+            .map_err(MicroClawError::Config)?;
+        self.web_fetch_markdown
+            .normalize()
+            .map_err(MicroClawError::Config)?;
+        self.web_fetch_per_host_rate.normalize();
+        self.http_request_auth
+            .normalize()
+            .map_err(MicroClawError::Config)?;
+        self.memory_backend = self.memory_backend.trim().to_lowercase();
+        if self.memory_backend.is_empty() {
+            self.memory_backend = default_memory_backend();
+        }
+        self.qdrant.normalize().map_err(MicroClawError::Config)?;
+        match self.memory_backend.as_str() {
+            "sqlite" => {}
+            "qdrant" => {
+                if self.qdrant.url.is_empty() {
+                    return Err(MicroClawError::Config(
+                        "memory_backend 'qdrant' requires qdrant.url".into(),
+                    ));
+                }
+                if self.embedding_provider.is_none() {
+                    return Err(MicroClawError::Config(
+                        "memory_backend 'qdrant' requires embedding_provider".into(),
+                    ));
+                }
+            }
+            other => {
+                return Err(MicroClawError::Config(format!(
+                    "memory_backend must be 'sqlite' or 'qdrant', got '{other}'"
+                )));
+            }
+        }
         if self.max_document_size_mb == 0 {
             self.max_document_size_mb = default_max_document_size_mb();
         }
@@ -702,6 +1773,12 @@ impl Config {
         if self.default_mcp_request_timeout_secs == 0 {
             self.default_mcp_request_timeout_secs = default_mcp_request_timeout_secs();
         }
+        if self.ask_user_timeout_secs == 0 {
+            self.ask_user_timeout_secs = default_ask_user_timeout_secs();
+        }
+        if self.wait_tool_max_secs == 0 {
+            self.wait_tool_max_secs = default_wait_tool_max_secs();
+        }
         self.tool_timeout_overrides = self
             .tool_timeout_overrides
             .drain()
@@ -714,6 +1791,28 @@ impl Config {
                 }
             })
             .collect();
+        if self.max_tool_timeout_secs > 0 {
+            let max = self.max_tool_timeout_secs;
+            if self.default_tool_timeout_secs > max {
+                return Err(MicroClawError::Config(format!(
+                    "default_tool_timeout_secs ({}) exceeds max_tool_timeout_secs ({max})",
+                    self.default_tool_timeout_secs
+                )));
+            }
+            let mut over: Vec<String> = self
+                .tool_timeout_overrides
+                .iter()
+                .filter(|(_, secs)| **secs > max)
+                .map(|(name, secs)| format!("{name}={secs}"))
+                .collect();
+            if !over.is_empty() {
+                over.sort();
+                return Err(MicroClawError::Config(format!(
+                    "tool_timeout_overrides exceed max_tool_timeout_secs ({max}): {}",
+                    over.join(", ")
+                )));
+            }
+        }
         self.openai_compat_body_overrides =
             normalize_body_override_params(std::mem::take(&mut self.openai_compat_body_overrides));
         self.openai_compat_body_overrides_by_provider = self
@@ -748,20 +1847,82 @@ impl Config {
                 }
             })
             .collect();
-        if self.memory_token_budget == 0 {
-            self.memory_token_budget = default_memory_token_budget();
+        self.sampling_params.normalize("sampling_params")?;
+        let mut sampling_by_provider = HashMap::new();
+        for (provider, mut params) in self.sampling_params_by_provider.drain() {
+            let provider = provider.trim().to_ascii_lowercase();
+            if provider.is_empty() {
+                continue;
+            }
+            params.normalize(&format!("sampling_params_by_provider.{provider}"))?;
+            if !params.is_empty() {
+                sampling_by_provider.insert(provider, params);
+            }
         }
-        for price in &mut self.model_prices {
-            price.model = price.model.trim().to_string();
-            if price.model.is_empty() {
-                return Err(MicroClawError::Config(
-                    "model_prices entries must include non-empty model".into(),
-                ));
+        self.sampling_params_by_provider = sampling_by_provider;
+        let mut sampling_by_model = HashMap::new();
+        for (model, mut params) in self.sampling_params_by_model.drain() {
+            let model = model.trim().to_string();
+            if model.is_empty() {
+                continue;
             }
-            if !(price.input_per_million_usd.is_finite() && price.input_per_million_usd >= 0.0) {
+            params.normalize(&format!("sampling_params_by_model.{model}"))?;
+            if !params.is_empty() {
+                sampling_by_model.insert(model, params);
+            }
+        }
+        self.sampling_params_by_model = sampling_by_model;
+        self.model_capabilities = std::mem::take(&mut self.model_capabilities)
+            .into_iter()
+            .map(|(pattern, caps)| (pattern.trim().to_ascii_lowercase(), caps))
+            .filter(|(pattern, _)| !pattern.is_empty())
+            .collect();
+        if let Some(overlay) = self.deterministic_sampling() {
+            self.sampling_params = self.sampling_params.merged_with(&overlay);
+            for params in self
+                .sampling_params_by_provider
+                .values_mut()
+                .chain(self.sampling_params_by_model.values_mut())
+            {
+                *params = params.merged_with(&overlay);
+            }
+        }
+        if self.anthropic_thinking {
+            let budget = self.anthropic_thinking_budget_tokens;
+            if budget < MIN_ANTHROPIC_THINKING_BUDGET_TOKENS {
                 return Err(MicroClawError::Config(format!(
-                    "model_prices[{}].input_per_million_usd must be >= 0",
-                    price.model
+                    "anthropic_thinking_budget_tokens must be at least {MIN_ANTHROPIC_THINKING_BUDGET_TOKENS}, got {budget}"
+                )));
+            }
+            let max_tokens = self.sampling_params.max_tokens().unwrap_or(self.max_tokens);
+            if budget >= max_tokens {
+                return Err(MicroClawError::Config(format!(
+                    "anthropic_thinking_budget_tokens ({budget}) must be less than max_tokens ({max_tokens})"
+                )));
+            }
+        }
+        if !(self.memory_dedup_similarity_threshold > 0.0
+            && self.memory_dedup_similarity_threshold <= 1.0)
+        {
+            return Err(MicroClawError::Config(format!(
+                "memory_dedup_similarity_threshold must be in (0, 1], got {}",
+                self.memory_dedup_similarity_threshold
+            )));
+        }
+        if self.memory_token_budget == 0 {
+            self.memory_token_budget = default_memory_token_budget();
+        }
+        for price in &mut self.model_prices {
+            price.model = price.model.trim().to_string();
+            if price.model.is_empty() {
+                return Err(MicroClawError::Config(
+                    "model_prices entries must include non-empty model".into(),
+                ));
+            }
+            if !(price.input_per_million_usd.is_finite() && price.input_per_million_usd >= 0.0) {
+                return Err(MicroClawError::Config(format!(
+                    "model_prices[{}].input_per_million_usd must be >= 0",
+                    price.model
                 )));
             }
             if !(price.output_per_million_usd.is_finite() && price.output_per_million_usd >= 0.0) {
@@ -773,15 +1934,22 @@ impl Config {
         }
 
         // Validate required fields
-        let has_any_channel = ["feishu", "email", "dingtalk"]
+        let has_any_channel = ["feishu", "email", "dingtalk", "webhook"]
             .iter()
             .any(|ch| self.channel_enabled(ch) && self.channels.contains_key(*ch));
 
         if !has_any_channel {
             return Err(MicroClawError::Config(
-                "At least one channel must be enabled and configured (feishu, email, or dingtalk)".into(),
+                "At least one channel must be enabled and configured (feishu, email, dingtalk, or webhook)".into(),
             ));
         }
+        if self.channel_enabled("webhook") {
+            let webhook = self.channels.get("webhook").cloned().unwrap_or_default();
+            serde_yaml::from_value::<crate::channels::webhook::WebhookChannelConfig>(webhook)
+                .map_err(|e| MicroClawError::Config(format!("channels.webhook: {e}")))?
+                .validate()
+                .map_err(MicroClawError::Config)?;
+        }
         if self.api_key.is_empty() && !provider_allows_empty_api_key(&self.llm_provider) {
             return Err(MicroClawError::Config("api_key is required".into()));
         }
@@ -819,6 +1987,41 @@ impl Config {
             .and_then(|v| serde_yaml::from_value(v.clone()).ok())
     }
 
+    /// Configured credential values (LLM/embedding keys, channel secrets) that
+    /// must never be echoed back through tool output.
+    pub fn known_secret_values(&self) -> Vec<String> {
+        let mut out: Vec<String> = [
+            Some(&self.api_key),
+            self.embedding_api_key.as_ref(),
+            self.qdrant.api_key.as_ref(),
+            self.openai_api_key.as_ref(),
+            self.clawhub.token.as_ref(),
+            self.web_search.tavily_api_key.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|v| !v.trim().is_empty())
+        .cloned()
+        .collect();
+        for value in self.channels.values() {
+            collect_secret_yaml_values(value, false, &mut out);
+        }
+        out.extend(self.http_request_auth.secret_values().cloned());
+        out.extend(
+            self.plugins
+                .secrets
+                .values()
+                .filter(|v| !v.trim().is_empty())
+                .cloned(),
+        );
+        out
+    }
+
+    /// Model for `role`: its `model_roles` entry, else the global `model`.
+    pub fn model_for_role(&self, role: ModelRole) -> &str {
+        self.model_roles.get(role).unwrap_or(&self.model)
+    }
+
     pub fn model_price(&self, model: &str) -> Option<&ModelPrice> {
         let needle = model.trim();
         self.model_prices
@@ -842,6 +2045,10 @@ impl Config {
         )
     }
 
+    pub fn web_domain_policy(&self) -> WebDomainPolicy {
+        WebDomainPolicy::new(&self.web_domain_allowlist, &self.web_domain_denylist)
+    }
+
     pub fn tool_timeout_secs(&self, tool_name: &str, fallback: u64) -> u64 {
         let normalized = tool_name.trim().to_ascii_lowercase();
         if let Some(timeout_secs) = self.tool_timeout_overrides.get(&normalized) {
@@ -854,6 +2061,22 @@ impl Config {
         }
     }
 
+    /// `tool_timeout_overrides` keys that match no built-in or `extra_known` tool.
+    pub fn unknown_tool_timeout_keys(&self, extra_known: &[String]) -> Vec<String> {
+        unknown_tool_names(self.tool_timeout_overrides.keys(), extra_known)
+    }
+
+    /// Effective working-dir isolation per auth context.
+    pub fn resolved_working_dir_isolation(&self) -> WorkingDirIsolationPolicy {
+        let overrides = &self.working_dir_isolation_policy;
+        WorkingDirIsolationPolicy {
+            control_chats: overrides
+                .control_chats
+                .unwrap_or(self.working_dir_isolation),
+            default: overrides.default.unwrap_or(self.working_dir_isolation),
+        }
+    }
+
     pub fn mcp_request_timeout_secs(&self) -> u64 {
         if self.default_mcp_request_timeout_secs == 0 {
             default_mcp_request_timeout_secs()
@@ -870,6 +2093,80 @@ impl Config {
         std::fs::write(path, content)?;
         Ok(())
     }
+
+    /// YAML snapshot for export: values under secret-looking keys are masked,
+    /// and any known credential that leaks elsewhere (e.g. inside a URL) too.
+    pub fn redacted_yaml(&self) -> Result<String, MicroClawError> {
+        let mut value = serde_yaml::to_value(self)
+            .map_err(|e| MicroClawError::Config(format!("Failed to serialize config: {e}")))?;
+        mask_secret_yaml_values(&mut value, false);
+        let mut content = serde_yaml::to_string(&value)
+            .map_err(|e| MicroClawError::Config(format!("Failed to serialize config: {e}")))?;
+        // Very short values would mangle unrelated text.
+        for secret in self.known_secret_values() {
+            if secret.len() >= 8 {
+                content =
+                    content.replace(&secret, microclaw_tools::redaction::REDACTED_PLACEHOLDER);
+            }
+        }
+        Ok(content)
+    }
+}
+
+fn is_secret_key_name(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    [
+        "secret",
+        "token",
+        "password",
+        "api_key",
+        "apikey",
+        "private_key",
+    ]
+    .iter()
+    .any(|needle| key.contains(needle))
+}
+
+fn collect_secret_yaml_values(value: &serde_yaml::Value, secret_key: bool, out: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::String(s) if secret_key && !s.trim().is_empty() => {
+            out.push(s.clone());
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (k, v) in map {
+                let is_secret = k.as_str().map(is_secret_key_name).unwrap_or(false);
+                collect_secret_yaml_values(v, is_secret, out);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                collect_secret_yaml_values(item, secret_key, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Everything below a secret key is masked, so maps such as `plugins.secrets`
+/// lose all their values.
+fn mask_secret_yaml_values(value: &mut serde_yaml::Value, secret_key: bool) {
+    match value {
+        serde_yaml::Value::String(s) if secret_key && !s.trim().is_empty() => {
+            *s = microclaw_tools::redaction::REDACTED_PLACEHOLDER.to_string();
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (k, v) in map.iter_mut() {
+                let is_secret = secret_key || k.as_str().map(is_secret_key_name).unwrap_or(false);
+                mask_secret_yaml_values(v, is_secret);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                mask_secret_yaml_values(item, secret_key);
+            }
+        }
+        _ => {}
+    }
 }
 
 fn normalize_body_override_params(
@@ -1079,6 +2376,42 @@ channels:
         assert_eq!(config.tool_timeout_secs("browser", 120), 45);
     }
 
+    #[test]
+    fn test_unknown_tool_timeout_keys() {
+        let mut config = test_config();
+        config.tool_timeout_overrides = HashMap::from([
+            ("bash".to_string(), 60),
+            ("bsah".to_string(), 60),
+            ("mcp_fs_read".to_string(), 60),
+            ("my_plugin_tool".to_string(), 60),
+        ]);
+        assert_eq!(
+            config.unknown_tool_timeout_keys(&["My_Plugin_Tool".to_string()]),
+            vec!["bsah".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_max_tool_timeout_bound() {
+        let mut config = test_config();
+        config.channels.insert(
+            "feishu".to_string(),
+            serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+        );
+        config.max_tool_timeout_secs = 100;
+        config.tool_timeout_overrides = HashMap::from([("bash".to_string(), 300)]);
+        let err = config.clone().post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("bash=300"), "{err}");
+
+        config.tool_timeout_overrides = HashMap::from([("bash".to_string(), 100)]);
+        config.default_tool_timeout_secs = 120;
+        let err = config.clone().post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("default_tool_timeout_secs"), "{err}");
+
+        config.default_tool_timeout_secs = 30;
+        config.post_deserialize().unwrap();
+    }
+
     #[test]
     fn test_default_data_dir_uses_microclaw_home() {
         let yaml = r#"bot_username: bot
@@ -1107,6 +2440,50 @@ channels:
         assert_eq!(config.sandbox.image, "ubuntu:25.10");
     }
 
+    #[test]
+    fn test_config_web_domain_lists() {
+        let yaml = r#"bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+web_domain_allowlist: ["Docs.rs", "*.rust-lang.org"]
+web_domain_denylist: ["ads.rust-lang.org"]
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let policy = config.web_domain_policy();
+        assert!(policy.check_url("https://docs.rs/tokio").is_ok());
+        assert!(policy.check_url("https://blog.rust-lang.org/").is_ok());
+        assert!(policy.check_url("https://ads.rust-lang.org/").is_err());
+        assert!(policy.check_url("https://example.com/").is_err());
+        assert!(test_config().web_domain_policy().is_empty());
+    }
+
+    #[test]
+    fn test_post_deserialize_validates_sandbox_command_template() {
+        let yaml = r#"bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+sandbox:
+  mode: all
+  advanced:
+    command_template: " docker run --rm --runtime=runsc {mounts} -w {workdir} {image} sh -c {cmd} "
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        let advanced = config.sandbox.advanced.as_ref().unwrap();
+        assert!(advanced.command_template.starts_with("docker run"));
+
+        config.sandbox.advanced.as_mut().unwrap().command_template =
+            "docker run --rm {image} sh -c {cmd}".into();
+        let err = config.post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("must reference {mounts}"), "{err}");
+    }
+
     #[test]
     fn test_post_deserialize_empty_working_dir_uses_default() {
         let yaml = r#"bot_username: bot
@@ -1136,6 +2513,48 @@ memory_token_budget: 0
         let mut config: Config = serde_yaml::from_str(yaml).unwrap();
         config.post_deserialize().unwrap();
         assert_eq!(config.memory_token_budget, 1500);
+        assert!(config.memory_auto_inject);
+        assert_eq!(config.memory_auto_inject_top_k, 0);
+
+        let yaml = format!("{yaml}memory_auto_inject: false\nmemory_auto_inject_top_k: 5\n");
+        let config: Config = serde_yaml::from_str(&yaml).unwrap();
+        assert!(!config.memory_auto_inject);
+        assert_eq!(config.memory_auto_inject_top_k, 5);
+    }
+
+    #[test]
+    fn test_post_deserialize_trims_bash_command_allowlist() {
+        let yaml = r#"bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+bash_command_allowlist: [" git ", "", "/usr/bin/rg"]
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(config.bash_command_allowlist, ["git", "/usr/bin/rg"]);
+    }
+
+    #[test]
+    fn test_post_deserialize_rejects_unknown_reaction_action() {
+        let yaml = r#"bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(config.reaction_commands["🔁"], "regenerate");
+        assert_eq!(config.reaction_commands["Pin"], "remember");
+
+        let yaml = format!("{yaml}reaction_commands:\n  THUMBSUP: /usage\n  \"👎\": explode\n");
+        let mut config: Config = serde_yaml::from_str(&yaml).unwrap();
+        let err = config.post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("reaction_commands['👎']"), "{err}");
     }
 
     #[test]
@@ -1205,6 +2624,31 @@ working_dir_isolation: false
         ));
     }
 
+    #[test]
+    fn test_config_working_dir_isolation_policy_overrides_preset() {
+        let yaml = r#"bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+working_dir_isolation: chat
+working_dir_isolation_policy:
+  control_chats: shared
+"#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let policy = config.resolved_working_dir_isolation();
+        assert_eq!(policy.control_chats, WorkingDirIsolation::Shared);
+        assert_eq!(policy.default, WorkingDirIsolation::Chat);
+
+        let mut preset_only = config.clone();
+        preset_only.working_dir_isolation_policy = WorkingDirIsolationOverrides::default();
+        assert_eq!(
+            preset_only.resolved_working_dir_isolation(),
+            WorkingDirIsolation::Chat.into()
+        );
+    }
+
     #[test]
     fn test_high_risk_tool_user_confirmation_required_defaults_true() {
         let yaml = r#"bot_username: bot
@@ -1405,6 +2849,239 @@ channels:
         assert!(config.channel_enabled("feishu"));
     }
 
+    #[test]
+    fn test_reply_decoration_resolves_account_then_channel_then_global() {
+        let yaml = r#"api_key: key
+bot_username: helper
+reply_prefix: "[{bot_username}]"
+reply_suffix: "Generated by {model}."
+channels:
+  feishu:
+    reply_suffix: "Feishu disclaimer"
+    default_account: main
+    accounts:
+      main:
+        app_id: a
+        app_secret: s
+      ops:
+        app_id: b
+        app_secret: t
+        bot_username: opsbot
+        reply_prefix: "[{bot_username} on {channel}]"
+  dingtalk:
+    robot_webhook_url: "https://example.com/hook"
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+
+        let ops = config.reply_decoration_for_channel("feishu.ops", "gpt-x");
+        assert_eq!(ops.prefix, "[opsbot on feishu.ops]");
+        assert_eq!(ops.suffix, "Feishu disclaimer");
+
+        let main = config.reply_decoration_for_channel("feishu", "gpt-x");
+        assert_eq!(main.prefix, "[helper]");
+        assert_eq!(main.suffix, "Feishu disclaimer");
+
+        let dingtalk = config.reply_decoration_for_channel("dingtalk", "claude-y");
+        assert_eq!(dingtalk.suffix, "Generated by claude-y.");
+
+        config.reply_prefix.clear();
+        config.reply_suffix.clear();
+        assert!(config
+            .reply_decoration_for_channel("dingtalk", "m")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_webhook_channel_counts_as_channel_and_requires_token() {
+        let yaml = "api_key: key\nchannels:\n  webhook:\n    auth_token: s3cret\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+
+        let yaml =
+            "api_key: key\nchannels:\n  webhook:\n    callback_url: https://example.com/cb\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        let err = config.post_deserialize().unwrap_err().to_string();
+        assert!(
+            err.contains("channels.webhook.auth_token is required"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_working_dir_roots_per_channel_account() {
+        let yaml = r#"api_key: key
+working_dir: /srv/work
+channels:
+  feishu:
+    default_account: main
+    accounts:
+      main:
+        app_id: a
+        app_secret: s
+      sales:
+        app_id: b
+        app_secret: t
+        working_dir: /mnt/sales
+  dingtalk:
+    robot_webhook_url: "https://example.com/hook"
+    working_dir: /mnt/dingtalk
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+
+        let roots = config.working_dir_roots();
+        assert_eq!(roots.default, PathBuf::from("/srv/work"));
+        assert_eq!(
+            roots.for_channel("feishu.sales"),
+            std::path::Path::new("/mnt/sales")
+        );
+        assert_eq!(
+            roots.for_channel("feishu"),
+            std::path::Path::new("/srv/work")
+        );
+        assert_eq!(
+            roots.for_channel("dingtalk"),
+            std::path::Path::new("/mnt/dingtalk")
+        );
+        assert_eq!(roots.channels.len(), 2);
+        assert_eq!(
+            config.working_dir_for_channel("feishu.sales"),
+            PathBuf::from("/mnt/sales")
+        );
+        assert_eq!(
+            config.working_dir_for_channel("email"),
+            PathBuf::from("/srv/work")
+        );
+    }
+
+    #[test]
+    fn test_serialize_per_chat_per_channel() {
+        let yaml = r#"api_key: key
+serialize_per_chat: true
+channels:
+  feishu:
+    default_account: main
+    serialize_ack_reaction: OnIt
+    accounts:
+      main:
+        app_id: a
+        app_secret: s
+      ops:
+        app_id: b
+        app_secret: t
+        serialize_per_chat: false
+  dingtalk:
+    robot_webhook_url: "https://example.com/hook"
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(config.serialize_per_chat_for_channel("feishu"), Ok(true));
+        assert_eq!(
+            config.serialize_per_chat_for_channel("feishu.ops"),
+            Ok(false)
+        );
+        assert_eq!(config.serialize_per_chat_for_channel("dingtalk"), Ok(true));
+        assert_eq!(
+            config
+                .serialize_ack_reaction_for_channel("feishu.ops")
+                .as_deref(),
+            Some("OnIt")
+        );
+        assert_eq!(config.serialize_ack_reaction_for_channel("dingtalk"), None);
+
+        let mut bad: Config = serde_yaml::from_str(
+            &yaml.replace("serialize_per_chat: false", "serialize_per_chat: sometimes"),
+        )
+        .unwrap();
+        let err = bad.post_deserialize().unwrap_err().to_string();
+        assert!(
+            err.contains("channels.feishu.ops.serialize_per_chat"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_max_response_tokens_per_channel() {
+        let yaml = r#"api_key: key
+max_response_tokens: 300
+channels:
+  feishu:
+    default_account: main
+    max_response_tokens: 120
+    accounts:
+      main:
+        app_id: a
+        app_secret: s
+      ops:
+        app_id: b
+        app_secret: t
+        max_response_tokens: 0
+  dingtalk:
+    robot_webhook_url: "https://example.com/hook"
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(
+            config.max_response_tokens_for_channel("feishu"),
+            Ok(Some(120))
+        );
+        assert_eq!(
+            config.max_response_tokens_for_channel("feishu.ops"),
+            Ok(None)
+        );
+        assert_eq!(
+            config.max_response_tokens_for_channel("dingtalk"),
+            Ok(Some(300))
+        );
+
+        let mut bad: Config = serde_yaml::from_str(
+            &yaml.replace("max_response_tokens: 120", "max_response_tokens: short"),
+        )
+        .unwrap();
+        let err = bad.post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("channels.feishu.max_response_tokens"), "{err}");
+    }
+
+    #[test]
+    fn test_quiet_hours_per_channel_account() {
+        let yaml = r#"api_key: key
+timezone: Asia/Tokyo
+channels:
+  feishu:
+    default_account: main
+    quiet_hours: {start: "22:00", end: "07:00"}
+    accounts:
+      main:
+        app_id: a
+        app_secret: s
+      ops:
+        app_id: b
+        app_secret: t
+        quiet_hours: {start: "01:00", end: "05:00", timezone: Europe/Berlin}
+  dingtalk:
+    robot_webhook_url: "https://example.com/hook"
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+
+        let main = config.quiet_hours_for_channel("feishu").unwrap().unwrap();
+        assert_eq!(main.describe(), "22:00-07:00 Asia/Tokyo");
+        let ops = config
+            .quiet_hours_for_channel("feishu.ops")
+            .unwrap()
+            .unwrap();
+        assert_eq!(ops.describe(), "01:00-05:00 Europe/Berlin");
+        assert!(config
+            .quiet_hours_for_channel("dingtalk")
+            .unwrap()
+            .is_none());
+
+        let mut bad: Config = serde_yaml::from_str(&yaml.replace("\"07:00\"", "\"7pm\"")).unwrap();
+        let err = bad.post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("quiet_hours.end must be HH:MM"), "{err}");
+    }
+
     #[test]
     fn test_post_deserialize_openai_default_model() {
         let yaml = r#"bot_username: bot
@@ -1600,6 +3277,28 @@ channels:
         assert_eq!(config.model, "llama3.2");
     }
 
+    #[test]
+    fn test_model_roles_fall_back_to_global_model() {
+        let yaml = r#"bot_username: bot
+api_key: key
+model: premium
+model_roles:
+  reflector: " cheap "
+  sub_agent: ""
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(config.model_for_role(ModelRole::Reflector), "cheap");
+        assert_eq!(config.model_roles.sub_agent, None);
+        assert_eq!(config.model_for_role(ModelRole::SubAgent), "premium");
+        assert_eq!(config.model_for_role(ModelRole::Main), "premium");
+        assert_eq!(config.model_for_role(ModelRole::Compaction), "premium");
+    }
+
     #[test]
     fn test_post_deserialize_empty_base_url_becomes_none() {
         let yaml = r#"bot_username: bot
@@ -1686,6 +3385,207 @@ openai_compat_body_overrides_by_model:
         assert!(!model_params.contains_key(""));
     }
 
+    #[test]
+    fn test_sampling_params_parse_null_and_resolve_precedence() {
+        let yaml = r#"
+bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+sampling_params:
+  temperature: 0.7
+  top_p: 0.9
+sampling_params_by_provider:
+  " OpenAI ":
+    max_tokens: 1024
+    reasoning_effort: " Medium "
+sampling_params_by_model:
+  " gpt-5.2 ":
+    temperature: null
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+
+        let model_params = config.sampling_params_by_model.get("gpt-5.2").unwrap();
+        assert_eq!(model_params.temperature, Some(None));
+        assert_eq!(model_params.top_p, None);
+
+        let resolved = resolve_sampling_params(
+            &config.sampling_params,
+            &config.sampling_params_by_provider,
+            &config.sampling_params_by_model,
+            "openai",
+            "gpt-5.2",
+        );
+        assert_eq!(resolved.temperature(), None);
+        assert_eq!(resolved.top_p(), Some(0.9));
+        assert_eq!(resolved.max_tokens(), Some(1024));
+        assert_eq!(resolved.reasoning_effort(), Some("medium"));
+
+        let other = resolve_sampling_params(
+            &config.sampling_params,
+            &config.sampling_params_by_provider,
+            &config.sampling_params_by_model,
+            "deepseek",
+            "deepseek-chat",
+        );
+        assert_eq!(other.temperature(), Some(0.7));
+        assert_eq!(other.max_tokens(), None);
+    }
+
+    #[test]
+    fn test_anthropic_thinking_budget_validation() {
+        let mut config = test_config();
+        config.channels.insert(
+            "feishu".to_string(),
+            serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+        );
+        config.anthropic_thinking_budget_tokens = 100_000;
+        config.clone().post_deserialize().unwrap();
+
+        config.anthropic_thinking = true;
+        config.max_tokens = 8192;
+        let err = config.clone().post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("must be less than max_tokens (8192)"), "{err}");
+
+        config.anthropic_thinking_budget_tokens = 512;
+        let err = config.clone().post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("must be at least 1024"), "{err}");
+
+        config.anthropic_thinking_budget_tokens = 4096;
+        config.clone().post_deserialize().unwrap();
+        config.sampling_params.max_tokens = Some(Some(4096));
+        assert!(config.post_deserialize().is_err());
+    }
+
+    #[test]
+    fn test_deterministic_overrides_temperature_and_seed_at_every_scope() {
+        let yaml = r#"
+bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+deterministic: true
+deterministic_seed: 7
+sampling_params:
+  temperature: 0.7
+  seed: 1
+sampling_params_by_model:
+  gpt-5.2:
+    temperature: 1.2
+    top_p: 0.5
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        for model in ["gpt-5.2", "other"] {
+            let resolved = resolve_sampling_params(
+                &config.sampling_params,
+                &config.sampling_params_by_provider,
+                &config.sampling_params_by_model,
+                "openai",
+                model,
+            );
+            assert_eq!(resolved.temperature(), Some(0.0));
+            assert_eq!(resolved.seed(), Some(7));
+        }
+        assert_eq!(
+            config.sampling_params_by_model["gpt-5.2"].top_p(),
+            Some(0.5)
+        );
+
+        let mut config: Config =
+            serde_yaml::from_str(&yaml.replace("deterministic: true", "deterministic: false"))
+                .unwrap();
+        config.post_deserialize().unwrap();
+        assert!(config.deterministic_sampling().is_none());
+        assert_eq!(config.sampling_params.temperature(), Some(0.7));
+        assert_eq!(config.sampling_params.seed(), Some(1));
+    }
+
+    #[test]
+    fn test_sampling_params_stop_and_response_format() {
+        let yaml = r#"
+bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+sampling_params:
+  stop: ["\n\nUser:"]
+  response_format: json_object
+sampling_params_by_model:
+  m:
+    response_format: null
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert!(config.sampling_params.json_mode());
+        assert_eq!(
+            config.sampling_params.stop(),
+            Some(&["\n\nUser:".to_string()][..])
+        );
+
+        let resolved = resolve_sampling_params(
+            &config.sampling_params,
+            &config.sampling_params_by_provider,
+            &config.sampling_params_by_model,
+            "openai",
+            "m",
+        );
+        assert!(!resolved.json_mode());
+        assert!(resolved
+            .merged_with(&SamplingParams::json_object())
+            .json_mode());
+    }
+
+    #[test]
+    fn test_sampling_params_invalid_rejected() {
+        let base = r#"
+bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+"#;
+        for (extra, expected) in [
+            (
+                "sampling_params:\n  temperature: 2.5\n",
+                "sampling_params.temperature must be between 0 and 2",
+            ),
+            (
+                "sampling_params_by_model:\n  m:\n    top_p: 0\n",
+                "sampling_params_by_model.m.top_p",
+            ),
+            (
+                "sampling_params_by_provider:\n  openai:\n    max_tokens: 0\n",
+                "sampling_params_by_provider.openai.max_tokens must be greater than 0",
+            ),
+            (
+                "sampling_params:\n  reasoning_effort: extreme\n",
+                "sampling_params.reasoning_effort must be one of",
+            ),
+            (
+                "sampling_params_by_model:\n  gpt-5:\n    verbosity: verbose\n",
+                "sampling_params_by_model.gpt-5.verbosity must be one of: low, medium, high",
+            ),
+            (
+                "sampling_params:\n  stop: [\"\"]\n",
+                "sampling_params.stop must not contain empty sequences",
+            ),
+        ] {
+            let yaml = format!("{base}{extra}");
+            let mut config: Config = serde_yaml::from_str(&yaml).unwrap();
+            let err = config.post_deserialize().unwrap_err();
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+    }
+
     #[test]
     fn test_model_prices_parse_and_estimate() {
         let yaml = r#"
@@ -1755,6 +3655,62 @@ compact_keep_recent: 30
         assert_eq!(config.compact_keep_recent, 30);
     }
 
+    #[test]
+    fn test_db_encryption_key_sources() {
+        let mut config = test_config();
+        assert_eq!(config.db_encryption_key().unwrap(), None);
+
+        config.db_encryption.enabled = true;
+        let err = config.post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("requires db_encryption.key_env"), "{err}");
+
+        let dir = std::env::temp_dir().join(format!("mc_dbkey_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key_file = dir.join("db.key");
+        std::fs::write(&key_file, "  s3cret\n").unwrap();
+        config.db_encryption.key_file = Some(key_file.to_string_lossy().to_string());
+        assert_eq!(
+            config.db_encryption_key().unwrap().as_deref(),
+            Some("s3cret")
+        );
+
+        std::fs::write(&key_file, "\n").unwrap();
+        let err = config.db_encryption_key().unwrap_err().to_string();
+        assert!(err.contains("key is empty"), "{err}");
+
+        config.db_encryption.key_env = Some("MICROCLAW_TEST_UNSET_DB_KEY".into());
+        let err = config.db_encryption_key().unwrap_err().to_string();
+        assert!(
+            err.contains("MICROCLAW_TEST_UNSET_DB_KEY is not set"),
+            "{err}"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_config_user_groups_validated_against_channel_lists() {
+        let yaml = r#"
+bot_username: bot
+api_key: key
+user_groups:
+  ops: ["ou_ops_*"]
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+    allowed_user_ids: ["ou_team_*", "group:ops"]
+    admin_user_ids: ["group:ops"]
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        assert_eq!(config.user_groups["ops"], vec!["ou_ops_*"]);
+
+        let yaml = yaml.replace("\"group:ops\"]\n", "\"group:sre\"]\n");
+        let mut config: Config = serde_yaml::from_str(&yaml).unwrap();
+        let err = config.post_deserialize().unwrap_err().to_string();
+        assert!(err.contains("unknown user group 'sre'"));
+    }
+
     #[test]
     fn test_config_save_yaml() {
         let config = test_config();
@@ -0,0 +1,251 @@
+//! `microclaw config check|schema`: validate a config file without starting
+//! the runtime, and export a JSON Schema for editor linting.
+
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "microclaw config",
+    about = "Validate a config file or print its JSON Schema",
+    disable_help_subcommand = true
+)]
+struct ConfigCli {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// Load and validate a config file (defaults to the one `microclaw start` would use)
+    Check {
+        path: Option<PathBuf>,
+        /// Treat warnings (unknown keys, ineffective settings) as errors
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Print a JSON Schema for microclaw.config.yaml
+    Schema,
+}
+
+/// Outcome of [`check_config_file`]. The config is usable when `errors` is empty.
+#[derive(Debug, Default)]
+pub struct ConfigCheckReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Run every validation `Config::load` runs against `path`, plus a scan for
+/// top-level keys the config does not know (which serde would silently ignore).
+///
+/// Semantic validation stops at its first failure, so at most one such error
+/// is reported per run.
+pub fn check_config_file(path: &Path) -> ConfigCheckReport {
+    let mut report = ConfigCheckReport::default();
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            report
+                .errors
+                .push(format!("failed to read {}: {e}", path.display()));
+            return report;
+        }
+    };
+    let raw: serde_yaml::Value = match serde_yaml::from_str(&content) {
+        Ok(v) => v,
+        Err(e) => {
+            report.errors.push(format!("invalid YAML: {e}"));
+            return report;
+        }
+    };
+    if let Some(map) = raw.as_mapping() {
+        let known = known_top_level_keys();
+        for key in map.keys() {
+            let key = key.as_str().unwrap_or_default();
+            if !known.iter().any(|k| k == key) {
+                report
+                    .warnings
+                    .push(format!("unknown key '{key}' is ignored"));
+            }
+        }
+    }
+    let mut config: Config = match serde_yaml::from_str(&content) {
+        Ok(c) => c,
+        Err(e) => {
+            report.errors.push(e.to_string());
+            return report;
+        }
+    };
+    if let Err(e) = config.post_deserialize() {
+        report.errors.push(e.to_string());
+        return report;
+    }
+    report.warnings.extend(config.load_warnings());
+    report
+}
+
+fn default_config_json() -> Value {
+    let config: Config = serde_yaml::from_str("{}").expect("config fields all have defaults");
+    serde_json::to_value(config).expect("config serializes to JSON")
+}
+
+fn known_top_level_keys() -> Vec<String> {
+    match default_config_json() {
+        Value::Object(map) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// JSON Schema (draft-07) for the config file, inferred from the default
+/// config: top-level keys are closed so typos are flagged, nested sections
+/// stay open.
+pub fn config_json_schema() -> Value {
+    let mut schema = infer_schema(&default_config_json());
+    if let Value::Object(obj) = &mut schema {
+        obj.insert(
+            "$schema".into(),
+            json!("http://json-schema.org/draft-07/schema#"),
+        );
+        obj.insert("title".into(), json!("microclaw.config.yaml"));
+        obj.insert("additionalProperties".into(), json!(false));
+    }
+    schema
+}
+
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        // Optional settings that default to unset accept any value.
+        Value::Null => json!({}),
+        Value::Bool(b) => json!({ "type": "boolean", "default": b }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number", "default": n }),
+        Value::Number(n) => json!({ "type": "integer", "default": n }),
+        Value::String(s) => json!({ "type": "string", "default": s }),
+        Value::Array(items) => match items.first() {
+            Some(first) => json!({ "type": "array", "items": infer_schema(first) }),
+            None => json!({ "type": "array" }),
+        },
+        Value::Object(map) => {
+            let properties: Map<String, Value> = map
+                .iter()
+                .map(|(k, v)| (k.clone(), infer_schema(v)))
+                .collect();
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+/// `microclaw config check [PATH] [--strict]` and `microclaw config schema`.
+pub fn handle_config_cli(args: &[String]) -> anyhow::Result<()> {
+    let cli = match ConfigCli::try_parse_from(
+        std::iter::once("config").chain(args.iter().map(String::as_str)),
+    ) {
+        Ok(cli) => cli,
+        Err(err)
+            if matches!(
+                err.kind(),
+                clap::error::ErrorKind::DisplayHelp
+                    | clap::error::ErrorKind::DisplayVersion
+                    | clap::error::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+            ) =>
+        {
+            err.print()?;
+            return Ok(());
+        }
+        Err(err) => return Err(anyhow::anyhow!(err.to_string())),
+    };
+    match cli.action {
+        ConfigAction::Schema => {
+            println!("{}", serde_json::to_string_pretty(&config_json_schema())?);
+            Ok(())
+        }
+        ConfigAction::Check { path, strict } => {
+            let path = match path {
+                Some(p) => p,
+                None => Config::resolve_config_path()?.ok_or_else(|| {
+                    anyhow::anyhow!("No microclaw.config.yaml found. Pass a path to check.")
+                })?,
+            };
+            let report = check_config_file(&path);
+            for warning in &report.warnings {
+                println!("warning: {warning}");
+            }
+            for error in &report.errors {
+                println!("error: {error}");
+            }
+            let failed = !report.errors.is_empty() || (strict && !report.warnings.is_empty());
+            if failed {
+                return Err(anyhow::anyhow!(
+                    "{} failed validation ({} error(s), {} warning(s))",
+                    path.display(),
+                    report.errors.len(),
+                    report.warnings.len()
+                ));
+            }
+            println!("{} is valid", path.display());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("mc_config_check_{}.yaml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    const VALID: &str = "api_key: k\nchannels:\n  feishu:\n    app_id: a\n    app_secret: s\n";
+
+    #[test]
+    fn check_accepts_valid_config_and_flags_unknown_keys() {
+        let path = write_config(&format!("{VALID}max_tokns: 10\n"));
+        let report = check_config_file(&path);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.warnings, vec!["unknown key 'max_tokns' is ignored"]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn check_reports_type_and_semantic_errors() {
+        let path = write_config(&format!("{VALID}max_tokens: lots\n"));
+        let report = check_config_file(&path);
+        assert_eq!(report.errors.len(), 1);
+        assert!(
+            report.errors[0].contains("max_tokens"),
+            "{:?}",
+            report.errors
+        );
+        let _ = std::fs::remove_file(&path);
+
+        let path = write_config(&format!("{VALID}timezone: Mars/Olympus\n"));
+        let report = check_config_file(&path);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].contains("Invalid timezone"));
+        let _ = std::fs::remove_file(&path);
+
+        let report = check_config_file(Path::new("/nonexistent/microclaw.config.yaml"));
+        assert!(report.errors[0].starts_with("failed to read"));
+    }
+
+    #[test]
+    fn schema_closes_top_level_and_types_fields() {
+        let schema = config_json_schema();
+        assert_eq!(schema["additionalProperties"], json!(false));
+        let props = &schema["properties"];
+        assert_eq!(props["max_tokens"]["type"], "integer");
+        assert_eq!(props["llm_provider"]["type"], "string");
+        assert_eq!(props["show_thinking"]["type"], "boolean");
+        assert_eq!(props["memory_dedup_similarity_threshold"]["type"], "number");
+        assert_eq!(props["clawhub_registry"]["type"], "string");
+        assert_eq!(props["sandbox"]["type"], "object");
+        assert!(props["sandbox"].get("additionalProperties").is_none());
+    }
+}
@@ -0,0 +1,73 @@
+//! `microclaw db`: maintenance commands for the runtime database.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::config::Config;
+use microclaw_storage::db::encrypt_database;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "microclaw db",
+    about = "Maintain the runtime database",
+    disable_help_subcommand = true
+)]
+struct DbCli {
+    #[command(subcommand)]
+    action: DbAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum DbAction {
+    /// Encrypt an existing plaintext database with the db_encryption key
+    Encrypt,
+    /// Write a zip of the config (secrets masked) and, optionally, a database snapshot
+    Backup {
+        /// Include a consistent snapshot of microclaw.db
+        #[arg(long)]
+        include_db: bool,
+        /// Archive path (default: microclaw-backup-<timestamp>.zip here)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// `microclaw db encrypt|backup`. Stop the runtime before `encrypt`: the file
+/// is replaced in place. `backup` is safe while it runs.
+pub async fn handle_db_cli(args: &[String]) -> anyhow::Result<()> {
+    let cli =
+        match DbCli::try_parse_from(std::iter::once("db").chain(args.iter().map(String::as_str))) {
+            Ok(cli) => cli,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    clap::error::ErrorKind::DisplayHelp
+                        | clap::error::ErrorKind::DisplayVersion
+                        | clap::error::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+                ) =>
+            {
+                err.print()?;
+                return Ok(());
+            }
+            Err(err) => return Err(anyhow::anyhow!(err.to_string())),
+        };
+    let config = Config::load()?;
+    match cli.action {
+        DbAction::Encrypt => {
+            let key = config.db_encryption_key()?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "db_encryption is not enabled; set db_encryption.enabled and key_env or key_file first so the runtime can open the encrypted database"
+                )
+            })?;
+            let runtime_dir = config.runtime_data_dir();
+            encrypt_database(&runtime_dir, &key)?;
+            println!("Encrypted {runtime_dir}/microclaw.db.");
+        }
+        DbAction::Backup { include_db, output } => {
+            let path = crate::backup::write_backup(&config, include_db, output).await?;
+            println!("Wrote {}.", path.display());
+        }
+    }
+    Ok(())
+}
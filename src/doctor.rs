@@ -6,7 +6,9 @@ use serde::Serialize;
 
 use crate::config::Config;
 use crate::config::SandboxMode;
-use crate::mcp::McpConfig;
+use crate::mcp::{collect_mcp_config_paths, McpConfig};
+use crate::setup::DYNAMIC_CHANNELS;
+use crate::setup_def::DynamicChannelDef;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -103,7 +105,7 @@ impl DoctorReport {
 #[command(
     name = "microclaw doctor",
     about = "Preflight diagnostics",
-    long_about = "Checks PATH, shell/runtime dependencies, browser automation prerequisites, MCP command dependencies, and sandbox readiness."
+    long_about = "Checks PATH, shell/runtime dependencies, browser automation prerequisites, MCP command dependencies, and sandbox readiness. `doctor mcp` also launches each MCP server and reports its tool count. `doctor smoke` runs one canned turn through the agent with the real LLM config against an offline channel. `doctor embedding` checks that the vector index matches the embedding provider. `doctor channels` checks each enabled channel account's credentials with a lightweight identity call."
 )]
 struct DoctorCli {
     #[command(subcommand)]
@@ -115,6 +117,22 @@ struct DoctorCli {
 #[derive(Debug, Subcommand)]
 enum DoctorCommand {
     Sandbox,
+    /// Validate MCP config fragments and launch each server with a handshake.
+    Mcp {
+        /// Seconds to wait for each server's initialize + tools/list handshake.
+        #[arg(long, default_value_t = 10)]
+        timeout_secs: u64,
+    },
+    /// Run a trivial tool and one agent turn end to end, without touching live channels.
+    Smoke {
+        /// Seconds to wait for each stage.
+        #[arg(long, default_value_t = 60)]
+        timeout_secs: u64,
+    },
+    /// Check the sqlite-vec build and that the vector index dimension matches the provider.
+    Embedding,
+    /// Check each enabled channel account's credentials without starting the bot.
+    Channels,
 }
 
 pub fn run_cli(args: &[String]) -> anyhow::Result<()> {
@@ -134,7 +152,6 @@ pub fn run_cli(args: &[String]) -> anyhow::Result<()> {
         Err(err) => return Err(anyhow::anyhow!(err.to_string())),
     };
     let json_output = cli.json;
-    let sandbox_only = matches!(cli.command, Some(DoctorCommand::Sandbox));
 
     match migrate_channels_config() {
         Ok(Some((path, changed))) => {
@@ -154,10 +171,17 @@ pub fn run_cli(args: &[String]) -> anyhow::Result<()> {
         }
     }
 
-    let report = if sandbox_only {
-        build_sandbox_report()
-    } else {
-        build_report()
+    let report = match cli.command {
+        Some(DoctorCommand::Sandbox) => build_sandbox_report(),
+        Some(DoctorCommand::Mcp { timeout_secs }) => {
+            build_mcp_report(std::time::Duration::from_secs(timeout_secs.max(1)))
+        }
+        Some(DoctorCommand::Smoke { timeout_secs }) => {
+            build_smoke_report(std::time::Duration::from_secs(timeout_secs.max(1)))
+        }
+        Some(DoctorCommand::Embedding) => build_embedding_report(),
+        Some(DoctorCommand::Channels) => build_channels_report(),
+        None => build_report(),
     };
 
     if json_output {
@@ -312,7 +336,27 @@ fn build_report() -> DoctorReport {
     check_shell(&mut report);
     check_browser_dependency(&mut report);
     check_mcp_dependencies(&mut report);
+    check_channel_delivery(&mut report);
+
+    report
+}
 
+fn build_mcp_report(handshake_timeout: std::time::Duration) -> DoctorReport {
+    let mut report = DoctorReport::new();
+    report.push(
+        "env.platform",
+        "Platform",
+        CheckStatus::Pass,
+        format!(
+            "os={} arch={} wsl={}",
+            report.platform, report.arch, report.in_wsl
+        ),
+        None,
+    );
+    check_config(&mut report);
+    if let Some(loaded) = check_mcp_dependencies(&mut report) {
+        check_mcp_handshakes(&mut report, loaded, handshake_timeout);
+    }
     report
 }
 
@@ -337,6 +381,462 @@ fn build_sandbox_report() -> DoctorReport {
     report
 }
 
+fn build_embedding_report() -> DoctorReport {
+    let mut report = DoctorReport::new();
+    report.push(
+        "env.platform",
+        "Platform",
+        CheckStatus::Pass,
+        format!(
+            "os={} arch={} wsl={}",
+            report.platform, report.arch, report.in_wsl
+        ),
+        None,
+    );
+    check_config(&mut report);
+    check_embedding(&mut report);
+    report
+}
+
+fn build_channels_report() -> DoctorReport {
+    let mut report = DoctorReport::new();
+    report.push(
+        "env.platform",
+        "Platform",
+        CheckStatus::Pass,
+        format!(
+            "os={} arch={} wsl={}",
+            report.platform, report.arch, report.in_wsl
+        ),
+        None,
+    );
+    check_config(&mut report);
+    match Config::load() {
+        Ok(config) => check_channel_credentials(&mut report, &config, DYNAMIC_CHANNELS),
+        Err(err) => report.push(
+            "channels.auth",
+            "Channel credentials",
+            CheckStatus::Fail,
+            err.to_string(),
+            Some("Run `microclaw setup` or fix the config first.".to_string()),
+        ),
+    }
+    report
+}
+
+/// Run each enabled channel's validation hook and report one check per account.
+fn check_channel_credentials(
+    report: &mut DoctorReport,
+    config: &Config,
+    channels: &[DynamicChannelDef],
+) {
+    let mut pending = Vec::new();
+    let mut enabled = 0usize;
+    for def in channels {
+        if !config.channel_enabled(def.name) {
+            continue;
+        }
+        enabled += 1;
+        match def.validate {
+            Some(validate) => pending.push((def.name, validate(config))),
+            None => report.push(
+                format!("channels.auth.{}", def.name),
+                format!("Channel credentials ({})", def.name),
+                CheckStatus::Miss,
+                "no identity call available for this channel; skipped",
+                None,
+            ),
+        }
+    }
+    if pending.is_empty() {
+        if enabled == 0 {
+            report.push(
+                "channels.auth",
+                "Channel credentials",
+                CheckStatus::Warn,
+                "no channel is enabled",
+                Some("Enable a channel under `channels:` or run `microclaw setup`.".to_string()),
+            );
+        }
+        return;
+    }
+    // Run on a dedicated thread so this works whether or not the caller is inside a runtime.
+    let results = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("failed to start runtime: {e}"))?;
+        let checks = pending
+            .into_iter()
+            .map(|(name, checks)| async move { (name, checks.await) });
+        Ok::<_, String>(runtime.block_on(futures_util::future::join_all(checks)))
+    })
+    .join()
+    .unwrap_or_else(|_| Err("channel check thread panicked".to_string()));
+    let results = match results {
+        Ok(results) => results,
+        Err(err) => {
+            report.push(
+                "channels.auth",
+                "Channel credentials",
+                CheckStatus::Fail,
+                err,
+                None,
+            );
+            return;
+        }
+    };
+    for (name, checks) in results {
+        if checks.is_empty() {
+            report.push(
+                format!("channels.auth.{name}"),
+                format!("Channel credentials ({name})"),
+                CheckStatus::Warn,
+                "channel is enabled but no account has credentials",
+                Some(format!("Fill in the credentials under `channels.{name}`.")),
+            );
+        }
+        for check in checks {
+            let channel = check.channel_name;
+            match check.result {
+                Ok(identity) => report.push(
+                    format!("channels.auth.{channel}"),
+                    format!("Channel credentials ({channel})"),
+                    CheckStatus::Pass,
+                    format!("ok: {identity}"),
+                    None,
+                ),
+                Err(err) => report.push(
+                    format!("channels.auth.{channel}"),
+                    format!("Channel credentials ({channel})"),
+                    CheckStatus::Fail,
+                    err,
+                    Some(format!("Check the credentials under `channels.{name}`.")),
+                ),
+            }
+        }
+    }
+}
+
+fn build_smoke_report(stage_timeout: std::time::Duration) -> DoctorReport {
+    let mut report = DoctorReport::new();
+    report.push(
+        "env.platform",
+        "Platform",
+        CheckStatus::Pass,
+        format!(
+            "os={} arch={} wsl={}",
+            report.platform, report.arch, report.in_wsl
+        ),
+        None,
+    );
+    check_config(&mut report);
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            report.push(
+                "smoke.config",
+                "Smoke: load config",
+                CheckStatus::Fail,
+                err.to_string(),
+                Some("Run `microclaw setup` or fix the config before smoke testing.".to_string()),
+            );
+            return report;
+        }
+    };
+    let llm = crate::llm::create_provider(&config);
+    // Run on a dedicated thread so this works whether or not the caller is inside a runtime.
+    let stages = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .map_err(|e| format!("failed to start runtime: {e}"))?;
+        let mut stages = DoctorReport::new();
+        runtime.block_on(run_smoke(&mut stages, config, llm, stage_timeout));
+        Ok::<_, String>(stages.checks)
+    })
+    .join()
+    .unwrap_or_else(|_| Err("smoke thread panicked".to_string()));
+    match stages {
+        Ok(checks) => report.checks.extend(checks),
+        Err(err) => report.push("smoke.run", "Smoke run", CheckStatus::Fail, err, None),
+    }
+    report
+}
+
+const SMOKE_CHANNEL: &str = "doctor_smoke";
+const SMOKE_CHAT_TYPE: &str = "doctor_smoke_private";
+const SMOKE_MARKER: &str = "SMOKE_OK";
+const SMOKE_PROMPT: &str =
+    "This is an automated MicroClaw health check. Reply with exactly: SMOKE_OK";
+
+/// Offline channel for the smoke turn. Registered instead of the configured
+/// channels, so nothing can be delivered to a live chat.
+struct SmokeAdapter;
+
+#[async_trait::async_trait]
+impl microclaw_channels::channel_adapter::ChannelAdapter for SmokeAdapter {
+    fn name(&self) -> &str {
+        SMOKE_CHANNEL
+    }
+
+    fn chat_type_routes(&self) -> Vec<(&str, microclaw_channels::channel::ConversationKind)> {
+        vec![(
+            SMOKE_CHAT_TYPE,
+            microclaw_channels::channel::ConversationKind::Private,
+        )]
+    }
+
+    fn is_local_only(&self) -> bool {
+        true
+    }
+
+    fn allows_cross_chat(&self) -> bool {
+        false
+    }
+
+    async fn send_text(&self, _external_chat_id: &str, _text: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Build an isolated app state from the real config: the configured LLM and
+/// tools, but a throwaway data dir and only the offline smoke channel.
+fn build_smoke_state(
+    mut config: Config,
+    llm: Box<dyn crate::llm::LlmProvider>,
+    base_dir: &Path,
+) -> Result<crate::runtime::AppState, String> {
+    use microclaw_storage::db::Database;
+    use std::sync::Arc;
+
+    let skills_dir = config.skills_data_dir();
+    config.data_dir = base_dir.to_string_lossy().to_string();
+    config.working_dir = base_dir.join("tmp").to_string_lossy().to_string();
+    let runtime_dir = config.runtime_data_dir();
+    std::fs::create_dir_all(&runtime_dir).map_err(|e| format!("create {runtime_dir}: {e}"))?;
+    let db = Arc::new(Database::new(&runtime_dir).map_err(|e| e.to_string())?);
+    let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
+    let mut registry = microclaw_channels::channel_adapter::ChannelRegistry::new();
+    registry.register(Arc::new(SmokeAdapter));
+    let channel_registry = Arc::new(registry);
+    Ok(crate::runtime::AppState {
+        tools: crate::tools::ToolRegistry::new(
+            &config,
+            channel_registry.clone(),
+            db.clone(),
+            memory_backend.clone(),
+        ),
+        memory: crate::memory::MemoryManager::new(&runtime_dir),
+        skills: crate::skills::SkillManager::from_skills_dir(&skills_dir),
+        hooks: Arc::new(crate::hooks::HookManager::from_config(&config)),
+        config,
+        channel_registry,
+        db,
+        llm,
+        llm_model_overrides: HashMap::new(),
+        embedding: None,
+        memory_backend,
+        mcp_manager: tokio::sync::Mutex::new(crate::mcp::McpManager::default()),
+    })
+}
+
+async fn run_smoke(
+    report: &mut DoctorReport,
+    config: Config,
+    llm: Box<dyn crate::llm::LlmProvider>,
+    stage_timeout: std::time::Duration,
+) {
+    use crate::agent_engine::{process_with_agent_with_events, AgentEvent, AgentRequestContext};
+    use std::time::Instant;
+
+    let base_dir = std::env::temp_dir().join(format!("microclaw-smoke-{}", uuid::Uuid::new_v4()));
+    let started = Instant::now();
+    let state = match build_smoke_state(config, llm, &base_dir) {
+        Ok(state) => state,
+        Err(err) => {
+            report.push(
+                "smoke.setup",
+                "Smoke: runtime setup",
+                CheckStatus::Fail,
+                err,
+                None,
+            );
+            let _ = std::fs::remove_dir_all(&base_dir);
+            return;
+        }
+    };
+    report.push(
+        "smoke.setup",
+        "Smoke: runtime setup",
+        CheckStatus::Pass,
+        format!(
+            "{} tool(s) registered ({} ms)",
+            state.tools.definitions().len(),
+            started.elapsed().as_millis()
+        ),
+        None,
+    );
+
+    let started = Instant::now();
+    let tool_result = tokio::time::timeout(
+        stage_timeout,
+        state
+            .tools
+            .execute("get_current_time", serde_json::json!({})),
+    )
+    .await;
+    let elapsed_ms = started.elapsed().as_millis();
+    match tool_result {
+        Ok(result) if !result.is_error => report.push(
+            "smoke.tool",
+            "Smoke: get_current_time",
+            CheckStatus::Pass,
+            format!(
+                "{} ({elapsed_ms} ms)",
+                result.content.lines().next().unwrap_or_default()
+            ),
+            None,
+        ),
+        Ok(result) => report.push(
+            "smoke.tool",
+            "Smoke: get_current_time",
+            CheckStatus::Fail,
+            format!("{} ({elapsed_ms} ms)", result.content),
+            Some("Check `timezone` in the config.".to_string()),
+        ),
+        Err(_) => report.push(
+            "smoke.tool",
+            "Smoke: get_current_time",
+            CheckStatus::Fail,
+            format!("timed out after {}s", stage_timeout.as_secs()),
+            None,
+        ),
+    }
+
+    let chat_id = match state.db.resolve_or_create_chat_id(
+        SMOKE_CHANNEL,
+        "doctor-smoke",
+        Some("doctor smoke"),
+        SMOKE_CHAT_TYPE,
+    ) {
+        Ok(chat_id) => chat_id,
+        Err(err) => {
+            report.push(
+                "smoke.agent",
+                "Smoke: agent turn",
+                CheckStatus::Fail,
+                format!("failed to create smoke chat: {err}"),
+                None,
+            );
+            let _ = std::fs::remove_dir_all(&base_dir);
+            return;
+        }
+    };
+    let stored = state
+        .db
+        .store_message(&microclaw_storage::db::StoredMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            chat_id,
+            sender_name: "doctor".to_string(),
+            content: SMOKE_PROMPT.to_string(),
+            is_from_bot: false,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    if let Err(err) = stored {
+        report.push(
+            "smoke.agent",
+            "Smoke: agent turn",
+            CheckStatus::Fail,
+            format!("failed to store smoke prompt: {err}"),
+            None,
+        );
+        let _ = std::fs::remove_dir_all(&base_dir);
+        return;
+    }
+
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel();
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(
+        stage_timeout,
+        process_with_agent_with_events(
+            &state,
+            AgentRequestContext {
+                caller_channel: SMOKE_CHANNEL,
+                chat_id,
+                chat_type: "private",
+                sender_id: None,
+                sender_is_admin: false,
+            },
+            None,
+            None,
+            Some(&event_tx),
+        ),
+    )
+    .await;
+    let elapsed_ms = started.elapsed().as_millis();
+    drop(event_tx);
+    let mut llm_rounds = 0usize;
+    while let Ok(event) = event_rx.try_recv() {
+        if matches!(event, AgentEvent::Iteration { .. }) {
+            llm_rounds += 1;
+        }
+    }
+    let llm_fix = Some("Check `llm_provider`, `api_key`, `model`, and `llm_base_url`.".to_string());
+    match outcome {
+        Ok(Ok(reply)) => {
+            let reply = reply.trim();
+            let preview: String = reply.chars().take(80).collect();
+            let detail = format!("{llm_rounds} LLM round(s), reply {preview:?} ({elapsed_ms} ms)");
+            if reply.contains(SMOKE_MARKER) {
+                report.push(
+                    "smoke.agent",
+                    "Smoke: agent turn",
+                    CheckStatus::Pass,
+                    detail,
+                    None,
+                );
+            } else if reply.is_empty() {
+                report.push(
+                    "smoke.agent",
+                    "Smoke: agent turn",
+                    CheckStatus::Fail,
+                    detail,
+                    llm_fix,
+                );
+            } else {
+                report.push(
+                    "smoke.agent",
+                    "Smoke: agent turn",
+                    CheckStatus::Warn,
+                    detail,
+                    Some(format!(
+                        "The model replied but did not echo {SMOKE_MARKER}; check the model choice and system prompt."
+                    )),
+                );
+            }
+        }
+        Ok(Err(err)) => report.push(
+            "smoke.agent",
+            "Smoke: agent turn",
+            CheckStatus::Fail,
+            format!("{err} ({elapsed_ms} ms)"),
+            llm_fix,
+        ),
+        Err(_) => report.push(
+            "smoke.agent",
+            "Smoke: agent turn",
+            CheckStatus::Fail,
+            format!("timed out after {}s", stage_timeout.as_secs()),
+            llm_fix,
+        ),
+    }
+
+    drop(state);
+    let _ = std::fs::remove_dir_all(&base_dir);
+}
+
 fn check_config(report: &mut DoctorReport) {
     match Config::resolve_config_path() {
         Ok(Some(path)) => report.push(
@@ -718,7 +1218,13 @@ fn check_browser_dependency(report: &mut DoctorReport) {
     );
 }
 
-fn check_mcp_dependencies(report: &mut DoctorReport) {
+/// MCP servers merged from every readable config fragment; later fragments win.
+struct LoadedMcpServers {
+    default_protocol_version: Option<String>,
+    servers: HashMap<String, crate::mcp::McpServerConfig>,
+}
+
+fn check_mcp_dependencies(report: &mut DoctorReport) -> Option<LoadedMcpServers> {
     let data_root = match Config::load() {
         Ok(cfg) => cfg.data_root_dir(),
         Err(_) => PathBuf::from("./microclaw.data"),
@@ -741,10 +1247,11 @@ fn check_mcp_dependencies(report: &mut DoctorReport) {
             ),
             Some("Create mcp.json or mcp.d/*.json if you need MCP servers.".to_string()),
         );
-        return;
+        return None;
     }
 
     let mut merged_servers: HashMap<String, crate::mcp::McpServerConfig> = HashMap::new();
+    let mut default_protocol_version = None;
     let mut loaded_sources = 0usize;
     for path in &existing_paths {
         let content = match std::fs::read_to_string(path) {
@@ -774,12 +1281,15 @@ fn check_mcp_dependencies(report: &mut DoctorReport) {
             }
         };
         loaded_sources += 1;
+        if parsed.default_protocol_version.is_some() {
+            default_protocol_version = parsed.default_protocol_version;
+        }
         for (name, server) in parsed.mcp_servers {
             merged_servers.insert(name, server);
         }
     }
     if loaded_sources == 0 {
-        return;
+        return None;
     }
 
     report.push(
@@ -800,6 +1310,16 @@ fn check_mcp_dependencies(report: &mut DoctorReport) {
         let Some(server) = merged_servers.get(&name) else {
             continue;
         };
+        if let Err(err) = server.validate() {
+            report.push(
+                format!("mcp.{name}.config"),
+                format!("MCP server '{name}' config"),
+                CheckStatus::Fail,
+                err,
+                Some("Fix the server entry in its MCP config fragment.".to_string()),
+            );
+            continue;
+        }
         let transport = server.transport.trim().to_ascii_lowercase();
         if transport == "streamable_http" || transport == "http" {
             if server.endpoint.trim().is_empty() {
@@ -852,22 +1372,313 @@ fn check_mcp_dependencies(report: &mut DoctorReport) {
             );
         }
     }
+
+    Some(LoadedMcpServers {
+        default_protocol_version,
+        servers: merged_servers,
+    })
+}
+
+/// Launch every statically valid MCP server and report its handshake result.
+fn check_mcp_handshakes(
+    report: &mut DoctorReport,
+    loaded: LoadedMcpServers,
+    timeout: std::time::Duration,
+) {
+    let mut entries: Vec<(String, crate::mcp::McpServerConfig)> = loaded
+        .servers
+        .into_iter()
+        .filter(|(_, server)| server.validate().is_ok())
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    if entries.is_empty() {
+        return;
+    }
+    let default_protocol_version = loaded.default_protocol_version;
+    let names: Vec<String> = entries.iter().map(|(name, _)| name.clone()).collect();
+    // Run on a dedicated thread so this works whether or not the caller is inside a runtime.
+    let results = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("failed to start runtime: {e}"))?;
+        Ok::<_, String>(runtime.block_on(async move {
+            let mut results = Vec::new();
+            for (name, server) in &entries {
+                let outcome = match tokio::time::timeout(
+                    timeout,
+                    crate::mcp::McpServer::connect(
+                        name,
+                        server,
+                        default_protocol_version.as_deref(),
+                        timeout.as_secs(),
+                    ),
+                )
+                .await
+                {
+                    Ok(Ok(connected)) => Ok((
+                        connected.tools_snapshot().len(),
+                        connected.protocol_version(),
+                    )),
+                    Ok(Err(err)) => Err(err),
+                    Err(_) => Err(format!("handshake timed out after {}s", timeout.as_secs())),
+                };
+                results.push(outcome);
+            }
+            results
+        }))
+    })
+    .join()
+    .unwrap_or_else(|_| Err("handshake thread panicked".to_string()));
+
+    let results = match results {
+        Ok(results) => results,
+        Err(err) => {
+            report.push(
+                "mcp.handshake",
+                "MCP handshake",
+                CheckStatus::Fail,
+                err,
+                None,
+            );
+            return;
+        }
+    };
+    for (name, outcome) in names.into_iter().zip(results) {
+        match outcome {
+            Ok((tool_count, protocol)) => report.push(
+                format!("mcp.{name}.handshake"),
+                format!("MCP server '{name}' handshake"),
+                if tool_count == 0 {
+                    CheckStatus::Warn
+                } else {
+                    CheckStatus::Pass
+                },
+                format!("connected: {tool_count} tool(s), protocol {protocol}"),
+                (tool_count == 0).then(|| "Server connected but exposes no tools.".to_string()),
+            ),
+            Err(err) => report.push(
+                format!("mcp.{name}.handshake"),
+                format!("MCP server '{name}' handshake"),
+                CheckStatus::Fail,
+                err,
+                Some(
+                    "Run the server command by hand to see its error, or raise --timeout-secs."
+                        .to_string(),
+                ),
+            ),
+        }
+    }
+}
+
+fn check_channel_delivery(report: &mut DoctorReport) {
+    let config = match Config::load() {
+        Ok(cfg) => cfg,
+        Err(_) => return,
+    };
+    let runtime_dir = config.runtime_data_dir();
+    if !Path::new(&runtime_dir).join("microclaw.db").exists() {
+        return;
+    }
+    let db = match config.open_database() {
+        Ok(db) => db,
+        Err(err) => {
+            report.push(
+                "channels.delivery",
+                "Channel delivery",
+                CheckStatus::Warn,
+                format!("failed to open database: {err}"),
+                None,
+            );
+            return;
+        }
+    };
+    let logs = db
+        .list_audit_logs(
+            Some(microclaw_channels::channel::CHANNEL_DELIVERY_AUDIT_KIND),
+            500,
+        )
+        .unwrap_or_default();
+    let failing = failing_channels_from_audit(&logs);
+    if failing.is_empty() {
+        report.push(
+            "channels.delivery",
+            "Channel delivery",
+            CheckStatus::Pass,
+            "no channel is in a failing send state".to_string(),
+            None,
+        );
+        return;
+    }
+    for (channel, since, detail) in failing {
+        report.push(
+            format!("channels.delivery.{channel}"),
+            format!("Channel delivery ({channel})"),
+            CheckStatus::Warn,
+            format!(
+                "failing since {since}: {}",
+                detail.unwrap_or_else(|| "unknown error".to_string())
+            ),
+            Some("Check the channel token/credentials and provider rate limits.".to_string()),
+        );
+    }
 }
 
-fn collect_mcp_config_paths(data_root: &Path) -> Vec<PathBuf> {
-    let mut paths = vec![data_root.join("mcp.json")];
-    let mcp_dir = data_root.join("mcp.d");
-    let mut fragments = match std::fs::read_dir(&mcp_dir) {
-        Ok(entries) => entries
-            .flatten()
-            .map(|entry| entry.path())
-            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("json"))
-            .collect::<Vec<_>>(),
-        Err(_) => Vec::new(),
+fn check_embedding(report: &mut DoctorReport) {
+    let config = match Config::load() {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            report.push(
+                "embedding.config",
+                "Embedding config",
+                CheckStatus::Warn,
+                format!("config unavailable: {err}"),
+                Some("Run `microclaw setup` first.".to_string()),
+            );
+            return;
+        }
+    };
+    let Some(provider_name) = config.embedding_provider.clone() else {
+        report.push(
+            "embedding.provider",
+            "Embedding provider",
+            CheckStatus::Miss,
+            "embedding_provider is not set; memory recall uses keyword matching only",
+            None,
+        );
+        return;
+    };
+    let uses_sqlite_vec = config.memory_backend != "qdrant";
+    if uses_sqlite_vec {
+        let compiled = cfg!(feature = "sqlite-vec");
+        report.push(
+            "embedding.sqlite_vec",
+            "sqlite-vec feature",
+            if compiled {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Fail
+            },
+            if compiled {
+                "compiled in".to_string()
+            } else {
+                "embedding_provider is set, but this build lacks sqlite-vec, so embeddings are never used".to_string()
+            },
+            (!compiled).then(|| {
+                "Rebuild with `cargo build --release --features sqlite-vec`.".to_string()
+            }),
+        );
+        if !compiled {
+            return;
+        }
+    }
+
+    let Some(provider) = crate::embedding::create_provider(&config) else {
+        report.push(
+            "embedding.provider",
+            "Embedding provider",
+            CheckStatus::Fail,
+            format!("embedding provider '{provider_name}' could not be initialized"),
+            Some(
+                "Use `openai` (with `embedding_api_key`) or `ollama` for `embedding_provider`."
+                    .to_string(),
+            ),
+        );
+        return;
+    };
+    let dimension = provider.dimension();
+    report.push(
+        "embedding.provider",
+        "Embedding provider",
+        CheckStatus::Pass,
+        format!(
+            "provider={provider_name} model={} dimension={dimension}",
+            provider.model()
+        ),
+        None,
+    );
+    if !uses_sqlite_vec {
+        return;
+    }
+
+    let runtime_dir = config.runtime_data_dir();
+    let stored = if Path::new(&runtime_dir).join("microclaw.db").exists() {
+        match config
+            .open_database()
+            .and_then(|db| db.vector_index_dimension())
+        {
+            Ok(stored) => stored,
+            Err(err) => {
+                report.push(
+                    "embedding.dimension",
+                    "Vector index dimension",
+                    CheckStatus::Warn,
+                    format!("failed to read database: {err}"),
+                    None,
+                );
+                return;
+            }
+        }
+    } else {
+        None
     };
-    fragments.sort();
-    paths.extend(fragments);
-    paths
+    let (status, detail, fix) = classify_embedding_dimension(dimension, stored);
+    report.push(
+        "embedding.dimension",
+        "Vector index dimension",
+        status,
+        detail,
+        fix,
+    );
+}
+
+/// Compare the provider's dimension with the one the vector index was built for.
+fn classify_embedding_dimension(
+    provider_dim: usize,
+    index_dim: Option<usize>,
+) -> (CheckStatus, String, Option<String>) {
+    match index_dim {
+        None => (
+            CheckStatus::Pass,
+            format!("no vector index yet; it is created with dimension {provider_dim} on start"),
+            None,
+        ),
+        Some(dim) if dim == provider_dim => (
+            CheckStatus::Pass,
+            format!("index={dim} provider={provider_dim}"),
+            None,
+        ),
+        Some(dim) => (
+            CheckStatus::Warn,
+            format!(
+                "index={dim} provider={provider_dim}: the index was built for another embedding model, so stored memories have no usable vectors"
+            ),
+            Some("Run `microclaw reembed` to rebuild the index with the current provider.".into()),
+        ),
+    }
+}
+
+/// Latest delivery state per channel from newest-first audit rows; returns channels
+/// whose most recent transition is `failing`.
+fn failing_channels_from_audit(
+    logs: &[microclaw_storage::db::AuditLogRecord],
+) -> Vec<(String, String, Option<String>)> {
+    let mut seen = std::collections::HashSet::new();
+    let mut failing = Vec::new();
+    for log in logs {
+        if !seen.insert(log.actor.clone()) {
+            continue;
+        }
+        if log.status == "failing" {
+            failing.push((
+                log.actor.clone(),
+                log.created_at.clone(),
+                log.detail.clone(),
+            ));
+        }
+    }
+    failing.sort();
+    failing
 }
 
 fn check_sandbox_config(report: &mut DoctorReport) {
@@ -911,6 +1722,29 @@ fn check_sandbox_config(report: &mut DoctorReport) {
             None
         },
     );
+    if let Some(advanced) = &config.sandbox.advanced {
+        let program = advanced
+            .command_template
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let found = command_exists(program);
+        report.push(
+            "sandbox.command_template",
+            "Sandbox command template",
+            if found {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Fail
+            },
+            advanced.command_template.clone(),
+            if found {
+                None
+            } else {
+                Some(format!("`{program}` was not found on PATH."))
+            },
+        );
+    }
 }
 
 fn check_docker_runtime(report: &mut DoctorReport) {
@@ -1087,6 +1921,182 @@ fn default_mount_allowlist_path() -> Option<PathBuf> {
     user_home_dir().map(|h| h.join(".microclaw/sandbox-mount-allowlist.txt"))
 }
 
+/// Startup self-test of the external dependencies tools need at call time.
+pub struct ToolDepsSelfTest {
+    pub report: DoctorReport,
+    /// Tools whose dependency check failed; they cannot work in this process.
+    pub unavailable_tools: Vec<String>,
+}
+
+impl ToolDepsSelfTest {
+    pub fn has_failures(&self) -> bool {
+        !self.unavailable_tools.is_empty()
+    }
+
+    /// Doctor-style lines: one per check, then the counts.
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .report
+            .checks
+            .iter()
+            .map(|check| {
+                let mut line = format!(
+                    "[{} {}] {} ({}) {}",
+                    check.status.as_emoji(),
+                    check.status.as_label(),
+                    check.title,
+                    check.id,
+                    check.detail
+                );
+                if let Some(fix) = &check.fix {
+                    line.push_str(&format!(" -- fix: {fix}"));
+                }
+                line
+            })
+            .collect();
+        let (pass, miss, warn, fail) = self.report.summary();
+        lines.push(format!(
+            "Tool dependency self-test: pass={pass} miss={miss} warn={warn} fail={fail}"
+        ));
+        lines
+    }
+}
+
+/// Probe the tools' external dependencies. The search backend only has to
+/// answer HTTP at all; the sandbox runtime was probed when the tool registry
+/// was built.
+pub async fn tool_deps_self_test(
+    config: &Config,
+    sandbox_runtime_available: bool,
+) -> ToolDepsSelfTest {
+    let browser_found = command_exists(&microclaw_tools::command_runner::agent_browser_program())
+        || command_exists("agent-browser");
+    let search_probe = match search_backend_url(config) {
+        Some(url) => Some((url.clone(), probe_http(&url).await)),
+        None => None,
+    };
+    evaluate_tool_deps(
+        config,
+        sandbox_runtime_available,
+        browser_found,
+        search_probe,
+    )
+}
+
+fn search_backend_url(config: &Config) -> Option<String> {
+    if let Some(endpoint) = config.web_search.searxng_endpoint.as_deref() {
+        return Some(endpoint.trim().to_string());
+    }
+    config
+        .web_search
+        .tavily_api_key
+        .as_ref()
+        .map(|_| "https://api.tavily.com".to_string())
+}
+
+async fn probe_http(url: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .map_err(|e| e.to_string())?;
+    client
+        .get(url)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn evaluate_tool_deps(
+    config: &Config,
+    sandbox_runtime_available: bool,
+    browser_found: bool,
+    search_probe: Option<(String, Result<(), String>)>,
+) -> ToolDepsSelfTest {
+    let mut report = DoctorReport::new();
+    let mut unavailable_tools = Vec::new();
+
+    if config.sandbox.mode == SandboxMode::All {
+        if sandbox_runtime_available {
+            report.push(
+                "tools.bash.sandbox",
+                "bash sandbox runtime",
+                CheckStatus::Pass,
+                "docker runtime available",
+                None,
+            );
+        } else if config.sandbox.require_runtime {
+            report.push(
+                "tools.bash.sandbox",
+                "bash sandbox runtime",
+                CheckStatus::Fail,
+                "sandbox requires a docker runtime but none is available; bash cannot run",
+                Some("Start Docker, or set sandbox.require_runtime: false.".to_string()),
+            );
+            unavailable_tools.push("bash".to_string());
+        } else {
+            report.push(
+                "tools.bash.sandbox",
+                "bash sandbox runtime",
+                CheckStatus::Warn,
+                "no docker runtime; bash commands run on the host",
+                Some("Start Docker to sandbox bash commands.".to_string()),
+            );
+        }
+    }
+
+    if browser_found {
+        report.push(
+            "tools.browser",
+            "browser (agent-browser)",
+            CheckStatus::Pass,
+            "agent-browser command found",
+            None,
+        );
+    } else {
+        report.push(
+            "tools.browser",
+            "browser (agent-browser)",
+            CheckStatus::Fail,
+            "agent-browser command not found; web_fetch render falls back to plain fetches",
+            Some("Run `npm install -g agent-browser && agent-browser install`.".to_string()),
+        );
+        unavailable_tools.push("browser".to_string());
+    }
+
+    match search_probe {
+        None => report.push(
+            "tools.web_search",
+            "web_search backend",
+            CheckStatus::Miss,
+            "no search backend configured",
+            Some("Set web_search.searxng_endpoint or web_search.tavily_api_key.".to_string()),
+        ),
+        Some((url, Ok(()))) => report.push(
+            "tools.web_search",
+            "web_search backend",
+            CheckStatus::Pass,
+            format!("{url} reachable"),
+            None,
+        ),
+        Some((url, Err(err))) => {
+            report.push(
+                "tools.web_search",
+                "web_search backend",
+                CheckStatus::Fail,
+                format!("{url} unreachable: {err}"),
+                Some("Check the search backend URL and outbound network access.".to_string()),
+            );
+            unavailable_tools.push("web_search".to_string());
+        }
+    }
+
+    ToolDepsSelfTest {
+        report,
+        unavailable_tools,
+    }
+}
+
 fn print_report(report: &DoctorReport) {
     println!("MicroClaw Doctor");
     println!(
@@ -1295,6 +2305,10 @@ mod tests {
     use super::*;
     use crate::config::Config;
     use chrono::Utc;
+    use microclaw_core::error::MicroClawError;
+    use microclaw_core::llm_types::{
+        Message, MessagesResponse, ResponseContentBlock, ToolDefinition,
+    };
 
     fn env_lock() -> std::sync::MutexGuard<'static, ()> {
         crate::test_support::env_lock()
@@ -1315,6 +2329,55 @@ mod tests {
         assert!(fix.is_none());
     }
 
+    #[test]
+    fn test_classify_embedding_dimension() {
+        let (status, _detail, fix) = classify_embedding_dimension(1536, None);
+        assert_eq!(status, CheckStatus::Pass);
+        assert!(fix.is_none());
+
+        let (status, _detail, fix) = classify_embedding_dimension(1536, Some(1536));
+        assert_eq!(status, CheckStatus::Pass);
+        assert!(fix.is_none());
+
+        let (status, detail, fix) = classify_embedding_dimension(768, Some(1536));
+        assert_eq!(status, CheckStatus::Warn);
+        assert!(detail.contains("index=1536 provider=768"));
+        assert!(fix.unwrap().contains("microclaw reembed"));
+    }
+
+    #[test]
+    fn test_tool_deps_self_test_disables_failed_tools() {
+        let mut cfg = Config::test_defaults();
+        cfg.sandbox.mode = SandboxMode::All;
+        cfg.sandbox.require_runtime = true;
+        let result = evaluate_tool_deps(
+            &cfg,
+            false,
+            false,
+            Some((
+                "https://search.example".into(),
+                Err("connection refused".into()),
+            )),
+        );
+        assert_eq!(
+            result.unavailable_tools,
+            vec!["bash", "browser", "web_search"]
+        );
+        let lines = result.summary_lines();
+        assert!(lines[0].contains("FAIL] bash sandbox runtime (tools.bash.sandbox)"));
+        assert!(lines[2].contains("https://search.example unreachable: connection refused"));
+        assert_eq!(
+            lines.last().unwrap(),
+            "Tool dependency self-test: pass=0 miss=0 warn=0 fail=3"
+        );
+
+        // A sandbox that may fall back to the host only warns.
+        cfg.sandbox.require_runtime = false;
+        let result = evaluate_tool_deps(&cfg, false, true, None);
+        assert!(!result.has_failures());
+        assert_eq!(result.report.summary(), (1, 1, 1, 0));
+    }
+
     #[test]
     fn test_normalize_path_compare() {
         let p = PathBuf::from("/tmp/abc/");
@@ -1343,6 +2406,43 @@ mod tests {
         assert!(report.checks.iter().any(|c| c.id == "sandbox.mode"));
     }
 
+    #[test]
+    fn test_build_embedding_report_checks_sqlite_vec_build() {
+        let _guard = env_lock();
+        let path = std::env::temp_dir().join(format!(
+            "microclaw_doctor_test_{}.yaml",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let mut cfg = Config::test_defaults();
+        cfg.channels.insert(
+            "feishu".to_string(),
+            serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+        );
+        cfg.embedding_provider = Some("ollama".into());
+        cfg.data_dir = std::env::temp_dir()
+            .join(format!("microclaw_doctor_embedding_{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+        cfg.save_yaml(path.to_string_lossy().as_ref()).unwrap();
+        std::env::set_var("MICROCLAW_CONFIG", &path);
+        let report = build_embedding_report();
+        std::env::remove_var("MICROCLAW_CONFIG");
+        let _ = std::fs::remove_file(path);
+
+        let check = report
+            .checks
+            .iter()
+            .find(|c| c.id == "embedding.sqlite_vec")
+            .unwrap();
+        if cfg!(feature = "sqlite-vec") {
+            assert_eq!(check.status, CheckStatus::Pass);
+            assert!(report.checks.iter().any(|c| c.id == "embedding.dimension"));
+        } else {
+            assert_eq!(check.status, CheckStatus::Fail);
+            assert!(check.fix.as_deref().unwrap().contains("sqlite-vec"));
+        }
+    }
+
     #[test]
     fn test_build_report_has_web_fetch_validation_checks() {
         let _guard = env_lock();
@@ -1474,4 +2574,207 @@ feishu:
             Some("already")
         );
     }
+
+    #[test]
+    fn test_failing_channels_from_audit_uses_latest_state() {
+        let row = |id: i64, actor: &str, status: &str| microclaw_storage::db::AuditLogRecord {
+            id,
+            kind: "channel_delivery".into(),
+            actor: actor.into(),
+            action: "send".into(),
+            target: None,
+            status: status.into(),
+            detail: Some("401".into()),
+            created_at: format!("2026-01-01T00:00:0{id}Z"),
+        };
+        // Newest first, as returned by list_audit_logs.
+        let logs = vec![
+            row(4, "feishu", "failing"),
+            row(3, "email", "recovered"),
+            row(2, "email", "failing"),
+            row(1, "feishu", "recovered"),
+        ];
+        let failing = failing_channels_from_audit(&logs);
+        assert_eq!(failing.len(), 1);
+        assert_eq!(failing[0].0, "feishu");
+    }
+
+    #[tokio::test]
+    async fn test_first_success_after_restart_clears_persisted_failure() {
+        use microclaw_channels::channel::{record_send_result, CHANNEL_DELIVERY_AUDIT_KIND};
+        use microclaw_channels::channel_adapter::ChannelRegistry;
+
+        let dir = std::env::temp_dir().join(format!("mc_doctor_health_{}", uuid::Uuid::new_v4()));
+        let db = std::sync::Arc::new(
+            microclaw_storage::db::Database::new(dir.to_str().unwrap()).unwrap(),
+        );
+        // Written by the previous process before it restarted.
+        db.log_audit_event(
+            CHANNEL_DELIVERY_AUDIT_KIND,
+            "feishu",
+            "send",
+            None,
+            "failing",
+            Some("401"),
+        )
+        .unwrap();
+        let logs = || {
+            db.list_audit_logs(Some(CHANNEL_DELIVERY_AUDIT_KIND), 100)
+                .unwrap()
+        };
+        assert_eq!(failing_channels_from_audit(&logs()).len(), 1);
+
+        // A fresh registry has no in-memory state for the channel.
+        let registry = ChannelRegistry::new();
+        record_send_result(&registry, db.clone(), "email", &Ok(())).await;
+        record_send_result(&registry, db.clone(), "feishu", &Ok(())).await;
+        assert!(failing_channels_from_audit(&logs()).is_empty());
+        assert_eq!(logs()[0].status, "recovered");
+
+        record_send_result(&registry, db.clone(), "feishu", &Ok(())).await;
+        assert_eq!(logs().len(), 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_mcp_handshake_reports_per_server_failures() {
+        let server = |value: serde_json::Value| -> crate::mcp::McpServerConfig {
+            serde_json::from_value(value).unwrap()
+        };
+        let loaded = LoadedMcpServers {
+            default_protocol_version: None,
+            servers: HashMap::from([
+                (
+                    "missing".to_string(),
+                    server(serde_json::json!({"command": "/nonexistent/microclaw-mcp-server"})),
+                ),
+                (
+                    "silent".to_string(),
+                    server(serde_json::json!({"command": "sleep", "args": ["30"]})),
+                ),
+                (
+                    "bad_transport".to_string(),
+                    server(serde_json::json!({"transport": "websocket", "command": "x"})),
+                ),
+            ]),
+        };
+        let mut report = DoctorReport::new();
+        check_mcp_handshakes(&mut report, loaded, std::time::Duration::from_secs(1));
+
+        let ids: Vec<&str> = report.checks.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["mcp.missing.handshake", "mcp.silent.handshake"]);
+        assert!(report.checks.iter().all(|c| c.status == CheckStatus::Fail));
+        assert!(report.checks[1].detail.contains("timed out"));
+    }
+
+    struct EchoLlm(&'static str);
+
+    #[async_trait::async_trait]
+    impl crate::llm::LlmProvider for EchoLlm {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            Ok(MessagesResponse {
+                content: vec![ResponseContentBlock::Text {
+                    text: self.0.to_string(),
+                }],
+                stop_reason: Some("end_turn".to_string()),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_smoke_reports_each_stage() {
+        let mut report = DoctorReport::new();
+        run_smoke(
+            &mut report,
+            Config::test_defaults(),
+            Box::new(EchoLlm("SMOKE_OK")),
+            std::time::Duration::from_secs(10),
+        )
+        .await;
+        let ids: Vec<&str> = report.checks.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["smoke.setup", "smoke.tool", "smoke.agent"]);
+        assert!(
+            report.checks.iter().all(|c| c.status == CheckStatus::Pass),
+            "{:?}",
+            report.checks
+        );
+        assert!(report.checks[2].detail.contains("1 LLM round(s)"));
+
+        let mut report = DoctorReport::new();
+        run_smoke(
+            &mut report,
+            Config::test_defaults(),
+            Box::new(EchoLlm("hello")),
+            std::time::Duration::from_secs(10),
+        )
+        .await;
+        assert_eq!(report.checks[2].status, CheckStatus::Warn);
+    }
+
+    fn fake_validate(_config: &Config) -> crate::setup_def::ChannelValidationFuture {
+        Box::pin(std::future::ready(vec![
+            crate::setup_def::ChannelAccountCheck {
+                channel_name: "fake".to_string(),
+                result: Ok("bot_one".to_string()),
+            },
+            crate::setup_def::ChannelAccountCheck {
+                channel_name: "fake.ops".to_string(),
+                result: Err("token error: code=10003".to_string()),
+            },
+        ]))
+    }
+
+    #[test]
+    fn test_channel_credentials_reported_per_account() {
+        let channels = [
+            DynamicChannelDef {
+                name: "fake",
+                presence_keys: &[],
+                fields: &[],
+                validate: Some(fake_validate),
+            },
+            DynamicChannelDef {
+                name: "plain",
+                presence_keys: &[],
+                fields: &[],
+                validate: None,
+            },
+            DynamicChannelDef {
+                name: "off",
+                presence_keys: &[],
+                fields: &[],
+                validate: Some(fake_validate),
+            },
+        ];
+        let mut cfg = Config::test_defaults();
+        for (name, enabled) in [("fake", true), ("plain", true), ("off", false)] {
+            cfg.channels.insert(
+                name.to_string(),
+                serde_yaml::from_str(&format!("enabled: {enabled}")).unwrap(),
+            );
+        }
+        let mut report = DoctorReport::new();
+        check_channel_credentials(&mut report, &cfg, &channels);
+
+        let statuses: Vec<(&str, CheckStatus)> = report
+            .checks
+            .iter()
+            .map(|c| (c.id.as_str(), c.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("channels.auth.plain", CheckStatus::Miss),
+                ("channels.auth.fake", CheckStatus::Pass),
+                ("channels.auth.fake.ops", CheckStatus::Fail),
+            ]
+        );
+        assert_eq!(report.checks[1].detail, "ok: bot_one");
+    }
 }
@@ -55,16 +55,9 @@ struct OllamaEmbeddingResponse {
     embedding: Vec<f32>,
 }
 
-#[cfg(feature = "sqlite-vec")]
 fn infer_default_dim(provider: &str, model: &str) -> usize {
     match provider {
-        "openai" => {
-            if model.contains("3-large") {
-                3072
-            } else {
-                1536
-            }
-        }
+        "openai" if model.contains("3-large") => 3072,
         "ollama" => 1024,
         _ => 1536,
     }
@@ -142,69 +135,65 @@ impl EmbeddingProvider for OllamaEmbeddingProvider {
 }
 
 pub fn create_provider(config: &Config) -> Option<Arc<dyn EmbeddingProvider>> {
-    #[cfg(not(feature = "sqlite-vec"))]
-    {
-        let _ = config;
-        None
+    // Embeddings are only consumed by sqlite-vec or an external vector store.
+    if !cfg!(feature = "sqlite-vec") && config.memory_backend != "qdrant" {
+        return None;
     }
 
-    #[cfg(feature = "sqlite-vec")]
-    {
-        let provider = config
-            .embedding_provider
-            .as_deref()
-            .unwrap_or("")
-            .trim()
-            .to_lowercase();
-        if provider.is_empty() {
-            return None;
-        }
+    let provider = config
+        .embedding_provider
+        .as_deref()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+    if provider.is_empty() {
+        return None;
+    }
 
-        let model = config
-            .embedding_model
-            .clone()
-            .unwrap_or_else(|| match provider.as_str() {
-                "openai" => "text-embedding-3-small".to_string(),
-                "ollama" => "nomic-embed-text".to_string(),
-                _ => "text-embedding-3-small".to_string(),
-            });
-        let dim = config
-            .embedding_dim
-            .unwrap_or_else(|| infer_default_dim(&provider, &model));
-        let client = reqwest::Client::new();
-
-        match provider.as_str() {
-            "openai" => {
-                let api_key = config.embedding_api_key.clone().unwrap_or_default();
-                if api_key.trim().is_empty() {
-                    return None;
-                }
-                let base_url = config
-                    .embedding_base_url
-                    .clone()
-                    .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
-                Some(Arc::new(OpenAIEmbeddingProvider {
-                    client,
-                    base_url,
-                    api_key,
-                    model,
-                    dim,
-                }))
-            }
-            "ollama" => {
-                let base_url = config
-                    .embedding_base_url
-                    .clone()
-                    .unwrap_or_else(|| "http://127.0.0.1:11434".to_string());
-                Some(Arc::new(OllamaEmbeddingProvider {
-                    client,
-                    base_url,
-                    model,
-                    dim,
-                }))
+    let model = config
+        .embedding_model
+        .clone()
+        .unwrap_or_else(|| match provider.as_str() {
+            "openai" => "text-embedding-3-small".to_string(),
+            "ollama" => "nomic-embed-text".to_string(),
+            _ => "text-embedding-3-small".to_string(),
+        });
+    let dim = config
+        .embedding_dim
+        .unwrap_or_else(|| infer_default_dim(&provider, &model));
+    let client = reqwest::Client::new();
+
+    match provider.as_str() {
+        "openai" => {
+            let api_key = config.embedding_api_key.clone().unwrap_or_default();
+            if api_key.trim().is_empty() {
+                return None;
             }
-            _ => None,
+            let base_url = config
+                .embedding_base_url
+                .clone()
+                .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+            Some(Arc::new(OpenAIEmbeddingProvider {
+                client,
+                base_url,
+                api_key,
+                model,
+                dim,
+            }))
         }
+        "ollama" => {
+            let base_url = config
+                .embedding_base_url
+                .clone()
+                .unwrap_or_else(|| "http://127.0.0.1:11434".to_string());
+            Some(Arc::new(OllamaEmbeddingProvider {
+                client,
+                base_url,
+                model,
+                dim,
+            }))
+        }
+        _ => None,
     }
 }
 
@@ -225,6 +214,21 @@ mod tests {
         assert!(create_provider(&cfg).is_none());
     }
 
+    #[test]
+    fn test_create_provider_for_qdrant_backend_without_sqlite_vec() {
+        let mut cfg = base_config();
+        cfg.embedding_provider = Some("ollama".into());
+        assert_eq!(
+            create_provider(&cfg).is_some(),
+            cfg!(feature = "sqlite-vec")
+        );
+
+        cfg.memory_backend = "qdrant".into();
+        let provider = create_provider(&cfg).expect("qdrant needs embeddings");
+        assert_eq!(provider.model(), "nomic-embed-text");
+        assert_eq!(provider.dimension(), 1024);
+    }
+
     #[cfg(feature = "sqlite-vec")]
     #[test]
     fn test_create_provider_openai_when_configured() {
@@ -136,6 +136,20 @@ impl HookManager {
         manager
     }
 
+    /// A manager with no hooks that never runs any, e.g. for dry-run replays.
+    pub fn disabled() -> Self {
+        Self {
+            hooks_dir_candidates: Vec::new(),
+            state_file: PathBuf::new(),
+            hooks: Arc::new(RwLock::new(Vec::new())),
+            state_overrides: Arc::new(RwLock::new(HashMap::new())),
+            db: None,
+            enabled: false,
+            max_input_bytes: 0,
+            max_output_bytes: 0,
+        }
+    }
+
     pub fn with_db(mut self, db: Arc<Database>) -> Self {
         self.db = Some(db);
         self
@@ -1,17 +1,23 @@
 pub mod agent_engine;
+pub mod audit;
+pub mod backup;
 pub mod channels;
 pub mod chat_commands;
 pub mod clawhub;
 pub mod codex_auth;
 pub mod config;
+pub mod config_check;
+pub mod db_admin;
 pub mod doctor;
 pub mod embedding;
 pub mod gateway;
 pub mod hooks;
 pub mod llm;
+pub mod llm_circuit;
 pub mod mcp;
 pub mod memory_backend;
 pub mod plugins;
+pub mod replay;
 pub(crate) mod run_control;
 pub mod runtime;
 pub mod scheduler;
@@ -19,6 +25,8 @@ pub mod setup;
 pub mod setup_def;
 pub mod skills;
 pub mod tools;
+pub mod vector_store;
+pub mod web_tokens;
 
 pub use microclaw_app::builtin_skills;
 pub use microclaw_app::logging;
@@ -3,22 +3,27 @@ use futures_util::StreamExt;
 use serde::Deserialize;
 use serde_json::json;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
 
 use crate::codex_auth::{
-    codex_config_default_openai_base_url, is_openai_codex_provider,
-    refresh_openai_codex_auth_if_needed, resolve_openai_codex_auth,
+    codex_config_default_openai_base_url, codex_token_expired, is_openai_codex_provider,
+    refresh_openai_codex_auth, refresh_openai_codex_auth_if_needed, resolve_openai_codex_auth,
+    CodexAuthResolved,
 };
-use crate::config::Config;
 #[cfg(test)]
 use crate::config::WorkingDirIsolation;
+use crate::config::{
+    resolve_sampling_params, Config, ModelCapabilityOverride, ModelRole, SamplingParams,
+};
 use microclaw_core::error::MicroClawError;
 use microclaw_core::llm_types::{
-    ContentBlock, ImageSource, Message, MessageContent, MessagesRequest, MessagesResponse,
-    ResponseContentBlock, ToolDefinition, Usage,
+    ContentBlock, FinishReason, Message, MessageContent, MessagesRequest, MessagesResponse,
+    RequestMetadata, ResponseContentBlock, ToolChoice, ToolDefinition, Usage,
 };
 
 /// Remove invalid `ToolResult` blocks that cannot be matched to the most recent
@@ -201,6 +206,20 @@ pub trait LlmProvider: Send + Sync {
         self.send_message(system, messages, tools).await
     }
 
+    /// Like `send_message`, with per-call sampling overrides (e.g. `stop` or
+    /// `response_format`) layered over the configured ones. The default
+    /// only honors JSON mode, via the system prompt.
+    async fn send_message_with_sampling(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        overrides: &SamplingParams,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let system = system_for_sampling(system, overrides);
+        self.send_message(&system, messages, tools).await
+    }
+
     async fn send_message_stream(
         &self,
         system: &str,
@@ -232,11 +251,556 @@ pub trait LlmProvider: Send + Sync {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Request context
+// ---------------------------------------------------------------------------
+
+/// Per-turn metadata attached to every provider request made inside
+/// [`with_request_context`].
+#[derive(Debug, Clone)]
+pub struct LlmRequestContext {
+    pub correlation_id: String,
+}
+
+impl LlmRequestContext {
+    pub fn new_turn() -> Self {
+        LlmRequestContext {
+            correlation_id: format!("mc-{}", uuid::Uuid::new_v4().simple()),
+        }
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: LlmRequestContext;
+}
+
+/// Run `fut` with `context` visible to every provider call it makes. Calls made
+/// from tasks spawned inside `fut` do not inherit it.
+pub async fn with_request_context<F: std::future::Future>(
+    context: LlmRequestContext,
+    fut: F,
+) -> F::Output {
+    REQUEST_CONTEXT.scope(context, fut).await
+}
+
+pub fn current_correlation_id() -> Option<String> {
+    REQUEST_CONTEXT
+        .try_with(|ctx| ctx.correlation_id.clone())
+        .ok()
+}
+
+const CORRELATION_ID_HEADER: &str = "x-client-request-id";
+
+fn with_correlation_header(
+    req: reqwest::RequestBuilder,
+    correlation_id: Option<&str>,
+) -> reqwest::RequestBuilder {
+    match correlation_id {
+        Some(id) => req.header(CORRELATION_ID_HEADER, id),
+        None => req,
+    }
+}
+
+/// Set the OpenAI `user` field unless a body override already did.
+fn apply_correlation_id_oai(body: &mut serde_json::Value, correlation_id: Option<&str>) {
+    let (Some(id), Some(obj)) = (correlation_id, body.as_object_mut()) else {
+        return;
+    };
+    obj.entry("user").or_insert_with(|| json!(id));
+}
+
+tokio::task_local! {
+    static TOOL_CHOICE: ToolChoice;
+}
+
+/// Run one provider call with `choice` applied. Calls without tools ignore it.
+pub async fn with_tool_choice<F: std::future::Future>(choice: ToolChoice, fut: F) -> F::Output {
+    TOOL_CHOICE.scope(choice, fut).await
+}
+
+/// The tool choice for a request that sends `tools`; `Auto` outside
+/// [`with_tool_choice`] or when no tools are sent.
+fn effective_tool_choice(tools: &Option<Vec<ToolDefinition>>) -> ToolChoice {
+    if tools.as_ref().is_none_or(|t| t.is_empty()) {
+        return ToolChoice::Auto;
+    }
+    TOOL_CHOICE.try_with(Clone::clone).unwrap_or_default()
+}
+
+tokio::task_local! {
+    static OUTPUT_TOKEN_CAP: u32;
+}
+
+/// Run provider calls with their output limit lowered to `cap` (the chat's
+/// reply length limit). `None` leaves the configured limit alone.
+pub async fn with_output_token_cap<F: std::future::Future>(cap: Option<u32>, fut: F) -> F::Output {
+    match cap {
+        Some(cap) => OUTPUT_TOKEN_CAP.scope(cap, fut).await,
+        None => fut.await,
+    }
+}
+
+/// `sampling` with `max_tokens` lowered to the cap of an enclosing
+/// [`with_output_token_cap`]; `default_max` stands in when it is unset.
+fn apply_output_token_cap(mut sampling: SamplingParams, default_max: u32) -> SamplingParams {
+    if let Ok(cap) = OUTPUT_TOKEN_CAP.try_with(|cap| *cap) {
+        let limit = sampling.max_tokens().unwrap_or(default_max).min(cap);
+        sampling.max_tokens = Some(Some(limit));
+    }
+    sampling
+}
+
+fn anthropic_tool_choice(choice: &ToolChoice) -> Option<serde_json::Value> {
+    match choice {
+        ToolChoice::Auto => None,
+        ToolChoice::None => Some(json!({"type": "none"})),
+        ToolChoice::Required => Some(json!({"type": "any"})),
+        ToolChoice::Tool(name) => Some(json!({"type": "tool", "name": name})),
+    }
+}
+
+fn oai_chat_tool_choice(choice: &ToolChoice) -> serde_json::Value {
+    match choice {
+        ToolChoice::Auto => json!("auto"),
+        ToolChoice::None => json!("none"),
+        ToolChoice::Required => json!("required"),
+        ToolChoice::Tool(name) => json!({"type": "function", "function": {"name": name}}),
+    }
+}
+
+fn oai_responses_tool_choice(choice: &ToolChoice) -> serde_json::Value {
+    match choice {
+        ToolChoice::Tool(name) => json!({"type": "function", "name": name}),
+        other => oai_chat_tool_choice(other),
+    }
+}
+
+/// OpenAI-compatible backends known to ignore or reject `tool_choice`.
+const PROVIDERS_WITHOUT_TOOL_CHOICE: &[&str] = &["ollama"];
+
+/// Fallback for backends without native forcing: state the choice in the
+/// system prompt instead.
+fn system_for_tool_choice<'a>(system: Cow<'a, str>, choice: &ToolChoice) -> Cow<'a, str> {
+    let instruction = match choice {
+        ToolChoice::Auto => return system,
+        ToolChoice::None => {
+            "Do not call any tools for this response. Answer directly with the information you already have.".to_string()
+        }
+        ToolChoice::Required => {
+            "You must call at least one of the available tools now. Do not answer before calling a tool.".to_string()
+        }
+        ToolChoice::Tool(name) => format!(
+            "You must call the `{name}` tool now. Do not answer or call any other tool before calling `{name}`."
+        ),
+    };
+    if system.trim().is_empty() {
+        Cow::Owned(instruction)
+    } else {
+        Cow::Owned(format!("{system}\n\n{instruction}"))
+    }
+}
+
+const JSON_MODE_INSTRUCTION: &str = "Respond with a single valid JSON object and nothing else: no prose, no Markdown code fences, no text before or after the JSON.";
+
+/// Append the JSON-only instruction when JSON mode is requested. OpenAI's
+/// `json_object` mode also requires the word "JSON" to appear in the prompt.
+fn system_for_sampling<'a>(system: &'a str, sampling: &SamplingParams) -> Cow<'a, str> {
+    if !sampling.json_mode() {
+        return Cow::Borrowed(system);
+    }
+    if system.trim().is_empty() {
+        Cow::Borrowed(JSON_MODE_INSTRUCTION)
+    } else {
+        Cow::Owned(format!("{system}\n\n{JSON_MODE_INSTRUCTION}"))
+    }
+}
+
+/// Request features that vary by model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    pub tools: bool,
+    pub vision: bool,
+    /// Native JSON mode (`response_format` / `text.format`). Without it the
+    /// JSON-only system instruction still applies.
+    pub json_mode: bool,
+    /// Extended thinking, the `thinking` body flag or `reasoning_effort`.
+    pub thinking: bool,
+    /// The `verbosity` knob (`text.verbosity` on the Responses API).
+    pub verbosity: bool,
+    /// A `thinking: {"type": "enabled" | "disabled"}` body flag on
+    /// OpenAI-compatible APIs, as DeepSeek and GLM accept.
+    pub thinking_toggle: bool,
+}
+
+impl ModelCapabilities {
+    /// Assumed for models the registry does not know: tool use only.
+    pub const BASELINE: ModelCapabilities = caps(true, false, false, false);
+
+    const fn with_verbosity(self) -> ModelCapabilities {
+        ModelCapabilities {
+            verbosity: true,
+            ..self
+        }
+    }
+
+    const fn with_thinking_toggle(self) -> ModelCapabilities {
+        ModelCapabilities {
+            thinking_toggle: true,
+            ..self
+        }
+    }
+}
+
+const fn caps(tools: bool, vision: bool, json_mode: bool, thinking: bool) -> ModelCapabilities {
+    ModelCapabilities {
+        tools,
+        vision,
+        json_mode,
+        thinking,
+        verbosity: false,
+        thinking_toggle: false,
+    }
+}
+
+/// Known model families as `(pattern, capabilities)`, first match wins.
+/// Patterns are lowercase globs where `*` matches any run of characters.
+const MODEL_CAPABILITIES: &[(&str, ModelCapabilities)] = &[
+    ("claude-opus-4*", caps(true, true, false, true)),
+    ("claude-sonnet-4*", caps(true, true, false, true)),
+    ("claude-haiku-4*", caps(true, true, false, true)),
+    ("claude-3-7-sonnet*", caps(true, true, false, true)),
+    ("claude-*", caps(true, true, false, false)),
+    ("o1-mini*", caps(false, false, false, false)),
+    ("o1-preview*", caps(false, false, false, false)),
+    ("o1*", caps(true, true, true, true)),
+    ("o3*", caps(true, true, true, true)),
+    ("o4-mini*", caps(true, true, true, true)),
+    ("gpt-5*", caps(true, true, true, true).with_verbosity()),
+    ("gpt-4o*", caps(true, true, true, false)),
+    ("gpt-4.1*", caps(true, true, true, false)),
+    ("gpt-4-turbo*", caps(true, true, true, false)),
+    ("gpt-4*", caps(true, false, false, false)),
+    ("gpt-3.5-turbo*", caps(true, false, true, false)),
+    ("gemini-2.5*", caps(true, true, true, true)),
+    ("gemini-*", caps(true, true, true, false)),
+    ("deepseek-reasoner*", caps(true, false, false, true)),
+    (
+        "deepseek-*",
+        caps(true, false, true, false).with_thinking_toggle(),
+    ),
+    ("qwen*vl*", caps(true, true, false, false)),
+    ("qwen3*", caps(true, false, true, true)),
+    ("qwen*", caps(true, false, true, false)),
+    ("glm-*v*", caps(true, true, false, false)),
+    (
+        "glm-4.5*",
+        caps(true, false, true, true).with_thinking_toggle(),
+    ),
+    (
+        "glm-4.6*",
+        caps(true, false, true, true).with_thinking_toggle(),
+    ),
+    ("glm-*", caps(true, false, true, false)),
+    ("kimi-*", caps(true, false, true, false)),
+    ("pixtral*", caps(true, true, true, false)),
+    ("mistral*", caps(true, false, true, false)),
+    ("llama*vision*", caps(false, true, false, false)),
+    ("llava*", caps(false, true, false, false)),
+];
+
+/// Capabilities of `model`, matched on the id after any `vendor/` prefix
+/// (as OpenRouter uses) and, failing that, after dotted `vendor.` prefixes
+/// (as Bedrock uses, e.g. `us.anthropic.claude-sonnet-4-…`). Unknown models
+/// get [`ModelCapabilities::BASELINE`].
+pub fn model_capabilities(model: &str) -> ModelCapabilities {
+    let model = model.trim().to_ascii_lowercase();
+    let mut id = model.rsplit('/').next().unwrap_or(&model);
+    loop {
+        if let Some((_, caps)) = MODEL_CAPABILITIES
+            .iter()
+            .find(|(pattern, _)| glob_matches(pattern, id))
+        {
+            return *caps;
+        }
+        match id.split_once('.') {
+            Some((vendor, rest))
+                if !vendor.is_empty()
+                    && vendor.bytes().all(|b| b.is_ascii_lowercase() || b == b'-') =>
+            {
+                id = rest;
+            }
+            _ => return ModelCapabilities::BASELINE,
+        }
+    }
+}
+
+/// [`model_capabilities`] with the configured `model_capabilities` overrides
+/// applied. Patterns match the whole model name or the id after a `vendor/`
+/// prefix; the longest matching pattern wins.
+pub fn model_capabilities_with_overrides(
+    model: &str,
+    overrides: &HashMap<String, ModelCapabilityOverride>,
+) -> ModelCapabilities {
+    let mut caps = model_capabilities(model);
+    let model = model.trim().to_ascii_lowercase();
+    let id = model.rsplit('/').next().unwrap_or(&model);
+    let Some((_, o)) = overrides
+        .iter()
+        .filter(|(pattern, _)| glob_matches(pattern, &model) || glob_matches(pattern, id))
+        .max_by_key(|(pattern, _)| (pattern.len(), pattern.as_str()))
+    else {
+        return caps;
+    };
+    caps.tools = o.tools.unwrap_or(caps.tools);
+    caps.vision = o.vision.unwrap_or(caps.vision);
+    caps.json_mode = o.json_mode.unwrap_or(caps.json_mode);
+    caps.thinking = o.thinking.unwrap_or(caps.thinking);
+    caps.verbosity = o.verbosity.unwrap_or(caps.verbosity);
+    caps.thinking_toggle = o.thinking_toggle.unwrap_or(caps.thinking_toggle);
+    caps
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let Some((head, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(text) = text.strip_prefix(head) else {
+        return false;
+    };
+    rest.is_empty()
+        || (0..=text.len())
+            .filter(|&i| text.is_char_boundary(i))
+            .any(|i| glob_matches(rest, &text[i..]))
+}
+
+/// Report that `feature` was dropped for `model`: at info level the first
+/// time per model and feature, at debug level after that.
+fn log_downgrade(model: &str, feature: &'static str) {
+    static LOGGED: OnceLock<Mutex<HashSet<(String, &'static str)>>> = OnceLock::new();
+    let first = LOGGED
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert((model.to_string(), feature));
+    if first {
+        info!(model = %model, feature, "model does not support {feature}; omitting it");
+    } else {
+        debug!(model = %model, feature, "model does not support {feature}; omitting it");
+    }
+}
+
+/// Drop the tool list and swap image blocks for a text note when `caps` rules
+/// them out, so the request doesn't fail on an unsupported feature.
+fn adapt_to_capabilities(
+    model: &str,
+    caps: ModelCapabilities,
+    mut messages: Vec<Message>,
+    tools: Option<Vec<ToolDefinition>>,
+) -> (Vec<Message>, Option<Vec<ToolDefinition>>) {
+    let tools = match tools {
+        Some(t) if !caps.tools && !t.is_empty() => {
+            log_downgrade(model, "tools");
+            None
+        }
+        other => other,
+    };
+    if !caps.vision {
+        let mut dropped = false;
+        for msg in &mut messages {
+            let MessageContent::Blocks(blocks) = &mut msg.content else {
+                continue;
+            };
+            for block in blocks.iter_mut() {
+                if matches!(block, ContentBlock::Image { .. }) {
+                    *block = ContentBlock::Text {
+                        text: "[image omitted: this model does not accept images]".to_string(),
+                    };
+                    dropped = true;
+                }
+            }
+        }
+        if dropped {
+            log_downgrade(model, "vision");
+        }
+    }
+    (messages, tools)
+}
+
+/// Remove OpenAI-compatible body parameters `caps` rules out. Runs before
+/// the configured body overrides, which can still add them back.
+fn strip_unsupported_oai_params(
+    body: &mut serde_json::Value,
+    model: &str,
+    caps: ModelCapabilities,
+) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    if !caps.json_mode {
+        let text_format = obj
+            .get_mut("text")
+            .and_then(|t| t.as_object_mut())
+            .and_then(|t| t.remove("format"));
+        if obj
+            .get("text")
+            .is_some_and(|t| t.as_object().is_some_and(|t| t.is_empty()))
+        {
+            obj.remove("text");
+        }
+        if obj.remove("response_format").is_some() || text_format.is_some() {
+            log_downgrade(model, "json_mode");
+        }
+    }
+    if !caps.thinking {
+        let mut removed = ["reasoning_effort", "reasoning"]
+            .iter()
+            .filter(|key| obj.remove(**key).is_some())
+            .count();
+        if !caps.thinking_toggle && obj.remove("thinking").is_some() {
+            removed += 1;
+        }
+        if removed > 0 {
+            log_downgrade(model, "thinking");
+        }
+    }
+}
+
+/// Set `key` inside the object at `parent`, keeping its other keys.
+fn insert_nested(
+    obj: &mut serde_json::Map<String, serde_json::Value>,
+    parent: &str,
+    key: &str,
+    value: serde_json::Value,
+) {
+    let entry = obj.entry(parent).or_insert_with(|| json!({}));
+    if !entry.is_object() {
+        *entry = json!({});
+    }
+    entry[key] = value;
+}
+
+/// Remove `key` inside the object at `parent`, and `parent` once it is empty.
+fn remove_nested(obj: &mut serde_json::Map<String, serde_json::Value>, parent: &str, key: &str) {
+    if let Some(entry) = obj.get_mut(parent).and_then(|v| v.as_object_mut()) {
+        entry.remove(key);
+        if entry.is_empty() {
+            obj.remove(parent);
+        }
+    }
+}
+
+/// Drop the body fields that `sampling` sets to an explicit `null`. Runs after
+/// the raw body overrides, so a `null` in `sampling_params*` also removes a
+/// value an override put there. `max_tokens: null` only falls back to the
+/// top-level `max_tokens`; a limit is always sent.
+fn remove_nulled_sampling_params_oai(
+    body: &mut serde_json::Value,
+    sampling: &SamplingParams,
+    responses_api: bool,
+) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    if sampling.temperature == Some(None) {
+        obj.remove("temperature");
+    }
+    if sampling.top_p == Some(None) {
+        obj.remove("top_p");
+    }
+    if sampling.thinking == Some(None) {
+        obj.remove("thinking");
+    }
+    if responses_api {
+        if sampling.reasoning_effort == Some(None) {
+            remove_nested(obj, "reasoning", "effort");
+        }
+        if sampling.verbosity == Some(None) {
+            remove_nested(obj, "text", "verbosity");
+        }
+        if sampling.response_format == Some(None) {
+            remove_nested(obj, "text", "format");
+        }
+        return;
+    }
+    if sampling.reasoning_effort == Some(None) {
+        obj.remove("reasoning_effort");
+    }
+    if sampling.verbosity == Some(None) {
+        obj.remove("verbosity");
+    }
+    if sampling.stop == Some(None) {
+        obj.remove("stop");
+    }
+    if sampling.seed == Some(None) {
+        obj.remove("seed");
+    }
+    if sampling.response_format == Some(None) {
+        obj.remove("response_format");
+    }
+}
+
+/// Apply the typed reasoning knobs of `sampling` (`reasoning_effort`,
+/// `verbosity`, `thinking`) to an OpenAI-compatible body. Runs after the raw
+/// body overrides so a typed knob wins over an override of the same field; a
+/// knob the model does not support is left out instead.
+fn apply_reasoning_knobs_oai(
+    body: &mut serde_json::Value,
+    model: &str,
+    caps: ModelCapabilities,
+    sampling: &SamplingParams,
+    responses_api: bool,
+) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    if let Some(effort) = sampling.reasoning_effort() {
+        if !caps.thinking {
+            log_downgrade(model, "reasoning_effort");
+        } else if responses_api {
+            insert_nested(obj, "reasoning", "effort", json!(effort));
+        } else {
+            obj.insert("reasoning_effort".to_string(), json!(effort));
+        }
+    }
+    if let Some(verbosity) = sampling.verbosity() {
+        if !caps.verbosity {
+            log_downgrade(model, "verbosity");
+        } else if responses_api {
+            insert_nested(obj, "text", "verbosity", json!(verbosity));
+        } else {
+            obj.insert("verbosity".to_string(), json!(verbosity));
+        }
+    }
+    if let Some(enabled) = sampling.thinking() {
+        if caps.thinking_toggle {
+            let kind = if enabled { "enabled" } else { "disabled" };
+            obj.insert("thinking".to_string(), json!({ "type": kind }));
+        } else {
+            log_downgrade(model, "thinking");
+        }
+    }
+}
+
 pub fn create_provider(config: &Config) -> Box<dyn LlmProvider> {
-    match config.llm_provider.trim().to_lowercase().as_str() {
+    let provider: Box<dyn LlmProvider> = match config.llm_provider.trim().to_lowercase().as_str() {
         "anthropic" => Box::new(AnthropicProvider::new(config)),
         _ => Box::new(OpenAiProvider::new(config)),
-    }
+    };
+    crate::llm_circuit::wrap_provider(config, provider)
+}
+
+/// Whether requests to `provider` carry a sampling `seed`. Anthropic and the
+/// Responses API (openai-codex) have none, so only temperature applies there.
+pub fn provider_supports_seed(provider: &str) -> bool {
+    let provider = provider.trim().to_ascii_lowercase();
+    provider != "anthropic" && !is_openai_codex_provider(&provider)
+}
+
+/// A provider whose default model is `config.model_for_role(role)`.
+pub fn create_provider_for_role(config: &Config, role: ModelRole) -> Box<dyn LlmProvider> {
+    let mut config = config.clone();
+    config.model = config.model_for_role(role).to_string();
+    create_provider(&config)
 }
 
 // ---------------------------------------------------------------------------
@@ -246,66 +810,259 @@ pub fn create_provider(config: &Config) -> Box<dyn LlmProvider> {
 pub struct AnthropicProvider {
     http: reqwest::Client,
     api_key: String,
+    provider: String,
     model: String,
     max_tokens: u32,
+    sampling_params: SamplingParams,
+    sampling_params_by_provider: HashMap<String, SamplingParams>,
+    sampling_params_by_model: HashMap<String, SamplingParams>,
+    capability_overrides: HashMap<String, ModelCapabilityOverride>,
     base_url: String,
+    correlation_id: bool,
+    /// Extended thinking unless a request's `thinking` knob says otherwise.
+    thinking: bool,
+    thinking_budget_tokens: u32,
+    interleaved_thinking: bool,
 }
 
+/// Beta flag that lets Anthropic models think between tool calls.
+const ANTHROPIC_INTERLEAVED_THINKING_BETA: &str = "interleaved-thinking-2025-05-14";
+
 impl AnthropicProvider {
     pub fn new(config: &Config) -> Self {
         AnthropicProvider {
             http: reqwest::Client::new(),
             api_key: config.api_key.clone(),
+            provider: config.llm_provider.clone(),
             model: config.model.clone(),
             max_tokens: config.max_tokens,
+            sampling_params: config.sampling_params.clone(),
+            sampling_params_by_provider: config.sampling_params_by_provider.clone(),
+            sampling_params_by_model: config.sampling_params_by_model.clone(),
+            capability_overrides: config.model_capabilities.clone(),
             base_url: resolve_anthropic_messages_url(config.llm_base_url.as_deref().unwrap_or("")),
+            correlation_id: config.llm_correlation_id,
+            thinking: config.anthropic_thinking,
+            thinking_budget_tokens: config.anthropic_thinking_budget_tokens,
+            interleaved_thinking: config.anthropic_interleaved_thinking,
         }
     }
 
-    async fn send_message_stream_single_pass(
+    fn build_request(
+        &self,
+        model: &str,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        stream: Option<bool>,
+        overrides: &SamplingParams,
+    ) -> MessagesRequest {
+        let caps = model_capabilities_with_overrides(model, &self.capability_overrides);
+        let (messages, tools) = adapt_to_capabilities(model, caps, messages, tools);
+        let sampling = apply_output_token_cap(
+            resolve_sampling_params(
+                &self.sampling_params,
+                &self.sampling_params_by_provider,
+                &self.sampling_params_by_model,
+                &self.provider,
+                model,
+            )
+            .merged_with(overrides),
+            self.max_tokens,
+        );
+        if let Some(effort) = sampling.reasoning_effort() {
+            debug!(
+                model = %model,
+                reasoning_effort = %effort,
+                "reasoning_effort is not supported by the Anthropic API; ignoring"
+            );
+        }
+        if sampling.verbosity().is_some() {
+            debug!(model = %model, "verbosity is not supported by the Anthropic API; ignoring");
+        }
+        if sampling.seed().is_some() {
+            debug!(model = %model, "seed is not supported by the Anthropic API; ignoring");
+        }
+        let max_tokens = sampling.max_tokens().unwrap_or(self.max_tokens);
+        let tool_choice = effective_tool_choice(&tools);
+        let thinking = if !sampling.thinking().unwrap_or(self.thinking) {
+            None
+        } else if caps.thinking {
+            self.thinking_param(model, max_tokens, &tool_choice)
+        } else {
+            log_downgrade(model, "thinking");
+            None
+        };
+        let (temperature, top_p) = if thinking.is_some() {
+            // Extended thinking rejects custom temperature and low top_p.
+            if sampling.temperature().is_some() || sampling.top_p().is_some() {
+                debug!(
+                    model = %model,
+                    "temperature/top_p are not supported with extended thinking; ignoring"
+                );
+            }
+            (None, None)
+        } else {
+            (sampling.temperature(), sampling.top_p())
+        };
+        MessagesRequest {
+            model: model.to_string(),
+            max_tokens,
+            // Anthropic has no JSON mode; rely on the instruction.
+            system: system_for_sampling(system, &sampling).into_owned(),
+            messages,
+            stream,
+            temperature,
+            top_p,
+            stop_sequences: sampling.stop().map(<[String]>::to_vec),
+            metadata: self
+                .correlation_id()
+                .map(|user_id| RequestMetadata { user_id }),
+            tool_choice: anthropic_tool_choice(&tool_choice),
+            tools,
+            thinking,
+        }
+    }
+
+    /// The `thinking` block for one request. Skipped when the budget does not fit
+    /// under this request's `max_tokens` or a tool call is forced, which extended
+    /// thinking does not allow.
+    fn thinking_param(
+        &self,
+        model: &str,
+        max_tokens: u32,
+        tool_choice: &ToolChoice,
+    ) -> Option<serde_json::Value> {
+        let budget = self.thinking_budget_tokens;
+        if budget >= max_tokens {
+            debug!(
+                model = %model,
+                budget,
+                max_tokens,
+                "thinking budget does not fit under max_tokens; thinking off"
+            );
+            return None;
+        }
+        if matches!(tool_choice, ToolChoice::Required | ToolChoice::Tool(_)) {
+            debug!(
+                model = %model,
+                "forced tool choice is incompatible with extended thinking; thinking off"
+            );
+            return None;
+        }
+        Some(json!({"type": "enabled", "budget_tokens": budget}))
+    }
+
+    fn post(&self, request: &MessagesRequest) -> reqwest::RequestBuilder {
+        let req = self
+            .http
+            .post(&self.base_url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json");
+        let req = if self.interleaved_thinking && request.thinking.is_some() {
+            req.header("anthropic-beta", ANTHROPIC_INTERLEAVED_THINKING_BETA)
+        } else {
+            req
+        };
+        with_correlation_header(req, request.metadata.as_ref().map(|m| m.user_id.as_str()))
+    }
+
+    fn correlation_id(&self) -> Option<String> {
+        self.correlation_id.then(current_correlation_id).flatten()
+    }
+
+    async fn send_request(
         &self,
         request: &MessagesRequest,
-        text_tx: Option<&UnboundedSender<String>>,
     ) -> Result<MessagesResponse, MicroClawError> {
-        let mut streamed_request = request.clone();
-        streamed_request.stream = Some(true);
-
+        let mut retries = 0u32;
+        let max_retries = 3;
+        let correlation_id = request.metadata.as_ref().map(|m| m.user_id.as_str());
         debug!(
             provider = "anthropic",
             model = %request.model,
-            url = %self.base_url,
+            correlation_id = correlation_id.unwrap_or(""),
             messages_count = request.messages.len(),
-            "Sending LLM stream request"
+            "Sending LLM request"
         );
 
-        let response = self
-            .http
-            .post(&self.base_url)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&streamed_request)
-            .send()
-            .await?;
+        loop {
+            let response = self.post(request).json(request).send().await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            if let Ok(api_err) = serde_json::from_str::<AnthropicApiError>(&body) {
-                return Err(MicroClawError::LlmApi(format!(
-                    "{}: {}",
-                    api_err.error.error_type, api_err.error.message
-                )));
-            }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
-        }
+            let status = response.status();
 
-        let mut byte_stream = response.bytes_stream();
-        let mut sse = SseEventParser::default();
+            if status.is_success() {
+                let body = response.text().await?;
+                let parsed: MessagesResponse = serde_json::from_str(&body).map_err(|e| {
+                    MicroClawError::LlmApi(format!("Failed to parse response: {e}\nBody: {body}"))
+                })?;
+                log_finish_reason("anthropic", &request.model, &parsed);
+                return Ok(parsed);
+            }
+
+            if status.as_u16() == 429 && retries < max_retries {
+                retries += 1;
+                let delay = std::time::Duration::from_secs(2u64.pow(retries));
+                warn!(
+                    "Rate limited, retrying in {:?} (attempt {retries}/{max_retries})",
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            if let Ok(api_err) = serde_json::from_str::<AnthropicApiError>(&body) {
+                return Err(MicroClawError::LlmApi(format!(
+                    "{}: {}",
+                    api_err.error.error_type, api_err.error.message
+                )));
+            }
+            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
+        }
+    }
+
+    async fn send_message_stream_single_pass(
+        &self,
+        request: &MessagesRequest,
+        text_tx: Option<&UnboundedSender<String>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let mut streamed_request = request.clone();
+        streamed_request.stream = Some(true);
+
+        let correlation_id = request.metadata.as_ref().map(|m| m.user_id.as_str());
+        debug!(
+            provider = "anthropic",
+            model = %request.model,
+            url = %self.base_url,
+            correlation_id = correlation_id.unwrap_or(""),
+            messages_count = request.messages.len(),
+            "Sending LLM stream request"
+        );
+
+        let response = self.post(request).json(&streamed_request).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            if let Ok(api_err) = serde_json::from_str::<AnthropicApiError>(&body) {
+                return Err(MicroClawError::LlmApi(format!(
+                    "{}: {}",
+                    api_err.error.error_type, api_err.error.message
+                )));
+            }
+            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
+        }
+
+        let mut byte_stream = response.bytes_stream();
+        let mut sse = SseEventParser::default();
         let mut stop_reason: Option<String> = None;
         let mut usage: Option<Usage> = None;
         let mut text_blocks: std::collections::HashMap<usize, String> =
             std::collections::HashMap::new();
+        let mut thinking_blocks: std::collections::HashMap<usize, StreamThinkingBlock> =
+            std::collections::HashMap::new();
         let mut tool_blocks: std::collections::HashMap<usize, StreamToolUseBlock> =
             std::collections::HashMap::new();
         let mut ordered_indexes: Vec<usize> = Vec::new();
@@ -325,6 +1082,7 @@ impl AnthropicProvider {
                     &mut stop_reason,
                     &mut usage,
                     &mut text_blocks,
+                    &mut thinking_blocks,
                     &mut tool_blocks,
                     &mut ordered_indexes,
                 );
@@ -340,18 +1098,22 @@ impl AnthropicProvider {
                 &mut stop_reason,
                 &mut usage,
                 &mut text_blocks,
+                &mut thinking_blocks,
                 &mut tool_blocks,
                 &mut ordered_indexes,
             );
         }
 
-        Ok(build_stream_response(
+        let response = build_stream_response(
             ordered_indexes,
             text_blocks,
+            thinking_blocks,
             tool_blocks,
             stop_reason,
             usage,
-        ))
+        );
+        log_finish_reason("anthropic", &request.model, &response);
+        Ok(response)
     }
 }
 
@@ -366,6 +1128,14 @@ fn resolve_anthropic_messages_url(configured_base: &str) -> String {
     format!("{trimmed}/v1/messages")
 }
 
+#[derive(Default)]
+struct StreamThinkingBlock {
+    thinking: String,
+    signature: Option<String>,
+    /// Set for `redacted_thinking` blocks.
+    redacted_data: Option<String>,
+}
+
 #[derive(Default)]
 struct StreamToolUseBlock {
     id: String,
@@ -386,12 +1156,14 @@ fn usage_from_json(v: &serde_json::Value) -> Option<Usage> {
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_anthropic_stream_event(
     data: &str,
     text_tx: Option<&UnboundedSender<String>>,
     stop_reason: &mut Option<String>,
     usage: &mut Option<Usage>,
     text_blocks: &mut std::collections::HashMap<usize, String>,
+    thinking_blocks: &mut std::collections::HashMap<usize, StreamThinkingBlock>,
     tool_blocks: &mut std::collections::HashMap<usize, StreamToolUseBlock>,
     ordered_indexes: &mut Vec<usize>,
 ) {
@@ -420,6 +1192,33 @@ fn process_anthropic_stream_event(
                                 .to_string();
                             text_blocks.insert(index, text);
                         }
+                        Some("thinking") => {
+                            let text = |key: &str| {
+                                block.get(key).and_then(|t| t.as_str()).map(str::to_string)
+                            };
+                            thinking_blocks.insert(
+                                index,
+                                StreamThinkingBlock {
+                                    thinking: text("thinking").unwrap_or_default(),
+                                    signature: text("signature").filter(|s| !s.is_empty()),
+                                    redacted_data: None,
+                                },
+                            );
+                        }
+                        Some("redacted_thinking") => {
+                            let data = block
+                                .get("data")
+                                .and_then(|t| t.as_str())
+                                .unwrap_or_default()
+                                .to_string();
+                            thinking_blocks.insert(
+                                index,
+                                StreamThinkingBlock {
+                                    redacted_data: Some(data),
+                                    ..StreamThinkingBlock::default()
+                                },
+                            );
+                        }
                         Some("tool_use") => {
                             let id = block
                                 .get("id")
@@ -477,6 +1276,34 @@ fn process_anthropic_stream_event(
                         }
                     }
                 }
+                // Reasoning is collected but never streamed to the channel.
+                Some("thinking_delta") => {
+                    let piece = delta
+                        .get("thinking")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or_default();
+                    if !piece.is_empty() {
+                        thinking_blocks
+                            .entry(index)
+                            .or_default()
+                            .thinking
+                            .push_str(piece);
+                    }
+                }
+                Some("signature_delta") => {
+                    let piece = delta
+                        .get("signature")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or_default();
+                    if !piece.is_empty() {
+                        thinking_blocks
+                            .entry(index)
+                            .or_default()
+                            .signature
+                            .get_or_insert_with(String::new)
+                            .push_str(piece);
+                    }
+                }
                 Some("input_json_delta") => {
                     let piece = delta
                         .get("partial_json")
@@ -590,12 +1417,35 @@ fn process_openai_stream_event(
     }
 }
 
+/// Rewrite a provider's stop/finish reason to its [`FinishReason`] name;
+/// reasons with no common meaning are kept verbatim.
 fn normalize_stop_reason(reason: Option<String>) -> Option<String> {
-    match reason.as_deref() {
-        Some("tool_use") | Some("tool_calls") => Some("tool_use".into()),
-        Some("max_tokens") | Some("length") => Some("max_tokens".into()),
-        Some("stop") | Some("end_turn") | None => Some("end_turn".into()),
-        Some(other) => Some(other.to_string()),
+    match FinishReason::parse(reason.as_deref()) {
+        FinishReason::Other => reason,
+        normalized => Some(normalized.as_str().into()),
+    }
+}
+
+/// One line per provider call with its normalized finish reason. Truncated or
+/// filtered output is a warning: the user would otherwise see a cut-off reply.
+fn log_finish_reason(provider: &str, model: &str, response: &MessagesResponse) {
+    let finish_reason = response.finish_reason();
+    let raw = response.stop_reason.as_deref().unwrap_or("");
+    match finish_reason {
+        FinishReason::MaxTokens | FinishReason::ContentFilter => warn!(
+            provider,
+            model,
+            finish_reason = %finish_reason,
+            raw,
+            "LLM response stopped early"
+        ),
+        _ => info!(
+            provider,
+            model,
+            finish_reason = %finish_reason,
+            raw,
+            "LLM response finished"
+        ),
     }
 }
 
@@ -610,12 +1460,23 @@ fn parse_tool_input(input_json: &str) -> serde_json::Value {
 fn build_stream_response(
     ordered_indexes: Vec<usize>,
     text_blocks: std::collections::HashMap<usize, String>,
+    thinking_blocks: std::collections::HashMap<usize, StreamThinkingBlock>,
     tool_blocks: std::collections::HashMap<usize, StreamToolUseBlock>,
     stop_reason: Option<String>,
     usage: Option<Usage>,
 ) -> MessagesResponse {
     let mut content = Vec::new();
     for index in ordered_indexes {
+        if let Some(block) = thinking_blocks.get(&index) {
+            if let Some(data) = &block.redacted_data {
+                content.push(ResponseContentBlock::RedactedThinking { data: data.clone() });
+            } else if !block.thinking.is_empty() || block.signature.is_some() {
+                content.push(ResponseContentBlock::Thinking {
+                    thinking: block.thinking.clone(),
+                    signature: block.signature.clone(),
+                });
+            }
+        }
         if let Some(text) = text_blocks.get(&index) {
             if !text.is_empty() {
                 content.push(ResponseContentBlock::Text { text: text.clone() });
@@ -680,59 +1541,27 @@ impl LlmProvider for AnthropicProvider {
             .filter(|v| !v.is_empty())
             .unwrap_or(&self.model);
 
-        let request = MessagesRequest {
-            model: model.to_string(),
-            max_tokens: self.max_tokens,
-            system: system.to_string(),
+        let request = self.build_request(
+            model,
+            system,
             messages,
             tools,
-            stream: None,
-        };
-
-        let mut retries = 0u32;
-        let max_retries = 3;
-
-        loop {
-            let response = self
-                .http
-                .post(&self.base_url)
-                .header("x-api-key", &self.api_key)
-                .header("anthropic-version", "2023-06-01")
-                .header("content-type", "application/json")
-                .json(&request)
-                .send()
-                .await?;
-
-            let status = response.status();
-
-            if status.is_success() {
-                let body = response.text().await?;
-                let parsed: MessagesResponse = serde_json::from_str(&body).map_err(|e| {
-                    MicroClawError::LlmApi(format!("Failed to parse response: {e}\nBody: {body}"))
-                })?;
-                return Ok(parsed);
-            }
-
-            if status.as_u16() == 429 && retries < max_retries {
-                retries += 1;
-                let delay = std::time::Duration::from_secs(2u64.pow(retries));
-                warn!(
-                    "Rate limited, retrying in {:?} (attempt {retries}/{max_retries})",
-                    delay
-                );
-                tokio::time::sleep(delay).await;
-                continue;
-            }
+            None,
+            &SamplingParams::default(),
+        );
+        self.send_request(&request).await
+    }
 
-            let body = response.text().await.unwrap_or_default();
-            if let Ok(api_err) = serde_json::from_str::<AnthropicApiError>(&body) {
-                return Err(MicroClawError::LlmApi(format!(
-                    "{}: {}",
-                    api_err.error.error_type, api_err.error.message
-                )));
-            }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {body}")));
-        }
+    async fn send_message_with_sampling(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        overrides: &SamplingParams,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let messages = sanitize_messages(messages);
+        let request = self.build_request(&self.model, system, messages, tools, None, overrides);
+        self.send_request(&request).await
     }
 
     async fn send_message_stream(
@@ -759,14 +1588,14 @@ impl LlmProvider for AnthropicProvider {
             .map(str::trim)
             .filter(|v| !v.is_empty())
             .unwrap_or(&self.model);
-        let request = MessagesRequest {
-            model: model.to_string(),
-            max_tokens: self.max_tokens,
-            system: system.to_string(),
+        let request = self.build_request(
+            model,
+            system,
             messages,
             tools,
-            stream: Some(true),
-        };
+            Some(true),
+            &SamplingParams::default(),
+        );
 
         self.send_message_stream_single_pass(&request, text_tx)
             .await
@@ -780,7 +1609,8 @@ impl LlmProvider for AnthropicProvider {
 pub struct OpenAiProvider {
     http: reqwest::Client,
     api_key: String,
-    codex_account_id: Option<String>,
+    /// Codex bearer token and account id; replaced in place when the token is refreshed.
+    codex_auth: std::sync::RwLock<CodexAuthResolved>,
     provider: String,
     model: String,
     max_tokens: u32,
@@ -791,8 +1621,15 @@ pub struct OpenAiProvider {
     openai_compat_body_overrides: HashMap<String, serde_json::Value>,
     openai_compat_body_overrides_by_provider: HashMap<String, HashMap<String, serde_json::Value>>,
     openai_compat_body_overrides_by_model: HashMap<String, HashMap<String, serde_json::Value>>,
+    sampling_params: SamplingParams,
+    sampling_params_by_provider: HashMap<String, SamplingParams>,
+    sampling_params_by_model: HashMap<String, SamplingParams>,
+    capability_overrides: HashMap<String, ModelCapabilityOverride>,
     chat_url: String,
     responses_url: String,
+    correlation_id: bool,
+    /// Send `tool_choice` in the body; otherwise fall back to an instruction.
+    native_tool_choice: bool,
 }
 
 fn resolve_openai_compat_base(provider: &str, configured_base: &str) -> String {
@@ -820,23 +1657,23 @@ impl OpenAiProvider {
         let configured_base = config.llm_base_url.as_deref().unwrap_or("");
         let base = resolve_openai_compat_base(&config.llm_provider, configured_base);
 
-        let (api_key, codex_account_id) = if is_openai_codex {
+        let (api_key, codex_auth) = if is_openai_codex {
             let _ = refresh_openai_codex_auth_if_needed();
             match resolve_openai_codex_auth("") {
-                Ok(auth) => (auth.bearer_token, auth.account_id),
+                Ok(auth) => (String::new(), auth),
                 Err(e) => {
                     warn!("{}", e);
-                    (String::new(), None)
+                    (String::new(), CodexAuthResolved::default())
                 }
             }
         } else {
-            (config.api_key.clone(), None)
+            (config.api_key.clone(), CodexAuthResolved::default())
         };
 
         OpenAiProvider {
             http: reqwest::Client::new(),
             api_key,
-            codex_account_id,
+            codex_auth: std::sync::RwLock::new(codex_auth),
             provider: config.llm_provider.clone(),
             model: config.model.clone(),
             max_tokens: config.max_tokens,
@@ -851,8 +1688,17 @@ impl OpenAiProvider {
             openai_compat_body_overrides_by_model: config
                 .openai_compat_body_overrides_by_model
                 .clone(),
+            sampling_params: config.sampling_params.clone(),
+            sampling_params_by_provider: config.sampling_params_by_provider.clone(),
+            sampling_params_by_model: config.sampling_params_by_model.clone(),
+            capability_overrides: config.model_capabilities.clone(),
             chat_url: format!("{}/chat/completions", base.trim_end_matches('/')),
             responses_url: format!("{}/responses", base.trim_end_matches('/')),
+            correlation_id: config.llm_correlation_id,
+            native_tool_choice: config.llm_native_tool_choice
+                && !PROVIDERS_WITHOUT_TOOL_CHOICE
+                    .iter()
+                    .any(|p| config.llm_provider.eq_ignore_ascii_case(p)),
         }
     }
 }
@@ -898,6 +1744,63 @@ fn apply_openai_compat_body_overrides(
     apply_body_override_map(body, by_model.get(model));
 }
 
+/// Chat Completions names: `temperature`, `top_p`, `stop`, `response_format`. The
+/// output token limit is set separately by `set_output_token_limit`, and the
+/// reasoning knobs by `apply_reasoning_knobs_oai`.
+fn apply_sampling_params_oai_chat(body: &mut serde_json::Value, sampling: &SamplingParams) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    if let Some(v) = sampling.temperature() {
+        obj.insert("temperature".to_string(), json!(v));
+    }
+    if let Some(v) = sampling.top_p() {
+        obj.insert("top_p".to_string(), json!(v));
+    }
+    if let Some(v) = sampling.stop() {
+        obj.insert("stop".to_string(), json!(v));
+    }
+    if let Some(v) = sampling.seed() {
+        obj.insert("seed".to_string(), json!(v));
+    }
+    if sampling.json_mode() {
+        obj.insert(
+            "response_format".to_string(),
+            json!({ "type": "json_object" }),
+        );
+    }
+}
+
+/// Responses API names: `max_output_tokens` and `text.format`; the reasoning
+/// knobs are set by `apply_reasoning_knobs_oai`. The Responses API has no stop
+/// sequences.
+fn apply_sampling_params_oai_responses(body: &mut serde_json::Value, sampling: &SamplingParams) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    if let Some(v) = sampling.temperature() {
+        obj.insert("temperature".to_string(), json!(v));
+    }
+    if let Some(v) = sampling.top_p() {
+        obj.insert("top_p".to_string(), json!(v));
+    }
+    if let Some(v) = sampling.max_tokens() {
+        obj.insert("max_output_tokens".to_string(), json!(v));
+    }
+    if sampling.stop().is_some() {
+        debug!("stop sequences are not supported by the Responses API; ignoring");
+    }
+    if sampling.seed().is_some() {
+        debug!("seed is not supported by the Responses API; ignoring");
+    }
+    if sampling.json_mode() {
+        obj.insert(
+            "text".to_string(),
+            json!({ "format": { "type": "json_object" } }),
+        );
+    }
+}
+
 // --- OpenAI response types ---
 
 #[derive(Debug, Deserialize)]
@@ -1008,6 +1911,14 @@ fn set_output_token_limit(
 struct OaiResponsesResponse {
     output: Vec<OaiResponsesOutputItem>,
     usage: Option<OaiResponsesUsage>,
+    /// Set when `status` is `incomplete`, e.g. `max_output_tokens`.
+    #[serde(default)]
+    incomplete_details: Option<OaiResponsesIncompleteDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OaiResponsesIncompleteDetails {
+    reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1061,33 +1972,99 @@ impl LlmProvider for OpenAiProvider {
         messages: Vec<Message>,
         tools: Option<Vec<ToolDefinition>>,
         model_override: Option<&str>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let model = model_override
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(&self.model);
+        self.send_chat_message(system, messages, tools, model, &SamplingParams::default())
+            .await
+    }
+
+    async fn send_message_with_sampling(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        overrides: &SamplingParams,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_chat_message(system, messages, tools, &self.model, overrides)
+            .await
+    }
+
+    async fn send_message_stream(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        text_tx: Option<&UnboundedSender<String>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.send_message_stream_with_model(system, messages, tools, text_tx, None)
+            .await
+    }
+
+    async fn send_message_stream_with_model(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        text_tx: Option<&UnboundedSender<String>>,
+        model_override: Option<&str>,
     ) -> Result<MessagesResponse, MicroClawError> {
         let model = model_override
             .map(str::trim)
             .filter(|v| !v.is_empty())
             .unwrap_or(&self.model);
         if self.is_openai_codex {
-            return self
-                .send_codex_message(system, messages, tools, model)
-                .await;
+            let response = self
+                .send_codex_message(system, messages, tools, model, &SamplingParams::default())
+                .await?;
+            if let Some(tx) = text_tx {
+                let text = response
+                    .content
+                    .iter()
+                    .filter_map(|block| match block {
+                        ResponseContentBlock::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("");
+                if !text.is_empty() {
+                    let _ = tx.send(text);
+                }
+            }
+            return Ok(response);
         }
 
+        let sampling = apply_output_token_cap(self.sampling_for(model), self.max_tokens);
+        let caps = model_capabilities_with_overrides(model, &self.capability_overrides);
+        let (messages, tools) = adapt_to_capabilities(model, caps, messages, tools);
+        let tool_choice = effective_tool_choice(&tools);
+        let system = system_for_sampling(system, &sampling);
+        let system = if self.native_tool_choice {
+            system
+        } else {
+            system_for_tool_choice(system, &tool_choice)
+        };
         let oai_messages = if self.enable_reasoning_content_bridge {
-            translate_messages_to_oai_with_reasoning(system, &messages, true)
+            translate_messages_to_oai_with_reasoning(&system, &messages, true)
         } else {
-            translate_messages_to_oai(system, &messages)
+            translate_messages_to_oai(&system, &messages)
         };
 
         let mut body = json!({
             "model": model,
             "messages": oai_messages,
+            "stream": true,
         });
         set_output_token_limit(
             &mut body,
-            self.max_tokens,
+            sampling.max_tokens().unwrap_or(self.max_tokens),
             self.prefer_max_completion_tokens,
         );
+        apply_sampling_params_oai_chat(&mut body, &sampling);
         maybe_enable_thinking_param(&mut body, self.enable_thinking_param);
+        strip_unsupported_oai_params(&mut body, model, caps);
         apply_openai_compat_body_overrides(
             &mut body,
             &self.provider,
@@ -1096,160 +2073,36 @@ impl LlmProvider for OpenAiProvider {
             &self.openai_compat_body_overrides_by_provider,
             &self.openai_compat_body_overrides_by_model,
         );
-        if let Some(obj) = body.as_object_mut() {
-            obj.remove("stream");
-        }
+        remove_nulled_sampling_params_oai(&mut body, &sampling, false);
+        apply_reasoning_knobs_oai(&mut body, model, caps, &sampling, false);
+        body["stream"] = json!(true);
+        let correlation_id = self.correlation_id();
+        apply_correlation_id_oai(&mut body, correlation_id.as_deref());
 
         if let Some(ref tool_defs) = tools {
             if !tool_defs.is_empty() {
                 body["tools"] = json!(translate_tools_to_oai(tool_defs));
+                if self.native_tool_choice && tool_choice != ToolChoice::Auto {
+                    body["tool_choice"] = oai_chat_tool_choice(&tool_choice);
+                }
             }
         }
 
-        let mut retries = 0u32;
-        let max_retries = 3;
-
-        loop {
-            let mut req = self
-                .http
-                .post(&self.chat_url)
-                .header("Content-Type", "application/json")
-                .json(&body);
-            if !self.api_key.trim().is_empty() {
-                req = req.header("Authorization", format!("Bearer {}", self.api_key));
-            }
-            let response = req.send().await?;
-
-            let status = response.status();
-
-            if status.is_success() {
-                let text = response.text().await?;
-                let oai: OaiResponse = serde_json::from_str(&text).map_err(|e| {
-                    MicroClawError::LlmApi(format!(
-                        "Failed to parse OpenAI response: {e}\nBody: {text}"
-                    ))
-                })?;
-                return Ok(translate_oai_response(oai));
-            }
-
-            if status.as_u16() == 429 && retries < max_retries {
-                retries += 1;
-                let delay = std::time::Duration::from_secs(2u64.pow(retries));
-                warn!(
-                    "Rate limited, retrying in {:?} (attempt {retries}/{max_retries})",
-                    delay
-                );
-                tokio::time::sleep(delay).await;
-                continue;
-            }
-
-            let text = response.text().await.unwrap_or_default();
-            if should_retry_with_max_completion_tokens(&text)
-                && switch_to_max_completion_tokens(&mut body)
-            {
-                warn!(
-                    "OpenAI-compatible API rejected max_tokens; retrying with max_completion_tokens"
-                );
-                continue;
-            }
-            if let Ok(err) = serde_json::from_str::<OaiErrorResponse>(&text) {
-                return Err(MicroClawError::LlmApi(err.error.message));
-            }
-            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {text}")));
-        }
-    }
-
-    async fn send_message_stream(
-        &self,
-        system: &str,
-        messages: Vec<Message>,
-        tools: Option<Vec<ToolDefinition>>,
-        text_tx: Option<&UnboundedSender<String>>,
-    ) -> Result<MessagesResponse, MicroClawError> {
-        self.send_message_stream_with_model(system, messages, tools, text_tx, None)
-            .await
-    }
-
-    async fn send_message_stream_with_model(
-        &self,
-        system: &str,
-        messages: Vec<Message>,
-        tools: Option<Vec<ToolDefinition>>,
-        text_tx: Option<&UnboundedSender<String>>,
-        model_override: Option<&str>,
-    ) -> Result<MessagesResponse, MicroClawError> {
-        let model = model_override
-            .map(str::trim)
-            .filter(|v| !v.is_empty())
-            .unwrap_or(&self.model);
-        if self.is_openai_codex {
-            let response = self
-                .send_codex_message(system, messages, tools, model)
-                .await?;
-            if let Some(tx) = text_tx {
-                let text = response
-                    .content
-                    .iter()
-                    .filter_map(|block| match block {
-                        ResponseContentBlock::Text { text } => Some(text.as_str()),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>()
-                    .join("");
-                if !text.is_empty() {
-                    let _ = tx.send(text);
-                }
-            }
-            return Ok(response);
-        }
-
-        let oai_messages = if self.enable_reasoning_content_bridge {
-            translate_messages_to_oai_with_reasoning(system, &messages, true)
-        } else {
-            translate_messages_to_oai(system, &messages)
-        };
-
-        let mut body = json!({
-            "model": model,
-            "messages": oai_messages,
-            "stream": true,
-        });
-        set_output_token_limit(
-            &mut body,
-            self.max_tokens,
-            self.prefer_max_completion_tokens,
-        );
-        maybe_enable_thinking_param(&mut body, self.enable_thinking_param);
-        apply_openai_compat_body_overrides(
-            &mut body,
-            &self.provider,
-            &self.model,
-            &self.openai_compat_body_overrides,
-            &self.openai_compat_body_overrides_by_provider,
-            &self.openai_compat_body_overrides_by_model,
-        );
-        body["stream"] = json!(true);
-
-        if let Some(ref tool_defs) = tools {
-            if !tool_defs.is_empty() {
-                body["tools"] = json!(translate_tools_to_oai(tool_defs));
-            }
-        }
-
-        debug!(
-            provider = %self.provider,
-            model = %model,
-            url = %self.chat_url,
-            messages_count = messages.len(),
-            "Sending LLM stream request"
-        );
+        debug!(
+            provider = %self.provider,
+            model = %model,
+            url = %self.chat_url,
+            correlation_id = correlation_id.as_deref().unwrap_or(""),
+            messages_count = messages.len(),
+            "Sending LLM stream request"
+        );
 
         let response = loop {
-            let mut req = self
+            let req = self
                 .http
                 .post(&self.chat_url)
-                .header("Content-Type", "application/json")
-                .json(&body);
+                .header("Content-Type", "application/json");
+            let mut req = with_correlation_header(req, correlation_id.as_deref()).json(&body);
             if !self.api_key.trim().is_empty() {
                 req = req.header("Authorization", format!("Bearer {}", self.api_key));
             }
@@ -1319,12 +2172,14 @@ impl LlmProvider for OpenAiProvider {
         }
 
         let mut content = Vec::new();
+        if !reasoning_text.is_empty() {
+            content.push(ResponseContentBlock::Thinking {
+                thinking: reasoning_text,
+                signature: None,
+            });
+        }
         if !text.is_empty() {
             content.push(ResponseContentBlock::Text { text });
-        } else if !reasoning_text.is_empty() && !tool_calls.is_empty() {
-            content.push(ResponseContentBlock::Text {
-                text: reasoning_text,
-            });
         }
         for (_index, tool) in tool_calls {
             content.push(ResponseContentBlock::ToolUse {
@@ -1339,42 +2194,76 @@ impl LlmProvider for OpenAiProvider {
             });
         }
 
-        Ok(MessagesResponse {
+        let response = MessagesResponse {
             content,
             stop_reason: normalize_stop_reason(stop_reason),
             usage,
-        })
+        };
+        log_finish_reason(&self.provider, model, &response);
+        Ok(response)
     }
 }
 
 impl OpenAiProvider {
-    async fn send_codex_message(
+    fn correlation_id(&self) -> Option<String> {
+        self.correlation_id.then(current_correlation_id).flatten()
+    }
+
+    fn sampling_for(&self, model: &str) -> SamplingParams {
+        resolve_sampling_params(
+            &self.sampling_params,
+            &self.sampling_params_by_provider,
+            &self.sampling_params_by_model,
+            &self.provider,
+            model,
+        )
+    }
+
+    async fn send_chat_message(
         &self,
         system: &str,
         messages: Vec<Message>,
         tools: Option<Vec<ToolDefinition>>,
         model: &str,
+        overrides: &SamplingParams,
     ) -> Result<MessagesResponse, MicroClawError> {
-        let instructions = if system.trim().is_empty() {
-            "You are a helpful assistant."
-        } else {
+        if self.is_openai_codex {
+            return self
+                .send_codex_message(system, messages, tools, model, overrides)
+                .await;
+        }
+
+        let sampling = apply_output_token_cap(
+            self.sampling_for(model).merged_with(overrides),
+            self.max_tokens,
+        );
+        let caps = model_capabilities_with_overrides(model, &self.capability_overrides);
+        let (messages, tools) = adapt_to_capabilities(model, caps, messages, tools);
+        let tool_choice = effective_tool_choice(&tools);
+        let system = system_for_sampling(system, &sampling);
+        let system = if self.native_tool_choice {
             system
+        } else {
+            system_for_tool_choice(system, &tool_choice)
         };
-        let mut input = translate_messages_to_oai_responses_input(&messages);
-        if input.is_empty() {
-            input.push(json!({
-                "type": "message",
-                "role": "user",
-                "content": "",
-            }));
-        }
+        let oai_messages = if self.enable_reasoning_content_bridge {
+            translate_messages_to_oai_with_reasoning(&system, &messages, true)
+        } else {
+            translate_messages_to_oai(&system, &messages)
+        };
+
         let mut body = json!({
             "model": model,
-            "input": input,
-            "instructions": instructions,
-            "store": false,
-            "stream": true,
+            "messages": oai_messages,
         });
+        set_output_token_limit(
+            &mut body,
+            sampling.max_tokens().unwrap_or(self.max_tokens),
+            self.prefer_max_completion_tokens,
+        );
+        apply_sampling_params_oai_chat(&mut body, &sampling);
+        maybe_enable_thinking_param(&mut body, self.enable_thinking_param);
+        strip_unsupported_oai_params(&mut body, model, caps);
         apply_openai_compat_body_overrides(
             &mut body,
             &self.provider,
@@ -1383,38 +2272,58 @@ impl OpenAiProvider {
             &self.openai_compat_body_overrides_by_provider,
             &self.openai_compat_body_overrides_by_model,
         );
-        body["stream"] = json!(true);
+        remove_nulled_sampling_params_oai(&mut body, &sampling, false);
+        apply_reasoning_knobs_oai(&mut body, model, caps, &sampling, false);
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove("stream");
+        }
+        let correlation_id = self.correlation_id();
+        apply_correlation_id_oai(&mut body, correlation_id.as_deref());
+
         if let Some(ref tool_defs) = tools {
             if !tool_defs.is_empty() {
-                body["tools"] = json!(translate_tools_to_oai_responses(tool_defs));
-                body["tool_choice"] = json!("auto");
+                body["tools"] = json!(translate_tools_to_oai(tool_defs));
+                if self.native_tool_choice && tool_choice != ToolChoice::Auto {
+                    body["tool_choice"] = oai_chat_tool_choice(&tool_choice);
+                }
             }
         }
 
+        debug!(
+            provider = %self.provider,
+            model = %model,
+            url = %self.chat_url,
+            correlation_id = correlation_id.as_deref().unwrap_or(""),
+            messages_count = messages.len(),
+            "Sending LLM request"
+        );
+
         let mut retries = 0u32;
         let max_retries = 3;
 
         loop {
-            let mut req = self
+            let req = self
                 .http
-                .post(&self.responses_url)
-                .header("Content-Type", "application/json")
-                .json(&body);
+                .post(&self.chat_url)
+                .header("Content-Type", "application/json");
+            let mut req = with_correlation_header(req, correlation_id.as_deref()).json(&body);
             if !self.api_key.trim().is_empty() {
                 req = req.header("Authorization", format!("Bearer {}", self.api_key));
             }
-            if let Some(account_id) = self.codex_account_id.as_deref() {
-                if !account_id.trim().is_empty() {
-                    req = req.header("ChatGPT-Account-ID", account_id);
-                }
-            }
             let response = req.send().await?;
+
             let status = response.status();
 
             if status.is_success() {
                 let text = response.text().await?;
-                let parsed = parse_openai_codex_response_payload(&text)?;
-                return Ok(translate_oai_responses_response(parsed));
+                let oai: OaiResponse = serde_json::from_str(&text).map_err(|e| {
+                    MicroClawError::LlmApi(format!(
+                        "Failed to parse OpenAI response: {e}\nBody: {text}"
+                    ))
+                })?;
+                let response = translate_oai_response(oai);
+                log_finish_reason(&self.provider, model, &response);
+                return Ok(response);
             }
 
             if status.as_u16() == 429 && retries < max_retries {
@@ -1429,63 +2338,222 @@ impl OpenAiProvider {
             }
 
             let text = response.text().await.unwrap_or_default();
+            if should_retry_with_max_completion_tokens(&text)
+                && switch_to_max_completion_tokens(&mut body)
+            {
+                warn!(
+                    "OpenAI-compatible API rejected max_tokens; retrying with max_completion_tokens"
+                );
+                continue;
+            }
             if let Ok(err) = serde_json::from_str::<OaiErrorResponse>(&text) {
                 return Err(MicroClawError::LlmApi(err.error.message));
             }
             return Err(MicroClawError::LlmApi(format!("HTTP {status}: {text}")));
         }
     }
-}
-
-fn parse_openai_codex_response_payload(text: &str) -> Result<OaiResponsesResponse, MicroClawError> {
-    if let Ok(parsed) = serde_json::from_str::<OaiResponsesResponse>(text) {
-        return Ok(parsed);
-    }
-
-    let mut from_done_event: Option<OaiResponsesResponse> = None;
-    for line in text.lines() {
-        let line = line.trim();
-        if !line.starts_with("data:") {
-            continue;
-        }
-        let payload = line.trim_start_matches("data:").trim();
-        if payload.is_empty() || payload == "[DONE]" {
-            continue;
-        }
-        let Ok(value) = serde_json::from_str::<serde_json::Value>(payload) else {
-            continue;
-        };
 
-        if let Some(response_value) = value.get("response") {
-            if let Ok(parsed) =
-                serde_json::from_value::<OaiResponsesResponse>(response_value.clone())
-            {
-                from_done_event = Some(parsed);
-                if value.get("type").and_then(|v| v.as_str()) == Some("response.done") {
-                    break;
-                }
-            }
-        }
+    fn current_codex_auth(&self) -> CodexAuthResolved {
+        self.codex_auth
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
     }
 
-    if let Some(parsed) = from_done_event {
-        return Ok(parsed);
+    fn replace_codex_auth(&self, auth: CodexAuthResolved) {
+        *self.codex_auth.write().unwrap_or_else(|e| e.into_inner()) = auth;
     }
 
-    Err(MicroClawError::LlmApi(format!(
-        "Failed to parse OpenAI Codex response payload. Body: {text}"
-    )))
-}
-
-// ---------------------------------------------------------------------------
-// Format translation helpers  (internal Anthropic-style ↔ OpenAI)
-// ---------------------------------------------------------------------------
-
-fn translate_messages_to_oai(system: &str, messages: &[Message]) -> Vec<serde_json::Value> {
-    translate_messages_to_oai_with_reasoning(system, messages, false)
-}
-
-fn translate_messages_to_oai_with_reasoning(
+    async fn send_codex_message(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        model: &str,
+        overrides: &SamplingParams,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        let sampling = apply_output_token_cap(
+            self.sampling_for(model).merged_with(overrides),
+            self.max_tokens,
+        );
+        let caps = model_capabilities_with_overrides(model, &self.capability_overrides);
+        let (messages, tools) = adapt_to_capabilities(model, caps, messages, tools);
+        let system = system_for_sampling(system, &sampling);
+        let instructions = if system.trim().is_empty() {
+            "You are a helpful assistant."
+        } else {
+            &system
+        };
+        let mut input = translate_messages_to_oai_responses_input(&messages);
+        if input.is_empty() {
+            input.push(json!({
+                "type": "message",
+                "role": "user",
+                "content": "",
+            }));
+        }
+        let mut body = json!({
+            "model": model,
+            "input": input,
+            "instructions": instructions,
+            "store": false,
+            "stream": true,
+        });
+        apply_sampling_params_oai_responses(&mut body, &sampling);
+        strip_unsupported_oai_params(&mut body, model, caps);
+        apply_openai_compat_body_overrides(
+            &mut body,
+            &self.provider,
+            &self.model,
+            &self.openai_compat_body_overrides,
+            &self.openai_compat_body_overrides_by_provider,
+            &self.openai_compat_body_overrides_by_model,
+        );
+        remove_nulled_sampling_params_oai(&mut body, &sampling, true);
+        apply_reasoning_knobs_oai(&mut body, model, caps, &sampling, true);
+        body["stream"] = json!(true);
+        if let Some(ref tool_defs) = tools {
+            if !tool_defs.is_empty() {
+                body["tools"] = json!(translate_tools_to_oai_responses(tool_defs));
+                body["tool_choice"] = oai_responses_tool_choice(&effective_tool_choice(&tools));
+            }
+        }
+
+        // Codex requests carry the correlation id in the header only.
+        let correlation_id = self.correlation_id();
+        let mut retries = 0u32;
+        let max_retries = 3;
+        let mut auth_refreshed = false;
+
+        if codex_token_expired(&self.current_codex_auth().bearer_token) {
+            match refresh_openai_codex_auth(false).await {
+                Ok(Some(auth)) => {
+                    info!("Refreshed expired OpenAI Codex access token");
+                    self.replace_codex_auth(auth);
+                    auth_refreshed = true;
+                }
+                Ok(None) => {}
+                Err(e) => warn!("{}", e),
+            }
+        }
+
+        loop {
+            let auth = self.current_codex_auth();
+            let req = self
+                .http
+                .post(&self.responses_url)
+                .header("Content-Type", "application/json");
+            let mut req = with_correlation_header(req, correlation_id.as_deref()).json(&body);
+            if !auth.bearer_token.trim().is_empty() {
+                req = req.header("Authorization", format!("Bearer {}", auth.bearer_token));
+            }
+            if let Some(account_id) = auth.account_id.as_deref() {
+                if !account_id.trim().is_empty() {
+                    req = req.header("ChatGPT-Account-ID", account_id);
+                }
+            }
+            let response = req.send().await?;
+            let status = response.status();
+
+            if status.is_success() {
+                let text = response.text().await?;
+                let parsed = parse_openai_codex_response_payload(&text)?;
+                let response = translate_oai_responses_response(parsed);
+                log_finish_reason(&self.provider, model, &response);
+                return Ok(response);
+            }
+
+            if status.as_u16() == 401 {
+                if !auth_refreshed {
+                    auth_refreshed = true;
+                    match refresh_openai_codex_auth(true).await {
+                        Ok(Some(auth)) => {
+                            info!("Refreshed OpenAI Codex access token after HTTP 401; retrying");
+                            self.replace_codex_auth(auth);
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            return Err(MicroClawError::LlmApi(format!(
+                                "OpenAI Codex rejected the access token (HTTP 401). {e}"
+                            )));
+                        }
+                    }
+                }
+                return Err(MicroClawError::LlmApi(
+                    "OpenAI Codex rejected the access token (HTTP 401) after refresh. Run `codex login` to re-authenticate.".into(),
+                ));
+            }
+
+            if status.as_u16() == 429 && retries < max_retries {
+                retries += 1;
+                let delay = std::time::Duration::from_secs(2u64.pow(retries));
+                warn!(
+                    "Rate limited, retrying in {:?} (attempt {retries}/{max_retries})",
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            let text = response.text().await.unwrap_or_default();
+            if let Ok(err) = serde_json::from_str::<OaiErrorResponse>(&text) {
+                return Err(MicroClawError::LlmApi(err.error.message));
+            }
+            return Err(MicroClawError::LlmApi(format!("HTTP {status}: {text}")));
+        }
+    }
+}
+
+fn parse_openai_codex_response_payload(text: &str) -> Result<OaiResponsesResponse, MicroClawError> {
+    if let Ok(parsed) = serde_json::from_str::<OaiResponsesResponse>(text) {
+        return Ok(parsed);
+    }
+
+    let mut from_done_event: Option<OaiResponsesResponse> = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if !line.starts_with("data:") {
+            continue;
+        }
+        let payload = line.trim_start_matches("data:").trim();
+        if payload.is_empty() || payload == "[DONE]" {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(payload) else {
+            continue;
+        };
+
+        if let Some(response_value) = value.get("response") {
+            if let Ok(parsed) =
+                serde_json::from_value::<OaiResponsesResponse>(response_value.clone())
+            {
+                from_done_event = Some(parsed);
+                if value.get("type").and_then(|v| v.as_str()) == Some("response.done") {
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(parsed) = from_done_event {
+        return Ok(parsed);
+    }
+
+    Err(MicroClawError::LlmApi(format!(
+        "Failed to parse OpenAI Codex response payload. Body: {text}"
+    )))
+}
+
+// ---------------------------------------------------------------------------
+// Format translation helpers  (internal Anthropic-style ↔ OpenAI)
+// ---------------------------------------------------------------------------
+
+fn translate_messages_to_oai(system: &str, messages: &[Message]) -> Vec<serde_json::Value> {
+    translate_messages_to_oai_with_reasoning(system, messages, false)
+}
+
+fn translate_messages_to_oai_with_reasoning(
     system: &str,
     messages: &[Message],
     include_reasoning_for_tool_calls: bool,
@@ -1599,18 +2667,10 @@ fn translate_messages_to_oai_with_reasoning(
                                     ContentBlock::Text { text } => {
                                         Some(json!({"type": "text", "text": text}))
                                     }
-                                    ContentBlock::Image {
-                                        source:
-                                            ImageSource {
-                                                media_type, data, ..
-                                            },
-                                    } => {
-                                        let url = format!("data:{media_type};base64,{data}");
-                                        Some(json!({
-                                            "type": "image_url",
-                                            "image_url": {"url": url}
-                                        }))
-                                    }
+                                    ContentBlock::Image { source } => Some(json!({
+                                        "type": "image_url",
+                                        "image_url": {"url": source.data_url()}
+                                    })),
                                     _ => None,
                                 })
                                 .collect();
@@ -1759,18 +2819,9 @@ fn translate_messages_to_oai_responses_input(messages: &[Message]) -> Vec<serde_
                                     ContentBlock::Text { text } => {
                                         Some(json!({"type": "input_text", "text": text}))
                                     }
-                                    ContentBlock::Image {
-                                        source:
-                                            ImageSource {
-                                                media_type, data, ..
-                                            },
-                                    } => Some(json!({
+                                    ContentBlock::Image { source } => Some(json!({
                                         "type": "input_image",
-                                        "source": {
-                                            "type": "base64",
-                                            "media_type": media_type,
-                                            "data": data,
-                                        }
+                                        "image_url": source.data_url(),
                                     })),
                                     _ => None,
                                 })
@@ -1849,13 +2900,15 @@ fn translate_oai_responses_response(resp: OaiResponsesResponse) -> MessagesRespo
         });
     }
 
+    let incomplete_reason = resp.incomplete_details.and_then(|d| d.reason);
+    let stop_reason = if saw_tool_use {
+        Some("tool_use".into())
+    } else {
+        normalize_stop_reason(incomplete_reason)
+    };
     MessagesResponse {
         content,
-        stop_reason: Some(if saw_tool_use {
-            "tool_use".into()
-        } else {
-            "end_turn".into()
-        }),
+        stop_reason,
         usage: resp.usage.map(|usage| Usage {
             input_tokens: usage.input_tokens,
             output_tokens: usage.output_tokens,
@@ -1878,21 +2931,27 @@ fn translate_oai_response(oai: OaiResponse) -> MessagesResponse {
     };
 
     let mut content = Vec::new();
-    let mut has_visible_text = false;
     let OaiMessage {
         content: message_content,
         reasoning_content,
         tool_calls,
     } = choice.message;
 
+    if let Some(thinking) = reasoning_content {
+        if !thinking.is_empty() {
+            content.push(ResponseContentBlock::Thinking {
+                thinking,
+                signature: None,
+            });
+        }
+    }
+
     if let Some(text) = message_content {
         if !text.is_empty() {
-            has_visible_text = true;
             content.push(ResponseContentBlock::Text { text });
         }
     }
 
-    let has_tool_calls = tool_calls.is_some();
     if let Some(tool_calls) = tool_calls {
         for tc in tool_calls {
             let input: serde_json::Value =
@@ -1905,25 +2964,13 @@ fn translate_oai_response(oai: OaiResponse) -> MessagesResponse {
         }
     }
 
-    if has_tool_calls && !has_visible_text {
-        if let Some(reasoning) = reasoning_content {
-            if !reasoning.is_empty() {
-                content.insert(0, ResponseContentBlock::Text { text: reasoning });
-            }
-        }
-    }
-
     if content.is_empty() {
         content.push(ResponseContentBlock::Text {
             text: String::new(),
         });
     }
 
-    let stop_reason = match choice.finish_reason.as_deref() {
-        Some("tool_calls") => Some("tool_use".into()),
-        Some("length") => Some("max_tokens".into()),
-        _ => Some("end_turn".into()),
-    };
+    let stop_reason = normalize_stop_reason(choice.finish_reason);
 
     let usage = oai.usage.map(|u| Usage {
         input_tokens: u.prompt_tokens,
@@ -1940,6 +2987,7 @@ fn translate_oai_response(oai: OaiResponse) -> MessagesResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use microclaw_core::llm_types::ImageSource;
     use serde_json::json;
     use std::io::{Read, Write};
     use std::net::TcpListener;
@@ -2171,6 +3219,27 @@ mod tests {
         assert_eq!(content[1]["text"], "describe");
     }
 
+    #[test]
+    fn test_translate_messages_to_oai_responses_image_block_uses_data_url() {
+        let msgs = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Blocks(vec![
+                ContentBlock::Image {
+                    source: ImageSource::base64("image/jpeg", "BBBB"),
+                },
+                ContentBlock::Text {
+                    text: "what is this".into(),
+                },
+            ]),
+        }];
+        let out = translate_messages_to_oai_responses_input(&msgs);
+        assert_eq!(out.len(), 1);
+        let content = out[0]["content"].as_array().unwrap();
+        assert_eq!(content[0]["type"], "input_image");
+        assert_eq!(content[0]["image_url"], "data:image/jpeg;base64,BBBB");
+        assert_eq!(content[1]["type"], "input_text");
+    }
+
     #[test]
     fn test_translate_messages_to_oai_responses_skips_stale_function_call_output() {
         let msgs = vec![
@@ -2390,8 +3459,8 @@ mod tests {
         let resp = translate_oai_response(oai);
         assert_eq!(resp.content.len(), 2);
         match &resp.content[0] {
-            ResponseContentBlock::Text { text } => assert_eq!(text, "plan"),
-            _ => panic!("Expected Text"),
+            ResponseContentBlock::Thinking { thinking, .. } => assert_eq!(thinking, "plan"),
+            _ => panic!("Expected Thinking"),
         }
         match &resp.content[1] {
             ResponseContentBlock::ToolUse { name, .. } => assert_eq!(name, "bash"),
@@ -2413,6 +3482,14 @@ mod tests {
             normalize_stop_reason(Some("stop".into())).as_deref(),
             Some("end_turn")
         );
+        assert_eq!(
+            normalize_stop_reason(Some("content_filter".into())).as_deref(),
+            Some("content_filter")
+        );
+        assert_eq!(
+            normalize_stop_reason(Some("pause_turn".into())).as_deref(),
+            Some("pause_turn")
+        );
     }
 
     #[test]
@@ -2443,95 +3520,870 @@ mod tests {
         assert_eq!(call.input_json, r#"{"command":"ls"}"#);
     }
 
-    #[test]
-    fn test_should_retry_with_max_completion_tokens() {
-        let err = r#"{"error":{"message":"Unsupported parameter: 'max_tokens' is not supported with this model. Use 'max_completion_tokens' instead.","param":"max_tokens"}}"#;
-        assert!(should_retry_with_max_completion_tokens(err));
-        assert!(!should_retry_with_max_completion_tokens(
-            r#"{"error":{"message":"bad request","param":"messages"}}"#
-        ));
+    #[test]
+    fn test_should_retry_with_max_completion_tokens() {
+        let err = r#"{"error":{"message":"Unsupported parameter: 'max_tokens' is not supported with this model. Use 'max_completion_tokens' instead.","param":"max_tokens"}}"#;
+        assert!(should_retry_with_max_completion_tokens(err));
+        assert!(!should_retry_with_max_completion_tokens(
+            r#"{"error":{"message":"bad request","param":"messages"}}"#
+        ));
+    }
+
+    #[test]
+    fn test_switch_to_max_completion_tokens() {
+        let mut body = json!({"model":"gpt-5.2","max_tokens":128});
+        assert!(switch_to_max_completion_tokens(&mut body));
+        assert_eq!(body.get("max_tokens"), None);
+        assert_eq!(body["max_completion_tokens"], 128);
+        assert!(!switch_to_max_completion_tokens(&mut body));
+    }
+
+    #[test]
+    fn test_maybe_enable_thinking_param_enabled() {
+        let mut body = json!({"model":"test-model","messages":[]});
+        maybe_enable_thinking_param(&mut body, true);
+        assert_eq!(body["thinking"]["type"], "enabled");
+    }
+
+    #[test]
+    fn test_maybe_enable_thinking_param_disabled() {
+        let mut body = json!({"model":"test-model","messages":[]});
+        maybe_enable_thinking_param(&mut body, false);
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[test]
+    fn test_apply_openai_compat_body_overrides_merges_global_provider_model() {
+        let mut body = json!({
+            "model": "gpt-5.2",
+            "temperature": 0.5,
+            "top_p": 0.9,
+            "stream": false
+        });
+        let mut global = HashMap::new();
+        global.insert("temperature".into(), json!(0.2));
+        global.insert("seed".into(), json!(42));
+        let mut by_provider = HashMap::new();
+        by_provider.insert(
+            "openai".into(),
+            HashMap::from([("top_p".into(), json!(0.8))]),
+        );
+        let mut by_model = HashMap::new();
+        by_model.insert(
+            "gpt-5.2".into(),
+            HashMap::from([("stream".into(), json!(true))]),
+        );
+
+        apply_openai_compat_body_overrides(
+            &mut body,
+            "openai",
+            "gpt-5.2",
+            &global,
+            &by_provider,
+            &by_model,
+        );
+
+        assert_eq!(body["temperature"], 0.2);
+        assert_eq!(body["top_p"], 0.8);
+        assert_eq!(body["seed"], 42);
+        assert_eq!(body["stream"], true);
+    }
+
+    #[test]
+    fn test_apply_openai_compat_body_overrides_null_unsets_key() {
+        let mut body = json!({"temperature": 0.7, "top_p": 0.9});
+        let mut by_provider = HashMap::new();
+        by_provider.insert(
+            "deepseek".into(),
+            HashMap::from([("top_p".into(), serde_json::Value::Null)]),
+        );
+
+        apply_openai_compat_body_overrides(
+            &mut body,
+            "deepseek",
+            "deepseek-chat",
+            &HashMap::new(),
+            &by_provider,
+            &HashMap::new(),
+        );
+
+        assert!(body.get("top_p").is_none());
+        assert_eq!(body["temperature"], 0.7);
+    }
+
+    #[test]
+    fn test_null_sampling_param_removes_body_override() {
+        let params: SamplingParams =
+            serde_yaml::from_str("temperature: null\nseed: null\nverbosity: null\n").unwrap();
+        let global = HashMap::from([
+            ("temperature".to_string(), json!(0.2)),
+            ("seed".to_string(), json!(42)),
+            ("top_p".to_string(), json!(0.8)),
+        ]);
+
+        let mut body = json!({"model": "gpt-4o", "messages": []});
+        apply_sampling_params_oai_chat(&mut body, &params);
+        apply_openai_compat_body_overrides(
+            &mut body,
+            "openai",
+            "gpt-4o",
+            &global,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        remove_nulled_sampling_params_oai(&mut body, &params, false);
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("seed").is_none());
+        assert_eq!(body["top_p"], 0.8);
+
+        let mut body = json!({"model": "gpt-5", "input": [], "text": {"verbosity": "high"}});
+        remove_nulled_sampling_params_oai(&mut body, &params, true);
+        assert!(body.get("text").is_none());
+    }
+
+    fn sampling(
+        temperature: Option<Option<f64>>,
+        top_p: Option<Option<f64>>,
+        max_tokens: Option<Option<u32>>,
+        reasoning_effort: Option<Option<&str>>,
+    ) -> SamplingParams {
+        SamplingParams {
+            temperature,
+            top_p,
+            max_tokens,
+            reasoning_effort: reasoning_effort.map(|v| v.map(str::to_string)),
+            ..SamplingParams::default()
+        }
+    }
+
+    #[test]
+    fn test_anthropic_request_applies_sampling_precedence() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        config.max_tokens = 4096;
+        config.sampling_params = sampling(Some(Some(0.7)), Some(Some(0.9)), None, None);
+        config.sampling_params_by_provider.insert(
+            "anthropic".into(),
+            sampling(Some(Some(0.3)), None, Some(Some(2048)), Some(Some("high"))),
+        );
+        config.sampling_params_by_model.insert(
+            "claude-haiku".into(),
+            sampling(None, Some(None), Some(None), None),
+        );
+        let provider = AnthropicProvider::new(&config);
+
+        let body = serde_json::to_value(provider.build_request(
+            "claude-sonnet",
+            "sys",
+            vec![],
+            None,
+            None,
+            &SamplingParams::default(),
+        ))
+        .unwrap();
+        assert_eq!(body["temperature"], 0.3);
+        assert_eq!(body["top_p"], 0.9);
+        assert_eq!(body["max_tokens"], 2048);
+        assert!(body.get("reasoning_effort").is_none());
+
+        // Per-model nulls unset top_p and fall back to the top-level max_tokens.
+        let body = serde_json::to_value(provider.build_request(
+            "claude-haiku",
+            "sys",
+            vec![],
+            None,
+            None,
+            &SamplingParams::default(),
+        ))
+        .unwrap();
+        assert_eq!(body["temperature"], 0.3);
+        assert!(body.get("top_p").is_none());
+        assert_eq!(body["max_tokens"], 4096);
+    }
+
+    #[tokio::test]
+    async fn test_output_token_cap_lowers_request_max_tokens() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        config.max_tokens = 4096;
+        let provider = AnthropicProvider::new(&config);
+        let request = |overrides: SamplingParams| {
+            serde_json::to_value(provider.build_request(
+                "claude-sonnet",
+                "sys",
+                vec![],
+                None,
+                None,
+                &overrides,
+            ))
+            .unwrap()
+        };
+
+        let capped =
+            with_output_token_cap(Some(300), async { request(SamplingParams::default()) }).await;
+        assert_eq!(capped["max_tokens"], 300);
+        // A cap above the configured limit changes nothing.
+        let loose = with_output_token_cap(Some(10_000), async {
+            request(sampling(None, None, Some(Some(2048)), None))
+        })
+        .await;
+        assert_eq!(loose["max_tokens"], 2048);
+        let uncapped =
+            with_output_token_cap(None, async { request(SamplingParams::default()) }).await;
+        assert_eq!(uncapped["max_tokens"], 4096);
+    }
+
+    #[test]
+    fn test_anthropic_request_stop_sequences_and_json_instruction() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        config.sampling_params.stop = Some(Some(vec!["</answer>".into()]));
+        let provider = AnthropicProvider::new(&config);
+
+        let body = serde_json::to_value(provider.build_request(
+            "claude-sonnet",
+            "sys",
+            vec![],
+            None,
+            None,
+            &SamplingParams::json_object(),
+        ))
+        .unwrap();
+        assert_eq!(body["stop_sequences"], json!(["</answer>"]));
+        let system = body["system"].as_str().unwrap();
+        assert!(system.starts_with("sys\n\n"));
+        assert!(system.contains("single valid JSON object"));
+        assert!(body.get("response_format").is_none());
+    }
+
+    #[test]
+    fn test_anthropic_request_extended_thinking() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        config.max_tokens = 8192;
+        config.anthropic_thinking = true;
+        config.anthropic_thinking_budget_tokens = 2048;
+        config.sampling_params = sampling(Some(Some(0.2)), Some(Some(0.9)), None, None);
+        let provider = AnthropicProvider::new(&config);
+        let tools = Some(vec![ToolDefinition {
+            name: "bash".into(),
+            description: "run".into(),
+            input_schema: json!({"type": "object"}),
+        }]);
+
+        let request = provider.build_request(
+            "claude-sonnet-4-5",
+            "sys",
+            vec![],
+            tools.clone(),
+            None,
+            &SamplingParams::default(),
+        );
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            body["thinking"],
+            json!({"type": "enabled", "budget_tokens": 2048})
+        );
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("top_p").is_none());
+        let built = provider.post(&request).build().unwrap();
+        assert!(built.headers().get("anthropic-beta").is_none());
+
+        // A budget that no longer fits under a per-request max_tokens turns thinking off.
+        let overrides = sampling(None, None, Some(Some(1024)), None);
+        let body = serde_json::to_value(provider.build_request(
+            "claude-sonnet-4-5",
+            "sys",
+            vec![],
+            None,
+            None,
+            &overrides,
+        ))
+        .unwrap();
+        assert!(body.get("thinking").is_none());
+        assert_eq!(body["temperature"], 0.2);
+
+        // Forced tool calls are not allowed with extended thinking.
+        let body = TOOL_CHOICE.sync_scope(ToolChoice::Required, || {
+            serde_json::to_value(provider.build_request(
+                "claude-sonnet-4-5",
+                "sys",
+                vec![],
+                tools.clone(),
+                None,
+                &SamplingParams::default(),
+            ))
+            .unwrap()
+        });
+        assert!(body.get("thinking").is_none());
+        assert_eq!(body["tool_choice"]["type"], "any");
+
+        config.anthropic_interleaved_thinking = true;
+        let provider = AnthropicProvider::new(&config);
+        let request = provider.build_request(
+            "claude-sonnet-4-5",
+            "sys",
+            vec![],
+            tools,
+            None,
+            &SamplingParams::default(),
+        );
+        let built = provider.post(&request).build().unwrap();
+        assert_eq!(
+            built.headers()["anthropic-beta"],
+            ANTHROPIC_INTERLEAVED_THINKING_BETA
+        );
+    }
+
+    #[test]
+    fn test_model_capabilities_registry() {
+        let sonnet = model_capabilities("claude-sonnet-4-5");
+        assert!(sonnet.tools && sonnet.vision && sonnet.thinking && !sonnet.json_mode);
+        assert!(!model_capabilities("claude-3-5-haiku-latest").thinking);
+        assert!(model_capabilities("openai/GPT-4o-mini").json_mode);
+        assert!(!model_capabilities("gpt-4o").thinking);
+        assert!(!model_capabilities("o1-mini").tools);
+        assert!(model_capabilities("qwen2.5vl:7b").vision);
+        assert!(!model_capabilities("qwen2.5:7b").vision);
+        assert_eq!(
+            model_capabilities("my-local-model"),
+            ModelCapabilities::BASELINE
+        );
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(!glob_matches("a*b", "abc"));
+
+        let bedrock = model_capabilities("us.anthropic.claude-sonnet-4-20250514-v1:0");
+        assert!(bedrock.vision && bedrock.thinking);
+        assert!(model_capabilities("anthropic.claude-3-5-sonnet-20240620-v1:0").vision);
+        assert!(model_capabilities("llava:13b").vision);
+        assert!(model_capabilities("gpt-4.1-mini").json_mode);
+        assert_eq!(
+            model_capabilities("v1.my-model"),
+            ModelCapabilities::BASELINE
+        );
+    }
+
+    #[test]
+    fn test_model_capability_overrides() {
+        let yaml = r#"
+bot_username: bot
+api_key: key
+channels:
+  feishu:
+    app_id: "test"
+    app_secret: "secret"
+model_capabilities:
+  " Prod-GPT4o* ":
+    vision: true
+    json_mode: true
+  "prod-gpt4o-mini*":
+    vision: false
+  "claude-*":
+    thinking: false
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.post_deserialize().unwrap();
+        let overrides = &config.model_capabilities;
+
+        let deployment = model_capabilities_with_overrides("prod-gpt4o-eu", overrides);
+        assert!(deployment.tools && deployment.vision && deployment.json_mode);
+        assert!(!deployment.thinking);
+        let mini = model_capabilities_with_overrides("azure/prod-gpt4o-mini-1", overrides);
+        assert!(!mini.vision && !mini.json_mode);
+        let sonnet = model_capabilities_with_overrides("claude-sonnet-4-5", overrides);
+        assert!(sonnet.vision && !sonnet.thinking);
+        assert_eq!(
+            model_capabilities_with_overrides("gpt-5", overrides),
+            model_capabilities("gpt-5")
+        );
+    }
+
+    #[test]
+    fn test_adapt_to_capabilities_drops_tools_and_images() {
+        let messages = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Blocks(vec![
+                ContentBlock::Text {
+                    text: "what is this?".into(),
+                },
+                ContentBlock::Image {
+                    source: ImageSource::base64("image/png", "AAAA"),
+                },
+            ]),
+        }];
+        let tools = Some(vec![ToolDefinition {
+            name: "bash".into(),
+            description: "run".into(),
+            input_schema: json!({"type": "object"}),
+        }]);
+
+        let (kept, kept_tools) = adapt_to_capabilities(
+            "gpt-4o",
+            model_capabilities("gpt-4o"),
+            messages.clone(),
+            tools.clone(),
+        );
+        assert!(kept_tools.is_some());
+        let MessageContent::Blocks(blocks) = &kept[0].content else {
+            panic!("expected blocks");
+        };
+        assert!(matches!(blocks[1], ContentBlock::Image { .. }));
+
+        let (adapted, adapted_tools) =
+            adapt_to_capabilities("o1-mini", model_capabilities("o1-mini"), messages, tools);
+        assert!(adapted_tools.is_none());
+        let MessageContent::Blocks(blocks) = &adapted[0].content else {
+            panic!("expected blocks");
+        };
+        assert!(
+            matches!(&blocks[1], ContentBlock::Text { text } if text.contains("image omitted"))
+        );
+    }
+
+    #[test]
+    fn test_strip_unsupported_oai_params() {
+        let mut body = json!({
+            "model": "my-local-model",
+            "response_format": {"type": "json_object"},
+            "reasoning_effort": "high",
+            "thinking": {"type": "enabled"},
+            "temperature": 0.2,
+        });
+        strip_unsupported_oai_params(&mut body, "my-local-model", ModelCapabilities::BASELINE);
+        assert_eq!(body, json!({"model": "my-local-model", "temperature": 0.2}));
+
+        let mut body = json!({
+            "text": {"format": {"type": "json_object"}},
+            "reasoning": {"effort": "high"},
+        });
+        strip_unsupported_oai_params(&mut body, "gpt-5", model_capabilities("gpt-5"));
+        assert_eq!(body["reasoning"]["effort"], "high");
+        assert_eq!(body["text"]["format"]["type"], "json_object");
+        strip_unsupported_oai_params(&mut body, "gpt-4", model_capabilities("gpt-4"));
+        assert_eq!(body, json!({}));
+
+        // DeepSeek chat models take the `thinking` toggle that `show_thinking` sets.
+        let mut body = json!({"thinking": {"type": "enabled"}, "reasoning_effort": "high"});
+        strip_unsupported_oai_params(
+            &mut body,
+            "deepseek-chat",
+            model_capabilities("deepseek-chat"),
+        );
+        assert_eq!(body, json!({"thinking": {"type": "enabled"}}));
+    }
+
+    #[test]
+    fn test_anthropic_request_skips_thinking_for_models_without_it() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        config.max_tokens = 8192;
+        config.anthropic_thinking = true;
+        config.anthropic_thinking_budget_tokens = 2048;
+        let provider = AnthropicProvider::new(&config);
+
+        let body = serde_json::to_value(provider.build_request(
+            "claude-3-5-haiku-latest",
+            "sys",
+            vec![],
+            None,
+            None,
+            &SamplingParams::default(),
+        ))
+        .unwrap();
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[test]
+    fn test_anthropic_thinking_knob_overrides_config() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        config.max_tokens = 8192;
+        config.anthropic_thinking_budget_tokens = 2048;
+        config.sampling_params_by_model.insert(
+            "claude-sonnet-4-5".into(),
+            SamplingParams {
+                thinking: Some(Some(true)),
+                ..SamplingParams::default()
+            },
+        );
+        let provider = AnthropicProvider::new(&config);
+        let build = |model: &str, overrides: &SamplingParams| {
+            serde_json::to_value(provider.build_request(
+                model,
+                "sys",
+                vec![],
+                None,
+                None,
+                overrides,
+            ))
+            .unwrap()
+        };
+
+        let body = build("claude-sonnet-4-5", &SamplingParams::default());
+        assert_eq!(body["thinking"]["budget_tokens"], 2048);
+        assert!(build("claude-opus-4-1", &SamplingParams::default())
+            .get("thinking")
+            .is_none());
+        let off = SamplingParams {
+            thinking: Some(Some(false)),
+            ..SamplingParams::default()
+        };
+        assert!(build("claude-sonnet-4-5", &off).get("thinking").is_none());
+    }
+
+    #[test]
+    fn test_reasoning_knobs_win_over_overrides_where_supported() {
+        let knobs = SamplingParams {
+            reasoning_effort: Some(Some("high".into())),
+            verbosity: Some(Some("low".into())),
+            thinking: Some(Some(false)),
+            ..SamplingParams::default()
+        };
+        let overrides = json!({"reasoning_effort": "low", "verbosity": "high"});
+
+        let mut body = overrides.clone();
+        apply_reasoning_knobs_oai(
+            &mut body,
+            "gpt-5",
+            model_capabilities("gpt-5"),
+            &knobs,
+            false,
+        );
+        assert_eq!(
+            body,
+            json!({"reasoning_effort": "high", "verbosity": "low"})
+        );
+
+        // Unsupported knobs are left out; a raw override stays as the escape hatch.
+        let mut body = overrides.clone();
+        apply_reasoning_knobs_oai(
+            &mut body,
+            "gpt-4o",
+            model_capabilities("gpt-4o"),
+            &knobs,
+            false,
+        );
+        assert_eq!(body, overrides);
+
+        let mut body = json!({});
+        let caps = model_capabilities("deepseek-chat");
+        apply_reasoning_knobs_oai(&mut body, "deepseek-chat", caps, &knobs, false);
+        assert_eq!(body, json!({"thinking": {"type": "disabled"}}));
+
+        let mut body = json!({
+            "text": {"format": {"type": "json_object"}},
+            "reasoning": {"effort": "minimal", "summary": "auto"},
+        });
+        apply_reasoning_knobs_oai(
+            &mut body,
+            "gpt-5",
+            model_capabilities("gpt-5"),
+            &knobs,
+            true,
+        );
+        assert_eq!(
+            body,
+            json!({
+                "text": {"format": {"type": "json_object"}, "verbosity": "low"},
+                "reasoning": {"effort": "high", "summary": "auto"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_seed_sent_only_where_supported() {
+        let params = SamplingParams {
+            temperature: Some(Some(0.0)),
+            seed: Some(Some(7)),
+            ..SamplingParams::default()
+        };
+        let mut body = json!({"model": "gpt-4o", "messages": []});
+        apply_sampling_params_oai_chat(&mut body, &params);
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["temperature"], 0.0);
+
+        let mut body = json!({"model": "gpt-5", "input": []});
+        apply_sampling_params_oai_responses(&mut body, &params);
+        assert!(body.get("seed").is_none());
+        assert_eq!(body["temperature"], 0.0);
+
+        assert!(provider_supports_seed("openai"));
+        assert!(provider_supports_seed("deepseek"));
+        assert!(!provider_supports_seed(" Anthropic "));
+        assert!(!provider_supports_seed("openai-codex"));
+    }
+
+    #[test]
+    fn test_apply_sampling_params_oai_chat_stop_and_json_mode() {
+        let mut body = json!({"model": "gpt-4o", "messages": []});
+        let params = SamplingParams {
+            stop: Some(Some(vec!["END".into()])),
+            ..SamplingParams::json_object()
+        };
+        apply_sampling_params_oai_chat(&mut body, &params);
+        assert_eq!(body["stop"], json!(["END"]));
+        assert_eq!(body["response_format"]["type"], "json_object");
+
+        let mut body = json!({"model": "gpt-5.3-codex", "input": []});
+        apply_sampling_params_oai_responses(&mut body, &params);
+        assert!(body.get("stop").is_none());
+        assert_eq!(body["text"]["format"]["type"], "json_object");
+    }
+
+    #[test]
+    fn test_apply_sampling_params_oai_responses_uses_responses_names() {
+        let mut body = json!({"model": "gpt-5.3-codex", "input": []});
+        let params = sampling(None, Some(Some(0.5)), Some(Some(1000)), Some(Some("low")));
+        apply_sampling_params_oai_responses(&mut body, &params);
+        let caps = model_capabilities("gpt-5.3-codex");
+        apply_reasoning_knobs_oai(&mut body, "gpt-5.3-codex", caps, &params, true);
+        assert_eq!(body["top_p"], 0.5);
+        assert_eq!(body["max_output_tokens"], 1000);
+        assert_eq!(body["reasoning"]["effort"], "low");
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("max_tokens").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_openai_request_carries_correlation_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (req_tx, req_rx) = mpsc::channel::<(String, serde_json::Value)>();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(2)))
+                .unwrap();
+            let mut req = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = stream.read(&mut buf).unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                req.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&req);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let len = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= len {
+                        let _ = req_tx.send((
+                            head.to_ascii_lowercase(),
+                            serde_json::from_str(body).unwrap(),
+                        ));
+                        break;
+                    }
+                }
+            }
+
+            let body = r#"{"choices":[{"message":{"content":"ok"},"finish_reason":"stop"}],"usage":{"prompt_tokens":1,"completion_tokens":1}}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+            let _ = stream.flush();
+        });
+
+        let mut config = Config::test_defaults();
+        config.llm_provider = "openai".into();
+        config.api_key = "sk-test".into();
+        config.llm_base_url = Some(format!("http://{addr}"));
+        let provider = OpenAiProvider::new(&config);
+        let messages = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Text("hi".into()),
+        }];
+        let context = LlmRequestContext {
+            correlation_id: "mc-turn-1".into(),
+        };
+        with_request_context(
+            context,
+            LlmProvider::send_message(&provider, "", messages, None),
+        )
+        .await
+        .unwrap();
+
+        let (head, body) = req_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        server.join().unwrap();
+        assert_eq!(body["user"], "mc-turn-1");
+        assert!(head.contains("x-client-request-id: mc-turn-1"));
     }
 
-    #[test]
-    fn test_switch_to_max_completion_tokens() {
-        let mut body = json!({"model":"gpt-5.2","max_tokens":128});
-        assert!(switch_to_max_completion_tokens(&mut body));
-        assert_eq!(body.get("max_tokens"), None);
-        assert_eq!(body["max_completion_tokens"], 128);
-        assert!(!switch_to_max_completion_tokens(&mut body));
-    }
+    #[tokio::test]
+    async fn test_anthropic_request_metadata_follows_request_context() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "anthropic".into();
+        let provider = AnthropicProvider::new(&config);
+        let build =
+            || provider.build_request("m", "", vec![], None, None, &SamplingParams::default());
+        assert!(build().metadata.is_none());
+
+        let context = LlmRequestContext {
+            correlation_id: "mc-turn-2".into(),
+        };
+        let request = with_request_context(context.clone(), async { build() }).await;
+        assert_eq!(request.metadata.unwrap().user_id, "mc-turn-2");
 
-    #[test]
-    fn test_maybe_enable_thinking_param_enabled() {
-        let mut body = json!({"model":"test-model","messages":[]});
-        maybe_enable_thinking_param(&mut body, true);
-        assert_eq!(body["thinking"]["type"], "enabled");
+        config.llm_correlation_id = false;
+        let provider = AnthropicProvider::new(&config);
+        let request = with_request_context(context, async {
+            provider.build_request("m", "", vec![], None, None, &SamplingParams::default())
+        })
+        .await;
+        assert!(request.metadata.is_none());
     }
 
-    #[test]
-    fn test_maybe_enable_thinking_param_disabled() {
-        let mut body = json!({"model":"test-model","messages":[]});
-        maybe_enable_thinking_param(&mut body, false);
-        assert!(body.get("thinking").is_none());
+    #[tokio::test]
+    async fn test_anthropic_tool_choice_applies_only_with_tools() {
+        let config = Config::test_defaults();
+        let provider = AnthropicProvider::new(&config);
+        let tools = vec![ToolDefinition {
+            name: "web_search".into(),
+            description: "Search".into(),
+            input_schema: json!({"type": "object"}),
+        }];
+        let build = |tools: Option<Vec<ToolDefinition>>| {
+            provider.build_request("m", "", vec![], tools, None, &SamplingParams::default())
+        };
+        assert!(build(Some(tools.clone())).tool_choice.is_none());
+
+        let forced = ToolChoice::Tool("web_search".into());
+        let request = with_tool_choice(forced.clone(), async { build(Some(tools.clone())) }).await;
+        assert_eq!(
+            request.tool_choice,
+            Some(json!({"type": "tool", "name": "web_search"}))
+        );
+        let request =
+            with_tool_choice(ToolChoice::Required, async { build(Some(tools.clone())) }).await;
+        assert_eq!(request.tool_choice, Some(json!({"type": "any"})));
+        let request = with_tool_choice(forced, async { build(None) }).await;
+        assert!(request.tool_choice.is_none());
     }
 
     #[test]
-    fn test_apply_openai_compat_body_overrides_merges_global_provider_model() {
-        let mut body = json!({
-            "model": "gpt-5.2",
-            "temperature": 0.5,
-            "top_p": 0.9,
-            "stream": false
-        });
-        let mut global = HashMap::new();
-        global.insert("temperature".into(), json!(0.2));
-        global.insert("seed".into(), json!(42));
-        let mut by_provider = HashMap::new();
-        by_provider.insert(
-            "openai".into(),
-            HashMap::from([("top_p".into(), json!(0.8))]),
+    fn test_tool_choice_openai_mapping_and_instruction_fallback() {
+        assert_eq!(
+            oai_chat_tool_choice(&ToolChoice::Required),
+            json!("required")
         );
-        let mut by_model = HashMap::new();
-        by_model.insert(
-            "gpt-5.2".into(),
-            HashMap::from([("stream".into(), json!(true))]),
+        assert_eq!(
+            oai_chat_tool_choice(&ToolChoice::Tool("bash".into())),
+            json!({"type": "function", "function": {"name": "bash"}})
         );
+        assert_eq!(
+            oai_responses_tool_choice(&ToolChoice::Tool("bash".into())),
+            json!({"type": "function", "name": "bash"})
+        );
+        assert_eq!(oai_responses_tool_choice(&ToolChoice::None), json!("none"));
 
-        apply_openai_compat_body_overrides(
-            &mut body,
-            "openai",
-            "gpt-5.2",
-            &global,
-            &by_provider,
-            &by_model,
+        let system = system_for_tool_choice(Cow::Borrowed("Be brief."), &ToolChoice::Auto);
+        assert_eq!(system, "Be brief.");
+        let system = system_for_tool_choice(
+            Cow::Borrowed("Be brief."),
+            &ToolChoice::Tool("web_search".into()),
         );
+        assert!(system.starts_with("Be brief.\n\nYou must call the `web_search` tool"));
+        assert!(system_for_tool_choice(Cow::Borrowed(""), &ToolChoice::None)
+            .starts_with("Do not call any tools"));
 
-        assert_eq!(body["temperature"], 0.2);
-        assert_eq!(body["top_p"], 0.8);
-        assert_eq!(body["seed"], 42);
-        assert_eq!(body["stream"], true);
+        let mut config = Config::test_defaults();
+        config.llm_provider = "ollama".into();
+        assert!(!OpenAiProvider::new(&config).native_tool_choice);
+        config.llm_provider = "openai".into();
+        assert!(OpenAiProvider::new(&config).native_tool_choice);
+        config.llm_native_tool_choice = false;
+        assert!(!OpenAiProvider::new(&config).native_tool_choice);
     }
 
-    #[test]
-    fn test_apply_openai_compat_body_overrides_null_unsets_key() {
-        let mut body = json!({"temperature": 0.7, "top_p": 0.9});
-        let mut by_provider = HashMap::new();
-        by_provider.insert(
-            "deepseek".into(),
-            HashMap::from([("top_p".into(), serde_json::Value::Null)]),
-        );
+    #[tokio::test]
+    async fn test_openai_request_body_merges_sampling_and_body_overrides() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (body_tx, body_rx) = mpsc::channel::<serde_json::Value>();
 
-        apply_openai_compat_body_overrides(
-            &mut body,
-            "deepseek",
-            "deepseek-chat",
-            &HashMap::new(),
-            &by_provider,
-            &HashMap::new(),
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(2)))
+                .unwrap();
+            let mut req = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = stream.read(&mut buf).unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                req.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&req);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let len = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= len {
+                        let _ = body_tx.send(serde_json::from_str(body).unwrap());
+                        break;
+                    }
+                }
+            }
+
+            let body = r#"{"choices":[{"message":{"content":"ok"},"finish_reason":"stop"}],"usage":{"prompt_tokens":1,"completion_tokens":1}}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+            let _ = stream.flush();
+        });
+
+        let mut config = Config::test_defaults();
+        config.llm_provider = "openai".into();
+        config.api_key = "sk-test".into();
+        config.model = "gpt-5.2".into();
+        config.llm_base_url = Some(format!("http://{addr}"));
+        config.sampling_params = sampling(Some(Some(1.0)), Some(Some(0.9)), None, None);
+        config.sampling_params_by_provider.insert(
+            "openai".into(),
+            sampling(None, None, Some(Some(1024)), Some(Some("medium"))),
+        );
+        config.sampling_params_by_model.insert(
+            "gpt-5.2".into(),
+            sampling(Some(None), None, None, Some(Some("high"))),
         );
+        config
+            .openai_compat_body_overrides
+            .insert("top_p".into(), serde_json::Value::Null);
+        let provider = OpenAiProvider::new(&config);
+        let messages = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Text("hi".into()),
+        }];
+        LlmProvider::send_message(&provider, "", messages, None)
+            .await
+            .unwrap();
 
+        let body = body_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        server.join().unwrap();
+        assert!(body.get("temperature").is_none());
+        // Raw body overrides are applied last and may still remove a sampling field.
         assert!(body.get("top_p").is_none());
-        assert_eq!(body["temperature"], 0.7);
+        assert_eq!(body["max_completion_tokens"], 1024);
+        assert!(body.get("max_tokens").is_none());
+        assert_eq!(body["reasoning_effort"], "high");
     }
 
     #[test]
@@ -2579,6 +4431,7 @@ mod tests {
         let resp = build_stream_response(
             vec![0],
             std::collections::HashMap::new(),
+            std::collections::HashMap::new(),
             tool_blocks,
             Some("tool_use".into()),
             None,
@@ -2595,6 +4448,97 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_anthropic_stream_thinking_is_separated_and_not_streamed() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let mut stop_reason = None;
+        let mut usage = None;
+        let mut text_blocks = std::collections::HashMap::new();
+        let mut thinking_blocks = std::collections::HashMap::new();
+        let mut tool_blocks = std::collections::HashMap::new();
+        let mut ordered_indexes = Vec::new();
+        for data in [
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"secret plan"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Hi"}}"#,
+        ] {
+            process_anthropic_stream_event(
+                data,
+                Some(&tx),
+                &mut stop_reason,
+                &mut usage,
+                &mut text_blocks,
+                &mut thinking_blocks,
+                &mut tool_blocks,
+                &mut ordered_indexes,
+            );
+        }
+        assert_eq!(rx.try_recv().unwrap(), "Hi");
+        assert!(rx.try_recv().is_err(), "thinking must not be streamed");
+
+        let resp = build_stream_response(
+            ordered_indexes,
+            text_blocks,
+            thinking_blocks,
+            tool_blocks,
+            stop_reason,
+            usage,
+        );
+        assert!(matches!(
+            &resp.content[0],
+            ResponseContentBlock::Thinking { thinking, signature: None } if thinking == "secret plan"
+        ));
+        assert!(matches!(
+            &resp.content[1],
+            ResponseContentBlock::Text { text } if text == "Hi"
+        ));
+    }
+
+    #[test]
+    fn test_anthropic_stream_keeps_thinking_signature_and_redacted_blocks() {
+        let mut stop_reason = None;
+        let mut usage = None;
+        let mut text_blocks = std::collections::HashMap::new();
+        let mut thinking_blocks = std::collections::HashMap::new();
+        let mut tool_blocks = std::collections::HashMap::new();
+        let mut ordered_indexes = Vec::new();
+        for data in [
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":"","signature":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"plan"}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"EqQB"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"redacted_thinking","data":"opaque"}}"#,
+        ] {
+            process_anthropic_stream_event(
+                data,
+                None,
+                &mut stop_reason,
+                &mut usage,
+                &mut text_blocks,
+                &mut thinking_blocks,
+                &mut tool_blocks,
+                &mut ordered_indexes,
+            );
+        }
+        let resp = build_stream_response(
+            ordered_indexes,
+            text_blocks,
+            thinking_blocks,
+            tool_blocks,
+            stop_reason,
+            usage,
+        );
+        assert!(matches!(
+            &resp.content[0],
+            ResponseContentBlock::Thinking { thinking, signature }
+                if thinking == "plan" && signature.as_deref() == Some("EqQB")
+        ));
+        assert!(matches!(
+            &resp.content[1],
+            ResponseContentBlock::RedactedThinking { data } if data == "opaque"
+        ));
+    }
+
     // -----------------------------------------------------------------------
     // create_provider
     // -----------------------------------------------------------------------
@@ -2732,6 +4676,139 @@ mod tests {
         assert_eq!(rx.recv().await.as_deref(), Some("ok"));
     }
 
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn test_openai_codex_refreshes_token_on_401_and_retries_once() {
+        let _guard = env_lock();
+        let prev_access = std::env::var("OPENAI_CODEX_ACCESS_TOKEN").ok();
+        let prev_codex_home = std::env::var("CODEX_HOME").ok();
+        let prev_refresh_url = std::env::var("CODEX_REFRESH_TOKEN_URL_OVERRIDE").ok();
+        std::env::remove_var("OPENAI_CODEX_ACCESS_TOKEN");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let codex_home = std::env::temp_dir().join(format!(
+            "microclaw-codex-home-refresh-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&codex_home).unwrap();
+        std::fs::write(
+            codex_home.join("config.toml"),
+            format!(
+                "model_provider = \"test\"\n\n[model_providers.test]\nbase_url = \"http://{}\"\n",
+                addr
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            codex_home.join("auth.json"),
+            r#"{"tokens":{"access_token":"stale-token","refresh_token":"refresh-1","account_id":"acct"}}"#,
+        )
+        .unwrap();
+        std::env::set_var("CODEX_HOME", &codex_home);
+        std::env::set_var(
+            "CODEX_REFRESH_TOKEN_URL_OVERRIDE",
+            format!("http://{addr}/oauth/token"),
+        );
+        let (request_tx, request_rx) = mpsc::channel::<(String, Option<String>)>();
+
+        let server = std::thread::spawn(move || {
+            for _ in 0..3 {
+                let (mut stream, _) = listener.accept().unwrap();
+                stream
+                    .set_read_timeout(Some(Duration::from_secs(2)))
+                    .unwrap();
+                let mut buf = [0u8; 8192];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let req = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = req
+                    .lines()
+                    .next()
+                    .and_then(|line| line.split_whitespace().nth(1))
+                    .unwrap_or("")
+                    .to_string();
+                let auth_header = req.lines().find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .starts_with("authorization:")
+                        .then(|| {
+                            line.split_once(':')
+                                .map(|(_, v)| v.trim().to_string())
+                                .unwrap_or_default()
+                        })
+                });
+                let (status, body) = if path == "/oauth/token" {
+                    (
+                        "200 OK",
+                        r#"{"access_token":"fresh-token","refresh_token":"refresh-2"}"#,
+                    )
+                } else if auth_header.as_deref() == Some("Bearer fresh-token") {
+                    (
+                        "200 OK",
+                        r#"{"output":[{"type":"message","content":[{"type":"output_text","text":"ok"}]}],"usage":{"input_tokens":1,"output_tokens":1}}"#,
+                    )
+                } else {
+                    ("401 Unauthorized", r#"{"error":{"message":"expired"}}"#)
+                };
+                let _ = request_tx.send((path, auth_header));
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.flush();
+            }
+        });
+
+        let mut config = Config::test_defaults();
+        config.llm_provider = "openai-codex".into();
+        config.api_key = String::new();
+        config.model = "gpt-5.3-codex".into();
+        let provider = OpenAiProvider::new(&config);
+        let messages = vec![Message {
+            role: "user".into(),
+            content: MessageContent::Text("hi".into()),
+        }];
+        let resp = provider.send_message("", messages, None).await;
+
+        let requests: Vec<(String, Option<String>)> = (0..3)
+            .filter_map(|_| request_rx.recv_timeout(Duration::from_secs(2)).ok())
+            .collect();
+        server.join().unwrap();
+        let persisted = std::fs::read_to_string(codex_home.join("auth.json")).unwrap();
+
+        if let Some(prev) = prev_access {
+            std::env::set_var("OPENAI_CODEX_ACCESS_TOKEN", prev);
+        }
+        if let Some(prev) = prev_codex_home {
+            std::env::set_var("CODEX_HOME", prev);
+        } else {
+            std::env::remove_var("CODEX_HOME");
+        }
+        if let Some(prev) = prev_refresh_url {
+            std::env::set_var("CODEX_REFRESH_TOKEN_URL_OVERRIDE", prev);
+        } else {
+            std::env::remove_var("CODEX_REFRESH_TOKEN_URL_OVERRIDE");
+        }
+        let _ = std::fs::remove_dir_all(&codex_home);
+
+        let resp = resp.unwrap();
+        match &resp.content[0] {
+            ResponseContentBlock::Text { text } => assert_eq!(text, "ok"),
+            _ => panic!("Expected text block"),
+        }
+        let paths: Vec<&str> = requests.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["/responses", "/oauth/token", "/responses"]);
+        assert_eq!(requests[0].1.as_deref(), Some("Bearer stale-token"));
+        let persisted: serde_json::Value = serde_json::from_str(&persisted).unwrap();
+        assert_eq!(persisted["tokens"]["access_token"], "fresh-token");
+        assert_eq!(persisted["tokens"]["refresh_token"], "refresh-2");
+        assert_eq!(persisted["tokens"]["account_id"], "acct");
+    }
+
     #[tokio::test]
     #[allow(clippy::await_holding_lock)]
     async fn test_openai_codex_stream_uses_auth_json_openai_api_key_when_oauth_missing() {
@@ -3092,6 +5169,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_openai_codex_response_payload_incomplete_is_max_tokens() {
+        let body = r#"{
+          "status":"incomplete",
+          "incomplete_details":{"reason":"max_output_tokens"},
+          "output":[{"type":"message","content":[{"type":"output_text","text":"Part"}]}]
+        }"#;
+        let parsed = parse_openai_codex_response_payload(body).unwrap();
+        let translated = translate_oai_responses_response(parsed);
+        assert_eq!(translated.finish_reason(), FinishReason::MaxTokens);
+    }
+
     #[test]
     fn test_parse_openai_codex_response_payload_sse_response_done() {
         let body = r#"event: response.created
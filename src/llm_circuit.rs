@@ -0,0 +1,484 @@
+//! Circuit breaker around the LLM provider.
+//!
+//! After `llm_circuit_breaker_threshold` consecutive outage-style failures the
+//! breaker opens and every call fails immediately for the cooldown, instead of
+//! each concurrent turn running its own retries against a provider that is
+//! down. After the cooldown one probe call is let through (half-open): success
+//! closes the breaker, failure reopens it.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{info, warn};
+
+use crate::config::{Config, SamplingParams};
+use crate::llm::LlmProvider;
+use microclaw_core::error::MicroClawError;
+use microclaw_core::llm_types::{Message, MessagesResponse, ToolDefinition};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half-open",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitSnapshot {
+    pub provider: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Times the breaker has opened since startup.
+    pub trips: u64,
+    /// Calls rejected without reaching the provider.
+    pub rejected: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_started: Option<Instant>,
+    trips: u64,
+    rejected: u64,
+    last_error: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    provider: String,
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(provider: &str, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            provider: provider.to_string(),
+            threshold,
+            cooldown,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_started: None,
+                trips: 0,
+                rejected: 0,
+                last_error: None,
+            }),
+        }
+    }
+
+    /// Whether a call may go to the provider now. `Err` carries the time left
+    /// until the next probe is allowed.
+    pub fn admit(&self) -> Result<(), Duration> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let elapsed = inner.opened_at.map_or(self.cooldown, |t| now - t);
+                if elapsed >= self.cooldown {
+                    info!(provider = %self.provider, "LLM circuit half-open; probing provider");
+                    inner.state = CircuitState::HalfOpen;
+                    inner.probe_started = Some(now);
+                    Ok(())
+                } else {
+                    inner.rejected += 1;
+                    Err(self.cooldown - elapsed)
+                }
+            }
+            // One probe at a time. A probe that never reported back (its turn was
+            // cancelled) is replaced after another cooldown.
+            CircuitState::HalfOpen => match inner.probe_started {
+                Some(started) if now - started < self.cooldown => {
+                    inner.rejected += 1;
+                    Err(self.cooldown - (now - started))
+                }
+                _ => {
+                    inner.probe_started = Some(now);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state != CircuitState::Closed {
+            info!(
+                provider = %self.provider,
+                "LLM circuit closed; provider recovered"
+            );
+        }
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_started = None;
+    }
+
+    pub fn record_failure(&self, error: &str) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        inner.last_error = Some(error.to_string());
+        let trip = match inner.state {
+            CircuitState::HalfOpen => true,
+            CircuitState::Closed => inner.consecutive_failures >= self.threshold,
+            CircuitState::Open => false,
+        };
+        if trip {
+            warn!(
+                provider = %self.provider,
+                consecutive_failures = inner.consecutive_failures,
+                cooldown_secs = self.cooldown.as_secs(),
+                "LLM circuit opened: {error}"
+            );
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+            inner.probe_started = None;
+            inner.trips += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> CircuitSnapshot {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        CircuitSnapshot {
+            provider: self.provider.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            trips: inner.trips,
+            rejected: inner.rejected,
+            last_error: inner.last_error.clone(),
+        }
+    }
+
+    async fn call<T>(
+        &self,
+        fut: impl Future<Output = Result<T, MicroClawError>>,
+    ) -> Result<T, MicroClawError> {
+        if let Err(retry_in) = self.admit() {
+            let snapshot = self.snapshot();
+            return Err(MicroClawError::LlmApi(format!(
+                "{} circuit breaker is open after {} consecutive failures; next attempt in {}s (last error: {})",
+                self.provider,
+                snapshot.consecutive_failures,
+                retry_in.as_secs().max(1),
+                snapshot.last_error.as_deref().unwrap_or("unknown")
+            )));
+        }
+        let result = fut.await;
+        match &result {
+            Err(e) if is_provider_outage(e) => self.record_failure(&e.to_string()),
+            // Any answer, including a rejected request, shows the provider is up.
+            _ => self.record_success(),
+        }
+        result
+    }
+}
+
+/// Errors that indicate the provider itself is unavailable, as opposed to a
+/// request it understood and refused.
+pub fn is_provider_outage(error: &MicroClawError) -> bool {
+    match error {
+        MicroClawError::Http(_) | MicroClawError::RateLimited => true,
+        MicroClawError::LlmApi(message) => {
+            let message = message.trim_start();
+            message.starts_with("HTTP 5")
+                || message.starts_with("HTTP 429")
+                || ["overloaded_error", "api_error", "rate_limit_error"]
+                    .iter()
+                    .any(|kind| message.starts_with(&format!("{kind}:")))
+        }
+        _ => false,
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Arc<CircuitBreaker>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<CircuitBreaker>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Current state of every registered breaker, sorted by provider.
+pub fn circuit_snapshots() -> Vec<CircuitSnapshot> {
+    let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut out: Vec<CircuitSnapshot> = registry.values().map(|b| b.snapshot()).collect();
+    out.sort_by(|a, b| a.provider.cmp(&b.provider));
+    out
+}
+
+/// One-line summary for `/status`.
+pub fn circuit_status_line() -> String {
+    let items: Vec<String> = circuit_snapshots()
+        .iter()
+        .filter(|s| s.state != CircuitState::Closed || s.trips > 0)
+        .map(|s| {
+            format!(
+                "{} {} ({} consecutive failures, trips={}, rejected={})",
+                s.provider, s.state, s.consecutive_failures, s.trips, s.rejected
+            )
+        })
+        .collect();
+    if items.is_empty() {
+        return "LLM circuit: closed".to_string();
+    }
+    format!("LLM circuit: {}", items.join("; "))
+}
+
+/// Wrap `inner` in a breaker for `config.llm_provider`, unless the breaker is
+/// disabled. The breaker is registered so `/status` can report it; clients for
+/// the same provider (main, sub-agents, role overrides) share it.
+pub fn wrap_provider(config: &Config, inner: Box<dyn LlmProvider>) -> Box<dyn LlmProvider> {
+    if config.llm_circuit_breaker_threshold == 0 {
+        return inner;
+    }
+    let provider = config.llm_provider.trim().to_lowercase();
+    let breaker = registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(provider.clone())
+        .or_insert_with(|| {
+            Arc::new(CircuitBreaker::new(
+                &provider,
+                config.llm_circuit_breaker_threshold,
+                Duration::from_secs(config.llm_circuit_breaker_cooldown_secs),
+            ))
+        })
+        .clone();
+    Box::new(CircuitBreakerProvider { inner, breaker })
+}
+
+pub struct CircuitBreakerProvider {
+    inner: Box<dyn LlmProvider>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+#[async_trait]
+impl LlmProvider for CircuitBreakerProvider {
+    async fn send_message(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.breaker
+            .call(self.inner.send_message(system, messages, tools))
+            .await
+    }
+
+    async fn send_message_with_model(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        model_override: Option<&str>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.breaker
+            .call(
+                self.inner
+                    .send_message_with_model(system, messages, tools, model_override),
+            )
+            .await
+    }
+
+    async fn send_message_with_sampling(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        overrides: &SamplingParams,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.breaker
+            .call(
+                self.inner
+                    .send_message_with_sampling(system, messages, tools, overrides),
+            )
+            .await
+    }
+
+    async fn send_message_stream(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        text_tx: Option<&UnboundedSender<String>>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.breaker
+            .call(
+                self.inner
+                    .send_message_stream(system, messages, tools, text_tx),
+            )
+            .await
+    }
+
+    async fn send_message_stream_with_model(
+        &self,
+        system: &str,
+        messages: Vec<Message>,
+        tools: Option<Vec<ToolDefinition>>,
+        text_tx: Option<&UnboundedSender<String>>,
+        model_override: Option<&str>,
+    ) -> Result<MessagesResponse, MicroClawError> {
+        self.breaker
+            .call(self.inner.send_message_stream_with_model(
+                system,
+                messages,
+                tools,
+                text_tx,
+                model_override,
+            ))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct FlakyProvider {
+        calls: Arc<AtomicUsize>,
+        fail: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for FlakyProvider {
+        async fn send_message(
+            &self,
+            _system: &str,
+            _messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(MicroClawError::LlmApi("HTTP 503: unavailable".into()));
+            }
+            Ok(MessagesResponse {
+                content: Vec::new(),
+                stop_reason: Some("end_turn".into()),
+                usage: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_is_provider_outage() {
+        assert!(is_provider_outage(&MicroClawError::RateLimited));
+        assert!(is_provider_outage(&MicroClawError::LlmApi(
+            "HTTP 502 Bad Gateway: upstream".into()
+        )));
+        assert!(is_provider_outage(&MicroClawError::LlmApi(
+            "overloaded_error: Overloaded".into()
+        )));
+        assert!(!is_provider_outage(&MicroClawError::LlmApi(
+            "invalid_request_error: prompt is too long".into()
+        )));
+        assert!(!is_provider_outage(&MicroClawError::LlmApi(
+            "HTTP 400 Bad Request: nope".into()
+        )));
+    }
+
+    #[test]
+    fn test_breaker_opens_half_opens_and_recovers() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::ZERO);
+        breaker.record_failure("HTTP 503");
+        assert_eq!(breaker.snapshot().state, CircuitState::Closed);
+        breaker.record_failure("HTTP 503");
+        assert_eq!(breaker.snapshot().state, CircuitState::Open);
+        assert_eq!(breaker.snapshot().trips, 1);
+
+        // Zero cooldown: the next call is the half-open probe.
+        assert!(breaker.admit().is_ok());
+        assert_eq!(breaker.snapshot().state, CircuitState::HalfOpen);
+        breaker.record_failure("HTTP 503");
+        assert_eq!(breaker.snapshot().state, CircuitState::Open);
+        assert_eq!(breaker.snapshot().trips, 2);
+
+        assert!(breaker.admit().is_ok());
+        breaker.record_success();
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_open_breaker_rejects_until_cooldown() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_secs(60));
+        assert!(breaker.admit().is_ok());
+        breaker.record_failure("HTTP 500");
+        let retry_in = breaker.admit().unwrap_err();
+        assert!(retry_in > Duration::from_secs(50));
+        assert_eq!(breaker.snapshot().rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_provider_fails_fast_while_open() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fail = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let mut config = Config::test_defaults();
+        config.llm_provider = "circuit-test".into();
+        config.llm_circuit_breaker_threshold = 2;
+        config.llm_circuit_breaker_cooldown_secs = 60;
+        let provider = wrap_provider(
+            &config,
+            Box::new(FlakyProvider {
+                calls: calls.clone(),
+                fail: fail.clone(),
+            }),
+        );
+
+        for _ in 0..2 {
+            assert!(provider.send_message("", Vec::new(), None).await.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        fail.store(false, Ordering::SeqCst);
+        let err = provider
+            .send_message("", Vec::new(), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("circuit breaker is open"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let snapshot = circuit_snapshots()
+            .into_iter()
+            .find(|s| s.provider == "circuit-test")
+            .unwrap();
+        assert_eq!(snapshot.state, CircuitState::Open);
+        assert_eq!(snapshot.rejected, 1);
+        assert!(circuit_status_line().contains("circuit-test open"));
+    }
+
+    #[test]
+    fn test_wrap_provider_disabled_by_zero_threshold() {
+        let mut config = Config::test_defaults();
+        config.llm_provider = "circuit-disabled".into();
+        config.llm_circuit_breaker_threshold = 0;
+        let _ = wrap_provider(
+            &config,
+            Box::new(FlakyProvider {
+                calls: Arc::new(AtomicUsize::new(0)),
+                fail: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            }),
+        );
+        assert!(circuit_snapshots()
+            .iter()
+            .all(|s| s.provider != "circuit-disabled"));
+    }
+}
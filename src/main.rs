@@ -2,9 +2,10 @@ use clap::{Args, CommandFactory, Parser, Subcommand};
 use microclaw::config::Config;
 use microclaw::error::MicroClawError;
 use microclaw::{
-    builtin_skills, db, doctor, gateway, hooks, logging, mcp, memory, runtime, setup, skills,
+    audit, builtin_skills, config_check, db_admin, doctor, gateway, hooks, logging, mcp, memory,
+    replay, runtime, scheduler, setup, skills, web_tokens,
 };
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use tracing::info;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -37,8 +38,13 @@ struct Cli {
 enum MainCommand {
     /// Start runtime (enabled channels)
     Start,
-    /// Full-screen setup wizard (or `setup --enable-sandbox`)
+    /// Full-screen setup wizard (or `setup --enable-sandbox` / `setup --non-interactive`)
     Setup(SetupCommand),
+    /// Validate a config file or print its JSON Schema (check/schema)
+    Config {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Preflight diagnostics
     Doctor {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
@@ -59,6 +65,31 @@ enum MainCommand {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Query the tool execution audit log
+    Audit {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Re-run a past message in dry-run mode (no sends, no memory writes)
+    Replay {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Pause or resume all scheduled tasks (pause/resume/status)
+    Scheduler {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Database maintenance (encrypt/backup)
+    Db {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Manage Web API tokens (token-create/token-list/token-revoke)
+    Web {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Re-embed active memories (requires `sqlite-vec` feature)
     Reembed,
     /// Show version
@@ -76,6 +107,15 @@ struct SetupCommand {
     /// Suppress follow-up tips
     #[arg(long)]
     quiet: bool,
+    /// Write the config without the TUI, from `--set` flags and MICROCLAW_SETUP_* env vars
+    #[arg(long)]
+    non_interactive: bool,
+    /// Field value for --non-interactive, e.g. `--set LLM_API_KEY=sk-...` (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", requires = "non_interactive")]
+    set: Vec<String>,
+    /// With --non-interactive, also check the API key and model with the provider
+    #[arg(long, requires = "non_interactive")]
+    validate_online: bool,
 }
 
 fn print_version() {
@@ -188,22 +228,6 @@ fn migrate_legacy_skills_dir(legacy_dir: &Path, preferred_dir: &Path) {
     }
 }
 
-fn collect_mcp_config_paths(data_root: &Path) -> Vec<PathBuf> {
-    let mut paths = vec![data_root.join("mcp.json")];
-    let mcp_dir = data_root.join("mcp.d");
-    let mut fragments = match std::fs::read_dir(&mcp_dir) {
-        Ok(entries) => entries
-            .flatten()
-            .map(|entry| entry.path())
-            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("json"))
-            .collect::<Vec<_>>(),
-        Err(_) => Vec::new(),
-    };
-    fragments.sort();
-    paths.extend(fragments);
-    paths
-}
-
 async fn reembed_memories() -> anyhow::Result<()> {
     let config = Config::load()?;
 
@@ -217,9 +241,10 @@ async fn reembed_memories() -> anyhow::Result<()> {
 
     #[cfg(feature = "sqlite-vec")]
     {
+        use microclaw::db::{MemoryBatchOp, MemoryEmbedding};
         use microclaw::embedding;
         let runtime_data_dir = config.runtime_data_dir();
-        let db = db::Database::new(&runtime_data_dir)?;
+        let db = config.open_database()?;
 
         let provider = embedding::create_provider(&config);
         let provider = match provider {
@@ -237,33 +262,42 @@ async fn reembed_memories() -> anyhow::Result<()> {
         let memories = db.get_all_active_memories()?;
         println!("Re-embedding {} active memories...", memories.len());
 
+        // Each progress chunk is written in one transaction.
+        const CHUNK: usize = 20;
         let mut success = 0usize;
         let mut failed = 0usize;
-        for (i, (id, content)) in memories.iter().enumerate() {
-            match provider.embed(content).await {
-                Ok(embedding) => {
-                    if let Err(e) = db.upsert_memory_vec(*id, &embedding) {
-                        eprintln!("  [{}] DB error: {}", id, e);
+        for (chunk_index, chunk) in memories.chunks(CHUNK).enumerate() {
+            let mut ops = Vec::with_capacity(chunk.len());
+            for (id, content) in chunk {
+                match provider.embed(content).await {
+                    Ok(vector) => ops.push(MemoryBatchOp::Embed {
+                        id: *id,
+                        embedding: MemoryEmbedding {
+                            vector,
+                            model: provider.model().to_string(),
+                        },
+                    }),
+                    Err(e) => {
+                        eprintln!("  [{}] Embed error: {}", id, e);
                         failed += 1;
-                    } else {
-                        let _ = db.update_memory_embedding_model(*id, provider.model());
-                        success += 1;
                     }
                 }
+            }
+            match db.write_memory_batch(&ops) {
+                Ok(ids) => success += ids.len(),
                 Err(e) => {
-                    eprintln!("  [{}] Embed error: {}", id, e);
-                    failed += 1;
+                    eprintln!("  DB error, chunk rolled back: {}", e);
+                    failed += ops.len();
                 }
             }
-            if (i + 1) % 20 == 0 {
-                println!(
-                    "  Progress: {}/{} (ok={}, fail={})",
-                    i + 1,
-                    memories.len(),
-                    success,
-                    failed
-                );
-            }
+            let done = (chunk_index * CHUNK + chunk.len()).min(memories.len());
+            println!(
+                "  Progress: {}/{} (ok={}, fail={})",
+                done,
+                memories.len(),
+                success,
+                failed
+            );
         }
 
         println!("Done! {} embedded, {} failed", success, failed);
@@ -295,6 +329,19 @@ async fn main() -> anyhow::Result<()> {
                         "Tip: run `microclaw doctor sandbox` to verify docker runtime and image readiness."
                     );
                 }
+            } else if setup_args.non_interactive {
+                let values = setup::non_interactive_values(std::env::vars(), &setup_args.set)?;
+                let summary = setup::run_non_interactive_setup(
+                    Path::new("microclaw.config.yaml"),
+                    &values,
+                    setup_args.validate_online,
+                )?;
+                if !setup_args.quiet {
+                    for line in summary {
+                        println!("{line}");
+                    }
+                }
+                println!("Setup saved to microclaw.config.yaml");
             } else {
                 let saved = setup::run_setup_wizard()?;
                 if saved {
@@ -305,6 +352,10 @@ async fn main() -> anyhow::Result<()> {
             }
             return Ok(());
         }
+        Some(MainCommand::Config { args }) => {
+            config_check::handle_config_cli(&args)?;
+            return Ok(());
+        }
         Some(MainCommand::Doctor { args }) => {
             doctor::run_cli(&args)?;
             return Ok(());
@@ -318,6 +369,26 @@ async fn main() -> anyhow::Result<()> {
             hooks::handle_hooks_cli(&args).await?;
             return Ok(());
         }
+        Some(MainCommand::Audit { args }) => {
+            audit::handle_audit_cli(&args).await?;
+            return Ok(());
+        }
+        Some(MainCommand::Replay { args }) => {
+            replay::handle_replay_cli(&args).await?;
+            return Ok(());
+        }
+        Some(MainCommand::Scheduler { args }) => {
+            scheduler::handle_scheduler_cli(&args).await?;
+            return Ok(());
+        }
+        Some(MainCommand::Db { args }) => {
+            db_admin::handle_db_cli(&args).await?;
+            return Ok(());
+        }
+        Some(MainCommand::Web { args }) => {
+            web_tokens::handle_web_cli(&args).await?;
+            return Ok(());
+        }
         Some(MainCommand::Reembed) => {
             return reembed_memories().await;
         }
@@ -356,7 +427,7 @@ async fn main() -> anyhow::Result<()> {
     let legacy_skills_dir = data_root_dir.join("skills");
     migrate_legacy_runtime_layout(&data_root_dir, Path::new(&runtime_data_dir));
     migrate_legacy_skills_dir(&legacy_skills_dir, Path::new(&skills_data_dir));
-    builtin_skills::ensure_builtin_skills(Path::new(&skills_data_dir))?;
+    builtin_skills::ensure_builtin_skills(Path::new(&skills_data_dir), env!("CARGO_PKG_VERSION"))?;
 
     if std::env::var("MICROCLAW_GATEWAY").is_ok() {
         logging::init_logging(
@@ -368,7 +439,7 @@ async fn main() -> anyhow::Result<()> {
         logging::init_console_logging();
     }
 
-    let db = db::Database::new(&runtime_data_dir)?;
+    let db = config.open_database()?;
     info!("Database initialized");
 
     let memory_manager = memory::MemoryManager::new(&runtime_data_dir);
@@ -382,10 +453,8 @@ async fn main() -> anyhow::Result<()> {
     );
 
     // Initialize MCP servers (optional, configured via <data_root>/mcp.json and <data_root>/mcp.d/*.json)
-    let mcp_config_paths = collect_mcp_config_paths(&data_root_dir);
     let mcp_manager =
-        mcp::McpManager::from_config_paths(&mcp_config_paths, config.mcp_request_timeout_secs())
-            .await;
+        mcp::McpManager::from_data_root(&data_root_dir, config.mcp_request_timeout_secs()).await;
     let mcp_tool_count: usize = mcp_manager.all_tools().len();
     if mcp_tool_count > 0 {
         info!("MCP initialized: {} tools available", mcp_tool_count);
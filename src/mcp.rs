@@ -13,6 +13,7 @@ const DEFAULT_PROTOCOL_VERSION: &str = "2025-11-05";
 const DEFAULT_MAX_RETRIES: u32 = 2;
 const DEFAULT_HEALTH_INTERVAL_SECS: u64 = 60;
 const TOOLS_CACHE_TTL_SECS: u64 = 300;
+const MAX_TOOLS_LIST_PAGES: usize = 50;
 const DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD: u32 = 5;
 const DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS: u64 = 30;
 const DEFAULT_MAX_CONCURRENT_REQUESTS: u32 = 4;
@@ -47,6 +48,80 @@ struct JsonRpcError {
     message: String,
 }
 
+/// One `notifications/progress` update for an in-flight request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct McpProgress {
+    pub progress: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl McpProgress {
+    /// Short status line, e.g. "scanning rows (40%)" or "progress 3".
+    pub fn summary(&self) -> String {
+        let amount = match self.total.filter(|t| *t > 0.0) {
+            Some(total) => format!("{:.0}%", (self.progress / total * 100.0).clamp(0.0, 100.0)),
+            None => format!("{}", self.progress),
+        };
+        match self
+            .message
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+        {
+            Some(message) => format!("{message} ({amount})"),
+            None => format!("progress {amount}"),
+        }
+    }
+}
+
+/// Receives the progress notifications tagged with `token`.
+struct ProgressSink {
+    token: String,
+    tx: tokio::sync::mpsc::UnboundedSender<McpProgress>,
+}
+
+impl ProgressSink {
+    /// Forward `line` if it is a progress notification for this request.
+    fn forward(&self, line: &str) -> bool {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            return false;
+        };
+        match parse_progress_notification(&value, &self.token) {
+            Some(update) => {
+                let _ = self.tx.send(update);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn parse_progress_notification(value: &serde_json::Value, token: &str) -> Option<McpProgress> {
+    if value.get("method").and_then(|v| v.as_str()) != Some("notifications/progress") {
+        return None;
+    }
+    let params = value.get("params")?;
+    let matches_token = match params.get("progressToken")? {
+        serde_json::Value::String(s) => s == token,
+        serde_json::Value::Number(n) => n.to_string() == token,
+        _ => false,
+    };
+    if !matches_token {
+        return None;
+    }
+    Some(McpProgress {
+        progress: params.get("progress").and_then(|v| v.as_f64())?,
+        total: params.get("total").and_then(|v| v.as_f64()),
+        message: params
+            .get("message")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+    })
+}
+
 // --- MCP config types ---
 
 fn default_transport() -> String {
@@ -62,7 +137,7 @@ fn resolve_request_timeout_secs(
         .max(1)
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct McpServerConfig {
     #[serde(default = "default_transport")]
     pub transport: String,
@@ -100,6 +175,34 @@ pub struct McpServerConfig {
     pub headers: HashMap<String, String>,
 }
 
+impl McpServerConfig {
+    /// Static checks that do not need the server running: a known transport and a
+    /// well-formed endpoint URL. Empty `command`/`endpoint` are reported separately.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.transport.trim().to_ascii_lowercase().as_str() {
+            "stdio" | "" => Ok(()),
+            "streamable_http" | "http" => {
+                let endpoint = self.endpoint.trim();
+                if endpoint.is_empty() {
+                    return Ok(());
+                }
+                let url = reqwest::Url::parse(endpoint)
+                    .map_err(|e| format!("invalid endpoint '{endpoint}': {e}"))?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return Err(format!(
+                        "endpoint '{endpoint}' must use http or https, got '{}'",
+                        url.scheme()
+                    ));
+                }
+                Ok(())
+            }
+            other => Err(format!(
+                "unknown transport '{other}' (expected stdio or streamable_http)"
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct McpConfig {
     #[serde(default, alias = "defaultProtocolVersion")]
@@ -301,6 +404,8 @@ fn spawn_stdio_inner(spec: &McpStdioSpawnSpec, server_name: &str) -> Result<McpS
     cmd.stdin(std::process::Stdio::piped());
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::null());
+    // A server dropped by a reload must not outlive its last handle.
+    cmd.kill_on_drop(true);
 
     let mut child = cmd
         .spawn()
@@ -446,6 +551,10 @@ impl McpServer {
         *ts = Some(Instant::now());
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn tools_snapshot(&self) -> Vec<McpToolInfo> {
         self.tools_cache
             .lock()
@@ -515,6 +624,7 @@ impl McpServer {
         &self,
         method: &str,
         params: Option<serde_json::Value>,
+        progress: Option<&ProgressSink>,
     ) -> Result<serde_json::Value, String> {
         let inner = match &self.transport {
             McpTransport::Stdio(inner) => inner,
@@ -572,6 +682,9 @@ impl McpServer {
             if trimmed.is_empty() {
                 continue;
             }
+            if progress.is_some_and(|sink| sink.forward(trimmed)) {
+                continue;
+            }
 
             if let Ok(response) = serde_json::from_str::<JsonRpcResponse>(trimmed) {
                 let is_response = match &response.id {
@@ -634,7 +747,7 @@ impl McpServer {
         });
 
         let result = self
-            .send_request_stdio_once("initialize", Some(params))
+            .send_request_stdio_once("initialize", Some(params), None)
             .await?;
         let negotiated = result
             .get("protocolVersion")
@@ -659,11 +772,15 @@ impl McpServer {
         &self,
         method: &str,
         params: Option<serde_json::Value>,
+        progress: Option<&ProgressSink>,
     ) -> Result<serde_json::Value, String> {
         let mut last_err: Option<String> = None;
 
         for attempt in 0..=self.max_retries {
-            match self.send_request_stdio_once(method, params.clone()).await {
+            match self
+                .send_request_stdio_once(method, params.clone(), progress)
+                .await
+            {
                 Ok(result) => return Ok(result),
                 Err(err) => {
                     last_err = Some(err.clone());
@@ -753,6 +870,17 @@ impl McpServer {
         &self,
         method: &str,
         params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, String> {
+        self.send_request_with_progress(method, params, None).await
+    }
+
+    /// `send_request`, forwarding progress notifications to `progress`. Only the
+    /// stdio transport delivers them; HTTP responses arrive in one piece.
+    async fn send_request_with_progress(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        progress: Option<&ProgressSink>,
     ) -> Result<serde_json::Value, String> {
         {
             let mut rate = self.rate_limiter.lock().unwrap_or_else(|e| e.into_inner());
@@ -792,7 +920,10 @@ impl McpServer {
         }
 
         let result = match &self.transport {
-            McpTransport::Stdio(_) => self.send_request_stdio_with_retries(method, params).await,
+            McpTransport::Stdio(_) => {
+                self.send_request_stdio_with_retries(method, params, progress)
+                    .await
+            }
             McpTransport::StreamableHttp(_) => self.send_request_http(method, params).await,
         };
         drop(permit);
@@ -891,38 +1022,36 @@ impl McpServer {
     }
 
     async fn list_tools_uncached(&self) -> Result<Vec<McpToolInfo>, String> {
-        let result = self
-            .send_request("tools/list", Some(serde_json::json!({})))
-            .await?;
-
-        let tools_value = result.get("tools").ok_or("No tools in response")?;
-        let tools_array = tools_value.as_array().ok_or("tools is not an array")?;
-
         let mut tools = Vec::new();
-        for tool in tools_array {
-            let name = tool
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let description = tool
-                .get("description")
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_TOOLS_LIST_PAGES {
+            let params = match &cursor {
+                Some(c) => serde_json::json!({ "cursor": c }),
+                None => serde_json::json!({}),
+            };
+            let result = self.send_request("tools/list", Some(params)).await?;
+
+            let tools_value = result.get("tools").ok_or("No tools in response")?;
+            let tools_array = tools_value.as_array().ok_or("tools is not an array")?;
+            tools.extend(
+                tools_array
+                    .iter()
+                    .filter_map(|tool| mcp_tool_info_from_value(&self.name, tool)),
+            );
+
+            cursor = result
+                .get("nextCursor")
                 .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            let input_schema = tool
-                .get("inputSchema")
-                .cloned()
-                .unwrap_or_else(|| serde_json::json!({"type": "object", "properties": {}}));
-
-            tools.push(McpToolInfo {
-                server_name: self.name.clone(),
-                name,
-                description,
-                input_schema,
-            });
+                .filter(|c| !c.is_empty())
+                .map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
         }
-
+        warn!(
+            "MCP server '{}' returned more than {} tools/list pages; truncating",
+            self.name, MAX_TOOLS_LIST_PAGES
+        );
         Ok(tools)
     }
 
@@ -946,11 +1075,17 @@ impl McpServer {
             return;
         }
 
+        // Hold only a weak handle so servers removed by a reload can be dropped.
+        let server = Arc::downgrade(&self);
+        drop(self);
         tokio::spawn(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(interval_secs)).await;
-                if let Err(e) = self.health_probe().await {
-                    warn!("MCP health probe failed for '{}': {}", self.name, e);
+                let Some(server) = server.upgrade() else {
+                    return;
+                };
+                if let Err(e) = server.health_probe().await {
+                    warn!("MCP health probe failed for '{}': {}", server.name, e);
                 }
             }
         });
@@ -960,6 +1095,32 @@ impl McpServer {
         &self,
         tool_name: &str,
         arguments: serde_json::Value,
+    ) -> Result<String, String> {
+        self.call_tool_inner(tool_name, arguments, None).await
+    }
+
+    /// `call_tool` that asks the server for progress notifications and sends
+    /// each one to `progress`. The sender is dropped when the call finishes.
+    /// Servers without progress support just return the result.
+    pub async fn call_tool_with_progress(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        progress: tokio::sync::mpsc::UnboundedSender<McpProgress>,
+    ) -> Result<String, String> {
+        let sink = ProgressSink {
+            token: format!("microclaw-{}", uuid::Uuid::new_v4().simple()),
+            tx: progress,
+        };
+        self.call_tool_inner(tool_name, arguments, Some(&sink))
+            .await
+    }
+
+    async fn call_tool_inner(
+        &self,
+        tool_name: &str,
+        arguments: serde_json::Value,
+        progress: Option<&ProgressSink>,
     ) -> Result<String, String> {
         let snapshot = self.tools_snapshot();
         if !snapshot.iter().any(|t| t.name == tool_name) {
@@ -968,12 +1129,18 @@ impl McpServer {
             let _ = self.refresh_tools_cache(false).await;
         }
 
-        let params = serde_json::json!({
+        let mut params = serde_json::json!({
             "name": tool_name,
             "arguments": arguments
         });
+        if let Some(sink) = progress {
+            params["_meta"] = serde_json::json!({ "progressToken": sink.token });
+        }
 
-        let result = match self.send_request("tools/call", Some(params)).await {
+        let result = match self
+            .send_request_with_progress("tools/call", Some(params), progress)
+            .await
+        {
             Ok(result) => result,
             Err(err) => {
                 if Self::is_tool_not_found_error(&err) {
@@ -1026,8 +1193,64 @@ impl McpServer {
 
 // --- MCP manager ---
 
+/// MCP config sources under the data root: `mcp.json` followed by `mcp.d/*.json`
+/// in name order (later files override servers of the same name).
+pub fn collect_mcp_config_paths(data_root: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![data_root.join("mcp.json")];
+    let mcp_dir = data_root.join("mcp.d");
+    let mut fragments = match std::fs::read_dir(&mcp_dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("json"))
+            .collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+    fragments.sort();
+    paths.extend(fragments);
+    paths
+}
+
+/// Outcome of [`McpManager::reload`].
+#[derive(Debug, Default)]
+pub struct McpReloadReport {
+    /// New or changed servers that connected.
+    pub connected: Vec<String>,
+    /// Servers no longer present in config.
+    pub removed: Vec<String>,
+    /// Servers that failed to (re)connect.
+    pub failed: Vec<String>,
+    pub unchanged: usize,
+}
+
+impl McpReloadReport {
+    pub fn summary(&self) -> String {
+        let list = |names: &[String]| {
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
+        };
+        format!(
+            "connected: {}; removed: {}; failed: {}; unchanged: {}",
+            list(&self.connected),
+            list(&self.removed),
+            list(&self.failed),
+            self.unchanged
+        )
+    }
+}
+
+#[derive(Default)]
 pub struct McpManager {
     servers: Vec<Arc<McpServer>>,
+    /// When set, config paths are re-collected from here on reload.
+    data_root: Option<PathBuf>,
+    config_paths: Vec<PathBuf>,
+    default_request_timeout_secs: u64,
+    default_protocol_version: Option<String>,
+    server_configs: HashMap<String, McpServerConfig>,
 }
 
 impl McpManager {
@@ -1035,58 +1258,128 @@ impl McpManager {
         Self::from_config_paths(&[PathBuf::from(path)], default_request_timeout_secs).await
     }
 
+    /// Load `mcp.json` and `mcp.d/*.json` under `data_root`; [`Self::reload`] re-scans it.
+    pub async fn from_data_root(data_root: &Path, default_request_timeout_secs: u64) -> Self {
+        let mut manager = Self::from_config_paths(
+            &collect_mcp_config_paths(data_root),
+            default_request_timeout_secs,
+        )
+        .await;
+        manager.data_root = Some(data_root.to_path_buf());
+        manager
+    }
+
     pub async fn from_config_paths(paths: &[PathBuf], default_request_timeout_secs: u64) -> Self {
-        let default_request_timeout_secs =
-            resolve_request_timeout_secs(None, default_request_timeout_secs);
-        let (loaded_any_config, merged_default_protocol_version, merged_servers) =
-            merge_config_sources(paths);
-        if !loaded_any_config {
-            // Config file not found is normal — MCP is optional
-            return McpManager {
-                servers: Vec::new(),
-            };
+        let mut manager = McpManager {
+            config_paths: paths.to_vec(),
+            default_request_timeout_secs: resolve_request_timeout_secs(
+                None,
+                default_request_timeout_secs,
+            ),
+            ..McpManager::default()
+        };
+        manager.reload().await;
+        manager
+    }
+
+    /// Re-read the config sources, connecting new or changed servers and dropping
+    /// removed ones. Unchanged servers keep their connection; tool calls already
+    /// running on a replaced server finish on the old handle.
+    pub async fn reload(&mut self) -> McpReloadReport {
+        if let Some(root) = &self.data_root {
+            self.config_paths = collect_mcp_config_paths(root);
         }
+        let (_, default_protocol_version, merged_servers) =
+            merge_config_sources(&self.config_paths);
+        let protocol_changed = default_protocol_version != self.default_protocol_version;
 
+        let mut report = McpReloadReport::default();
         let mut servers = Vec::new();
-        let mut entries: Vec<(String, McpServerConfig)> = merged_servers.into_iter().collect();
-        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for server in std::mem::take(&mut self.servers) {
+            let name = server.name().to_string();
+            match merged_servers.get(&name) {
+                Some(cfg) if !protocol_changed && self.server_configs.get(&name) == Some(cfg) => {
+                    report.unchanged += 1;
+                    servers.push(server);
+                }
+                Some(_) => info!("MCP server '{name}' config changed; reconnecting"),
+                None => {
+                    info!("MCP server '{name}' removed from config");
+                    report.removed.push(name);
+                }
+            }
+        }
+
+        let mut entries: Vec<(&String, &McpServerConfig)> = merged_servers
+            .iter()
+            .filter(|(name, _)| !servers.iter().any(|s| s.name() == name.as_str()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
         for (name, server_config) in entries {
-            info!("Connecting to MCP server '{name}'...");
-            match tokio::time::timeout(
-                Duration::from_secs(30),
-                McpServer::connect(
-                    &name,
-                    &server_config,
-                    merged_default_protocol_version.as_deref(),
-                    default_request_timeout_secs,
-                ),
+            match Self::connect_server(
+                name,
+                server_config,
+                default_protocol_version.as_deref(),
+                self.default_request_timeout_secs,
             )
             .await
             {
-                Ok(Ok(server)) => {
-                    let server = Arc::new(server);
-                    let interval = server_config
-                        .health_interval_secs
-                        .unwrap_or(DEFAULT_HEALTH_INTERVAL_SECS);
-                    server.clone().start_health_probe(interval);
-
-                    info!(
-                        "MCP server '{name}' connected ({} tools, protocol {})",
-                        server.tools_snapshot().len(),
-                        server.protocol_version()
-                    );
+                Some(server) => {
+                    report.connected.push(name.clone());
                     servers.push(server);
                 }
-                Ok(Err(e)) => {
-                    warn!("Failed to connect MCP server '{name}': {e}");
-                }
-                Err(_) => {
-                    warn!("MCP server '{name}' connection timed out (30s)");
-                }
+                None => report.failed.push(name.clone()),
             }
         }
+        servers.sort_by(|a, b| a.name().cmp(b.name()));
+
+        self.servers = servers;
+        self.default_protocol_version = default_protocol_version;
+        self.server_configs = merged_servers;
+        report
+    }
+
+    async fn connect_server(
+        name: &str,
+        server_config: &McpServerConfig,
+        default_protocol_version: Option<&str>,
+        default_request_timeout_secs: u64,
+    ) -> Option<Arc<McpServer>> {
+        info!("Connecting to MCP server '{name}'...");
+        match tokio::time::timeout(
+            Duration::from_secs(30),
+            McpServer::connect(
+                name,
+                server_config,
+                default_protocol_version,
+                default_request_timeout_secs,
+            ),
+        )
+        .await
+        {
+            Ok(Ok(server)) => {
+                let server = Arc::new(server);
+                let interval = server_config
+                    .health_interval_secs
+                    .unwrap_or(DEFAULT_HEALTH_INTERVAL_SECS);
+                server.clone().start_health_probe(interval);
 
-        McpManager { servers }
+                info!(
+                    "MCP server '{name}' connected ({} tools, protocol {})",
+                    server.tools_snapshot().len(),
+                    server.protocol_version()
+                );
+                Some(server)
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to connect MCP server '{name}': {e}");
+                None
+            }
+            Err(_) => {
+                warn!("MCP server '{name}' connection timed out (30s)");
+                None
+            }
+        }
     }
 
     #[allow(dead_code)]
@@ -1105,6 +1398,39 @@ impl McpManager {
     }
 }
 
+/// Map one `tools/list` entry to [`McpToolInfo`], keeping the advertised
+/// description and JSON Schema as-is (enums, nested objects, `$defs`, etc.).
+/// Only a missing or non-object schema is replaced, and a schema without a
+/// top-level `type` gets `"type": "object"`, which providers require.
+fn mcp_tool_info_from_value(server_name: &str, tool: &serde_json::Value) -> Option<McpToolInfo> {
+    let name = tool.get("name").and_then(|v| v.as_str())?.to_string();
+    if name.is_empty() {
+        return None;
+    }
+    let description = tool
+        .get("description")
+        .and_then(|v| v.as_str())
+        .or_else(|| tool.get("title").and_then(|v| v.as_str()))
+        .unwrap_or("")
+        .to_string();
+    let input_schema = match tool.get("inputSchema") {
+        Some(serde_json::Value::Object(schema)) => {
+            let mut schema = schema.clone();
+            schema
+                .entry("type")
+                .or_insert_with(|| serde_json::json!("object"));
+            serde_json::Value::Object(schema)
+        }
+        _ => serde_json::json!({"type": "object", "properties": {}}),
+    };
+    Some(McpToolInfo {
+        server_name: server_name.to_string(),
+        name,
+        description,
+        input_schema,
+    })
+}
+
 fn merge_config_sources(
     paths: &[PathBuf],
 ) -> (bool, Option<String>, HashMap<String, McpServerConfig>) {
@@ -1208,6 +1534,35 @@ mod tests {
         assert_eq!(remote.health_interval_secs, Some(15));
     }
 
+    #[test]
+    fn test_server_config_validate() {
+        let cfg = |value: serde_json::Value| -> McpServerConfig {
+            serde_json::from_value(value).unwrap()
+        };
+        assert!(cfg(serde_json::json!({"command": "npx"}))
+            .validate()
+            .is_ok());
+        assert!(cfg(
+            serde_json::json!({"transport": "http", "url": "https://mcp.example.com/mcp"})
+        )
+        .validate()
+        .is_ok());
+        let err = cfg(serde_json::json!({"transport": "sse", "command": "npx"}))
+            .validate()
+            .unwrap_err();
+        assert!(err.contains("unknown transport 'sse'"), "{err}");
+        let err =
+            cfg(serde_json::json!({"transport": "streamable_http", "endpoint": "ftp://host/mcp"}))
+                .validate()
+                .unwrap_err();
+        assert!(err.contains("http or https"), "{err}");
+        assert!(
+            cfg(serde_json::json!({"transport": "http", "endpoint": "not a url"}))
+                .validate()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_resolve_request_timeout_secs_prefers_server_override() {
         assert_eq!(resolve_request_timeout_secs(Some(25), 90), 25);
@@ -1333,4 +1688,274 @@ mod tests {
         assert!(!breaker.record_failure(Instant::now()));
         assert!(breaker.check_ready(Instant::now()).is_ok());
     }
+
+    fn rich_tool_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                "location": {
+                    "type": "object",
+                    "properties": {
+                        "city": {"type": "string", "minLength": 1},
+                        "coords": {
+                            "type": "array",
+                            "items": {"type": "number"},
+                            "minItems": 2,
+                            "maxItems": 2
+                        }
+                    },
+                    "required": ["city"],
+                    "additionalProperties": false
+                },
+                "days": {"type": "integer", "minimum": 1, "maximum": 14, "default": 3}
+            },
+            "required": ["location"],
+            "$defs": {"unused": {"type": "boolean"}}
+        })
+    }
+
+    /// Minimal streamable-HTTP MCP server: one JSON-RPC request per connection.
+    async fn spawn_mock_http_mcp_server() -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let body = loop {
+                    let n = stream.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        break None;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    let Some(header_end) = text.find("\r\n\r\n") else {
+                        continue;
+                    };
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|l| {
+                            let (k, v) = l.split_once(':')?;
+                            k.eq_ignore_ascii_case("content-length")
+                                .then(|| v.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if buf.len() >= header_end + 4 + content_length {
+                        break Some(buf[header_end + 4..header_end + 4 + content_length].to_vec());
+                    }
+                };
+                let Some(body) = body else { continue };
+                let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                let id = request
+                    .get("id")
+                    .cloned()
+                    .unwrap_or(serde_json::Value::Null);
+                let result = match request["method"].as_str().unwrap_or("") {
+                    "initialize" => serde_json::json!({"protocolVersion": "2025-11-05"}),
+                    "tools/list" if request["params"]["cursor"] == "page2" => serde_json::json!({
+                        "tools": [{"name": "no_schema", "title": "Titled tool"}]
+                    }),
+                    "tools/list" => serde_json::json!({
+                        "tools": [
+                            {
+                                "name": "forecast",
+                                "description": "Get a forecast.\n\nUse `unit` to pick the scale.",
+                                "inputSchema": rich_tool_schema()
+                            },
+                            {"description": "nameless tool is dropped"}
+                        ],
+                        "nextCursor": "page2"
+                    }),
+                    _ => serde_json::json!({}),
+                };
+                let payload =
+                    serde_json::json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    payload.len(),
+                    payload
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        format!("http://{addr}/mcp")
+    }
+
+    #[tokio::test]
+    async fn test_all_tools_preserves_schema_and_description_verbatim() {
+        let endpoint = spawn_mock_http_mcp_server().await;
+        let config: McpServerConfig = serde_json::from_value(serde_json::json!({
+            "transport": "streamable_http",
+            "endpoint": endpoint
+        }))
+        .unwrap();
+        let server = McpServer::connect("weather", &config, None, 10)
+            .await
+            .unwrap();
+        let manager = McpManager {
+            servers: vec![Arc::new(server)],
+            ..McpManager::default()
+        };
+
+        let tools = manager.all_tools();
+        assert_eq!(tools.len(), 2);
+        let (_, forecast) = &tools[0];
+        assert_eq!(forecast.name, "forecast");
+        assert_eq!(
+            forecast.description,
+            "Get a forecast.\n\nUse `unit` to pick the scale."
+        );
+        assert_eq!(forecast.input_schema, rich_tool_schema());
+
+        let (_, paged) = &tools[1];
+        assert_eq!(paged.name, "no_schema");
+        assert_eq!(paged.description, "Titled tool");
+        assert_eq!(
+            paged.input_schema,
+            serde_json::json!({"type": "object", "properties": {}})
+        );
+
+        let (server, info) = tools[0].clone();
+        let def = crate::tools::Tool::definition(&crate::tools::mcp::McpTool::new(server, info));
+        assert_eq!(def.input_schema, rich_tool_schema());
+        assert!(def
+            .description
+            .ends_with("Get a forecast.\n\nUse `unit` to pick the scale."));
+    }
+
+    #[tokio::test]
+    async fn test_reload_connects_new_and_drops_removed_servers() {
+        let endpoint = spawn_mock_http_mcp_server().await;
+        let root =
+            std::env::temp_dir().join(format!("microclaw_mcp_reload_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("mcp.d")).unwrap();
+        let server_json = |name: &str| {
+            serde_json::json!({
+                "mcpServers": {name: {"transport": "streamable_http", "endpoint": endpoint}}
+            })
+            .to_string()
+        };
+        std::fs::write(root.join("mcp.json"), server_json("weather")).unwrap();
+
+        let mut manager = McpManager::from_data_root(&root, 10).await;
+        assert_eq!(manager.servers().len(), 1);
+        let weather = manager.servers()[0].clone();
+
+        std::fs::write(root.join("mcp.d").join("extra.json"), server_json("extra")).unwrap();
+        let report = manager.reload().await;
+        assert_eq!(report.connected, vec!["extra".to_string()]);
+        assert_eq!(report.unchanged, 1);
+        assert!(report.removed.is_empty() && report.failed.is_empty());
+        let names: Vec<&str> = manager.servers().iter().map(|s| s.name()).collect();
+        assert_eq!(names, vec!["extra", "weather"]);
+        assert!(Arc::ptr_eq(&manager.servers()[1], &weather));
+
+        std::fs::remove_file(root.join("mcp.json")).unwrap();
+        let report = manager.reload().await;
+        assert_eq!(report.removed, vec!["weather".to_string()]);
+        assert_eq!(manager.servers().len(), 1);
+        assert!(manager.all_tools().iter().all(|(s, _)| s.name() == "extra"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_progress_notification_matches_token() {
+        let note = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {"progressToken": "tok", "progress": 30, "total": 120, "message": "scanning"}
+        });
+        let update = parse_progress_notification(&note, "tok").unwrap();
+        assert_eq!(update.total, Some(120.0));
+        assert_eq!(update.summary(), "scanning (25%)");
+        assert!(parse_progress_notification(&note, "other").is_none());
+
+        let numeric = serde_json::json!({
+            "method": "notifications/progress",
+            "params": {"progressToken": 7, "progress": 3}
+        });
+        assert_eq!(
+            parse_progress_notification(&numeric, "7")
+                .unwrap()
+                .summary(),
+            "progress 3"
+        );
+        let response = serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {}});
+        assert!(parse_progress_notification(&response, "tok").is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_tool_call_relays_progress_and_keeps_trail() {
+        let script = r#"while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2025-11-05"}}\n' "$id" ;;
+    *'"method":"tools/list"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"slow"}]}}\n' "$id" ;;
+    *'"method":"tools/call"'*)
+      token=$(printf '%s' "$line" | sed -n 's/.*"progressToken":"\([^"]*\)".*/\1/p')
+      printf '{"jsonrpc":"2.0","method":"notifications/progress","params":{"progressToken":"%s","progress":1,"total":2,"message":"halfway"}}\n' "$token"
+      printf '{"jsonrpc":"2.0","method":"notifications/progress","params":{"progressToken":"other","progress":9}}\n'
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"done"}]}}\n' "$id" ;;
+  esac
+done"#;
+        let config: McpServerConfig = serde_json::from_value(serde_json::json!({
+            "command": "sh",
+            "args": ["-c", script]
+        }))
+        .unwrap();
+        let server = Arc::new(
+            McpServer::connect("slowdb", &config, None, 10)
+                .await
+                .unwrap(),
+        );
+        let info = server.tools_snapshot().into_iter().next().unwrap();
+        let tool = crate::tools::mcp::McpTool::new(server.clone(), info);
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = crate::tools::with_tool_progress(
+            tx,
+            crate::tools::Tool::execute(&tool, serde_json::json!({})),
+        )
+        .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(result.content, "done");
+        assert_eq!(rx.recv().await.as_deref(), Some("halfway (50%)"));
+        assert!(rx.recv().await.is_none());
+        assert_eq!(
+            result.metadata.unwrap()["progress"],
+            serde_json::json!([{"progress": 1.0, "total": 2.0, "message": "halfway"}])
+        );
+
+        // Plain calls never ask for progress and behave as before.
+        assert_eq!(
+            server
+                .call_tool("slow", serde_json::json!({}))
+                .await
+                .unwrap(),
+            "done"
+        );
+    }
+
+    #[test]
+    fn test_mcp_tool_info_adds_missing_type_only() {
+        let tool = serde_json::json!({
+            "name": "t",
+            "inputSchema": {"properties": {"mode": {"enum": ["a", "b"]}}}
+        });
+        let info = mcp_tool_info_from_value("srv", &tool).unwrap();
+        assert_eq!(
+            info.input_schema,
+            serde_json::json!({"type": "object", "properties": {"mode": {"enum": ["a", "b"]}}})
+        );
+    }
 }
@@ -3,9 +3,14 @@ use std::sync::Arc;
 
 use tracing::{info, warn};
 
+use crate::embedding::EmbeddingProvider;
 use crate::mcp::{McpManager, McpServer, McpToolInfo};
+use crate::vector_store::VectorStore;
 use microclaw_core::error::MicroClawError;
-use microclaw_storage::db::{call_blocking, Database, Memory};
+#[cfg(feature = "sqlite-vec")]
+use microclaw_storage::db::MemoryEmbedding;
+use microclaw_storage::db::{call_blocking, Database, Memory, MemoryBatchOp};
+use microclaw_storage::memory_quality;
 
 #[derive(Clone)]
 pub struct MemoryMcpClient {
@@ -57,24 +62,182 @@ impl MemoryMcpClient {
     }
 }
 
+/// Result of a deduplicating memory write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryWriteOutcome {
+    Stored(i64),
+    /// Folded into an existing near-duplicate, which now holds the new content.
+    Merged(i64),
+    /// An existing near-duplicate already covers the content.
+    Skipped(i64),
+}
+
+impl MemoryWriteOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stored(_) => "stored",
+            Self::Merged(_) => "merged",
+            Self::Skipped(_) => "skipped",
+        }
+    }
+
+    pub fn memory_id(&self) -> i64 {
+        match self {
+            Self::Stored(id) | Self::Merged(id) | Self::Skipped(id) => *id,
+        }
+    }
+}
+
+/// Whether `incoming` should replace a near-duplicate `existing` memory rather than be dropped.
+pub(crate) fn should_merge_duplicate(
+    existing: &Memory,
+    incoming_content: &str,
+    incoming_category: &str,
+) -> bool {
+    if existing.is_archived {
+        return true;
+    }
+    if existing.content.eq_ignore_ascii_case(incoming_content) {
+        return false;
+    }
+    if incoming_category == "PROFILE" && existing.category != "PROFILE" {
+        return true;
+    }
+    incoming_content.len() > existing.content.len() + 8
+}
+
+/// An external vector store with the embedding provider that feeds it.
+type VectorIndex<'a> = (&'a Arc<dyn VectorStore>, &'a Arc<dyn EmbeddingProvider>);
+
 pub struct MemoryBackend {
     db: Arc<Database>,
     mcp: Option<MemoryMcpClient>,
+    embedding: Option<Arc<dyn EmbeddingProvider>>,
+    dedup_similarity_threshold: f64,
+    vector_store: Option<Arc<dyn VectorStore>>,
 }
 
 impl MemoryBackend {
     pub fn new(db: Arc<Database>, mcp: Option<MemoryMcpClient>) -> Self {
-        Self { db, mcp }
+        Self {
+            db,
+            mcp,
+            embedding: None,
+            dedup_similarity_threshold: 1.0,
+            vector_store: None,
+        }
     }
 
     pub fn local_only(db: Arc<Database>) -> Self {
-        Self { db, mcp: None }
+        Self::new(db, None)
+    }
+
+    /// Enable semantic dedup on [`Self::insert_memory_deduped`] using cosine similarity.
+    pub fn with_semantic_dedup(
+        mut self,
+        embedding: Option<Arc<dyn EmbeddingProvider>>,
+        similarity_threshold: f64,
+    ) -> Self {
+        self.embedding = embedding;
+        self.dedup_similarity_threshold = similarity_threshold;
+        self
+    }
+
+    /// Index embeddings in an external vector store instead of sqlite-vec. Uses the
+    /// embedding provider from [`Self::with_semantic_dedup`]; memory rows stay in SQLite.
+    pub fn with_vector_store(mut self, store: Option<Arc<dyn VectorStore>>) -> Self {
+        self.vector_store = store;
+        self
     }
 
     pub fn prefers_mcp(&self) -> bool {
         self.mcp.is_some()
     }
 
+    fn vector_index(&self) -> Option<VectorIndex<'_>> {
+        if self.mcp.is_some() {
+            return None;
+        }
+        Some((self.vector_store.as_ref()?, self.embedding.as_ref()?))
+    }
+
+    /// Nearest memory ids (with similarity) from the external vector store, or None
+    /// when none is configured or the query fails.
+    pub async fn vector_search(
+        &self,
+        chat_id: Option<i64>,
+        query: &str,
+        limit: usize,
+    ) -> Option<Vec<(i64, f64)>> {
+        let (store, provider) = self.vector_index()?;
+        if query.trim().is_empty() {
+            return None;
+        }
+        let vector = match provider.embed(query).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    "memory embedding failed; skipping {} search: {e}",
+                    store.name()
+                );
+                return None;
+            }
+        };
+        match store.search(chat_id, &vector, limit).await {
+            Ok(hits) => Some(hits),
+            Err(e) => {
+                warn!("{} memory search failed: {e}", store.name());
+                None
+            }
+        }
+    }
+
+    /// Mirror memory `id` into the external vector store: re-embed it when active,
+    /// drop its vector when archived or gone. Failures are logged, not returned,
+    /// since SQLite stays the source of truth.
+    async fn sync_vector(&self, id: i64) {
+        let Some((store, provider)) = self.vector_index() else {
+            return;
+        };
+        let memory = match call_blocking(self.db.clone(), move |db| db.get_memory_by_id(id)).await {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("failed to load memory #{id} for vector sync: {e}");
+                return;
+            }
+        };
+        let result = match memory {
+            Some(m) if !m.is_archived => match provider.embed(&m.content).await {
+                Ok(vector) => store.upsert(id, m.chat_id, &vector).await,
+                Err(e) => Err(format!("embedding failed: {e}")),
+            },
+            _ => store.delete(id).await,
+        };
+        if let Err(e) = result {
+            warn!("failed to sync memory #{id} to {}: {e}", store.name());
+        }
+    }
+
+    /// Active memories for `ids` in the given order, limited to `chat_id` and global scope.
+    async fn load_scoped_memories(
+        &self,
+        chat_id: Option<i64>,
+        ids: Vec<i64>,
+    ) -> Result<Vec<Memory>, MicroClawError> {
+        call_blocking(self.db.clone(), move |db| {
+            let mut out = Vec::with_capacity(ids.len());
+            for id in ids {
+                if let Some(m) = db.get_memory_by_id(id)? {
+                    if !m.is_archived && (m.chat_id.is_none() || m.chat_id == chat_id) {
+                        out.push(m);
+                    }
+                }
+            }
+            Ok(out)
+        })
+        .await
+    }
+
     pub async fn get_all_memories_for_chat(
         &self,
         chat_id: Option<i64>,
@@ -153,6 +316,16 @@ impl MemoryBackend {
             );
         }
 
+        if !include_archived {
+            if let Some(hits) = self.vector_search(Some(chat_id), query, limit).await {
+                let ids = hits.into_iter().map(|(id, _)| id).collect();
+                let memories = self.load_scoped_memories(Some(chat_id), ids).await?;
+                if !memories.is_empty() {
+                    return Ok(memories);
+                }
+            }
+        }
+
         let q = query.to_string();
         call_blocking(self.db.clone(), move |db| {
             db.search_memories_with_options(chat_id, &q, limit, include_archived, broad_recall)
@@ -210,10 +383,12 @@ impl MemoryBackend {
         let text = content.to_string();
         let cat = category.to_string();
         let src = source.to_string();
-        call_blocking(self.db.clone(), move |db| {
+        let id = call_blocking(self.db.clone(), move |db| {
             db.insert_memory_with_metadata(chat_id, &text, &cat, &src, confidence)
         })
-        .await
+        .await?;
+        self.sync_vector(id).await;
+        Ok(id)
     }
 
     pub async fn update_memory_with_metadata(
@@ -246,10 +421,14 @@ impl MemoryBackend {
         let text = content.to_string();
         let cat = category.to_string();
         let src = source.to_string();
-        call_blocking(self.db.clone(), move |db| {
+        let updated = call_blocking(self.db.clone(), move |db| {
             db.update_memory_with_metadata(id, &text, &cat, confidence, &src)
         })
-        .await
+        .await?;
+        if updated {
+            self.sync_vector(id).await;
+        }
+        Ok(updated)
     }
 
     pub async fn update_memory_content(
@@ -278,7 +457,11 @@ impl MemoryBackend {
             );
         }
 
-        call_blocking(self.db.clone(), move |db| db.archive_memory(id)).await
+        let archived = call_blocking(self.db.clone(), move |db| db.archive_memory(id)).await?;
+        if archived {
+            self.sync_vector(id).await;
+        }
+        Ok(archived)
     }
 
     pub async fn supersede_memory(
@@ -312,7 +495,7 @@ impl MemoryBackend {
         let cat = category.to_string();
         let src = source.to_string();
         let why = reason.map(|v| v.to_string());
-        call_blocking(self.db.clone(), move |db| {
+        let new_id = call_blocking(self.db.clone(), move |db| {
             db.supersede_memory(
                 from_memory_id,
                 &text,
@@ -322,7 +505,303 @@ impl MemoryBackend {
                 why.as_deref(),
             )
         })
-        .await
+        .await?;
+        self.sync_vector(from_memory_id).await;
+        self.sync_vector(new_id).await;
+        Ok(new_id)
+    }
+
+    /// Nearest memory in exactly this scope (same `chat_id`, same `sender_id` owner) at or
+    /// above the similarity threshold, so a chat or user write never merges into a global
+    /// memory or another member's. Errors when semantic search is unavailable, so callers
+    /// can fall back to lexical matching.
+    pub async fn find_semantic_duplicate(
+        &self,
+        chat_id: Option<i64>,
+        sender_id: Option<&str>,
+        content: &str,
+    ) -> Result<Option<(Memory, f64)>, MicroClawError> {
+        let same_scope = |m: &Memory| m.chat_id == chat_id && m.sender_id.as_deref() == sender_id;
+        if let Some((store, provider)) = self.vector_index() {
+            let query_vec = provider
+                .embed(content)
+                .await
+                .map_err(|e| MicroClawError::Config(format!("embedding failed: {e}")))?;
+            let nearest = store
+                .search(chat_id, &query_vec, 5)
+                .await
+                .map_err(MicroClawError::Config)?;
+            let ids = nearest
+                .into_iter()
+                .take_while(|(_, similarity)| *similarity >= self.dedup_similarity_threshold)
+                .collect::<Vec<_>>();
+            let similarity: HashMap<i64, f64> = ids.iter().copied().collect();
+            let memories = self
+                .load_scoped_memories(chat_id, ids.into_iter().map(|(id, _)| id).collect())
+                .await?;
+            return Ok(memories.into_iter().find(same_scope).map(|m| {
+                let score = similarity.get(&m.id).copied().unwrap_or_default();
+                (m, score)
+            }));
+        }
+        #[cfg(feature = "sqlite-vec")]
+        {
+            let Some(provider) = &self.embedding else {
+                return Err(MicroClawError::Config(
+                    "semantic dedup requires an embedding provider".into(),
+                ));
+            };
+            let query_vec = provider
+                .embed(content)
+                .await
+                .map_err(|e| MicroClawError::Config(format!("embedding failed: {e}")))?;
+            // The KNN query always includes global rows; scope is narrowed below.
+            let nearest = call_blocking(self.db.clone(), move |db| {
+                db.knn_memories(chat_id.unwrap_or_default(), &query_vec, 5)
+            })
+            .await?;
+            for (id, distance) in nearest {
+                let similarity = 1.0 - f64::from(distance);
+                if similarity < self.dedup_similarity_threshold {
+                    break;
+                }
+                let Some(memory) = self.get_memory_by_id(id).await? else {
+                    continue;
+                };
+                if same_scope(&memory) {
+                    return Ok(Some((memory, similarity)));
+                }
+            }
+            Ok(None)
+        }
+        #[cfg(not(feature = "sqlite-vec"))]
+        {
+            let _ = (
+                same_scope,
+                content,
+                &self.embedding,
+                self.dedup_similarity_threshold,
+            );
+            Err(MicroClawError::Config(
+                "semantic dedup requires the sqlite-vec feature".into(),
+            ))
+        }
+    }
+
+    /// Insert a memory unless a near-duplicate exists, in which case the duplicate is
+    /// updated (merged) or left as-is (skipped); either way its confidence is reinforced
+    /// and it counts as seen now. Near-duplicates are found semantically when embeddings
+    /// are available and by exact content match otherwise.
+    pub async fn insert_memory_deduped(
+        &self,
+        chat_id: Option<i64>,
+        content: &str,
+        category: &str,
+        source: &str,
+        confidence: f64,
+    ) -> Result<MemoryWriteOutcome, MicroClawError> {
+        let duplicate = match self.find_semantic_duplicate(chat_id, None, content).await {
+            Ok(found) => found,
+            Err(_) => self
+                .find_exact_duplicate(chat_id, content)
+                .await
+                .map(|m| (m, 1.0)),
+        };
+        if let Some((existing, similarity)) = duplicate {
+            let reinforced = memory_quality::reinforced_confidence(existing.confidence);
+            if should_merge_duplicate(&existing, content, category) {
+                info!(
+                    "Memory write merged into #{} (similarity {:.3})",
+                    existing.id, similarity
+                );
+                self.write_batch(vec![MemoryBatchOp::Update {
+                    id: existing.id,
+                    content: content.to_string(),
+                    category: category.to_string(),
+                    confidence: confidence.max(reinforced),
+                    source: source.to_string(),
+                    embedding: None,
+                }])
+                .await?;
+                return Ok(MemoryWriteOutcome::Merged(existing.id));
+            }
+            info!(
+                "Memory write skipped as near-duplicate of #{} (similarity {:.3}); confidence {:.2} -> {:.2}",
+                existing.id, similarity, existing.confidence, reinforced
+            );
+            let _ = self
+                .touch_memory_last_seen(existing.id, Some(reinforced))
+                .await;
+            return Ok(MemoryWriteOutcome::Skipped(existing.id));
+        }
+        let ids = self
+            .write_batch(vec![MemoryBatchOp::Insert {
+                chat_id,
+                content: content.to_string(),
+                category: category.to_string(),
+                source: source.to_string(),
+                confidence,
+                sender_id: None,
+                embedding: None,
+            }])
+            .await?;
+        Ok(MemoryWriteOutcome::Stored(ids[0]))
+    }
+
+    /// Unowned memory in exactly this scope whose content matches ignoring case and
+    /// whitespace. Archived matches count, so rewriting a fact revives it.
+    async fn find_exact_duplicate(&self, chat_id: Option<i64>, content: &str) -> Option<Memory> {
+        let key = |text: &str| {
+            text.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        let wanted = key(content);
+        self.get_all_memories_for_chat(chat_id)
+            .await
+            .ok()?
+            .into_iter()
+            .find(|m| m.chat_id == chat_id && m.sender_id.is_none() && key(&m.content) == wanted)
+    }
+
+    /// Apply `ops` in a single SQLite transaction, so either all of them land or
+    /// none do, and return the affected memory id per op. Content-writing ops
+    /// without an embedding are embedded first (outside the transaction) when
+    /// sqlite-vec is in use; an external vector store is synced after commit.
+    /// The MCP backend has no shared transaction, so there ops run one by one.
+    pub async fn write_batch(
+        &self,
+        mut ops: Vec<MemoryBatchOp>,
+    ) -> Result<Vec<i64>, MicroClawError> {
+        if ops.is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.mcp.is_some() {
+            return self.write_batch_sequential(ops).await;
+        }
+        self.embed_batch(&mut ops).await;
+        let superseded: Vec<i64> = ops
+            .iter()
+            .filter_map(|op| match op {
+                MemoryBatchOp::Supersede { from_memory_id, .. } => Some(*from_memory_id),
+                _ => None,
+            })
+            .collect();
+        let resync: Vec<bool> = ops.iter().map(|op| op.content().is_some()).collect();
+        let ids = call_blocking(self.db.clone(), move |db| db.write_memory_batch(&ops)).await?;
+        if self.vector_index().is_some() {
+            for id in superseded {
+                self.sync_vector(id).await;
+            }
+            for (id, _) in ids.iter().zip(resync).filter(|(_, resync)| *resync) {
+                self.sync_vector(*id).await;
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn write_batch_sequential(
+        &self,
+        ops: Vec<MemoryBatchOp>,
+    ) -> Result<Vec<i64>, MicroClawError> {
+        let mut ids = Vec::with_capacity(ops.len());
+        for op in ops {
+            let id = match op {
+                MemoryBatchOp::Insert {
+                    chat_id,
+                    content,
+                    category,
+                    source,
+                    confidence,
+                    sender_id,
+                    ..
+                } => {
+                    let id = self
+                        .insert_memory_with_metadata(
+                            chat_id, &content, &category, &source, confidence,
+                        )
+                        .await?;
+                    if sender_id.is_some() {
+                        self.set_memory_sender_id(id, sender_id.as_deref()).await?;
+                    }
+                    id
+                }
+                MemoryBatchOp::Update {
+                    id,
+                    content,
+                    category,
+                    confidence,
+                    source,
+                    ..
+                } => {
+                    self.update_memory_with_metadata(id, &content, &category, confidence, &source)
+                        .await?;
+                    id
+                }
+                MemoryBatchOp::Supersede {
+                    from_memory_id,
+                    content,
+                    category,
+                    source,
+                    confidence,
+                    reason,
+                    ..
+                } => {
+                    self.supersede_memory(
+                        from_memory_id,
+                        &content,
+                        &category,
+                        &source,
+                        confidence,
+                        reason.as_deref(),
+                    )
+                    .await?
+                }
+                MemoryBatchOp::Touch {
+                    id,
+                    confidence_floor,
+                } => {
+                    self.touch_memory_last_seen(id, confidence_floor).await?;
+                    id
+                }
+                // Vectors are local-only; the MCP server indexes its own memories.
+                MemoryBatchOp::Embed { id, .. } => id,
+            };
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Fill in missing sqlite-vec embeddings. Failures leave the slot empty so the
+    /// reflector's backfill picks the memory up later.
+    async fn embed_batch(&self, ops: &mut [MemoryBatchOp]) {
+        if self.vector_index().is_some() {
+            // Mirrored to the external store after commit instead.
+            return;
+        }
+        #[cfg(feature = "sqlite-vec")]
+        {
+            let Some(provider) = &self.embedding else {
+                return;
+            };
+            for op in ops.iter_mut() {
+                let Some(content) = op.content().map(str::to_string) else {
+                    continue;
+                };
+                let Some(slot) = op.embedding_mut().filter(|slot| slot.is_none()) else {
+                    continue;
+                };
+                if let Ok(vector) = provider.embed(&content).await {
+                    *slot = Some(MemoryEmbedding {
+                        vector,
+                        model: provider.model().to_string(),
+                    });
+                }
+            }
+        }
+        #[cfg(not(feature = "sqlite-vec"))]
+        let _ = ops;
     }
 
     pub async fn touch_memory_last_seen(
@@ -351,6 +830,30 @@ impl MemoryBackend {
         })
         .await
     }
+
+    /// Attribute a memory to one group member. Sender ownership is local-only
+    /// metadata, so this always writes to sqlite.
+    pub async fn set_memory_sender_id(
+        &self,
+        id: i64,
+        sender_id: Option<&str>,
+    ) -> Result<bool, MicroClawError> {
+        let sender_id = sender_id.map(str::to_string);
+        call_blocking(self.db.clone(), move |db| {
+            db.set_memory_sender_id(id, sender_id.as_deref())
+        })
+        .await
+    }
+
+    /// Pin or unpin a memory. Like sender ownership, pins are local metadata
+    /// and always live in sqlite.
+    pub async fn set_memory_pinned(&self, id: i64, pinned: bool) -> Result<bool, MicroClawError> {
+        call_blocking(self.db.clone(), move |db| db.set_memory_pinned(id, pinned)).await
+    }
+
+    pub async fn get_pinned_memories(&self, chat_id: i64) -> Result<Vec<Memory>, MicroClawError> {
+        call_blocking(self.db.clone(), move |db| db.get_pinned_memories(chat_id)).await
+    }
 }
 
 fn parse_json_loose(text: &str) -> Result<serde_json::Value, String> {
@@ -436,6 +939,11 @@ fn parse_single_memory(value: &serde_json::Value) -> Option<Memory> {
             .get("archived_at")
             .and_then(|v| v.as_str())
             .map(|v| v.to_string()),
+        sender_id: obj
+            .get("sender_id")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+        pinned: obj.get("pinned").and_then(|v| v.as_bool()).unwrap_or(false),
     })
 }
 
@@ -464,3 +972,268 @@ fn extract_bool_flag(value: &serde_json::Value) -> Option<bool> {
 fn _extract_tool_info(tools: &[McpToolInfo]) -> Vec<String> {
     tools.iter().map(|t| t.name.clone()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Two-topic embedding: coffee words on one axis, cat words on the other.
+    struct TopicEmbedding;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedding {
+        async fn embed(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+            let t = text.to_lowercase();
+            let coffee = ["coffee", "espresso", "latte"]
+                .iter()
+                .any(|w| t.contains(w));
+            let cat = ["cat", "kitten"].iter().any(|w| t.contains(w));
+            Ok(vec![coffee as u8 as f32, cat as u8 as f32])
+        }
+        fn model(&self) -> &str {
+            "topic"
+        }
+        fn dimension(&self) -> usize {
+            2
+        }
+    }
+
+    /// memory id -> (chat_id, vector)
+    type Points = HashMap<i64, (Option<i64>, Vec<f32>)>;
+
+    #[derive(Default)]
+    struct FakeStore {
+        points: Mutex<Points>,
+    }
+
+    #[async_trait]
+    impl VectorStore for FakeStore {
+        fn name(&self) -> &str {
+            "fake"
+        }
+        async fn upsert(
+            &self,
+            memory_id: i64,
+            chat_id: Option<i64>,
+            vector: &[f32],
+        ) -> Result<(), String> {
+            self.points
+                .lock()
+                .unwrap()
+                .insert(memory_id, (chat_id, vector.to_vec()));
+            Ok(())
+        }
+        async fn search(
+            &self,
+            chat_id: Option<i64>,
+            vector: &[f32],
+            limit: usize,
+        ) -> Result<Vec<(i64, f64)>, String> {
+            let points = self.points.lock().unwrap();
+            let mut hits: Vec<(i64, f64)> = points
+                .iter()
+                .filter(|(_, (c, _))| c.is_none() || (chat_id.is_some() && *c == chat_id))
+                .map(|(id, (_, v))| {
+                    let dot: f32 = v.iter().zip(vector).map(|(a, b)| a * b).sum();
+                    (*id, f64::from(dot))
+                })
+                .filter(|(_, score)| *score > 0.0)
+                .collect();
+            hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+            hits.truncate(limit);
+            Ok(hits)
+        }
+        async fn delete(&self, memory_id: i64) -> Result<(), String> {
+            self.points.lock().unwrap().remove(&memory_id);
+            Ok(())
+        }
+    }
+
+    fn backend() -> (MemoryBackend, Arc<FakeStore>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("mc_vecstore_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let store = Arc::new(FakeStore::default());
+        let backend = MemoryBackend::local_only(db)
+            .with_semantic_dedup(Some(Arc::new(TopicEmbedding)), 0.99)
+            .with_vector_store(Some(store.clone()));
+        (backend, store, dir)
+    }
+
+    #[tokio::test]
+    async fn test_vector_store_receives_writes_and_serves_search() {
+        let (backend, store, dir) = backend();
+        let coffee = backend
+            .insert_memory_with_metadata(Some(1), "Prefers espresso", "PROFILE", "tool", 0.9)
+            .await
+            .unwrap();
+        let cat = backend
+            .insert_memory_with_metadata(None, "Has a kitten named Miso", "PROFILE", "tool", 0.9)
+            .await
+            .unwrap();
+        let other_chat = backend
+            .insert_memory_with_metadata(Some(2), "Drinks a latte daily", "PROFILE", "tool", 0.9)
+            .await
+            .unwrap();
+        assert_eq!(store.points.lock().unwrap().len(), 3);
+        assert_eq!(store.points.lock().unwrap()[&cat].0, None);
+
+        // No lexical overlap with "coffee"; only the vector store can find it.
+        let found = backend
+            .search_memories_with_options(1, "coffee order", 5, false, true)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = found.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![coffee], "chat 2's memory must not leak");
+
+        backend.archive_memory(coffee).await.unwrap();
+        assert!(!store.points.lock().unwrap().contains_key(&coffee));
+
+        let (dup, _) = backend
+            .find_semantic_duplicate(Some(2), None, "Loves latte art")
+            .await
+            .unwrap()
+            .expect("near duplicate via vector store");
+        assert_eq!(dup.id, other_chat);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_only_merges_within_the_same_scope() {
+        let (backend, _store, dir) = backend();
+        let global = backend
+            .insert_memory_with_metadata(None, "Has a kitten named Miso", "PROFILE", "tool", 0.9)
+            .await
+            .unwrap();
+        let alice = backend
+            .write_batch(vec![MemoryBatchOp::Insert {
+                chat_id: Some(1),
+                content: "Alice has a cat".into(),
+                category: "PROFILE".into(),
+                source: "tool".into(),
+                confidence: 0.9,
+                sender_id: Some("alice".into()),
+                embedding: None,
+            }])
+            .await
+            .unwrap()[0];
+
+        assert!(backend
+            .find_semantic_duplicate(Some(1), None, "The kitten is called Miso")
+            .await
+            .unwrap()
+            .is_none());
+        let (dup, _) = backend
+            .find_semantic_duplicate(Some(1), Some("alice"), "Alice's cat is Miso")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(dup.id, alice);
+
+        let outcome = backend
+            .insert_memory_deduped(Some(1), "The kitten is called Miso", "PROFILE", "tool", 0.8)
+            .await
+            .unwrap();
+        let MemoryWriteOutcome::Stored(id) = outcome else {
+            panic!("chat write merged across scopes: {outcome:?}");
+        };
+        assert_ne!(id, global);
+        let kept = backend.get_memory_by_id(global).await.unwrap().unwrap();
+        assert_eq!(kept.content, "Has a kitten named Miso");
+
+        let again = backend
+            .insert_memory_deduped(Some(1), "Kitten: Miso", "PROFILE", "tool", 0.8)
+            .await
+            .unwrap();
+        assert!(!matches!(again, MemoryWriteOutcome::Stored(_)), "{again:?}");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_write_batch_syncs_store_only_after_commit() {
+        let (backend, store, dir) = backend();
+        let old = backend
+            .insert_memory_with_metadata(Some(1), "Prefers espresso", "PROFILE", "tool", 0.9)
+            .await
+            .unwrap();
+        let insert = |content: &str| MemoryBatchOp::Insert {
+            chat_id: Some(1),
+            content: content.into(),
+            category: "PROFILE".into(),
+            source: "reflector".into(),
+            confidence: 0.68,
+            sender_id: Some("ou_alice".into()),
+            embedding: None,
+        };
+
+        let err = backend
+            .write_batch(vec![
+                insert("Has a kitten named Miso"),
+                MemoryBatchOp::Touch {
+                    id: 777,
+                    confidence_floor: None,
+                },
+            ])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("memory #777 not found"));
+        assert_eq!(store.points.lock().unwrap().len(), 1);
+        assert_eq!(
+            backend
+                .get_all_memories_for_chat(Some(1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let ids = backend
+            .write_batch(vec![
+                insert("Has a kitten named Miso"),
+                MemoryBatchOp::Supersede {
+                    from_memory_id: old,
+                    content: "Switched to latte".into(),
+                    category: "PROFILE".into(),
+                    source: "reflector_conflict".into(),
+                    confidence: 0.74,
+                    reason: None,
+                    embedding: None,
+                },
+            ])
+            .await
+            .unwrap();
+        {
+            let points = store.points.lock().unwrap();
+            assert!(points.contains_key(&ids[0]) && points.contains_key(&ids[1]));
+            assert!(
+                !points.contains_key(&old),
+                "superseded memory leaves the store"
+            );
+        }
+        let kitten = backend.get_memory_by_id(ids[0]).await.unwrap().unwrap();
+        assert_eq!(kitten.sender_id.as_deref(), Some("ou_alice"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_mcp_or_missing_store_falls_back_to_sqlite() {
+        let dir = std::env::temp_dir().join(format!("mc_vecstore_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        let backend =
+            MemoryBackend::local_only(db).with_semantic_dedup(Some(Arc::new(TopicEmbedding)), 0.9);
+        backend
+            .insert_memory_with_metadata(Some(1), "Prefers espresso", "PROFILE", "tool", 0.9)
+            .await
+            .unwrap();
+        assert!(backend.vector_search(Some(1), "coffee", 5).await.is_none());
+        let found = backend
+            .search_memories_with_options(1, "espresso", 5, false, true)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::warn;
 
-use crate::config::{Config, WorkingDirIsolation};
+use crate::config::{Config, WorkingDirIsolationPolicy, WorkingDirRoots};
 use crate::tools::{auth_context_from_input, schema_object, Tool, ToolResult};
 
 fn default_plugin_enabled() -> bool {
@@ -52,12 +52,27 @@ impl PluginExecutionPolicy {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct PluginsConfig {
     #[serde(default = "default_plugin_enabled")]
     pub enabled: bool,
     #[serde(default)]
     pub dir: Option<String>,
+    /// Named secrets that plugin manifests can reference from `env` as `${secret:name}`.
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+}
+
+impl std::fmt::Debug for PluginsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut secret_names: Vec<&String> = self.secrets.keys().collect();
+        secret_names.sort();
+        f.debug_struct("PluginsConfig")
+            .field("enabled", &self.enabled)
+            .field("dir", &self.dir)
+            .field("secrets", &secret_names)
+            .finish()
+    }
 }
 
 impl Default for PluginsConfig {
@@ -65,6 +80,7 @@ impl Default for PluginsConfig {
         Self {
             enabled: true,
             dir: None,
+            secrets: HashMap::new(),
         }
     }
 }
@@ -80,6 +96,11 @@ pub struct PluginManifest {
     pub tools: Vec<PluginToolSpec>,
     #[serde(default)]
     pub context_providers: Vec<PluginContextProviderSpec>,
+    /// Environment variables set on every process this plugin runs. Values may
+    /// reference `plugins.secrets` as `${secret:name}`; they never appear in the
+    /// command line.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -242,6 +263,9 @@ pub fn load_plugin_report(config: &Config) -> PluginLoadReport {
                     continue;
                 }
                 validate_manifest(&manifest, &path, &mut report.errors);
+                if let Err(e) = resolve_plugin_env(config, &manifest.name, &manifest.env) {
+                    report.errors.push(format!("{}: {e}", path.display()));
+                }
                 if manifest.enabled {
                     manifests.push(manifest);
                 }
@@ -486,20 +510,21 @@ pub async fn execute_dynamic_plugin_tool(
     tool_name: &str,
     input: serde_json::Value,
 ) -> Option<ToolResult> {
-    let mut matched: Option<(String, PluginToolSpec)> = None;
+    let mut matched: Option<(String, PluginToolSpec, PluginEnvResult)> = None;
     for manifest in load_plugin_manifests(config) {
+        let envs = resolve_plugin_env(config, &manifest.name, &manifest.env);
         if let Some(spec) = manifest
             .tools
             .into_iter()
             .find(|t| t.name.eq_ignore_ascii_case(tool_name))
         {
-            matched = Some((manifest.name, spec));
+            matched = Some((manifest.name, spec, envs));
             break;
         }
     }
-    let (plugin_name, spec) = matched?;
+    let (plugin_name, spec, envs) = matched?;
 
-    Some(execute_plugin_tool_spec(config, &plugin_name, &spec, input).await)
+    Some(execute_plugin_tool_spec(config, &plugin_name, &spec, envs, input).await)
 }
 
 pub fn handle_plugins_admin_command(
@@ -628,6 +653,12 @@ pub async fn execute_plugin_slash_command(
             }
 
             if let Some(run) = &command.run {
+                let envs = match resolve_plugin_env(config, &manifest.name, &manifest.env) {
+                    Ok(envs) => envs,
+                    Err(e) => {
+                        return Some(format!("Plugin command environment error: {e}"));
+                    }
+                };
                 match execute_with_template(
                     config,
                     caller_channel,
@@ -637,6 +668,7 @@ pub async fn execute_plugin_slash_command(
                     run.execution_policy
                         .unwrap_or(PluginExecutionPolicy::HostOnly),
                     &vars,
+                    envs,
                 )
                 .await
                 {
@@ -705,6 +737,18 @@ pub async fn collect_plugin_context_injections(
                     exit_code: 0,
                 })
             } else if let Some(run) = &provider.run {
+                let envs = match resolve_plugin_env(config, &manifest.name, &manifest.env) {
+                    Ok(envs) => envs,
+                    Err(e) => {
+                        warn!(
+                            plugin = manifest.name.as_str(),
+                            provider = provider.name.as_str(),
+                            error = %e,
+                            "plugin context provider environment error"
+                        );
+                        continue;
+                    }
+                };
                 execute_with_template(
                     config,
                     caller_channel,
@@ -717,6 +761,7 @@ pub async fn collect_plugin_context_injections(
                         .or(run.execution_policy)
                         .unwrap_or(PluginExecutionPolicy::HostOnly),
                     &vars,
+                    envs,
                 )
                 .await
             } else {
@@ -829,6 +874,69 @@ fn template_keys(template: &str) -> Vec<String> {
     out
 }
 
+const SECRET_REF_PREFIX: &str = "${secret:";
+
+type PluginEnvResult = Result<HashMap<String, String>, String>;
+
+/// Resolve a manifest's `env` block, substituting `${secret:name}` references
+/// from `plugins.secrets`. Errors name the missing secret, never a value.
+pub fn resolve_plugin_env(
+    config: &Config,
+    plugin_name: &str,
+    env: &HashMap<String, String>,
+) -> PluginEnvResult {
+    let mut out = HashMap::new();
+    for (key, raw) in env {
+        let key = key.trim();
+        if key.is_empty()
+            || key.starts_with(|c: char| c.is_ascii_digit())
+            || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(format!(
+                "plugin '{}' has invalid env variable name '{key}'",
+                plugin_name
+            ));
+        }
+        let mut value = String::new();
+        let mut rest = raw.as_str();
+        while let Some(start) = rest.find(SECRET_REF_PREFIX) {
+            value.push_str(&rest[..start]);
+            let after = &rest[start + SECRET_REF_PREFIX.len()..];
+            let end = after.find('}').ok_or_else(|| {
+                format!(
+                    "plugin '{}' env '{key}' has an unterminated secret reference",
+                    plugin_name
+                )
+            })?;
+            let name = after[..end].trim();
+            let secret = config.plugins.secrets.get(name).ok_or_else(|| {
+                format!(
+                    "plugin '{}' env '{key}' references unknown secret '{name}'",
+                    plugin_name
+                )
+            })?;
+            value.push_str(secret);
+            rest = &after[end + 1..];
+        }
+        value.push_str(rest);
+        out.insert(key.to_string(), value);
+    }
+    Ok(out)
+}
+
+fn redact_plugin_secrets(config: &Config, text: &str) -> String {
+    let mut out = text.to_string();
+    for secret in config.plugins.secrets.values() {
+        if !secret.is_empty() && out.contains(secret.as_str()) {
+            out = out.replace(
+                secret.as_str(),
+                microclaw_tools::redaction::REDACTED_PLACEHOLDER,
+            );
+        }
+    }
+    out
+}
+
 fn render_template_checked(
     template: &str,
     vars: &HashMap<String, String>,
@@ -854,14 +962,15 @@ fn shell_escape_single(value: &str) -> String {
 
 fn make_tool_working_dir(
     base_working_dir: &Path,
-    isolation: WorkingDirIsolation,
+    isolation: WorkingDirIsolationPolicy,
+    control_chat_ids: &[i64],
     caller_channel: &str,
     caller_chat_id: i64,
 ) -> PathBuf {
     let mut auth = serde_json::Map::new();
     auth.insert("caller_channel".to_string(), json!(caller_channel));
     auth.insert("caller_chat_id".to_string(), json!(caller_chat_id));
-    auth.insert("control_chat_ids".to_string(), json!([]));
+    auth.insert("control_chat_ids".to_string(), json!(control_chat_ids));
 
     let mut input = serde_json::Map::new();
     input.insert(
@@ -870,12 +979,13 @@ fn make_tool_working_dir(
     );
 
     crate::tools::resolve_tool_working_dir(
-        base_working_dir,
+        &WorkingDirRoots::from(base_working_dir),
         isolation,
         &serde_json::Value::Object(input),
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn execute_with_template(
     config: &Config,
     caller_channel: &str,
@@ -884,13 +994,15 @@ async fn execute_with_template(
     timeout_secs: u64,
     execution_policy: PluginExecutionPolicy,
     vars: &HashMap<String, String>,
+    envs: HashMap<String, String>,
 ) -> anyhow::Result<SandboxExecResult> {
     let command = render_template_checked(command_template, vars, true)?;
 
-    let base_working_dir = PathBuf::from(&config.working_dir);
+    let base_working_dir = config.working_dir_for_channel(caller_channel);
     let working_dir = make_tool_working_dir(
         &base_working_dir,
-        config.working_dir_isolation,
+        config.resolved_working_dir_isolation(),
+        &config.control_chat_ids,
         caller_channel,
         caller_chat_id,
     );
@@ -902,6 +1014,7 @@ async fn execute_with_template(
         vec![],
     ));
     execute_command_with_policy(
+        config,
         router,
         caller_channel,
         caller_chat_id,
@@ -909,11 +1022,14 @@ async fn execute_with_template(
         timeout_secs,
         working_dir,
         execution_policy,
+        envs,
     )
     .await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn execute_command_with_policy(
+    config: &Config,
     router: Arc<SandboxRouter>,
     caller_channel: &str,
     caller_chat_id: i64,
@@ -921,11 +1037,13 @@ async fn execute_command_with_policy(
     timeout_secs: u64,
     working_dir: PathBuf,
     execution_policy: PluginExecutionPolicy,
+    envs: HashMap<String, String>,
 ) -> anyhow::Result<SandboxExecResult> {
     let opts = SandboxExecOptions {
         timeout: std::time::Duration::from_secs(timeout_secs.max(1)),
         working_dir: Some(working_dir),
-        envs: std::collections::HashMap::new(),
+        envs,
+        shell: None,
     };
 
     if !execution_policy.is_allowed(router.mode(), router.runtime_available()) {
@@ -936,7 +1054,7 @@ async fn execute_command_with_policy(
     }
 
     let session_key = format!("{}-{}", caller_channel, caller_chat_id);
-    match execution_policy {
+    let mut result = match execution_policy {
         PluginExecutionPolicy::HostOnly => {
             microclaw_tools::sandbox::exec_host_command(command, &opts).await
         }
@@ -948,7 +1066,10 @@ async fn execute_command_with_policy(
                 microclaw_tools::sandbox::exec_host_command(command, &opts).await
             }
         }
-    }
+    }?;
+    result.stdout = redact_plugin_secrets(config, &result.stdout);
+    result.stderr = redact_plugin_secrets(config, &result.stderr);
+    Ok(result)
 }
 
 fn format_exec_result(result: &SandboxExecResult) -> String {
@@ -973,6 +1094,7 @@ async fn execute_plugin_tool_spec(
     config: &Config,
     _plugin_name: &str,
     spec: &PluginToolSpec,
+    envs: PluginEnvResult,
     input: serde_json::Value,
 ) -> ToolResult {
     let auth = auth_context_from_input(&input);
@@ -997,6 +1119,14 @@ async fn execute_plugin_tool_spec(
         .with_error_type("plugin_permission_denied");
     }
 
+    let envs = match envs {
+        Ok(envs) => envs,
+        Err(e) => {
+            return ToolResult::error(format!("Plugin tool environment error: {e}"))
+                .with_error_type("plugin_env_error");
+        }
+    };
+
     let mut vars = HashMap::new();
     vars.insert("channel".to_string(), caller_channel.to_string());
     vars.insert("chat_id".to_string(), caller_chat_id.to_string());
@@ -1024,10 +1154,11 @@ async fn execute_plugin_tool_spec(
         }
     };
 
-    let base_working_dir = PathBuf::from(&config.working_dir);
+    let base_working_dir = config.working_dir_for_channel(caller_channel);
     let working_dir = make_tool_working_dir(
         &base_working_dir,
-        config.working_dir_isolation,
+        config.resolved_working_dir_isolation(),
+        &config.control_chat_ids,
         caller_channel,
         caller_chat_id,
     );
@@ -1045,6 +1176,7 @@ async fn execute_plugin_tool_spec(
         vec![],
     ));
     let result = execute_command_with_policy(
+        config,
         router,
         caller_channel,
         caller_chat_id,
@@ -1052,6 +1184,7 @@ async fn execute_plugin_tool_spec(
         spec.run.timeout_secs,
         working_dir,
         PluginTool::resolve_policy(spec),
+        envs,
     )
     .await;
 
@@ -1086,17 +1219,20 @@ impl PluginTool {
         }
     }
 
-    fn resolve_runtime_spec(&self) -> PluginToolSpec {
-        let manifests = load_plugin_manifests(&self.config);
-        manifests
+    fn resolve_runtime_spec(&self) -> (PluginToolSpec, PluginEnvResult) {
+        let manifest = load_plugin_manifests(&self.config)
+            .into_iter()
+            .find(|m| m.name == self.plugin_name);
+        let Some(manifest) = manifest else {
+            return (self.spec.clone(), Ok(HashMap::new()));
+        };
+        let envs = resolve_plugin_env(&self.config, &manifest.name, &manifest.env);
+        let spec = manifest
+            .tools
             .into_iter()
-            .find(|m| m.name == self.plugin_name)
-            .and_then(|m| {
-                m.tools
-                    .into_iter()
-                    .find(|t| t.name.eq_ignore_ascii_case(&self.spec.name))
-            })
-            .unwrap_or_else(|| self.spec.clone())
+            .find(|t| t.name.eq_ignore_ascii_case(&self.spec.name))
+            .unwrap_or_else(|| self.spec.clone());
+        (spec, envs)
     }
 
     fn resolve_policy(spec: &PluginToolSpec) -> PluginExecutionPolicy {
@@ -1126,8 +1262,8 @@ impl Tool for PluginTool {
     }
 
     async fn execute(&self, input: serde_json::Value) -> ToolResult {
-        let runtime_spec = self.resolve_runtime_spec();
-        execute_plugin_tool_spec(&self.config, &self.plugin_name, &runtime_spec, input).await
+        let (runtime_spec, envs) = self.resolve_runtime_spec();
+        execute_plugin_tool_spec(&self.config, &self.plugin_name, &runtime_spec, envs, input).await
     }
 }
 
@@ -1194,6 +1330,7 @@ mod tests {
             }],
             tools: vec![],
             context_providers: vec![],
+            env: HashMap::new(),
         };
         normalize_manifest(&mut manifest);
         assert_eq!(manifest.name, "demo");
@@ -1262,6 +1399,7 @@ mod tests {
                 },
             ],
             context_providers: vec![],
+            env: HashMap::new(),
         };
 
         let mut errors = Vec::new();
@@ -1333,6 +1471,7 @@ mod tests {
                     permissions: PluginContextPermissions::default(),
                 },
             ],
+            env: HashMap::new(),
         };
 
         let mut errors = Vec::new();
@@ -1568,6 +1707,80 @@ tools:
         let _ = std::fs::remove_dir_all(root);
     }
 
+    #[tokio::test]
+    async fn test_plugin_env_injects_secret_without_command_line() {
+        let root = make_temp_plugins_dir("env_secret");
+        std::fs::write(
+            root.join("env.yaml"),
+            r#"
+name: envplug
+enabled: true
+env:
+  API_KEY: "${secret:weather}"
+  API_URL: "https://api.example.com/v1?key=${secret:weather}&units=metric"
+tools:
+  - name: env_tool
+    description: print env
+    run:
+      command: "printf 'len=%s url=%s key=%s' ${#API_KEY} \"$API_URL\" \"$API_KEY\""
+      timeout_secs: 5
+      execution_policy: host_only
+"#,
+        )
+        .unwrap();
+        let mut cfg = config_with_plugins_dir(&root);
+        cfg.plugins
+            .secrets
+            .insert("weather".into(), "wx-secret-123456".into());
+
+        let result = execute_dynamic_plugin_tool(&cfg, "env_tool", json!({}))
+            .await
+            .unwrap();
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("len=16"), "{}", result.content);
+        assert!(result
+            .content
+            .contains("url=https://api.example.com/v1?key=***redacted***&units=metric"));
+        assert!(!result.content.contains("wx-secret-123456"));
+        assert!(!format!("{:?}", cfg.plugins).contains("wx-secret-123456"));
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_plugin_env_unknown_secret_is_reported() {
+        let root = make_temp_plugins_dir("env_missing");
+        std::fs::write(
+            root.join("env.yaml"),
+            r#"
+name: envmissing
+enabled: true
+env:
+  API_KEY: "${secret:nope}"
+tools:
+  - name: env_missing_tool
+    description: print env
+    run:
+      command: "printf ok"
+      timeout_secs: 5
+      execution_policy: host_only
+"#,
+        )
+        .unwrap();
+        let cfg = config_with_plugins_dir(&root);
+        let report = load_plugin_report(&cfg);
+        assert!(report
+            .errors
+            .iter()
+            .any(|e| e.contains("unknown secret 'nope'")));
+
+        let result = execute_dynamic_plugin_tool(&cfg, "env_missing_tool", json!({}))
+            .await
+            .unwrap();
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("plugin_env_error"));
+        let _ = std::fs::remove_dir_all(root);
+    }
+
     #[test]
     fn test_plugin_report_detects_duplicate_plugin_names_across_files() {
         let root = make_temp_plugins_dir("dupe_plugin_names");
@@ -0,0 +1,515 @@
+//! `microclaw replay`: re-run a past turn against a scratch copy of the
+//! database, with side-effecting tools stubbed and no channel to deliver to.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use microclaw_channels::channel_adapter::ChannelRegistry;
+use microclaw_storage::db::{Database, StoredMessage};
+use microclaw_storage::memory::MemoryManager;
+
+use crate::agent_engine::{process_with_agent_with_events, AgentEvent, AgentRequestContext};
+use crate::config::Config;
+use crate::llm::LlmProvider;
+use crate::runtime::AppState;
+
+/// Longest tool input, in bytes, printed per call.
+const INPUT_PREVIEW_MAX_BYTES: usize = 300;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "microclaw replay",
+    about = "Re-run the agent on a past message without sending anything or writing memory",
+    disable_help_subcommand = true
+)]
+struct ReplayCli {
+    /// Internal chat id (as shown by `microclaw audit` and the web UI)
+    chat_id: i64,
+    /// Id of the user message to replay
+    message_id: String,
+    /// Model to replay with instead of the one the turn would normally use
+    #[arg(long)]
+    model: Option<String>,
+}
+
+/// A tool call made during a replayed turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayToolCall {
+    pub name: String,
+    pub input: serde_json::Value,
+    pub is_error: bool,
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub channel: String,
+    pub tool_calls: Vec<ReplayToolCall>,
+    pub reply: String,
+}
+
+pub async fn handle_replay_cli(args: &[String]) -> Result<()> {
+    let cli = match ReplayCli::try_parse_from(
+        std::iter::once("replay").chain(args.iter().map(std::string::String::as_str)),
+    ) {
+        Ok(cli) => cli,
+        Err(err)
+            if matches!(
+                err.kind(),
+                clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion
+            ) =>
+        {
+            err.print()?;
+            return Ok(());
+        }
+        Err(err) => return Err(anyhow!(err.to_string())),
+    };
+
+    let config = Config::load()?;
+    let db = config.open_database()?;
+    let llm = crate::llm::create_provider(&config);
+    let scratch_dir =
+        std::env::temp_dir().join(format!("microclaw-replay-{}", uuid::Uuid::new_v4()));
+    let report = replay_turn(
+        config,
+        &db,
+        llm,
+        &scratch_dir,
+        cli.chat_id,
+        &cli.message_id,
+        cli.model.as_deref(),
+    )
+    .await;
+    let _ = std::fs::remove_dir_all(&scratch_dir);
+    print!("{}", format_report(&report?, cli.model.as_deref()));
+    Ok(())
+}
+
+/// Rebuild the chat as it was when `message_id` arrived and run the agent on
+/// it. The original database is only read: the turn runs against a copy in
+/// `scratch_dir` with later messages and the saved session removed, so the
+/// context is rebuilt from stored messages.
+pub async fn replay_turn(
+    config: Config,
+    db: &Database,
+    llm: Box<dyn LlmProvider>,
+    scratch_dir: &Path,
+    chat_id: i64,
+    message_id: &str,
+    model: Option<&str>,
+) -> Result<ReplayReport> {
+    let Some(chat_type) = db.get_chat_type(chat_id)? else {
+        bail!("chat {chat_id} not found");
+    };
+    let Some(target) = db
+        .get_all_messages(chat_id)?
+        .into_iter()
+        .find(|m| m.id == message_id)
+    else {
+        bail!("message {message_id} not found in chat {chat_id}");
+    };
+    if target.is_from_bot {
+        bail!("message {message_id} was sent by the bot; pass the id of the user message that triggered the turn");
+    }
+    let channel = db.get_chat_channel(chat_id)?.unwrap_or_else(|| {
+        chat_type
+            .split('_')
+            .next()
+            .unwrap_or(&chat_type)
+            .to_string()
+    });
+
+    let scratch = scratch_dir.to_string_lossy().to_string();
+    let scratch_db = Arc::new(db.copy_to_dir(&scratch)?);
+    scratch_db.delete_session(chat_id)?;
+    scratch_db.delete_messages_after(chat_id, message_id)?;
+    let memory = copy_file_memory(&config, &scratch, chat_id)?;
+
+    let state = build_replay_state(config, llm, scratch_db, memory);
+    if let Some(model) = model {
+        crate::run_control::set_next_model(&channel, chat_id, model).await;
+    }
+
+    let (event_tx, mut event_rx) = tokio::sync::mpsc::unbounded_channel();
+    let agent_chat_type = if chat_type.ends_with("group") {
+        "group"
+    } else {
+        "private"
+    };
+    let reply = process_with_agent_with_events(
+        &state,
+        AgentRequestContext {
+            caller_channel: &channel,
+            chat_id,
+            chat_type: agent_chat_type,
+            sender_id: replay_sender_id(&target),
+            sender_is_admin: false,
+        },
+        None,
+        None,
+        Some(&event_tx),
+    )
+    .await?;
+    drop(event_tx);
+
+    let mut tool_calls: Vec<ReplayToolCall> = Vec::new();
+    while let Some(event) = event_rx.recv().await {
+        match event {
+            AgentEvent::ToolStart { name, input } => tool_calls.push(ReplayToolCall {
+                name,
+                input,
+                is_error: false,
+                preview: String::new(),
+            }),
+            AgentEvent::ToolResult {
+                name,
+                is_error,
+                preview,
+                ..
+            } => {
+                if let Some(call) = tool_calls
+                    .iter_mut()
+                    .find(|c| c.name == name && c.preview.is_empty())
+                {
+                    call.is_error = is_error;
+                    call.preview = preview;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(ReplayReport {
+        channel,
+        tool_calls,
+        reply,
+    })
+}
+
+/// The `sender_id` the live turn ran with. Adapters store the member's
+/// platform id (Feishu open_id, DingTalk staff id, email address, webhook
+/// `sender_id`) as the message's `sender_name`, and pass that same value as
+/// `AgentRequestContext::sender_id`.
+fn replay_sender_id(message: &StoredMessage) -> Option<&str> {
+    Some(message.sender_name.as_str()).filter(|s| !s.is_empty())
+}
+
+/// Copy the global and chat AGENTS.md files into the scratch dir, so the
+/// replay reads the live memory but can never write to it.
+fn copy_file_memory(config: &Config, scratch: &str, chat_id: i64) -> Result<MemoryManager> {
+    let live = MemoryManager::new(&config.runtime_data_dir());
+    let memory = MemoryManager::new(scratch);
+    if let Some(global) = live.read_global_memory() {
+        memory.write_global_memory(&global)?;
+    }
+    if let Some(chat) = live.read_chat_memory(chat_id) {
+        memory.write_chat_memory(chat_id, &chat)?;
+    }
+    Ok(memory)
+}
+
+/// App state for a replay: the configured LLM and skills, but the scratch
+/// database and memory files, dry-run tools, no hooks, no MCP servers and no
+/// channel adapters, so nothing can be delivered or changed.
+fn build_replay_state(
+    config: Config,
+    llm: Box<dyn LlmProvider>,
+    db: Arc<Database>,
+    memory: MemoryManager,
+) -> AppState {
+    let memory_backend = Arc::new(crate::memory_backend::MemoryBackend::local_only(db.clone()));
+    let channel_registry = Arc::new(ChannelRegistry::new());
+    let mut tools = crate::tools::ToolRegistry::new(
+        &config,
+        channel_registry.clone(),
+        db.clone(),
+        memory_backend.clone(),
+    );
+    tools.set_dry_run(true);
+    AppState {
+        tools,
+        memory,
+        skills: crate::skills::SkillManager::from_skills_dir(&config.skills_data_dir()),
+        hooks: Arc::new(crate::hooks::HookManager::disabled()),
+        embedding: crate::embedding::create_provider(&config),
+        config,
+        channel_registry,
+        db,
+        llm,
+        llm_model_overrides: HashMap::new(),
+        memory_backend,
+        mcp_manager: tokio::sync::Mutex::new(crate::mcp::McpManager::default()),
+    }
+}
+
+fn format_report(report: &ReplayReport, model: Option<&str>) -> String {
+    let mut out = format!("Replayed on {} (dry run", report.channel);
+    if let Some(model) = model {
+        out.push_str(&format!(", model {model}"));
+    }
+    out.push_str(")\n\n");
+    if report.tool_calls.is_empty() {
+        out.push_str("Tool calls: none\n");
+    } else {
+        out.push_str(&format!("Tool calls ({}):\n", report.tool_calls.len()));
+        for (i, call) in report.tool_calls.iter().enumerate() {
+            let mut input = call.input.to_string();
+            if input.len() > INPUT_PREVIEW_MAX_BYTES {
+                let end =
+                    microclaw_core::text::floor_char_boundary(&input, INPUT_PREVIEW_MAX_BYTES);
+                input.truncate(end);
+                input.push_str("...");
+            }
+            let status = if call.is_error { "error" } else { "ok" };
+            out.push_str(&format!("{}. {} {input}\n", i + 1, call.name));
+            out.push_str(&format!("   -> [{status}] {}\n", call.preview));
+        }
+    }
+    out.push_str(&format!("\nReply:\n{}\n", report.reply));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use microclaw_core::error::MicroClawError;
+    use microclaw_core::llm_types::{
+        Message, MessageContent, MessagesResponse, ResponseContentBlock, ToolDefinition,
+    };
+    use microclaw_storage::db::StoredMessage;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc::UnboundedSender;
+
+    /// Model override, last user message and system prompt of each call.
+    type SeenCalls = Arc<Mutex<Vec<(Option<String>, String, String)>>>;
+
+    /// Writes a file on its first call, then replies with text.
+    struct WriteThenReplyLlm {
+        seen: SeenCalls,
+    }
+
+    fn last_user_text(messages: &[Message]) -> String {
+        messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .map(|m| match &m.content {
+                MessageContent::Text(text) => text.clone(),
+                MessageContent::Blocks(_) => String::new(),
+            })
+            .unwrap_or_default()
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for WriteThenReplyLlm {
+        async fn send_message(
+            &self,
+            system: &str,
+            messages: Vec<Message>,
+            tools: Option<Vec<ToolDefinition>>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            self.send_message_stream_with_model(system, messages, tools, None, None)
+                .await
+        }
+
+        async fn send_message_stream_with_model(
+            &self,
+            system: &str,
+            messages: Vec<Message>,
+            _tools: Option<Vec<ToolDefinition>>,
+            _text_tx: Option<&UnboundedSender<String>>,
+            model_override: Option<&str>,
+        ) -> Result<MessagesResponse, MicroClawError> {
+            let mut seen = self.seen.lock().unwrap();
+            let first = seen.is_empty();
+            seen.push((
+                model_override.map(str::to_string),
+                last_user_text(&messages),
+                system.to_string(),
+            ));
+            let content = if first {
+                vec![ResponseContentBlock::ToolUse {
+                    id: "tool-0".into(),
+                    name: "write_file".into(),
+                    input: serde_json::json!({"path": "replayed.txt", "content": "x"}),
+                }]
+            } else {
+                vec![ResponseContentBlock::Text {
+                    text: "Done.".into(),
+                }]
+            };
+            Ok(MessagesResponse {
+                stop_reason: Some(if first { "tool_use" } else { "end_turn" }.into()),
+                content,
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_turn_stubs_side_effects_and_leaves_db_untouched() {
+        let base_dir =
+            std::env::temp_dir().join(format!("microclaw_replay_{}", uuid::Uuid::new_v4()));
+        let mut config = Config::test_defaults();
+        config.data_dir = base_dir.to_string_lossy().to_string();
+        config.working_dir = base_dir.join("work").to_string_lossy().to_string();
+        let db = Database::new(&config.runtime_data_dir()).unwrap();
+        let chat_id = db
+            .resolve_or_create_chat_id("web", "replay-chat", Some("replay"), "web")
+            .unwrap();
+        let store = |id: &str, sender: &str, content: &str, is_from_bot: bool, ts: &str| {
+            db.store_message(&StoredMessage {
+                id: id.into(),
+                chat_id,
+                sender_name: sender.into(),
+                content: content.into(),
+                is_from_bot,
+                timestamp: ts.into(),
+            })
+            .unwrap();
+        };
+        store(
+            "m1",
+            "ou_alice",
+            "write it up",
+            false,
+            "2024-01-01T00:00:01Z",
+        );
+        store("m2", "bot", "Written.", true, "2024-01-01T00:00:02Z");
+        store("m3", "ou_alice", "thanks", false, "2024-01-01T00:00:03Z");
+        db.save_session(chat_id, "[]").unwrap();
+        let alice = db
+            .insert_memory(Some(chat_id), "alice prefers tea", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(alice, Some("ou_alice")).unwrap();
+        let bob = db
+            .insert_memory(Some(chat_id), "bob prefers coffee", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(bob, Some("ou_bob")).unwrap();
+        let live_memory = MemoryManager::new(&config.runtime_data_dir());
+        live_memory
+            .write_chat_memory(chat_id, "Reports go in the shared drive.")
+            .unwrap();
+
+        // A configured hook that would leave a marker file if it ran.
+        let marker = base_dir.join("hook-ran");
+        #[cfg(unix)]
+        {
+            let hook_dir = base_dir.join("hooks").join("touch");
+            std::fs::create_dir_all(&hook_dir).unwrap();
+            std::fs::write(
+                hook_dir.join("HOOK.md"),
+                "---\nname: touch\nevents: [BeforeLLMCall, BeforeToolCall]\ncommand: \"sh hook.sh\"\n---\n",
+            )
+            .unwrap();
+            std::fs::write(
+                hook_dir.join("hook.sh"),
+                format!(
+                    "touch '{}'\necho '{{\"action\":\"allow\"}}'\n",
+                    marker.display()
+                ),
+            )
+            .unwrap();
+            let live_hooks = crate::hooks::HookManager::from_config(&config);
+            assert!(live_hooks.list().await.iter().any(|h| h.name == "touch"));
+        }
+
+        let seen: SeenCalls = Arc::new(Mutex::new(Vec::new()));
+        let report = replay_turn(
+            config,
+            &db,
+            Box::new(WriteThenReplyLlm { seen: seen.clone() }),
+            &base_dir.join("scratch"),
+            chat_id,
+            "m1",
+            Some("replay-model"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.channel, "web");
+        assert_eq!(report.reply, "Done.");
+        assert_eq!(report.tool_calls.len(), 1);
+        assert_eq!(report.tool_calls[0].name, "write_file");
+        assert!(!report.tool_calls[0].is_error);
+        assert!(report.tool_calls[0].preview.contains("[dry run]"));
+        assert!(!base_dir.join("work").join("replayed.txt").exists());
+        assert!(!marker.exists(), "hook ran during replay");
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen[0].0.as_deref(), Some("replay-model"));
+        assert!(seen[0].1.contains("write it up"));
+        assert!(!seen[0].1.contains("thanks"));
+        // Memory is scoped to the original sender, and file memory is read.
+        assert!(seen[0].2.contains("alice prefers tea"));
+        assert!(!seen[0].2.contains("bob prefers coffee"));
+        assert!(seen[0].2.contains("Reports go in the shared drive."));
+
+        // The live database keeps its history and session.
+        assert_eq!(db.get_all_messages(chat_id).unwrap().len(), 3);
+        assert!(db.load_session(chat_id).unwrap().is_some());
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[tokio::test]
+    async fn test_replay_turn_rejects_unknown_and_bot_messages() {
+        let base_dir =
+            std::env::temp_dir().join(format!("microclaw_replay2_{}", uuid::Uuid::new_v4()));
+        let mut config = Config::test_defaults();
+        config.data_dir = base_dir.to_string_lossy().to_string();
+        let db = Database::new(&config.runtime_data_dir()).unwrap();
+        let chat_id = db
+            .resolve_or_create_chat_id("web", "replay-chat", Some("replay"), "web")
+            .unwrap();
+        db.store_message(&StoredMessage {
+            id: "b1".into(),
+            chat_id,
+            sender_name: "bot".into(),
+            content: "hello".into(),
+            is_from_bot: true,
+            timestamp: "2024-01-01T00:00:01Z".into(),
+        })
+        .unwrap();
+        let llm = || -> Box<dyn LlmProvider> {
+            Box::new(WriteThenReplyLlm {
+                seen: Arc::new(Mutex::new(Vec::new())),
+            })
+        };
+        let scratch = base_dir.join("scratch");
+
+        let err = replay_turn(config.clone(), &db, llm(), &scratch, chat_id, "b1", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("sent by the bot"), "{err}");
+        let err = replay_turn(config.clone(), &db, llm(), &scratch, chat_id, "nope", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+        let err = replay_turn(config, &db, llm(), &scratch, chat_id + 1000, "b1", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("chat"), "{err}");
+        let _ = std::fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn test_format_report_lists_tool_calls() {
+        let report = ReplayReport {
+            channel: "web".into(),
+            tool_calls: vec![ReplayToolCall {
+                name: "bash".into(),
+                input: serde_json::json!({"command": "ls"}),
+                is_error: false,
+                preview: "[dry run] `bash` was not executed.".into(),
+            }],
+            reply: "ok".into(),
+        };
+        let text = format_report(&report, Some("gpt-x"));
+        assert!(text.starts_with("Replayed on web (dry run, model gpt-x)"));
+        assert!(text.contains("1. bash {\"command\":\"ls\"}"));
+        assert!(text.contains("-> [ok] [dry run] `bash` was not executed."));
+        assert!(text.ends_with("Reply:\nok\n"));
+    }
+}
@@ -2,7 +2,8 @@ use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, LazyLock};
 
-use tokio::sync::{Mutex, Notify};
+use microclaw_core::llm_types::ToolChoice;
+use tokio::sync::{Mutex, Notify, OwnedMutexGuard};
 
 type RunKey = (String, i64);
 
@@ -19,6 +20,15 @@ static ACTIVE_RUNS: LazyLock<Mutex<HashMap<RunKey, Vec<ActiveRun>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 static ABORTED_SOURCE_MESSAGE_IDS: LazyLock<Mutex<HashMap<RunKey, HashSet<String>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
+static NEXT_RUN_TOOL_CHOICE: LazyLock<Mutex<HashMap<RunKey, ToolChoice>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static NEXT_RUN_MODEL: LazyLock<Mutex<HashMap<RunKey, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+// A std mutex so releasing a turn can prune its entry from `Drop`.
+static CHAT_TURN_LOCKS: LazyLock<std::sync::Mutex<HashMap<RunKey, Arc<Mutex<()>>>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
+static MESSAGES_LOADED_AT: LazyLock<Mutex<HashMap<RunKey, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 pub const STOPPED_TEXT: &str = "Current run aborted.";
 
@@ -43,6 +53,21 @@ pub async fn register_run(
     (run_id, cancelled, notify)
 }
 
+tokio::task_local! {
+    static CURRENT_RUN_ID: u64;
+}
+
+/// Run `fut` as part of run `run_id`; tool calls made inside it are scoped to
+/// that run. Tasks spawned inside `fut` do not inherit it.
+pub async fn with_run_id<F: std::future::Future>(run_id: u64, fut: F) -> F::Output {
+    CURRENT_RUN_ID.scope(run_id, fut).await
+}
+
+/// The run the current task belongs to; 0 outside [`with_run_id`].
+pub fn current_run_id() -> u64 {
+    CURRENT_RUN_ID.try_with(|id| *id).unwrap_or(0)
+}
+
 pub async fn unregister_run(channel: &str, chat_id: i64, run_id: u64) {
     let mut map = ACTIVE_RUNS.lock().await;
     let key = (channel.to_string(), chat_id);
@@ -92,6 +117,100 @@ pub async fn is_aborted_source_message(channel: &str, chat_id: i64, message_id:
         .unwrap_or(false)
 }
 
+/// Apply `choice` to the next run in this chat only (`/tools`).
+pub async fn set_next_tool_choice(channel: &str, chat_id: i64, choice: ToolChoice) {
+    let mut map = NEXT_RUN_TOOL_CHOICE.lock().await;
+    map.insert((channel.to_string(), chat_id), choice);
+}
+
+pub async fn peek_next_tool_choice(channel: &str, chat_id: i64) -> Option<ToolChoice> {
+    let map = NEXT_RUN_TOOL_CHOICE.lock().await;
+    map.get(&(channel.to_string(), chat_id)).cloned()
+}
+
+pub async fn take_next_tool_choice(channel: &str, chat_id: i64) -> Option<ToolChoice> {
+    let mut map = NEXT_RUN_TOOL_CHOICE.lock().await;
+    map.remove(&(channel.to_string(), chat_id))
+}
+
+/// Run the next turn in this chat on `model` (`/retry <model>`).
+pub async fn set_next_model(channel: &str, chat_id: i64, model: &str) {
+    let mut map = NEXT_RUN_MODEL.lock().await;
+    map.insert((channel.to_string(), chat_id), model.to_string());
+}
+
+pub async fn take_next_model(channel: &str, chat_id: i64) -> Option<String> {
+    let mut map = NEXT_RUN_MODEL.lock().await;
+    map.remove(&(channel.to_string(), chat_id))
+}
+
+fn chat_turn_locks() -> std::sync::MutexGuard<'static, HashMap<RunKey, Arc<Mutex<()>>>> {
+    CHAT_TURN_LOCKS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A turn's handle on its chat's lock. Dropping the last handle removes the
+/// chat's entry, so the map only holds chats with a running or waiting turn.
+struct ChatTurnLockRef {
+    key: RunKey,
+    lock: Option<Arc<Mutex<()>>>,
+}
+
+impl Drop for ChatTurnLockRef {
+    fn drop(&mut self) {
+        let mut map = chat_turn_locks();
+        drop(self.lock.take());
+        if map
+            .get(&self.key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            map.remove(&self.key);
+        }
+    }
+}
+
+/// Held by a serialized turn until it ends; see [`lock_chat_turn`].
+pub struct ChatTurnGuard {
+    // Released before `_lock_ref` prunes the entry.
+    _guard: OwnedMutexGuard<()>,
+    _lock_ref: ChatTurnLockRef,
+}
+
+/// Wait until no other turn runs in this chat (`serialize_per_chat`). The turn
+/// keeps the guard until it ends; waiters are served in arrival order.
+pub async fn lock_chat_turn(channel: &str, chat_id: i64) -> ChatTurnGuard {
+    let key = (channel.to_string(), chat_id);
+    let lock = chat_turn_locks().entry(key.clone()).or_default().clone();
+    let lock_ref = ChatTurnLockRef {
+        key,
+        lock: Some(lock.clone()),
+    };
+    ChatTurnGuard {
+        _guard: lock.lock_owned().await,
+        _lock_ref: lock_ref,
+    }
+}
+
+/// Whether a turn holds this chat's lock, i.e. a new turn would have to wait.
+pub async fn is_chat_turn_busy(channel: &str, chat_id: i64) -> bool {
+    chat_turn_locks()
+        .get(&(channel.to_string(), chat_id))
+        .is_some_and(|lock| lock.try_lock().is_err())
+}
+
+/// Record when a serialized turn read the chat's new messages. The session it
+/// saves covers messages up to this point, not up to its save time.
+pub async fn note_messages_loaded(channel: &str, chat_id: i64, at: &str) {
+    let mut map = MESSAGES_LOADED_AT.lock().await;
+    map.insert((channel.to_string(), chat_id), at.to_string());
+}
+
+pub async fn messages_loaded_at(channel: &str, chat_id: i64) -> Option<String> {
+    let map = MESSAGES_LOADED_AT.lock().await;
+    map.get(&(channel.to_string(), chat_id)).cloned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,4 +236,69 @@ mod tests {
         let aborted = abort_runs("test.none", 999).await;
         assert_eq!(aborted, 0);
     }
+
+    #[tokio::test]
+    async fn test_next_tool_choice_is_taken_once() {
+        let channel = "test.tool_choice";
+        set_next_tool_choice(channel, 7, ToolChoice::None).await;
+        assert_eq!(
+            peek_next_tool_choice(channel, 7).await,
+            Some(ToolChoice::None)
+        );
+        assert_eq!(
+            take_next_tool_choice(channel, 7).await,
+            Some(ToolChoice::None)
+        );
+        assert_eq!(take_next_tool_choice(channel, 7).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_next_model_is_taken_once() {
+        let channel = "test.next_model";
+        set_next_model(channel, 7, "gpt-alt").await;
+        assert_eq!(
+            take_next_model(channel, 7).await.as_deref(),
+            Some("gpt-alt")
+        );
+        assert_eq!(take_next_model(channel, 7).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_chat_turn_lock_serializes_one_chat_only() {
+        let channel = "test.chat_turn_lock";
+        let first = lock_chat_turn(channel, 1).await;
+        assert!(is_chat_turn_busy(channel, 1).await);
+        assert!(!is_chat_turn_busy(channel, 2).await);
+        let other_chat = lock_chat_turn(channel, 2).await;
+
+        let waiter = tokio::spawn(async move {
+            let _guard = lock_chat_turn(channel, 1).await;
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        drop(first);
+        tokio::time::timeout(std::time::Duration::from_secs(5), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!is_chat_turn_busy(channel, 1).await);
+        // Released chats leave no entry behind.
+        let has_entry =
+            |chat_id: i64| chat_turn_locks().contains_key(&(channel.to_string(), chat_id));
+        assert!(!has_entry(1));
+        assert!(has_entry(2));
+        drop(other_chat);
+        assert!(!has_entry(2));
+
+        // So do turns cancelled while still waiting.
+        let holder = lock_chat_turn(channel, 3).await;
+        let waiter = tokio::spawn(async move {
+            let _guard = lock_chat_turn(channel, 3).await;
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        waiter.abort();
+        let _ = waiter.await;
+        drop(holder);
+        assert!(!has_entry(3));
+    }
 }
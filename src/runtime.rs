@@ -10,16 +10,20 @@ use tracing::{info, warn};
 use crate::channels::dingtalk::{build_dingtalk_runtime_contexts, DingTalkRuntimeContext};
 use crate::channels::email::{build_email_runtime_contexts, EmailRuntimeContext};
 use crate::channels::feishu::{build_feishu_runtime_contexts, FeishuRuntimeContext};
-use crate::channels::{DingTalkAdapter, EmailAdapter, FeishuAdapter};
-use crate::config::Config;
+use crate::channels::webhook::{build_webhook_runtime_contexts, WebhookRuntimeContext};
+use crate::channels::{DingTalkAdapter, EmailAdapter, FeishuAdapter, WebhookAdapter};
+use crate::config::{Config, ModelRole};
 use crate::embedding::EmbeddingProvider;
 use crate::hooks::HookManager;
 use crate::llm::LlmProvider;
+use crate::mcp::{McpManager, McpReloadReport};
 use crate::memory::MemoryManager;
 use crate::memory_backend::MemoryBackend;
 use crate::skills::SkillManager;
-use crate::tools::ToolRegistry;
+use crate::tools::{Tool, ToolRegistry};
 use microclaw_channels::channel_adapter::ChannelRegistry;
+use microclaw_channels::health::SendFailurePolicy;
+use microclaw_channels::inbound_queue::{InboundQueuePolicy, QueueDropPolicy};
 use microclaw_storage::db::Database;
 
 pub struct AppState {
@@ -34,6 +38,46 @@ pub struct AppState {
     pub embedding: Option<Arc<dyn EmbeddingProvider>>,
     pub memory_backend: Arc<MemoryBackend>,
     pub tools: ToolRegistry,
+    pub mcp_manager: tokio::sync::Mutex<McpManager>,
+}
+
+fn mcp_tools(mcp_manager: &McpManager) -> Vec<Arc<dyn Tool>> {
+    mcp_manager
+        .all_tools()
+        .into_iter()
+        .map(|(server, tool_info)| {
+            Arc::new(crate::tools::mcp::McpTool::new(server, tool_info)) as Arc<dyn Tool>
+        })
+        .collect()
+}
+
+/// Re-scan MCP config and swap the registry's MCP tools. Plugin tools are read from
+/// disk on every call, so they need no reload. Turns in flight keep their old handles.
+pub async fn reload_mcp_tools(state: &AppState) -> McpReloadReport {
+    let mut mcp_manager = state.mcp_manager.lock().await;
+    let report = mcp_manager.reload().await;
+    state.tools.set_mcp_tools(mcp_tools(&mcp_manager));
+    info!("MCP reloaded ({})", report.summary());
+    report
+}
+
+#[cfg(unix)]
+fn spawn_reload_on_sighup(state: Arc<AppState>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Failed to listen for SIGHUP; MCP reload is only available via /reload: {e}");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            info!("SIGHUP received; reloading MCP servers");
+            reload_mcp_tools(&state).await;
+        }
+    });
 }
 
 fn prepare_channel_runtimes<T, Build, Register, ModelOverride>(
@@ -107,10 +151,23 @@ pub async fn run(
     db: Database,
     memory: MemoryManager,
     skills: SkillManager,
-    mcp_manager: crate::mcp::McpManager,
+    mcp_manager: McpManager,
 ) -> anyhow::Result<()> {
     let db = Arc::new(db);
     let llm = crate::llm::create_provider(&config);
+    if config.deterministic {
+        if crate::llm::provider_supports_seed(&config.llm_provider) {
+            info!(
+                "Deterministic mode: temperature=0, seed={}",
+                config.deterministic_seed
+            );
+        } else {
+            warn!(
+                "Deterministic mode: temperature=0; provider '{}' does not accept a seed, so output may still vary",
+                config.llm_provider
+            );
+        }
+    }
     let embedding = crate::embedding::create_provider(&config);
     #[cfg(feature = "sqlite-vec")]
     {
@@ -126,6 +183,16 @@ pub async fn run(
 
     // Build channel registry from config
     let mut registry = ChannelRegistry::new();
+    registry.set_send_failure_policy(SendFailurePolicy {
+        alert_threshold: config.channel_send_failure_alert_threshold,
+        backoff_secs: config.channel_send_failure_backoff_secs,
+        alert_chat_ids: config.control_chat_ids.clone(),
+    });
+    registry.set_inbound_queue_policy(InboundQueuePolicy {
+        capacity: config.channel_queue_capacity,
+        max_concurrency: config.channel_queue_max_concurrency,
+        drop_policy: QueueDropPolicy::parse(&config.channel_queue_drop_policy).unwrap_or_default(),
+    });
     let mut llm_model_overrides: HashMap<String, String> = HashMap::new();
 
     let feishu_runtimes: Vec<FeishuRuntimeContext> = prepare_channel_runtimes(
@@ -188,23 +255,78 @@ pub async fn run(
                 .map(|model| (runtime.channel_name.clone(), model))
         },
     );
+    let webhook_runtimes: Vec<WebhookRuntimeContext> = prepare_channel_runtimes(
+        &config,
+        "webhook",
+        &mut registry,
+        &mut llm_model_overrides,
+        build_webhook_runtime_contexts,
+        |runtime, reg| {
+            reg.register(Arc::new(WebhookAdapter::new(
+                runtime.channel_name.clone(),
+                runtime.callback_url.clone(),
+                runtime.callback_token.clone(),
+            )));
+        },
+        |runtime| {
+            runtime
+                .model
+                .clone()
+                .map(|model| (runtime.channel_name.clone(), model))
+        },
+    );
+
+    for channel in registry.channel_names() {
+        let model = llm_model_overrides
+            .get(&channel)
+            .map(String::as_str)
+            .unwrap_or(config.model_for_role(ModelRole::Main));
+        let decoration = config.reply_decoration_for_channel(&channel, model);
+        registry.set_reply_decoration(&channel, decoration);
+        // Malformed windows are rejected at config load.
+        let quiet_hours = config.quiet_hours_for_channel(&channel).ok().flatten();
+        registry.set_quiet_hours(&channel, quiet_hours);
+    }
 
     let channel_registry = Arc::new(registry);
 
-    let memory_backend = Arc::new(MemoryBackend::new(
-        db.clone(),
-        crate::memory_backend::MemoryMcpClient::discover(&mcp_manager),
-    ));
+    let memory_backend = Arc::new(
+        MemoryBackend::new(
+            db.clone(),
+            crate::memory_backend::MemoryMcpClient::discover(&mcp_manager),
+        )
+        .with_semantic_dedup(embedding.clone(), config.memory_dedup_similarity_threshold)
+        .with_vector_store(crate::vector_store::create_vector_store(
+            &config,
+            embedding.as_ref(),
+        )),
+    );
     let mut tools = ToolRegistry::new(
         &config,
         channel_registry.clone(),
         db.clone(),
         memory_backend.clone(),
     );
-
-    for (server, tool_info) in mcp_manager.all_tools() {
-        tools.add_tool(Box::new(crate::tools::mcp::McpTool::new(server, tool_info)));
+    let self_test =
+        crate::doctor::tool_deps_self_test(&config, tools.sandbox_runtime_available()).await;
+    for line in self_test.summary_lines() {
+        if self_test.has_failures() {
+            warn!("{line}");
+        } else {
+            info!("{line}");
+        }
+    }
+    if self_test.has_failures() {
+        let missing = self_test.unavailable_tools.join(", ");
+        if config.fail_fast_on_missing_tool_deps {
+            anyhow::bail!(
+                "tool dependency self-test failed for: {missing} (fail_fast_on_missing_tool_deps is set; see the summary above or run `microclaw doctor`)"
+            );
+        }
+        warn!("Disabling tools with missing dependencies: {missing}. Fix them and restart to re-enable.");
+        tools.disable_tools(&self_test.unavailable_tools);
     }
+    tools.set_mcp_tools(mcp_tools(&mcp_manager));
 
     let hooks = Arc::new(HookManager::from_config(&config).with_db(db.clone()));
 
@@ -220,8 +342,12 @@ pub async fn run(
         embedding,
         memory_backend,
         tools,
+        mcp_manager: tokio::sync::Mutex::new(mcp_manager),
     });
 
+    #[cfg(unix)]
+    spawn_reload_on_sighup(state.clone());
+    crate::skills::spawn_skill_watcher(state.skills.skills_dir().clone());
     crate::scheduler::spawn_scheduler(state.clone());
     crate::scheduler::spawn_reflector(state.clone());
 
@@ -267,7 +393,19 @@ pub async fn run(
         );
     }
 
-    let has_active_channels = [has_feishu, has_email, has_dingtalk]
+    let has_webhook = !webhook_runtimes.is_empty();
+    if has_webhook {
+        spawn_channel_runtimes(
+            state.clone(),
+            webhook_runtimes,
+            |channel_state, runtime_ctx| async move {
+                info!("Starting Webhook adapter '{}'", runtime_ctx.channel_name);
+                crate::channels::webhook::start_webhook_bot(channel_state, runtime_ctx).await;
+            },
+        );
+    }
+
+    let has_active_channels = [has_feishu, has_email, has_dingtalk, has_webhook]
         .into_iter()
         .any(|v| v);
 
@@ -279,7 +417,7 @@ pub async fn run(
         Ok(())
     } else {
         Err(anyhow!(
-            "No channel is enabled. Configure channels.<name>.enabled for Feishu, Email, or DingTalk."
+            "No channel is enabled. Configure channels.<name>.enabled for Feishu, Email, DingTalk, or Webhook."
         ))
     }
 }
\ No newline at end of file
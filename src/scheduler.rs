@@ -1,20 +1,29 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
 use tokio::time::{Duration, Instant, MissedTickBehavior};
 use tracing::{error, info, warn};
 
 use crate::agent_engine::process_with_agent;
 use crate::agent_engine::AgentRequestContext;
+use crate::config::{Config, ModelRole, SamplingParams};
+use crate::memory_backend::should_merge_duplicate;
 use crate::runtime::AppState;
+use crate::tools::schedule::{MissedRunPolicy, TaskDelivery};
 use crate::{db::Memory, memory_quality};
 use microclaw_channels::channel::{
-    deliver_and_store_bot_message, get_chat_routing, ChatRouting, ConversationKind,
+    deliver_or_defer_bot_message, flush_deferred_messages, get_chat_routing, ChatRouting,
+    ConversationKind, OutboundDelivery,
 };
+use microclaw_core::error::MicroClawError;
 use microclaw_core::llm_types::{Message, MessageContent, ResponseContentBlock};
 use microclaw_core::text::floor_char_boundary;
-use microclaw_storage::db::call_blocking;
+#[cfg(feature = "sqlite-vec")]
+use microclaw_storage::db::MemoryEmbedding;
+use microclaw_storage::db::{call_blocking, Database, MemoryBatchOp, ScheduledTask};
 
 pub fn spawn_scheduler(state: Arc<AppState>) {
     tokio::spawn(async move {
@@ -30,7 +39,8 @@ pub fn spawn_scheduler(state: Arc<AppState>) {
             }
         }
         // Run once at startup so overdue tasks are not delayed until the first tick.
-        run_due_tasks(&state).await;
+        let catch_up = plan_catch_up_runs(&state).await;
+        run_due_tasks(&state, &catch_up).await;
 
         // Align polling to wall-clock minute boundaries for stable "every minute" behavior.
         let now = Utc::now();
@@ -48,150 +58,303 @@ pub fn spawn_scheduler(state: Arc<AppState>) {
 
         loop {
             ticker.tick().await;
-            run_due_tasks(&state).await;
+            run_due_tasks(&state, &HashMap::new()).await;
         }
     });
 }
 
-async fn run_due_tasks(state: &Arc<AppState>) {
-    let now = Utc::now().to_rfc3339();
-    let tasks = match call_blocking(state.db.clone(), move |db| db.claim_due_tasks(&now, 200)).await
-    {
-        Ok(t) => t,
-        Err(e) => {
-            error!("Scheduler: failed to query due tasks: {e}");
-            return;
-        }
+/// Send a run's output according to the task's `deliver` setting.
+async fn deliver_task_output(
+    state: &Arc<AppState>,
+    task: &ScheduledTask,
+    routing: &ChatRouting,
+    success: bool,
+    text: &str,
+) {
+    let Some(target) = TaskDelivery::target_chat(task, success) else {
+        return;
     };
-
-    for task in tasks {
-        info!(
-            "Scheduler: executing task #{} for chat {}",
-            task.id, task.chat_id
-        );
-
-        let started_at = Utc::now();
-        let started_at_str = started_at.to_rfc3339();
-        let routing = get_chat_routing(&state.channel_registry, state.db.clone(), task.chat_id)
+    let channel_name = if target == task.chat_id {
+        routing.channel_name.clone()
+    } else {
+        get_chat_routing(&state.channel_registry, state.db.clone(), target)
             .await
             .ok()
             .flatten()
-            .unwrap_or_else(|| {
-                warn!(
-                    "Scheduler: no chat routing found for chat {}, defaulting to telegram/private",
-                    task.chat_id
-                );
-                ChatRouting {
-                    channel_name: "telegram".to_string(),
-                    conversation: ConversationKind::Private,
-                }
-            });
+            .map(|r| r.channel_name)
+            .unwrap_or_else(|| routing.channel_name.clone())
+    };
+    let bot_username = state.config.bot_username_for_channel(&channel_name);
+    match deliver_or_defer_bot_message(
+        &state.channel_registry,
+        state.db.clone(),
+        &bot_username,
+        target,
+        text,
+    )
+    .await
+    {
+        Ok(OutboundDelivery::Sent) => {}
+        Ok(OutboundDelivery::Deferred(until)) => info!(
+            "Scheduler: output of task #{} to chat {target} deferred until {until} (quiet hours)",
+            task.id
+        ),
+        Err(e) => warn!(
+            "Scheduler: failed to deliver output of task #{} to chat {target}: {e}",
+            task.id
+        ),
+    }
+}
 
-        // Run agent loop with the task prompt
-        let (success, result_summary) = match process_with_agent(
-            state,
-            AgentRequestContext {
-                caller_channel: &routing.channel_name,
-                chat_id: task.chat_id,
-                chat_type: routing.conversation.as_agent_chat_type(),
-            },
-            Some(&task.prompt),
-            None,
-        )
-        .await
-        {
-            Ok(response) => {
-                if !response.is_empty() {
-                    let bot_username = state.config.bot_username_for_channel(&routing.channel_name);
-                    let _ = deliver_and_store_bot_message(
-                        &state.channel_registry,
-                        state.db.clone(),
-                        &bot_username,
-                        task.chat_id,
-                        &response,
-                    )
-                    .await;
-                }
-                let summary = if response.len() > 200 {
-                    format!("{}...", &response[..floor_char_boundary(&response, 200)])
-                } else {
-                    response
-                };
-                (true, Some(summary))
-            }
-            Err(e) => {
-                error!("Scheduler: task #{} failed: {e}", task.id);
-                let err_text = format!("Scheduled task #{} failed: {e}", task.id);
-                let bot_username = state.config.bot_username_for_channel(&routing.channel_name);
-                let _ = deliver_and_store_bot_message(
-                    &state.channel_registry,
-                    state.db.clone(),
-                    &bot_username,
-                    task.chat_id,
-                    &err_text,
-                )
-                .await;
-                (false, Some(format!("Error: {e}")))
-            }
+/// Stop every scheduled task from firing until [`resume_scheduler`]. The flag
+/// lives in the database, so it survives restarts. Returns `false` if the
+/// scheduler was already paused.
+pub fn pause_scheduler(db: &Database) -> Result<bool, MicroClawError> {
+    db.set_scheduler_paused_at(Some(&Utc::now().to_rfc3339()))
+}
+
+/// Clear the global pause. Cron tasks that came due while paused skip ahead to
+/// their next occurrence instead of firing the missed runs; overdue one-shot
+/// tasks still run once. Returns the number of rescheduled cron tasks, or
+/// `None` if the scheduler was not paused.
+pub fn resume_scheduler(db: &Database, default_tz: &str) -> Result<Option<usize>, MicroClawError> {
+    if db.scheduler_paused_at()?.is_none() {
+        return Ok(None);
+    }
+    let now = Utc::now();
+    let default_tz: chrono_tz::Tz = default_tz.parse().unwrap_or(chrono_tz::Tz::UTC);
+    let mut rescheduled = 0;
+    for task in db.get_due_tasks(&now.to_rfc3339())? {
+        if task.schedule_type != "cron" {
+            continue;
+        }
+        let Ok(schedule) = cron::Schedule::from_str(&task.schedule_value) else {
+            continue;
         };
+        let tz = crate::tools::schedule::task_tz(&task).unwrap_or(default_tz);
+        if let Some(next) = crate::tools::schedule::next_cron_run_after(&schedule, tz, now) {
+            if db.requeue_scheduled_task(task.id, &next.to_rfc3339())? {
+                rescheduled += 1;
+            }
+        }
+    }
+    db.set_scheduler_paused_at(None)?;
+    Ok(Some(rescheduled))
+}
 
-        let finished_at = Utc::now();
-        let finished_at_str = finished_at.to_rfc3339();
-        let duration_ms = (finished_at - started_at).num_milliseconds();
+fn scheduler_pause_text(paused_at: Option<&str>) -> String {
+    match paused_at {
+        Some(ts) => format!("Scheduler: paused globally since {ts}; no task runs until resumed."),
+        None => "Scheduler: running.".to_string(),
+    }
+}
 
-        // Log the task run
-        let log_summary = result_summary.clone();
-        let started_for_log = started_at_str.clone();
-        let finished_for_log = finished_at_str.clone();
-        if let Err(e) = call_blocking(state.db.clone(), move |db| {
-            db.log_task_run(
-                task.id,
-                task.chat_id,
-                &started_for_log,
-                &finished_for_log,
-                duration_ms,
-                success,
-                log_summary.as_deref(),
-            )?;
-            Ok(())
-        })
-        .await
+/// One-line global scheduler state for `/scheduler` and the CLI.
+pub fn scheduler_status_text(db: &Database) -> Result<String, MicroClawError> {
+    Ok(scheduler_pause_text(db.scheduler_paused_at()?.as_deref()))
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "microclaw scheduler",
+    about = "Pause or resume all scheduled tasks",
+    disable_help_subcommand = true
+)]
+struct SchedulerCli {
+    #[command(subcommand)]
+    action: SchedulerAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum SchedulerAction {
+    /// Stop all scheduled tasks from firing (persists across restarts)
+    Pause,
+    /// Resume; cron tasks skip runs missed while paused
+    Resume,
+    /// Show whether the scheduler is paused
+    Status,
+}
+
+/// `microclaw scheduler pause|resume|status`. A running runtime picks the
+/// change up on its next tick.
+pub async fn handle_scheduler_cli(args: &[String]) -> anyhow::Result<()> {
+    let cli = match SchedulerCli::try_parse_from(
+        std::iter::once("scheduler").chain(args.iter().map(String::as_str)),
+    ) {
+        Ok(cli) => cli,
+        Err(err)
+            if matches!(
+                err.kind(),
+                clap::error::ErrorKind::DisplayHelp
+                    | clap::error::ErrorKind::DisplayVersion
+                    | clap::error::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+            ) =>
         {
-            error!("Scheduler: failed to log task run for #{}: {e}", task.id);
+            err.print()?;
+            return Ok(());
+        }
+        Err(err) => return Err(anyhow::anyhow!(err.to_string())),
+    };
+    let config = Config::load()?;
+    let db = config.open_database()?;
+    let message = match cli.action {
+        SchedulerAction::Pause => pause_response(pause_scheduler(&db)?),
+        SchedulerAction::Resume => resume_response(resume_scheduler(&db, &config.timezone)?),
+        SchedulerAction::Status => scheduler_status_text(&db)?,
+    };
+    println!("{message}");
+    Ok(())
+}
+
+pub fn pause_response(changed: bool) -> String {
+    if changed {
+        "Scheduler paused. No scheduled task will run until it is resumed.".to_string()
+    } else {
+        "Scheduler is already paused.".to_string()
+    }
+}
+
+pub fn resume_response(rescheduled: Option<usize>) -> String {
+    match rescheduled {
+        Some(0) => "Scheduler resumed.".to_string(),
+        Some(n) => format!(
+            "Scheduler resumed. {n} cron task(s) that came due while paused moved to their next run."
+        ),
+        None => "Scheduler is not paused.".to_string(),
+    }
+}
+
+/// Most runs a `run_all` task makes on startup, however many it missed.
+const MAX_CATCH_UP_RUNS: usize = 5;
+
+/// Runs an overdue task makes on startup under its missed-run policy; `0`
+/// means skip to the next occurrence. One-shot tasks always run once.
+fn catch_up_run_count(task: &ScheduledTask, tz: chrono_tz::Tz, now: DateTime<Utc>) -> usize {
+    if task.schedule_type != "cron" {
+        return 1;
+    }
+    match MissedRunPolicy::parse(&task.missed_run_policy).unwrap_or_default() {
+        MissedRunPolicy::Skip => 0,
+        MissedRunPolicy::RunOnceOnStartup => 1,
+        MissedRunPolicy::RunAll => {
+            let (Ok(schedule), Ok(first)) = (
+                cron::Schedule::from_str(&task.schedule_value),
+                DateTime::parse_from_rfc3339(&task.next_run),
+            ) else {
+                return 1;
+            };
+            // The stored next_run is the first missed fire time.
+            let mut missed = 1;
+            let mut last = first.with_timezone(&Utc);
+            while missed < MAX_CATCH_UP_RUNS {
+                match crate::tools::schedule::next_cron_run_after(&schedule, tz, last) {
+                    Some(next) if next <= now => {
+                        missed += 1;
+                        last = next;
+                    }
+                    _ => break,
+                }
+            }
+            missed
         }
+    }
+}
 
-        if !success {
-            let started_for_dlq = started_at_str.clone();
-            let finished_for_dlq = finished_at_str.clone();
-            let dlq_summary = result_summary.clone();
-            if let Err(e) = call_blocking(state.db.clone(), move |db| {
-                db.insert_scheduled_task_dlq(
+/// Apply each overdue task's missed-run policy before the first startup pass.
+/// `skip` tasks move to their next occurrence; the rest map to the number of
+/// catch-up runs [`run_due_tasks`] should make. Nothing is planned while the
+/// scheduler is paused, since [`resume_scheduler`] handles those runs.
+async fn plan_catch_up_runs(state: &Arc<AppState>) -> HashMap<i64, usize> {
+    let default_tz: chrono_tz::Tz = state.config.timezone.parse().unwrap_or(chrono_tz::Tz::UTC);
+    let planned = call_blocking(state.db.clone(), move |db| {
+        let mut runs = HashMap::new();
+        if db.scheduler_paused_at()?.is_some() {
+            return Ok(runs);
+        }
+        let now = Utc::now();
+        for task in db.get_due_tasks(&now.to_rfc3339())? {
+            let tz = crate::tools::schedule::task_tz(&task).unwrap_or(default_tz);
+            let count = catch_up_run_count(&task, tz, now);
+            if count > 0 {
+                runs.insert(task.id, count);
+                continue;
+            }
+            let Ok(schedule) = cron::Schedule::from_str(&task.schedule_value) else {
+                continue;
+            };
+            if let Some(next) = crate::tools::schedule::next_cron_run_after(&schedule, tz, now) {
+                db.requeue_scheduled_task(task.id, &next.to_rfc3339())?;
+                info!(
+                    "Scheduler: task #{} missed its run at {} while down; skipping to {}",
                     task.id,
-                    task.chat_id,
-                    &started_for_dlq,
-                    &finished_for_dlq,
-                    duration_ms,
-                    dlq_summary.as_deref(),
-                )?;
-                Ok(())
-            })
-            .await
-            {
-                error!(
-                    "Scheduler: failed to enqueue DLQ for task #{}: {e}",
-                    task.id
+                    task.next_run,
+                    next.to_rfc3339()
                 );
             }
         }
+        Ok(runs)
+    })
+    .await;
+    match planned {
+        Ok(runs) => {
+            if !runs.is_empty() {
+                info!(
+                    "Scheduler: catching up {} overdue task(s) ({} run(s))",
+                    runs.len(),
+                    runs.values().sum::<usize>()
+                );
+            }
+            runs
+        }
+        Err(e) => {
+            error!("Scheduler: failed to plan catch-up runs: {e}");
+            HashMap::new()
+        }
+    }
+}
 
-        // Compute next run
-        let tz: chrono_tz::Tz = state.config.timezone.parse().unwrap_or(chrono_tz::Tz::UTC);
+/// Run every due task. `catch_up` holds the tasks found overdue on startup and
+/// how many runs each makes; those runs are logged as catch-up runs.
+async fn run_due_tasks(state: &Arc<AppState>, catch_up: &HashMap<i64, usize>) {
+    // Quiet hours hold messages rather than tasks, so flush even while paused.
+    let flushed = flush_deferred_messages(&state.channel_registry, state.db.clone()).await;
+    if flushed > 0 {
+        info!("Scheduler: delivered {flushed} message(s) held during quiet hours");
+    }
+    match call_blocking(state.db.clone(), |db| db.scheduler_paused_at()).await {
+        Ok(Some(_)) => return,
+        Ok(None) => {}
+        Err(e) => error!("Scheduler: failed to read pause state: {e}"),
+    }
+    let now = Utc::now().to_rfc3339();
+    let tasks = match call_blocking(state.db.clone(), move |db| db.claim_due_tasks(&now, 200)).await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            error!("Scheduler: failed to query due tasks: {e}");
+            return;
+        }
+    };
+
+    for task in tasks {
+        let runs = catch_up.get(&task.id).copied();
+        let mut first_started_at = None;
+        for _ in 0..runs.unwrap_or(1) {
+            let started_at = execute_task_run(state, &task, runs.is_some()).await;
+            first_started_at.get_or_insert(started_at);
+        }
+        let started_at_str = first_started_at.unwrap_or_else(|| Utc::now().to_rfc3339());
+
+        // Compute next run in the zone the task was scheduled in; older tasks
+        // without one fall back to the global timezone.
+        let tz: chrono_tz::Tz = crate::tools::schedule::task_tz(&task)
+            .unwrap_or_else(|| state.config.timezone.parse().unwrap_or(chrono_tz::Tz::UTC));
         let next_run = if task.schedule_type == "cron" {
             match cron::Schedule::from_str(&task.schedule_value) {
-                Ok(schedule) => schedule
-                    .upcoming(tz)
-                    .next()
-                    .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339()),
+                Ok(schedule) => {
+                    crate::tools::schedule::next_cron_run_after(&schedule, tz, chrono::Utc::now())
+                        .map(|t| t.to_rfc3339())
+                }
                 Err(e) => {
                     error!("Scheduler: invalid cron for task #{}: {e}", task.id);
                     None
@@ -213,6 +376,121 @@ async fn run_due_tasks(state: &Arc<AppState>) {
     }
 }
 
+/// Run a claimed task's prompt once, deliver the output and log the run.
+/// Returns when the run started.
+async fn execute_task_run(state: &Arc<AppState>, task: &ScheduledTask, catch_up: bool) -> String {
+    info!(
+        "Scheduler: executing task #{} for chat {}{}",
+        task.id,
+        task.chat_id,
+        if catch_up { " (catch-up)" } else { "" }
+    );
+    let (task_id, chat_id) = (task.id, task.chat_id);
+
+    let started_at = Utc::now();
+    let started_at_str = started_at.to_rfc3339();
+    let routing = get_chat_routing(&state.channel_registry, state.db.clone(), task.chat_id)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| {
+            warn!(
+                "Scheduler: no chat routing found for chat {}, defaulting to telegram/private",
+                task.chat_id
+            );
+            ChatRouting {
+                channel_name: "telegram".to_string(),
+                conversation: ConversationKind::Private,
+            }
+        });
+
+    // Run agent loop with the task prompt
+    let (success, result_summary) = match process_with_agent(
+        state,
+        AgentRequestContext {
+            caller_channel: &routing.channel_name,
+            chat_id: task.chat_id,
+            chat_type: routing.conversation.as_agent_chat_type(),
+            sender_id: None,
+            sender_is_admin: false,
+        },
+        Some(&task.prompt),
+        None,
+    )
+    .await
+    {
+        Ok(response) => {
+            if !response.is_empty() {
+                deliver_task_output(state, task, &routing, true, &response).await;
+            }
+            let summary = if response.len() > 200 {
+                format!("{}...", &response[..floor_char_boundary(&response, 200)])
+            } else {
+                response
+            };
+            (true, Some(summary))
+        }
+        Err(e) => {
+            error!("Scheduler: task #{} failed: {e}", task.id);
+            let err_text = format!("Scheduled task #{} failed: {e}", task.id);
+            deliver_task_output(state, task, &routing, false, &err_text).await;
+            (false, Some(format!("Error: {e}")))
+        }
+    };
+
+    let finished_at = Utc::now();
+    let finished_at_str = finished_at.to_rfc3339();
+    let duration_ms = (finished_at - started_at).num_milliseconds();
+
+    // Log the task run
+    let log_summary = result_summary.clone();
+    let started_for_log = started_at_str.clone();
+    let finished_for_log = finished_at_str.clone();
+    if let Err(e) = call_blocking(state.db.clone(), move |db| {
+        db.log_task_run_with_catch_up(
+            task_id,
+            chat_id,
+            &started_for_log,
+            &finished_for_log,
+            duration_ms,
+            success,
+            log_summary.as_deref(),
+            catch_up,
+        )?;
+        Ok(())
+    })
+    .await
+    {
+        error!("Scheduler: failed to log task run for #{}: {e}", task.id);
+    }
+
+    if !success {
+        let started_for_dlq = started_at_str.clone();
+        let finished_for_dlq = finished_at_str.clone();
+        let dlq_summary = result_summary.clone();
+        if let Err(e) = call_blocking(state.db.clone(), move |db| {
+            db.insert_scheduled_task_dlq(
+                task_id,
+                chat_id,
+                &started_for_dlq,
+                &finished_for_dlq,
+                duration_ms,
+                dlq_summary.as_deref(),
+            )?;
+            Ok(())
+        })
+        .await
+        {
+            error!(
+                "Scheduler: failed to enqueue DLQ for task #{}: {e}",
+                task.id
+            );
+        }
+    }
+
+    started_at_str
+}
+
 const REFLECTOR_SYSTEM_PROMPT: &str = r#"You are a memory extraction specialist. Extract durable, factual information from conversations.
 
 Rules:
@@ -221,8 +499,8 @@ Rules:
 - Each memory < 100 characters, specific and concrete
 - Category must be exactly one of: PROFILE (user attributes/preferences), KNOWLEDGE (facts/expertise), EVENT (significant things that happened)
 - If a new memory updates or supersedes an existing one, add "supersedes_id": <id> to replace it
-- Output ONLY valid JSON array: [{"content":"...","category":"PROFILE","supersedes_id":null}]
-- If nothing worth remembering: []
+- Output ONLY a valid JSON object: {"memories":[{"content":"...","category":"PROFILE","supersedes_id":null}]}
+- If nothing worth remembering: {"memories":[]}
 
 CRITICAL — how to memorize bugs and problems:
 - NEVER describe broken behavior as a fact (e.g. "tool calls were broken", "agent typed tool calls as text"). This causes the agent to repeat the broken behavior in future sessions.
@@ -236,6 +514,58 @@ CRITICAL — how to memorize bugs and problems:
   GOOD: "TODO: strictly follow TOOLS.md rules for every tool call"
 - The memory should tell the agent HOW TO BEHAVE CORRECTLY, never describe the broken behavior."#;
 
+const REFLECTOR_SENDER_HINT: &str = "\n\nThis is a shared group chat. For a memory about one participant, add \"sender\": \"<name shown in brackets>\"; omit it for facts about the whole group.";
+
+/// The participant a reflector memory belongs to, if the model named one who
+/// actually spoke in the reflected window.
+fn reflector_memory_owner(
+    item: &serde_json::Value,
+    senders: &std::collections::HashSet<String>,
+) -> Option<String> {
+    item.get("sender")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| senders.contains(*s))
+        .map(str::to_string)
+}
+
+/// Accepts `{"memories": [...]}` (JSON mode) or a bare array, and tolerates
+/// prose around the JSON from models that ignore the format request.
+fn parse_reflector_output(text: &str) -> Result<Vec<serde_json::Value>, String> {
+    fn memories(value: serde_json::Value) -> Option<Vec<serde_json::Value>> {
+        match value {
+            serde_json::Value::Array(items) => Some(items),
+            serde_json::Value::Object(mut obj) => match obj.remove("memories") {
+                Some(serde_json::Value::Array(items)) => Some(items),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    if let Some(items) = serde_json::from_str(text.trim()).ok().and_then(memories) {
+        return Ok(items);
+    }
+    for (open, close) in [('{', '}'), ('[', ']')] {
+        let (Some(start), Some(end)) = (text.find(open), text.rfind(close)) else {
+            continue;
+        };
+        if start >= end {
+            continue;
+        }
+        match serde_json::from_str(&text[start..=end]) {
+            Ok(value) => {
+                if let Some(items) = memories(value) {
+                    return Ok(items);
+                }
+            }
+            Err(e) if open == '[' => return Err(e.to_string()),
+            Err(_) => {}
+        }
+    }
+    Err("no JSON memories found".to_string())
+}
+
 fn jaccard_similar(a: &str, b: &str, threshold: f64) -> bool {
     use std::collections::HashSet;
     let a_words: HashSet<&str> = a.split_whitespace().collect();
@@ -248,23 +578,6 @@ fn jaccard_similar(a: &str, b: &str, threshold: f64) -> bool {
     intersection as f64 / union as f64 >= threshold
 }
 
-fn should_merge_duplicate(
-    existing: &Memory,
-    incoming_content: &str,
-    incoming_category: &str,
-) -> bool {
-    if existing.is_archived {
-        return true;
-    }
-    if existing.content.eq_ignore_ascii_case(incoming_content) {
-        return false;
-    }
-    if incoming_category == "PROFILE" && existing.category != "PROFILE" {
-        return true;
-    }
-    incoming_content.len() > existing.content.len() + 8
-}
-
 fn is_corrective_action_item(content: &str) -> bool {
     let lower = content.to_ascii_lowercase();
     let trimmed = lower.trim();
@@ -296,32 +609,11 @@ fn should_skip_memory_poisoning_risk(content: &str) -> bool {
     looks_like_broken_behavior_fact(content) && !is_corrective_action_item(content)
 }
 
-#[cfg(feature = "sqlite-vec")]
-async fn upsert_memory_embedding(
-    state: &Arc<AppState>,
-    memory_id: i64,
-    content: &str,
-) -> Result<(), ()> {
-    let provider = match &state.embedding {
-        Some(p) => p,
-        None => return Ok(()),
-    };
-    let model_name = provider.model().to_string();
-    let embedding = provider.embed(content).await.map_err(|_| ())?;
-    call_blocking(state.db.clone(), move |db| {
-        db.upsert_memory_vec(memory_id, &embedding)?;
-        db.update_memory_embedding_model(memory_id, &model_name)?;
-        Ok(())
-    })
-    .await
-    .map_err(|_| ())
-}
-
 #[cfg(feature = "sqlite-vec")]
 async fn backfill_embeddings(state: &Arc<AppState>) {
-    if state.embedding.is_none() {
+    let Some(provider) = &state.embedding else {
         return;
-    }
+    };
     let pending = match call_blocking(state.db.clone(), move |db| {
         db.get_memories_without_embedding(None, 50)
     })
@@ -330,8 +622,23 @@ async fn backfill_embeddings(state: &Arc<AppState>) {
         Ok(rows) => rows,
         Err(_) => return,
     };
+    let mut ops = Vec::with_capacity(pending.len());
     for mem in pending {
-        let _ = upsert_memory_embedding(state, mem.id, &mem.content).await;
+        if let Ok(vector) = provider.embed(&mem.content).await {
+            ops.push(MemoryBatchOp::Embed {
+                id: mem.id,
+                embedding: MemoryEmbedding {
+                    vector,
+                    model: provider.model().to_string(),
+                },
+            });
+        }
+    }
+    if ops.is_empty() {
+        return;
+    }
+    if let Err(e) = call_blocking(state.db.clone(), move |db| db.write_memory_batch(&ops)).await {
+        warn!("Reflector: embedding backfill failed: {e}");
     }
 }
 
@@ -414,6 +721,22 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
     }
     let latest_message_ts = messages.last().map(|m| m.timestamp.clone());
 
+    // Per-user attribution only applies to group chats with isolation enabled.
+    let isolate_senders = state.config.group_user_isolation
+        && matches!(
+            get_chat_routing(&state.channel_registry, state.db.clone(), chat_id).await,
+            Ok(Some(routing)) if routing.conversation == ConversationKind::Group
+        );
+    let senders: std::collections::HashSet<String> = if isolate_senders {
+        messages
+            .iter()
+            .filter(|m| !m.is_from_bot)
+            .map(|m| m.sender_name.clone())
+            .collect()
+    } else {
+        std::collections::HashSet::new()
+    };
+
     // 3. Format conversation for the LLM
     let conversation = messages
         .iter()
@@ -441,17 +764,33 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
             .join("\n");
         format!("\n\nExisting memories (use supersedes_id to replace stale ones):\n{lines}")
     };
+    let sender_hint = if isolate_senders {
+        REFLECTOR_SENDER_HINT
+    } else {
+        ""
+    };
 
     // 5. Call LLM directly (no tools, no session)
     let user_msg = Message {
         role: "user".into(),
         content: MessageContent::Text(format!(
-            "Extract memories from this conversation (chat_id={chat_id}):{existing_hint}\n\nConversation:\n{conversation}"
+            "Extract memories from this conversation (chat_id={chat_id}):{sender_hint}{existing_hint}\n\nConversation:\n{conversation}"
         )),
     };
-    let response = match state
-        .llm
-        .send_message(REFLECTOR_SYSTEM_PROMPT, vec![user_msg], None)
+    let role_llm = state
+        .config
+        .model_roles
+        .reflector
+        .is_some()
+        .then(|| crate::llm::create_provider_for_role(&state.config, ModelRole::Reflector));
+    let llm = role_llm.as_deref().unwrap_or(state.llm.as_ref());
+    let response = match llm
+        .send_message_with_sampling(
+            REFLECTOR_SYSTEM_PROMPT,
+            vec![user_msg],
+            None,
+            &SamplingParams::json_object(),
+        )
         .await
     {
         Ok(r) => r,
@@ -493,58 +832,29 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
         .collect::<Vec<_>>()
         .join("");
 
-    // 7. Parse JSON array
-    let extracted: Vec<serde_json::Value> = match serde_json::from_str(text.trim()) {
+    // 7. Parse JSON
+    let extracted = match parse_reflector_output(&text) {
         Ok(v) => v,
-        Err(_) => {
-            let start = text.find('[').unwrap_or(0);
-            let end = text.rfind(']').map(|i| i + 1).unwrap_or(text.len());
-            if start >= end {
-                error!("Reflector: parse failed for chat {chat_id}: no JSON array found");
-                let finished_at = Utc::now().to_rfc3339();
-                let _ = call_blocking(state.db.clone(), move |db| {
-                    db.log_reflector_run(
-                        chat_id,
-                        &started_at,
-                        &finished_at,
-                        0,
-                        0,
-                        0,
-                        0,
-                        "none",
-                        false,
-                        Some("no JSON array found"),
-                    )
-                    .map(|_| ())
-                })
-                .await;
-                return;
-            }
-            match serde_json::from_str(&text[start..end]) {
-                Ok(v) => v,
-                Err(e) => {
-                    error!("Reflector: parse failed for chat {chat_id}: {e}");
-                    let finished_at = Utc::now().to_rfc3339();
-                    let error_msg = e.to_string();
-                    let _ = call_blocking(state.db.clone(), move |db| {
-                        db.log_reflector_run(
-                            chat_id,
-                            &started_at,
-                            &finished_at,
-                            0,
-                            0,
-                            0,
-                            0,
-                            "none",
-                            false,
-                            Some(&error_msg),
-                        )
-                        .map(|_| ())
-                    })
-                    .await;
-                    return;
-                }
-            }
+        Err(e) => {
+            error!("Reflector: parse failed for chat {chat_id}: {e}");
+            let finished_at = Utc::now().to_rfc3339();
+            let _ = call_blocking(state.db.clone(), move |db| {
+                db.log_reflector_run(
+                    chat_id,
+                    &started_at,
+                    &finished_at,
+                    0,
+                    0,
+                    0,
+                    0,
+                    "none",
+                    false,
+                    Some(&e),
+                )
+                .map(|_| ())
+            })
+            .await;
+            return;
         }
     };
 
@@ -558,7 +868,9 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
         return;
     }
 
-    // 8. Insert new memories or update superseded ones
+    // 8. Plan inserts and updates, then apply them as one batch so a failed
+    // write rolls the whole run back instead of leaving it half applied.
+    let mut ops: Vec<MemoryBatchOp> = Vec::new();
     let mut inserted = 0usize;
     let mut updated = 0usize;
     let mut skipped = 0usize;
@@ -570,15 +882,28 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
     };
     #[cfg(not(feature = "sqlite-vec"))]
     let dedup_method = "jaccard";
-    let mut seen_contents: Vec<(i64, String)> =
-        existing.iter().map(|m| (m.id, m.content.clone())).collect();
-    let existing_by_id: std::collections::HashMap<i64, &Memory> =
-        existing.iter().map(|m| (m.id, m)).collect();
-    let mut topic_latest: std::collections::HashMap<String, i64> = existing
+    // Dedup and topic conflicts never cross owners, so one member's fact cannot
+    // replace another's. A `None` id marks a memory planned earlier in this run.
+    let mut seen_contents: Vec<(Option<i64>, Option<String>, String)> = existing
         .iter()
-        .filter(|m| !m.is_archived)
-        .map(|m| (memory_quality::memory_topic_key(&m.content), m.id))
+        .map(|m| (Some(m.id), m.sender_id.clone(), m.content.clone()))
         .collect();
+    let existing_by_id: std::collections::HashMap<i64, &Memory> =
+        existing.iter().map(|m| (m.id, m)).collect();
+    let mut topic_latest: std::collections::HashMap<(Option<String>, String), Option<i64>> =
+        existing
+            .iter()
+            .filter(|m| !m.is_archived)
+            .map(|m| {
+                (
+                    (
+                        m.sender_id.clone(),
+                        memory_quality::memory_topic_key(&m.content),
+                    ),
+                    Some(m.id),
+                )
+            })
+            .collect();
     for item in &extracted {
         let content = match item.get("content").and_then(|v| v.as_str()) {
             Some(s) => s,
@@ -603,151 +928,136 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
         if !memory_quality::memory_quality_ok(&content) {
             continue;
         }
+        let owner = reflector_memory_owner(item, &senders);
 
         // If the LLM flagged an existing memory to supersede, update it
         let supersedes_id = item.get("supersedes_id").and_then(|v| v.as_i64());
         if let Some(sid) = supersedes_id {
-            if existing.iter().any(|m| m.id == sid) {
-                let content = content.to_string();
-                let category = category.to_string();
-                let db_content = content.clone();
-                if state
-                    .memory_backend
-                    .update_memory_with_metadata(sid, &db_content, &category, 0.78, "reflector")
-                    .await
-                    .is_ok()
-                {
-                    updated += 1;
-                    #[cfg(feature = "sqlite-vec")]
-                    {
-                        let _ = upsert_memory_embedding(state, sid, &content).await;
-                    }
-                    seen_contents.push((sid, content));
-                }
+            if existing
+                .iter()
+                .any(|m| m.id == sid && (!isolate_senders || m.sender_id == owner))
+            {
+                ops.push(MemoryBatchOp::Update {
+                    id: sid,
+                    content: content.clone(),
+                    category: category.clone(),
+                    confidence: 0.78,
+                    source: "reflector".into(),
+                    embedding: None,
+                });
+                updated += 1;
+                seen_contents.push((Some(sid), owner, content));
                 continue;
             }
         }
 
-        let topic_key = memory_quality::memory_topic_key(&content);
-        if let Some(prev_id) = topic_latest.get(&topic_key).copied() {
+        let topic_key = (owner.clone(), memory_quality::memory_topic_key(&content));
+        if let Some(Some(prev_id)) = topic_latest.get(&topic_key).copied() {
             if let Some(prev) = existing_by_id.get(&prev_id) {
                 if !prev.content.eq_ignore_ascii_case(&content)
                     && !jaccard_similar(&prev.content, &content, 0.85)
                 {
-                    let new_content = content.to_string();
-                    let new_category = category.to_string();
-                    if let Ok(new_id) = state
-                        .memory_backend
-                        .supersede_memory(
-                            prev_id,
-                            &new_content,
-                            &new_category,
-                            "reflector_conflict",
-                            0.74,
-                            Some("topic_conflict"),
-                        )
-                        .await
-                    {
-                        updated += 1;
-                        #[cfg(feature = "sqlite-vec")]
-                        {
-                            let _ = upsert_memory_embedding(state, new_id, &content).await;
-                        }
-                        topic_latest.insert(topic_key, new_id);
-                        seen_contents.push((new_id, content));
-                        continue;
-                    }
+                    ops.push(MemoryBatchOp::Supersede {
+                        from_memory_id: prev_id,
+                        content: content.clone(),
+                        category: category.clone(),
+                        source: "reflector_conflict".into(),
+                        confidence: 0.74,
+                        reason: Some("topic_conflict".into()),
+                        embedding: None,
+                    });
+                    updated += 1;
+                    topic_latest.insert(topic_key, None);
+                    seen_contents.push((None, owner, content));
+                    continue;
                 }
             }
         }
 
-        // Dedup: semantic KNN when available, otherwise lexical Jaccard.
-        let duplicate_id = {
-            #[cfg(feature = "sqlite-vec")]
-            {
-                if let Some(provider) = &state.embedding {
-                    if let Ok(query_vec) = provider.embed(&content).await {
-                        let nearest = call_blocking(state.db.clone(), move |db| {
-                            db.knn_memories(chat_id, &query_vec, 1)
-                        })
-                        .await
-                        .ok()
-                        .and_then(|rows| rows.first().copied());
-                        nearest.and_then(|(id, dist)| if dist < 0.15 { Some(id) } else { None })
-                    } else {
-                        seen_contents
-                            .iter()
-                            .find(|(_, existing)| jaccard_similar(existing, &content, 0.5))
-                            .map(|(id, _)| *id)
-                    }
-                } else {
-                    seen_contents
-                        .iter()
-                        .find(|(_, existing)| jaccard_similar(existing, &content, 0.5))
-                        .map(|(id, _)| *id)
-                }
-            }
-            #[cfg(not(feature = "sqlite-vec"))]
-            {
-                seen_contents
-                    .iter()
-                    .find(|(_, existing)| jaccard_similar(existing, &content, 0.5))
-                    .map(|(id, _)| *id)
-            }
+        // Dedup: semantic KNN when available, otherwise lexical Jaccard. Memories
+        // planned in this run are not searchable yet, so they are always
+        // compared lexically.
+        let lexical_duplicate = |seen: &[(Option<i64>, Option<String>, String)],
+                                 pending_only: bool| {
+            seen.iter()
+                .find(|(id, existing_owner, existing)| {
+                    (!pending_only || id.is_none())
+                        && *existing_owner == owner
+                        && jaccard_similar(existing, &content, 0.5)
+                })
+                .map(|(id, _, _)| *id)
         };
-        if let Some(dup_id) = duplicate_id {
-            if let Some(existing_mem) = existing_by_id.get(&dup_id) {
-                if should_merge_duplicate(existing_mem, &content, &category) {
-                    let update_content = content.to_string();
-                    let update_category = category.to_string();
-                    if state
-                        .memory_backend
-                        .update_memory_with_metadata(
-                            dup_id,
-                            &update_content,
-                            &update_category,
-                            0.70,
-                            "reflector",
-                        )
-                        .await
-                        .is_ok()
-                    {
-                        updated += 1;
-                    } else {
-                        skipped += 1;
-                    }
-                } else {
-                    let _ = state
-                        .memory_backend
-                        .touch_memory_last_seen(dup_id, Some(0.55))
-                        .await;
+        let duplicate = match state
+            .memory_backend
+            .find_semantic_duplicate(Some(chat_id), owner.as_deref(), &content)
+            .await
+        {
+            Ok(found) => found
+                .map(|(memory, _)| Some(memory.id))
+                .or_else(|| lexical_duplicate(&seen_contents, true)),
+            Err(_) => lexical_duplicate(&seen_contents, false),
+        };
+        if let Some(dup_id) = duplicate {
+            match dup_id.and_then(|id| existing_by_id.get(&id)) {
+                Some(existing_mem) if should_merge_duplicate(existing_mem, &content, &category) => {
+                    ops.push(MemoryBatchOp::Update {
+                        id: existing_mem.id,
+                        content: content.clone(),
+                        category: category.clone(),
+                        confidence: 0.70,
+                        source: "reflector".into(),
+                        embedding: None,
+                    });
+                    updated += 1;
+                }
+                Some(existing_mem) => {
+                    ops.push(MemoryBatchOp::Touch {
+                        id: existing_mem.id,
+                        confidence_floor: Some(0.55),
+                    });
                     skipped += 1;
                 }
-            } else {
-                skipped += 1;
+                None => skipped += 1,
             }
             continue;
         }
 
-        let content = content.to_string();
-        let db_content = content.clone();
-        let category = category.to_string();
-        let inserted_id = state
-            .memory_backend
-            .insert_memory_with_metadata(Some(chat_id), &db_content, &category, "reflector", 0.68)
-            .await
-            .ok();
-        if let Some(memory_id) = inserted_id {
-            inserted += 1;
-            #[cfg(feature = "sqlite-vec")]
-            {
-                let _ = upsert_memory_embedding(state, memory_id, &content).await;
-            }
-            #[cfg(not(feature = "sqlite-vec"))]
-            let _ = memory_id;
-            seen_contents.push((memory_id, content));
-            topic_latest.insert(topic_key, memory_id);
-        }
+        ops.push(MemoryBatchOp::Insert {
+            chat_id: Some(chat_id),
+            content: content.clone(),
+            category: category.clone(),
+            source: "reflector".into(),
+            confidence: 0.68,
+            sender_id: owner.clone(),
+            embedding: None,
+        });
+        inserted += 1;
+        seen_contents.push((None, owner, content));
+        topic_latest.insert(topic_key, None);
+    }
+
+    if let Err(e) = state.memory_backend.write_batch(ops).await {
+        // The cursor stays put, so these messages are reflected again next run.
+        error!("Reflector: memory batch for chat {chat_id} rolled back: {e}");
+        let finished_at = Utc::now().to_rfc3339();
+        let error_msg = e.to_string();
+        let _ = call_blocking(state.db.clone(), move |db| {
+            db.log_reflector_run(
+                chat_id,
+                &started_at,
+                &finished_at,
+                extracted.len(),
+                0,
+                0,
+                0,
+                dedup_method,
+                true,
+                Some(&error_msg),
+            )
+            .map(|_| ())
+        })
+        .await;
+        return;
     }
 
     if let Some(ts) = latest_message_ts {
@@ -786,6 +1096,102 @@ async fn reflect_for_chat(state: &Arc<AppState>, chat_id: i64) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resume_scheduler_skips_missed_cron_runs() {
+        let dir = std::env::temp_dir().join(format!("mc_sched_pause_{}", uuid::Uuid::new_v4()));
+        let db = Database::new(dir.to_str().unwrap()).unwrap();
+        let cron_id = db
+            .create_scheduled_task(1, "hourly", "cron", "0 0 * * * *", "2024-01-01T00:00:00Z")
+            .unwrap();
+        let once_id = db
+            .create_scheduled_task(
+                1,
+                "once",
+                "once",
+                "2024-01-01T00:00:00Z",
+                "2024-01-01T00:00:00Z",
+            )
+            .unwrap();
+
+        assert_eq!(resume_scheduler(&db, "UTC").unwrap(), None);
+        assert!(pause_scheduler(&db).unwrap());
+        assert!(!pause_scheduler(&db).unwrap());
+        assert!(scheduler_status_text(&db).unwrap().contains("paused"));
+
+        assert_eq!(resume_scheduler(&db, "UTC").unwrap(), Some(1));
+        assert_eq!(scheduler_status_text(&db).unwrap(), "Scheduler: running.");
+        let next = db.get_task_by_id(cron_id).unwrap().unwrap().next_run;
+        assert!(chrono::DateTime::parse_from_rfc3339(&next).unwrap() > Utc::now());
+        let once = db.get_task_by_id(once_id).unwrap().unwrap();
+        assert_eq!(once.next_run, "2024-01-01T00:00:00Z");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_catch_up_run_count_follows_policy() {
+        let task = |schedule_type: &str, policy: &str| ScheduledTask {
+            id: 1,
+            chat_id: 1,
+            prompt: "check".into(),
+            schedule_type: schedule_type.into(),
+            schedule_value: "0 0 * * * *".into(),
+            next_run: "2024-01-01T00:00:00Z".into(),
+            last_run: None,
+            status: "active".into(),
+            created_at: "2024-01-01T00:00:00Z".into(),
+            timezone: None,
+            deliver: "channel".into(),
+            deliver_chat_id: None,
+            template: None,
+            missed_run_policy: policy.into(),
+        };
+        let tz = chrono_tz::Tz::UTC;
+        let now = DateTime::parse_from_rfc3339("2024-01-01T02:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(catch_up_run_count(&task("cron", "skip"), tz, now), 0);
+        assert_eq!(
+            catch_up_run_count(&task("cron", "run_once_on_startup"), tz, now),
+            1
+        );
+        // Missed 00:00, 01:00 and 02:00.
+        assert_eq!(catch_up_run_count(&task("cron", "run_all"), tz, now), 3);
+        let long_outage = now + chrono::Duration::days(1);
+        assert_eq!(
+            catch_up_run_count(&task("cron", "run_all"), tz, long_outage),
+            MAX_CATCH_UP_RUNS
+        );
+        assert_eq!(catch_up_run_count(&task("once", "skip"), tz, now), 1);
+    }
+
+    #[test]
+    fn test_parse_reflector_output_accepts_object_array_and_prose() {
+        let obj = parse_reflector_output(r#"{"memories":[{"content":"a"}]}"#).unwrap();
+        assert_eq!(obj.len(), 1);
+        let arr = parse_reflector_output(r#"[{"content":"a"},{"content":"b"}]"#).unwrap();
+        assert_eq!(arr.len(), 2);
+        let wrapped =
+            parse_reflector_output("Here you go:\n```json\n{\"memories\":[]}\n```").unwrap();
+        assert!(wrapped.is_empty());
+        assert!(parse_reflector_output("nothing to remember").is_err());
+    }
+
+    #[test]
+    fn test_reflector_memory_owner_requires_known_sender() {
+        let senders: std::collections::HashSet<String> =
+            ["ou_alice".to_string()].into_iter().collect();
+        let owned = serde_json::json!({"content": "likes tea", "sender": " ou_alice "});
+        assert_eq!(
+            reflector_memory_owner(&owned, &senders).as_deref(),
+            Some("ou_alice")
+        );
+        let unknown = serde_json::json!({"content": "likes tea", "sender": "ou_mallory"});
+        assert_eq!(reflector_memory_owner(&unknown, &senders), None);
+        let shared = serde_json::json!({"content": "team ships on friday"});
+        assert_eq!(reflector_memory_owner(&shared, &senders), None);
+    }
+
     #[test]
     fn test_jaccard_similar_identical() {
         assert!(jaccard_similar("hello world", "hello world", 0.5));
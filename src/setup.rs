@@ -28,15 +28,16 @@ use microclaw_core::error::MicroClawError;
 use microclaw_core::text::floor_char_boundary;
 
 use crate::channels::{
-    dingtalk, email, feishu,
+    dingtalk, email, feishu, webhook,
 };
 use crate::setup_def::DynamicChannelDef;
 
 // Declarative channel metadata is owned by each channel module.
-const DYNAMIC_CHANNELS: &[DynamicChannelDef] = &[
+pub(crate) const DYNAMIC_CHANNELS: &[DynamicChannelDef] = &[
     feishu::SETUP_DEF,
     email::SETUP_DEF,
     dingtalk::SETUP_DEF,
+    webhook::SETUP_DEF,
 ];
 
 /// Build the setup-wizard field key from channel name + yaml key.
@@ -514,6 +515,8 @@ struct SetupApp {
     completion_summary: Vec<String>,
     llm_override_page: Option<LlmOverridePage>,
     llm_override_picker: Option<LlmOverridePicker>,
+    /// Per-field errors from checking each field as the cursor leaves it.
+    field_errors: HashMap<String, String>,
 }
 
 #[derive(Clone)]
@@ -746,6 +749,7 @@ impl SetupApp {
             completion_summary: Vec::new(),
             llm_override_page: None,
             llm_override_picker: None,
+            field_errors: HashMap::new(),
         };
 
         // Generate fields for dynamic channels (feishu, email, dingtalk, etc.)
@@ -1361,6 +1365,61 @@ impl SetupApp {
         parse_bot_count(&self.field_value(&key), &key).unwrap_or(1)
     }
 
+    /// A bot slot counts as used once any of its channel fields is filled in.
+    fn dynamic_slot_in_use(&self, ch: &DynamicChannelDef, slot: usize) -> bool {
+        ch.fields.iter().any(|f| {
+            !self
+                .field_value(&dynamic_slot_field_key(ch.name, slot, f.yaml_key))
+                .is_empty()
+        })
+    }
+
+    /// Cross-slot checks for a used bot slot's account id: it must be set and no
+    /// other used slot of the channel may share it. Slot #1 holds the default account.
+    fn check_slot_account_id(
+        &self,
+        ch: &DynamicChannelDef,
+        slot: usize,
+    ) -> Result<(), MicroClawError> {
+        let id_key = dynamic_slot_id_field_key(ch.name, slot);
+        let id = self.field_value(&id_key);
+        if id.is_empty() {
+            return Err(MicroClawError::Config(format!(
+                "{id_key} is empty but {} bot slot #{slot} is configured; give it an account id",
+                ch.name
+            )));
+        }
+        for other in 1..=self.dynamic_bot_count(ch.name) {
+            if other == slot || !self.dynamic_slot_in_use(ch, other) {
+                continue;
+            }
+            let other_key = dynamic_slot_id_field_key(ch.name, other);
+            if self.field_value(&other_key) != id {
+                continue;
+            }
+            return Err(MicroClawError::Config(if slot == 1 || other == 1 {
+                let (key, default_key) = if slot == 1 {
+                    (other_key, id_key)
+                } else {
+                    (id_key, other_key)
+                };
+                format!(
+                    "{key} '{id}' collides with the {} default account ({default_key}); each bot needs its own id",
+                    ch.name
+                )
+            } else {
+                let (first, second) = (slot.min(other), slot.max(other));
+                format!(
+                    "{} '{id}' duplicates {}; {} bot slots #{first} and #{second} need different ids",
+                    dynamic_slot_id_field_key(ch.name, second),
+                    dynamic_slot_id_field_key(ch.name, first),
+                    ch.name
+                )
+            }));
+        }
+        Ok(())
+    }
+
     fn dynamic_field_channel(key: &str) -> Option<&'static str> {
         for ch in DYNAMIC_CHANNELS {
             if key == dynamic_bot_count_field_key(ch.name) {
@@ -1397,49 +1456,44 @@ impl SetupApp {
     }
 
     fn is_field_visible(&self, key: &str) -> bool {
-        match key {
-            _ => {
-                if let Some(ch) = Self::dynamic_field_channel(key) {
-                    if !self.channel_enabled(ch) {
-                        return false;
-                    }
-                    if key == dynamic_account_id_field_key(ch)
-                        || key == dynamic_accounts_json_field_key(ch)
-                    {
-                        return false;
-                    }
-                    if key == dynamic_bot_count_field_key(ch) {
-                        return true;
+        if let Some(ch) = Self::dynamic_field_channel(key) {
+            if !self.channel_enabled(ch) {
+                return false;
+            }
+            if key == dynamic_account_id_field_key(ch) || key == dynamic_accounts_json_field_key(ch)
+            {
+                return false;
+            }
+            if key == dynamic_bot_count_field_key(ch) {
+                return true;
+            }
+            for slot in 1..=MAX_BOT_SLOTS {
+                if key == dynamic_slot_id_field_key(ch, slot)
+                    || key == dynamic_slot_enabled_field_key(ch, slot)
+                {
+                    return slot <= self.dynamic_bot_count(ch);
+                }
+                if key == dynamic_slot_llm_provider_key(ch, slot)
+                    || key == dynamic_slot_llm_api_key_key(ch, slot)
+                    || key == dynamic_slot_llm_base_url_key(ch, slot)
+                {
+                    return false;
+                }
+                for d in DYNAMIC_CHANNELS {
+                    if d.name != ch {
+                        continue;
                     }
-                    for slot in 1..=MAX_BOT_SLOTS {
-                        if key == dynamic_slot_id_field_key(ch, slot)
-                            || key == dynamic_slot_enabled_field_key(ch, slot)
-                        {
+                    for f in d.fields {
+                        if key == dynamic_slot_field_key(ch, slot, f.yaml_key) {
                             return slot <= self.dynamic_bot_count(ch);
                         }
-                        if key == dynamic_slot_llm_provider_key(ch, slot)
-                            || key == dynamic_slot_llm_api_key_key(ch, slot)
-                            || key == dynamic_slot_llm_base_url_key(ch, slot)
-                        {
-                            return false;
-                        }
-                        for d in DYNAMIC_CHANNELS {
-                            if d.name != ch {
-                                continue;
-                            }
-                            for f in d.fields {
-                                if key == dynamic_slot_field_key(ch, slot, f.yaml_key) {
-                                    return slot <= self.dynamic_bot_count(ch);
-                                }
-                            }
-                        }
                     }
-                    // Hide legacy single-account dynamic keys in setup UI.
-                    false
-                } else {
-                    true
                 }
             }
+            // Hide legacy single-account dynamic keys in setup UI.
+            false
+        } else {
+            true
         }
     }
 
@@ -1573,54 +1627,25 @@ impl SetupApp {
         for ch in DYNAMIC_CHANNELS {
             if self.channel_enabled(ch.name) {
                 let bot_count_key = dynamic_bot_count_field_key(ch.name);
+                self.validate_field(&dynamic_accounts_json_field_key(ch.name))?;
                 let bot_count = parse_bot_count(&self.field_value(&bot_count_key), &bot_count_key)?;
                 let mut seen_any = false;
                 for slot in 1..=bot_count {
                     let id_key = dynamic_slot_id_field_key(ch.name, slot);
-                    let id_raw = self.field_value(&id_key);
-                    let has_any = ch.fields.iter().any(|f| {
-                        !self
-                            .field_value(&dynamic_slot_field_key(ch.name, slot, f.yaml_key))
-                            .is_empty()
-                    });
-                    if !has_any {
+                    if !self.dynamic_slot_in_use(ch, slot) {
                         continue;
                     }
                     seen_any = true;
-                    let account_id = account_id_from_value(&id_raw);
-                    if !is_valid_account_id(&account_id) {
-                        return Err(MicroClawError::Config(format!(
-                            "{} must use only letters, numbers, '_' or '-'",
-                            id_key
-                        )));
-                    }
-                    let enabled_key = dynamic_slot_enabled_field_key(ch.name, slot);
-                    let _ = parse_boolish(&self.field_value(&enabled_key), true).map_err(|_| {
-                        MicroClawError::Config(format!(
-                            "{} must be true/false (or 1/0)",
-                            enabled_key
-                        ))
-                    })?;
+                    self.validate_field(&id_key)?;
+                    self.check_slot_account_id(ch, slot)?;
+                    self.validate_field(&dynamic_slot_enabled_field_key(ch.name, slot))?;
                     if ch.name == "feishu" {
-                        let mut topic_mode = false;
                         for yaml_key in ["topic_mode", "show_progress"] {
-                            let field_key = dynamic_slot_field_key(ch.name, slot, yaml_key);
-                            let field_raw = self.field_value(&field_key);
-                            let parsed = if field_raw.trim().is_empty() {
-                                false
-                            } else {
-                                parse_boolish(&field_raw, false).map_err(|_| {
-                                    MicroClawError::Config(format!(
-                                        "{} must be true/false (or 1/0)",
-                                        field_key
-                                    ))
-                                })?
-                            };
-                            if yaml_key == "topic_mode" {
-                                topic_mode = parsed;
-                            }
+                            self.validate_field(&dynamic_slot_field_key(ch.name, slot, yaml_key))?;
                         }
-                        if topic_mode {
+                        let topic_mode_raw =
+                            self.field_value(&dynamic_slot_field_key(ch.name, slot, "topic_mode"));
+                        if parse_boolish(&topic_mode_raw, false).unwrap_or(false) {
                             let domain_key = dynamic_slot_field_key(ch.name, slot, "domain");
                             let domain = self.field_value(&domain_key).trim().to_ascii_lowercase();
                             let domain = if domain.is_empty() {
@@ -1675,14 +1700,7 @@ impl SetupApp {
             }
         }
 
-        let timezone = self.field_value("TIMEZONE");
-        let tz = if timezone.is_empty() {
-            "UTC".to_string()
-        } else {
-            timezone
-        };
-        tz.parse::<chrono_tz::Tz>()
-            .map_err(|_| MicroClawError::Config(format!("Invalid TIMEZONE: {tz}")))?;
+        self.validate_field("TIMEZONE")?;
 
         let data_dir = self.field_value("DATA_DIR");
         let dir = if data_dir.is_empty() {
@@ -1703,58 +1721,124 @@ impl SetupApp {
         };
         fs::create_dir_all(&workdir)?;
 
-        let sandbox_enabled = self.field_value("SANDBOX_ENABLED");
-        if !sandbox_enabled.is_empty() {
-            let lower = sandbox_enabled.to_ascii_lowercase();
-            let valid = matches!(lower.as_str(), "true" | "false" | "1" | "0" | "yes" | "no");
-            if !valid {
-                return Err(MicroClawError::Config(
-                    "SANDBOX_ENABLED must be true/false (or 1/0)".into(),
-                ));
-            }
-        }
-        let high_risk_confirm = self.field_value("HIGH_RISK_TOOL_USER_CONFIRMATION_REQUIRED");
-        if !high_risk_confirm.is_empty() {
-            let lower = high_risk_confirm.to_ascii_lowercase();
-            let valid = matches!(lower.as_str(), "true" | "false" | "1" | "0" | "yes" | "no");
-            if !valid {
-                return Err(MicroClawError::Config(
-                    "HIGH_RISK_TOOL_USER_CONFIRMATION_REQUIRED must be true/false (or 1/0)".into(),
-                ));
-            }
+        for key in [
+            "SANDBOX_ENABLED",
+            "HIGH_RISK_TOOL_USER_CONFIRMATION_REQUIRED",
+            "MEMORY_TOKEN_BUDGET",
+            "EMBEDDING_DIM",
+        ] {
+            self.validate_field(key)?;
         }
 
-        let memory_token_budget_raw = self.field_value("MEMORY_TOKEN_BUDGET");
-        if !memory_token_budget_raw.is_empty() {
-            let memory_token_budget = memory_token_budget_raw.parse::<usize>().map_err(|_| {
-                MicroClawError::Config("MEMORY_TOKEN_BUDGET must be a positive integer".into())
-            })?;
-            if memory_token_budget == 0 {
-                return Err(MicroClawError::Config(
-                    "MEMORY_TOKEN_BUDGET must be greater than 0".into(),
-                ));
+        Ok(())
+    }
+
+    /// Format checks for a single field that don't depend on other fields or touch the
+    /// filesystem; run by `validate_local` and whenever the cursor leaves a field.
+    fn validate_field(&self, key: &str) -> Result<(), MicroClawError> {
+        let value = self.field_value(key);
+        match key {
+            "TIMEZONE" => {
+                let tz = if value.is_empty() { "UTC" } else { &value };
+                tz.parse::<chrono_tz::Tz>()
+                    .map_err(|_| MicroClawError::Config(format!("Invalid TIMEZONE: {tz}")))?;
+            }
+            "SANDBOX_ENABLED" | "HIGH_RISK_TOOL_USER_CONFIRMATION_REQUIRED" => {
+                if !value.is_empty() {
+                    let lower = value.to_ascii_lowercase();
+                    let valid =
+                        matches!(lower.as_str(), "true" | "false" | "1" | "0" | "yes" | "no");
+                    if !valid {
+                        return Err(MicroClawError::Config(format!(
+                            "{key} must be true/false (or 1/0)"
+                        )));
+                    }
+                }
+            }
+            "MEMORY_TOKEN_BUDGET" | "EMBEDDING_DIM" => {
+                if !value.is_empty() {
+                    let parsed = value.parse::<usize>().map_err(|_| {
+                        MicroClawError::Config(format!("{key} must be a positive integer"))
+                    })?;
+                    if parsed == 0 {
+                        return Err(MicroClawError::Config(format!(
+                            "{key} must be greater than 0"
+                        )));
+                    }
+                }
+            }
+            _ => {
+                let Some(channel) = Self::dynamic_field_channel(key) else {
+                    return Ok(());
+                };
+                if key == dynamic_bot_count_field_key(channel) {
+                    parse_bot_count(&value, key)?;
+                } else if key == dynamic_accounts_json_field_key(channel) {
+                    if !value.is_empty() {
+                        let parsed: serde_json::Value =
+                            serde_json::from_str(&value).map_err(|e| {
+                                MicroClawError::Config(format!("{key} is not valid JSON: {e}"))
+                            })?;
+                        if !parsed.is_object() {
+                            return Err(MicroClawError::Config(format!(
+                                "{key} must be a JSON object keyed by account id"
+                            )));
+                        }
+                    }
+                } else {
+                    for slot in 1..=MAX_BOT_SLOTS {
+                        if key == dynamic_slot_id_field_key(channel, slot) {
+                            if !is_valid_account_id(&account_id_from_value(&value)) {
+                                return Err(MicroClawError::Config(format!(
+                                    "{key} must use only letters, numbers, '_' or '-'"
+                                )));
+                            }
+                            let ch = DYNAMIC_CHANNELS.iter().find(|ch| ch.name == channel);
+                            if let Some(ch) = ch.filter(|ch| {
+                                slot <= self.dynamic_bot_count(channel)
+                                    && self.dynamic_slot_in_use(ch, slot)
+                            }) {
+                                self.check_slot_account_id(ch, slot)?;
+                            }
+                        } else if key == dynamic_slot_enabled_field_key(channel, slot)
+                            || ["topic_mode", "show_progress"].iter().any(|yaml_key| {
+                                dynamic_field_is_bool(channel, yaml_key)
+                                    && key == dynamic_slot_field_key(channel, slot, yaml_key)
+                            })
+                        {
+                            parse_boolish(&value, false).map_err(|_| {
+                                MicroClawError::Config(format!("{key} must be true/false (or 1/0)"))
+                            })?;
+                        }
+                    }
+                }
             }
         }
+        Ok(())
+    }
 
-        let embedding_dim_raw = self.field_value("EMBEDDING_DIM");
-        if !embedding_dim_raw.is_empty() {
-            let embedding_dim = embedding_dim_raw.parse::<usize>().map_err(|_| {
-                MicroClawError::Config("EMBEDDING_DIM must be a positive integer".into())
-            })?;
-            if embedding_dim == 0 {
-                return Err(MicroClawError::Config(
-                    "EMBEDDING_DIM must be greater than 0".into(),
-                ));
+    /// Re-check the field at `index` after the cursor moves off it or an edit is committed.
+    /// Marks the field invalid and reports the error in the status line, or clears the mark.
+    fn check_field_at(&mut self, index: usize) {
+        let Some(key) = self.fields.get(index).map(|f| f.key.clone()) else {
+            return;
+        };
+        match self.validate_field(&key) {
+            Ok(()) => {
+                self.field_errors.remove(&key);
+            }
+            Err(e) => {
+                self.status = format!("Validation failed: {e}");
+                self.field_errors.insert(key, e.to_string());
             }
         }
-
-        Ok(())
     }
 
     fn validate_online(&self) -> Result<Vec<String>, MicroClawError> {
         let tg_enabled = self.channel_enabled("telegram");
         let tg_token = self.field_value("TELEGRAM_BOT_TOKEN");
-        let env_username = self.field_value("BOT_USERNAME")
+        let env_username = self
+            .field_value("BOT_USERNAME")
             .trim_start_matches('@')
             .to_string();
         let provider = self.field_value("LLM_PROVIDER").to_lowercase();
@@ -2019,8 +2103,7 @@ impl SetupApp {
         match key {
             "ENABLED_CHANNELS" => "web".into(),
             "TELEGRAM_ACCOUNT_ID" | "DISCORD_ACCOUNT_ID" => default_account_id().to_string(),
-            "TELEGRAM_BOT_TOKEN"
-            | "LLM_API_KEY" => String::new(),
+            "TELEGRAM_BOT_TOKEN" | "LLM_API_KEY" => String::new(),
             "LLM_PROVIDER" => "anthropic".into(),
             "LLM_MODEL" => default_model_for_provider(&provider).into(),
             "LLM_BASE_URL" => find_provider_preset(&provider)
@@ -2076,6 +2159,7 @@ impl SetupApp {
             self.selected_field_mut().value.clear();
         }
         self.status = format!("Cleared {key}");
+        self.check_field_at(self.selected);
     }
 
     fn restore_selected_field_default(&mut self) {
@@ -2086,6 +2170,7 @@ impl SetupApp {
         } else {
             self.selected_field_mut().value = default.clone();
             self.status = format!("Restored {key} to default: {default}");
+            self.check_field_at(self.selected);
         }
     }
 
@@ -2493,7 +2578,7 @@ fn prune_old_config_backups(
             .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
         entries.push((modified, entry.path()));
     }
-    entries.sort_by(|a, b| b.0.cmp(&a.0));
+    entries.sort_by_key(|e| std::cmp::Reverse(e.0));
     for (_, path) in entries.into_iter().skip(keep_latest) {
         let _ = fs::remove_file(path);
     }
@@ -2943,11 +3028,15 @@ fn draw_ui(frame: &mut ratatui::Frame<'_>, app: &SetupApp) {
 
         let selected = i == app.selected;
         let is_required = app.is_field_required(f);
-        let label = if is_required {
+        let invalid = app.field_errors.contains_key(&f.key);
+        let mut label = if is_required {
             format!("{}  [required]", f.label)
         } else {
             f.label.to_string()
         };
+        if invalid {
+            label.push_str("  [invalid]");
+        }
         let value = if f.key == "LLM_PROVIDER" {
             provider_display(&f.value)
         } else if let Some(provider_key) = SetupApp::llm_provider_key_for_model_field(&f.key) {
@@ -2966,7 +3055,9 @@ fn draw_ui(frame: &mut ratatui::Frame<'_>, app: &SetupApp) {
             f.display_value(selected && app.editing)
         };
         let prefix = if selected { "▶" } else { " " };
-        let color = if selected {
+        let color = if invalid {
+            Color::LightRed
+        } else if selected {
             Color::Yellow
         } else {
             Color::White
@@ -2992,7 +3083,7 @@ fn draw_ui(frame: &mut ratatui::Frame<'_>, app: &SetupApp) {
     frame.render_widget(body, left_inner);
 
     let field = app.selected_field();
-    let help = Paragraph::new(vec![
+    let mut help_lines = vec![
         Line::from(vec![
             Span::styled("Key: ", Style::default().fg(Color::DarkGray)),
             Span::styled(field.key.clone(), Style::default().fg(Color::Magenta)),
@@ -3009,6 +3100,14 @@ fn draw_ui(frame: &mut ratatui::Frame<'_>, app: &SetupApp) {
             Span::styled("Editing: ", Style::default().fg(Color::DarkGray)),
             Span::raw(if app.editing { "active" } else { "idle" }),
         ]),
+    ];
+    if let Some(error) = app.field_errors.get(&field.key) {
+        help_lines.push(Line::from(vec![
+            Span::styled("Invalid: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(error.clone(), Style::default().fg(Color::LightRed)),
+        ]));
+    }
+    help_lines.extend([
         Line::from(""),
         Line::from(Span::styled(
             "Tips",
@@ -3030,13 +3129,14 @@ fn draw_ui(frame: &mut ratatui::Frame<'_>, app: &SetupApp) {
         Line::from("• F2: validate + online checks"),
         Line::from("• s / Ctrl+S: save with online validation"),
         Line::from("• Ctrl+Shift+S: save without online model validation"),
-    ])
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("Details / Help"),
-    )
-    .wrap(Wrap { trim: false });
+    ]);
+    let help = Paragraph::new(help_lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Details / Help"),
+        )
+        .wrap(Wrap { trim: false });
     frame.render_widget(help, body_chunks[1].inner(Margin::new(1, 0)));
 
     let (status_icon, status_color) =
@@ -3505,6 +3605,7 @@ fn run_wizard(mut terminal: DefaultTerminal) -> Result<bool, MicroClawError> {
                     KeyCode::Enter => {
                         app.editing = false;
                         app.status = format!("Updated {}", app.selected_field().key);
+                        app.check_field_at(app.selected);
                     }
                     KeyCode::Backspace => {
                         app.selected_field_mut().value.pop();
@@ -3523,6 +3624,7 @@ fn run_wizard(mut terminal: DefaultTerminal) -> Result<bool, MicroClawError> {
                 continue;
             }
 
+            let left = app.selected;
             match key.code {
                 KeyCode::Char('q') => return Ok(false),
                 KeyCode::Up => app.prev(),
@@ -3597,6 +3699,9 @@ fn run_wizard(mut terminal: DefaultTerminal) -> Result<bool, MicroClawError> {
                 }
                 _ => {}
             }
+            if app.selected != left {
+                app.check_field_at(left);
+            }
         }
     }
 }
@@ -3612,6 +3717,75 @@ pub fn run_setup_wizard() -> Result<bool, MicroClawError> {
     result
 }
 
+/// Env var prefix read by `setup --non-interactive`, e.g. `MICROCLAW_SETUP_LLM_API_KEY`.
+pub const NON_INTERACTIVE_ENV_PREFIX: &str = "MICROCLAW_SETUP_";
+
+/// Field values for `setup --non-interactive`: `MICROCLAW_SETUP_*` env vars,
+/// then `--set KEY=VALUE` pairs, so flags win over env.
+pub fn non_interactive_values(
+    env: impl IntoIterator<Item = (String, String)>,
+    set_args: &[String],
+) -> Result<Vec<(String, String)>, MicroClawError> {
+    let mut values: Vec<(String, String)> = env
+        .into_iter()
+        .filter_map(|(k, v)| {
+            k.strip_prefix(NON_INTERACTIVE_ENV_PREFIX)
+                .map(|key| (key.to_string(), v))
+        })
+        .collect();
+    values.sort();
+    for arg in set_args {
+        let Some((key, value)) = arg.split_once('=') else {
+            return Err(MicroClawError::Config(format!(
+                "--set expects KEY=VALUE, got '{arg}'"
+            )));
+        };
+        values.push((key.trim().to_string(), value.to_string()));
+    }
+    Ok(values)
+}
+
+/// Apply `values` over the current config (or defaults), validate them as the
+/// wizard would, and write `path`. Returns summary lines for the caller to print.
+pub fn run_non_interactive_setup(
+    path: &Path,
+    values: &[(String, String)],
+    validate_online: bool,
+) -> Result<Vec<String>, MicroClawError> {
+    let mut app = SetupApp::new();
+    for (key, _) in values {
+        let key = key.to_ascii_uppercase();
+        if !app.fields.iter().any(|f| f.key == key) {
+            return Err(MicroClawError::Config(format!(
+                "Unknown setup field '{key}' (e.g. ENABLED_CHANNELS, LLM_PROVIDER, LLM_API_KEY, LLM_MODEL, DATA_DIR, DYN_FEISHU_BOT1_APP_ID)"
+            )));
+        }
+    }
+    // The provider goes first so its default base URL and model apply unless
+    // those are set explicitly too.
+    if let Some((_, provider)) = values
+        .iter()
+        .rev()
+        .find(|(k, _)| k.eq_ignore_ascii_case("LLM_PROVIDER"))
+    {
+        app.set_provider(provider.trim());
+    }
+    for (key, value) in values {
+        app.set_field_value(&key.to_ascii_uppercase(), value.clone());
+    }
+
+    app.validate_local()?;
+    let mut summary = if validate_online {
+        app.validate_online()?
+    } else {
+        vec!["Online/model validation skipped (pass --validate-online to run it)".to_string()]
+    };
+    if let Some(backup) = save_config_yaml(path, &app.to_env_map())? {
+        summary.push(format!("Previous config backed up to {backup}"));
+    }
+    Ok(summary)
+}
+
 pub fn enable_sandbox_in_config() -> Result<String, MicroClawError> {
     let Some(path) = Config::resolve_config_path()? else {
         return Err(MicroClawError::Config(
@@ -3776,6 +3950,89 @@ sandbox:
         let _ = fs::remove_file(&yaml_path);
     }
 
+    #[test]
+    fn test_non_interactive_values_prefer_flags_over_env() {
+        let env = vec![
+            (
+                "MICROCLAW_SETUP_LLM_MODEL".to_string(),
+                "from-env".to_string(),
+            ),
+            ("MICROCLAW_SETUP_TIMEZONE".to_string(), "UTC".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        let values = non_interactive_values(env, &["LLM_MODEL=from-flag=x".to_string()]).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("LLM_MODEL".to_string(), "from-env".to_string()),
+                ("TIMEZONE".to_string(), "UTC".to_string()),
+                ("LLM_MODEL".to_string(), "from-flag=x".to_string()),
+            ]
+        );
+        assert!(non_interactive_values(Vec::new(), &["LLM_MODEL".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_run_non_interactive_setup_validates_and_writes_config() {
+        let _guard = env_lock();
+        let base = std::env::temp_dir().join(format!(
+            "microclaw_setup_non_interactive_{}",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let yaml_path = base.join("microclaw.config.yaml");
+        let dirs = vec![
+            (
+                "DATA_DIR".to_string(),
+                base.join("data").to_string_lossy().to_string(),
+            ),
+            (
+                "WORKING_DIR".to_string(),
+                base.join("work").to_string_lossy().to_string(),
+            ),
+        ];
+        let with = |extra: &[(&str, &str)]| {
+            let mut values = dirs.clone();
+            values.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+            values
+        };
+
+        let missing_key = run_non_interactive_setup(
+            &yaml_path,
+            &with(&[("LLM_PROVIDER", "openai"), ("LLM_API_KEY", "")]),
+            false,
+        )
+        .unwrap_err();
+        assert!(missing_key.to_string().contains("LLM_API_KEY is required"));
+        assert!(!yaml_path.exists());
+
+        let unknown = run_non_interactive_setup(&yaml_path, &with(&[("NOT_A_FIELD", "1")]), false)
+            .unwrap_err();
+        assert!(unknown
+            .to_string()
+            .contains("Unknown setup field 'NOT_A_FIELD'"));
+
+        let summary = run_non_interactive_setup(
+            &yaml_path,
+            &with(&[
+                ("LLM_PROVIDER", "openai"),
+                ("llm_api_key", "sk-test"),
+                ("ENABLED_CHANNELS", "web"),
+            ]),
+            false,
+        )
+        .unwrap();
+        assert!(summary[0].contains("validation skipped"));
+        let s = fs::read_to_string(&yaml_path).unwrap();
+        assert!(s.contains("llm_provider: \"openai\""));
+        assert!(s.contains("api_key: \"sk-test\""));
+        assert!(s.contains(&format!(
+            "model: \"{}\"",
+            default_model_for_provider("openai")
+        )));
+
+        let _ = fs::remove_dir_all(&base);
+    }
+
     #[test]
     fn test_channel_dependent_fields_are_hidden_until_enabled() {
         let mut app = SetupApp::new();
@@ -4157,4 +4414,93 @@ sandbox:
         assert!(app.editing);
         assert!(app.status.contains("manual input"));
     }
+
+    #[test]
+    fn test_leaving_field_flags_invalid_value_and_clears_after_fix() {
+        let mut app = SetupApp::new();
+        let tz_idx = app
+            .fields
+            .iter()
+            .position(|f| f.key == "TIMEZONE")
+            .expect("TIMEZONE field missing");
+        app.set_field_value("TIMEZONE", "Mars/Olympus".into());
+        app.check_field_at(tz_idx);
+        assert!(app.status.contains("Invalid TIMEZONE: Mars/Olympus"));
+        assert!(app.field_errors.contains_key("TIMEZONE"));
+
+        app.set_field_value("TIMEZONE", "Asia/Shanghai".into());
+        app.check_field_at(tz_idx);
+        assert!(!app.field_errors.contains_key("TIMEZONE"));
+    }
+
+    #[test]
+    fn test_validate_field_checks_bot_count_and_accounts_json() {
+        let mut app = SetupApp::new();
+        let bot_count_key = dynamic_bot_count_field_key("feishu");
+        let accounts_key = dynamic_accounts_json_field_key("feishu");
+
+        app.set_field_value(&bot_count_key, "11".into());
+        let err = app.validate_field(&bot_count_key).unwrap_err().to_string();
+        assert!(err.contains("between 1 and 10"), "{err}");
+        app.set_field_value(&bot_count_key, "2".into());
+        assert!(app.validate_field(&bot_count_key).is_ok());
+
+        app.set_field_value(&accounts_key, "{\"main\":".into());
+        let err = app.validate_field(&accounts_key).unwrap_err().to_string();
+        assert!(err.contains("not valid JSON"), "{err}");
+        app.set_field_value(&accounts_key, "[]".into());
+        assert!(app.validate_field(&accounts_key).is_err());
+        app.set_field_value(&accounts_key, "{\"main\":{}}".into());
+        assert!(app.validate_field(&accounts_key).is_ok());
+
+        let topic_key = dynamic_slot_field_key("feishu", 1, "topic_mode");
+        app.set_field_value(&topic_key, "maybe".into());
+        assert!(app.validate_field(&topic_key).is_err());
+    }
+
+    #[test]
+    fn test_validate_slot_account_ids_reports_duplicates_and_empty_ids() {
+        let mut app = SetupApp::new();
+        app.set_field_value("ENABLED_CHANNELS", "feishu".into());
+        app.set_field_value("LLM_API_KEY", "key".into());
+        app.set_field_value(&dynamic_bot_count_field_key("feishu"), "3".into());
+        for slot in 1..=3 {
+            app.set_field_value(
+                &dynamic_slot_field_key("feishu", slot, "app_id"),
+                format!("app_id_{slot}"),
+            );
+            app.set_field_value(
+                &dynamic_slot_field_key("feishu", slot, "app_secret"),
+                format!("app_secret_{slot}"),
+            );
+        }
+        let id_key = |slot| dynamic_slot_id_field_key("feishu", slot);
+        app.set_field_value(&id_key(1), "main".into());
+        app.set_field_value(&id_key(2), "ops".into());
+        app.set_field_value(&id_key(3), "ops".into());
+
+        let err = app.validate_local().unwrap_err().to_string();
+        assert!(err.contains("duplicates DYN_FEISHU_BOT2_ID"), "{err}");
+        assert!(err.contains("slots #2 and #3"), "{err}");
+        assert!(app.validate_field(&id_key(3)).is_err());
+
+        app.set_field_value(&id_key(3), "main".into());
+        let err = app.validate_field(&id_key(3)).unwrap_err().to_string();
+        assert!(
+            err.contains("collides with the feishu default account"),
+            "{err}"
+        );
+        assert!(err.contains("DYN_FEISHU_BOT1_ID"), "{err}");
+
+        app.set_field_value(&id_key(3), "".into());
+        let err = app.validate_local().unwrap_err().to_string();
+        assert!(err.contains("DYN_FEISHU_BOT3_ID is empty"), "{err}");
+
+        app.set_field_value(&id_key(3), "support".into());
+        assert!(app.validate_field(&id_key(3)).is_ok());
+
+        // Unused slots beyond the bot count don't take part in the check.
+        app.set_field_value(&id_key(4), "ops".into());
+        assert!(app.validate_field(&id_key(4)).is_ok());
+    }
 }
@@ -1,3 +1,8 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::config::Config;
+
 #[derive(Clone, Copy)]
 pub struct ChannelFieldDef {
     pub yaml_key: &'static str,
@@ -7,9 +12,22 @@ pub struct ChannelFieldDef {
     pub required: bool,
 }
 
+/// Outcome of one account's credential check: the bot identity the platform
+/// reported, or why the check failed.
+pub struct ChannelAccountCheck {
+    /// Runtime channel name, e.g. `feishu` or `feishu.bot2`.
+    pub channel_name: String,
+    pub result: Result<String, String>,
+}
+
+pub type ChannelValidationFuture = Pin<Box<dyn Future<Output = Vec<ChannelAccountCheck>> + Send>>;
+
 #[derive(Clone, Copy)]
 pub struct DynamicChannelDef {
     pub name: &'static str,
     pub presence_keys: &'static [&'static str],
     pub fields: &'static [ChannelFieldDef],
+    /// Lightweight identity call per enabled account for `microclaw doctor
+    /// channels`. `None` when the platform has nothing to check without sending.
+    pub validate: Option<fn(&Config) -> ChannelValidationFuture>,
 }
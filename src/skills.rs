@@ -1,6 +1,11 @@
+use microclaw_core::llm_types::ToolChoice;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
 
 #[derive(Debug, Clone)]
 pub struct SkillMetadata {
@@ -13,6 +18,18 @@ pub struct SkillMetadata {
     pub version: Option<String>,
     pub updated_at: Option<String>,
     pub env_file: Option<String>,
+    /// Oldest MicroClaw release the skill supports (inclusive).
+    pub min_microclaw_version: Option<String>,
+    /// Newest MicroClaw release the skill supports (inclusive).
+    pub max_microclaw_version: Option<String>,
+    /// Tool choice applied to the model call right after activation.
+    pub tool_choice: Option<ToolChoice>,
+    /// Model used while the skill is active.
+    pub model: Option<String>,
+    /// Tool iteration budget per turn while the skill is active.
+    pub max_tool_iterations: Option<usize>,
+    /// Reply length limit while the skill is active.
+    pub max_response_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +60,18 @@ struct SkillFrontmatter {
     #[serde(default)]
     env_file: Option<String>,
     #[serde(default)]
+    min_microclaw_version: Option<String>,
+    #[serde(default)]
+    max_microclaw_version: Option<String>,
+    #[serde(default)]
+    tool_choice: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    max_tool_iterations: Option<usize>,
+    #[serde(default)]
+    max_response_tokens: Option<u32>,
+    #[serde(default)]
     metadata: SkillFrontmatterMetadata,
 }
 
@@ -81,6 +110,10 @@ struct SkillCompatibility {
     os: Vec<String>,
     #[serde(default)]
     deps: Vec<String>,
+    #[serde(default)]
+    min_microclaw_version: Option<String>,
+    #[serde(default)]
+    max_microclaw_version: Option<String>,
 }
 
 pub struct SkillManager {
@@ -142,25 +175,22 @@ impl SkillManager {
             if !path.is_dir() {
                 continue;
             }
-            let skill_md = path.join("SKILL.md");
-            if !skill_md.exists() {
+            if !path.join("SKILL.md").exists() {
                 continue;
             }
-            if let Ok(content) = std::fs::read_to_string(&skill_md) {
-                if let Some((meta, _body)) = parse_skill_md(&content, &path) {
-                    match self.skill_is_available(&meta) {
-                        Ok(()) => statuses.push(SkillAvailability {
-                            meta,
-                            available: true,
-                            reason: None,
-                        }),
-                        Err(reason) => statuses.push(SkillAvailability {
-                            meta,
-                            available: false,
-                            reason: Some(reason),
-                        }),
-                    };
-                }
+            if let Some((meta, _body)) = read_skill_dir(&path) {
+                match self.skill_is_available(&meta) {
+                    Ok(()) => statuses.push(SkillAvailability {
+                        meta,
+                        available: true,
+                        reason: None,
+                    }),
+                    Err(reason) => statuses.push(SkillAvailability {
+                        meta,
+                        available: false,
+                        reason: Some(reason),
+                    }),
+                };
             }
         }
 
@@ -189,13 +219,8 @@ impl SkillManager {
                     "Skill '{name}' is currently unavailable: {reason}\nRun `microclaw skill available --all` for full diagnostics."
                 ));
             }
-            let skill_md = skill.meta.dir_path.join("SKILL.md");
-            if let Ok(content) = std::fs::read_to_string(&skill_md) {
-                if let Some((meta, body)) = parse_skill_md(&content, &skill.meta.dir_path) {
-                    return Ok((meta, body));
-                }
-            }
-            return Err(format!("Skill '{name}' exists but could not be loaded."));
+            return read_skill_dir(&skill.meta.dir_path)
+                .ok_or_else(|| format!("Skill '{name}' exists but could not be loaded."));
         }
 
         let available = self.discover_skills();
@@ -213,6 +238,17 @@ impl SkillManager {
     }
 
     fn skill_is_available(&self, skill: &SkillMetadata) -> Result<(), String> {
+        if let Some(reason) = crate::builtin_skills::microclaw_version_mismatch(
+            skill.min_microclaw_version.as_deref(),
+            skill.max_microclaw_version.as_deref(),
+            env!("CARGO_PKG_VERSION"),
+        ) {
+            return Err(format!(
+                "Skill '{}' is incompatible with this build: {reason}.",
+                skill.name
+            ));
+        }
+
         if !platform_allowed(&skill.platforms) {
             return Err(format!(
                 "Skill '{}' is not available on this platform (current: {}, supported: {}).",
@@ -323,12 +359,119 @@ impl SkillManager {
         output
     }
 
-    #[allow(dead_code)]
     pub fn skills_dir(&self) -> &PathBuf {
         &self.skills_dir
     }
 }
 
+/// Last successfully parsed version of each SKILL.md, so an edit that breaks the
+/// file keeps serving the previous instructions instead of dropping the skill.
+struct LastGoodSkill {
+    meta: SkillMetadata,
+    body: String,
+    /// Fingerprint of the broken content already warned about.
+    warned_fingerprint: Option<u64>,
+}
+
+fn last_good_skills() -> &'static Mutex<HashMap<PathBuf, LastGoodSkill>> {
+    static LAST_GOOD: OnceLock<Mutex<HashMap<PathBuf, LastGoodSkill>>> = OnceLock::new();
+    LAST_GOOD.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn content_fingerprint(content: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Read and parse `<dir>/SKILL.md`, falling back to its last good version
+/// (with a warning, once per broken revision) when it is unreadable or malformed.
+fn read_skill_dir(dir: &Path) -> Option<(SkillMetadata, String)> {
+    read_skill_dir_tracked(dir).map(|(skill, _fresh)| skill)
+}
+
+/// Like [`read_skill_dir`]; the flag is false when the last good version was served.
+fn read_skill_dir_tracked(dir: &Path) -> Option<((SkillMetadata, String), bool)> {
+    let skill_md = dir.join("SKILL.md");
+    let content = std::fs::read_to_string(&skill_md);
+    let parsed = content.as_ref().ok().and_then(|c| parse_skill_md(c, dir));
+    let mut cache = last_good_skills().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((meta, body)) = parsed {
+        cache.insert(
+            skill_md,
+            LastGoodSkill {
+                meta: meta.clone(),
+                body: body.clone(),
+                warned_fingerprint: None,
+            },
+        );
+        return Some(((meta, body), true));
+    }
+    let last_good = cache.get_mut(&skill_md)?;
+    let fingerprint = match &content {
+        Ok(c) => content_fingerprint(c),
+        Err(_) => 0,
+    };
+    if last_good.warned_fingerprint != Some(fingerprint) {
+        last_good.warned_fingerprint = Some(fingerprint);
+        warn!(
+            "Skill '{}': {} is unreadable or malformed; keeping the last good version",
+            last_good.meta.name,
+            skill_md.display()
+        );
+    }
+    Some(((last_good.meta.clone(), last_good.body.clone()), false))
+}
+
+/// How often [`spawn_skill_watcher`] checks SKILL.md files for changes.
+pub const SKILL_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+fn skill_md_mtimes(skills_dir: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut out = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(skills_dir) else {
+        return out;
+    };
+    for entry in entries.flatten() {
+        let skill_md = entry.path().join("SKILL.md");
+        if let Ok(modified) = std::fs::metadata(&skill_md).and_then(|m| m.modified()) {
+            out.insert(skill_md, modified);
+        }
+    }
+    out
+}
+
+/// Re-parse skills whose SKILL.md changed since the last poll, so edits are
+/// logged (and malformed ones warned about) as soon as they are saved.
+/// Activation reads from disk anyway; this only refreshes the last-good cache.
+pub fn spawn_skill_watcher(skills_dir: PathBuf) {
+    tokio::spawn(async move {
+        let mut known = skill_md_mtimes(&skills_dir);
+        for skill_md in known.keys() {
+            if let Some(dir) = skill_md.parent() {
+                read_skill_dir(dir);
+            }
+        }
+        let mut interval = tokio::time::interval(SKILL_WATCH_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let current = skill_md_mtimes(&skills_dir);
+            for (skill_md, modified) in &current {
+                if known.get(skill_md) == Some(modified) {
+                    continue;
+                }
+                let Some(dir) = skill_md.parent() else {
+                    continue;
+                };
+                if let Some(((meta, _), true)) = read_skill_dir_tracked(dir) {
+                    info!("Skill '{}' reloaded from {}", meta.name, skill_md.display());
+                }
+            }
+            known = current;
+        }
+    });
+}
+
 pub fn load_skill_env_vars(meta: &SkillMetadata) -> HashMap<String, String> {
     let env_file_name = match &meta.env_file {
         Some(f) => f.as_str(),
@@ -611,6 +754,23 @@ fn parse_skill_md(content: &str, dir_path: &std::path::Path) -> Option<(SkillMet
                 .env_file
                 .map(|s| s.trim().to_string())
                 .filter(|s| !s.is_empty()),
+            min_microclaw_version: fm
+                .min_microclaw_version
+                .or(fm.compatibility.min_microclaw_version)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            max_microclaw_version: fm
+                .max_microclaw_version
+                .or(fm.compatibility.max_microclaw_version)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            tool_choice: fm.tool_choice.as_deref().and_then(ToolChoice::parse),
+            model: fm
+                .model
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+            max_tool_iterations: fm.max_tool_iterations.filter(|n| *n > 0),
+            max_response_tokens: fm.max_response_tokens.filter(|n| *n > 0),
         },
         body,
     ))
@@ -639,9 +799,29 @@ Use this skill to convert documents.
         assert_eq!(meta.platforms, vec!["darwin", "linux"]);
         assert_eq!(meta.deps, vec!["pandoc"]);
         assert_eq!(meta.source, "local");
+        assert_eq!(meta.tool_choice, None);
         assert!(body.contains("Use this skill"));
     }
 
+    #[test]
+    fn test_parse_skill_md_tool_choice() {
+        let content = "---\nname: fact-check\ndescription: Check facts\ntool_choice: web_search\n---\nSearch first.\n";
+        let (meta, _) = parse_skill_md(content, Path::new("/tmp/skills/fact-check")).unwrap();
+        assert_eq!(
+            meta.tool_choice,
+            Some(ToolChoice::Tool("web_search".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_skill_md_runtime_overrides() {
+        let content = "---\nname: deep-research\ndescription: Research\nmodel: claude-opus-4-1\nmax_tool_iterations: 200\nmax_response_tokens: 0\n---\nDig.\n";
+        let (meta, _) = parse_skill_md(content, Path::new("/tmp/skills/deep-research")).unwrap();
+        assert_eq!(meta.model.as_deref(), Some("claude-opus-4-1"));
+        assert_eq!(meta.max_tool_iterations, Some(200));
+        assert_eq!(meta.max_response_tokens, None);
+    }
+
     #[test]
     fn test_parse_skill_md_compatibility_os() {
         let content = r#"---
@@ -866,6 +1046,82 @@ nope
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_malformed_edit_keeps_last_good_version() {
+        let dir = std::env::temp_dir().join(format!(
+            "microclaw_skills_reload_test_{}",
+            uuid::Uuid::new_v4()
+        ));
+        let skill_dir = dir.join("notes");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        let skill_md = skill_dir.join("SKILL.md");
+        std::fs::write(&skill_md, "---\nname: notes\ndescription: v1\n---\nfirst\n").unwrap();
+        let sm = SkillManager::from_skills_dir(dir.to_str().unwrap());
+        assert_eq!(sm.load_skill_checked("notes").unwrap().1, "first");
+        let mtimes = skill_md_mtimes(&dir);
+        assert!(mtimes.contains_key(&skill_md));
+
+        std::fs::write(
+            &skill_md,
+            "---\nname: notes\ndescription: v2\n---\nsecond\n",
+        )
+        .unwrap();
+        assert_eq!(sm.load_skill_checked("notes").unwrap().1, "second");
+
+        std::fs::write(&skill_md, "---\nname: [unterminated\n---\nbroken\n").unwrap();
+        let (meta, body) = sm.load_skill_checked("notes").unwrap();
+        assert_eq!((meta.description.as_str(), body.as_str()), ("v2", "second"));
+        assert_eq!(sm.discover_skills().len(), 1);
+        assert!(!read_skill_dir_tracked(&skill_dir).unwrap().1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_incompatible_version_is_listed_but_not_activatable() {
+        let dir = std::env::temp_dir().join(format!(
+            "microclaw_skills_version_test_{}",
+            uuid::Uuid::new_v4()
+        ));
+        let future = dir.join("future");
+        std::fs::create_dir_all(&future).unwrap();
+        std::fs::write(
+            future.join("SKILL.md"),
+            r#"---
+name: future
+description: Needs a newer build
+compatibility:
+  min_microclaw_version: "999.0.0"
+---
+later
+"#,
+        )
+        .unwrap();
+        let current = dir.join("current");
+        std::fs::create_dir_all(&current).unwrap();
+        std::fs::write(
+            current.join("SKILL.md"),
+            format!(
+                "---\nname: current\ndescription: ok\nmin_microclaw_version: \"{}\"\nmax_microclaw_version: \"999\"\n---\nnow\n",
+                env!("CARGO_PKG_VERSION")
+            ),
+        )
+        .unwrap();
+
+        let sm = SkillManager::from_skills_dir(dir.to_str().unwrap());
+        let names: Vec<String> = sm.discover_skills().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["current".to_string()]);
+
+        let text = sm.list_skills_formatted_all();
+        assert!(text.contains("Unavailable skills (1)"));
+        assert!(text.contains("requires MicroClaw >= 999.0.0"));
+
+        let err = sm.load_skill_checked("future").unwrap_err();
+        assert!(err.contains("currently unavailable"));
+        assert!(err.contains("requires MicroClaw >= 999.0.0"));
+        assert!(sm.load_skill_checked("current").is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_parse_dotenv_basic() {
         let content = "KEY1=value1\nKEY2=value2\n# comment\n\nKEY3=\"quoted value\"";
@@ -911,6 +1167,12 @@ nope
             version: None,
             updated_at: None,
             env_file: Some(".env".to_string()),
+            min_microclaw_version: None,
+            max_microclaw_version: None,
+            tool_choice: None,
+            model: None,
+            max_tool_iterations: None,
+            max_response_tokens: None,
         };
         let envs = load_skill_env_vars(&meta);
         assert_eq!(envs.get("OUTLINE_API_KEY").unwrap(), "test123");
@@ -933,6 +1195,12 @@ nope
             version: None,
             updated_at: None,
             env_file: None,
+            min_microclaw_version: None,
+            max_microclaw_version: None,
+            tool_choice: None,
+            model: None,
+            max_tool_iterations: None,
+            max_response_tokens: None,
         };
         let envs = load_skill_env_vars(&meta);
         assert!(envs.is_empty());
@@ -954,6 +1222,12 @@ nope
             version: None,
             updated_at: None,
             env_file: Some("../.env".to_string()),
+            min_microclaw_version: None,
+            max_microclaw_version: None,
+            tool_choice: None,
+            model: None,
+            max_tool_iterations: None,
+            max_response_tokens: None,
         };
         let envs = load_skill_env_vars(&meta);
         assert!(envs.is_empty());
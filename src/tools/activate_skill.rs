@@ -70,13 +70,28 @@ impl Tool for ActivateSkillTool {
                 result.push_str("\n## Instructions\n\n");
                 result.push_str(&body);
                 let env_vars = load_skill_env_vars(&meta);
-                let mut tool_result = ToolResult::success(result);
+                let mut metadata = serde_json::Map::new();
                 if !env_vars.is_empty() {
-                    tool_result = tool_result.with_metadata(json!({
-                        "skill_envs": env_vars,
-                    }));
+                    metadata.insert("skill_envs".into(), json!(env_vars));
+                }
+                if let Some(choice) = &meta.tool_choice {
+                    metadata.insert("tool_choice".into(), json!(choice.to_string()));
+                }
+                if let Some(model) = &meta.model {
+                    metadata.insert("model".into(), json!(model));
+                }
+                if let Some(n) = meta.max_tool_iterations {
+                    metadata.insert("max_tool_iterations".into(), json!(n));
+                }
+                if let Some(n) = meta.max_response_tokens {
+                    metadata.insert("max_response_tokens".into(), json!(n));
+                }
+                let tool_result = ToolResult::success(result);
+                if metadata.is_empty() {
+                    tool_result
+                } else {
+                    tool_result.with_metadata(serde_json::Value::Object(metadata))
                 }
-                tool_result
             }
             Err(e) => ToolResult::error(format!(
                 "{}\nHint: Run `/skills` to list available skills, or `microclaw skill available --all` for unavailable-skill diagnostics.",
@@ -147,6 +162,41 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_activate_skill_reports_tool_choice() {
+        let dir = test_dir();
+        let skill_dir = dir.join("fact-check");
+        std::fs::create_dir_all(&skill_dir).unwrap();
+        std::fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\nname: fact-check\ndescription: Check facts\ntool_choice: required\n---\nSearch first.\n",
+        )
+        .unwrap();
+
+        let tool = ActivateSkillTool::new(dir.to_str().unwrap());
+        let result = tool.execute(json!({"skill_name": "fact-check"})).await;
+        assert!(!result.is_error);
+        assert_eq!(result.metadata.unwrap()["tool_choice"], "required");
+
+        let heavy_dir = dir.join("deep-research");
+        std::fs::create_dir_all(&heavy_dir).unwrap();
+        std::fs::write(
+            heavy_dir.join("SKILL.md"),
+            "---\nname: deep-research\ndescription: Research\nmodel: gpt-5\nmax_tool_iterations: 200\n---\nDig.\n",
+        )
+        .unwrap();
+        let heavy = tool.execute(json!({"skill_name": "deep-research"})).await;
+        let metadata = heavy.metadata.unwrap();
+        assert_eq!(metadata["model"], "gpt-5");
+        assert_eq!(metadata["max_tool_iterations"], 200);
+        assert!(metadata.get("max_response_tokens").is_none());
+
+        create_skill(&dir, "pdf", "Convert to PDF", "Instructions");
+        let plain = tool.execute(json!({"skill_name": "pdf"})).await;
+        assert!(plain.metadata.is_none());
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_activate_skill_not_found() {
         let dir = test_dir();
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{auth_context_from_input, schema_object, Tool, ToolResult};
+use microclaw_channels::channel::{deliver_and_store_bot_message, get_required_chat_routing};
+use microclaw_channels::channel_adapter::ChannelRegistry;
+use microclaw_core::llm_types::ToolDefinition;
+use microclaw_storage::db::Database;
+
+pub const ASK_USER_TOOL_NAME: &str = "ask_user";
+
+/// Metadata flag telling the agent loop to end the turn and wait for a reply.
+pub const AWAITING_USER_INPUT_KEY: &str = "awaiting_user_input";
+
+const MAX_OPTIONS: usize = 10;
+
+pub struct AskUserTool {
+    registry: Arc<ChannelRegistry>,
+    db: Arc<Database>,
+    default_bot_username: String,
+    channel_bot_usernames: HashMap<String, String>,
+}
+
+impl AskUserTool {
+    pub fn new(
+        registry: Arc<ChannelRegistry>,
+        db: Arc<Database>,
+        default_bot_username: String,
+        channel_bot_usernames: HashMap<String, String>,
+    ) -> Self {
+        AskUserTool {
+            registry,
+            db,
+            default_bot_username,
+            channel_bot_usernames,
+        }
+    }
+
+    async fn bot_username_for_chat(&self, chat_id: i64) -> String {
+        match get_required_chat_routing(&self.registry, self.db.clone(), chat_id).await {
+            Ok(routing) => self
+                .channel_bot_usernames
+                .get(&routing.channel_name)
+                .cloned()
+                .unwrap_or_else(|| self.default_bot_username.clone()),
+            Err(_) => self.default_bot_username.clone(),
+        }
+    }
+}
+
+fn parse_options(input: &serde_json::Value) -> Vec<String> {
+    let mut options: Vec<String> = Vec::new();
+    for option in input
+        .get("options")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        if options.len() < MAX_OPTIONS && !options.iter().any(|o| o == option) {
+            options.push(option.to_string());
+        }
+    }
+    options
+}
+
+/// The message the user sees: the question, then any suggested answers numbered.
+fn format_question(question: &str, options: &[String]) -> String {
+    let mut text = question.to_string();
+    if !options.is_empty() {
+        text.push('\n');
+        for (i, option) in options.iter().enumerate() {
+            text.push_str(&format!("\n{}. {option}", i + 1));
+        }
+    }
+    text
+}
+
+#[async_trait]
+impl Tool for AskUserTool {
+    fn name(&self) -> &str {
+        ASK_USER_TOOL_NAME
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: ASK_USER_TOOL_NAME.into(),
+            description: "Ask the user in the current chat a clarifying question and pause until they reply. Use it only when you cannot proceed without their answer; do not call other tools in the same step. The turn ends after the question is sent and resumes with the user's reply as the next message.".into(),
+            input_schema: schema_object(
+                json!({
+                    "question": {
+                        "type": "string",
+                        "description": "The question to ask"
+                    },
+                    "options": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Optional suggested answers, shown as a numbered list (max 10)"
+                    }
+                }),
+                &["question"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let question = match input
+            .get("question")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+        {
+            Some(q) if !q.is_empty() => q.to_string(),
+            _ => return ToolResult::error("Missing required parameter: question".into()),
+        };
+        let auth = match auth_context_from_input(&input) {
+            Some(auth) => auth,
+            None => return ToolResult::error("ask_user needs a chat to ask in".into()),
+        };
+        if !auth.is_user_turn() {
+            return ToolResult::error(
+                "ask_user is unavailable in scheduled runs: no user is waiting to answer. Decide with the information you have.".into(),
+            );
+        }
+        let chat_id = auth.caller_chat_id;
+        let options = parse_options(&input);
+        let text = format_question(&question, &options);
+
+        let bot_username = self.bot_username_for_chat(chat_id).await;
+        match deliver_and_store_bot_message(
+            &self.registry,
+            self.db.clone(),
+            &bot_username,
+            chat_id,
+            &text,
+        )
+        .await
+        {
+            Ok(()) => {
+                info!("ask_user question sent: chat_id={}", chat_id);
+                ToolResult::success(
+                    "Question sent. The turn is paused; the user's reply will arrive as the next message.".into(),
+                )
+                .with_metadata(json!({
+                    AWAITING_USER_INPUT_KEY: true,
+                    "question": question,
+                    "options": options,
+                }))
+            }
+            Err(e) => {
+                warn!("ask_user delivery failed: chat_id={}, error={}", chat_id, e);
+                ToolResult::error(format!("Failed to send question: {e}"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use microclaw_channels::channel::ConversationKind;
+    use microclaw_channels::channel_adapter::ChannelAdapter;
+
+    fn test_db() -> (Arc<Database>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("microclaw_askuser_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        (db, dir)
+    }
+
+    fn cleanup(dir: &std::path::Path) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    struct RecordingAdapter {
+        sent: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChannelAdapter for RecordingAdapter {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
+            vec![("mock_private", ConversationKind::Private)]
+        }
+
+        async fn send_text(&self, _external_chat_id: &str, text: &str) -> Result<(), String> {
+            self.sent.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    fn tool(db: Arc<Database>, sent: Arc<std::sync::Mutex<Vec<String>>>) -> AskUserTool {
+        let mut registry = ChannelRegistry::new();
+        registry.register(Arc::new(RecordingAdapter { sent }));
+        AskUserTool::new(Arc::new(registry), db, "bot".into(), HashMap::new())
+    }
+
+    #[test]
+    fn test_format_question_numbers_options() {
+        let options = parse_options(&json!({"options": [" staging ", "prod", "prod", ""]}));
+        assert_eq!(options, vec!["staging", "prod"]);
+        assert_eq!(
+            format_question("Deploy where?", &options),
+            "Deploy where?\n\n1. staging\n2. prod"
+        );
+        assert_eq!(format_question("Why?", &[]), "Why?");
+    }
+
+    #[tokio::test]
+    async fn test_ask_user_sends_question_to_caller_chat() {
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("mock", "ext-1", Some("alice"), "mock_private")
+            .unwrap();
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let result = tool(db.clone(), sent.clone())
+            .execute(json!({
+                "question": "Which region?",
+                "options": ["eu", "us"],
+                "__microclaw_auth": {
+                    "caller_chat_id": chat_id,
+                    "caller_sender_id": "alice",
+                    "control_chat_ids": []
+                }
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        let metadata = result.metadata.unwrap();
+        assert_eq!(metadata[AWAITING_USER_INPUT_KEY], true);
+        assert_eq!(metadata["options"], json!(["eu", "us"]));
+        assert_eq!(
+            sent.lock().unwrap().as_slice(),
+            ["Which region?\n\n1. eu\n2. us"]
+        );
+        let stored = db.get_all_messages(chat_id).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].is_from_bot);
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_ask_user_rejects_scheduled_runs_and_missing_question() {
+        let (db, dir) = test_db();
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let tool = tool(db, sent.clone());
+
+        let result = tool
+            .execute(json!({
+                "question": "Still there?",
+                "__microclaw_auth": {"caller_chat_id": 1, "control_chat_ids": []}
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("unavailable in scheduled runs"));
+
+        let result = tool.execute(json!({"question": "  "})).await;
+        assert!(result.is_error);
+        assert!(result
+            .content
+            .contains("Missing required parameter: question"));
+        assert!(sent.lock().unwrap().is_empty());
+        cleanup(&dir);
+    }
+}
@@ -1,22 +1,28 @@
 use async_trait::async_trait;
 use serde_json::json;
 use std::collections::HashMap;
-use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::info;
 
-use crate::config::WorkingDirIsolation;
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_core::llm_types::ToolDefinition;
 use microclaw_core::text::floor_char_boundary;
-use microclaw_tools::sandbox::{SandboxExecOptions, SandboxRouter};
+use microclaw_tools::command_runner::{
+    check_command_allowed, check_program_allowed, likely_interactive, shell_join, strip_ansi,
+    NON_INTERACTIVE_ENV,
+};
+use microclaw_tools::sandbox::{CommandTimedOut, SandboxExecOptions, SandboxRouter};
 
 use super::{schema_object, Tool, ToolResult};
 
 pub struct BashTool {
-    working_dir: PathBuf,
-    working_dir_isolation: WorkingDirIsolation,
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
     default_timeout_secs: u64,
     sandbox_router: Option<Arc<SandboxRouter>>,
+    no_tty: bool,
+    shell: Option<String>,
+    command_allowlist: Vec<String>,
 }
 
 impl BashTool {
@@ -25,17 +31,27 @@ impl BashTool {
     }
 
     pub fn new_with_isolation(
-        working_dir: &str,
-        working_dir_isolation: WorkingDirIsolation,
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
     ) -> Self {
         Self {
-            working_dir: PathBuf::from(working_dir),
-            working_dir_isolation,
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
             default_timeout_secs: 120,
             sandbox_router: None,
+            no_tty: true,
+            shell: None,
+            command_allowlist: Vec::new(),
         }
     }
 
+    /// Run commands with non-interactive environment defaults and explain
+    /// timeouts that look like a blocked prompt.
+    pub fn with_no_tty(mut self, no_tty: bool) -> Self {
+        self.no_tty = no_tty;
+        self
+    }
+
     pub fn with_default_timeout_secs(mut self, timeout_secs: u64) -> Self {
         self.default_timeout_secs = timeout_secs;
         self
@@ -45,6 +61,72 @@ impl BashTool {
         self.sandbox_router = Some(router);
         self
     }
+
+    /// Shell for `command` strings; `None` keeps the platform default.
+    pub fn with_shell(mut self, shell: Option<String>) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Only run these programs; empty allows everything.
+    pub fn with_command_allowlist(mut self, allowlist: Vec<String>) -> Self {
+        self.command_allowlist = allowlist;
+        self
+    }
+}
+
+/// A `command` string run through the shell, or an `argv` run directly.
+enum Invocation {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+impl Invocation {
+    fn from_input(input: &serde_json::Value) -> Result<Self, String> {
+        let command = input.get("command").and_then(|v| v.as_str());
+        let argv = input.get("argv").filter(|v| !v.is_null());
+        match (command, argv) {
+            (Some(_), Some(_)) => Err("Pass either 'command' or 'argv', not both".into()),
+            (Some(command), None) => Ok(Invocation::Shell(command.to_string())),
+            (None, Some(argv)) => {
+                let argv: Option<Vec<String>> = argv.as_array().and_then(|items| {
+                    items
+                        .iter()
+                        .map(|v| v.as_str().map(str::to_string))
+                        .collect()
+                });
+                match argv {
+                    Some(argv) if argv.first().is_some_and(|p| !p.trim().is_empty()) => {
+                        Ok(Invocation::Argv(argv))
+                    }
+                    _ => Err(
+                        "'argv' must be a non-empty array of strings starting with the program"
+                            .into(),
+                    ),
+                }
+            }
+            (None, None) => Err("Missing 'command' parameter".into()),
+        }
+    }
+
+    /// Refuse anything outside a non-empty `allowlist` before it runs.
+    fn check_allowlist(&self, allowlist: &[String]) -> Result<(), String> {
+        if allowlist.is_empty() {
+            return Ok(());
+        }
+        match self {
+            Invocation::Shell(command) => check_command_allowed(allowlist, command),
+            Invocation::Argv(argv) => check_program_allowed(allowlist, &argv[0]),
+        }
+    }
+
+    /// Text used for logging, path policy checks and interactive-prompt hints.
+    fn display(&self) -> String {
+        match self {
+            Invocation::Shell(command) => command.clone(),
+            Invocation::Argv(argv) => shell_join(argv),
+        }
+    }
 }
 
 fn extract_envs(input: &serde_json::Value) -> HashMap<String, String> {
@@ -90,28 +172,43 @@ impl Tool for BashTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "bash".into(),
-            description: "Execute a bash command and return the output. IMPORTANT: You must CALL this tool (not write it as text) to run a command. Use for running shell commands, scripts, or system operations.".into(),
+            description: "Execute a bash command and return the output. IMPORTANT: You must CALL this tool (not write it as text) to run a command. Use for running shell commands, scripts, or system operations. Pass `argv` instead of `command` to run one program directly without a shell (no quoting, globbing, pipes or variable expansion).".into(),
             input_schema: schema_object(
                 json!({
                     "command": {
                         "type": "string",
                         "description": "The bash command to execute"
                     },
+                    "argv": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Program and arguments to run without a shell, e.g. [\"grep\", \"-rn\", \"a b\", \"src\"]. Use instead of command."
+                    },
                     "timeout_secs": {
                         "type": "integer",
                         "description": "Timeout in seconds (defaults to configured tool timeout budget)"
+                    },
+                    "keep_ansi": {
+                        "type": "boolean",
+                        "description": "Keep ANSI color/escape codes in the output (default false: they are stripped)"
                     }
                 }),
-                &["command"],
+                &[],
             ),
         }
     }
 
     async fn execute(&self, input: serde_json::Value) -> ToolResult {
-        let command = match input.get("command").and_then(|v| v.as_str()) {
-            Some(c) => c,
-            None => return ToolResult::error("Missing 'command' parameter".into()),
+        let invocation = match Invocation::from_input(&input) {
+            Ok(invocation) => invocation,
+            Err(e) => return ToolResult::error(e),
         };
+        if let Err(e) = invocation.check_allowlist(&self.command_allowlist) {
+            return ToolResult::error(format!("Command not allowed: {e}"))
+                .with_error_type("command_not_allowed");
+        }
+        let command = invocation.display();
+        let command = command.as_str();
 
         let timeout_secs = input
             .get("timeout_secs")
@@ -127,7 +224,13 @@ impl Tool for BashTool {
             ));
         }
 
-        if contains_explicit_tmp_absolute_path(command) {
+        let uses_tmp = match &invocation {
+            Invocation::Shell(command) => contains_explicit_tmp_absolute_path(command),
+            Invocation::Argv(argv) => argv
+                .iter()
+                .any(|arg| contains_explicit_tmp_absolute_path(arg)),
+        };
+        if uses_tmp {
             return ToolResult::error(format!(
                 "Command contains absolute /tmp path, which is disallowed. Use paths under current chat working directory: {}",
                 working_dir.display()
@@ -140,22 +243,45 @@ impl Tool for BashTool {
         let session_key = super::auth_context_from_input(&input)
             .map(|auth| format!("{}-{}", auth.caller_channel, auth.caller_chat_id))
             .unwrap_or_else(|| "shared".to_string());
-        let envs = extract_envs(&input);
+        let mut envs = extract_envs(&input);
+        if self.no_tty {
+            for (key, value) in NON_INTERACTIVE_ENV {
+                envs.entry((*key).to_string())
+                    .or_insert_with(|| (*value).to_string());
+            }
+        }
         let exec_opts = SandboxExecOptions {
             timeout: std::time::Duration::from_secs(timeout_secs),
             working_dir: Some(working_dir.clone()),
             envs,
+            shell: self.shell.clone(),
         };
-        let result = if let Some(router) = &self.sandbox_router {
-            router.exec(&session_key, command, &exec_opts).await
-        } else {
-            microclaw_tools::sandbox::exec_host_command(command, &exec_opts).await
+        let result = match (&invocation, &self.sandbox_router) {
+            (Invocation::Shell(command), Some(router)) => {
+                router.exec(&session_key, command, &exec_opts).await
+            }
+            (Invocation::Shell(command), None) => {
+                microclaw_tools::sandbox::exec_host_command(command, &exec_opts).await
+            }
+            (Invocation::Argv(argv), Some(router)) => {
+                router.exec_argv(&session_key, argv, &exec_opts).await
+            }
+            (Invocation::Argv(argv), None) => {
+                microclaw_tools::sandbox::exec_host_argv(argv, &exec_opts).await
+            }
         };
 
         match result {
             Ok(output) => {
-                let stdout = output.stdout;
-                let stderr = output.stderr;
+                let keep_ansi = input
+                    .get("keep_ansi")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let (stdout, stderr) = if keep_ansi {
+                    (output.stdout, output.stderr)
+                } else {
+                    (strip_ansi(&output.stdout), strip_ansi(&output.stderr))
+                };
                 let exit_code = output.exit_code;
 
                 let mut result_text = String::new();
@@ -190,7 +316,16 @@ impl Tool for BashTool {
             }
             Err(e) => {
                 let msg = e.to_string();
-                if msg.contains("timed out after") {
+                let blocked_on_input = e
+                    .downcast_ref::<CommandTimedOut>()
+                    .filter(|t| self.no_tty && !t.produced_output())
+                    .and_then(|_| likely_interactive(command));
+                if let Some(reason) = blocked_on_input {
+                    ToolResult::error(format!(
+                        "Command timed out after {timeout_secs} seconds without output; it likely expected input ({reason}). There is no terminal here, so re-run it non-interactively (e.g. -y, -m, or piped input)."
+                    ))
+                    .with_error_type("interactive_input")
+                } else if msg.contains("timed out after") {
                     ToolResult::error(format!("Command timed out after {timeout_secs} seconds"))
                         .with_error_type("timeout")
                 } else {
@@ -273,6 +408,21 @@ mod tests {
         assert!(result.content.contains("err"));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_strips_ansi_unless_kept() {
+        let tool = BashTool::new(".");
+        let command = r"printf '\033[1;31mred\033[0m \377\n'; printf '\033[33mwarn\033[0m' >&2";
+        let result = tool.execute(json!({"command": command})).await;
+        assert!(!result.is_error);
+        assert_eq!(result.content, "red \u{fffd}\n\nSTDERR:\nwarn");
+
+        let result = tool
+            .execute(json!({"command": command, "keep_ansi": true}))
+            .await;
+        assert!(result.content.contains("\u{1b}[1;31mred\u{1b}[0m"));
+    }
+
     #[tokio::test]
     async fn test_bash_timeout() {
         let tool = BashTool::new(".");
@@ -283,6 +433,41 @@ mod tests {
         assert!(result.content.contains("timed out"));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_timeout_flags_likely_interactive_command() {
+        let tool = BashTool::new(".");
+        let result = tool
+            .execute(json!({"command": "sleep 5 && vim notes.txt", "timeout_secs": 1}))
+            .await;
+        assert_eq!(result.error_type.as_deref(), Some("interactive_input"));
+        assert!(result
+            .content
+            .contains("`vim` needs an interactive terminal"));
+
+        // Output before the hang means it was not stuck on a prompt.
+        let result = tool
+            .execute(json!({"command": "echo working; sleep 5; vim notes.txt", "timeout_secs": 1}))
+            .await;
+        assert_eq!(result.error_type.as_deref(), Some("timeout"));
+
+        let tool = BashTool::new(".").with_no_tty(false);
+        let result = tool
+            .execute(json!({"command": "sleep 5 && vim notes.txt", "timeout_secs": 1}))
+            .await;
+        assert_eq!(result.error_type.as_deref(), Some("timeout"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_sets_non_interactive_env() {
+        let tool = BashTool::new(".");
+        let result = tool
+            .execute(json!({"command": "echo $DEBIAN_FRONTEND $GIT_TERMINAL_PROMPT"}))
+            .await;
+        assert_eq!(result.content.trim(), "noninteractive 0");
+    }
+
     #[tokio::test]
     async fn test_bash_blocks_tmp_absolute_path() {
         let tool = BashTool::new(".");
@@ -300,6 +485,77 @@ mod tests {
         assert!(result.content.contains("Missing 'command'"));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_argv_runs_without_shell() {
+        let tool = BashTool::new(".");
+        let result = tool
+            .execute(json!({"argv": ["printf", "%s|%s", "$HOME", "a b; echo injected"]}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(result.content, "$HOME|a b; echo injected");
+
+        let result = tool.execute(json!({"argv": ["ls", "/tmp/x"]})).await;
+        assert_eq!(result.error_type.as_deref(), Some("path_policy_blocked"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_command_allowlist() {
+        let tool = BashTool::new(".").with_command_allowlist(vec!["echo".into()]);
+        let result = tool.execute(json!({"command": "echo hi"})).await;
+        assert!(!result.is_error, "{}", result.content);
+
+        for command in [
+            "rm -rf nothing-here",
+            "echo hi; rm x",
+            "echo $(id)",
+            "echo x > allowlist-escape.txt",
+            "echo x >> allowlist-escape.txt",
+        ] {
+            let result = tool.execute(json!({"command": command})).await;
+            assert_eq!(
+                result.error_type.as_deref(),
+                Some("command_not_allowed"),
+                "{command}"
+            );
+        }
+
+        assert!(!std::path::Path::new("allowlist-escape.txt").exists());
+
+        let result = tool.execute(json!({"argv": ["echo", "a; b"]})).await;
+        assert_eq!(result.content.trim(), "a; b");
+        let result = tool.execute(json!({"argv": ["echo", ">", "x"]})).await;
+        assert_eq!(result.content.trim(), "> x");
+        let result = tool.execute(json!({"argv": ["printf", "x"]})).await;
+        assert_eq!(result.error_type.as_deref(), Some("command_not_allowed"));
+    }
+
+    #[tokio::test]
+    async fn test_bash_rejects_invalid_argv() {
+        let tool = BashTool::new(".");
+        for input in [
+            json!({"argv": []}),
+            json!({"argv": ["echo", 1]}),
+            json!({"argv": "echo hi"}),
+        ] {
+            let result = tool.execute(input).await;
+            assert!(result.content.contains("non-empty array of strings"));
+        }
+        let result = tool
+            .execute(json!({"command": "echo hi", "argv": ["echo", "hi"]}))
+            .await;
+        assert!(result.content.contains("not both"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bash_uses_configured_shell() {
+        let tool = BashTool::new(".").with_shell(Some("sh".into()));
+        let result = tool.execute(json!({"command": "echo $0"})).await;
+        assert_eq!(result.content.trim(), "sh");
+    }
+
     #[test]
     fn test_bash_tool_name_and_definition() {
         let tool = BashTool::new(".");
@@ -10,6 +10,10 @@ use microclaw_tools::command_runner::agent_browser_program;
 
 use super::{auth_context_from_input, schema_object, Tool, ToolResult};
 
+/// agent-browser session for `web_fetch` renders, kept apart from chat sessions.
+const RENDER_SESSION_NAME: &str = "microclaw-render";
+
+#[derive(Clone)]
 pub struct BrowserTool {
     data_dir: PathBuf,
     default_timeout_secs: u64,
@@ -89,6 +93,43 @@ impl BrowserTool {
             .join("browser-profile")
     }
 
+    /// Load `url` in a dedicated session without any chat's profile and return
+    /// the DOM as HTML once its scripts have run. Used by `web_fetch` with
+    /// `render: true`; `timeout_secs` bounds the whole sequence.
+    pub async fn render_html(&self, url: &str, timeout_secs: u64) -> Result<String, String> {
+        let program = agent_browser_program();
+        let run = |command: Vec<&str>| {
+            let mut args = vec!["--session", RENDER_SESSION_NAME];
+            args.extend(command);
+            let mut cmd = tokio::process::Command::new(&program);
+            cmd.args(args);
+            async move {
+                let output = cmd
+                    .output()
+                    .await
+                    .map_err(|e| format!("failed to execute agent-browser: {e}"))?;
+                if output.status.success() {
+                    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+                } else {
+                    Err(format!(
+                        "agent-browser exited with {}: {}",
+                        output.status.code().unwrap_or(-1),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ))
+                }
+            }
+        };
+        info!("Rendering page via '{}'", program);
+        tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), async {
+            run(vec!["open", url]).await?;
+            // Best effort: some pages never go network-idle.
+            let _ = run(vec!["wait", "--load", "networkidle"]).await;
+            run(vec!["get", "html", "html"]).await
+        })
+        .await
+        .map_err(|_| format!("page render timed out after {timeout_secs} seconds"))?
+    }
+
     fn session_name_for_chat(chat_id: i64) -> String {
         let normalized = if chat_id < 0 {
             format!("neg{}", chat_id.unsigned_abs())
@@ -0,0 +1,479 @@
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::info;
+
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
+use microclaw_core::llm_types::ToolDefinition;
+use microclaw_core::text::floor_char_boundary;
+
+use super::{schema_object, Tool, ToolResult};
+
+const DEFAULT_CONTEXT_LINES: usize = 3;
+const MAX_CONTEXT_LINES: usize = 50;
+const MAX_DIFF_INPUT_BYTES: u64 = 2 * 1024 * 1024;
+/// Above this many changed lines the diff is refused rather than computed;
+/// the edit script's memory grows with its square.
+const MAX_EDIT_DISTANCE: usize = 2000;
+const MAX_DIFF_OUTPUT_CHARS: usize = 30000;
+
+pub struct DiffTool {
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
+}
+
+impl DiffTool {
+    pub fn new(working_dir: &str) -> Self {
+        Self::new_with_isolation(working_dir, WorkingDirIsolation::Shared)
+    }
+
+    pub fn new_with_isolation(
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
+    ) -> Self {
+        Self {
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Myers' shortest edit script turning `a` into `b`, or `None` when more than
+/// `max_d` lines would change.
+fn diff_ops<T: PartialEq>(a: &[T], b: &[T], max_d: usize) -> Option<Vec<DiffOp>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops: Vec<DiffOp> = (0..prefix).map(|i| DiffOp::Equal(i, i)).collect();
+    for op in myers(mid_a, mid_b, max_d)? {
+        ops.push(match op {
+            DiffOp::Equal(i, j) => DiffOp::Equal(prefix + i, prefix + j),
+            DiffOp::Delete(i) => DiffOp::Delete(prefix + i),
+            DiffOp::Insert(j) => DiffOp::Insert(prefix + j),
+        });
+    }
+    let (tail_a, tail_b) = (a.len() - suffix, b.len() - suffix);
+    ops.extend((0..suffix).map(|i| DiffOp::Equal(tail_a + i, tail_b + i)));
+    Some(ops)
+}
+
+fn myers<T: PartialEq>(a: &[T], b: &[T], max_d: usize) -> Option<Vec<DiffOp>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(max_d) as isize;
+    if n + m == 0 {
+        return Some(Vec::new());
+    }
+    let offset = max + 1;
+    let mut v = vec![0isize; (2 * offset + 1) as usize];
+    // trace[d] holds V for diagonals -d..=d as it was before step d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut found = None;
+    'search: for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let idx = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                found = Some(d);
+                break 'search;
+            }
+        }
+    }
+    let depth = found?;
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..=depth).rev() {
+        let prev = &trace[d as usize];
+        let at = |k: isize| prev[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            ops.push(DiffOp::Equal(x as usize, y as usize));
+        }
+        if x == prev_x {
+            y -= 1;
+            ops.push(DiffOp::Insert(y as usize));
+        } else {
+            x -= 1;
+            ops.push(DiffOp::Delete(x as usize));
+        }
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        ops.push(DiffOp::Equal(x as usize, y as usize));
+    }
+    ops.reverse();
+    Some(ops)
+}
+
+/// Render `ops` as unified diff hunks with `context` unchanged lines around
+/// each change. Empty when nothing changed.
+fn unified_hunks(ops: &[DiffOp], a: &[&str], b: &[&str], context: usize) -> String {
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < changes.len() {
+        // Extend the hunk while the next change is within two contexts' reach.
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] <= 2 * context + 1 {
+            j += 1;
+        }
+        let start = changes[i].saturating_sub(context);
+        let end = (changes[j] + context + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        // Line positions before the hunk, for its header.
+        let (mut a_pos, mut b_pos) = (0, 0);
+        for op in &ops[..start] {
+            match op {
+                DiffOp::Equal(..) => {
+                    a_pos += 1;
+                    b_pos += 1;
+                }
+                DiffOp::Delete(_) => a_pos += 1,
+                DiffOp::Insert(_) => b_pos += 1,
+            }
+        }
+        let a_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Insert(_)))
+            .count();
+        let b_len = hunk
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Delete(_)))
+            .count();
+        let header_start = |pos: usize, len: usize| if len == 0 { pos } else { pos + 1 };
+        out.push_str(&format!(
+            "@@ -{},{a_len} +{},{b_len} @@\n",
+            header_start(a_pos, a_len),
+            header_start(b_pos, b_len)
+        ));
+        for op in hunk {
+            match op {
+                DiffOp::Equal(x, _) => out.push_str(&format!(" {}\n", a[*x])),
+                DiffOp::Delete(x) => out.push_str(&format!("-{}\n", a[*x])),
+                DiffOp::Insert(y) => out.push_str(&format!("+{}\n", b[*y])),
+            }
+        }
+        i = j + 1;
+    }
+    out
+}
+
+fn normalize_whitespace(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A unified diff of `old` against `new`, or `Ok(None)` when they match.
+fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context: usize,
+    ignore_whitespace: bool,
+) -> Result<Option<String>, String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = if ignore_whitespace {
+        let ka: Vec<String> = a.iter().map(|l| normalize_whitespace(l)).collect();
+        let kb: Vec<String> = b.iter().map(|l| normalize_whitespace(l)).collect();
+        diff_ops(&ka, &kb, MAX_EDIT_DISTANCE)
+    } else {
+        diff_ops(&a, &b, MAX_EDIT_DISTANCE)
+    }
+    .ok_or_else(|| {
+        format!(
+            "More than {MAX_EDIT_DISTANCE} lines differ; diff a smaller section or compare with bash instead"
+        )
+    })?;
+    let hunks = unified_hunks(&ops, &a, &b, context);
+    if hunks.is_empty() {
+        if !ignore_whitespace && old != new && old.ends_with('\n') != new.ends_with('\n') {
+            let missing = if old.ends_with('\n') {
+                new_label
+            } else {
+                old_label
+            };
+            return Ok(Some(format!(
+                "Only difference: {missing} has no newline at end of file."
+            )));
+        }
+        return Ok(None);
+    }
+    Ok(Some(format!("--- {old_label}\n+++ {new_label}\n{hunks}")))
+}
+
+#[async_trait]
+impl Tool for DiffTool {
+    fn name(&self) -> &str {
+        "diff"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "diff".into(),
+            description: "Show a unified diff between two files, or between a file and given content. Use it to review or verify edits.".into(),
+            input_schema: schema_object(
+                json!({
+                    "path": {
+                        "type": "string",
+                        "description": "The original file"
+                    },
+                    "other_path": {
+                        "type": "string",
+                        "description": "The file to compare against (give this or content)"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Text to compare the file against (give this or other_path)"
+                    },
+                    "context_lines": {
+                        "type": "integer",
+                        "description": "Unchanged lines shown around each change (default: 3)"
+                    },
+                    "ignore_whitespace": {
+                        "type": "boolean",
+                        "description": "Treat lines that differ only in whitespace as equal (default: false)"
+                    }
+                }),
+                &["path"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let path = match input.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolResult::error("Missing 'path' parameter".into()),
+        };
+        let other_path = input.get("other_path").and_then(|v| v.as_str());
+        let content = input.get("content").and_then(|v| v.as_str());
+        if other_path.is_some() == content.is_some() {
+            return ToolResult::error("Provide exactly one of 'other_path' or 'content'".into());
+        }
+        let context = input
+            .get("context_lines")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n as usize).min(MAX_CONTEXT_LINES))
+            .unwrap_or(DEFAULT_CONTEXT_LINES);
+        let ignore_whitespace = input
+            .get("ignore_whitespace")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let working_dir =
+            super::resolve_tool_working_dir(&self.working_dir, self.working_dir_isolation, &input);
+        let read = |p: &str| {
+            let resolved = super::resolve_tool_path(&working_dir, p);
+            async move {
+                let resolved_str = resolved.to_string_lossy().to_string();
+                if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_str) {
+                    return Err(ToolResult::error(msg).with_error_type("path_policy_blocked"));
+                }
+                match tokio::fs::metadata(&resolved).await {
+                    Ok(meta) if meta.len() > MAX_DIFF_INPUT_BYTES => {
+                        return Err(ToolResult::error(format!(
+                            "{} is too large to diff ({} bytes, limit {MAX_DIFF_INPUT_BYTES})",
+                            resolved.display(),
+                            meta.len()
+                        )))
+                    }
+                    _ => {}
+                }
+                tokio::fs::read_to_string(&resolved).await.map_err(|e| {
+                    ToolResult::error(format!("Failed to read {}: {e}", resolved.display()))
+                })
+            }
+        };
+
+        info!("Diffing file: {path}");
+        let old = match read(path).await {
+            Ok(text) => text,
+            Err(result) => return result,
+        };
+        let (new, new_label) = match (other_path, content) {
+            (Some(other), _) => match read(other).await {
+                Ok(text) => (text, other.to_string()),
+                Err(result) => return result,
+            },
+            (None, Some(text)) => (text.to_string(), format!("{path} (content)")),
+            (None, None) => unreachable!("checked above"),
+        };
+        let old_label = path.to_string();
+        let diffed = tokio::task::spawn_blocking(move || {
+            unified_diff(
+                &old,
+                &new,
+                &old_label,
+                &new_label,
+                context,
+                ignore_whitespace,
+            )
+            .map(|diff| {
+                diff.unwrap_or_else(|| {
+                    format!("No differences between {old_label} and {new_label}.")
+                })
+            })
+        })
+        .await;
+        match diffed {
+            Ok(Ok(mut text)) => {
+                if text.len() > MAX_DIFF_OUTPUT_CHARS {
+                    let cutoff = floor_char_boundary(&text, MAX_DIFF_OUTPUT_CHARS);
+                    text.truncate(cutoff);
+                    text.push_str("\n... (diff truncated)");
+                }
+                ToolResult::success(text)
+            }
+            Ok(Err(e)) => ToolResult::error(e),
+            Err(e) => ToolResult::error(format!("Diff failed: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn setup_files(files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("microclaw_diff_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_unified_diff_hunks_and_headers() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let diff = unified_diff(old, new, "old.txt", "new.txt", 1, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            diff,
+            "--- old.txt\n+++ new.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -10,1 +10,2 @@\n j\n+k\n"
+        );
+
+        // Nearby changes share one hunk.
+        let diff = unified_diff("1\n2\n3\n", "x\n2\ny\n", "a", "b", 1, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.matches("@@ -").count(), 1);
+        assert!(diff.contains("@@ -1,3 +1,3 @@"));
+
+        let diff = unified_diff("", "new\n", "a", "b", 3, false)
+            .unwrap()
+            .unwrap();
+        assert!(diff.contains("@@ -0,0 +1,1 @@\n+new\n"));
+    }
+
+    #[test]
+    fn test_unified_diff_ignore_whitespace_and_identical() {
+        assert_eq!(
+            unified_diff("x\n", "x\n", "a", "b", 3, false).unwrap(),
+            None
+        );
+        assert!(
+            unified_diff("fn  main() {\n", "fn main() {  \n", "a", "b", 3, true)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            unified_diff("fn  main() {\n", "fn main() {  \n", "a", "b", 3, false)
+                .unwrap()
+                .is_some()
+        );
+        let only_newline = unified_diff("x\n", "x", "a", "b", 3, false)
+            .unwrap()
+            .unwrap();
+        assert!(only_newline.contains("b has no newline"));
+    }
+
+    #[test]
+    fn test_diff_ops_matches_lcs_length() {
+        let a: Vec<char> = "ABCABBA".chars().collect();
+        let b: Vec<char> = "CBABAC".chars().collect();
+        let ops = diff_ops(&a, &b, 100).unwrap();
+        let edits = ops
+            .iter()
+            .filter(|op| !matches!(op, DiffOp::Equal(..)))
+            .count();
+        assert_eq!(edits, 5);
+        assert!(diff_ops(&a, &b, 2).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_diff_tool_files_and_content() {
+        let dir = setup_files(&[("old.txt", "one\ntwo\n"), ("new.txt", "one\n2\n")]);
+        let tool = DiffTool::new(dir.to_str().unwrap());
+        let old = dir.join("old.txt");
+        let new = dir.join("new.txt");
+
+        let result = tool
+            .execute(json!({
+                "path": old.to_str().unwrap(),
+                "other_path": new.to_str().unwrap()
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("-two\n+2\n"));
+
+        let result = tool
+            .execute(json!({"path": old.to_str().unwrap(), "content": "one\ntwo\n"}))
+            .await;
+        assert!(!result.is_error);
+        assert!(result.content.starts_with("No differences"));
+
+        let result = tool.execute(json!({"path": old.to_str().unwrap()})).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("exactly one"));
+
+        let result = tool
+            .execute(json!({"path": old.to_str().unwrap(), "other_path": "/nonexistent/x"}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Failed to read"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
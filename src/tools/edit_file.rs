@@ -1,16 +1,15 @@
 use async_trait::async_trait;
 use serde_json::json;
-use std::path::PathBuf;
 use tracing::info;
 
-use crate::config::WorkingDirIsolation;
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_core::llm_types::ToolDefinition;
 
 use super::{schema_object, Tool, ToolResult};
 
 pub struct EditFileTool {
-    working_dir: PathBuf,
-    working_dir_isolation: WorkingDirIsolation,
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
 }
 
 impl EditFileTool {
@@ -19,12 +18,12 @@ impl EditFileTool {
     }
 
     pub fn new_with_isolation(
-        working_dir: &str,
-        working_dir_isolation: WorkingDirIsolation,
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
     ) -> Self {
         Self {
-            working_dir: PathBuf::from(working_dir),
-            working_dir_isolation,
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
         }
     }
 }
@@ -70,7 +69,7 @@ impl Tool for EditFileTool {
         let resolved_path_str = resolved_path.to_string_lossy().to_string();
 
         if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_path_str) {
-            return ToolResult::error(msg);
+            return ToolResult::error(msg).with_error_type("path_policy_blocked");
         }
 
         let old_string = match input.get("old_string").and_then(|v| v.as_str()) {
@@ -6,10 +6,12 @@ use serde_json::json;
 use super::{authorize_chat_access, schema_object, Tool, ToolResult};
 use microclaw_core::llm_types::ToolDefinition;
 use microclaw_storage::db::{call_blocking, Database};
+use microclaw_tools::redaction::{SecretRedactor, ToolResultRedactionConfig};
 
 pub struct ExportChatTool {
     db: Arc<Database>,
     data_dir: String,
+    redactor: SecretRedactor,
 }
 
 impl ExportChatTool {
@@ -17,8 +19,16 @@ impl ExportChatTool {
         ExportChatTool {
             db,
             data_dir: data_dir.to_string(),
+            redactor: SecretRedactor::new(&ToolResultRedactionConfig::default(), [""; 0])
+                .with_pii(),
         }
     }
+
+    /// Redactor applied to message content unless the caller passes `redact: false`.
+    pub fn with_redactor(mut self, redactor: SecretRedactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
 }
 
 #[async_trait]
@@ -30,7 +40,7 @@ impl Tool for ExportChatTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "export_chat".into(),
-            description: "Export chat history to a markdown file. Returns the file path.".into(),
+            description: "Export chat history to a markdown file. Secrets and personal data (emails, phone numbers) in message content are redacted by default. Returns the file path.".into(),
             input_schema: schema_object(
                 json!({
                     "chat_id": {
@@ -40,6 +50,10 @@ impl Tool for ExportChatTool {
                     "path": {
                         "type": "string",
                         "description": "Optional output file path. Defaults to data/exports/{chat_id}_{timestamp}.md"
+                    },
+                    "redact": {
+                        "type": "boolean",
+                        "description": "Redact secrets and personal data from message content (default true). Set false only for internal exports."
                     }
                 }),
                 &["chat_id"],
@@ -72,6 +86,11 @@ impl Tool for ExportChatTool {
             .get("path")
             .and_then(|v| v.as_str())
             .unwrap_or(&default_path);
+        let redact = input
+            .get("redact")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let mut redactions = 0usize;
 
         // Build markdown
         let mut md = format!("# Chat Export: {chat_id}\n\n");
@@ -86,9 +105,16 @@ impl Tool for ExportChatTool {
             } else {
                 &msg.sender_name
             };
+            let content = if redact {
+                let (content, count) = self.redactor.redact_counted(&msg.content);
+                redactions += count;
+                content
+            } else {
+                msg.content.clone()
+            };
             md.push_str(&format!(
                 "**{}** ({})\n\n{}\n\n---\n\n",
-                sender, msg.timestamp, msg.content
+                sender, msg.timestamp, content
             ));
         }
 
@@ -99,12 +125,21 @@ impl Tool for ExportChatTool {
                 return ToolResult::error(format!("Failed to create directory: {e}"));
             }
         }
+        let redaction_note = if redact {
+            format!("{redactions} redaction(s) applied")
+        } else {
+            "redaction disabled".to_string()
+        };
         match std::fs::write(path, &md) {
             Ok(_) => ToolResult::success(format!(
-                "Exported {} messages to {}",
+                "Exported {} messages to {} ({redaction_note})",
                 messages.len(),
                 path.display()
-            )),
+            ))
+            .with_metadata(json!({
+                "redacted": redact,
+                "redactions": redactions,
+            })),
             Err(e) => ToolResult::error(format!("Failed to write file: {e}")),
         }
     }
@@ -230,4 +265,39 @@ mod tests {
         assert!(content.contains("hello"));
         cleanup(&dir);
     }
+
+    #[tokio::test]
+    async fn test_export_redacts_secrets_and_pii_by_default() {
+        let (db, dir) = test_db();
+        db.store_message(&StoredMessage {
+            id: "m1".into(),
+            chat_id: 200,
+            sender_name: "alice".into(),
+            content: "key sk-ant-REDACTED, reach me at alice@example.com".into(),
+            is_from_bot: false,
+            timestamp: "2024-01-01T00:00:01Z".into(),
+        })
+        .unwrap();
+        let tool = ExportChatTool::new(db, dir.to_str().unwrap());
+
+        let redacted_path = dir.join("redacted.md");
+        let result = tool
+            .execute(json!({"chat_id": 200, "path": redacted_path.to_str().unwrap()}))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains("2 redaction(s) applied"));
+        let content = std::fs::read_to_string(&redacted_path).unwrap();
+        assert!(!content.contains("sk-ant-"));
+        assert!(!content.contains("alice@example.com"));
+        assert!(content.contains("alice"));
+
+        let raw_path = dir.join("raw.md");
+        let result = tool
+            .execute(json!({"chat_id": 200, "path": raw_path.to_str().unwrap(), "redact": false}))
+            .await;
+        assert!(result.content.contains("redaction disabled"));
+        let content = std::fs::read_to_string(&raw_path).unwrap();
+        assert!(content.contains("alice@example.com"));
+        cleanup(&dir);
+    }
 }
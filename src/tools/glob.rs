@@ -1,16 +1,17 @@
 use async_trait::async_trait;
 use serde_json::json;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use tracing::info;
 
-use crate::config::WorkingDirIsolation;
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_core::llm_types::ToolDefinition;
 
 use super::{schema_object, Tool, ToolResult};
 
 pub struct GlobTool {
-    working_dir: PathBuf,
-    working_dir_isolation: WorkingDirIsolation,
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
+    skills_root: Option<PathBuf>,
 }
 
 impl GlobTool {
@@ -19,14 +20,31 @@ impl GlobTool {
     }
 
     pub fn new_with_isolation(
-        working_dir: &str,
-        working_dir_isolation: WorkingDirIsolation,
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
     ) -> Self {
         Self {
-            working_dir: PathBuf::from(working_dir),
-            working_dir_isolation,
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
+            skills_root: None,
         }
     }
+
+    /// Read-only skills root searchable with `include_skills: true`.
+    pub fn with_skills_root(mut self, skills_root: Option<PathBuf>) -> Self {
+        self.skills_root = skills_root;
+        self
+    }
+}
+
+fn glob_matches(full_pattern: &str) -> Result<Vec<String>, glob::PatternError> {
+    let paths = glob::glob(full_pattern)?;
+    Ok(microclaw_tools::path_guard::filter_paths(
+        paths
+            .filter_map(|p| p.ok())
+            .map(|p| p.display().to_string())
+            .collect(),
+    ))
 }
 
 #[async_trait]
@@ -48,6 +66,10 @@ impl Tool for GlobTool {
                     "path": {
                         "type": "string",
                         "description": "Base directory to search from (default: current directory)"
+                    },
+                    "include_skills": {
+                        "type": "boolean",
+                        "description": "Also match the pattern against the read-only skills directory. Results are labeled by root."
                     }
                 }),
                 &["pattern"],
@@ -67,7 +89,7 @@ impl Tool for GlobTool {
         let resolved_base_str = resolved_base.to_string_lossy().to_string();
 
         if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_base_str) {
-            return ToolResult::error(msg);
+            return ToolResult::error(msg).with_error_type("path_policy_blocked");
         }
 
         info!("Glob: {} in {}", pattern, resolved_base.display());
@@ -78,31 +100,82 @@ impl Tool for GlobTool {
             format!("{}/{}", resolved_base.display(), pattern)
         };
 
-        match glob::glob(&full_pattern) {
-            Ok(paths) => {
-                let mut matches: Vec<String> = paths
-                    .filter_map(|p| p.ok())
-                    .map(|p| p.display().to_string())
-                    .collect();
-                matches = microclaw_tools::path_guard::filter_paths(matches);
-                matches.sort();
-
-                if matches.is_empty() {
-                    ToolResult::success("No files found matching pattern.".into())
-                } else {
-                    let count = matches.len();
-                    if count > 500 {
-                        matches.truncate(500);
-                        matches.push(format!("... and {} more files", count - 500));
+        let include_skills = input
+            .get("include_skills")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let mut matches = match glob_matches(&full_pattern) {
+            Ok(m) => m,
+            Err(e) => return ToolResult::error(format!("Invalid glob pattern: {e}")),
+        };
+        matches.sort();
+
+        if include_skills {
+            let skills = match self.skills_root.as_deref() {
+                Some(root) => {
+                    let canonical_root =
+                        std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+                    if let Err(msg) =
+                        microclaw_tools::path_guard::check_path(&canonical_root.to_string_lossy())
+                    {
+                        return ToolResult::error(msg).with_error_type("path_policy_blocked");
+                    }
+                    match skills_matches(&canonical_root, pattern) {
+                        Ok(m) => m,
+                        Err(msg) => return ToolResult::error(msg),
                     }
-                    ToolResult::success(matches.join("\n"))
                 }
+                None => Vec::new(),
+            };
+            matches = matches
+                .into_iter()
+                .map(|m| format!("[working_dir] {m}"))
+                .chain(skills.into_iter().map(|m| format!("[skills] {m}")))
+                .collect();
+        }
+
+        if matches.is_empty() {
+            ToolResult::success("No files found matching pattern.".into())
+        } else {
+            let count = matches.len();
+            if count > 500 {
+                matches.truncate(500);
+                matches.push(format!("... and {} more files", count - 500));
             }
-            Err(e) => ToolResult::error(format!("Invalid glob pattern: {e}")),
+            ToolResult::success(matches.join("\n"))
         }
     }
 }
 
+/// Match `pattern` under the canonical skills root, refusing patterns or results
+/// that escape it or resolve to a blocked path.
+fn skills_matches(canonical_root: &Path, pattern: &str) -> Result<Vec<String>, String> {
+    if Path::new(pattern).is_absolute()
+        || Path::new(pattern)
+            .components()
+            .any(|c| matches!(c, Component::ParentDir))
+    {
+        return Err(
+            "include_skills requires a relative pattern without '..' components".to_string(),
+        );
+    }
+    let full_pattern = format!("{}/{}", canonical_root.display(), pattern);
+    let mut matches: Vec<String> = glob_matches(&full_pattern)
+        .map_err(|e| format!("Invalid glob pattern: {e}"))?
+        .into_iter()
+        .filter(|m| {
+            std::fs::canonicalize(m)
+                .map(|c| {
+                    c.starts_with(canonical_root) && !microclaw_tools::path_guard::is_blocked(&c)
+                })
+                .unwrap_or(false)
+        })
+        .collect();
+    matches.sort();
+    Ok(matches)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,4 +239,54 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&root);
     }
+
+    #[tokio::test]
+    async fn test_glob_include_skills_labels_roots_and_stays_confined() {
+        let root = std::env::temp_dir().join(format!("microclaw_glob4_{}", uuid::Uuid::new_v4()));
+        let work = root.join("workspace");
+        let skills = root.join("skills");
+        std::fs::create_dir_all(work.join("shared")).unwrap();
+        std::fs::create_dir_all(skills.join("pdf")).unwrap();
+        std::fs::write(work.join("shared").join("notes.md"), "").unwrap();
+        std::fs::write(skills.join("pdf").join("SKILL.md"), "").unwrap();
+        std::fs::create_dir_all(skills.join(".ssh")).unwrap();
+        std::fs::write(skills.join(".ssh").join("id_rsa"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            skills.join(".ssh").join("id_rsa"),
+            skills.join("pdf").join("key.md"),
+        )
+        .unwrap();
+
+        let tool = GlobTool::new(work.to_str().unwrap()).with_skills_root(Some(skills.clone()));
+        let plain = tool.execute(json!({"pattern": "**/*.md"})).await;
+        assert!(!plain.content.contains("SKILL.md"));
+
+        let result = tool
+            .execute(json!({"pattern": "**/*.md", "include_skills": true}))
+            .await;
+        assert!(!result.is_error);
+        assert!(result.content.contains("[working_dir] "));
+        assert!(result.content.contains("notes.md"));
+        assert!(result.content.contains("[skills] "));
+        assert!(result.content.contains("SKILL.md"));
+        assert!(!result.content.contains("key.md"));
+
+        let escape = tool
+            .execute(json!({"pattern": "../workspace/**/*.md", "include_skills": true}))
+            .await;
+        assert!(escape.is_error);
+
+        let blocked_skills = root.join(".ssh").join("skills");
+        std::fs::create_dir_all(&blocked_skills).unwrap();
+        std::fs::write(blocked_skills.join("SKILL.md"), "").unwrap();
+        let tool = GlobTool::new(work.to_str().unwrap()).with_skills_root(Some(blocked_skills));
+        let result = tool
+            .execute(json!({"pattern": "**/*.md", "include_skills": true}))
+            .await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("path_policy_blocked"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
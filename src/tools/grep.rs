@@ -3,14 +3,15 @@ use serde_json::json;
 use std::path::{Path, PathBuf};
 use tracing::info;
 
-use crate::config::WorkingDirIsolation;
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_core::llm_types::ToolDefinition;
 
 use super::{schema_object, Tool, ToolResult};
 
 pub struct GrepTool {
-    working_dir: PathBuf,
-    working_dir_isolation: WorkingDirIsolation,
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
+    skills_root: Option<PathBuf>,
 }
 
 impl GrepTool {
@@ -19,14 +20,21 @@ impl GrepTool {
     }
 
     pub fn new_with_isolation(
-        working_dir: &str,
-        working_dir_isolation: WorkingDirIsolation,
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
     ) -> Self {
         Self {
-            working_dir: PathBuf::from(working_dir),
-            working_dir_isolation,
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
+            skills_root: None,
         }
     }
+
+    /// Read-only skills root searchable with `include_skills: true`.
+    pub fn with_skills_root(mut self, skills_root: Option<PathBuf>) -> Self {
+        self.skills_root = skills_root;
+        self
+    }
 }
 
 #[async_trait]
@@ -52,6 +60,10 @@ impl Tool for GrepTool {
                     "glob": {
                         "type": "string",
                         "description": "Glob pattern to filter files (e.g., '*.rs')"
+                    },
+                    "include_skills": {
+                        "type": "boolean",
+                        "description": "Also search the read-only skills directory. Results are labeled by root."
                     }
                 }),
                 &["pattern"],
@@ -70,7 +82,7 @@ impl Tool for GrepTool {
         let resolved_path = super::resolve_tool_path(&working_dir, path);
         let resolved_path_str = resolved_path.to_string_lossy().to_string();
         if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_path_str) {
-            return ToolResult::error(msg);
+            return ToolResult::error(msg).with_error_type("path_policy_blocked");
         }
         let file_glob = input.get("glob").and_then(|v| v.as_str());
 
@@ -90,10 +102,46 @@ impl Tool for GrepTool {
             &re,
             &mut results,
             &mut file_count,
+            None,
         ) {
             return ToolResult::error(format!("Search error: {e}"));
         }
 
+        let include_skills = input
+            .get("include_skills")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if include_skills {
+            let mut skill_results = Vec::new();
+            if let Some(root) = self.skills_root.as_deref() {
+                let canonical_root =
+                    std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+                if let Err(msg) =
+                    microclaw_tools::path_guard::check_path(&canonical_root.to_string_lossy())
+                {
+                    return ToolResult::error(msg).with_error_type("path_policy_blocked");
+                }
+                let mut skill_file_count = 0;
+                if canonical_root.is_dir() {
+                    if let Err(e) = grep_recursive(
+                        &canonical_root,
+                        file_glob,
+                        &re,
+                        &mut skill_results,
+                        &mut skill_file_count,
+                        Some(&canonical_root),
+                    ) {
+                        return ToolResult::error(format!("Search error in skills: {e}"));
+                    }
+                }
+            }
+            results = results
+                .into_iter()
+                .map(|r| format!("[working_dir] {r}"))
+                .chain(skill_results.into_iter().map(|r| format!("[skills] {r}")))
+                .collect();
+        }
+
         if results.is_empty() {
             ToolResult::success("No matches found.".into())
         } else {
@@ -112,6 +160,7 @@ fn grep_recursive(
     re: &regex::Regex,
     results: &mut Vec<String>,
     file_count: &mut usize,
+    confine_to: Option<&Path>,
 ) -> std::io::Result<()> {
     let metadata = std::fs::metadata(path)?;
 
@@ -129,9 +178,18 @@ fn grep_recursive(
             if name.starts_with('.') || name == "node_modules" || name == "target" {
                 continue;
             }
+            // Symlinks must not lead outside a confined root.
+            if let Some(root) = confine_to {
+                let inside = std::fs::canonicalize(&entry_path)
+                    .map(|c| c.starts_with(root))
+                    .unwrap_or(false);
+                if !inside {
+                    continue;
+                }
+            }
 
             if entry_path.is_dir() {
-                grep_recursive(&entry_path, file_glob, re, results, file_count)?;
+                grep_recursive(&entry_path, file_glob, re, results, file_count, confine_to)?;
             } else if entry_path.is_file() {
                 if microclaw_tools::path_guard::is_blocked(&entry_path) {
                     continue;
@@ -276,7 +334,7 @@ mod tests {
         let re = regex::Regex::new("match_me").unwrap();
         let mut results = Vec::new();
         let mut count = 0;
-        grep_recursive(&dir, None, &re, &mut results, &mut count).unwrap();
+        grep_recursive(&dir, None, &re, &mut results, &mut count, None).unwrap();
 
         // Should only find in visible.txt
         assert_eq!(results.len(), 1);
@@ -300,4 +358,47 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&root);
     }
+
+    #[tokio::test]
+    async fn test_grep_include_skills_labels_roots_and_stays_confined() {
+        let root = std::env::temp_dir().join(format!("microclaw_grep3_{}", uuid::Uuid::new_v4()));
+        let work = root.join("workspace");
+        let skills = root.join("skills");
+        let outside = root.join("outside");
+        std::fs::create_dir_all(work.join("shared")).unwrap();
+        std::fs::create_dir_all(skills.join("pdf")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(work.join("shared").join("a.txt"), "needle").unwrap();
+        std::fs::write(skills.join("pdf").join("SKILL.md"), "needle in skill").unwrap();
+        std::fs::write(skills.join("pdf").join("secrets.yaml"), "needle secret").unwrap();
+        std::fs::write(outside.join("private.txt"), "needle outside").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&outside, skills.join("escape")).unwrap();
+
+        let tool = GrepTool::new(work.to_str().unwrap()).with_skills_root(Some(skills.clone()));
+        let plain = tool.execute(json!({"pattern": "needle"})).await;
+        assert!(!plain.content.contains("SKILL.md"));
+
+        let result = tool
+            .execute(json!({"pattern": "needle", "include_skills": true}))
+            .await;
+        assert!(!result.is_error);
+        assert!(result.content.contains("[working_dir] "));
+        assert!(result.content.contains("a.txt"));
+        assert!(result.content.contains("[skills] "));
+        assert!(result.content.contains("needle in skill"));
+        assert!(!result.content.contains("needle outside"));
+        assert!(!result.content.contains("needle secret"));
+
+        let blocked_skills = root.join(".ssh").join("skills");
+        std::fs::create_dir_all(&blocked_skills).unwrap();
+        let tool = GrepTool::new(work.to_str().unwrap()).with_skills_root(Some(blocked_skills));
+        let result = tool
+            .execute(json!({"pattern": "needle", "include_skills": true}))
+            .await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("path_policy_blocked"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
@@ -0,0 +1,264 @@
+use async_trait::async_trait;
+use microclaw_tools::http_request::{
+    parse_method, send_http_request, HttpRequestAuthConfig, HttpRequestPolicy, HttpRequestSpec,
+    HTTP_REQUEST_METHODS,
+};
+use microclaw_tools::web_content_validation::WebContentValidationConfig;
+use microclaw_tools::web_domains::WebDomainPolicy;
+use microclaw_tools::web_fetch::{
+    WebFetchHostRateConfig, WebFetchProxyConfig, WebFetchUrlValidationConfig,
+};
+use serde_json::json;
+
+use super::{schema_object, Tool, ToolResult};
+use microclaw_core::llm_types::ToolDefinition;
+
+pub struct HttpRequestTool {
+    policy: HttpRequestPolicy,
+}
+
+impl HttpRequestTool {
+    pub fn new(
+        default_timeout_secs: u64,
+        validation: WebContentValidationConfig,
+        url_validation: WebFetchUrlValidationConfig,
+    ) -> Self {
+        Self {
+            policy: HttpRequestPolicy {
+                timeout_secs: default_timeout_secs,
+                validation,
+                url_validation,
+                proxy: WebFetchProxyConfig::default(),
+                host_rate: WebFetchHostRateConfig::default(),
+                auth: HttpRequestAuthConfig::default(),
+            },
+        }
+    }
+
+    pub fn with_proxy(mut self, proxy: WebFetchProxyConfig) -> Self {
+        self.policy.proxy = proxy;
+        self
+    }
+
+    pub fn with_host_rate(mut self, host_rate: WebFetchHostRateConfig) -> Self {
+        self.policy.host_rate = host_rate;
+        self
+    }
+
+    pub fn with_auth(mut self, auth: HttpRequestAuthConfig) -> Self {
+        self.policy.auth = auth;
+        self
+    }
+
+    pub fn with_domain_policy(mut self, domain_policy: WebDomainPolicy) -> Self {
+        self.policy.url_validation.domain_policy = domain_policy;
+        self
+    }
+}
+
+fn parse_spec(input: &serde_json::Value) -> Result<HttpRequestSpec, String> {
+    let url = input
+        .get("url")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| "Missing required parameter: url".to_string())?;
+    let method = parse_method(
+        input
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET"),
+    )?;
+
+    let mut headers = Vec::new();
+    match input.get("headers") {
+        None | Some(serde_json::Value::Null) => {}
+        Some(serde_json::Value::Object(map)) => {
+            for (name, value) in map {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.to_string(),
+                    _ => return Err(format!("header '{name}' must be a string")),
+                };
+                headers.push((name.clone(), value));
+            }
+        }
+        Some(_) => return Err("headers must be an object of name -> value".into()),
+    }
+    let has_header = |headers: &[(String, String)], name: &str| {
+        headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(name))
+    };
+
+    let body = match input.get("body") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(s)) => Some(s.clone()),
+        Some(value) => {
+            if !has_header(&headers, "content-type") {
+                headers.push(("content-type".into(), "application/json".into()));
+            }
+            Some(value.to_string())
+        }
+    };
+    if body.is_some() && method == reqwest::Method::HEAD {
+        return Err("HEAD requests cannot have a body".into());
+    }
+
+    Ok(HttpRequestSpec {
+        method,
+        url: url.to_string(),
+        headers,
+        body,
+    })
+}
+
+#[async_trait]
+impl Tool for HttpRequestTool {
+    fn name(&self) -> &str {
+        "http_request"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "http_request".into(),
+            description:
+                "Send an HTTP request (e.g. to a JSON API) and return the status line, response headers, and body (max 20KB). Use for POST/PUT/PATCH/DELETE or custom headers; use web_fetch to read web pages. Credentials for configured hosts are added automatically. Non-2xx responses are reported as errors with their body."
+                    .into(),
+            input_schema: schema_object(
+                json!({
+                    "method": {
+                        "type": "string",
+                        "enum": HTTP_REQUEST_METHODS,
+                        "description": "HTTP method (default GET)"
+                    },
+                    "url": {
+                        "type": "string",
+                        "description": "The URL to request"
+                    },
+                    "headers": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "Request headers as name -> value"
+                    },
+                    "body": {
+                        "description": "Request body. A string is sent as-is; any other JSON value is serialized and sent with content-type application/json unless one is given."
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "description": "Timeout in seconds (defaults to configured tool timeout budget)"
+                    }
+                }),
+                &["url"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let spec = match parse_spec(&input) {
+            Ok(spec) => spec,
+            Err(e) => return ToolResult::error(e),
+        };
+        let mut policy = self.policy.clone();
+        if let Some(timeout_secs) = input.get("timeout_secs").and_then(|v| v.as_u64()) {
+            policy.timeout_secs = timeout_secs;
+        }
+
+        match send_http_request(&spec, &policy).await {
+            Ok(outcome) => {
+                let mut metadata = json!({
+                    "status": outcome.status.as_u16(),
+                    "method": spec.method.as_str(),
+                    "final_url": outcome.final_url,
+                    "truncated": outcome.truncated,
+                });
+                if !outcome.rate_limit_wait.is_zero() {
+                    metadata["rate_limit_wait_ms"] =
+                        json!(outcome.rate_limit_wait.as_millis() as u64);
+                }
+                let content = outcome.format();
+                let status = outcome.status;
+                let result = if status.is_client_error() || status.is_server_error() {
+                    ToolResult::error(content).with_error_type("http_status")
+                } else {
+                    ToolResult::success(content)
+                };
+                result.with_metadata(metadata)
+            }
+            Err(e) => ToolResult::error(format!("HTTP request failed: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool() -> HttpRequestTool {
+        HttpRequestTool::new(
+            15,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_http_request_definition() {
+        let def = tool().definition();
+        assert_eq!(def.name, "http_request");
+        assert!(def.input_schema["properties"]["body"].is_object());
+        assert_eq!(
+            def.input_schema["properties"]["method"]["enum"][1],
+            json!("POST")
+        );
+        let required = def.input_schema["required"].as_array().unwrap();
+        assert!(required.iter().any(|v| v == "url"));
+    }
+
+    #[test]
+    fn test_parse_spec_serializes_json_bodies() {
+        let spec = parse_spec(&json!({
+            "method": "post",
+            "url": "https://api.example.com/items",
+            "headers": {"X-Id": 7},
+            "body": {"name": "x"}
+        }))
+        .unwrap();
+        assert_eq!(spec.method, reqwest::Method::POST);
+        assert_eq!(spec.body.as_deref(), Some("{\"name\":\"x\"}"));
+        assert!(spec.headers.contains(&("X-Id".into(), "7".into())));
+        assert!(spec
+            .headers
+            .contains(&("content-type".into(), "application/json".into())));
+
+        let spec = parse_spec(&json!({
+            "url": "https://api.example.com",
+            "headers": {"Content-Type": "text/csv"},
+            "body": "a,b"
+        }))
+        .unwrap();
+        assert_eq!(spec.method, reqwest::Method::GET);
+        assert_eq!(spec.headers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_http_request_rejects_bad_input() {
+        let result = tool().execute(json!({"method": "POST"})).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Missing required parameter: url"));
+
+        let result = tool()
+            .execute(json!({"url": "https://example.com", "method": "TRACE"}))
+            .await;
+        assert!(result.content.contains("Unsupported method"));
+
+        let result = tool()
+            .execute(json!({"url": "https://example.com", "method": "HEAD", "body": "x"}))
+            .await;
+        assert!(result.content.contains("cannot have a body"));
+
+        let result = tool()
+            .execute(json!({"url": "https://example.com", "headers": ["x"]}))
+            .await;
+        assert!(result.content.contains("headers must be an object"));
+    }
+}
@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::Path;
+use tracing::info;
+
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
+use microclaw_core::llm_types::ToolDefinition;
+
+use super::{schema_object, Tool, ToolResult};
+
+const MAX_DEPTH: u64 = 5;
+const MAX_ENTRIES: usize = 500;
+
+/// Lists a directory with each entry's type, size and modification time, so the
+/// agent does not need `ls -la` (whose output varies by platform and sandbox).
+pub struct ListDirectoryTool {
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
+}
+
+impl ListDirectoryTool {
+    pub fn new(working_dir: &str) -> Self {
+        Self::new_with_isolation(working_dir, WorkingDirIsolation::Shared)
+    }
+
+    pub fn new_with_isolation(
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
+    ) -> Self {
+        Self {
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
+        }
+    }
+}
+
+/// Walk `root` down to `depth` levels, naming entries relative to it. Symlinks are reported but never followed, and sensitive paths are
+/// left out. Returns whether `MAX_ENTRIES` cut the listing short.
+fn collect_entries(
+    root: &Path,
+    depth: u64,
+    entries: &mut Vec<serde_json::Value>,
+) -> std::io::Result<bool> {
+    let mut pending = vec![(root.to_path_buf(), 1u64)];
+    while let Some((dir, level)) = pending.pop() {
+        let mut children: Vec<_> = match std::fs::read_dir(&dir) {
+            Ok(iter) => iter.filter_map(|e| e.ok()).collect(),
+            // Only the requested directory itself must be readable.
+            Err(e) if dir == root => return Err(e),
+            Err(_) => continue,
+        };
+        children.sort_by_key(|e| e.file_name());
+        let mut subdirs = Vec::new();
+        for child in children {
+            let path = child.path();
+            if microclaw_tools::path_guard::is_blocked(&path) {
+                continue;
+            }
+            if entries.len() >= MAX_ENTRIES {
+                return Ok(true);
+            }
+            let Ok(meta) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            let file_type = meta.file_type();
+            let kind = if file_type.is_symlink() {
+                "symlink"
+            } else if file_type.is_dir() {
+                "dir"
+            } else if file_type.is_file() {
+                "file"
+            } else {
+                "other"
+            };
+            let mut entry = json!({ "name": name, "type": kind });
+            if file_type.is_file() {
+                entry["size"] = json!(meta.len());
+            }
+            if let Ok(modified) = meta.modified() {
+                entry["modified"] = json!(chrono::DateTime::<chrono::Utc>::from(modified)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+            }
+            if file_type.is_symlink() {
+                if let Ok(target) = std::fs::read_link(&path) {
+                    entry["target"] = json!(target.to_string_lossy());
+                }
+            }
+            entries.push(entry);
+            if file_type.is_dir() && level < depth {
+                subdirs.push((path, level + 1));
+            }
+        }
+        // Reversed so the stack visits subdirectories in name order.
+        pending.extend(subdirs.into_iter().rev());
+    }
+    Ok(false)
+}
+
+#[async_trait]
+impl Tool for ListDirectoryTool {
+    fn name(&self) -> &str {
+        "list_directory"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_directory".into(),
+            description: format!(
+                "List a directory's entries as JSON: name, type (file/dir/symlink), size in bytes \
+                 (files) and modified time (UTC). Non-recursive unless `depth` is set. Prefer this \
+                 over `ls` in bash. At most {MAX_ENTRIES} entries are returned."
+            ),
+            input_schema: schema_object(
+                json!({
+                    "path": {
+                        "type": "string",
+                        "description": "Directory to list, relative to the working directory (default: the working directory)"
+                    },
+                    "depth": {
+                        "type": "integer",
+                        "description": format!("Levels to descend: 1 lists only the directory itself (default 1, max {MAX_DEPTH})")
+                    }
+                }),
+                &[],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let path = input.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+        let depth = input
+            .get("depth")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .clamp(1, MAX_DEPTH);
+        let working_dir =
+            super::resolve_tool_working_dir(&self.working_dir, self.working_dir_isolation, &input);
+        let resolved = super::resolve_tool_path(&working_dir, path);
+        let resolved_str = resolved.to_string_lossy().to_string();
+
+        if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_str) {
+            return ToolResult::error(msg).with_error_type("path_policy_blocked");
+        }
+        if !resolved.is_dir() {
+            return ToolResult::error(format!("Not a directory: {path}"))
+                .with_error_type("not_found");
+        }
+
+        info!("List directory: {} (depth {depth})", resolved.display());
+
+        let mut entries = Vec::new();
+        let truncated = match collect_entries(&resolved, depth, &mut entries) {
+            Ok(truncated) => truncated,
+            Err(e) => return ToolResult::error(format!("Failed to list directory: {e}")),
+        };
+        let listing = json!({
+            "path": resolved_str,
+            "entries": entries,
+            "truncated": truncated,
+        });
+        ToolResult::success(listing.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(result: &ToolResult) -> serde_json::Value {
+        assert!(!result.is_error, "{}", result.content);
+        serde_json::from_str(&result.content).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_directory_reports_metadata() {
+        let root = std::env::temp_dir().join(format!("microclaw_ls_{}", uuid::Uuid::new_v4()));
+        let work = root.join("workspace");
+        let shared = work.join("shared");
+        std::fs::create_dir_all(shared.join("src").join("deep")).unwrap();
+        std::fs::write(shared.join("notes.txt"), "hello").unwrap();
+        std::fs::write(shared.join("src").join("main.rs"), "fn main() {}").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("notes.txt", shared.join("link")).unwrap();
+
+        let tool = ListDirectoryTool::new(work.to_str().unwrap());
+        let out = listing(&tool.execute(json!({})).await);
+        let entries = out["entries"].as_array().unwrap();
+        let names: Vec<&str> = entries
+            .iter()
+            .map(|e| e["name"].as_str().unwrap())
+            .collect();
+        #[cfg(unix)]
+        assert_eq!(names, ["link", "notes.txt", "src"]);
+        let notes = entries.iter().find(|e| e["name"] == "notes.txt").unwrap();
+        assert_eq!(notes["type"], "file");
+        assert_eq!(notes["size"], 5);
+        assert!(notes["modified"].as_str().unwrap().ends_with('Z'));
+        let src = entries.iter().find(|e| e["name"] == "src").unwrap();
+        assert_eq!(src["type"], "dir");
+        assert!(src.get("size").is_none());
+        #[cfg(unix)]
+        {
+            let link = entries.iter().find(|e| e["name"] == "link").unwrap();
+            assert_eq!(link["type"], "symlink");
+            assert_eq!(link["target"], "notes.txt");
+        }
+        assert_eq!(out["truncated"], false);
+
+        let out = listing(&tool.execute(json!({"path": "src", "depth": 2})).await);
+        let names: Vec<&str> = out["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["deep", "main.rs"]);
+
+        let out = listing(&tool.execute(json!({"depth": 2})).await);
+        let names: Vec<&str> = out["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"src/main.rs"));
+        assert!(!names.iter().any(|n| n.starts_with("src/deep/")));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_list_directory_rejects_missing_and_sensitive_paths() {
+        let root = std::env::temp_dir().join(format!("microclaw_ls2_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let tool = ListDirectoryTool::new(root.to_str().unwrap());
+
+        let missing = tool.execute(json!({"path": "nope"})).await;
+        assert!(missing.is_error);
+        assert_eq!(missing.error_type.as_deref(), Some("not_found"));
+
+        let blocked = tool.execute(json!({"path": "/root/.ssh"})).await;
+        assert!(blocked.is_error);
+        assert_eq!(blocked.error_type.as_deref(), Some("path_policy_blocked"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -2,10 +2,10 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 
-use crate::mcp::{McpServer, McpToolInfo};
+use crate::mcp::{McpProgress, McpServer, McpToolInfo};
 use microclaw_core::llm_types::ToolDefinition;
 
-use super::{Tool, ToolResult};
+use super::{report_tool_progress, Tool, ToolResult};
 
 pub struct McpTool {
     server: Arc<McpServer>,
@@ -69,10 +69,28 @@ impl Tool for McpTool {
     }
 
     async fn execute(&self, input: serde_json::Value) -> ToolResult {
-        match self.server.call_tool(&self.tool_info.name, input).await {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<McpProgress>();
+        let call = self
+            .server
+            .call_tool_with_progress(&self.tool_info.name, input, tx);
+        let relay = async {
+            let mut trail = Vec::new();
+            while let Some(update) = rx.recv().await {
+                report_tool_progress(update.summary());
+                trail.push(update);
+            }
+            trail
+        };
+        let (outcome, trail) = tokio::join!(call, relay);
+        let result = match outcome {
             Ok(output) => ToolResult::success(output),
             Err(e) => ToolResult::error(format!("MCP tool error: {e}"))
                 .with_error_type(Self::classify_mcp_error_type(&e)),
+        };
+        if trail.is_empty() {
+            result
+        } else {
+            result.with_metadata(serde_json::json!({ "progress": trail }))
         }
     }
 }
@@ -4,7 +4,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::info;
 
-use crate::memory_backend::MemoryBackend;
+use crate::memory_backend::{MemoryBackend, MemoryWriteOutcome};
 use microclaw_core::llm_types::ToolDefinition;
 use microclaw_storage::db::Database;
 use microclaw_storage::memory_quality;
@@ -125,6 +125,10 @@ impl Tool for WriteMemoryTool {
                     "content": {
                         "type": "string",
                         "description": "The content to write to the memory file (replaces existing content)"
+                    },
+                    "pinned": {
+                        "type": "boolean",
+                        "description": "Pin the stored structured memory so it is included in context every turn, regardless of relevance. Reserve for standing rules and key facts."
                     }
                 }),
                 &["scope", "content"],
@@ -180,6 +184,7 @@ impl Tool for WriteMemoryTool {
 
         match std::fs::write(&path, content) {
             Ok(()) => {
+                let mut outcome = None;
                 let memory_content = content.trim().to_string();
                 if !memory_content.is_empty() {
                     if let Some(normalized) =
@@ -187,21 +192,57 @@ impl Tool for WriteMemoryTool {
                     {
                         if memory_quality::memory_quality_ok(&normalized) {
                             let chat_id = memory_chat_id;
-                            let _ = self
+                            outcome = self
                                 .memory_backend
-                                .insert_memory_with_metadata(
+                                .insert_memory_deduped(
                                     chat_id,
                                     &normalized,
                                     "KNOWLEDGE",
                                     "write_memory_tool",
                                     0.85,
                                 )
-                                .await;
+                                .await
+                                .ok();
                         }
                     }
                 }
 
-                ToolResult::success(format!("Memory saved to {} scope.", scope))
+                let pin = input
+                    .get("pinned")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let mut message = format!("Memory saved to {} scope.", scope);
+                match outcome {
+                    Some(outcome) => {
+                        if pin {
+                            match self
+                                .memory_backend
+                                .set_memory_pinned(outcome.memory_id(), true)
+                                .await
+                            {
+                                Ok(true) => message.push_str(" Pinned."),
+                                _ => message.push_str(" Could not pin the structured memory."),
+                            }
+                        }
+                        let message = match outcome {
+                            MemoryWriteOutcome::Stored(_) => message,
+                            MemoryWriteOutcome::Merged(id) => {
+                                format!("{message} Merged into existing memory #{id}.")
+                            }
+                            MemoryWriteOutcome::Skipped(id) => format!(
+                                "{message} Structured memory unchanged: near-duplicate of #{id}."
+                            ),
+                        };
+                        ToolResult::success(message).with_metadata(json!({
+                            "memory_write": outcome.as_str(),
+                            "memory_id": outcome.memory_id(),
+                        }))
+                    }
+                    None if pin => ToolResult::success(format!(
+                        "{message} Nothing was pinned: the content was not kept as a structured memory."
+                    )),
+                    None => ToolResult::success(message),
+                }
             }
             Err(e) => ToolResult::error(format!("Failed to write memory: {e}")),
         }
@@ -264,6 +305,53 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[tokio::test]
+    async fn test_write_memory_reports_structured_outcome() {
+        let dir = test_dir();
+        let db = test_db(&dir);
+        let backend = test_backend(db.clone());
+        assert!(backend
+            .find_semantic_duplicate(Some(7), None, "x")
+            .await
+            .is_err());
+        let tool = WriteMemoryTool::new(dir.to_str().unwrap(), db.clone(), backend);
+        let result = tool
+            .execute(json!({
+                "scope": "chat",
+                "chat_id": 7,
+                "content": "User prefers concise answers in English"
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        let metadata = result.metadata.unwrap();
+        // Without embeddings there is nothing to dedup against.
+        assert_eq!(metadata["memory_write"], "stored");
+        let id = metadata["memory_id"].as_i64().unwrap();
+        assert!(db.get_memory_by_id(id).unwrap().is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_write_memory_pinned() {
+        let dir = test_dir();
+        let db = test_db(&dir);
+        let tool =
+            WriteMemoryTool::new(dir.to_str().unwrap(), db.clone(), test_backend(db.clone()));
+        let result = tool
+            .execute(json!({
+                "scope": "chat",
+                "chat_id": 7,
+                "content": "Always reply in British English",
+                "pinned": true
+            }))
+            .await;
+        assert!(result.content.contains("Pinned."), "{}", result.content);
+        let pinned = db.get_pinned_memories(7).unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].content, "Always reply in British English");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[tokio::test]
     async fn test_write_and_read_memory_chat() {
         let dir = test_dir();
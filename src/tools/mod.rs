@@ -1,38 +1,53 @@
 pub mod activate_skill;
+pub mod ask_user;
 pub mod bash;
 pub mod browser;
+pub mod diff;
 pub mod edit_file;
 pub mod export_chat;
 pub mod glob;
 pub mod grep;
+pub mod http_request;
+pub mod list_directory;
 pub mod mcp;
 pub mod memory;
 pub mod read_file;
 pub mod schedule;
+pub mod scratch;
+pub mod search_messages;
 pub mod send_message;
 pub mod structured_memory;
 pub mod sub_agent;
 pub mod sync_skills;
 pub mod time_math;
 pub mod todo;
+pub mod wait;
 pub mod web_fetch;
 pub mod web_search;
 pub mod write_file;
 
-use std::sync::{Arc, OnceLock};
-use std::{path::PathBuf, time::Instant};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
-use crate::config::Config;
+use crate::config::{Config, WorkingDirRoots};
 use crate::memory_backend::MemoryBackend;
 use microclaw_channels::channel_adapter::ChannelRegistry;
 use microclaw_core::llm_types::ToolDefinition;
-use microclaw_storage::db::Database;
+use microclaw_storage::db::{call_blocking, Database, ToolAuditRecord};
+use microclaw_tools::input_coercion::coerce_tool_input;
+use microclaw_tools::input_defaults::{apply_input_defaults, unknown_default_keys};
+use microclaw_tools::redaction::SecretRedactor;
 pub use microclaw_tools::runtime::{
     auth_context_from_input, authorize_chat_access, resolve_tool_path, resolve_tool_working_dir,
     schema_object, tool_execution_policy, tool_risk, validate_execution_policy, Tool,
     ToolAuthContext, ToolResult, ToolRisk,
 };
-use microclaw_tools::runtime::{inject_auth_context, require_high_risk_approval};
+use microclaw_tools::runtime::{
+    high_risk_approval_status, inject_auth_context, require_high_risk_approval,
+};
 use microclaw_tools::sandbox::{ExtraMount, SandboxMode, SandboxRouter};
 
 pub struct ToolRegistry {
@@ -41,8 +56,115 @@ pub struct ToolRegistry {
     sandbox_mode: SandboxMode,
     sandbox_runtime_available: bool,
     cached_static_definitions: OnceLock<Vec<ToolDefinition>>,
+    /// MCP tools, swapped as a whole when MCP servers are reloaded.
+    mcp_tools: RwLock<Vec<Arc<dyn Tool>>>,
+    redactor: SecretRedactor,
+    /// Where tool executions are audited; None when `tool_audit_log` is off.
+    audit_db: Option<Arc<Database>>,
+    /// Per-turn values behind `scratch_set`/`scratch_get` and `{{scratch:key}}`.
+    scratch: scratch::ScratchStore,
+    /// When set, only [`DRY_RUN_SAFE_TOOLS`] run; every other call is stubbed.
+    dry_run: bool,
+}
+
+tokio::task_local! {
+    static TOOL_PROGRESS: tokio::sync::mpsc::UnboundedSender<String>;
+}
+
+/// Run `fut` with interim status from [`report_tool_progress`] sent to `tx`.
+/// Tools run in tasks spawned inside `fut` do not inherit it.
+pub async fn with_tool_progress<F: std::future::Future>(
+    tx: tokio::sync::mpsc::UnboundedSender<String>,
+    fut: F,
+) -> F::Output {
+    TOOL_PROGRESS.scope(tx, fut).await
 }
 
+/// Interim status from a running tool. Dropped when nobody is listening.
+pub fn report_tool_progress(message: impl Into<String>) {
+    let _ = TOOL_PROGRESS.try_with(|tx| tx.send(message.into()));
+}
+
+/// Longest tool input, in bytes, kept in an audit record.
+const AUDIT_INPUT_MAX_BYTES: usize = 4000;
+
+/// Names of every built-in tool either registry may expose; used to validate
+/// tool-keyed config such as `tool_timeout_overrides`.
+pub const BUILTIN_TOOL_NAMES: &[&str] = &[
+    "activate_skill",
+    "ask_user",
+    "bash",
+    "browser",
+    "calculate",
+    "cancel_scheduled_task",
+    "clawhub_install",
+    "clawhub_search",
+    "compare_time",
+    "diff",
+    "edit_file",
+    "export_chat",
+    "get_current_time",
+    "get_task_history",
+    "glob",
+    "grep",
+    "http_request",
+    "list_directory",
+    "list_scheduled_task_dlq",
+    "list_scheduled_tasks",
+    "list_task_templates",
+    "pause_scheduled_task",
+    "read_file",
+    "read_memory",
+    "replay_scheduled_task_dlq",
+    "resume_scheduled_task",
+    "save_task_template",
+    "schedule_task",
+    "scratch_get",
+    "scratch_set",
+    "search_messages",
+    "send_message",
+    "structured_memory_delete",
+    "structured_memory_search",
+    "structured_memory_update",
+    "structured_memory_write",
+    "sub_agent",
+    "sync_skills",
+    "todo_read",
+    "todo_write",
+    "wait",
+    "web_fetch",
+    "web_search",
+    "write_file",
+    "write_memory",
+];
+
+/// Read-only tools that still run under [`ToolRegistry::set_dry_run`]; any
+/// tool that writes files, memory, schedules or messages is stubbed instead.
+const DRY_RUN_SAFE_TOOLS: &[&str] = &[
+    "activate_skill",
+    "calculate",
+    "clawhub_search",
+    "compare_time",
+    "diff",
+    "get_current_time",
+    "get_task_history",
+    "glob",
+    "grep",
+    "list_directory",
+    "list_scheduled_task_dlq",
+    "list_scheduled_tasks",
+    "list_task_templates",
+    "read_file",
+    "read_memory",
+    "scratch_get",
+    "scratch_set",
+    "search_messages",
+    "structured_memory_search",
+    "todo_read",
+    "web_fetch",
+    "web_search",
+];
+
 impl ToolRegistry {
     pub fn new(
         config: &Config,
@@ -58,49 +180,70 @@ impl ToolRegistry {
                 e
             );
         }
+        let working_dir_roots = config.working_dir_roots();
         let skills_data_dir = config.skills_data_dir();
+        let extra_mounts = Self::build_extra_mounts(&working_dir_roots, &skills_data_dir);
+        let skills_root = Self::skills_search_root(&extra_mounts);
         let sandbox_router = Arc::new(SandboxRouter::new(
             config.sandbox.clone(),
             &working_dir,
-            Self::build_extra_mounts(&working_dir, &skills_data_dir),
+            extra_mounts,
         ));
         tracing::info!(
             mode = ?sandbox_router.mode(),
             backend = sandbox_router.backend_name(),
             "Sandbox initialized"
         );
+        let scratch = scratch::ScratchStore::new();
         let mut tools: Vec<Box<dyn Tool>> = vec![
             Box::new(
                 bash::BashTool::new_with_isolation(
-                    &config.working_dir,
-                    config.working_dir_isolation,
+                    working_dir_roots.clone(),
+                    config.resolved_working_dir_isolation(),
                 )
                 .with_default_timeout_secs(config.tool_timeout_secs("bash", 120))
-                .with_sandbox_router(sandbox_router.clone()),
+                .with_sandbox_router(sandbox_router.clone())
+                .with_no_tty(config.bash_no_tty)
+                .with_shell(config.bash_shell.clone())
+                .with_command_allowlist(config.bash_command_allowlist.clone()),
             ),
             Box::new(
                 browser::BrowserTool::new(&config.data_dir)
                     .with_default_timeout_secs(config.tool_timeout_secs("browser", 30)),
             ),
             Box::new(read_file::ReadFileTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
             Box::new(write_file::WriteFileTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
             Box::new(edit_file::EditFileTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
-            Box::new(glob::GlobTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+            Box::new(diff::DiffTool::new_with_isolation(
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
-            Box::new(grep::GrepTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+            Box::new(
+                glob::GlobTool::new_with_isolation(
+                    working_dir_roots.clone(),
+                    config.resolved_working_dir_isolation(),
+                )
+                .with_skills_root(skills_root.clone()),
+            ),
+            Box::new(
+                grep::GrepTool::new_with_isolation(
+                    working_dir_roots.clone(),
+                    config.resolved_working_dir_isolation(),
+                )
+                .with_skills_root(skills_root.clone()),
+            ),
+            Box::new(list_directory::ListDirectoryTool::new_with_isolation(
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
             Box::new(memory::ReadMemoryTool::new(&config.data_dir)),
             Box::new(memory::WriteMemoryTool::new(
@@ -108,16 +251,53 @@ impl ToolRegistry {
                 db.clone(),
                 memory_backend.clone(),
             )),
-            Box::new(web_fetch::WebFetchTool::new(
-                config.tool_timeout_secs("web_fetch", 15),
-                config.web_fetch_validation,
-                config.web_fetch_url_validation.clone(),
-            )),
-            Box::new(web_search::WebSearchTool::new(config.web_search.clone())),
+            Box::new(
+                web_fetch::WebFetchTool::new(
+                    config.tool_timeout_secs("web_fetch", 15),
+                    config.web_fetch_validation,
+                    config.web_fetch_url_validation.clone(),
+                )
+                .with_cache(config.web_fetch_cache.clone())
+                .with_proxy(config.web_fetch_proxy.clone())
+                .with_markdown(config.web_fetch_markdown.clone())
+                .with_host_rate(config.web_fetch_per_host_rate.clone())
+                .with_domain_policy(config.web_domain_policy())
+                .with_renderer(browser::BrowserTool::new(&config.data_dir)),
+            ),
+            Box::new(
+                http_request::HttpRequestTool::new(
+                    config.tool_timeout_secs("http_request", 30),
+                    config.web_fetch_validation,
+                    config.web_fetch_url_validation.clone(),
+                )
+                .with_proxy(config.web_fetch_proxy.clone())
+                .with_host_rate(config.web_fetch_per_host_rate.clone())
+                .with_auth(config.http_request_auth.clone())
+                .with_domain_policy(config.web_domain_policy()),
+            ),
+            Box::new(
+                web_search::WebSearchTool::new(config.web_search.clone())
+                    .with_domain_policy(config.web_domain_policy())
+                    .with_cache_ttl_secs(config.web_search_cache_ttl_secs),
+            ),
             Box::new(time_math::GetCurrentTimeTool::new(config.timezone.clone())),
             Box::new(time_math::CompareTimeTool::new(config.timezone.clone())),
             Box::new(time_math::CalculateTool::new()),
-            Box::new(send_message::SendMessageTool::new(
+            Box::new(wait::WaitTool::new(config.wait_tool_max_secs)),
+            Box::new(
+                send_message::SendMessageTool::new(
+                    channel_registry.clone(),
+                    db.clone(),
+                    if config.bot_username.trim().is_empty() {
+                        "bot".to_string()
+                    } else {
+                        config.bot_username.clone()
+                    },
+                    config.bot_username_overrides(),
+                )
+                .with_proactive_messaging(config.allow_proactive_messaging),
+            ),
+            Box::new(ask_user::AskUserTool::new(
                 channel_registry.clone(),
                 db.clone(),
                 if config.bot_username.trim().is_empty() {
@@ -127,12 +307,23 @@ impl ToolRegistry {
                 },
                 config.bot_username_overrides(),
             )),
-            Box::new(schedule::ScheduleTaskTool::new(
+            Box::new(
+                schedule::ScheduleTaskTool::new(
+                    channel_registry.clone(),
+                    db.clone(),
+                    config.timezone.clone(),
+                )
+                .with_proactive_messaging(config.allow_proactive_messaging),
+            ),
+            Box::new(schedule::ListTasksTool::new(
                 channel_registry.clone(),
                 db.clone(),
-                config.timezone.clone(),
             )),
-            Box::new(schedule::ListTasksTool::new(
+            Box::new(schedule::SaveTaskTemplateTool::new(
+                channel_registry.clone(),
+                db.clone(),
+            )),
+            Box::new(schedule::ListTaskTemplatesTool::new(
                 channel_registry.clone(),
                 db.clone(),
             )),
@@ -160,13 +351,16 @@ impl ToolRegistry {
                 channel_registry.clone(),
                 db.clone(),
             )),
-            Box::new(export_chat::ExportChatTool::new(
-                db.clone(),
-                &config.data_dir,
-            )),
+            Box::new(
+                export_chat::ExportChatTool::new(db.clone(), &config.data_dir)
+                    .with_redactor(Self::build_redactor(config).with_pii()),
+            ),
+            Box::new(search_messages::SearchMessagesTool::new(db.clone())),
             Box::new(sub_agent::SubAgentTool::new(config, db.clone())),
             Box::new(activate_skill::ActivateSkillTool::new(&skills_data_dir)),
             Box::new(sync_skills::SyncSkillsTool::new(&skills_data_dir)),
+            Box::new(scratch::ScratchSetTool::new(scratch.clone())),
+            Box::new(scratch::ScratchGetTool::new(scratch.clone())),
             Box::new(todo::TodoReadTool::new(&config.data_dir)),
             Box::new(todo::TodoWriteTool::new(&config.data_dir)),
             Box::new(structured_memory::StructuredMemorySearchTool::new(
@@ -181,6 +375,9 @@ impl ToolRegistry {
                 db.clone(),
                 memory_backend.clone(),
             )),
+            Box::new(structured_memory::StructuredMemoryWriteTool::new(
+                memory_backend.clone(),
+            )),
         ];
 
         // Add ClawHub tools if enabled
@@ -193,12 +390,19 @@ impl ToolRegistry {
             )));
         }
 
+        Self::warn_unknown_input_defaults(config, &tools);
+
         ToolRegistry {
             config: config.clone(),
             tools,
             sandbox_mode: sandbox_router.mode(),
             sandbox_runtime_available: sandbox_router.runtime_available(),
             cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: Self::build_redactor(config),
+            audit_db: config.tool_audit_log.then_some(db),
+            scratch,
+            dry_run: false,
         }
     }
 
@@ -212,59 +416,92 @@ impl ToolRegistry {
                 e
             );
         }
+        let working_dir_roots = config.working_dir_roots();
         let skills_data_dir = config.skills_data_dir();
+        let extra_mounts = Self::build_extra_mounts(&working_dir_roots, &skills_data_dir);
+        let skills_root = Self::skills_search_root(&extra_mounts);
         let sandbox_router = Arc::new(SandboxRouter::new(
             config.sandbox.clone(),
             &working_dir,
-            Self::build_extra_mounts(&working_dir, &skills_data_dir),
+            extra_mounts,
         ));
         let memory_backend = Arc::new(MemoryBackend::local_only(db.clone()));
         let tools: Vec<Box<dyn Tool>> = vec![
             Box::new(
                 bash::BashTool::new_with_isolation(
-                    &config.working_dir,
-                    config.working_dir_isolation,
+                    working_dir_roots.clone(),
+                    config.resolved_working_dir_isolation(),
                 )
                 .with_default_timeout_secs(config.tool_timeout_secs("bash", 120))
-                .with_sandbox_router(sandbox_router.clone()),
+                .with_sandbox_router(sandbox_router.clone())
+                .with_no_tty(config.bash_no_tty)
+                .with_shell(config.bash_shell.clone())
+                .with_command_allowlist(config.bash_command_allowlist.clone()),
             ),
             Box::new(
                 browser::BrowserTool::new(&config.data_dir)
                     .with_default_timeout_secs(config.tool_timeout_secs("browser", 30)),
             ),
             Box::new(read_file::ReadFileTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
             Box::new(write_file::WriteFileTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
             Box::new(edit_file::EditFileTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
-            Box::new(glob::GlobTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+            Box::new(diff::DiffTool::new_with_isolation(
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
-            Box::new(grep::GrepTool::new_with_isolation(
-                &config.working_dir,
-                config.working_dir_isolation,
+            Box::new(
+                glob::GlobTool::new_with_isolation(
+                    working_dir_roots.clone(),
+                    config.resolved_working_dir_isolation(),
+                )
+                .with_skills_root(skills_root.clone()),
+            ),
+            Box::new(
+                grep::GrepTool::new_with_isolation(
+                    working_dir_roots.clone(),
+                    config.resolved_working_dir_isolation(),
+                )
+                .with_skills_root(skills_root.clone()),
+            ),
+            Box::new(list_directory::ListDirectoryTool::new_with_isolation(
+                working_dir_roots.clone(),
+                config.resolved_working_dir_isolation(),
             )),
             Box::new(memory::ReadMemoryTool::new(&config.data_dir)),
-            Box::new(web_fetch::WebFetchTool::new(
-                config.tool_timeout_secs("web_fetch", 15),
-                config.web_fetch_validation,
-                config.web_fetch_url_validation.clone(),
-            )),
-            Box::new(web_search::WebSearchTool::new(config.web_search.clone())),
+            Box::new(
+                web_fetch::WebFetchTool::new(
+                    config.tool_timeout_secs("web_fetch", 15),
+                    config.web_fetch_validation,
+                    config.web_fetch_url_validation.clone(),
+                )
+                .with_cache(config.web_fetch_cache.clone())
+                .with_proxy(config.web_fetch_proxy.clone())
+                .with_markdown(config.web_fetch_markdown.clone())
+                .with_host_rate(config.web_fetch_per_host_rate.clone())
+                .with_domain_policy(config.web_domain_policy())
+                .with_renderer(browser::BrowserTool::new(&config.data_dir)),
+            ),
+            Box::new(
+                web_search::WebSearchTool::new(config.web_search.clone())
+                    .with_domain_policy(config.web_domain_policy())
+                    .with_cache_ttl_secs(config.web_search_cache_ttl_secs),
+            ),
             Box::new(time_math::GetCurrentTimeTool::new(config.timezone.clone())),
             Box::new(time_math::CompareTimeTool::new(config.timezone.clone())),
             Box::new(time_math::CalculateTool::new()),
+            Box::new(wait::WaitTool::new(config.wait_tool_max_secs)),
             Box::new(activate_skill::ActivateSkillTool::new(&skills_data_dir)),
             Box::new(structured_memory::StructuredMemorySearchTool::new(
-                db,
+                db.clone(),
                 memory_backend,
             )),
         ];
@@ -274,14 +511,57 @@ impl ToolRegistry {
             sandbox_mode: sandbox_router.mode(),
             sandbox_runtime_available: sandbox_router.runtime_available(),
             cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: Self::build_redactor(config),
+            audit_db: config.tool_audit_log.then_some(db),
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+        }
+    }
+
+    /// Log `tool_input_defaults` keys the target tool's schema does not declare.
+    fn warn_unknown_input_defaults(config: &Config, tools: &[Box<dyn Tool>]) {
+        for (name, defaults) in &config.tool_input_defaults {
+            let Some(tool) = tools.iter().find(|t| t.name() == name) else {
+                continue;
+            };
+            let unknown = unknown_default_keys(&tool.definition().input_schema, defaults);
+            if !unknown.is_empty() {
+                tracing::warn!(
+                    "tool_input_defaults.{name} sets {} which the tool does not accept",
+                    unknown.join(", ")
+                );
+            }
+        }
+    }
+
+    fn build_redactor(config: &Config) -> SecretRedactor {
+        SecretRedactor::new(&config.tool_result_redaction, config.known_secret_values())
+    }
+
+    fn redact_result(&self, name: &str, mut result: ToolResult) -> ToolResult {
+        let (content, changed) = self.redactor.redact(&result.content);
+        if changed {
+            tracing::info!(tool = name, "Redacted secrets from tool result");
+            result.content = content;
+            result.bytes = result.content.len();
         }
+        result
     }
 
-    fn build_extra_mounts(working_dir: &PathBuf, skills_data_dir: &str) -> Vec<ExtraMount> {
+    /// The skills directory as mounted read-only into the sandbox, if any.
+    fn skills_search_root(mounts: &[ExtraMount]) -> Option<PathBuf> {
+        mounts
+            .iter()
+            .find(|m| m.read_only)
+            .map(|m| m.host_path.clone())
+    }
+
+    fn build_extra_mounts(roots: &WorkingDirRoots, skills_data_dir: &str) -> Vec<ExtraMount> {
+        let canonical = |path: &Path| std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
         let skills_path = PathBuf::from(skills_data_dir);
-        let canonical_skills = std::fs::canonicalize(&skills_path).unwrap_or(skills_path.clone());
-        let canonical_working =
-            std::fs::canonicalize(working_dir).unwrap_or_else(|_| working_dir.clone());
+        let canonical_skills = canonical(&skills_path);
+        let canonical_working = canonical(&roots.default);
         let mut mounts = Vec::new();
         if canonical_skills.exists() && canonical_skills != canonical_working {
             mounts.push(ExtraMount {
@@ -289,6 +569,20 @@ impl ToolRegistry {
                 read_only: true,
             });
         }
+        // Channel roots outside the global working_dir need their own writable mount.
+        for root in roots.channels.values() {
+            let _ = std::fs::create_dir_all(root);
+            let host_path = canonical(root);
+            if host_path.starts_with(&canonical_working)
+                || mounts.iter().any(|m| m.host_path == host_path)
+            {
+                continue;
+            }
+            mounts.push(ExtraMount {
+                host_path,
+                read_only: false,
+            });
+        }
         mounts
     }
 
@@ -298,6 +592,38 @@ impl ToolRegistry {
         self.tools.push(tool);
     }
 
+    /// Whether the sandbox found a container runtime when the registry was built.
+    pub fn sandbox_runtime_available(&self) -> bool {
+        self.sandbox_runtime_available
+    }
+
+    /// Remove built-in tools whose dependencies are missing, so the model is
+    /// never offered them.
+    pub fn disable_tools(&mut self, names: &[String]) {
+        self.tools.retain(|tool| !names.iter().any(|n| n == tool.name()));
+        self.cached_static_definitions = OnceLock::new();
+    }
+
+    /// Stub every tool call outside [`DRY_RUN_SAFE_TOOLS`], so a turn can be
+    /// re-run without side effects.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Replace the MCP tool set. Calls already running keep the tool handle they started with.
+    pub fn set_mcp_tools(&self, tools: Vec<Arc<dyn Tool>>) {
+        *self.mcp_tools.write().unwrap_or_else(|e| e.into_inner()) = tools;
+    }
+
+    fn find_mcp_tool(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.mcp_tools
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|t| t.name() == name)
+            .cloned()
+    }
+
     pub fn definitions(&self) -> Vec<ToolDefinition> {
         let static_defs = self
             .cached_static_definitions
@@ -306,6 +632,17 @@ impl ToolRegistry {
         let mut out = static_defs;
         let mut existing: std::collections::HashSet<String> =
             out.iter().map(|d| d.name.to_ascii_lowercase()).collect();
+        for tool in self
+            .mcp_tools
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+        {
+            let def = tool.definition();
+            if existing.insert(def.name.to_ascii_lowercase()) {
+                out.push(def);
+            }
+        }
         for plugin_def in crate::plugins::dynamic_plugin_tool_definitions(&self.config) {
             let normalized = plugin_def.name.to_ascii_lowercase();
             if existing.insert(normalized) {
@@ -316,25 +653,117 @@ impl ToolRegistry {
     }
 
     pub async fn execute(&self, name: &str, input: serde_json::Value) -> ToolResult {
-        for tool in &self.tools {
-            if tool.name() == name {
-                let started = Instant::now();
-                let mut result = tool.execute(input).await;
-                result.duration_ms = Some(started.elapsed().as_millis());
-                result.bytes = result.content.len();
-                if result.is_error && result.error_type.is_none() {
-                    result.error_type = Some("tool_error".to_string());
-                }
-                if result.status_code.is_none() {
-                    result.status_code = Some(if result.is_error { 1 } else { 0 });
-                }
-                return result;
-            }
+        if let Some(tool) = self.tools.iter().find(|t| t.name() == name) {
+            return self.run_tool(tool.as_ref(), name, input).await;
+        }
+        if let Some(tool) = self.find_mcp_tool(name) {
+            return self.run_tool(tool.as_ref(), name, input).await;
         }
         ToolResult::error(format!("Unknown tool: {name}")).with_error_type("unknown_tool")
     }
 
+    async fn run_tool(
+        &self,
+        tool: &dyn Tool,
+        name: &str,
+        mut input: serde_json::Value,
+    ) -> ToolResult {
+        if let Some(defaults) = self.config.tool_input_defaults.get(name) {
+            let applied = apply_input_defaults(defaults, &mut input);
+            if !applied.is_empty() {
+                tracing::debug!(
+                    tool = name,
+                    applied = ?applied,
+                    "Applied configured tool input defaults"
+                );
+            }
+        }
+        if self.config.tool_input_coercion.applies_to(name) {
+            let coerced = coerce_tool_input(&tool.definition().input_schema, &mut input);
+            if !coerced.is_empty() {
+                tracing::info!(
+                    tool = name,
+                    coerced = ?coerced,
+                    "Coerced tool input to match schema"
+                );
+            }
+        }
+        let started = Instant::now();
+        let mut result = self.redact_result(name, tool.execute(input).await);
+        result.duration_ms = Some(started.elapsed().as_millis());
+        result.bytes = result.content.len();
+        if result.is_error && result.error_type.is_none() {
+            result.error_type = Some("tool_error".to_string());
+        }
+        if result.status_code.is_none() {
+            result.status_code = Some(if result.is_error { 1 } else { 0 });
+        }
+        result
+    }
+
+    /// Execute `name` on behalf of `auth`, enforcing execution policy and
+    /// high-risk approval, and append the outcome to the tool audit log.
+    /// `{{scratch:key}}` references are expanded first, so approval and the
+    /// audit record see the input the tool actually runs with.
     pub async fn execute_with_auth(
+        &self,
+        name: &str,
+        mut input: serde_json::Value,
+        auth: &ToolAuthContext,
+    ) -> ToolResult {
+        let started = Instant::now();
+        let expanded = self
+            .scratch
+            .expand_refs(scratch::scratch_scope(auth), &mut input);
+        let audit = self.audit_db.as_ref().map(|db| {
+            (
+                db.clone(),
+                self.audit_input(&input),
+                high_risk_approval_status(name, auth, &input),
+            )
+        });
+        let result = match expanded {
+            Ok(()) => self.execute_authorized(name, input, auth).await,
+            Err(e) => ToolResult::error(e).with_error_type("scratch_ref_unknown"),
+        };
+        if let Some((db, input, approval)) = audit {
+            let record = ToolAuditRecord {
+                id: 0,
+                created_at: String::new(),
+                channel: auth.caller_channel.clone(),
+                chat_id: auth.caller_chat_id,
+                tool_name: name.to_string(),
+                input,
+                risk: tool_risk(name).as_str().to_string(),
+                approval: approval.to_string(),
+                status: if result.is_error { "error" } else { "ok" }.to_string(),
+                error_type: result.error_type.clone(),
+                duration_ms: started.elapsed().as_millis() as i64,
+            };
+            if let Err(e) = call_blocking(db, move |d| d.log_tool_audit(&record)).await {
+                tracing::warn!(tool = name, "Failed to write tool audit record: {}", e);
+            }
+        }
+        result
+    }
+
+    /// Tool input as stored in the audit log: internal `__microclaw*` keys
+    /// dropped, secrets redacted, and capped at [`AUDIT_INPUT_MAX_BYTES`].
+    fn audit_input(&self, input: &serde_json::Value) -> String {
+        let mut input = input.clone();
+        if let Some(obj) = input.as_object_mut() {
+            obj.retain(|k, _| !k.starts_with("__microclaw"));
+        }
+        let (mut text, _) = self.redactor.redact(&input.to_string());
+        if text.len() > AUDIT_INPUT_MAX_BYTES {
+            let end = microclaw_core::text::floor_char_boundary(&text, AUDIT_INPUT_MAX_BYTES);
+            text.truncate(end);
+            text.push_str("...");
+        }
+        text
+    }
+
+    async fn execute_authorized(
         &self,
         name: &str,
         input: serde_json::Value,
@@ -358,16 +787,32 @@ impl ToolRegistry {
             "tool execution policy evaluated"
         );
         let input = inject_auth_context(input, auth);
-        let result = self.execute(name, input.clone()).await;
+        if self.dry_run && !DRY_RUN_SAFE_TOOLS.contains(&name) {
+            return ToolResult::success(format!("[dry run] `{name}` was not executed."));
+        }
+        let mut result = self.execute(name, input.clone()).await;
         if result.error_type.as_deref() == Some("unknown_tool") {
             if let Some(dynamic) =
                 crate::plugins::execute_dynamic_plugin_tool(&self.config, name, input).await
             {
-                return dynamic;
+                result = self.redact_result(name, dynamic);
             }
         }
+        if !result.is_error
+            && name != scratch::SCRATCH_SET_TOOL_NAME
+            && name != scratch::SCRATCH_GET_TOOL_NAME
+        {
+            self.scratch
+                .record_result(scratch::scratch_scope(auth), &result.content);
+        }
         result
     }
+
+    /// Drop the scratch values of run `run_id` in `chat_id`; called when the
+    /// run ends.
+    pub fn clear_scratch(&self, chat_id: i64, run_id: u64) {
+        self.scratch.clear((chat_id, run_id));
+    }
 }
 
 #[cfg(test)]
@@ -447,7 +892,7 @@ mod tests {
     #[test]
     fn test_resolve_tool_working_dir_shared() {
         let dir = resolve_tool_working_dir(
-            std::path::Path::new("/tmp/work"),
+            &WorkingDirRoots::from("/tmp/work"),
             WorkingDirIsolation::Shared,
             &json!({
                 "__microclaw_auth": {
@@ -463,7 +908,7 @@ mod tests {
     #[test]
     fn test_resolve_tool_working_dir_chat() {
         let dir = resolve_tool_working_dir(
-            std::path::Path::new("/tmp/work"),
+            &WorkingDirRoots::from("/tmp/work"),
             WorkingDirIsolation::Chat,
             &json!({
                 "__microclaw_auth": {
@@ -479,6 +924,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_tool_working_dir_policy_by_auth_context() {
+        let policy = crate::config::WorkingDirIsolationPolicy {
+            control_chats: WorkingDirIsolation::Shared,
+            default: WorkingDirIsolation::Chat,
+        };
+        let input = |chat_id: i64| {
+            json!({
+                "__microclaw_auth": {
+                    "caller_channel": "feishu",
+                    "caller_chat_id": chat_id,
+                    "control_chat_ids": [1]
+                }
+            })
+        };
+        let base = WorkingDirRoots::from("/tmp/work");
+        assert_eq!(
+            resolve_tool_working_dir(&base, policy, &input(1)),
+            std::path::PathBuf::from("/tmp/work/shared")
+        );
+        assert_eq!(
+            resolve_tool_working_dir(&base, policy, &input(2)),
+            std::path::PathBuf::from("/tmp/work/chat/feishu/2")
+        );
+    }
+
+    #[test]
+    fn test_resolve_tool_working_dir_uses_channel_root() {
+        let mut roots = WorkingDirRoots::from("/tmp/work");
+        roots
+            .channels
+            .insert("feishu.sales".into(), "/tmp/work-sales".into());
+        let input = |channel: &str| {
+            json!({
+                "__microclaw_auth": {
+                    "caller_channel": channel,
+                    "caller_chat_id": 7,
+                    "control_chat_ids": []
+                }
+            })
+        };
+        assert_eq!(
+            resolve_tool_working_dir(&roots, WorkingDirIsolation::Chat, &input("feishu.sales")),
+            std::path::PathBuf::from("/tmp/work-sales/chat/feishu_sales/7")
+        );
+        assert_eq!(
+            resolve_tool_working_dir(&roots, WorkingDirIsolation::Shared, &input("feishu")),
+            std::path::PathBuf::from("/tmp/work/shared")
+        );
+        assert_eq!(
+            resolve_tool_working_dir(&roots, WorkingDirIsolation::Chat, &json!({})),
+            std::path::PathBuf::from("/tmp/work/shared")
+        );
+    }
+
     struct DummyTool {
         tool_name: String,
     }
@@ -502,15 +1002,289 @@ mod tests {
         }
     }
 
+    struct LeakyTool;
+
+    #[async_trait]
+    impl Tool for LeakyTool {
+        fn name(&self) -> &str {
+            "bash"
+        }
+
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "bash".into(),
+                description: "leaky".into(),
+                input_schema: schema_object(json!({}), &[]),
+            }
+        }
+
+        async fn execute(&self, _input: serde_json::Value) -> ToolResult {
+            ToolResult::success(
+                "ANTHROPIC_API_KEY=sk-ant-REDACTED\nfeishu secret configured-app-secret".into(),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_redacts_secrets_in_tool_result() {
+        let mut config = crate::config::Config::test_defaults();
+        config.channels.insert(
+            "feishu".into(),
+            serde_yaml::from_str("app_id: cli_123\napp_secret: configured-app-secret").unwrap(),
+        );
+        let registry = ToolRegistry {
+            redactor: ToolRegistry::build_redactor(&config),
+            config,
+            tools: vec![Box::new(LeakyTool)],
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+        };
+
+        let result = registry.execute("bash", json!({})).await;
+        assert!(!result.is_error);
+        assert!(!result.content.contains("sk-ant-abcdef"));
+        assert!(!result.content.contains("configured-app-secret"));
+        assert!(result.content.contains("***redacted***"));
+        assert_eq!(result.bytes, result.content.len());
+    }
+
+    struct EchoTool;
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "echo".into(),
+                description: "echo".into(),
+                input_schema: schema_object(
+                    json!({
+                        "limit": {"type": "integer"},
+                        "tags": {"type": "array", "items": {"type": "string"}}
+                    }),
+                    &[],
+                ),
+            }
+        }
+
+        async fn execute(&self, input: serde_json::Value) -> ToolResult {
+            ToolResult::success(input.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_coerces_input_only_for_opted_in_tools() {
+        let registry_with = |tools: Vec<String>| {
+            let mut config = crate::config::Config::test_defaults();
+            config.tool_input_coercion.tools = tools;
+            ToolRegistry {
+                config,
+                tools: vec![Box::new(EchoTool)],
+                sandbox_mode: SandboxMode::Off,
+                sandbox_runtime_available: false,
+                cached_static_definitions: OnceLock::new(),
+                mcp_tools: RwLock::default(),
+                redactor: SecretRedactor::default(),
+                audit_db: None,
+                scratch: scratch::ScratchStore::new(),
+                dry_run: false,
+            }
+        };
+        let input = json!({"limit": "5", "tags": "a"});
+
+        let coerced = registry_with(vec!["echo".into()])
+            .execute("echo", input.clone())
+            .await;
+        let echoed: serde_json::Value = serde_json::from_str(&coerced.content).unwrap();
+        assert_eq!(echoed, json!({"limit": 5, "tags": ["a"]}));
+
+        let untouched = registry_with(vec![]).execute("echo", input.clone()).await;
+        let echoed: serde_json::Value = serde_json::from_str(&untouched.content).unwrap();
+        assert_eq!(echoed, input);
+    }
+
+    #[tokio::test]
+    async fn test_execute_merges_configured_input_defaults_under_model_input() {
+        let mut config = crate::config::Config::test_defaults();
+        config.tool_input_defaults.insert(
+            "echo".into(),
+            json!({"limit": "10", "tags": ["x"]})
+                .as_object()
+                .cloned()
+                .unwrap(),
+        );
+        config.tool_input_coercion.tools = vec!["echo".into()];
+        let registry = ToolRegistry {
+            config,
+            tools: vec![Box::new(EchoTool)],
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: SecretRedactor::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+        };
+
+        let result = registry.execute("echo", json!({"tags": ["model"]})).await;
+        let echoed: serde_json::Value = serde_json::from_str(&result.content).unwrap();
+        assert_eq!(echoed, json!({"limit": 10, "tags": ["model"]}));
+
+        let result = registry.execute("echo", json!({"limit": 3})).await;
+        let echoed: serde_json::Value = serde_json::from_str(&result.content).unwrap();
+        assert_eq!(echoed, json!({"limit": 3, "tags": ["x"]}));
+    }
+
+    #[tokio::test]
+    async fn test_scratch_refs_expand_in_later_tool_inputs() {
+        let store = scratch::ScratchStore::new();
+        let registry = ToolRegistry {
+            config: crate::config::Config::test_defaults(),
+            tools: vec![
+                Box::new(EchoTool),
+                Box::new(scratch::ScratchSetTool::new(store.clone())),
+            ],
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: SecretRedactor::default(),
+            audit_db: None,
+            scratch: store,
+            dry_run: false,
+        };
+        let auth = ToolAuthContext {
+            caller_channel: "web".into(),
+            caller_chat_id: 5,
+            control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: Some("alice".into()),
+            run_id: 0,
+        };
+
+        let first = registry
+            .execute_with_auth("echo", json!({"tags": ["big page"]}), &auth)
+            .await;
+        let stored = registry
+            .execute_with_auth(
+                "scratch_set",
+                json!({"key": "page", "from_last_result": true}),
+                &auth,
+            )
+            .await;
+        assert!(!stored.is_error, "{}", stored.content);
+
+        let echoed = registry
+            .execute_with_auth("echo", json!({"tags": ["{{scratch:page}}"]}), &auth)
+            .await;
+        let echoed: serde_json::Value = serde_json::from_str(&echoed.content).unwrap();
+        assert_eq!(echoed["tags"][0], first.content);
+
+        registry.clear_scratch(5, 0);
+        let missing = registry
+            .execute_with_auth("echo", json!({"tags": ["{{scratch:page}}"]}), &auth)
+            .await;
+        assert_eq!(missing.error_type.as_deref(), Some("scratch_ref_unknown"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_stubs_tools_with_side_effects() {
+        let store = scratch::ScratchStore::new();
+        let mut registry = ToolRegistry {
+            config: crate::config::Config::test_defaults(),
+            tools: vec![
+                Box::new(EchoTool),
+                Box::new(scratch::ScratchSetTool::new(store.clone())),
+            ],
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: SecretRedactor::default(),
+            audit_db: None,
+            scratch: store,
+            dry_run: false,
+        };
+        registry.set_dry_run(true);
+        let auth = ToolAuthContext {
+            caller_channel: "web".into(),
+            caller_chat_id: 5,
+            control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: Some("alice".into()),
+            run_id: 0,
+        };
+
+        let stubbed = registry
+            .execute_with_auth("echo", json!({"tags": ["x"]}), &auth)
+            .await;
+        assert!(!stubbed.is_error);
+        assert_eq!(stubbed.content, "[dry run] `echo` was not executed.");
+
+        let stored = registry
+            .execute_with_auth("scratch_set", json!({"key": "k", "value": "v"}), &auth)
+            .await;
+        assert!(!stored.is_error, "{}", stored.content);
+        assert!(!stored.content.starts_with("[dry run]"));
+    }
+
     #[test]
     fn test_tool_risk_levels() {
         assert_eq!(tool_risk("bash"), ToolRisk::High);
         assert_eq!(tool_risk("write_file"), ToolRisk::Medium);
         assert_eq!(tool_risk("pause_scheduled_task"), ToolRisk::Medium);
         assert_eq!(tool_risk("sync_skills"), ToolRisk::Medium);
+        assert_eq!(tool_risk("http_request"), ToolRisk::Medium);
         assert_eq!(tool_risk("read_file"), ToolRisk::Low);
     }
 
+    #[tokio::test]
+    async fn test_set_mcp_tools_swaps_definitions_and_keeps_running_handles() {
+        let registry = ToolRegistry {
+            config: crate::config::Config::test_defaults(),
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+            redactor: SecretRedactor::default(),
+            tools: vec![Box::new(DummyTool {
+                tool_name: "read_file".into(),
+            })],
+        };
+        let mcp_tool = |name: &str| -> Arc<dyn Tool> {
+            Arc::new(DummyTool {
+                tool_name: name.into(),
+            })
+        };
+        let has = |name: &str| registry.definitions().iter().any(|d| d.name == name);
+
+        registry.set_mcp_tools(vec![mcp_tool("mcp_a_x"), mcp_tool("read_file")]);
+        assert!(has("mcp_a_x"));
+        let names: Vec<String> = registry.definitions().into_iter().map(|d| d.name).collect();
+        assert_eq!(names.iter().filter(|n| *n == "read_file").count(), 1);
+        assert_eq!(registry.execute("mcp_a_x", json!({})).await.content, "ok");
+
+        let in_flight = registry.find_mcp_tool("mcp_a_x").unwrap();
+        registry.set_mcp_tools(vec![mcp_tool("mcp_b_y")]);
+        assert!(!has("mcp_a_x"));
+        assert!(has("mcp_b_y"));
+        let gone = registry.execute("mcp_a_x", json!({})).await;
+        assert_eq!(gone.error_type.as_deref(), Some("unknown_tool"));
+        assert_eq!(in_flight.execute(json!({})).await.content, "ok");
+    }
+
     #[tokio::test]
     async fn test_high_risk_tool_requires_explicit_approval_on_web() {
         let registry = ToolRegistry {
@@ -518,6 +1292,11 @@ mod tests {
             sandbox_mode: SandboxMode::Off,
             sandbox_runtime_available: false,
             cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+            redactor: SecretRedactor::default(),
             tools: vec![Box::new(DummyTool {
                 tool_name: "bash".into(),
             })],
@@ -526,6 +1305,9 @@ mod tests {
             caller_channel: "web".into(),
             caller_chat_id: 1,
             control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: None,
+            run_id: 0,
         };
 
         let first = registry.execute_with_auth("bash", json!({}), &auth).await;
@@ -554,6 +1336,11 @@ mod tests {
             sandbox_mode: SandboxMode::Off,
             sandbox_runtime_available: false,
             cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+            redactor: SecretRedactor::default(),
             tools: vec![Box::new(DummyTool {
                 tool_name: "bash".into(),
             })],
@@ -562,6 +1349,9 @@ mod tests {
             caller_channel: "telegram".into(),
             caller_chat_id: 123,
             control_chat_ids: vec![123],
+            caller_is_admin: false,
+            caller_sender_id: None,
+            run_id: 0,
         };
 
         let first = registry.execute_with_auth("bash", json!({}), &auth).await;
@@ -586,6 +1376,11 @@ mod tests {
             sandbox_mode: SandboxMode::Off,
             sandbox_runtime_available: false,
             cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+            redactor: SecretRedactor::default(),
             tools: vec![Box::new(DummyTool {
                 tool_name: "write_file".into(),
             })],
@@ -594,6 +1389,9 @@ mod tests {
             caller_channel: "web".into(),
             caller_chat_id: 1,
             control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: None,
+            run_id: 0,
         };
 
         let result = registry
@@ -603,6 +1401,135 @@ mod tests {
         assert_eq!(result.content, "ok");
     }
 
+    #[tokio::test]
+    async fn test_execute_with_auth_writes_tool_audit_log() {
+        let root = std::env::temp_dir().join(format!("microclaw_audit_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(root.to_str().unwrap()).unwrap());
+        let registry = ToolRegistry {
+            config: crate::config::Config::test_defaults(),
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: SecretRedactor::new(
+                &microclaw_tools::redaction::ToolResultRedactionConfig::default(),
+                [""; 0],
+            ),
+            audit_db: Some(db.clone()),
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+            tools: vec![Box::new(DummyTool {
+                tool_name: "bash".into(),
+            })],
+        };
+        let auth = ToolAuthContext {
+            caller_channel: "web".into(),
+            caller_chat_id: 42,
+            control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: None,
+            run_id: 0,
+        };
+
+        let denied = registry
+            .execute_with_auth("bash", json!({"command": "ls"}), &auth)
+            .await;
+        assert!(denied.is_error);
+        let approved = registry
+            .execute_with_auth(
+                "bash",
+                json!({
+                    "command": "echo OPENAI_API_KEY=sk-proj-abcdefghijklmnopqrstuvwx",
+                    "__microclaw_high_risk_approved": true
+                }),
+                &auth,
+            )
+            .await;
+        assert!(!approved.is_error);
+
+        let logs = db
+            .list_tool_audit_logs(&microclaw_storage::db::ToolAuditFilter {
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(logs.len(), 2);
+        let (ok, blocked) = (&logs[0], &logs[1]);
+        assert_eq!(blocked.approval, "denied");
+        assert_eq!(blocked.status, "error");
+        assert_eq!(blocked.error_type.as_deref(), Some("approval_required"));
+        assert_eq!(ok.channel, "web");
+        assert_eq!(ok.chat_id, 42);
+        assert_eq!(ok.tool_name, "bash");
+        assert_eq!(ok.risk, "high");
+        assert_eq!(ok.approval, "approved");
+        assert_eq!(ok.status, "ok");
+        assert!(!ok.input.contains("sk-proj-abcdef"), "{}", ok.input);
+        assert!(!ok.input.contains("__microclaw"), "{}", ok.input);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_tool_audit_log_records_expanded_scratch_refs() {
+        let root = std::env::temp_dir().join(format!("microclaw_audit_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(root.to_str().unwrap()).unwrap());
+        let store = scratch::ScratchStore::new();
+        let registry = ToolRegistry {
+            config: crate::config::Config::test_defaults(),
+            sandbox_mode: SandboxMode::Off,
+            sandbox_runtime_available: false,
+            cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            redactor: SecretRedactor::new(
+                &microclaw_tools::redaction::ToolResultRedactionConfig::default(),
+                [""; 0],
+            ),
+            audit_db: Some(db.clone()),
+            scratch: store.clone(),
+            dry_run: false,
+            tools: vec![
+                Box::new(EchoTool),
+                Box::new(scratch::ScratchSetTool::new(store)),
+            ],
+        };
+        let auth = ToolAuthContext {
+            caller_channel: "web".into(),
+            caller_chat_id: 42,
+            control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: None,
+            run_id: 7,
+        };
+
+        registry
+            .execute_with_auth(
+                "scratch_set",
+                json!({"key": "q", "value": "rows OPENAI_API_KEY=sk-proj-abcdefghijklmnopqrstuvwx"}),
+                &auth,
+            )
+            .await;
+        let echoed = registry
+            .execute_with_auth("echo", json!({"tags": ["{{scratch:q}}"]}), &auth)
+            .await;
+        assert!(!echoed.is_error, "{}", echoed.content);
+
+        let logs = db
+            .list_tool_audit_logs(&microclaw_storage::db::ToolAuditFilter {
+                tool_name: Some("echo".into()),
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+        let input = &logs[0].input;
+        assert!(input.contains("rows"), "{input}");
+        assert!(!input.contains("{{scratch:"), "{input}");
+        assert!(!input.contains("sk-proj-abcdef"), "{input}");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
     #[tokio::test]
     async fn test_dynamic_plugin_tool_executes_without_restart() {
         let root = std::env::temp_dir().join(format!("microclaw_plugin_{}", uuid::Uuid::new_v4()));
@@ -639,11 +1566,19 @@ tools:
             sandbox_mode: SandboxMode::Off,
             sandbox_runtime_available: false,
             cached_static_definitions: OnceLock::new(),
+            mcp_tools: RwLock::default(),
+            audit_db: None,
+            scratch: scratch::ScratchStore::new(),
+            dry_run: false,
+            redactor: SecretRedactor::default(),
         };
         let auth = ToolAuthContext {
             caller_channel: "web".into(),
             caller_chat_id: 7,
             control_chat_ids: vec![],
+            caller_is_admin: false,
+            caller_sender_id: None,
+            run_id: 0,
         };
 
         let defs = registry.definitions();
@@ -657,4 +1592,37 @@ tools:
 
         let _ = std::fs::remove_dir_all(root);
     }
+
+    #[test]
+    fn test_builtin_tool_names_cover_registries() {
+        let root =
+            std::env::temp_dir().join(format!("microclaw_tool_names_{}", uuid::Uuid::new_v4()));
+        let mut config = Config::test_defaults();
+        config.data_dir = root.to_string_lossy().to_string();
+        config.working_dir = root.join("work").to_string_lossy().to_string();
+        config.clawhub.agent_tools_enabled = true;
+        let db = Arc::new(Database::new(root.join("runtime").to_str().unwrap()).unwrap());
+        let memory_backend = Arc::new(MemoryBackend::local_only(db.clone()));
+
+        let full = ToolRegistry::new(
+            &config,
+            Arc::new(ChannelRegistry::new()),
+            db.clone(),
+            memory_backend,
+        );
+        let sub = ToolRegistry::new_sub_agent(&config, db);
+        for tool in full.tools.iter().chain(sub.tools.iter()) {
+            assert!(
+                BUILTIN_TOOL_NAMES.contains(&tool.name()),
+                "{} missing from BUILTIN_TOOL_NAMES",
+                tool.name()
+            );
+        }
+
+        let mut full = full;
+        assert!(full.definitions().iter().any(|d| d.name == "browser"));
+        full.disable_tools(&["browser".to_string()]);
+        assert!(!full.definitions().iter().any(|d| d.name == "browser"));
+        let _ = std::fs::remove_dir_all(root);
+    }
 }
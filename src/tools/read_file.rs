@@ -1,16 +1,15 @@
 use async_trait::async_trait;
 use serde_json::json;
-use std::path::PathBuf;
 use tracing::info;
 
-use crate::config::WorkingDirIsolation;
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_core::llm_types::ToolDefinition;
 
 use super::{schema_object, Tool, ToolResult};
 
 pub struct ReadFileTool {
-    working_dir: PathBuf,
-    working_dir_isolation: WorkingDirIsolation,
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
 }
 
 impl ReadFileTool {
@@ -19,16 +18,129 @@ impl ReadFileTool {
     }
 
     pub fn new_with_isolation(
-        working_dir: &str,
-        working_dir_isolation: WorkingDirIsolation,
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
     ) -> Self {
         Self {
-            working_dir: PathBuf::from(working_dir),
-            working_dir_isolation,
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
         }
     }
 }
 
+/// Longest outline label, in characters.
+const OUTLINE_LABEL_MAX_CHARS: usize = 160;
+
+/// Declaration patterns, matched against the trimmed line, for the code
+/// languages `outline: true` understands.
+fn outline_patterns(extension: &str) -> Option<&'static [&'static str]> {
+    let patterns: &'static [&'static str] = match extension {
+        "rs" => &[
+            r#"^(pub(\([^)]*\))?\s+)?((async|const|unsafe|default|extern\s+"[^"]*")\s+)*(fn|struct|enum|union|trait|impl|mod|type)\b"#,
+            r"^macro_rules!",
+        ],
+        "py" | "pyi" => &[r"^(async\s+)?def\s+\w+", r"^class\s+\w+"],
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => &[
+            r"^(export\s+(default\s+)?)?(async\s+)?function\b",
+            r"^(export\s+(default\s+)?)?(abstract\s+)?class\s+\w+",
+            r"^(export\s+)?(declare\s+)?(interface|type|enum|namespace)\s+\w+",
+            r"^(export\s+)?(const|let|var)\s+\w+\s*(:[^=]+)?=\s*(async\s+)?(function\b|(\([^)]*\)|\w+)\s*(:[^=]+)?=>)",
+        ],
+        "go" => &[r"^func\b", r"^type\s+\w+"],
+        "java" | "kt" | "kts" | "scala" | "cs" => &[
+            r"^((public|private|protected|internal|static|final|abstract|sealed|open|data|partial)\s+)*(class|interface|enum|record|object|struct|namespace)\s+\w+",
+            r"^((public|private|protected|internal|static|final|abstract|override|suspend|async|virtual|synchronized)\s+)+[\w<>\[\],.?\s]*\w+\s*\(",
+            r"^fun\s",
+        ],
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" => &[
+            r"^(typedef\s+)?(struct|enum|union|class|namespace)\s+\w+\s*(\{|:|$)",
+            r"^[A-Za-z_][\w\s\*&:<>,]*[\s\*&]\**~?[A-Za-z_][\w:]*\s*\([^;]*$",
+        ],
+        "rb" => &[r"^(class|module|def)\s"],
+        "sh" | "bash" | "zsh" => &[r"^function\s+\w+", r"^[A-Za-z_][\w-]*\s*\(\)\s*(\{|$)"],
+        "php" => &[
+            r"^((abstract|final|public|private|protected|static)\s+)*(function|class|interface|trait|enum)\s+\w+",
+        ],
+        "swift" => &[
+            r"^((public|private|internal|fileprivate|open|static|final|override)\s+)*(func|class|struct|enum|protocol|extension)\s+\w+",
+        ],
+        _ => return None,
+    };
+    Some(patterns)
+}
+
+fn outline_label(line: &str) -> String {
+    let label = line
+        .trim_end()
+        .trim_end_matches('{')
+        .trim_end_matches(':')
+        .trim_end();
+    if label.chars().count() > OUTLINE_LABEL_MAX_CHARS {
+        let cut: String = label.chars().take(OUTLINE_LABEL_MAX_CHARS).collect();
+        format!("{cut}…")
+    } else {
+        label.to_string()
+    }
+}
+
+/// Markdown headings, indented by level. Fenced code blocks are skipped.
+fn markdown_outline(lines: &[&str]) -> Vec<(usize, String)> {
+    let mut out = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with([' ', '\t']) {
+            let title = trimmed[level..].trim().trim_end_matches('#').trim_end();
+            out.push((
+                idx + 1,
+                format!("{}{} {}", "  ".repeat(level - 1), &trimmed[..level], title),
+            ));
+        }
+    }
+    out
+}
+
+/// `(line number, label)` for each declaration or heading in `content`, or
+/// `None` when the file type is not supported.
+fn build_outline(extension: &str, content: &str) -> Option<Vec<(usize, String)>> {
+    let lines: Vec<&str> = content.lines().collect();
+    if matches!(extension, "md" | "markdown" | "mdx") {
+        return Some(markdown_outline(&lines));
+    }
+    let patterns = outline_patterns(extension)?
+        .iter()
+        .filter_map(|p| regex::Regex::new(p).ok())
+        .collect::<Vec<_>>();
+    Some(
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| {
+                let trimmed = line.trim_start();
+                patterns.iter().any(|re| re.is_match(trimmed))
+            })
+            .map(|(idx, line)| {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                (
+                    idx + 1,
+                    format!("{indent}{}", outline_label(line.trim_start())),
+                )
+            })
+            .collect(),
+    )
+}
+
 #[async_trait]
 impl Tool for ReadFileTool {
     fn name(&self) -> &str {
@@ -38,7 +150,7 @@ impl Tool for ReadFileTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "read_file".into(),
-            description: "Read the contents of a file at the given path. Returns the file content with line numbers.".into(),
+            description: "Read the contents of a file at the given path. Returns the file content with line numbers. For large code or Markdown files, set outline=true first to list functions, types and headings with their line numbers, then read the range you need with offset/limit.".into(),
             input_schema: schema_object(
                 json!({
                     "path": {
@@ -52,6 +164,10 @@ impl Tool for ReadFileTool {
                     "limit": {
                         "type": "integer",
                         "description": "Maximum number of lines to read"
+                    },
+                    "outline": {
+                        "type": "boolean",
+                        "description": "Return a structural outline (declarations or Markdown headings with line numbers) instead of the contents"
                     }
                 }),
                 &["path"],
@@ -70,7 +186,7 @@ impl Tool for ReadFileTool {
         let resolved_path_str = resolved_path.to_string_lossy().to_string();
 
         if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_path_str) {
-            return ToolResult::error(msg);
+            return ToolResult::error(msg).with_error_type("path_policy_blocked");
         }
 
         info!("Reading file: {}", resolved_path.display());
@@ -80,6 +196,37 @@ impl Tool for ReadFileTool {
             Err(e) => return ToolResult::error(format!("Failed to read file: {e}")),
         };
 
+        if input
+            .get("outline")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            let extension = resolved_path
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let Some(entries) = build_outline(&extension, &content) else {
+                return ToolResult::error(format!(
+                    "Outline is not supported for '{path}'; read it with offset/limit instead"
+                ))
+                .with_error_type("outline_unsupported");
+            };
+            let total = content.lines().count();
+            if entries.is_empty() {
+                return ToolResult::success(format!(
+                    "No declarations or headings found in {path} ({total} lines)"
+                ));
+            }
+            let body: Vec<String> = entries
+                .iter()
+                .map(|(line, label)| format!("{line:>6}\t{label}"))
+                .collect();
+            return ToolResult::success(format!(
+                "Outline of {path} ({total} lines):\n{}",
+                body.join("\n")
+            ));
+        }
+
         let lines: Vec<&str> = content.lines().collect();
         let offset = input
             .get("offset")
@@ -146,6 +293,78 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn test_build_outline_rust_keeps_nesting() {
+        let src = "use std::fmt;\n\npub struct Foo {\n    x: u8,\n}\n\nimpl Foo {\n    pub async fn bar(&self) -> u8 {\n        self.x\n    }\n}\n\nfn main() {}\n";
+        let outline = build_outline("rs", src).unwrap();
+        assert_eq!(
+            outline,
+            vec![
+                (3, "pub struct Foo".to_string()),
+                (7, "impl Foo".to_string()),
+                (8, "    pub async fn bar(&self) -> u8".to_string()),
+                (13, "fn main() {}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_outline_python_and_unknown_extension() {
+        let src = "import os\n\nclass A:\n    def run(self):\n        pass\n\nasync def main():\n    x = 1\n";
+        let outline = build_outline("py", src).unwrap();
+        let lines: Vec<usize> = outline.iter().map(|(l, _)| *l).collect();
+        assert_eq!(lines, vec![3, 4, 7]);
+        assert_eq!(outline[1].1, "    def run(self)");
+        assert!(build_outline("bin", src).is_none());
+    }
+
+    #[test]
+    fn test_markdown_outline_skips_code_fences() {
+        let src = "# Title\n\nText\n\n```sh\n# not a heading\n```\n\n## Setup ##\n#hashtag\n### Details\n";
+        assert_eq!(
+            build_outline("md", src).unwrap(),
+            vec![
+                (1, "# Title".to_string()),
+                (9, "  ## Setup".to_string()),
+                (11, "    ### Details".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_file_outline_mode() {
+        let dir = std::env::temp_dir().join(format!("microclaw_rf4_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("lib.ts");
+        std::fs::write(
+            &file,
+            "import x from 'y';\n\nexport interface Opts {}\n\nexport async function load(o: Opts) {\n  return 1;\n}\nexport const add = (a: number) => a + 1;\n",
+        )
+        .unwrap();
+        let notes = dir.join("notes.txt");
+        std::fs::write(&notes, "plain").unwrap();
+
+        let tool = ReadFileTool::new(".");
+        let path = file.to_str().unwrap();
+        let result = tool.execute(json!({"path": path, "outline": true})).await;
+        assert!(!result.is_error);
+        assert_eq!(
+            result.content,
+            format!(
+                "Outline of {path} (8 lines):\n     3\texport interface Opts {{}}\n     5\texport async function load(o: Opts)\n     8\texport const add = (a: number) => a + 1;"
+            )
+        );
+        assert!(!result.content.contains("return 1"));
+
+        let result = tool
+            .execute(json!({"path": notes.to_str().unwrap(), "outline": true}))
+            .await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("outline_unsupported"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[tokio::test]
     async fn test_read_file_not_found() {
         let tool = ReadFileTool::new(".");
@@ -154,6 +373,14 @@ mod tests {
         assert!(result.content.contains("Failed to read file"));
     }
 
+    #[tokio::test]
+    async fn test_read_file_blocked_path_sets_error_type() {
+        let tool = ReadFileTool::new(".");
+        let result = tool.execute(json!({"path": "/etc/shadow"})).await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("path_policy_blocked"));
+    }
+
     #[tokio::test]
     async fn test_read_file_missing_path() {
         let tool = ReadFileTool::new(".");
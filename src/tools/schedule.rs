@@ -2,26 +2,63 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::{NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
 use serde_json::json;
 
-use super::{authorize_chat_access, schema_object, Tool, ToolResult};
-use microclaw_channels::channel::enforce_channel_policy;
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use microclaw_channels::channel::{
+    enforce_channel_policy, get_required_chat_routing, ConversationKind,
+};
 use microclaw_channels::channel_adapter::ChannelRegistry;
 use microclaw_core::llm_types::ToolDefinition;
 use microclaw_storage::db::{call_blocking, Database};
 
+/// Next fire time of `schedule` strictly after `after`, evaluated in the task's
+/// timezone. Local times skipped by a spring-forward gap never fire; local times
+/// repeated by a fall-back overlap fire once, on their first occurrence.
+pub(crate) fn next_cron_run_after(
+    schedule: &cron::Schedule,
+    tz: chrono_tz::Tz,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    schedule
+        .after(&after.with_timezone(&tz))
+        .filter(|candidate| candidate.with_timezone(&Utc) > after)
+        .find(
+            |candidate| match tz.from_local_datetime(&candidate.naive_local()) {
+                LocalResult::Ambiguous(earliest, _) => earliest == *candidate,
+                _ => true,
+            },
+        )
+        .map(|next| next.with_timezone(&Utc))
+}
+
 fn compute_next_run(cron_expr: &str, tz_name: &str) -> Result<String, String> {
     let tz: chrono_tz::Tz = tz_name
         .parse()
         .map_err(|_| format!("Invalid timezone: {tz_name}"))?;
     let schedule =
         cron::Schedule::from_str(cron_expr).map_err(|e| format!("Invalid cron expression: {e}"))?;
-    let next = schedule
-        .upcoming(tz)
-        .next()
+    let next = next_cron_run_after(&schedule, tz, Utc::now())
         .ok_or_else(|| "No upcoming run found for this cron expression".to_string())?;
-    Ok(next.with_timezone(&chrono::Utc).to_rfc3339())
+    Ok(next.to_rfc3339())
+}
+
+/// Timezone a task was scheduled in, if it was recorded and is still valid.
+pub(crate) fn task_tz(task: &microclaw_storage::db::ScheduledTask) -> Option<chrono_tz::Tz> {
+    task.timezone.as_deref().and_then(|name| name.parse().ok())
+}
+
+/// Render a stored RFC3339 timestamp in the task's timezone; unparseable values
+/// are returned unchanged.
+fn format_in_task_tz(timestamp: &str, tz: chrono_tz::Tz) -> String {
+    match chrono::DateTime::parse_from_rfc3339(timestamp) {
+        Ok(dt) => dt
+            .with_timezone(&tz)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+            .to_string(),
+        Err(_) => timestamp.to_string(),
+    }
 }
 
 fn parse_once_schedule_value(
@@ -126,12 +163,163 @@ fn cron_human_hint(cron_expr: &str) -> Option<String> {
     None
 }
 
+/// Where a scheduled task's output goes. Run history is recorded either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TaskDelivery {
+    /// Post to the chat the task was scheduled for.
+    Channel,
+    /// Post to a private chat (`deliver_chat_id`, or the task chat when unset).
+    Dm,
+    /// Post to the task chat only when the run fails.
+    OnErrorOnly,
+    /// Never post.
+    Silent,
+}
+
+impl TaskDelivery {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "channel" => Some(TaskDelivery::Channel),
+            "dm" => Some(TaskDelivery::Dm),
+            "on_error_only" => Some(TaskDelivery::OnErrorOnly),
+            "silent" => Some(TaskDelivery::Silent),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            TaskDelivery::Channel => "channel",
+            TaskDelivery::Dm => "dm",
+            TaskDelivery::OnErrorOnly => "on_error_only",
+            TaskDelivery::Silent => "silent",
+        }
+    }
+
+    /// Chat that should receive the output of a run, if any.
+    pub(crate) fn target_chat(
+        task: &microclaw_storage::db::ScheduledTask,
+        success: bool,
+    ) -> Option<i64> {
+        match TaskDelivery::parse(&task.deliver).unwrap_or(TaskDelivery::Channel) {
+            TaskDelivery::Channel => Some(task.chat_id),
+            TaskDelivery::Dm => Some(task.deliver_chat_id.unwrap_or(task.chat_id)),
+            TaskDelivery::OnErrorOnly => (!success).then_some(task.chat_id),
+            TaskDelivery::Silent => None,
+        }
+    }
+}
+
+/// What a cron task does about fire times it missed while the bot was down.
+/// Applied once on startup; one-shot tasks that came due always run once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum MissedRunPolicy {
+    /// Move on to the next occurrence without running.
+    Skip,
+    /// Run once for all missed fire times.
+    #[default]
+    RunOnceOnStartup,
+    /// Run once per missed fire time, up to the scheduler's catch-up cap.
+    RunAll,
+}
+
+impl MissedRunPolicy {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "skip" => Some(MissedRunPolicy::Skip),
+            "run_once_on_startup" => Some(MissedRunPolicy::RunOnceOnStartup),
+            "run_all" => Some(MissedRunPolicy::RunAll),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            MissedRunPolicy::Skip => "skip",
+            MissedRunPolicy::RunOnceOnStartup => "run_once_on_startup",
+            MissedRunPolicy::RunAll => "run_all",
+        }
+    }
+}
+
+/// Names of the `{{param}}` placeholders in `text`, in order of first use.
+fn template_placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    names
+}
+
+/// Fill `{{param}}` placeholders from `params`; non-string values are inserted
+/// as JSON. Returns the names of placeholders with no value.
+fn render_task_template(
+    text: &str,
+    params: &serde_json::Map<String, serde_json::Value>,
+) -> Result<String, Vec<String>> {
+    let mut out = String::with_capacity(text.len());
+    let mut missing: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        match params.get(name) {
+            Some(serde_json::Value::String(v)) => out.push_str(v),
+            Some(v) => out.push_str(&v.to_string()),
+            None => {
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    if missing.is_empty() {
+        Ok(out)
+    } else {
+        Err(missing)
+    }
+}
+
+/// Parameters a template takes, across its prompt and schedule value.
+fn template_params(prompt: &str, schedule_value: &str) -> Vec<String> {
+    let mut params = template_placeholders(prompt);
+    for param in template_placeholders(schedule_value) {
+        if !params.contains(&param) {
+            params.push(param);
+        }
+    }
+    params
+}
+
+fn template_name_from_input(input: &serde_json::Value, key: &str) -> Option<String> {
+    input
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
 // --- schedule_task ---
 
 pub struct ScheduleTaskTool {
     registry: Arc<ChannelRegistry>,
     db: Arc<Database>,
     default_timezone: String,
+    allow_proactive_messaging: bool,
 }
 
 impl ScheduleTaskTool {
@@ -144,8 +332,14 @@ impl ScheduleTaskTool {
             registry,
             db,
             default_timezone,
+            allow_proactive_messaging: false,
         }
     }
+
+    pub fn with_proactive_messaging(mut self, allow: bool) -> Self {
+        self.allow_proactive_messaging = allow;
+        self
+    }
 }
 
 #[async_trait]
@@ -157,7 +351,7 @@ impl Tool for ScheduleTaskTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "schedule_task".into(),
-            description: "Schedule a recurring or one-time task. For recurring tasks, provide a 6-field cron expression (sec min hour dom month dow). For one-time tasks, provide an ISO 8601 timestamp. The bot will execute the prompt at the scheduled time and send the result to this chat.".into(),
+            description: "Schedule a recurring or one-time task. For recurring tasks, provide a 6-field cron expression (sec min hour dom month dow). For one-time tasks, provide an ISO 8601 timestamp. The bot will execute the prompt at the scheduled time and send the result to this chat, unless 'deliver' says otherwise. To reuse a saved task template, pass 'template' and its 'params'; prompt and schedule fields then default to the template's.".into(),
             input_schema: schema_object(
                 json!({
                     "chat_id": {
@@ -180,9 +374,31 @@ impl Tool for ScheduleTaskTool {
                     "timezone": {
                         "type": "string",
                         "description": "Optional IANA timezone name (e.g. 'US/Eastern', 'Europe/London'). Defaults to server timezone setting."
+                    },
+                    "deliver": {
+                        "type": "string",
+                        "enum": ["channel", "dm", "on_error_only", "silent"],
+                        "description": "Where to send each run's output: 'channel' (this chat, default), 'dm' (a private chat), 'on_error_only' (this chat, failures only) or 'silent' (never). Run history is recorded either way."
+                    },
+                    "dm_chat_id": {
+                        "type": "integer",
+                        "description": "Private chat that receives output when deliver is 'dm'. Defaults to chat_id, which must then be a private chat."
+                    },
+                    "missed_run_policy": {
+                        "type": "string",
+                        "enum": ["skip", "run_once_on_startup", "run_all"],
+                        "description": "For cron tasks: what to do about runs missed while the bot was down. 'skip' moves on to the next run, 'run_once_on_startup' (default) runs once, 'run_all' runs once per missed time (capped)."
+                    },
+                    "template": {
+                        "type": "string",
+                        "description": "Name of a task template saved with save_task_template. Fields given here override the template's."
+                    },
+                    "params": {
+                        "type": "object",
+                        "description": "Values for the template's {{param}} placeholders, e.g. {\"team\": \"infra\"}"
                     }
                 }),
-                &["chat_id", "prompt", "schedule_type", "schedule_value"],
+                &["chat_id"],
             ),
         }
     }
@@ -195,27 +411,140 @@ impl Tool for ScheduleTaskTool {
         if let Err(e) = authorize_chat_access(&input, chat_id) {
             return ToolResult::error(e);
         }
+        if !self.allow_proactive_messaging
+            && auth_context_from_input(&input).is_some_and(|auth| !auth.is_user_turn())
+        {
+            return ToolResult::error(
+                "Proactive messaging is disabled: scheduled tasks can only be created in reply to a user. An operator can enable allow_proactive_messaging to allow follow-ups.".into(),
+            );
+        }
         if let Err(e) =
             enforce_channel_policy(&self.registry, self.db.clone(), &input, chat_id).await
         {
             return ToolResult::error(e);
         }
-        let prompt = match input.get("prompt").and_then(|v| v.as_str()) {
-            Some(p) => p,
-            None => return ToolResult::error("Missing required parameter: prompt".into()),
+        let template_name = template_name_from_input(&input, "template");
+        let template = match template_name.clone() {
+            Some(name) => {
+                let lookup = name.clone();
+                match call_blocking(self.db.clone(), move |db| {
+                    db.get_task_template(chat_id, &lookup)
+                })
+                .await
+                {
+                    Ok(Some(template)) => Some(template),
+                    Ok(None) => {
+                        return ToolResult::error(format!(
+                            "No task template named '{name}' in this chat. Use list_task_templates to see saved templates."
+                        ))
+                    }
+                    Err(e) => return ToolResult::error(format!("Failed to load template: {e}")),
+                }
+            }
+            None => None,
         };
-        let schedule_type = match input.get("schedule_type").and_then(|v| v.as_str()) {
-            Some(t) => t,
-            None => return ToolResult::error("Missing required parameter: schedule_type".into()),
+        let field = |key: &str, from_template: Option<&String>| {
+            input
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .or_else(|| from_template.cloned())
+        };
+        let Some(mut prompt) = field("prompt", template.as_ref().map(|t| &t.prompt)) else {
+            return ToolResult::error("Missing required parameter: prompt".into());
         };
-        let schedule_value = match input.get("schedule_value").and_then(|v| v.as_str()) {
-            Some(v) => v,
-            None => return ToolResult::error("Missing required parameter: schedule_value".into()),
+        let Some(schedule_type) =
+            field("schedule_type", template.as_ref().map(|t| &t.schedule_type))
+        else {
+            return ToolResult::error("Missing required parameter: schedule_type".into());
         };
+        let Some(mut schedule_value) = field(
+            "schedule_value",
+            template.as_ref().map(|t| &t.schedule_value),
+        ) else {
+            return ToolResult::error("Missing required parameter: schedule_value".into());
+        };
+        if template.is_some() {
+            let params = input
+                .get("params")
+                .and_then(|v| v.as_object())
+                .cloned()
+                .unwrap_or_default();
+            match (
+                render_task_template(&prompt, &params),
+                render_task_template(&schedule_value, &params),
+            ) {
+                (Ok(p), Ok(v)) => {
+                    prompt = p;
+                    schedule_value = v;
+                }
+                (p, v) => {
+                    let mut missing = p.err().unwrap_or_default();
+                    for name in v.err().unwrap_or_default() {
+                        if !missing.contains(&name) {
+                            missing.push(name);
+                        }
+                    }
+                    return ToolResult::error(format!(
+                        "Missing template parameter(s): {}. Pass them in params.",
+                        missing.join(", ")
+                    ));
+                }
+            }
+        }
+        let (prompt, schedule_type, schedule_value) = (
+            prompt.as_str(),
+            schedule_type.as_str(),
+            schedule_value.as_str(),
+        );
         let tz_name = input
             .get("timezone")
             .and_then(|v| v.as_str())
             .unwrap_or(&self.default_timezone);
+        let deliver = match input.get("deliver").and_then(|v| v.as_str()) {
+            None => TaskDelivery::Channel,
+            Some(v) => match TaskDelivery::parse(v) {
+                Some(d) => d,
+                None => {
+                    return ToolResult::error(
+                        "deliver must be one of: channel, dm, on_error_only, silent".into(),
+                    )
+                }
+            },
+        };
+        let missed_run_policy = match input.get("missed_run_policy").and_then(|v| v.as_str()) {
+            None => MissedRunPolicy::default(),
+            Some(v) => match MissedRunPolicy::parse(v) {
+                Some(p) => p,
+                None => {
+                    return ToolResult::error(
+                        "missed_run_policy must be one of: skip, run_once_on_startup, run_all"
+                            .into(),
+                    )
+                }
+            },
+        };
+        let deliver_chat_id = if deliver == TaskDelivery::Dm {
+            let target = input
+                .get("dm_chat_id")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(chat_id);
+            if let Err(e) = authorize_chat_access(&input, target) {
+                return ToolResult::error(e);
+            }
+            match get_required_chat_routing(&self.registry, self.db.clone(), target).await {
+                Ok(routing) if routing.conversation == ConversationKind::Private => {}
+                Ok(_) => {
+                    return ToolResult::error(format!(
+                        "deliver=dm needs a private chat, but chat {target} is a group; pass dm_chat_id"
+                    ))
+                }
+                Err(e) => return ToolResult::error(e),
+            }
+            Some(target)
+        } else {
+            None
+        };
 
         let next_run = match schedule_type {
             "cron" => match compute_next_run(schedule_value, tz_name) {
@@ -250,14 +579,31 @@ impl Tool for ScheduleTaskTool {
         let schedule_type_owned = schedule_type.to_string();
         let schedule_value_owned = schedule_value.to_string();
         let next_run_owned = next_run.clone();
+        // Persist the zone so later runs are computed (and displayed) in it even if
+        // the global timezone changes.
+        let timezone_owned = tz_name
+            .parse::<chrono_tz::Tz>()
+            .ok()
+            .map(|tz| tz.name().to_string());
+        let template_owned = template_name.clone();
         match call_blocking(self.db.clone(), move |db| {
-            db.create_scheduled_task(
+            let id = db.create_scheduled_task_with_delivery(
                 chat_id,
                 &prompt_owned,
                 &schedule_type_owned,
                 &schedule_value_owned,
                 &next_run_owned,
-            )
+                timezone_owned.as_deref(),
+                deliver.as_str(),
+                deliver_chat_id,
+            )?;
+            if let Some(template) = template_owned {
+                db.set_task_template(id, &template)?;
+            }
+            if missed_run_policy != MissedRunPolicy::default() {
+                db.set_task_missed_run_policy(id, missed_run_policy.as_str())?;
+            }
+            Ok(id)
         })
         .await
         {
@@ -272,6 +618,19 @@ impl Tool for ScheduleTaskTool {
                 if let Some(c) = cadence {
                     message.push_str(&format!("\nCron interpretation: {c}."));
                 }
+                match (deliver, deliver_chat_id) {
+                    (TaskDelivery::Channel, _) => {}
+                    (TaskDelivery::Dm, Some(target)) => {
+                        message.push_str(&format!("\nDelivery: dm (chat {target})."))
+                    }
+                    _ => message.push_str(&format!("\nDelivery: {}.", deliver.as_str())),
+                }
+                if missed_run_policy != MissedRunPolicy::default() {
+                    message.push_str(&format!("\nMissed runs: {}.", missed_run_policy.as_str()));
+                }
+                if let Some(name) = &template_name {
+                    message.push_str(&format!("\nFrom template: {name}\nPrompt: {prompt}"));
+                }
                 ToolResult::success(message)
             }
             Err(e) => ToolResult::error(format!("Failed to create task: {e}")),
@@ -328,12 +687,25 @@ impl Tool for ListTasksTool {
             return ToolResult::error(e);
         }
 
+        let paused_at = call_blocking(self.db.clone(), |db| db.scheduler_paused_at())
+            .await
+            .ok()
+            .flatten();
+        let global_pause = paused_at
+            .map(|ts| {
+                format!(
+                    "Scheduler is paused globally since {ts}; no task runs until it is resumed.\n"
+                )
+            })
+            .unwrap_or_default();
         match call_blocking(self.db.clone(), move |db| db.get_tasks_for_chat(chat_id)).await {
             Ok(tasks) => {
                 if tasks.is_empty() {
-                    return ToolResult::success("No scheduled tasks found for this chat.".into());
+                    return ToolResult::success(format!(
+                        "{global_pause}No scheduled tasks found for this chat."
+                    ));
                 }
-                let mut output = String::new();
+                let mut output = global_pause;
                 for t in &tasks {
                     let cadence = if t.schedule_type == "cron" {
                         cron_human_hint(&t.schedule_value)
@@ -342,15 +714,39 @@ impl Tool for ListTasksTool {
                     } else {
                         String::new()
                     };
+                    let next = match task_tz(t) {
+                        Some(tz) => format_in_task_tz(&t.next_run, tz),
+                        None => t.next_run.clone(),
+                    };
+                    let deliver = if t.deliver == "channel" {
+                        String::new()
+                    } else {
+                        format!(" | deliver: {}", t.deliver)
+                    };
+                    let template = t
+                        .template
+                        .as_deref()
+                        .map(|name| format!(" | template: {name}"))
+                        .unwrap_or_default();
+                    let missed = if t.schedule_type == "cron"
+                        && t.missed_run_policy != MissedRunPolicy::default().as_str()
+                    {
+                        format!(" | missed runs: {}", t.missed_run_policy)
+                    } else {
+                        String::new()
+                    };
                     output.push_str(&format!(
-                        "#{} [{}] {} | {} '{}'{} | next: {}\n",
+                        "#{} [{}] {} | {} '{}'{} | next: {}{}{}{}\n",
                         t.id,
                         t.status,
                         t.prompt,
                         t.schedule_type,
                         t.schedule_value,
                         cadence,
-                        t.next_run
+                        next,
+                        deliver,
+                        template,
+                        missed
                     ));
                 }
                 ToolResult::success(output)
@@ -360,6 +756,219 @@ impl Tool for ListTasksTool {
     }
 }
 
+// --- save_task_template ---
+
+pub struct SaveTaskTemplateTool {
+    registry: Arc<ChannelRegistry>,
+    db: Arc<Database>,
+}
+
+impl SaveTaskTemplateTool {
+    pub fn new(registry: Arc<ChannelRegistry>, db: Arc<Database>) -> Self {
+        SaveTaskTemplateTool { registry, db }
+    }
+}
+
+#[async_trait]
+impl Tool for SaveTaskTemplateTool {
+    fn name(&self) -> &str {
+        "save_task_template"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "save_task_template".into(),
+            description: "Save a reusable task template for a chat, or replace one with the same name. Use {{param}} placeholders in prompt or schedule_value and fill them later with schedule_task's template and params. Set delete=true to remove a template.".into(),
+            input_schema: schema_object(
+                json!({
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "The chat that owns the template"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Template name, e.g. 'standup'"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Prompt to run, e.g. 'Remind {{team}} about standup'"
+                    },
+                    "schedule_type": {
+                        "type": "string",
+                        "enum": ["cron", "once"],
+                        "description": "Type of schedule: 'cron' for recurring, 'once' for one-time"
+                    },
+                    "schedule_value": {
+                        "type": "string",
+                        "description": "6-field cron expression or ISO 8601 timestamp; may contain placeholders"
+                    },
+                    "delete": {
+                        "type": "boolean",
+                        "description": "Remove the named template instead of saving it"
+                    }
+                }),
+                &["chat_id", "name"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match input.get("chat_id").and_then(|v| v.as_i64()) {
+            Some(id) => id,
+            None => return ToolResult::error("Missing required parameter: chat_id".into()),
+        };
+        let Some(name) = template_name_from_input(&input, "name") else {
+            return ToolResult::error("Missing required parameter: name".into());
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        if let Err(e) =
+            enforce_channel_policy(&self.registry, self.db.clone(), &input, chat_id).await
+        {
+            return ToolResult::error(e);
+        }
+
+        if input.get("delete").and_then(|v| v.as_bool()) == Some(true) {
+            let lookup = name.clone();
+            return match call_blocking(self.db.clone(), move |db| {
+                db.delete_task_template(chat_id, &lookup)
+            })
+            .await
+            {
+                Ok(true) => ToolResult::success(format!("Template '{name}' deleted.")),
+                Ok(false) => ToolResult::error(format!("No task template named '{name}'.")),
+                Err(e) => ToolResult::error(format!("Failed to delete template: {e}")),
+            };
+        }
+
+        let text = |key: &str| {
+            input
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let Some(prompt) = text("prompt") else {
+            return ToolResult::error("Missing required parameter: prompt".into());
+        };
+        let Some(schedule_value) = text("schedule_value") else {
+            return ToolResult::error("Missing required parameter: schedule_value".into());
+        };
+        let schedule_type = match text("schedule_type").as_deref() {
+            Some(t @ ("cron" | "once")) => t.to_string(),
+            Some(_) => return ToolResult::error("schedule_type must be 'cron' or 'once'".into()),
+            None => return ToolResult::error("Missing required parameter: schedule_type".into()),
+        };
+        // Without placeholders the schedule is fixed, so check it now rather
+        // than at every use.
+        if schedule_type == "cron" && template_placeholders(&schedule_value).is_empty() {
+            if let Err(e) = cron::Schedule::from_str(&schedule_value) {
+                return ToolResult::error(format!("Invalid cron expression: {e}"));
+            }
+        }
+
+        let params = template_params(&prompt, &schedule_value);
+        let saved_name = name.clone();
+        match call_blocking(self.db.clone(), move |db| {
+            db.save_task_template(
+                chat_id,
+                &saved_name,
+                &prompt,
+                &schedule_type,
+                &schedule_value,
+            )
+        })
+        .await
+        {
+            Ok(()) => {
+                let params_note = if params.is_empty() {
+                    "no parameters".to_string()
+                } else {
+                    format!("parameters: {}", params.join(", "))
+                };
+                ToolResult::success(format!("Template '{name}' saved ({params_note})."))
+            }
+            Err(e) => ToolResult::error(format!("Failed to save template: {e}")),
+        }
+    }
+}
+
+// --- list_task_templates ---
+
+pub struct ListTaskTemplatesTool {
+    registry: Arc<ChannelRegistry>,
+    db: Arc<Database>,
+}
+
+impl ListTaskTemplatesTool {
+    pub fn new(registry: Arc<ChannelRegistry>, db: Arc<Database>) -> Self {
+        ListTaskTemplatesTool { registry, db }
+    }
+}
+
+#[async_trait]
+impl Tool for ListTaskTemplatesTool {
+    fn name(&self) -> &str {
+        "list_task_templates"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "list_task_templates".into(),
+            description: "List the task templates saved for a chat, with their parameters.".into(),
+            input_schema: schema_object(
+                json!({
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "The chat ID to list templates for"
+                    }
+                }),
+                &["chat_id"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let chat_id = match input.get("chat_id").and_then(|v| v.as_i64()) {
+            Some(id) => id,
+            None => return ToolResult::error("Missing required parameter: chat_id".into()),
+        };
+        if let Err(e) = authorize_chat_access(&input, chat_id) {
+            return ToolResult::error(e);
+        }
+        if let Err(e) =
+            enforce_channel_policy(&self.registry, self.db.clone(), &input, chat_id).await
+        {
+            return ToolResult::error(e);
+        }
+
+        match call_blocking(self.db.clone(), move |db| db.list_task_templates(chat_id)).await {
+            Ok(templates) if templates.is_empty() => {
+                ToolResult::success("No task templates saved for this chat.".into())
+            }
+            Ok(templates) => {
+                let mut output = String::new();
+                for t in &templates {
+                    let params = template_params(&t.prompt, &t.schedule_value);
+                    let params = if params.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" | params: {}", params.join(", "))
+                    };
+                    output.push_str(&format!(
+                        "{}: {} | {} '{}'{}\n",
+                        t.name, t.prompt, t.schedule_type, t.schedule_value, params
+                    ));
+                }
+                ToolResult::success(output)
+            }
+            Err(e) => ToolResult::error(format!("Failed to list templates: {e}")),
+        }
+    }
+}
+
 // --- pause_task ---
 
 pub struct PauseTaskTool {
@@ -863,14 +1472,24 @@ impl Tool for GetTaskHistoryTool {
                         "No run history found for task #{task_id}."
                     ));
                 }
+                let tz = task_tz(&task);
                 let mut output =
                     format!("Run history for task #{task_id} (most recent first):\n\n");
                 for log in &logs {
-                    let status = if log.success { "OK" } else { "FAIL" };
+                    let status = match (log.success, log.catch_up) {
+                        (true, false) => "OK",
+                        (false, false) => "FAIL",
+                        (true, true) => "OK, catch-up",
+                        (false, true) => "FAIL, catch-up",
+                    };
+                    let started_at = match tz {
+                        Some(tz) => format_in_task_tz(&log.started_at, tz),
+                        None => log.started_at.clone(),
+                    };
                     output.push_str(&format!(
                         "- [{}] {} | duration: {}ms | {}\n",
                         status,
-                        log.started_at,
+                        started_at,
                         log.duration_ms,
                         log.result_summary.as_deref().unwrap_or("(no summary)"),
                     ));
@@ -950,6 +1569,40 @@ mod tests {
         assert!(result.unwrap_err().contains("Invalid timezone"));
     }
 
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_next_cron_run_skips_spring_forward_gap() {
+        // Europe/Berlin jumps 02:00 -> 03:00 on 2026-03-29, so 02:30 does not exist that day.
+        let schedule = cron::Schedule::from_str("0 30 2 * * *").unwrap();
+        let tz = chrono_tz::Europe::Berlin;
+        let after_saturday_run = utc("2026-03-28T01:31:00Z");
+        let next = next_cron_run_after(&schedule, tz, after_saturday_run).unwrap();
+        assert_eq!(next, utc("2026-03-30T00:30:00Z"));
+        assert_eq!(
+            format_in_task_tz(&next.to_rfc3339(), tz),
+            "2026-03-30 02:30:00 CEST"
+        );
+    }
+
+    #[test]
+    fn test_next_cron_run_fires_once_in_fall_back_overlap() {
+        // Europe/Berlin repeats 02:00-03:00 on 2026-10-25; 02:30 must fire only once.
+        let schedule = cron::Schedule::from_str("0 30 2 * * *").unwrap();
+        let tz = chrono_tz::Europe::Berlin;
+        let first = next_cron_run_after(&schedule, tz, utc("2026-10-24T01:00:00Z")).unwrap();
+        assert_eq!(first, utc("2026-10-25T00:30:00Z"));
+        let after_run = first + chrono::Duration::minutes(1);
+        let second = next_cron_run_after(&schedule, tz, after_run).unwrap();
+        assert_eq!(second, utc("2026-10-26T01:30:00Z"));
+        assert_eq!(
+            format_in_task_tz(&second.to_rfc3339(), tz),
+            "2026-10-26 02:30:00 CET"
+        );
+    }
+
     #[test]
     fn test_cron_human_hint_every_minutes() {
         let hint = cron_human_hint("0 */2 * * * *");
@@ -981,6 +1634,172 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[test]
+    fn test_render_task_template() {
+        let params = json!({"team": "infra", "hour": 9});
+        let params = params.as_object().unwrap();
+        assert_eq!(
+            render_task_template("Remind {{ team }} at {{hour}}, {{team}}!", params).unwrap(),
+            "Remind infra at 9, infra!"
+        );
+        assert_eq!(
+            render_task_template("{{team}} {{room}} {{room}} {{x", params).unwrap_err(),
+            vec!["room".to_string()]
+        );
+        assert_eq!(
+            template_params("Ping {{team}}", "0 0 {{hour}} * * {{days}}"),
+            vec!["team", "hour", "days"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schedule_task_from_template() {
+        let (db, dir) = test_db();
+        let save = SaveTaskTemplateTool::new(test_registry(), db.clone());
+        let result = save
+            .execute(json!({
+                "chat_id": 100,
+                "name": "standup",
+                "prompt": "Remind {{team}} about standup",
+                "schedule_type": "cron",
+                "schedule_value": "0 0 {{hour}} * * 1-5"
+            }))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains("parameters: team, hour"));
+
+        let tool = ScheduleTaskTool::new(test_registry(), db.clone(), "UTC".into());
+        let result = tool
+            .execute(json!({"chat_id": 100, "template": "standup", "params": {"team": "infra"}}))
+            .await;
+        assert!(result.is_error);
+        assert!(result
+            .content
+            .contains("Missing template parameter(s): hour"));
+
+        let result = tool
+            .execute(json!({
+                "chat_id": 100,
+                "template": "standup",
+                "params": {"team": "infra", "hour": 9}
+            }))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains("From template: standup"));
+        let tasks = db.get_tasks_for_chat(100).unwrap();
+        assert_eq!(tasks[0].prompt, "Remind infra about standup");
+        assert_eq!(tasks[0].schedule_value, "0 0 9 * * 1-5");
+
+        let list = ListTasksTool::new(test_registry(), db.clone())
+            .execute(json!({"chat_id": 100}))
+            .await;
+        assert!(list.content.contains("| template: standup"));
+        let templates = ListTaskTemplatesTool::new(test_registry(), db.clone())
+            .execute(json!({"chat_id": 100}))
+            .await;
+        assert!(templates.content.contains("standup: Remind {{team}}"));
+        assert!(templates.content.contains("params: team, hour"));
+
+        let result = tool
+            .execute(json!({"chat_id": 100, "template": "retro"}))
+            .await;
+        assert!(result.content.contains("No task template named 'retro'"));
+        let result = save
+            .execute(json!({"chat_id": 100, "name": "standup", "delete": true}))
+            .await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_save_task_template_validates_fixed_cron() {
+        let (db, dir) = test_db();
+        let save = SaveTaskTemplateTool::new(test_registry(), db);
+        let result = save
+            .execute(json!({
+                "chat_id": 100,
+                "name": "bad",
+                "prompt": "x",
+                "schedule_type": "cron",
+                "schedule_value": "every day"
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Invalid cron expression"));
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_schedule_task_deliver_options() {
+        let (db, dir) = test_db();
+        db.upsert_chat(200, Some("me"), "mock_private").unwrap();
+        let tool = ScheduleTaskTool::new(test_registry(), db.clone(), "UTC".into());
+        let base = json!({
+            "chat_id": 100,
+            "prompt": "daily digest",
+            "schedule_type": "cron",
+            "schedule_value": "0 0 9 * * *",
+            "__microclaw_auth": {
+                "caller_chat_id": 100,
+                "control_chat_ids": [100],
+                "caller_sender_id": "alice"
+            }
+        });
+
+        let mut input = base.clone();
+        input["deliver"] = json!("dm");
+        input["dm_chat_id"] = json!(200);
+        let result = tool.execute(input).await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains("Delivery: dm (chat 200)"));
+
+        let mut input = base.clone();
+        input["deliver"] = json!("silent");
+        let result = tool.execute(input).await;
+        assert!(!result.is_error, "Error: {}", result.content);
+
+        let tasks = db.get_tasks_for_chat(100).unwrap();
+        assert_eq!(tasks[0].deliver, "dm");
+        assert_eq!(tasks[0].deliver_chat_id, Some(200));
+        assert_eq!(TaskDelivery::target_chat(&tasks[0], true), Some(200));
+        assert_eq!(tasks[1].deliver, "silent");
+        assert_eq!(TaskDelivery::target_chat(&tasks[1], false), None);
+
+        let mut input = base.clone();
+        input["deliver"] = json!("dm");
+        let result = tool.execute(input).await;
+        assert!(result.is_error);
+
+        let mut input = base;
+        input["deliver"] = json!("email");
+        let result = tool.execute(input).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("deliver must be one of"));
+        cleanup(&dir);
+    }
+
+    #[test]
+    fn test_task_delivery_on_error_only_targets_failures() {
+        let task = microclaw_storage::db::ScheduledTask {
+            id: 1,
+            chat_id: 100,
+            prompt: "check".into(),
+            schedule_type: "cron".into(),
+            schedule_value: "0 * * * * *".into(),
+            next_run: "2024-01-01T00:00:00Z".into(),
+            last_run: None,
+            status: "active".into(),
+            created_at: "2024-01-01T00:00:00Z".into(),
+            timezone: None,
+            deliver: "on_error_only".into(),
+            deliver_chat_id: None,
+            template: None,
+            missed_run_policy: "run_once_on_startup".into(),
+        };
+        assert_eq!(TaskDelivery::target_chat(&task, true), None);
+        assert_eq!(TaskDelivery::target_chat(&task, false), Some(100));
+    }
+
     #[tokio::test]
     async fn test_schedule_task_once() {
         let (db, dir) = test_db();
@@ -1138,6 +1957,24 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_list_tasks_reports_global_pause() {
+        let (db, dir) = test_db();
+        db.create_scheduled_task(100, "task A", "cron", "0 * * * * *", "2024-01-01T00:00:00Z")
+            .unwrap();
+        db.set_scheduler_paused_at(Some("2024-01-01T00:00:00Z"))
+            .unwrap();
+
+        let tool = ListTasksTool::new(test_registry(), db);
+        let result = tool.execute(json!({"chat_id": 100})).await;
+        assert!(!result.is_error);
+        assert!(result
+            .content
+            .starts_with("Scheduler is paused globally since 2024-01-01T00:00:00Z"));
+        assert!(result.content.contains("task A"));
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_pause_and_resume_task() {
         let (db, dir) = test_db();
@@ -1255,6 +2092,87 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_schedule_task_missed_run_policy_and_catch_up_history() {
+        let (db, dir) = test_db();
+        let tool = ScheduleTaskTool::new(test_registry(), db.clone(), "UTC".into());
+        let base = json!({
+            "chat_id": 100,
+            "prompt": "hourly check",
+            "schedule_type": "cron",
+            "schedule_value": "0 0 * * * *"
+        });
+
+        let mut input = base.clone();
+        input["missed_run_policy"] = json!("run_all");
+        let result = tool.execute(input).await;
+        assert!(!result.is_error, "Error: {}", result.content);
+        assert!(result.content.contains("Missed runs: run_all"));
+
+        let mut input = base;
+        input["missed_run_policy"] = json!("sometimes");
+        let result = tool.execute(input).await;
+        assert!(result.content.contains("missed_run_policy must be one of"));
+
+        let task = db.get_tasks_for_chat(100).unwrap().remove(0);
+        assert_eq!(task.missed_run_policy, "run_all");
+        db.log_task_run_with_catch_up(
+            task.id,
+            100,
+            "2024-01-01T00:00:00Z",
+            "2024-01-01T00:00:05Z",
+            5000,
+            true,
+            Some("caught up"),
+            true,
+        )
+        .unwrap();
+        let history = GetTaskHistoryTool::new(test_registry(), db);
+        let result = history.execute(json!({"task_id": task.id})).await;
+        assert!(
+            result.content.contains("[OK, catch-up]"),
+            "{}",
+            result.content
+        );
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_get_task_history_uses_task_timezone() {
+        let (db, dir) = test_db();
+        let task_id = db
+            .create_scheduled_task_with_timezone(
+                100,
+                "test",
+                "cron",
+                "0 30 2 * * *",
+                "2026-03-30T00:30:00Z",
+                Some("Europe/Berlin"),
+            )
+            .unwrap();
+        db.log_task_run(
+            task_id,
+            100,
+            "2026-03-28T01:30:00Z",
+            "2026-03-28T01:30:04Z",
+            4000,
+            true,
+            Some("done"),
+        )
+        .unwrap();
+
+        let tool = GetTaskHistoryTool::new(test_registry(), db.clone());
+        let result = tool.execute(json!({"task_id": task_id})).await;
+        assert!(!result.is_error);
+        assert!(result.content.contains("2026-03-28 02:30:00 CET"));
+
+        let list = ListTasksTool::new(test_registry(), db)
+            .execute(json!({"chat_id": 100}))
+            .await;
+        assert!(list.content.contains("next: 2026-03-30 02:30:00 CEST"));
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_list_task_dlq_with_entries() {
         let (db, dir) = test_db();
@@ -1385,7 +2303,8 @@ mod tests {
                 "schedule_value": "2099-12-31T23:59:59+00:00",
                 "__microclaw_auth": {
                     "caller_chat_id": 100,
-                    "control_chat_ids": [100]
+                    "control_chat_ids": [100],
+                    "caller_sender_id": "alice"
                 }
             }))
             .await;
@@ -1395,6 +2314,30 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_schedule_task_blocked_in_scheduled_run_unless_proactive_allowed() {
+        let (db, dir) = test_db();
+        let input = json!({
+            "chat_id": 100,
+            "prompt": "check again tomorrow",
+            "schedule_type": "once",
+            "schedule_value": "2099-12-31T23:59:59+00:00",
+            "__microclaw_auth": {"caller_chat_id": 100, "control_chat_ids": []}
+        });
+
+        let tool = ScheduleTaskTool::new(test_registry(), db.clone(), "UTC".into());
+        let result = tool.execute(input.clone()).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Proactive messaging is disabled"));
+        assert!(db.get_tasks_for_chat(100).unwrap().is_empty());
+
+        let tool = tool.with_proactive_messaging(true);
+        let result = tool.execute(input).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(db.get_tasks_for_chat(100).unwrap().len(), 1);
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_pause_task_allowed_for_control_chat_cross_chat() {
         let (db, dir) = test_db();
@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, schema_object, Tool, ToolAuthContext, ToolResult};
+use microclaw_core::llm_types::ToolDefinition;
+
+pub const SCRATCH_SET_TOOL_NAME: &str = "scratch_set";
+pub const SCRATCH_GET_TOOL_NAME: &str = "scratch_get";
+
+/// Total bytes one run may keep in scratch.
+const MAX_SCRATCH_BYTES: usize = 1024 * 1024;
+const MAX_SCRATCH_KEYS: usize = 32;
+const MAX_KEY_LEN: usize = 64;
+/// Bytes `scratch_get` returns per call; `offset` pages through longer values.
+const MAX_GET_BYTES: usize = 20_000;
+
+#[derive(Default)]
+struct ChatScratch {
+    entries: HashMap<String, String>,
+    /// Content of the last successful non-scratch tool result this turn.
+    last_result: Option<String>,
+}
+
+impl ChatScratch {
+    fn used_bytes(&self) -> usize {
+        self.entries.values().map(String::len).sum()
+    }
+}
+
+/// Whose scratch a call sees: `(chat_id, run_id)`. Concurrent turns in one
+/// chat are separate runs and never share or clear each other's values.
+pub type ScratchScope = (i64, u64);
+
+pub fn scratch_scope(auth: &ToolAuthContext) -> ScratchScope {
+    (auth.caller_chat_id, auth.run_id)
+}
+
+/// In-memory key/value blobs shared by the tool calls of one agent run. The
+/// agent loop clears a run's entries when it ends.
+#[derive(Clone, Default)]
+pub struct ScratchStore {
+    chats: Arc<Mutex<HashMap<ScratchScope, ChatScratch>>>,
+}
+
+impl ScratchStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_chat<T>(&self, scope: ScratchScope, f: impl FnOnce(&mut ChatScratch) -> T) -> T {
+        let mut chats = self.chats.lock().unwrap_or_else(|e| e.into_inner());
+        f(chats.entry(scope).or_default())
+    }
+
+    pub fn set(&self, scope: ScratchScope, key: &str, value: String) -> Result<usize, String> {
+        validate_key(key)?;
+        self.with_chat(scope, |scratch| {
+            let replaced = scratch.entries.get(key).map(String::len);
+            if replaced.is_none() && scratch.entries.len() >= MAX_SCRATCH_KEYS {
+                return Err(format!(
+                    "scratch is full ({MAX_SCRATCH_KEYS} keys); overwrite an existing key"
+                ));
+            }
+            let used = scratch.used_bytes() - replaced.unwrap_or(0);
+            if used + value.len() > MAX_SCRATCH_BYTES {
+                return Err(format!(
+                    "value of {} bytes exceeds the scratch limit ({} of {MAX_SCRATCH_BYTES} bytes in use)",
+                    value.len(),
+                    used
+                ));
+            }
+            let len = value.len();
+            scratch.entries.insert(key.to_string(), value);
+            Ok(len)
+        })
+    }
+
+    pub fn get(&self, scope: ScratchScope, key: &str) -> Option<String> {
+        self.with_chat(scope, |scratch| scratch.entries.get(key).cloned())
+    }
+
+    pub fn keys(&self, scope: ScratchScope) -> Vec<String> {
+        let mut keys: Vec<String> =
+            self.with_chat(scope, |scratch| scratch.entries.keys().cloned().collect());
+        keys.sort();
+        keys
+    }
+
+    pub fn last_result(&self, scope: ScratchScope) -> Option<String> {
+        self.with_chat(scope, |scratch| scratch.last_result.clone())
+    }
+
+    pub fn record_result(&self, scope: ScratchScope, content: &str) {
+        self.with_chat(scope, |scratch| {
+            scratch.last_result = Some(content.to_string());
+        });
+    }
+
+    pub fn clear(&self, scope: ScratchScope) {
+        let mut chats = self.chats.lock().unwrap_or_else(|e| e.into_inner());
+        chats.remove(&scope);
+    }
+
+    /// Replace `{{scratch:key}}` in every string of `input` with the stored
+    /// value. Unknown keys are an error rather than passed through literally.
+    pub fn expand_refs(
+        &self,
+        scope: ScratchScope,
+        input: &mut serde_json::Value,
+    ) -> Result<(), String> {
+        match input {
+            serde_json::Value::String(s) if s.contains(SCRATCH_REF_OPEN) => {
+                *s = self.expand_str(scope, s)?;
+                Ok(())
+            }
+            serde_json::Value::Array(items) => items
+                .iter_mut()
+                .try_for_each(|v| self.expand_refs(scope, v)),
+            serde_json::Value::Object(map) => map
+                .iter_mut()
+                .filter(|(k, _)| !k.starts_with("__microclaw"))
+                .try_for_each(|(_, v)| self.expand_refs(scope, v)),
+            _ => Ok(()),
+        }
+    }
+
+    fn expand_str(&self, scope: ScratchScope, text: &str) -> Result<String, String> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(SCRATCH_REF_OPEN) {
+            let after = &rest[start + SCRATCH_REF_OPEN.len()..];
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            let key = after[..end].trim();
+            let value = self.get(scope, key).ok_or_else(|| {
+                format!("unknown scratch key '{key}' in {{{{scratch:{key}}}}}; set it with scratch_set first")
+            })?;
+            out.push_str(&rest[..start]);
+            out.push_str(&value);
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+const SCRATCH_REF_OPEN: &str = "{{scratch:";
+
+fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(format!("scratch key must be 1-{MAX_KEY_LEN} characters"));
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!(
+            "scratch key '{key}' may only use letters, digits, '_', '-' and '.'"
+        ));
+    }
+    Ok(())
+}
+
+fn caller_scope(input: &serde_json::Value) -> Result<ScratchScope, String> {
+    auth_context_from_input(input)
+        .map(|auth| scratch_scope(&auth))
+        .ok_or_else(|| "scratch tools need a chat context".to_string())
+}
+
+pub struct ScratchSetTool {
+    store: ScratchStore,
+}
+
+impl ScratchSetTool {
+    pub fn new(store: ScratchStore) -> Self {
+        ScratchSetTool { store }
+    }
+}
+
+#[async_trait]
+impl Tool for ScratchSetTool {
+    fn name(&self) -> &str {
+        SCRATCH_SET_TOOL_NAME
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: SCRATCH_SET_TOOL_NAME.into(),
+            description: "Stash a value under a key for later tool calls in this turn, so large data (a fetched page, a query result) need not pass through the conversation again. Set from_last_result to store the full output of the previous tool call without repeating it. Any later tool input may contain {{scratch:key}}, which is replaced with the stored value before the tool runs. Scratch is cleared when the turn ends (1 MB, 32 keys per turn).".into(),
+            input_schema: schema_object(
+                json!({
+                    "key": {
+                        "type": "string",
+                        "description": "Key to store under (letters, digits, '_', '-', '.')"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "Value to store; omit when from_last_result is true"
+                    },
+                    "from_last_result": {
+                        "type": "boolean",
+                        "description": "Store the output of the previous successful tool call instead of value"
+                    }
+                }),
+                &["key"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let scope = match caller_scope(&input) {
+            Ok(scope) => scope,
+            Err(e) => return ToolResult::error(e),
+        };
+        let key = input
+            .get("key")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or_default();
+        let from_last_result = input
+            .get("from_last_result")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let value = if from_last_result {
+            match self.store.last_result(scope) {
+                Some(value) => value,
+                None => {
+                    return ToolResult::error("No earlier tool result in this turn to store".into())
+                }
+            }
+        } else {
+            match input.get("value").and_then(|v| v.as_str()) {
+                Some(value) => value.to_string(),
+                None => {
+                    return ToolResult::error(
+                        "Provide value, or set from_last_result to true".into(),
+                    )
+                }
+            }
+        };
+        match self.store.set(scope, key, value) {
+            Ok(bytes) => ToolResult::success(format!(
+                "Stored {bytes} bytes under '{key}'. Reference it as {{{{scratch:{key}}}}} in later tool inputs."
+            ))
+            .with_metadata(json!({ "key": key, "bytes": bytes })),
+            Err(e) => ToolResult::error(e),
+        }
+    }
+}
+
+pub struct ScratchGetTool {
+    store: ScratchStore,
+}
+
+impl ScratchGetTool {
+    pub fn new(store: ScratchStore) -> Self {
+        ScratchGetTool { store }
+    }
+}
+
+#[async_trait]
+impl Tool for ScratchGetTool {
+    fn name(&self) -> &str {
+        SCRATCH_GET_TOOL_NAME
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: SCRATCH_GET_TOOL_NAME.into(),
+            description: "Read a value stored with scratch_set in this turn. Returns up to 20000 bytes; use offset to page through longer values. Prefer passing {{scratch:key}} to other tools over reading large values back.".into(),
+            input_schema: schema_object(
+                json!({
+                    "key": {
+                        "type": "string",
+                        "description": "Key to read"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Byte offset to start reading from (default 0)"
+                    }
+                }),
+                &["key"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let scope = match caller_scope(&input) {
+            Ok(scope) => scope,
+            Err(e) => return ToolResult::error(e),
+        };
+        let key = input
+            .get("key")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .unwrap_or_default();
+        let Some(value) = self.store.get(scope, key) else {
+            let keys = self.store.keys(scope);
+            let known = if keys.is_empty() {
+                "scratch is empty".to_string()
+            } else {
+                format!("known keys: {}", keys.join(", "))
+            };
+            return ToolResult::error(format!("No scratch value under '{key}' ({known})"));
+        };
+        let offset = input
+            .get("offset")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(0);
+        let start = microclaw_core::text::floor_char_boundary(&value, offset.min(value.len()));
+        let end = microclaw_core::text::floor_char_boundary(
+            &value,
+            (start + MAX_GET_BYTES).min(value.len()),
+        );
+        let mut content = value[start..end].to_string();
+        if end < value.len() {
+            content.push_str(&format!(
+                "\n\n[{} more bytes; continue with offset {end}]",
+                value.len() - end
+            ));
+        }
+        ToolResult::success(content).with_metadata(json!({
+            "key": key,
+            "bytes": value.len(),
+            "offset": start,
+            "next_offset": (end < value.len()).then_some(end),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(chat_id: i64) -> serde_json::Value {
+        json!({"caller_chat_id": chat_id, "control_chat_ids": [], "run_id": 3})
+    }
+
+    #[test]
+    fn test_expand_refs_substitutes_stored_values() {
+        let store = ScratchStore::new();
+        store.set((1, 1), "page", "<html>".into()).unwrap();
+        let mut input = json!({
+            "content": "before {{scratch:page}} after {{ scratch:page }}",
+            "args": ["{{scratch:page}}", 3],
+            "__microclaw_auth": {"note": "{{scratch:missing}}"}
+        });
+        store.expand_refs((1, 1), &mut input).unwrap();
+        assert_eq!(input["content"], "before <html> after {{ scratch:page }}");
+        assert_eq!(input["args"], json!(["<html>", 3]));
+
+        let mut other_chat = json!({"content": "{{scratch:page}}"});
+        let err = store.expand_refs((2, 1), &mut other_chat).unwrap_err();
+        assert!(err.contains("unknown scratch key 'page'"));
+
+        store.clear((1, 1));
+        assert!(store.get((1, 1), "page").is_none());
+    }
+
+    #[test]
+    fn test_concurrent_runs_in_one_chat_keep_separate_scratch() {
+        let store = ScratchStore::new();
+        store.set((1, 10), "page", "first".into()).unwrap();
+        store.set((1, 11), "page", "second".into()).unwrap();
+
+        store.clear((1, 11));
+        assert_eq!(store.get((1, 10), "page").as_deref(), Some("first"));
+        let mut input = json!({"content": "{{scratch:page}}"});
+        store.expand_refs((1, 10), &mut input).unwrap();
+        assert_eq!(input["content"], "first");
+    }
+
+    #[test]
+    fn test_set_enforces_key_and_size_limits() {
+        let store = ScratchStore::new();
+        assert!(store.set((1, 1), "bad key", "x".into()).is_err());
+        store
+            .set((1, 1), "big", "x".repeat(MAX_SCRATCH_BYTES))
+            .unwrap();
+        let err = store.set((1, 1), "more", "y".into()).unwrap_err();
+        assert!(err.contains("exceeds the scratch limit"));
+        // Overwriting a key frees its old bytes first.
+        store.set((1, 1), "big", "small".into()).unwrap();
+        store.set((1, 1), "more", "y".into()).unwrap();
+        assert_eq!(store.keys((1, 1)), vec!["big", "more"]);
+    }
+
+    #[tokio::test]
+    async fn test_scratch_tools_round_trip_and_page() {
+        let store = ScratchStore::new();
+        let set = ScratchSetTool::new(store.clone());
+        let get = ScratchGetTool::new(store.clone());
+
+        let result = set
+            .execute(json!({"key": "rows", "from_last_result": true, "__microclaw_auth": auth(7)}))
+            .await;
+        assert!(result.is_error);
+
+        store.record_result((7, 3), &"r".repeat(MAX_GET_BYTES + 5));
+        let result = set
+            .execute(json!({"key": "rows", "from_last_result": true, "__microclaw_auth": auth(7)}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("{{scratch:rows}}"));
+
+        let result = get
+            .execute(json!({"key": "rows", "__microclaw_auth": auth(7)}))
+            .await;
+        assert!(result.content.ends_with(&format!(
+            "[5 more bytes; continue with offset {MAX_GET_BYTES}]"
+        )));
+        let result = get
+            .execute(json!({"key": "rows", "offset": MAX_GET_BYTES, "__microclaw_auth": auth(7)}))
+            .await;
+        assert_eq!(result.content, "rrrrr");
+
+        let result = get
+            .execute(json!({"key": "nope", "__microclaw_auth": auth(7)}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("known keys: rows"));
+    }
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
+use microclaw_core::llm_types::ToolDefinition;
+use microclaw_core::text::floor_char_boundary;
+use microclaw_storage::db::{call_blocking, Database, StoredMessage};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+/// Bytes of context kept before the first match in a snippet; twice that follows it.
+const SNIPPET_CONTEXT: usize = 80;
+
+pub struct SearchMessagesTool {
+    db: Arc<Database>,
+}
+
+impl SearchMessagesTool {
+    pub fn new(db: Arc<Database>) -> Self {
+        SearchMessagesTool { db }
+    }
+}
+
+/// Which chat to search: `None` means every chat (control chats only).
+fn resolve_search_scope(input: &serde_json::Value) -> Result<Option<i64>, String> {
+    let auth = auth_context_from_input(input);
+    if input
+        .get("all_chats")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        if let Some(auth) = auth.filter(|a| !a.is_control_chat()) {
+            return Err(format!(
+                "Permission denied: only control chats can search all chats (caller: {})",
+                auth.caller_chat_id
+            ));
+        }
+        return Ok(None);
+    }
+    let chat_id = match input.get("chat_id").and_then(|v| v.as_i64()) {
+        Some(chat_id) => chat_id,
+        None => auth
+            .map(|a| a.caller_chat_id)
+            .ok_or_else(|| "Missing required parameter: chat_id".to_string())?,
+    };
+    authorize_chat_access(input, chat_id)?;
+    Ok(Some(chat_id))
+}
+
+/// One line of `content` around the first occurrence of any query term.
+fn snippet(content: &str, query: &str) -> String {
+    let lower = content.to_lowercase();
+    // Lowercasing can change byte lengths; only trust offsets when it did not.
+    let hit = (lower.len() == content.len())
+        .then(|| {
+            query
+                .split_whitespace()
+                .filter_map(|t| lower.find(&t.trim_matches('"').to_lowercase()))
+                .min()
+        })
+        .flatten()
+        .unwrap_or(0);
+    let start = floor_char_boundary(content, hit.saturating_sub(SNIPPET_CONTEXT));
+    let end = floor_char_boundary(content, (hit + 2 * SNIPPET_CONTEXT).min(content.len()));
+    let mut out = content[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        out.insert_str(0, "...");
+    }
+    if end < content.len() {
+        out.push_str("...");
+    }
+    out
+}
+
+fn format_hit(msg: &StoredMessage, query: &str, show_chat: bool) -> String {
+    let sender = if msg.is_from_bot {
+        "bot"
+    } else {
+        msg.sender_name.as_str()
+    };
+    let chat = if show_chat {
+        format!("chat {} · ", msg.chat_id)
+    } else {
+        String::new()
+    };
+    format!(
+        "[{}] {chat}{sender}: {}",
+        msg.timestamp,
+        snippet(&msg.content, query)
+    )
+}
+
+#[async_trait]
+impl Tool for SearchMessagesTool {
+    fn name(&self) -> &str {
+        "search_messages"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "search_messages".into(),
+            description: "Full-text search over the raw conversation history of this chat. Use it to find the exact message where something was said; use structured_memory_search for distilled facts. All terms must appear (case-insensitive). Returns matching messages with timestamps, senders and a snippet.".into(),
+            input_schema: schema_object(
+                json!({
+                    "query": {
+                        "type": "string",
+                        "description": "Words or phrases to look for; every term must appear"
+                    },
+                    "chat_id": {
+                        "type": "integer",
+                        "description": "Chat to search (defaults to the current chat)"
+                    },
+                    "all_chats": {
+                        "type": "boolean",
+                        "description": "Search every chat (control chats only)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum results (default 10, max 50)"
+                    }
+                }),
+                &["query"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let query = match input.get("query").and_then(|v| v.as_str()).map(str::trim) {
+            Some(q) if !q.is_empty() => q.to_string(),
+            _ => return ToolResult::error("Missing required parameter: query".into()),
+        };
+        let scope = match resolve_search_scope(&input) {
+            Ok(scope) => scope,
+            Err(e) => return ToolResult::error(e),
+        };
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, MAX_LIMIT))
+            .unwrap_or(DEFAULT_LIMIT);
+
+        let q = query.clone();
+        let hits = match call_blocking(self.db.clone(), move |db| {
+            db.search_messages(&q, scope, limit)
+        })
+        .await
+        {
+            Ok(hits) => hits,
+            Err(e) => return ToolResult::error(format!("Failed to search messages: {e}")),
+        };
+        if hits.is_empty() {
+            return ToolResult::success(format!("No messages matching \"{query}\"."));
+        }
+
+        let lines: Vec<String> = hits
+            .iter()
+            .map(|m| format_hit(m, &query, scope.is_none()))
+            .collect();
+        let results: Vec<serde_json::Value> = hits
+            .iter()
+            .map(|m| {
+                json!({
+                    "id": m.id,
+                    "chat_id": m.chat_id,
+                    "sender": m.sender_name,
+                    "is_from_bot": m.is_from_bot,
+                    "timestamp": m.timestamp,
+                })
+            })
+            .collect();
+        ToolResult::success(format!(
+            "Found {} message(s):\n{}",
+            hits.len(),
+            lines.join("\n")
+        ))
+        .with_metadata(json!({ "results": results }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (Arc<Database>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("microclaw_search_{}", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(dir.to_str().unwrap()).unwrap());
+        (db, dir)
+    }
+
+    fn cleanup(dir: &std::path::Path) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn store(db: &Database, id: &str, chat_id: i64, content: &str) {
+        db.store_message(&StoredMessage {
+            id: id.into(),
+            chat_id,
+            sender_name: "alice".into(),
+            content: content.into(),
+            is_from_bot: false,
+            timestamp: format!("2024-01-01T00:00:0{}Z", id.len()),
+        })
+        .unwrap();
+    }
+
+    fn auth(chat: i64, control: &[i64]) -> serde_json::Value {
+        json!({"caller_chat_id": chat, "control_chat_ids": control})
+    }
+
+    #[test]
+    fn test_snippet_centers_on_first_match() {
+        let content = format!(
+            "{} the launch date is March 3 {}",
+            "x".repeat(200),
+            "y".repeat(200)
+        );
+        let s = snippet(&content, "LAUNCH");
+        assert!(s.starts_with("..."));
+        assert!(s.ends_with("..."));
+        assert!(s.contains("the launch date is March 3"));
+        assert_eq!(snippet("short message", "message"), "short message");
+    }
+
+    #[tokio::test]
+    async fn test_search_messages_scoped_to_caller_chat() {
+        let (db, dir) = test_db();
+        store(&db, "a", 100, "Let's ship the invoice export on Monday");
+        store(&db, "bb", 200, "Invoice export for the other chat");
+        let tool = SearchMessagesTool::new(db.clone());
+
+        let result = tool
+            .execute(json!({"query": "invoice export", "__microclaw_auth": auth(100, &[])}))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.starts_with("Found 1 message(s)"));
+        assert!(result
+            .content
+            .contains("[2024-01-01T00:00:01Z] alice: Let's ship"));
+        assert_eq!(result.metadata.unwrap()["results"][0]["chat_id"], 100);
+
+        let result = tool
+            .execute(
+                json!({"query": "invoice", "chat_id": 200, "__microclaw_auth": auth(100, &[])}),
+            )
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Permission denied"));
+
+        let result = tool
+            .execute(
+                json!({"query": "invoice", "all_chats": true, "__microclaw_auth": auth(100, &[])}),
+            )
+            .await;
+        assert!(result.is_error);
+
+        let result = tool
+            .execute(
+                json!({"query": "invoice", "all_chats": true, "__microclaw_auth": auth(1, &[1])}),
+            )
+            .await;
+        assert!(result.content.starts_with("Found 2 message(s)"));
+        assert!(result.content.contains("chat 200 · alice"));
+
+        let result = tool
+            .execute(json!({"query": "payroll", "__microclaw_auth": auth(100, &[])}))
+            .await;
+        assert!(!result.is_error);
+        assert!(result.content.contains("No messages matching"));
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_search_messages_requires_query() {
+        let (db, dir) = test_db();
+        let tool = SearchMessagesTool::new(db);
+        let result = tool
+            .execute(json!({"query": "  ", "__microclaw_auth": auth(100, &[])}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Missing required parameter: query"));
+        cleanup(&dir);
+    }
+}
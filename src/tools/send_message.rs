@@ -5,11 +5,12 @@ use async_trait::async_trait;
 use serde_json::json;
 use tracing::{info, warn};
 
-use super::{authorize_chat_access, schema_object, Tool, ToolResult};
+use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
 use microclaw_channels::channel::{
-    deliver_and_store_bot_message, enforce_channel_policy, get_required_chat_routing,
+    deliver_and_store_bot_message_with_mentions, deliver_or_defer_bot_message,
+    enforce_channel_policy, get_required_chat_routing, OutboundDelivery,
 };
-use microclaw_channels::channel_adapter::ChannelRegistry;
+use microclaw_channels::channel_adapter::{apply_mentions, ChannelRegistry, ResolvedMention};
 use microclaw_core::llm_types::ToolDefinition;
 use microclaw_storage::db::{call_blocking, Database, StoredMessage};
 
@@ -18,6 +19,7 @@ pub struct SendMessageTool {
     db: Arc<Database>,
     default_bot_username: String,
     channel_bot_usernames: std::collections::HashMap<String, String>,
+    allow_proactive_messaging: bool,
 }
 
 impl SendMessageTool {
@@ -32,9 +34,15 @@ impl SendMessageTool {
             db,
             default_bot_username,
             channel_bot_usernames,
+            allow_proactive_messaging: false,
         }
     }
 
+    pub fn with_proactive_messaging(mut self, allow: bool) -> Self {
+        self.allow_proactive_messaging = allow;
+        self
+    }
+
     fn bot_username_for_channel(&self, channel_name: &str) -> String {
         self.channel_bot_usernames
             .get(channel_name)
@@ -61,12 +69,111 @@ impl SendMessageTool {
             .map_err(|e| format!("Failed to store sent message: {e}"))
     }
 
+    /// Turns no user triggered (scheduled runs) may only post to their own chat
+    /// unless `allow_proactive_messaging` is on.
+    fn authorize_unprompted_send(
+        &self,
+        input: &serde_json::Value,
+        chat_id: i64,
+    ) -> Result<(), String> {
+        if self.allow_proactive_messaging {
+            return Ok(());
+        }
+        match auth_context_from_input(input) {
+            Some(auth) if !auth.is_user_turn() && auth.caller_chat_id != chat_id => Err(format!(
+                "Proactive messaging is disabled: a scheduled run for chat {} cannot message chat {}. Reply in this chat instead; an operator can enable allow_proactive_messaging.",
+                auth.caller_chat_id, chat_id
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// When quiet hours end for `chat_id`'s channel, if they are in effect now.
+    async fn quiet_hours_end(&self, chat_id: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+        let routing = get_required_chat_routing(&self.registry, self.db.clone(), chat_id)
+            .await
+            .ok()?;
+        let now = chrono::Utc::now();
+        self.registry
+            .quiet_hours(&routing.channel_name)
+            .filter(|q| q.is_quiet_at(now))
+            .map(|q| q.ends_after(now))
+    }
+
     async fn resolve_external_chat_id(&self, chat_id: i64) -> Result<String, String> {
         let external = call_blocking(self.db.clone(), move |db| db.get_chat_external_id(chat_id))
             .await
             .map_err(|e| format!("Failed to resolve external chat id: {e}"))?;
         Ok(external.unwrap_or_else(|| chat_id.to_string()))
     }
+
+    /// Resolve requested mentions through the chat's adapter. Returns the
+    /// resolved mentions and the names that could not be resolved.
+    async fn resolve_mentions(
+        &self,
+        chat_id: i64,
+        names: &[String],
+    ) -> (Vec<ResolvedMention>, Vec<String>) {
+        if names.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+        let adapter =
+            match get_required_chat_routing(&self.registry, self.db.clone(), chat_id).await {
+                Ok(routing) => self.registry.get(&routing.channel_name).cloned(),
+                Err(_) => None,
+            };
+        let (Some(adapter), Ok(external_chat_id)) =
+            (adapter, self.resolve_external_chat_id(chat_id).await)
+        else {
+            return (Vec::new(), names.to_vec());
+        };
+        let known_ids = call_blocking(self.db.clone(), move |db| db.get_chat_sender_names(chat_id))
+            .await
+            .unwrap_or_default();
+
+        let mut resolved = Vec::new();
+        let mut unresolved = Vec::new();
+        for name in names {
+            match adapter
+                .resolve_mention(&external_chat_id, name, &known_ids)
+                .await
+            {
+                Some(mention) => resolved.push(mention),
+                None => unresolved.push(name.clone()),
+            }
+        }
+        (resolved, unresolved)
+    }
+}
+
+fn parse_mentions(input: &serde_json::Value) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in input
+        .get("mentions")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+    {
+        let name = name.trim().trim_start_matches('@').trim();
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Make sure every unresolved mention still appears as plain `@name` text.
+fn plain_mention_fallback(text: &str, unresolved: &[String]) -> String {
+    let plain: Vec<ResolvedMention> = unresolved
+        .iter()
+        .map(|name| ResolvedMention {
+            name: name.clone(),
+            user_id: name.clone(),
+            display: name.clone(),
+        })
+        .collect();
+    apply_mentions(text, &plain, |m| format!("@{}", m.display))
 }
 
 #[async_trait]
@@ -78,7 +185,7 @@ impl Tool for SendMessageTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "send_message".into(),
-            description: "Send a message mid-conversation. Supports text for all channels, and attachments for Feishu via attachment_path.".into(),
+            description: "Send a message mid-conversation. Supports text for all channels, and attachments for Feishu via attachment_path. Use mentions to @-mention users; do not hand-write mention markup.".into(),
             input_schema: schema_object(
                 json!({
                     "chat_id": {
@@ -96,6 +203,11 @@ impl Tool for SendMessageTool {
                     "caption": {
                         "type": "string",
                         "description": "Optional caption used when sending attachment"
+                    },
+                    "mentions": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Usernames or user ids to @-mention. Each is converted to the channel's mention syntax; write @name in text to place it, otherwise it is prepended. Unresolvable names are sent as plain text."
                     }
                 }),
                 &["chat_id"],
@@ -125,9 +237,14 @@ impl Tool for SendMessageTool {
             .map(|v| v.trim().to_string())
             .filter(|v| !v.is_empty());
 
+        let mentions = parse_mentions(&input);
+
         if text.is_empty() && attachment_path.is_none() {
             return ToolResult::error("Provide text and/or attachment_path".into());
         }
+        if !mentions.is_empty() && attachment_path.is_some() {
+            return ToolResult::error("mentions are only supported for text messages".into());
+        }
         info!(
             "send_message start: chat_id={}, has_text={}, has_attachment={}",
             chat_id,
@@ -138,14 +255,26 @@ impl Tool for SendMessageTool {
         if let Err(e) = authorize_chat_access(&input, chat_id) {
             return ToolResult::error(e);
         }
+        if let Err(e) = self.authorize_unprompted_send(&input, chat_id) {
+            return ToolResult::error(e);
+        }
 
         if let Err(e) =
             enforce_channel_policy(&self.registry, self.db.clone(), &input, chat_id).await
         {
             return ToolResult::error(e);
         }
+        let quiet_until = match auth_context_from_input(&input) {
+            Some(auth) if !auth.is_user_turn() => self.quiet_hours_end(chat_id).await,
+            _ => None,
+        };
 
         if let Some(path) = attachment_path {
+            if let Some(until) = quiet_until {
+                return ToolResult::error(format!(
+                    "Chat {chat_id} is in quiet hours until {until}; attachments are not queued. Send text instead (it is delivered when quiet hours end) or schedule the attachment for later."
+                ));
+            }
             let routing =
                 match get_required_chat_routing(&self.registry, self.db.clone(), chat_id).await {
                     Ok(v) => v,
@@ -231,18 +360,59 @@ impl Tool for SendMessageTool {
                     Ok(routing) => self.bot_username_for_channel(&routing.channel_name),
                     Err(_) => self.default_bot_username.clone(),
                 };
-            match deliver_and_store_bot_message(
+            let (resolved, unresolved) = self.resolve_mentions(chat_id, &mentions).await;
+            let text = if unresolved.is_empty() {
+                text
+            } else {
+                warn!(
+                    "send_message unresolved mentions: chat_id={}, names={}",
+                    chat_id,
+                    unresolved.join(",")
+                );
+                plain_mention_fallback(&text, &unresolved)
+            };
+            if quiet_until.is_some() {
+                // The queued copy cannot ping anyone; mentions become plain text.
+                let text = apply_mentions(&text, &resolved, |m| format!("@{}", m.display));
+                return match deliver_or_defer_bot_message(
+                    &self.registry,
+                    self.db.clone(),
+                    &sender_name,
+                    chat_id,
+                    &text,
+                )
+                .await
+                {
+                    Ok(OutboundDelivery::Deferred(until)) => ToolResult::success(format!(
+                        "Chat {chat_id} is in quiet hours; message queued for delivery at {until}."
+                    ))
+                    .with_metadata(json!({"deferred_until": until.to_rfc3339()})),
+                    Ok(OutboundDelivery::Sent) => {
+                        ToolResult::success("Message sent successfully.".into())
+                    }
+                    Err(e) => ToolResult::error(e),
+                };
+            }
+            match deliver_and_store_bot_message_with_mentions(
                 &self.registry,
                 self.db.clone(),
                 &sender_name,
                 chat_id,
                 &text,
+                &resolved,
             )
             .await
             {
                 Ok(_) => {
                     info!("send_message text sent: chat_id={}", chat_id);
-                    ToolResult::success("Message sent successfully.".into())
+                    if unresolved.is_empty() {
+                        ToolResult::success("Message sent successfully.".into())
+                    } else {
+                        ToolResult::success(format!(
+                            "Message sent successfully. Warning: could not resolve mention(s) {}; sent as plain text.",
+                            unresolved.join(", ")
+                        ))
+                    }
                 }
                 Err(e) => {
                     warn!(
@@ -261,7 +431,7 @@ mod tests {
     use super::*;
     use microclaw_channels::channel::ConversationKind;
     use microclaw_channels::channel_adapter::ChannelAdapter;
-    use microclaw_channels::channel_adapter::ChannelRegistry;
+    use microclaw_channels::channel_adapter::{ChannelRegistry, ReplyDecoration};
     use serde_json::json;
     use std::path::Path;
 
@@ -351,6 +521,148 @@ mod tests {
         cleanup(&dir);
     }
 
+    #[tokio::test]
+    async fn test_send_message_scheduled_run_cannot_message_other_chats() {
+        let (db, dir) = test_db();
+        let tool = SendMessageTool::new(
+            test_registry(),
+            db,
+            "bot".into(),
+            std::collections::HashMap::new(),
+        );
+        let input = |sender: Option<&str>| {
+            json!({
+                "chat_id": 200,
+                "text": "hello",
+                "__microclaw_auth": {
+                    "caller_chat_id": 100,
+                    "control_chat_ids": [100],
+                    "caller_sender_id": sender
+                }
+            })
+        };
+        let result = tool.execute(input(None)).await;
+        assert!(result.is_error);
+        assert!(result.content.contains("Proactive messaging is disabled"));
+
+        let result = tool.execute(input(Some("alice"))).await;
+        assert!(!result.content.contains("Proactive messaging"));
+
+        let tool = tool.with_proactive_messaging(true);
+        let result = tool.execute(input(None)).await;
+        assert!(!result.content.contains("Proactive messaging"));
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_send_message_defers_scheduled_sends_during_quiet_hours() {
+        use microclaw_channels::channel::flush_deferred_messages;
+        use microclaw_channels::quiet_hours::QuietHours;
+
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("local", "ext-1", Some("ops"), "private")
+            .unwrap();
+        let now = chrono::Utc::now();
+        let window = QuietHours::parse(
+            &(now - chrono::Duration::hours(1))
+                .format("%H:%M")
+                .to_string(),
+            &(now + chrono::Duration::hours(1))
+                .format("%H:%M")
+                .to_string(),
+            "UTC",
+        )
+        .unwrap();
+        let mut registry = ChannelRegistry::new();
+        registry.register(Arc::new(LocalOnlyAdapter {
+            name: "local".to_string(),
+        }));
+        registry.set_quiet_hours("local", Some(window));
+        let tool = SendMessageTool::new(
+            Arc::new(registry),
+            db.clone(),
+            "bot".into(),
+            std::collections::HashMap::new(),
+        );
+        let input = |sender: Option<&str>, text: &str| {
+            json!({
+                "chat_id": chat_id,
+                "text": text,
+                "__microclaw_auth": {
+                    "caller_chat_id": chat_id,
+                    "control_chat_ids": [],
+                    "caller_sender_id": sender
+                }
+            })
+        };
+
+        let result = tool.execute(input(None, "nightly report")).await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("quiet hours; message queued"));
+        assert!(result.metadata.unwrap()["deferred_until"].is_string());
+        assert!(db.get_all_messages(chat_id).unwrap().is_empty());
+        assert_eq!(db.list_deferred_messages().unwrap().len(), 1);
+
+        // Replies to a user go out immediately.
+        let result = tool.execute(input(Some("alice"), "on it")).await;
+        assert_eq!(result.content, "Message sent successfully.");
+        assert_eq!(db.get_all_messages(chat_id).unwrap().len(), 1);
+
+        let mut open = ChannelRegistry::new();
+        open.register(Arc::new(LocalOnlyAdapter {
+            name: "local".to_string(),
+        }));
+        assert_eq!(flush_deferred_messages(&open, db.clone()).await, 1);
+        let stored = db.get_all_messages(chat_id).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().any(|m| m.content == "nightly report"));
+        assert!(db.list_deferred_messages().unwrap().is_empty());
+        cleanup(&dir);
+    }
+
+    struct FailingAdapter;
+
+    #[async_trait::async_trait]
+    impl ChannelAdapter for FailingAdapter {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
+            vec![("private", ConversationKind::Private)]
+        }
+
+        async fn send_text(&self, _external_chat_id: &str, _text: &str) -> Result<(), String> {
+            Err("upstream unavailable".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_deferred_message_stays_queued_until_attempts_run_out() {
+        use microclaw_channels::channel::{flush_deferred_messages, DEFERRED_MESSAGE_MAX_ATTEMPTS};
+
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("flaky", "ext-1", Some("ops"), "private")
+            .unwrap();
+        db.insert_deferred_message(chat_id, "bot", "nightly report")
+            .unwrap();
+        let mut registry = ChannelRegistry::new();
+        registry.register(Arc::new(FailingAdapter));
+
+        for attempt in 1..DEFERRED_MESSAGE_MAX_ATTEMPTS {
+            assert_eq!(flush_deferred_messages(&registry, db.clone()).await, 0);
+            let queued = db.list_deferred_messages().unwrap();
+            assert_eq!(queued.len(), 1);
+            assert_eq!(queued[0].attempts, attempt);
+        }
+        assert_eq!(flush_deferred_messages(&registry, db.clone()).await, 0);
+        assert!(db.list_deferred_messages().unwrap().is_empty());
+        assert!(db.get_all_messages(chat_id).unwrap().is_empty());
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_send_message_uses_channel_account_sender_name() {
         let (db, dir) = test_db();
@@ -390,6 +702,142 @@ mod tests {
         cleanup(&dir);
     }
 
+    struct MentionAdapter {
+        sent: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChannelAdapter for MentionAdapter {
+        fn name(&self) -> &str {
+            "mentions"
+        }
+
+        fn chat_type_routes(&self) -> Vec<(&str, ConversationKind)> {
+            vec![("mentions_group", ConversationKind::Group)]
+        }
+
+        async fn send_text(&self, _external_chat_id: &str, text: &str) -> Result<(), String> {
+            self.sent.lock().unwrap().push(text.to_string());
+            Ok(())
+        }
+
+        async fn resolve_mention(
+            &self,
+            _external_chat_id: &str,
+            name: &str,
+            known_ids: &[String],
+        ) -> Option<ResolvedMention> {
+            known_ids
+                .iter()
+                .any(|id| id == name)
+                .then(|| ResolvedMention {
+                    name: name.to_string(),
+                    user_id: format!("id-{name}"),
+                    display: name.to_uppercase(),
+                })
+        }
+
+        async fn send_text_with_mentions(
+            &self,
+            external_chat_id: &str,
+            text: &str,
+            mentions: &[ResolvedMention],
+        ) -> Result<(), String> {
+            let text = apply_mentions(text, mentions, |m| format!("<at {}>", m.user_id));
+            self.send_text(external_chat_id, &text).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_message_resolves_mentions_and_falls_back_to_plain_text() {
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("mentions", "room-1", Some("room"), "mentions_group")
+            .unwrap();
+        db.store_message(&StoredMessage {
+            id: "m1".to_string(),
+            chat_id,
+            sender_name: "alice".to_string(),
+            content: "hi".to_string(),
+            is_from_bot: false,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+        .unwrap();
+
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut registry = ChannelRegistry::new();
+        registry.register(Arc::new(MentionAdapter { sent: sent.clone() }));
+        let tool = SendMessageTool::new(
+            Arc::new(registry),
+            db.clone(),
+            "bot".into(),
+            std::collections::HashMap::new(),
+        );
+        let result = tool
+            .execute(json!({
+                "chat_id": chat_id,
+                "text": "@alice please review",
+                "mentions": ["@alice", "bob", "alice"],
+                "__microclaw_auth": {
+                    "caller_chat_id": chat_id,
+                    "control_chat_ids": []
+                }
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert!(result.content.contains("could not resolve mention(s) bob"));
+        assert_eq!(
+            sent.lock().unwrap().as_slice(),
+            ["@bob <at id-alice> please review"]
+        );
+        let stored = db.get_all_messages(chat_id).unwrap();
+        assert_eq!(stored.last().unwrap().content, "@bob @ALICE please review");
+        cleanup(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_send_message_applies_reply_decoration_but_stores_plain_text() {
+        let (db, dir) = test_db();
+        let chat_id = db
+            .resolve_or_create_chat_id("mentions", "room-2", Some("room"), "mentions_group")
+            .unwrap();
+
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut registry = ChannelRegistry::new();
+        registry.register(Arc::new(MentionAdapter { sent: sent.clone() }));
+        registry.set_reply_decoration(
+            "mentions",
+            ReplyDecoration {
+                prefix: "[Ops]".into(),
+                suffix: "Automated reply.".into(),
+            },
+        );
+        let tool = SendMessageTool::new(
+            Arc::new(registry),
+            db.clone(),
+            "bot".into(),
+            std::collections::HashMap::new(),
+        );
+        let result = tool
+            .execute(json!({
+                "chat_id": chat_id,
+                "text": "deploy finished",
+                "__microclaw_auth": {
+                    "caller_chat_id": chat_id,
+                    "control_chat_ids": []
+                }
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+        assert_eq!(
+            sent.lock().unwrap().as_slice(),
+            ["[Ops] deploy finished\n\nAutomated reply."]
+        );
+        let stored = db.get_all_messages(chat_id).unwrap();
+        assert_eq!(stored.last().unwrap().content, "deploy finished");
+        cleanup(&dir);
+    }
+
     #[tokio::test]
     async fn test_send_message_requires_text_or_attachment() {
         let (db, dir) = test_db();
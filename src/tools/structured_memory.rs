@@ -3,12 +3,144 @@ use serde_json::json;
 use std::sync::Arc;
 use tracing::info;
 
-use crate::memory_backend::MemoryBackend;
+use crate::memory_backend::{MemoryBackend, MemoryWriteOutcome};
 use microclaw_core::llm_types::ToolDefinition;
-use microclaw_storage::db::Database;
+use microclaw_storage::db::{Database, Memory, MemoryBatchOp, MemoryScope};
+use microclaw_storage::memory_quality;
 
 use super::{auth_context_from_input, authorize_chat_access, schema_object, Tool, ToolResult};
 
+const VALID_CATEGORIES: [&str; 3] = ["PROFILE", "KNOWLEDGE", "EVENT"];
+
+/// Resolve a requested scope (`global`, `chat`, `chat:<id>`, `user`, `user:<id>`)
+/// against the caller, enforcing who may write where.
+fn resolve_write_scope(input: &serde_json::Value, raw: &str) -> Result<MemoryScope, String> {
+    let auth = auth_context_from_input(input);
+    let scope = match raw.trim() {
+        "chat" => match &auth {
+            Some(auth) => MemoryScope::Chat(auth.caller_chat_id),
+            None => return Err("Scope 'chat' requires a calling chat".into()),
+        },
+        "user" => match auth.as_ref().and_then(|a| a.caller_sender_id.clone()) {
+            Some(sender) => MemoryScope::User(sender),
+            None => return Err("Scope 'user' requires a known sender".into()),
+        },
+        other => MemoryScope::parse(other).ok_or_else(|| {
+            format!("Invalid scope '{other}'. Use global, chat, chat:<id>, user or user:<id>")
+        })?,
+    };
+    let Some(auth) = auth else {
+        return Ok(scope);
+    };
+    match &scope {
+        MemoryScope::Global if !auth.is_control_chat() => Err(format!(
+            "Permission denied: only control chats can write global memories (caller: {})",
+            auth.caller_chat_id
+        )),
+        MemoryScope::Chat(chat_id) => authorize_chat_access(input, *chat_id).map(|_| scope),
+        MemoryScope::User(sender)
+            if auth.caller_sender_id.as_deref() != Some(sender.as_str())
+                && !auth.is_control_chat() =>
+        {
+            Err(format!(
+                "Permission denied: cannot write memories for another user ({sender})"
+            ))
+        }
+        _ => Ok(scope),
+    }
+}
+
+/// Scope for a write that names none: `global` where the caller may write it,
+/// otherwise the calling chat.
+fn default_write_scope(input: &serde_json::Value) -> &'static str {
+    match auth_context_from_input(input) {
+        Some(auth) if !auth.is_control_chat() => "chat",
+        _ => "global",
+    }
+}
+
+/// Delete/update authorization: chat memories need chat access, global ones a
+/// control chat, and user memories their owner (or a control chat).
+fn authorize_memory_change(
+    input: &serde_json::Value,
+    mem: &Memory,
+    action: &str,
+) -> Result<(), String> {
+    let Some(auth) = auth_context_from_input(input) else {
+        return Ok(());
+    };
+    if let Some(mem_chat_id) = mem.chat_id {
+        authorize_chat_access(input, mem_chat_id)?;
+    }
+    match mem.scope() {
+        MemoryScope::Global if !auth.is_control_chat() => Err(format!(
+            "Permission denied: only control chats can {action} global memories (caller: {})",
+            auth.caller_chat_id
+        )),
+        MemoryScope::User(owner)
+            if auth.caller_sender_id.as_deref() != Some(owner.as_str())
+                && !auth.is_control_chat() =>
+        {
+            Err(format!(
+                "Permission denied: memory id={} belongs to another user",
+                mem.id
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A search hit with its decayed confidence and final ranking score.
+struct RankedMemory<'a> {
+    memory: &'a Memory,
+    scope: MemoryScope,
+    confidence: f64,
+    score: f64,
+}
+
+/// Order hits by relevance weighted by decayed confidence. Relevance blends the
+/// backend's order (similarity for vector search) with how many query words the
+/// memory contains, so a stale fact drops below a fresher, equally relevant one.
+fn rank_memories<'a>(
+    hits: Vec<(&'a Memory, MemoryScope)>,
+    query: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<RankedMemory<'a>> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let total = hits.len().max(1) as f64;
+    let mut ranked: Vec<RankedMemory<'a>> = hits
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (memory, scope))| {
+            let content = memory.content.to_lowercase();
+            let coverage = if terms.is_empty() {
+                0.0
+            } else {
+                terms
+                    .iter()
+                    .filter(|t| content.contains(t.as_str()))
+                    .count() as f64
+                    / terms.len() as f64
+            };
+            let position = 1.0 - idx as f64 / total;
+            // Pinned memories do not decay.
+            let confidence = if memory.pinned {
+                memory.confidence
+            } else {
+                memory_quality::effective_confidence(memory.confidence, &memory.last_seen_at, now)
+            };
+            RankedMemory {
+                memory,
+                scope,
+                confidence,
+                score: memory_quality::memory_rank_score((coverage + position) / 2.0, confidence),
+            }
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
 // ── Search ────────────────────────────────────────────────────────────────────
 
 pub struct StructuredMemorySearchTool {
@@ -31,7 +163,7 @@ impl Tool for StructuredMemorySearchTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "structured_memory_search".into(),
-            description: "Search structured memories extracted from past conversations. Returns memories whose content contains the query string.".into(),
+            description: "Search structured memories extracted from past conversations. Returns memories whose content contains the query string and that are visible here: global memories, this chat's memories, and your own user memories. Results are ranked by relevance and confidence; confidence grows when a fact is written again and decays while it goes unconfirmed, so prefer high-confidence results when they conflict.".into(),
             input_schema: schema_object(
                 json!({
                     "query": {
@@ -45,6 +177,11 @@ impl Tool for StructuredMemorySearchTool {
                     "include_archived": {
                         "type": "boolean",
                         "description": "Whether to include archived memories in results (default false)"
+                    },
+                    "scope": {
+                        "type": "string",
+                        "description": "Only return memories of this scope kind",
+                        "enum": ["global", "chat", "user"]
                     }
                 }),
                 &["query"],
@@ -67,41 +204,218 @@ impl Tool for StructuredMemorySearchTool {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        let chat_id = auth_context_from_input(&input)
-            .map(|a| a.caller_chat_id)
-            .unwrap_or(0);
+        let scope_kind = input.get("scope").and_then(|v| v.as_str());
+        if let Some(kind) = scope_kind {
+            if !["global", "chat", "user"].contains(&kind) {
+                return ToolResult::error(format!(
+                    "Invalid scope '{kind}'. Must be one of: global, chat, user"
+                ));
+            }
+        }
+
+        let auth = auth_context_from_input(&input);
+        let chat_id = auth.as_ref().map(|a| a.caller_chat_id).unwrap_or(0);
+        let sender_id = auth.and_then(|a| a.caller_sender_id);
 
         info!(
             "structured_memory_search: query={query:?} chat_id={chat_id} limit={limit} include_archived={include_archived}"
         );
 
+        // Over-fetch so hidden user memories do not starve the visible results.
         match self
             .memory_backend
-            .search_memories_with_options(chat_id, &query, limit, include_archived, true)
+            .search_memories_with_options(chat_id, &query, limit * 3, include_archived, true)
             .await
         {
-            Ok(memories) if memories.is_empty() => {
-                ToolResult::success("No memories found matching that query.".into())
-            }
             Ok(memories) => {
-                let lines: Vec<String> = memories
+                let visible: Vec<(&Memory, MemoryScope)> = memories
                     .iter()
-                    .map(|m| {
-                        let scope = if m.chat_id.is_none() {
-                            "global"
-                        } else {
-                            "chat"
-                        };
-                        format!("[id={}] [{}] [{}] {}", m.id, m.category, scope, m.content)
+                    .filter(|m| m.visible_to(chat_id, sender_id.as_deref()))
+                    .map(|m| (m, m.scope()))
+                    .filter(|(_, scope)| match scope_kind {
+                        Some("global") => *scope == MemoryScope::Global,
+                        Some("chat") => matches!(scope, MemoryScope::Chat(_)),
+                        Some("user") => matches!(scope, MemoryScope::User(_)),
+                        _ => true,
                     })
                     .collect();
-                ToolResult::success(lines.join("\n"))
+                let ranked = rank_memories(visible, &query, chrono::Utc::now());
+                if ranked.is_empty() {
+                    return ToolResult::success("No memories found matching that query.".into());
+                }
+                let hits = &ranked[..ranked.len().min(limit)];
+                let lines: Vec<String> = hits
+                    .iter()
+                    .map(|h| {
+                        format!(
+                            "[id={}] [{}] [{}]{} {} (confidence {:.2})",
+                            h.memory.id,
+                            h.memory.category,
+                            h.scope,
+                            if h.memory.pinned { " [pinned]" } else { "" },
+                            h.memory.content,
+                            h.confidence
+                        )
+                    })
+                    .collect();
+                let results: Vec<serde_json::Value> = hits
+                    .iter()
+                    .map(|h| {
+                        json!({
+                            "id": h.memory.id,
+                            "scope": h.scope.to_string(),
+                            "confidence": h.confidence,
+                            "stored_confidence": h.memory.confidence,
+                            "last_seen_at": h.memory.last_seen_at,
+                            "score": h.score,
+                            "pinned": h.memory.pinned,
+                        })
+                    })
+                    .collect();
+                ToolResult::success(lines.join("\n")).with_metadata(json!({ "results": results }))
             }
             Err(e) => ToolResult::error(format!("Search failed: {e}")),
         }
     }
 }
 
+// ── Write ─────────────────────────────────────────────────────────────────────
+
+pub struct StructuredMemoryWriteTool {
+    memory_backend: Arc<MemoryBackend>,
+}
+
+impl StructuredMemoryWriteTool {
+    pub fn new(memory_backend: Arc<MemoryBackend>) -> Self {
+        Self { memory_backend }
+    }
+}
+
+#[async_trait]
+impl Tool for StructuredMemoryWriteTool {
+    fn name(&self) -> &str {
+        "structured_memory_write"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "structured_memory_write".into(),
+            description: "Store a structured memory in a scope. 'global' is visible everywhere (control chats only), 'chat' / 'chat:<id>' only in that chat, and 'user' / 'user:<id>' only to that user in any chat. Defaults to 'global' in control chats and 'chat' elsewhere.".into(),
+            input_schema: schema_object(
+                json!({
+                    "content": {
+                        "type": "string",
+                        "description": "The fact to remember (max 300 characters)"
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "Category: PROFILE, KNOWLEDGE, or EVENT (default KNOWLEDGE)",
+                        "enum": ["PROFILE", "KNOWLEDGE", "EVENT"]
+                    },
+                    "scope": {
+                        "type": "string",
+                        "description": "global, chat, chat:<id>, user, or user:<id> (default: global in control chats, chat elsewhere)"
+                    },
+                    "pinned": {
+                        "type": "boolean",
+                        "description": "Include this memory in context every turn, regardless of relevance (default false). Reserve for standing rules and key facts."
+                    }
+                }),
+                &["content"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let content = match input.get("content").and_then(|v| v.as_str()) {
+            Some(c) if !c.trim().is_empty() => c.trim().to_string(),
+            _ => return ToolResult::error("Missing or empty 'content' parameter".into()),
+        };
+        if content.len() > 300 {
+            return ToolResult::error("Content exceeds 300 character limit".into());
+        }
+        let category = input
+            .get("category")
+            .and_then(|v| v.as_str())
+            .unwrap_or("KNOWLEDGE");
+        if !VALID_CATEGORIES.contains(&category) {
+            return ToolResult::error(format!(
+                "Invalid category '{category}'. Must be one of: PROFILE, KNOWLEDGE, EVENT"
+            ));
+        }
+        let raw_scope = match input.get("scope").and_then(|v| v.as_str()) {
+            Some(raw) => raw,
+            None => default_write_scope(&input),
+        };
+        let scope = match resolve_write_scope(&input, raw_scope) {
+            Ok(scope) => scope,
+            Err(e) => return ToolResult::error(e),
+        };
+
+        info!("structured_memory_write: scope={scope} category={category}");
+
+        let outcome = match &scope {
+            MemoryScope::Global | MemoryScope::Chat(_) => {
+                let chat_id = match scope {
+                    MemoryScope::Chat(id) => Some(id),
+                    _ => None,
+                };
+                self.memory_backend
+                    .insert_memory_deduped(
+                        chat_id,
+                        &content,
+                        category,
+                        "structured_memory_tool",
+                        0.85,
+                    )
+                    .await
+            }
+            // Dedup compares against the unowned pool, so user memories are
+            // always stored as their own row.
+            MemoryScope::User(sender) => self
+                .memory_backend
+                .write_batch(vec![MemoryBatchOp::Insert {
+                    chat_id: None,
+                    content: content.clone(),
+                    category: category.to_string(),
+                    source: "structured_memory_tool".into(),
+                    confidence: 0.85,
+                    sender_id: Some(sender.clone()),
+                    embedding: None,
+                }])
+                .await
+                .map(|ids| MemoryWriteOutcome::Stored(ids[0])),
+        };
+
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => return ToolResult::error(format!("Write failed: {e}")),
+        };
+        let mut message = match outcome {
+            MemoryWriteOutcome::Stored(id) => format!("Memory id={id} stored in {scope} scope."),
+            MemoryWriteOutcome::Merged(id) => {
+                format!("Merged into existing memory id={id} in {scope} scope.")
+            }
+            MemoryWriteOutcome::Skipped(id) => format!(
+                "Memory unchanged: near-duplicate of id={id}; its confidence was reinforced."
+            ),
+        };
+        if input
+            .get("pinned")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            let id = outcome.memory_id();
+            match self.memory_backend.set_memory_pinned(id, true).await {
+                Ok(true) => message.push_str(&format!(" Memory id={id} is pinned.")),
+                Ok(false) => message.push_str(&format!(" Memory id={id} could not be pinned.")),
+                Err(e) => return ToolResult::error(format!("Pin failed: {e}")),
+            }
+        }
+        ToolResult::success(message)
+    }
+}
+
 // ── Delete ────────────────────────────────────────────────────────────────────
 
 pub struct StructuredMemoryDeleteTool {
@@ -124,7 +438,7 @@ impl Tool for StructuredMemoryDeleteTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "structured_memory_delete".into(),
-            description: "Archive a structured memory by its id (soft delete). Use structured_memory_search first to find the id. You can only archive memories that belong to the current chat or global memories if you are a control chat.".into(),
+            description: "Archive a structured memory by its id (soft delete). Use structured_memory_search first to find the id. You can only archive memories that belong to the current chat, your own user memories, or global memories if you are a control chat.".into(),
             input_schema: schema_object(
                 json!({
                     "id": {
@@ -150,24 +464,8 @@ impl Tool for StructuredMemoryDeleteTool {
             Err(e) => return ToolResult::error(format!("DB error: {e}")),
         };
 
-        // Authorize: caller must own the chat or be a control chat; global memories only by control
-        if let Some(auth) = auth_context_from_input(&input) {
-            match mem.chat_id {
-                Some(mem_chat_id) => {
-                    if let Err(e) = authorize_chat_access(&input, mem_chat_id) {
-                        return ToolResult::error(e);
-                    }
-                }
-                None => {
-                    // Global memory — requires control chat
-                    if !auth.is_control_chat() {
-                        return ToolResult::error(format!(
-                            "Permission denied: only control chats can delete global memories (caller: {})",
-                            auth.caller_chat_id
-                        ));
-                    }
-                }
-            }
+        if let Err(e) = authorize_memory_change(&input, &mem, "delete") {
+            return ToolResult::error(e);
         }
 
         info!("structured_memory_delete: id={id}");
@@ -244,23 +542,8 @@ impl Tool for StructuredMemoryUpdateTool {
             Err(e) => return ToolResult::error(format!("DB error: {e}")),
         };
 
-        // Authorize same as delete
-        if let Some(auth) = auth_context_from_input(&input) {
-            match mem.chat_id {
-                Some(mem_chat_id) => {
-                    if let Err(e) = authorize_chat_access(&input, mem_chat_id) {
-                        return ToolResult::error(e);
-                    }
-                }
-                None => {
-                    if !auth.is_control_chat() {
-                        return ToolResult::error(format!(
-                            "Permission denied: only control chats can update global memories (caller: {})",
-                            auth.caller_chat_id
-                        ));
-                    }
-                }
-            }
+        if let Err(e) = authorize_memory_change(&input, &mem, "update") {
+            return ToolResult::error(e);
         }
 
         let category = input
@@ -269,8 +552,7 @@ impl Tool for StructuredMemoryUpdateTool {
             .unwrap_or(&mem.category)
             .to_string();
 
-        let valid_categories = ["PROFILE", "KNOWLEDGE", "EVENT"];
-        if !valid_categories.contains(&category.as_str()) {
+        if !VALID_CATEGORIES.contains(&category.as_str()) {
             return ToolResult::error(format!(
                 "Invalid category '{category}'. Must be one of: PROFILE, KNOWLEDGE, EVENT"
             ));
@@ -324,6 +606,86 @@ mod tests {
         assert!(!result.content.contains("coffee"));
     }
 
+    fn memory(id: i64, content: &str, confidence: f64, last_seen_at: &str) -> Memory {
+        Memory {
+            id,
+            chat_id: None,
+            content: content.into(),
+            category: "KNOWLEDGE".into(),
+            created_at: last_seen_at.into(),
+            updated_at: last_seen_at.into(),
+            embedding_model: None,
+            confidence,
+            source: "test".into(),
+            last_seen_at: last_seen_at.into(),
+            is_archived: false,
+            archived_at: None,
+            sender_id: None,
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn test_rank_memories_prefers_fresh_confident_facts() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let stale = memory(1, "Deploys go through Jenkins", 0.9, "2025-06-01T00:00:00Z");
+        let fresh = memory(
+            2,
+            "Deploys go through GitHub Actions",
+            0.8,
+            "2026-05-30T00:00:00Z",
+        );
+        let ranked = rank_memories(
+            vec![(&stale, stale.scope()), (&fresh, fresh.scope())],
+            "deploys",
+            now,
+        );
+        assert_eq!(ranked[0].memory.id, 2);
+        assert!(ranked[0].score > ranked[1].score);
+        assert!(ranked[1].confidence < 0.2, "{}", ranked[1].confidence);
+    }
+
+    #[tokio::test]
+    async fn test_rewriting_memory_reinforces_confidence() {
+        let db = test_db();
+        let backend = test_backend(db.clone());
+        let write = StructuredMemoryWriteTool::new(backend.clone());
+        let input = json!({
+            "content": "Staging database runs on port 5433",
+            "__microclaw_auth": {"caller_chat_id": 1, "control_chat_ids": [1]}
+        });
+        let first = write.execute(input.clone()).await;
+        assert!(first.content.contains("stored"), "{}", first.content);
+        let second = write.execute(input).await;
+        assert!(
+            second.content.contains("confidence was reinforced"),
+            "{}",
+            second.content
+        );
+
+        let memories = db.get_all_memories_for_chat(None).unwrap();
+        assert_eq!(memories.len(), 1);
+        assert!(memories[0].confidence > 0.85);
+
+        let search = StructuredMemorySearchTool::new(db.clone(), backend);
+        let result = search
+            .execute(json!({
+                "query": "staging",
+                "__microclaw_auth": {"caller_chat_id": 1, "control_chat_ids": [1]}
+            }))
+            .await;
+        assert!(
+            result.content.contains("port 5433 (confidence 0.89)"),
+            "{}",
+            result.content
+        );
+        let metadata = result.metadata.unwrap();
+        assert_eq!(metadata["results"][0]["id"], memories[0].id);
+        assert!(metadata["results"][0]["score"].as_f64().unwrap() > 0.0);
+    }
+
     #[tokio::test]
     async fn test_search_empty_query_errors() {
         let db = test_db();
@@ -398,4 +760,136 @@ mod tests {
         assert!(result.is_error);
         assert!(result.content.contains("300 character"));
     }
+
+    #[tokio::test]
+    async fn test_write_pinned_memory_shows_in_search() {
+        let db = test_db();
+        let backend = test_backend(db.clone());
+        let write = StructuredMemoryWriteTool::new(backend.clone());
+        let result = write
+            .execute(json!({
+                "content": "Deploys freeze on Fridays",
+                "scope": "chat",
+                "pinned": true,
+                "__microclaw_auth": {"caller_chat_id": 100, "control_chat_ids": []}
+            }))
+            .await;
+        assert!(result.content.contains("is pinned"), "{}", result.content);
+        assert_eq!(db.get_pinned_memories(100).unwrap().len(), 1);
+
+        let search = StructuredMemorySearchTool::new(db.clone(), backend);
+        let result = search
+            .execute(json!({
+                "query": "deploys",
+                "__microclaw_auth": {"caller_chat_id": 100, "control_chat_ids": []}
+            }))
+            .await;
+        assert!(
+            result.content.contains("] [pinned] Deploys freeze"),
+            "{}",
+            result.content
+        );
+        assert_eq!(result.metadata.unwrap()["results"][0]["pinned"], true);
+    }
+
+    #[tokio::test]
+    async fn test_write_scopes_and_search_visibility() {
+        let db = test_db();
+        let backend = test_backend(db.clone());
+        let write = StructuredMemoryWriteTool::new(backend.clone());
+        let auth = |chat: i64, sender: &str| json!({"caller_chat_id": chat, "caller_sender_id": sender, "control_chat_ids": []});
+
+        let denied = write
+            .execute(json!({
+                "content": "prod db is pg15",
+                "scope": "global",
+                "__microclaw_auth": auth(100, "ou_alice")
+            }))
+            .await;
+        assert!(denied.is_error);
+        assert!(denied.content.contains("only control chats"));
+
+        // Without a scope, a non-control chat writes to itself.
+        let plain = write
+            .execute(
+                json!({"content": "release train is weekly", "__microclaw_auth": auth(100, "ou_alice")}),
+            )
+            .await;
+        assert!(!plain.is_error, "{}", plain.content);
+        assert!(
+            plain.content.contains("in chat:100 scope"),
+            "{}",
+            plain.content
+        );
+        assert!(db.get_all_memories_for_chat(None).unwrap().is_empty());
+
+        for (content, scope) in [
+            ("alice prefers tea", "user"),
+            ("standup moved to 10", "chat"),
+        ] {
+            let result = write
+                .execute(json!({
+                    "content": content,
+                    "scope": scope,
+                    "__microclaw_auth": auth(100, "ou_alice")
+                }))
+                .await;
+            assert!(!result.is_error, "{}", result.content);
+        }
+        let other_user = write
+            .execute(json!({
+                "content": "bob prefers coffee",
+                "scope": "user:ou_bob",
+                "__microclaw_auth": auth(100, "ou_alice")
+            }))
+            .await;
+        assert!(other_user.is_error);
+
+        let search = StructuredMemorySearchTool::new(db.clone(), backend);
+        let alice_elsewhere = search
+            .execute(json!({"query": "prefers", "__microclaw_auth": auth(200, "ou_alice")}))
+            .await;
+        assert!(alice_elsewhere
+            .content
+            .contains("[user:ou_alice] alice prefers tea"));
+        let bob = search
+            .execute(json!({"query": "prefers", "__microclaw_auth": auth(100, "ou_bob")}))
+            .await;
+        assert!(!bob.content.contains("alice prefers tea"));
+        let chat_only = search
+            .execute(json!({
+                "query": "o",
+                "scope": "chat",
+                "__microclaw_auth": auth(100, "ou_bob")
+            }))
+            .await;
+        assert!(chat_only.content.contains("[chat:100] standup moved to 10"));
+        assert!(!chat_only.content.contains("tea"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_other_users_memory_denied() {
+        let db = test_db();
+        let id = db
+            .insert_memory(Some(100), "alice prefers tea", "PROFILE")
+            .unwrap();
+        db.set_memory_sender_id(id, Some("ou_alice")).unwrap();
+        let tool = StructuredMemoryDeleteTool::new(db.clone(), test_backend(db.clone()));
+        let result = tool
+            .execute(json!({
+                "id": id,
+                "__microclaw_auth": {"caller_chat_id": 100, "caller_sender_id": "ou_bob", "control_chat_ids": []}
+            }))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("belongs to another user"));
+
+        let result = tool
+            .execute(json!({
+                "id": id,
+                "__microclaw_auth": {"caller_chat_id": 100, "caller_sender_id": "ou_alice", "control_chat_ids": []}
+            }))
+            .await;
+        assert!(!result.is_error, "{}", result.content);
+    }
 }
@@ -1,19 +1,105 @@
 use async_trait::async_trait;
 use serde_json::json;
+use std::future::Future;
 use std::sync::Arc;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
 use super::{auth_context_from_input, schema_object, Tool, ToolRegistry, ToolResult};
-use crate::config::Config;
 #[cfg(test)]
 use crate::config::WorkingDirIsolation;
+use crate::config::{Config, ModelRole};
 use microclaw_core::llm_types::{
-    ContentBlock, Message, MessageContent, ResponseContentBlock, ToolDefinition,
+    ContentBlock, FinishReason, Message, MessageContent, ResponseContentBlock, ToolDefinition,
 };
 use microclaw_storage::db::{call_blocking, Database};
 
 const MAX_SUB_AGENT_ITERATIONS: usize = 10;
 
+/// Time and token limits for one sub-agent run. `None` means unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SubAgentBudget {
+    max_duration: Option<Duration>,
+    max_tokens: Option<u64>,
+}
+
+impl SubAgentBudget {
+    /// Per-call values from `input` may only tighten the configured bounds.
+    fn resolve(config: &Config, input: &serde_json::Value) -> Self {
+        let requested = |key: &str| input.get(key).and_then(|v| v.as_u64()).filter(|v| *v > 0);
+        let bound = |requested: Option<u64>, configured: u64| match (requested, configured) {
+            (Some(r), 0) => Some(r),
+            (Some(r), c) => Some(r.min(c)),
+            (None, 0) => None,
+            (None, c) => Some(c),
+        };
+        Self {
+            max_duration: bound(
+                requested("max_duration_secs"),
+                config.sub_agent_max_duration_secs,
+            )
+            .map(Duration::from_secs),
+            max_tokens: bound(requested("max_tokens"), config.sub_agent_max_tokens),
+        }
+    }
+
+    /// Runs `fut` within the remaining time budget; `None` if the deadline passed first.
+    async fn within<F: Future>(&self, started: Instant, fut: F) -> Option<F::Output> {
+        match self.max_duration {
+            None => Some(fut.await),
+            Some(max) => tokio::time::timeout(max.saturating_sub(started.elapsed()), fut)
+                .await
+                .ok(),
+        }
+    }
+
+    fn tokens_exhausted(&self, used: u64) -> bool {
+        self.max_tokens.is_some_and(|max| used >= max)
+    }
+}
+
+fn response_text(content: &[ResponseContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ResponseContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+fn budget_exceeded_result(
+    kind: &str,
+    partial: &str,
+    started: Instant,
+    tokens_used: u64,
+) -> ToolResult {
+    let elapsed = started.elapsed();
+    warn!(
+        "Sub-agent stopped: {} budget exceeded after {}ms and {} tokens",
+        kind,
+        elapsed.as_millis(),
+        tokens_used
+    );
+    let summary = format!(
+        "Sub-agent stopped: {kind} budget exceeded after {}s and {tokens_used} tokens.",
+        elapsed.as_secs()
+    );
+    let content = if partial.is_empty() {
+        format!("{summary} No partial result was produced.")
+    } else {
+        format!("{summary}\n\nPartial result:\n{partial}")
+    };
+    ToolResult::error(content)
+        .with_error_type("budget_exceeded")
+        .with_metadata(json!({
+            "budget_exceeded": kind,
+            "elapsed_ms": elapsed.as_millis() as u64,
+            "tokens_used": tokens_used,
+        }))
+}
+
 pub struct SubAgentTool {
     config: Config,
     db: Arc<Database>,
@@ -47,6 +133,16 @@ impl Tool for SubAgentTool {
                     "context": {
                         "type": "string",
                         "description": "Optional additional context to provide to the sub-agent"
+                    },
+                    "max_duration_secs": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Optional wall-clock limit in seconds; cannot exceed the configured limit"
+                    },
+                    "max_tokens": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Optional limit on input plus output tokens; cannot exceed the configured limit"
                     }
                 }),
                 &["task"],
@@ -62,10 +158,14 @@ impl Tool for SubAgentTool {
         };
 
         let context = input.get("context").and_then(|v| v.as_str()).unwrap_or("");
+        let budget = SubAgentBudget::resolve(&self.config, &input);
+        let started = Instant::now();
+        let mut tokens_used: u64 = 0;
+        let mut partial = String::new();
 
         info!("Sub-agent starting task: {}", task);
 
-        let llm = crate::llm::create_provider(&self.config);
+        let llm = crate::llm::create_provider_for_role(&self.config, ModelRole::SubAgent);
         let tools = ToolRegistry::new_sub_agent(&self.config, self.db.clone());
         let tool_defs = tools.definitions().to_vec();
 
@@ -83,24 +183,31 @@ impl Tool for SubAgentTool {
         }];
 
         for iteration in 0..MAX_SUB_AGENT_ITERATIONS {
-            let response = match llm
-                .send_message(&system_prompt, messages.clone(), Some(tool_defs.clone()))
+            let response = match budget
+                .within(
+                    started,
+                    llm.send_message(&system_prompt, messages.clone(), Some(tool_defs.clone())),
+                )
                 .await
             {
-                Ok(r) => r,
-                Err(e) => {
+                Some(Ok(r)) => r,
+                Some(Err(e)) => {
                     return ToolResult::error(format!("Sub-agent API error: {e}"));
                 }
+                None => return budget_exceeded_result("time", &partial, started, tokens_used),
             };
 
             if let Some(usage) = &response.usage {
+                tokens_used = tokens_used
+                    .saturating_add(u64::from(usage.input_tokens))
+                    .saturating_add(u64::from(usage.output_tokens));
                 let chat_id = auth_context.as_ref().map(|a| a.caller_chat_id).unwrap_or(0);
                 let caller_channel = auth_context
                     .as_ref()
                     .map(|a| a.caller_channel.clone())
                     .unwrap_or_else(|| "sub_agent".to_string());
                 let provider = self.config.llm_provider.clone();
-                let model = self.config.model.clone();
+                let model = self.config.model_for_role(ModelRole::SubAgent).to_string();
                 let input_tokens = i64::from(usage.input_tokens);
                 let output_tokens = i64::from(usage.output_tokens);
                 let _ = call_blocking(self.db.clone(), move |db| {
@@ -118,19 +225,10 @@ impl Tool for SubAgentTool {
                 .await;
             }
 
-            let stop_reason = response.stop_reason.as_deref().unwrap_or("end_turn");
-
-            if stop_reason == "end_turn" || stop_reason == "max_tokens" {
-                let text = response
-                    .content
-                    .iter()
-                    .filter_map(|block| match block {
-                        ResponseContentBlock::Text { text } => Some(text.as_str()),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>()
-                    .join("");
+            let finish_reason = response.finish_reason();
+            let text = response_text(&response.content);
 
+            if finish_reason != FinishReason::ToolUse && finish_reason != FinishReason::Other {
                 return ToolResult::success(if text.is_empty() {
                     "(sub-agent produced no output)".into()
                 } else {
@@ -138,24 +236,16 @@ impl Tool for SubAgentTool {
                 });
             }
 
-            if stop_reason == "tool_use" {
-                let assistant_content: Vec<ContentBlock> = response
-                    .content
-                    .iter()
-                    .filter_map(|block| match block {
-                        ResponseContentBlock::Text { text } => {
-                            Some(ContentBlock::Text { text: text.clone() })
-                        }
-                        ResponseContentBlock::ToolUse { id, name, input } => {
-                            Some(ContentBlock::ToolUse {
-                                id: id.clone(),
-                                name: name.clone(),
-                                input: input.clone(),
-                            })
-                        }
-                        ResponseContentBlock::Other => None,
-                    })
-                    .collect();
+            if !text.is_empty() {
+                partial = text.clone();
+            }
+            if budget.tokens_exhausted(tokens_used) {
+                return budget_exceeded_result("token", &partial, started, tokens_used);
+            }
+
+            if finish_reason == FinishReason::ToolUse {
+                let assistant_content =
+                    crate::agent_engine::assistant_history_blocks(&response.content);
 
                 messages.push(Message {
                     role: "assistant".into(),
@@ -170,14 +260,26 @@ impl Tool for SubAgentTool {
                             name,
                             iteration + 1
                         );
-                        let result = if let Some(ref auth) = auth_context {
-                            tools.execute_with_auth(name, input.clone(), auth).await
+                        let execution = async {
+                            if let Some(ref auth) = auth_context {
+                                tools.execute_with_auth(name, input.clone(), auth).await
+                            } else {
+                                tools.execute(name, input.clone()).await
+                            }
+                        };
+                        let Some(result) = budget.within(started, execution).await else {
+                            return budget_exceeded_result("time", &partial, started, tokens_used);
+                        };
+                        let content = if result.is_error {
+                            self.config
+                                .tool_error_hints
+                                .annotate(&result.content, result.error_type.as_deref())
                         } else {
-                            tools.execute(name, input.clone()).await
+                            result.content
                         };
                         tool_results.push(ContentBlock::ToolResult {
                             tool_use_id: id.clone(),
-                            content: result.content,
+                            content,
                             is_error: if result.is_error { Some(true) } else { None },
                         });
                     }
@@ -192,15 +294,6 @@ impl Tool for SubAgentTool {
             }
 
             // Unknown stop reason
-            let text = response
-                .content
-                .iter()
-                .filter_map(|block| match block {
-                    ResponseContentBlock::Text { text } => Some(text.as_str()),
-                    _ => None,
-                })
-                .collect::<Vec<_>>()
-                .join("");
             return ToolResult::success(if text.is_empty() {
                 "(sub-agent produced no output)".into()
             } else {
@@ -258,6 +351,127 @@ mod tests {
         assert!(result.content.contains("Missing required parameter: task"));
     }
 
+    #[test]
+    fn test_budget_resolve_clamps_to_config() {
+        let mut config = test_config();
+        let budget = SubAgentBudget::resolve(&config, &json!({}));
+        assert_eq!(budget.max_duration, None);
+
+        config.sub_agent_max_duration_secs = 60;
+        config.sub_agent_max_tokens = 0;
+
+        let budget = SubAgentBudget::resolve(&config, &json!({}));
+        assert_eq!(budget.max_duration, Some(Duration::from_secs(60)));
+        assert_eq!(budget.max_tokens, None);
+
+        let budget = SubAgentBudget::resolve(
+            &config,
+            &json!({"max_duration_secs": 600, "max_tokens": 5000}),
+        );
+        assert_eq!(budget.max_duration, Some(Duration::from_secs(60)));
+        assert_eq!(budget.max_tokens, Some(5000));
+
+        let budget = SubAgentBudget::resolve(&config, &json!({"max_duration_secs": 10}));
+        assert_eq!(budget.max_duration, Some(Duration::from_secs(10)));
+        assert!(!budget.tokens_exhausted(0));
+    }
+
+    /// Serves `response` (or nothing, when `None`) to every request on a local port.
+    async fn mock_llm(response: Option<serde_json::Value>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let response = response.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    loop {
+                        let n = stream.read(&mut chunk).await.unwrap_or(0);
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        let text = String::from_utf8_lossy(&buf).to_string();
+                        let Some(header_end) = text.find("\r\n\r\n") else {
+                            continue;
+                        };
+                        let content_length = text[..header_end]
+                            .lines()
+                            .find_map(|l| {
+                                let (k, v) = l.split_once(':')?;
+                                k.eq_ignore_ascii_case("content-length")
+                                    .then(|| v.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if buf.len() >= header_end + 4 + content_length {
+                            break;
+                        }
+                    }
+                    let Some(body) = response else {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        return;
+                    };
+                    let body = body.to_string();
+                    let reply = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(reply.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_sub_agent_time_budget_exceeded() {
+        let mut config = test_config();
+        config.llm_base_url = Some(mock_llm(None).await);
+        let tool = SubAgentTool::new(&config, test_db());
+        let result = tool
+            .execute(json!({"task": "research", "max_duration_secs": 1}))
+            .await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("budget_exceeded"));
+        assert!(result.content.contains("time budget exceeded"));
+        assert_eq!(result.metadata.unwrap()["budget_exceeded"], "time");
+    }
+
+    #[tokio::test]
+    async fn test_sub_agent_token_budget_returns_partial_result() {
+        let mut config = test_config();
+        config.llm_base_url = Some(
+            mock_llm(Some(json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [
+                    {"type": "text", "text": "Found three candidate files so far."},
+                    {"type": "tool_use", "id": "t1", "name": "glob", "input": {"pattern": "*.rs"}}
+                ],
+                "stop_reason": "tool_use",
+                "usage": {"input_tokens": 400, "output_tokens": 200}
+            })))
+            .await,
+        );
+        config.sub_agent_max_tokens = 500;
+        let tool = SubAgentTool::new(&config, test_db());
+        let result = tool.execute(json!({"task": "research"})).await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("budget_exceeded"));
+        assert!(result.content.contains("token budget exceeded"));
+        assert!(result
+            .content
+            .contains("Partial result:\nFound three candidate files so far."));
+        assert_eq!(result.metadata.unwrap()["tokens_used"], 600);
+    }
+
     #[test]
     fn test_sub_agent_restricted_registry_tool_count() {
         let config = test_config();
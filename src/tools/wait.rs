@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{report_tool_progress, schema_object, Tool, ToolResult};
+use microclaw_core::llm_types::ToolDefinition;
+
+/// Pauses the turn for a bounded time, for polling loops ("wait, then check the
+/// build again"). Only this chat's turn sleeps, and `/stop` ends it mid-wait.
+pub struct WaitTool {
+    max_secs: u64,
+}
+
+impl WaitTool {
+    pub fn new(max_secs: u64) -> Self {
+        Self { max_secs }
+    }
+}
+
+#[async_trait]
+impl Tool for WaitTool {
+    fn name(&self) -> &str {
+        "wait"
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: self.name().into(),
+            description: format!(
+                "Wait before continuing, e.g. between checks while polling a build or deploy. \
+                 Waits at most {} seconds per call; call it again to wait longer. Use this \
+                 instead of `sleep` in bash.",
+                self.max_secs
+            ),
+            input_schema: schema_object(
+                json!({
+                    "seconds": {
+                        "type": "number",
+                        "description": format!("How long to wait, in seconds (at most {})", self.max_secs)
+                    },
+                    "reason": {
+                        "type": "string",
+                        "description": "Optional short note on what you are waiting for, shown as progress"
+                    }
+                }),
+                &["seconds"],
+            ),
+        }
+    }
+
+    async fn execute(&self, input: serde_json::Value) -> ToolResult {
+        let Some(requested) = input
+            .get("seconds")
+            .and_then(|v| v.as_f64())
+            .filter(|s| s.is_finite() && *s > 0.0)
+        else {
+            return ToolResult::error("`seconds` must be a positive number".into())
+                .with_error_type("invalid_input");
+        };
+        let max = self.max_secs as f64;
+        let seconds = requested.min(max);
+        let reason = input
+            .get("reason")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|r| !r.is_empty());
+        report_tool_progress(match reason {
+            Some(reason) => format!("Waiting {seconds}s: {reason}"),
+            None => format!("Waiting {seconds}s"),
+        });
+        tokio::time::sleep(Duration::from_secs_f64(seconds)).await;
+        if requested > max {
+            ToolResult::success(format!(
+                "Waited {seconds}s (requested {requested}s; the limit per call is {max}s)."
+            ))
+        } else {
+            ToolResult::success(format!("Waited {seconds}s."))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_sleeps_and_caps_duration() {
+        let tool = WaitTool::new(0);
+        let started = std::time::Instant::now();
+        let out = tool.execute(json!({"seconds": 30})).await;
+        assert!(!out.is_error);
+        assert!(out.content.contains("requested 30s"), "{}", out.content);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let tool = WaitTool::new(5);
+        let started = std::time::Instant::now();
+        let out = tool
+            .execute(json!({"seconds": 0.05, "reason": "build"}))
+            .await;
+        assert_eq!(out.content, "Waited 0.05s.");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_wait_rejects_invalid_seconds() {
+        let tool = WaitTool::new(60);
+        for input in [json!({}), json!({"seconds": 0}), json!({"seconds": -3})] {
+            let out = tool.execute(input).await;
+            assert!(out.is_error);
+            assert_eq!(out.error_type.as_deref(), Some("invalid_input"));
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use async_trait::async_trait;
 use microclaw_tools::web_content_validation::WebContentValidationConfig;
-use microclaw_tools::web_fetch::WebFetchUrlValidationConfig;
+use microclaw_tools::web_domains::WebDomainPolicy;
+use microclaw_tools::web_fetch::{
+    RenderedFetchError, WebFetchCacheConfig, WebFetchCacheStatus, WebFetchHostRateConfig,
+    WebFetchMarkdownConfig, WebFetchOutcome, WebFetchProxyConfig, WebFetchRenderMode,
+    WebFetchUrlValidationConfig,
+};
 use serde_json::json;
+use tracing::warn;
 
+use super::browser::BrowserTool;
 use super::{schema_object, Tool, ToolResult};
 use microclaw_core::llm_types::ToolDefinition;
 
@@ -10,6 +17,13 @@ pub struct WebFetchTool {
     default_timeout_secs: u64,
     validation: WebContentValidationConfig,
     url_validation: WebFetchUrlValidationConfig,
+    cache: WebFetchCacheConfig,
+    proxy: WebFetchProxyConfig,
+    markdown: WebFetchMarkdownConfig,
+    host_rate: WebFetchHostRateConfig,
+    domain_policy: WebDomainPolicy,
+    /// Headless browser for `render: true`; without it such fetches fall back to plain.
+    renderer: Option<BrowserTool>,
 }
 
 impl WebFetchTool {
@@ -22,6 +36,75 @@ impl WebFetchTool {
             default_timeout_secs,
             validation,
             url_validation,
+            cache: WebFetchCacheConfig::default(),
+            proxy: WebFetchProxyConfig::default(),
+            markdown: WebFetchMarkdownConfig::default(),
+            host_rate: WebFetchHostRateConfig::default(),
+            domain_policy: WebDomainPolicy::default(),
+            renderer: None,
+        }
+    }
+
+    pub fn with_cache(mut self, cache: WebFetchCacheConfig) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: WebFetchProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn with_markdown(mut self, markdown: WebFetchMarkdownConfig) -> Self {
+        self.markdown = markdown;
+        self
+    }
+
+    pub fn with_host_rate(mut self, host_rate: WebFetchHostRateConfig) -> Self {
+        self.host_rate = host_rate;
+        self
+    }
+
+    pub fn with_domain_policy(mut self, domain_policy: WebDomainPolicy) -> Self {
+        self.url_validation.domain_policy = domain_policy.clone();
+        self.domain_policy = domain_policy;
+        self
+    }
+
+    pub fn with_renderer(mut self, renderer: BrowserTool) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+
+    /// Fetch through the headless browser. `Ok(Err(reason))` means the browser
+    /// could not render the page and the caller should fetch it plainly.
+    async fn fetch_rendered(
+        &self,
+        url: &str,
+        timeout_secs: u64,
+        mode: WebFetchRenderMode,
+    ) -> Result<Result<WebFetchOutcome, String>, String> {
+        let Some(renderer) = &self.renderer else {
+            return Ok(Err("no browser is configured".into()));
+        };
+        let result = microclaw_tools::web_fetch::fetch_rendered_url(
+            url,
+            timeout_secs,
+            self.validation,
+            self.url_validation.clone(),
+            &self.markdown,
+            mode,
+            &self.host_rate,
+            |url, timeout_secs| async move { renderer.render_html(&url, timeout_secs).await },
+        )
+        .await;
+        match result {
+            Ok(outcome) => Ok(Ok(outcome)),
+            Err(RenderedFetchError::Refused(e)) => Err(e),
+            Err(RenderedFetchError::Renderer(e)) => {
+                warn!("web_fetch render failed for {url}, falling back to plain fetch: {e}");
+                Ok(Err(e))
+            }
         }
     }
 }
@@ -36,7 +119,7 @@ impl Tool for WebFetchTool {
         ToolDefinition {
             name: "web_fetch".into(),
             description:
-                "Fetch a URL and return its text content (HTML parsed, scripts/styles removed). Max 20KB."
+                "Fetch a URL and return its content (scripts/styles removed). Article-like pages keep headings, lists, quotes and links as Markdown; others return plain text. Max 20KB. Set render=true for JavaScript-heavy pages (single-page apps) that return an empty shell: the page is loaded in a headless browser first, which is slower."
                     .into(),
             input_schema: schema_object(
                 json!({
@@ -47,6 +130,15 @@ impl Tool for WebFetchTool {
                     "timeout_secs": {
                         "type": "integer",
                         "description": "Timeout in seconds (defaults to configured tool timeout budget)"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["auto", "markdown", "text"],
+                        "description": "Output format. 'auto' (default) uses Markdown for article-like pages."
+                    },
+                    "render": {
+                        "type": "boolean",
+                        "description": "Run the page's JavaScript in a headless browser and read the rendered page (default false). Falls back to a plain fetch if the browser is unavailable."
                     }
                 }),
                 &["url"],
@@ -63,16 +155,99 @@ impl Tool for WebFetchTool {
             .get("timeout_secs")
             .and_then(|v| v.as_u64())
             .unwrap_or(self.default_timeout_secs);
+        let mode = match input.get("format").and_then(|v| v.as_str()) {
+            None => self.markdown.mode,
+            Some(v) => match WebFetchRenderMode::parse(v) {
+                Some(mode) => mode,
+                None => {
+                    return ToolResult::error("format must be one of: auto, markdown, text".into())
+                }
+            },
+        };
+        if let Err(reason) = self.domain_policy.check_url(url) {
+            return ToolResult::error(format!("Refused to fetch URL: {reason}"))
+                .with_error_type("domain_blocked");
+        }
 
-        match microclaw_tools::web_fetch::fetch_url_with_timeout_and_validation(
-            url,
-            timeout_secs,
-            self.validation,
-            self.url_validation.clone(),
-        )
-        .await
-        {
-            Ok(text) => ToolResult::success(text),
+        let mut notes = Vec::new();
+        let mut metadata = serde_json::Map::new();
+        let render = input
+            .get("render")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        // Rendered pages bypass the page cache.
+        let mut via_browser = false;
+        let rendered = if render {
+            match self.fetch_rendered(url, timeout_secs, mode).await {
+                Ok(Ok(outcome)) => {
+                    metadata.insert("render".into(), json!("browser"));
+                    via_browser = true;
+                    Some(Ok(outcome))
+                }
+                Ok(Err(reason)) => {
+                    notes.push(format!(
+                        "[render: browser unavailable ({reason}); returned a plain fetch without JavaScript]"
+                    ));
+                    metadata.insert("render".into(), json!("fallback"));
+                    None
+                }
+                Err(e) => Some(Err(e)),
+            }
+        } else {
+            None
+        };
+        let fetched = match rendered {
+            Some(result) => result,
+            None => {
+                microclaw_tools::web_fetch::fetch_url_with_cache(
+                    url,
+                    timeout_secs,
+                    self.validation,
+                    self.url_validation.clone(),
+                    &self.cache,
+                    &self.proxy,
+                    &self.markdown,
+                    mode,
+                    &self.host_rate,
+                )
+                .await
+            }
+        };
+
+        match fetched {
+            Ok(outcome) => {
+                if !outcome.metadata.is_empty() {
+                    metadata.insert("page".into(), json!(outcome.metadata));
+                }
+                if self.cache.enabled && !via_browser {
+                    notes.push(match outcome.cache_status {
+                        WebFetchCacheStatus::Fetched => "[cache: fetched fresh copy]".to_string(),
+                        WebFetchCacheStatus::Cached => "[cache: served from cache]".to_string(),
+                        WebFetchCacheStatus::Revalidated => {
+                            "[cache: revalidated, 304 Not Modified; served cached copy]".to_string()
+                        }
+                    });
+                    metadata.insert("cache".into(), json!(outcome.cache_status.as_str()));
+                }
+                if !outcome.rate_limit_wait.is_zero() {
+                    let wait_ms = outcome.rate_limit_wait.as_millis() as u64;
+                    notes.push(format!(
+                        "[rate limit: waited {:.1}s for this host's next request slot]",
+                        wait_ms as f64 / 1000.0
+                    ));
+                    metadata.insert("rate_limit_wait_ms".into(), json!(wait_ms));
+                }
+                let result = if notes.is_empty() {
+                    ToolResult::success(outcome.text)
+                } else {
+                    ToolResult::success(format!("{}\n\n{}", outcome.text, notes.join("\n")))
+                };
+                if metadata.is_empty() {
+                    result
+                } else {
+                    result.with_metadata(serde_json::Value::Object(metadata))
+                }
+            }
             Err(e) => ToolResult::error(format!("Failed to fetch URL: {e}")),
         }
     }
@@ -95,6 +270,7 @@ mod tests {
         assert_eq!(def.name, "web_fetch");
         assert!(def.description.contains("20KB"));
         assert!(def.input_schema["properties"]["url"].is_object());
+        assert_eq!(def.input_schema["properties"]["render"]["type"], "boolean");
         let required = def.input_schema["required"].as_array().unwrap();
         assert!(required.iter().any(|v| v == "url"));
     }
@@ -109,6 +285,48 @@ mod tests {
         let result = tool.execute(json!({})).await;
         assert!(result.is_error);
         assert!(result.content.contains("Missing required parameter: url"));
+
+        let result = tool
+            .execute(json!({"url": "https://example.com", "format": "pdf"}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("format must be one of"));
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_refuses_disallowed_domain() {
+        let tool = WebFetchTool::new(
+            15,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig::default(),
+        )
+        .with_domain_policy(WebDomainPolicy::new(&["docs.rs".into()], &[]));
+        let result = tool
+            .execute(json!({"url": "https://example.com/page"}))
+            .await;
+        assert!(result.is_error);
+        assert_eq!(result.error_type.as_deref(), Some("domain_blocked"));
+        assert!(result
+            .content
+            .contains("domain 'example.com' is not in web_domain_allowlist"));
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_render_applies_url_validation() {
+        let tool = WebFetchTool::new(
+            15,
+            WebContentValidationConfig::default(),
+            WebFetchUrlValidationConfig {
+                denylist_hosts: vec!["example.com".to_string()],
+                ..WebFetchUrlValidationConfig::default()
+            },
+        )
+        .with_renderer(BrowserTool::new("/nonexistent"));
+        let result = tool
+            .execute(json!({"url": "https://example.com/app", "render": true}))
+            .await;
+        assert!(result.is_error);
+        assert!(result.content.contains("denylisted"), "{}", result.content);
     }
 
     #[tokio::test]
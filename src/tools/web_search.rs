@@ -1,18 +1,74 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use serde_json::json;
 
 use super::{schema_object, Tool, ToolResult};
 use crate::config::WebSearchConfig;
 use microclaw_core::llm_types::ToolDefinition;
-use microclaw_tools::web_search;
+use microclaw_tools::web_domains::WebDomainPolicy;
+use microclaw_tools::web_search::{
+    self, SafeSearch, SearchOptions, WebSearchCacheStatus, WebSearchResult,
+};
 
 pub struct WebSearchTool {
     config: WebSearchConfig,
+    domain_policy: WebDomainPolicy,
+    cache_ttl: Duration,
 }
 
 impl WebSearchTool {
     pub fn new(config: WebSearchConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            domain_policy: WebDomainPolicy::default(),
+            cache_ttl: Duration::ZERO,
+        }
+    }
+
+    pub fn with_domain_policy(mut self, domain_policy: WebDomainPolicy) -> Self {
+        self.domain_policy = domain_policy;
+        self
+    }
+
+    /// Serve a repeated query from the search cache for `secs`; 0 disables it.
+    pub fn with_cache_ttl_secs(mut self, secs: u64) -> Self {
+        self.cache_ttl = Duration::from_secs(secs);
+        self
+    }
+
+    fn render_results(
+        &self,
+        results: Vec<WebSearchResult>,
+        cache_status: WebSearchCacheStatus,
+    ) -> ToolResult {
+        let total = results.len();
+        let (results, omitted_domains) = self.domain_policy.filter_results(results);
+        let mut text = if results.is_empty() {
+            "No results found.".to_string()
+        } else {
+            web_search::format_results(&results)
+        };
+        let mut metadata = json!({ "results": results });
+        if !omitted_domains.is_empty() {
+            text = format!(
+                "{}\n\n[{} result(s) omitted: domain not allowed by the web domain policy ({})]",
+                text.trim_end(),
+                total - results.len(),
+                omitted_domains.join(", ")
+            );
+            metadata["omitted_domains"] = json!(omitted_domains);
+        }
+        if cache_status == WebSearchCacheStatus::Cached {
+            text = format!(
+                "{}\n\n[cache: repeated search, served from cache; pass fresh: true to search again]",
+                text.trim_end()
+            );
+            metadata["cache"] = json!(cache_status.as_str());
+        } else if results.is_empty() && omitted_domains.is_empty() {
+            return ToolResult::success(text);
+        }
+        ToolResult::success(text).with_metadata(metadata)
     }
 }
 
@@ -24,9 +80,9 @@ impl Tool for WebSearchTool {
 
     fn definition(&self) -> ToolDefinition {
         let description = if self.config.searxng_endpoint.is_some() {
-            "Search the web using SearXNG (with Tavily fallback if configured). Returns titles, URLs, and snippets."
+            "Search the web using SearXNG (with Tavily fallback if configured). Returns titles, URLs, snippets, source domains, and published dates when known. Answer from snippets when they suffice; use web_fetch only for details they lack."
         } else if self.config.tavily_api_key.is_some() {
-            "Search the web using Tavily. Returns titles, URLs, and snippets."
+            "Search the web using Tavily. Returns titles, URLs, snippets, source domains, and published dates when known. Answer from snippets when they suffice; use web_fetch only for details they lack."
         } else {
             "Search the web. Requires searxng_endpoint or tavily_api_key in config."
         };
@@ -39,6 +95,23 @@ impl Tool for WebSearchTool {
                     "query": {
                         "type": "string",
                         "description": "The search query"
+                    },
+                    "region": {
+                        "type": "string",
+                        "description": "Region code such as 'us-en' or 'de-de' (optional; ignored by backends without region support)"
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Result language such as 'en' or 'fr-FR' (optional)"
+                    },
+                    "safe_search": {
+                        "type": "string",
+                        "enum": ["off", "moderate", "strict"],
+                        "description": "Adult-content filtering level (optional)"
+                    },
+                    "fresh": {
+                        "type": "boolean",
+                        "description": "Skip the cache of recent identical searches and query the backend again (default false)"
                     }
                 }),
                 &["query"],
@@ -52,19 +125,46 @@ impl Tool for WebSearchTool {
             Err(msg) => return ToolResult::error(msg),
         };
 
-        match web_search::search(&query, &self.config).await {
-            Ok(results) => {
-                if results.is_empty() {
-                    ToolResult::success("No results found.".into())
-                } else {
-                    ToolResult::success(web_search::format_results(&results))
-                }
-            }
+        let options = match parse_options(&input) {
+            Ok(o) => o,
+            Err(msg) => return ToolResult::error(msg),
+        };
+
+        let fresh = input
+            .get("fresh")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        match web_search::search_with_cache(&query, &self.config, &options, self.cache_ttl, fresh)
+            .await
+        {
+            Ok((results, cache_status)) => self.render_results(results, cache_status),
             Err(e) => ToolResult::error(format!("Search failed: {e}")),
         }
     }
 }
 
+fn parse_options(input: &serde_json::Value) -> Result<SearchOptions, String> {
+    let text = |key: &str| {
+        input
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim().replace('_', "-"))
+            .filter(|v| !v.is_empty())
+    };
+    let safe_search = match input.get("safe_search").and_then(|v| v.as_str()) {
+        Some(raw) if !raw.trim().is_empty() => Some(SafeSearch::parse(raw).ok_or_else(|| {
+            format!("Invalid safe_search '{raw}': expected off, moderate, or strict")
+        })?),
+        _ => None,
+    };
+    Ok(SearchOptions {
+        region: text("region"),
+        language: text("language"),
+        safe_search,
+    })
+}
+
 fn parse_query(input: &serde_json::Value) -> Result<String, String> {
     let query = input
         .get("query")
@@ -89,6 +189,7 @@ mod tests {
             tavily_api_key: None,
             max_results: 8,
             timeout_secs: 15,
+            ..WebSearchConfig::default()
         };
         let tool = WebSearchTool::new(config);
         assert_eq!(tool.name(), "web_search");
@@ -98,6 +199,78 @@ mod tests {
         assert!(def.input_schema["properties"]["query"].is_object());
         let required = def.input_schema["required"].as_array().unwrap();
         assert!(required.iter().any(|v| v == "query"));
+        assert_eq!(
+            def.input_schema["properties"]["safe_search"]["enum"],
+            json!(["off", "moderate", "strict"])
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let opts = parse_options(&json!({
+            "query": "q",
+            "region": " us_en ",
+            "language": "",
+            "safe_search": "Strict"
+        }))
+        .unwrap();
+        assert_eq!(opts.region.as_deref(), Some("us-en"));
+        assert_eq!(opts.language, None);
+        assert_eq!(opts.safe_search, Some(SafeSearch::Strict));
+
+        let err = parse_options(&json!({"safe_search": "medium"})).unwrap_err();
+        assert!(err.contains("Invalid safe_search"));
+    }
+
+    fn result(url: &str) -> WebSearchResult {
+        WebSearchResult::new("Title".into(), url.into(), "snippet".into(), None)
+    }
+
+    #[test]
+    fn test_render_results_omits_disallowed_domains() {
+        let tool = WebSearchTool::new(WebSearchConfig::default())
+            .with_domain_policy(WebDomainPolicy::new(&[], &["spam.example".into()]));
+        let out = tool.render_results(
+            vec![
+                result("https://docs.example/a"),
+                result("https://spam.example/1"),
+                result("https://spam.example/2"),
+            ],
+            WebSearchCacheStatus::Fetched,
+        );
+        assert!(!out.is_error);
+        assert!(out.content.contains("https://docs.example/a"));
+        assert!(!out.content.contains("https://spam.example/1"));
+        assert!(out.content.ends_with(
+            "[2 result(s) omitted: domain not allowed by the web domain policy (spam.example)]"
+        ));
+        let metadata = out.metadata.unwrap();
+        assert_eq!(metadata["results"].as_array().unwrap().len(), 1);
+        assert_eq!(metadata["omitted_domains"], json!(["spam.example"]));
+
+        let allow_only = WebSearchTool::new(WebSearchConfig::default())
+            .with_domain_policy(WebDomainPolicy::new(&["rust-lang.org".into()], &[]));
+        let out = allow_only.render_results(
+            vec![result("https://example.com")],
+            WebSearchCacheStatus::Fetched,
+        );
+        assert!(out.content.starts_with("No results found."));
+        assert!(out.content.contains("(example.com)"));
+
+        let out = WebSearchTool::new(WebSearchConfig::default())
+            .render_results(vec![], WebSearchCacheStatus::Fetched);
+        assert_eq!(out.content, "No results found.");
+        assert!(out.metadata.is_none());
+
+        let out = WebSearchTool::new(WebSearchConfig::default()).render_results(
+            vec![result("https://docs.example/a")],
+            WebSearchCacheStatus::Cached,
+        );
+        assert!(out.content.contains("https://docs.example/a"));
+        assert!(out
+            .content
+            .contains("[cache: repeated search, served from cache"));
+        assert_eq!(out.metadata.unwrap()["cache"], "cached");
     }
 
     #[tokio::test]
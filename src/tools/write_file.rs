@@ -1,16 +1,15 @@
 use async_trait::async_trait;
 use serde_json::json;
-use std::path::PathBuf;
 use tracing::info;
 
-use crate::config::WorkingDirIsolation;
+use crate::config::{WorkingDirIsolation, WorkingDirIsolationPolicy, WorkingDirRoots};
 use microclaw_core::llm_types::ToolDefinition;
 
 use super::{schema_object, Tool, ToolResult};
 
 pub struct WriteFileTool {
-    working_dir: PathBuf,
-    working_dir_isolation: WorkingDirIsolation,
+    working_dir: WorkingDirRoots,
+    working_dir_isolation: WorkingDirIsolationPolicy,
 }
 
 impl WriteFileTool {
@@ -19,12 +18,12 @@ impl WriteFileTool {
     }
 
     pub fn new_with_isolation(
-        working_dir: &str,
-        working_dir_isolation: WorkingDirIsolation,
+        working_dir: impl Into<WorkingDirRoots>,
+        working_dir_isolation: impl Into<WorkingDirIsolationPolicy>,
     ) -> Self {
         Self {
-            working_dir: PathBuf::from(working_dir),
-            working_dir_isolation,
+            working_dir: working_dir.into(),
+            working_dir_isolation: working_dir_isolation.into(),
         }
     }
 }
@@ -66,7 +65,7 @@ impl Tool for WriteFileTool {
         let resolved_path_str = resolved_path.to_string_lossy().to_string();
 
         if let Err(msg) = microclaw_tools::path_guard::check_path(&resolved_path_str) {
-            return ToolResult::error(msg);
+            return ToolResult::error(msg).with_error_type("path_policy_blocked");
         }
 
         // Guard: SKILL.md files must go in the dedicated skills directory, not runtime/skills/.
@@ -0,0 +1,471 @@
+//! External vector stores for memory embeddings.
+//!
+//! Memory rows (content, category, confidence, archive state) always stay in
+//! SQLite. A vector store only holds one embedding per memory id, tagged with
+//! the memory's chat scope, and answers nearest-neighbour queries with ids.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::OnceCell;
+
+use crate::config::Config;
+use crate::embedding::EmbeddingProvider;
+
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    fn name(&self) -> &str;
+    /// Insert or replace the vector for `memory_id`; `chat_id` None marks a global memory.
+    async fn upsert(
+        &self,
+        memory_id: i64,
+        chat_id: Option<i64>,
+        vector: &[f32],
+    ) -> Result<(), String>;
+    /// Nearest memories visible to `chat_id` (its own plus global ones; global only
+    /// when None), best first, as `(memory_id, cosine similarity)`.
+    async fn search(
+        &self,
+        chat_id: Option<i64>,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(i64, f64)>, String>;
+    async fn delete(&self, memory_id: i64) -> Result<(), String>;
+}
+
+fn default_qdrant_collection() -> String {
+    "microclaw_memories".into()
+}
+fn default_qdrant_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QdrantConfig {
+    /// Base URL of the Qdrant REST API, e.g. `http://127.0.0.1:6333`.
+    #[serde(default)]
+    pub url: String,
+    /// Created on first use with the embedding dimension and cosine distance.
+    #[serde(default = "default_qdrant_collection")]
+    pub collection: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_qdrant_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for QdrantConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            collection: default_qdrant_collection(),
+            api_key: None,
+            timeout_secs: default_qdrant_timeout_secs(),
+        }
+    }
+}
+
+impl QdrantConfig {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.url = self.url.trim().trim_end_matches('/').to_string();
+        if !self.url.is_empty()
+            && !self.url.starts_with("http://")
+            && !self.url.starts_with("https://")
+        {
+            return Err(format!(
+                "qdrant.url must start with http:// or https:// (got '{}')",
+                self.url
+            ));
+        }
+        self.collection = self.collection.trim().to_string();
+        if self.collection.is_empty() {
+            self.collection = default_qdrant_collection();
+        }
+        if !self
+            .collection
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!(
+                "qdrant.collection may only contain letters, digits, '_' and '-' (got '{}')",
+                self.collection
+            ));
+        }
+        self.api_key = self
+            .api_key
+            .take()
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty());
+        if self.timeout_secs == 0 {
+            self.timeout_secs = default_qdrant_timeout_secs();
+        }
+        Ok(())
+    }
+}
+
+/// Qdrant over its REST API. Points are keyed by memory id with a `chat_id`
+/// payload (null for global memories) used to scope searches.
+pub struct QdrantVectorStore {
+    client: reqwest::Client,
+    base_url: String,
+    collection: String,
+    api_key: Option<String>,
+    dimension: usize,
+    collection_ready: OnceCell<()>,
+}
+
+impl QdrantVectorStore {
+    pub fn new(config: &QdrantConfig, dimension: usize) -> Result<Self, String> {
+        if config.url.is_empty() {
+            return Err("qdrant.url is required".into());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| format!("failed to build Qdrant HTTP client: {e}"))?;
+        Ok(Self {
+            client,
+            base_url: config.url.clone(),
+            collection: config.collection.clone(),
+            api_key: config.api_key.clone(),
+            dimension,
+            collection_ready: OnceCell::new(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/collections/{}{}", self.base_url, self.collection, path);
+        let builder = self.client.request(method, url);
+        match &self.api_key {
+            Some(key) => builder.header("api-key", key),
+            None => builder,
+        }
+    }
+
+    async fn send(&self, builder: reqwest::RequestBuilder) -> Result<serde_json::Value, String> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| format!("Qdrant request failed: {e}"))?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("Qdrant returned HTTP {status}: {body}"));
+        }
+        serde_json::from_str(&body).map_err(|e| format!("Qdrant returned invalid JSON: {e}"))
+    }
+
+    async fn ensure_collection(&self) -> Result<(), String> {
+        self.collection_ready
+            .get_or_try_init(|| async {
+                let response = self
+                    .request(reqwest::Method::GET, "")
+                    .send()
+                    .await
+                    .map_err(|e| format!("Qdrant request failed: {e}"))?;
+                if response.status().is_success() {
+                    return Ok(());
+                }
+                if response.status() != reqwest::StatusCode::NOT_FOUND {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(format!("Qdrant returned HTTP {status}: {body}"));
+                }
+                self.send(self.request(reqwest::Method::PUT, "").json(&json!({
+                    "vectors": {"size": self.dimension, "distance": "Cosine"}
+                })))
+                .await
+                .map(|_| ())
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+impl VectorStore for QdrantVectorStore {
+    fn name(&self) -> &str {
+        "qdrant"
+    }
+
+    async fn upsert(
+        &self,
+        memory_id: i64,
+        chat_id: Option<i64>,
+        vector: &[f32],
+    ) -> Result<(), String> {
+        if vector.len() != self.dimension {
+            return Err(format!(
+                "embedding has {} dimensions, Qdrant collection expects {}",
+                vector.len(),
+                self.dimension
+            ));
+        }
+        self.ensure_collection().await?;
+        let body = json!({
+            "points": [{
+                "id": memory_id,
+                "vector": vector,
+                "payload": {"chat_id": chat_id},
+            }]
+        });
+        self.send(
+            self.request(reqwest::Method::PUT, "/points?wait=true")
+                .json(&body),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn search(
+        &self,
+        chat_id: Option<i64>,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(i64, f64)>, String> {
+        self.ensure_collection().await?;
+        let global = json!({"is_null": {"key": "chat_id"}});
+        let filter = match chat_id {
+            Some(id) => json!({"should": [{"key": "chat_id", "match": {"value": id}}, global]}),
+            None => json!({"must": [global]}),
+        };
+        let body = json!({
+            "vector": vector,
+            "limit": limit,
+            "filter": filter,
+            "with_payload": false,
+        });
+        let value = self
+            .send(
+                self.request(reqwest::Method::POST, "/points/search")
+                    .json(&body),
+            )
+            .await?;
+        let hits = value
+            .get("result")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| "Qdrant search response has no result array".to_string())?;
+        Ok(hits
+            .iter()
+            .filter_map(|hit| {
+                let id = hit.get("id")?.as_i64()?;
+                let score = hit.get("score")?.as_f64()?;
+                Some((id, score))
+            })
+            .collect())
+    }
+
+    async fn delete(&self, memory_id: i64) -> Result<(), String> {
+        self.ensure_collection().await?;
+        self.send(
+            self.request(reqwest::Method::POST, "/points/delete?wait=true")
+                .json(&json!({"points": [memory_id]})),
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// The vector store selected by `memory_backend`, or None for plain SQLite.
+/// An external store needs an embedding provider to know the vector size.
+pub fn create_vector_store(
+    config: &Config,
+    embedding: Option<&Arc<dyn EmbeddingProvider>>,
+) -> Option<Arc<dyn VectorStore>> {
+    if config.memory_backend != "qdrant" {
+        return None;
+    }
+    let Some(embedding) = embedding else {
+        tracing::warn!(
+            "memory_backend is 'qdrant' but no embedding provider is configured; using sqlite only"
+        );
+        return None;
+    };
+    match QdrantVectorStore::new(&config.qdrant, embedding.dimension()) {
+        Ok(store) => {
+            tracing::info!(
+                "Memory vectors stored in Qdrant collection '{}' at {}",
+                config.qdrant.collection,
+                config.qdrant.url
+            );
+            Some(Arc::new(store))
+        }
+        Err(e) => {
+            tracing::warn!("Qdrant vector store disabled: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Path, State};
+    use axum::http::StatusCode;
+    use axum::routing::{get, post, put};
+    use axum::{Json, Router};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeQdrant {
+        collection_size: Option<usize>,
+        /// id -> (chat_id, vector)
+        points: HashMap<i64, (Option<i64>, Vec<f32>)>,
+    }
+
+    type Shared = Arc<Mutex<FakeQdrant>>;
+
+    async fn get_collection(State(s): State<Shared>, Path(_c): Path<String>) -> StatusCode {
+        if s.lock().unwrap().collection_size.is_some() {
+            StatusCode::OK
+        } else {
+            StatusCode::NOT_FOUND
+        }
+    }
+
+    async fn put_collection(
+        State(s): State<Shared>,
+        Path(_c): Path<String>,
+        Json(body): Json<serde_json::Value>,
+    ) -> Json<serde_json::Value> {
+        s.lock().unwrap().collection_size = body["vectors"]["size"].as_u64().map(|v| v as usize);
+        Json(json!({"result": true}))
+    }
+
+    async fn put_points(
+        State(s): State<Shared>,
+        Path(_c): Path<String>,
+        Json(body): Json<serde_json::Value>,
+    ) -> Json<serde_json::Value> {
+        let mut s = s.lock().unwrap();
+        for p in body["points"].as_array().unwrap() {
+            let vector = p["vector"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_f64().unwrap() as f32)
+                .collect();
+            s.points.insert(
+                p["id"].as_i64().unwrap(),
+                (p["payload"]["chat_id"].as_i64(), vector),
+            );
+        }
+        Json(json!({"result": {"status": "completed"}}))
+    }
+
+    async fn search_points(
+        State(s): State<Shared>,
+        Path(_c): Path<String>,
+        Json(body): Json<serde_json::Value>,
+    ) -> Json<serde_json::Value> {
+        let s = s.lock().unwrap();
+        let query: Vec<f32> = body["vector"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_f64().unwrap() as f32)
+            .collect();
+        let scoped_chat = body["filter"]["should"][0]["match"]["value"].as_i64();
+        let mut hits: Vec<(i64, f32)> = s
+            .points
+            .iter()
+            .filter(|(_, (chat, _))| {
+                chat.is_none() || (scoped_chat.is_some() && *chat == scoped_chat)
+            })
+            .map(|(id, (_, v))| (*id, v.iter().zip(&query).map(|(a, b)| a * b).sum()))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hits.truncate(body["limit"].as_u64().unwrap() as usize);
+        Json(json!({
+            "result": hits.iter().map(|(id, score)| json!({"id": id, "score": score})).collect::<Vec<_>>()
+        }))
+    }
+
+    async fn delete_points(
+        State(s): State<Shared>,
+        Path(_c): Path<String>,
+        Json(body): Json<serde_json::Value>,
+    ) -> Json<serde_json::Value> {
+        let mut s = s.lock().unwrap();
+        for id in body["points"].as_array().unwrap() {
+            s.points.remove(&id.as_i64().unwrap());
+        }
+        Json(json!({"result": {"status": "completed"}}))
+    }
+
+    async fn spawn_fake_qdrant() -> (String, Shared) {
+        let state: Shared = Arc::default();
+        let app = Router::new()
+            .route("/collections/:c", get(get_collection).put(put_collection))
+            .route("/collections/:c/points", put(put_points))
+            .route("/collections/:c/points/search", post(search_points))
+            .route("/collections/:c/points/delete", post(delete_points))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{addr}"), state)
+    }
+
+    #[tokio::test]
+    async fn test_qdrant_store_creates_collection_and_scopes_search() {
+        let (url, state) = spawn_fake_qdrant().await;
+        let config = QdrantConfig {
+            url,
+            ..QdrantConfig::default()
+        };
+        let store = QdrantVectorStore::new(&config, 2).unwrap();
+
+        store.upsert(1, Some(10), &[1.0, 0.0]).await.unwrap();
+        store.upsert(2, None, &[0.8, 0.6]).await.unwrap();
+        store.upsert(3, Some(20), &[1.0, 0.0]).await.unwrap();
+        assert_eq!(state.lock().unwrap().collection_size, Some(2));
+
+        let hits = store.search(Some(10), &[1.0, 0.0], 5).await.unwrap();
+        let ids: Vec<i64> = hits.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1, 2], "other chats' memories are excluded");
+
+        let global_only = store.search(None, &[1.0, 0.0], 5).await.unwrap();
+        assert_eq!(global_only.len(), 1);
+        assert_eq!(global_only[0].0, 2);
+
+        store.delete(1).await.unwrap();
+        let hits = store.search(Some(10), &[1.0, 0.0], 5).await.unwrap();
+        assert_eq!(hits.len(), 1);
+
+        let err = store.upsert(4, None, &[1.0]).await.unwrap_err();
+        assert!(err.contains("expects 2"), "{err}");
+    }
+
+    #[test]
+    fn test_qdrant_config_normalize() {
+        let mut config = QdrantConfig {
+            url: " http://qdrant:6333/ ".into(),
+            collection: "  ".into(),
+            api_key: Some(" ".into()),
+            timeout_secs: 0,
+        };
+        config.normalize().unwrap();
+        assert_eq!(config.url, "http://qdrant:6333");
+        assert_eq!(config.collection, "microclaw_memories");
+        assert_eq!(config.api_key, None);
+        assert_eq!(config.timeout_secs, 10);
+
+        let mut bad = QdrantConfig {
+            url: "qdrant:6333".into(),
+            ..QdrantConfig::default()
+        };
+        assert!(bad.normalize().is_err());
+        let mut bad = QdrantConfig {
+            collection: "a/b".into(),
+            ..QdrantConfig::default()
+        };
+        assert!(bad.normalize().is_err());
+    }
+}
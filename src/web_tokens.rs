@@ -0,0 +1,257 @@
+//! Long-lived API tokens for scripted access, and the `microclaw web token-*`
+//! commands that manage them. The webhook channel accepts them in place of
+//! its `auth_token`. Only a SHA-256 hash of each token is stored (in
+//! `api_keys`); the plaintext is printed once.
+
+use axum::http::HeaderMap;
+use clap::{Parser, Subcommand};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use microclaw_storage::db::{AuthApiKeyRecord, Database};
+
+pub const TOKEN_PREFIX: &str = "mcw_";
+/// Characters of the token kept in plaintext so `token-list` can identify it.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "microclaw web",
+    about = "Manage Web API tokens",
+    disable_help_subcommand = true
+)]
+struct WebCli {
+    #[command(subcommand)]
+    action: WebAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum WebAction {
+    /// Create a token; it is printed once and cannot be recovered
+    #[command(name = "token-create")]
+    Create {
+        /// Name shown in token-list
+        #[arg(long)]
+        label: String,
+        /// Expire the token after this many days (default: never)
+        #[arg(long)]
+        expires_in_days: Option<u32>,
+    },
+    /// List tokens (hashes are never shown)
+    #[command(name = "token-list")]
+    List,
+    /// Revoke a token by id
+    #[command(name = "token-revoke")]
+    Revoke { id: i64 },
+}
+
+/// A fresh random token: `mcw_` followed by 64 hex characters.
+pub fn generate_api_token() -> String {
+    format!(
+        "{TOKEN_PREFIX}{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+pub fn hash_api_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// The `Authorization: Bearer <token>` value, if present.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Store a new token and return `(id, plaintext)`.
+pub fn create_api_token(
+    db: &Database,
+    label: &str,
+    expires_in_days: Option<u32>,
+) -> anyhow::Result<(i64, String)> {
+    let label = label.trim();
+    if label.is_empty() {
+        anyhow::bail!("--label must not be empty");
+    }
+    let expires_at = expires_in_days
+        .map(|days| (chrono::Utc::now() + chrono::Duration::days(i64::from(days))).to_rfc3339());
+    let token = generate_api_token();
+    let id = db.create_api_key(
+        label,
+        &hash_api_token(&token),
+        &token[..DISPLAY_PREFIX_LEN],
+        &[],
+        expires_at.as_deref(),
+        None,
+    )?;
+    Ok((id, token))
+}
+
+/// Whether the request carries a valid, unexpired, unrevoked API token.
+pub fn is_valid_api_token(db: &Database, headers: &HeaderMap) -> bool {
+    let Some(token) = bearer_token(headers) else {
+        return false;
+    };
+    if !token.starts_with(TOKEN_PREFIX) {
+        return false;
+    }
+    match db.validate_api_key_hash(&hash_api_token(token)) {
+        Ok(found) => found.is_some(),
+        Err(e) => {
+            tracing::warn!("API token lookup failed: {e}");
+            false
+        }
+    }
+}
+
+fn token_status(record: &AuthApiKeyRecord, now: &str) -> &'static str {
+    if record.revoked_at.is_some() {
+        "revoked"
+    } else if record
+        .expires_at
+        .as_deref()
+        .is_some_and(|expires| expires <= now)
+    {
+        "expired"
+    } else {
+        "active"
+    }
+}
+
+pub fn format_token_list(records: &[AuthApiKeyRecord]) -> String {
+    if records.is_empty() {
+        return "No API tokens.".to_string();
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    records
+        .iter()
+        .map(|r| {
+            format!(
+                "#{} {} [{}] {}… created {} | expires {} | last used {}",
+                r.id,
+                r.label,
+                token_status(r, &now),
+                r.prefix,
+                r.created_at,
+                r.expires_at.as_deref().unwrap_or("never"),
+                r.last_used_at.as_deref().unwrap_or("never"),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub async fn handle_web_cli(args: &[String]) -> anyhow::Result<()> {
+    let cli =
+        match WebCli::try_parse_from(std::iter::once("web").chain(args.iter().map(String::as_str)))
+        {
+            Ok(cli) => cli,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    clap::error::ErrorKind::DisplayHelp
+                        | clap::error::ErrorKind::DisplayVersion
+                        | clap::error::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+                ) =>
+            {
+                err.print()?;
+                return Ok(());
+            }
+            Err(err) => return Err(anyhow::anyhow!(err.to_string())),
+        };
+    let config = Config::load()?;
+    let db = config.open_database()?;
+    match cli.action {
+        WebAction::Create {
+            label,
+            expires_in_days,
+        } => {
+            let (id, token) = create_api_token(&db, &label, expires_in_days)?;
+            println!("Created API token #{id} ({}).", label.trim());
+            println!("{token}");
+            println!(
+                "Store it now; it cannot be shown again. Send it as `Authorization: Bearer <token>`."
+            );
+        }
+        WebAction::List => println!("{}", format_token_list(&db.list_api_keys()?)),
+        WebAction::Revoke { id } => {
+            if db.revoke_api_key(id)? {
+                println!("Revoked API token #{id}.");
+            } else {
+                anyhow::bail!("no API token with id {id}");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (Database, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("mc_web_tokens_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (Database::new(dir.to_str().unwrap()).unwrap(), dir)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_generated_tokens_are_unique_and_hashed() {
+        let a = generate_api_token();
+        let b = generate_api_token();
+        assert!(a.starts_with(TOKEN_PREFIX));
+        assert_eq!(a.len(), TOKEN_PREFIX.len() + 64);
+        assert_ne!(a, b);
+        assert_eq!(hash_api_token(&a).len(), 64);
+        assert_ne!(hash_api_token(&a), a);
+    }
+
+    #[test]
+    fn test_api_token_accepted_until_revoked() {
+        let (db, dir) = test_db();
+        let (id, token) = create_api_token(&db, "ci", None).unwrap();
+        assert!(is_valid_api_token(&db, &bearer(&token)));
+        assert!(!is_valid_api_token(&db, &bearer("mcw_wrong")));
+        assert!(!is_valid_api_token(&db, &HeaderMap::new()));
+
+        let records = db.list_api_keys().unwrap();
+        assert_eq!(records[0].label, "ci");
+        assert!(token.starts_with(&records[0].prefix));
+        assert!(records[0].last_used_at.is_some());
+
+        assert!(db.revoke_api_key(id).unwrap());
+        assert!(!is_valid_api_token(&db, &bearer(&token)));
+        assert!(format_token_list(&db.list_api_keys().unwrap()).contains("[revoked]"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_expired_api_token_rejected() {
+        let (db, dir) = test_db();
+        let (_, token) = create_api_token(&db, "short", Some(0)).unwrap();
+        assert!(!is_valid_api_token(&db, &bearer(&token)));
+        assert!(format_token_list(&db.list_api_keys().unwrap()).contains("[expired]"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_create_rejects_blank_label() {
+        let (db, dir) = test_db();
+        assert!(create_api_token(&db, "  ", None).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -8,46 +8,94 @@ fn minimal_config() -> Config {
         llm_provider: "anthropic".into(),
         api_key: "test-key".into(),
         model: String::new(),
+        model_roles: Default::default(),
         llm_base_url: None,
+        llm_correlation_id: true,
+        llm_native_tool_choice: true,
+        llm_vision: true,
+        llm_circuit_breaker_threshold: 5,
+        llm_circuit_breaker_cooldown_secs: 30,
         max_tokens: 8192,
+        max_response_tokens: None,
         max_tool_iterations: 25,
+        allow_skill_budget_increase: false,
         max_history_messages: 50,
+        group_user_isolation: false,
         max_document_size_mb: 100,
+        attachment_image_vision: true,
         memory_token_budget: 1500,
+        memory_auto_inject: true,
+        memory_auto_inject_top_k: 0,
+        memory_pinned_token_budget: 300,
         data_dir: "./microclaw.data".into(),
         skills_dir: None,
+        skills_live_refresh: false,
         working_dir: "./tmp".into(),
         working_dir_isolation: WorkingDirIsolation::Chat,
+        working_dir_isolation_policy: Default::default(),
         high_risk_tool_user_confirmation_required: true,
+        allow_proactive_messaging: false,
+        ask_user_timeout_secs: 600,
+        bash_no_tty: true,
+        bash_shell: None,
+        bash_command_allowlist: Vec::new(),
+        tool_audit_log: true,
+        fail_fast_on_missing_tool_deps: false,
         sandbox: microclaw::config::SandboxConfig::default(),
         openai_api_key: None,
         timezone: "UTC".into(),
         allowed_groups: vec![],
         control_chat_ids: vec![],
+        user_groups: std::collections::HashMap::new(),
         max_session_messages: 40,
         compact_keep_recent: 20,
         default_tool_timeout_secs: 30,
         tool_timeout_overrides: std::collections::HashMap::new(),
+        max_tool_timeout_secs: 0,
+        sub_agent_max_duration_secs: 0,
+        sub_agent_max_tokens: 0,
+        wait_tool_max_secs: 60,
         default_mcp_request_timeout_secs: 120,
         compaction_timeout_secs: 180,
         allow_group_slash_without_mention: false,
+        reaction_commands: std::collections::HashMap::new(),
+        channel_send_failure_alert_threshold: 3,
+        channel_send_failure_backoff_secs: 0,
+        channel_queue_capacity: 100,
+        channel_queue_drop_policy: "drop_oldest".into(),
+        channel_queue_max_concurrency: 8,
+        serialize_per_chat: false,
         show_thinking: false,
+        log_thinking: false,
         openai_compat_body_overrides: std::collections::HashMap::new(),
         openai_compat_body_overrides_by_provider: std::collections::HashMap::new(),
         openai_compat_body_overrides_by_model: std::collections::HashMap::new(),
-        web_enabled: false,
-        web_host: "127.0.0.1".into(),
-        web_port: 3900,
-        web_auth_token: None,
-        web_max_inflight_per_session: 2,
-        web_max_requests_per_window: 8,
-        web_rate_window_seconds: 10,
-        web_run_history_limit: 512,
-        web_session_idle_ttl_seconds: 300,
+        sampling_params: microclaw::config::SamplingParams::default(),
+        sampling_params_by_provider: std::collections::HashMap::new(),
+        sampling_params_by_model: std::collections::HashMap::new(),
+        model_capabilities: std::collections::HashMap::new(),
+        deterministic: false,
+        deterministic_seed: 42,
+        anthropic_thinking: false,
+        anthropic_thinking_budget_tokens: 4096,
+        anthropic_interleaved_thinking: false,
         web_fetch_validation:
             microclaw_tools::web_content_validation::WebContentValidationConfig::default(),
         web_fetch_url_validation: microclaw_tools::web_fetch::WebFetchUrlValidationConfig::default(
         ),
+        web_fetch_cache: microclaw_tools::web_fetch::WebFetchCacheConfig::default(),
+        web_fetch_proxy: microclaw_tools::web_fetch::WebFetchProxyConfig::default(),
+        web_fetch_markdown: microclaw_tools::web_fetch::WebFetchMarkdownConfig::default(),
+        web_fetch_per_host_rate: microclaw_tools::web_fetch::WebFetchHostRateConfig::default(),
+        http_request_auth: microclaw_tools::http_request::HttpRequestAuthConfig::default(),
+        web_search: microclaw::config::WebSearchConfig::default(),
+        web_search_cache_ttl_secs: 300,
+        web_domain_allowlist: vec![],
+        web_domain_denylist: vec![],
+        tool_result_redaction: microclaw::config::ToolResultRedactionConfig::default(),
+        tool_error_hints: microclaw::config::ToolErrorHintsConfig::default(),
+        tool_input_coercion: microclaw::config::ToolInputCoercionConfig::default(),
+        tool_input_defaults: microclaw::config::ToolInputDefaults::new(),
         model_prices: vec![],
         embedding_provider: None,
         embedding_api_key: None,
@@ -56,13 +104,20 @@ fn minimal_config() -> Config {
         embedding_dim: None,
         reflector_enabled: true,
         reflector_interval_mins: 15,
+        memory_dedup_similarity_threshold: 0.85,
+        memory_backend: "sqlite".into(),
+        qdrant: microclaw::vector_store::QdrantConfig::default(),
         soul_path: None,
         clawhub: microclaw::config::ClawHubConfig::default(),
         plugins: microclaw::plugins::PluginsConfig::default(),
         voice_provider: "openai".into(),
         voice_transcription_command: None,
+        logging: microclaw::config::LoggingConfig::default(),
+        db_encryption: microclaw::config::DbEncryptionConfig::default(),
         send_progress: false,
         send_tool_hints: false,
+        reply_prefix: String::new(),
+        reply_suffix: String::new(),
         channels: std::collections::HashMap::new(),
         bot_username: "testbot".into(),
     }
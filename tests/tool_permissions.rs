@@ -15,6 +15,9 @@ fn test_auth_context_control_chat() {
         caller_channel: "telegram".into(),
         caller_chat_id: 100,
         control_chat_ids: vec![100, 200],
+        caller_is_admin: false,
+        caller_sender_id: None,
+        run_id: 0,
     };
     assert!(auth.is_control_chat());
     assert!(auth.can_access_chat(999)); // control can access any chat
@@ -26,6 +29,9 @@ fn test_auth_context_regular_chat() {
         caller_channel: "telegram".into(),
         caller_chat_id: 300,
         control_chat_ids: vec![100, 200],
+        caller_is_admin: false,
+        caller_sender_id: None,
+        run_id: 0,
     };
     assert!(!auth.is_control_chat());
     assert!(auth.can_access_chat(300)); // can access own chat
@@ -38,12 +44,29 @@ fn test_auth_context_empty_control_list() {
         caller_channel: "telegram".into(),
         caller_chat_id: 100,
         control_chat_ids: vec![],
+        caller_is_admin: false,
+        caller_sender_id: None,
+        run_id: 0,
     };
     assert!(!auth.is_control_chat());
     assert!(auth.can_access_chat(100)); // can access own
     assert!(!auth.can_access_chat(200)); // cannot access other
 }
 
+#[test]
+fn test_auth_context_admin_sender_in_regular_chat() {
+    let auth = ToolAuthContext {
+        caller_channel: "feishu".into(),
+        caller_chat_id: 300,
+        control_chat_ids: vec![100],
+        caller_is_admin: true,
+        caller_sender_id: None,
+        run_id: 0,
+    };
+    assert!(auth.is_control_chat());
+    assert!(auth.can_access_chat(999));
+}
+
 // -----------------------------------------------------------------------
 // auth_context_from_input
 // -----------------------------------------------------------------------
@@ -105,6 +128,20 @@ fn test_auth_context_from_input_no_control_ids_key() {
     assert!(auth.control_chat_ids.is_empty());
 }
 
+#[test]
+fn test_auth_context_from_input_admin_flag() {
+    let input = json!({
+        "__microclaw_auth": {
+            "caller_chat_id": 42,
+            "control_chat_ids": [],
+            "caller_is_admin": true
+        }
+    });
+    let auth = auth_context_from_input(&input).unwrap();
+    assert!(auth.caller_is_admin);
+    assert!(auth.is_control_chat());
+}
+
 // -----------------------------------------------------------------------
 // authorize_chat_access
 // -----------------------------------------------------------------------